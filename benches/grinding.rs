@@ -0,0 +1,32 @@
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use ministark::grinding::grind;
+use ministark::grinding::NonceEncoding;
+use sha2::Sha256;
+
+const BENCHMARK_GRINDING_FACTORS: [u32; 3] = [12, 16, 20];
+
+fn grinding_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("grinding (sha256)");
+    group.sample_size(10);
+
+    for grinding_factor in BENCHMARK_GRINDING_FACTORS {
+        // a different seed per iteration so the search length varies
+        let mut seed = [0u8; 32];
+        group.bench_with_input(
+            BenchmarkId::new("grind", grinding_factor),
+            &grinding_factor,
+            |b, &grinding_factor| {
+                b.iter(|| {
+                    seed[0] = seed[0].wrapping_add(1);
+                    grind::<Sha256>(&seed, grinding_factor, NonceEncoding::U64Le)
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(benches, grinding_benches);
+criterion_main!(benches);
@@ -10,6 +10,9 @@ use criterion::Criterion;
 use digest::Digest;
 use digest::Output;
 use gpu_poly::GpuField;
+use ministark::hashes::GriffinDigest;
+use ministark::hashes::RescueDigest;
+use ministark::merkle::hash_leaf;
 use ministark::merkle::MerkleTree;
 use sha2::Sha256;
 
@@ -35,11 +38,24 @@ fn build_merkle_tree_bench<F: GpuField, D: Digest>(c: &mut Criterion, name: &str
         group.bench_with_input(BenchmarkId::new("new", n), &n, |b, _| {
             b.iter(|| MerkleTree::<D>::new(leaf_nodes.clone()))
         });
+
+        // commit phase cost: hashing the rows of a trace and building the tree
+        group.bench_with_input(BenchmarkId::new("commit", n), &n, |b, _| {
+            b.iter(|| {
+                let leaf_nodes = leaves
+                    .chunks(4)
+                    .map(hash_leaf::<D, Fp>)
+                    .collect::<Vec<Output<D>>>();
+                MerkleTree::<D>::new(leaf_nodes)
+            })
+        });
     }
 }
 
 fn build_merkle_tree_benches(c: &mut Criterion) {
     build_merkle_tree_bench::<Fp, Sha256>(c, "build merkle tree (sha256)");
+    build_merkle_tree_bench::<Fp, RescueDigest>(c, "build merkle tree (rescue)");
+    build_merkle_tree_bench::<Fp, GriffinDigest>(c, "build merkle tree (griffin)");
 }
 
 criterion_group!(benches, build_merkle_tree_benches);
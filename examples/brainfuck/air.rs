@@ -15,14 +15,47 @@ use ministark::Air;
 use ministark::Constraint;
 use ministark::ProofOptions;
 use ministark::TraceInfo;
+use sha2::Digest;
+use sha2::Sha256;
 
-#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq, Eq, Debug)]
 pub struct ExecutionInfo {
-    pub source_code: String,
-    pub input: Vec<u8>,
+    /// Compiled program. Binds the proof to the program rather than its source
+    /// code so comments and whitespace don't change the public inputs.
+    pub program: Vec<usize>,
+    /// Input tape. `None` if the input is private in which case only its
+    /// length is part of the public inputs.
+    pub input: Option<Vec<u8>>,
+    pub input_len: usize,
     pub output: Vec<u8>,
 }
 
+impl ExecutionInfo {
+    pub fn new(source_code: &str, input: Vec<u8>, output: Vec<u8>) -> Self {
+        ExecutionInfo {
+            program: compile(source_code),
+            input_len: input.len(),
+            input: Some(input),
+            output,
+        }
+    }
+
+    /// Removes the input from the public inputs. The proof then shows the
+    /// program produces the output for some input of length `input_len`.
+    /// Proofs are not zero-knowledge so this doesn't hide the input.
+    pub fn with_private_input(mut self) -> Self {
+        self.input = None;
+        self
+    }
+
+    /// SHA-256 digest of the compiled program
+    pub fn program_hash(&self) -> [u8; 32] {
+        let mut bytes = Vec::new();
+        self.program.serialize_compressed(&mut bytes).unwrap();
+        Sha256::digest(bytes).into()
+    }
+}
+
 pub struct BrainfuckAir {
     options: ProofOptions,
     trace_info: TraceInfo,
@@ -38,6 +71,10 @@ impl Air for BrainfuckAir {
     type PublicInputs = ExecutionInfo;
 
     fn new(trace_info: TraceInfo, execution_info: ExecutionInfo, options: ProofOptions) -> Self {
+        let input_terminal_constraints = match execution_info.input {
+            Some(_) => tables::InputExtensionColumn::terminal_constraints(),
+            None => tables::InputExtensionColumn::private_terminal_constraints(),
+        };
         BrainfuckAir {
             options,
             trace_info,
@@ -49,6 +86,7 @@ impl Air for BrainfuckAir {
                 tables::MemoryExtensionColumn::transition_constraints(),
                 tables::InstructionBaseColumn::transition_constraints(),
                 tables::InstructionExtensionColumn::transition_constraints(),
+                tables::InputBaseColumn::byte_constraints(),
                 tables::InputExtensionColumn::transition_constraints(),
                 tables::OutputExtensionColumn::transition_constraints(),
             ]
@@ -66,7 +104,8 @@ impl Air for BrainfuckAir {
             terminal_constraints: vec![
                 tables::ProcessorExtensionColumn::terminal_constraints(),
                 tables::InstructionExtensionColumn::terminal_constraints(),
-                tables::InputExtensionColumn::terminal_constraints(),
+                tables::InputBaseColumn::byte_constraints(),
+                input_terminal_constraints,
                 tables::OutputExtensionColumn::terminal_constraints(),
             ]
             .concat(),
@@ -78,17 +117,30 @@ impl Air for BrainfuckAir {
         use EvaluationArgumentHint::*;
 
         let ExecutionInfo {
-            source_code,
+            program,
             input,
+            input_len,
             output,
         } = &self.execution_info;
         let trace_len = self.trace_info().trace_len;
 
-        let (input_eval_arg, input_eval_offset) =
-            io_terminal_helper(input, challenges[Gamma], trace_len);
+        let (input_eval_arg, input_eval_offset) = match input {
+            Some(input) => io_terminal_helper(input, challenges[Gamma], trace_len),
+            // the evaluation argument is unused when the input is private. An
+            // input longer than the trace can't have been read so the offset
+            // is zero which only an all zero input table satisfies
+            None => (
+                Fq3::zero(),
+                trace_len
+                    .checked_sub(*input_len)
+                    .map_or(Fq3::zero(), |padding| {
+                        challenges[Gamma].pow([padding as u64])
+                    }),
+            ),
+        };
         let (output_eval_arg, output_eval_offset) =
             io_terminal_helper(output, challenges[Delta], trace_len);
-        let instruction_eval_arg = compute_instruction_evaluation_argument(source_code, challenges);
+        let instruction_eval_arg = compute_instruction_evaluation_argument(program, challenges);
 
         Hints::new(vec![
             (Instruction.index(), instruction_eval_arg),
@@ -124,13 +176,14 @@ impl Air for BrainfuckAir {
     }
 }
 
-// Computes the evaluation terminal for the instruction table
-fn compute_instruction_evaluation_argument(source_code: &str, challenges: &Challenges<Fq3>) -> Fq3 {
+// Computes the evaluation terminal for the instruction table from the
+// program in the public inputs. This binds the proof to the program.
+fn compute_instruction_evaluation_argument(program: &[usize], challenges: &Challenges<Fq3>) -> Fq3 {
     use Challenge::Eta;
     use Challenge::A;
     use Challenge::B;
     use Challenge::C;
-    let mut program = compile(source_code);
+    let mut program = program.to_vec();
     // add padding
     program.push(0);
     // let prev_ip = None;
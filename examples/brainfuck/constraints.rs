@@ -1,3 +1,4 @@
+use crate::tables::BrainfuckColumn;
 use crate::tables::Challenge;
 use crate::tables::EvaluationArgumentHint;
 use crate::tables::InputBaseColumn;
@@ -13,8 +14,11 @@ use crate::tables::ProcessorExtensionColumn;
 use crate::vm::OpCode;
 use ark_ff::Zero;
 use gpu_poly::GpuField;
+use ministark::constraint;
+use ministark::constraint::is_one_of;
 use ministark::constraint::Challenge as _;
 use ministark::constraint::Hint;
+use ministark::gadgets::bits::BitColumns;
 use ministark::Column;
 use ministark::Constraint;
 use std::borrow::Borrow;
@@ -35,8 +39,9 @@ impl ProcessorBaseColumn {
     pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
         use ProcessorBaseColumn::*;
         let one = F::one();
-        let two = one + one;
+        let max_mem_val = F::from(u8::MAX);
         let mem_val_is_zero = MemVal.curr() * MemValInv.curr() - one;
+        let mem_val_is_max = (MemVal.curr() - max_mem_val) * MemValMaxInv.curr() - one;
         let mut constraints = (Constraint::zero(), Constraint::zero(), Constraint::zero());
 
         use OpCode::*;
@@ -47,43 +52,58 @@ impl ProcessorBaseColumn {
 
             match instr {
                 IncrementPointer => {
-                    instr_constraints.0 = Ip.next() - Ip.curr() - one;
-                    instr_constraints.1 = Mp.next() - Mp.curr() - one;
+                    instr_constraints.0 = constraint!(Ip[next] - Ip[curr] - 1);
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr] - 1);
                 }
                 DecrementPointer => {
-                    instr_constraints.0 = Ip.next() - Ip.curr() - one;
-                    instr_constraints.1 = Mp.next() - Mp.curr() + one;
+                    instr_constraints.0 = constraint!(Ip[next] - Ip[curr] - 1);
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr] + 1);
                 }
                 Increment => {
-                    instr_constraints.0 = Ip.next() - Ip.curr() - one;
-                    instr_constraints.1 = Mp.next() - Mp.curr();
-                    instr_constraints.2 = MemVal.next() - MemVal.curr() - one;
+                    // memory cells are bytes that wrap from 255 to 0
+                    instr_constraints.0 = constraint!(Ip[next] - Ip[curr] - 1);
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr]);
+                    instr_constraints.2 = constraint!(
+                        MemVal[next]
+                            - (MemVal[curr] + 1)
+                                * (MemVal[curr] - max_mem_val)
+                                * MemValMaxInv[curr]
+                    );
                 }
                 Decrement => {
-                    instr_constraints.0 = Ip.next() - Ip.curr() - one;
-                    instr_constraints.1 = Mp.next() - Mp.curr();
-                    instr_constraints.2 = MemVal.next() - MemVal.curr() + one;
+                    // memory cells are bytes that wrap from 0 to 255
+                    instr_constraints.0 = constraint!(Ip[next] - Ip[curr] - 1);
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr]);
+                    instr_constraints.2 = constraint!(
+                        MemVal[next] - (MemVal[curr] - 1) * MemVal[curr] * MemValInv[curr]
+                            + &mem_val_is_zero * max_mem_val
+                    );
                 }
                 Write => {
-                    instr_constraints.0 = Ip.next() - Ip.curr() - one;
-                    instr_constraints.1 = Mp.next() - Mp.curr();
+                    instr_constraints.0 = constraint!(Ip[next] - Ip[curr] - 1);
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr]);
                 }
                 Read => {
-                    instr_constraints.0 = Ip.next() - Ip.curr() - one;
-                    instr_constraints.1 = Mp.next() - Mp.curr();
-                    instr_constraints.2 = MemVal.next() - MemVal.curr();
+                    // the new memory value is bound to the input table by the input
+                    // evaluation argument and range checked to a byte there
+                    instr_constraints.0 = constraint!(Ip[next] - Ip[curr] - 1);
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr]);
                 }
                 LoopBegin => {
-                    instr_constraints.0 = MemVal.curr() * (Ip.next() - Ip.curr() - two)
-                        + mem_val_is_zero.clone() * (Ip.next() - NextInstr.curr());
-                    instr_constraints.1 = Mp.next() - Mp.curr();
-                    instr_constraints.2 = MemVal.next() - MemVal.curr();
+                    instr_constraints.0 = constraint!(
+                        MemVal[curr] * (Ip[next] - Ip[curr] - 2)
+                            + &mem_val_is_zero * (Ip[next] - NextInstr[curr])
+                    );
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr]);
+                    instr_constraints.2 = constraint!(MemVal[next] - MemVal[curr]);
                 }
                 LoopEnd => {
-                    instr_constraints.0 = &mem_val_is_zero * (Ip.next() - Ip.curr() - two)
-                        + MemVal.curr() * (Ip.next() - NextInstr.curr());
-                    instr_constraints.1 = Mp.next() - Mp.curr();
-                    instr_constraints.2 = MemVal.next() - MemVal.curr();
+                    instr_constraints.0 = constraint!(
+                        &mem_val_is_zero * (Ip[next] - Ip[curr] - 2)
+                            + MemVal[curr] * (Ip[next] - NextInstr[curr])
+                    );
+                    instr_constraints.1 = constraint!(Mp[next] - Mp[curr]);
+                    instr_constraints.2 = constraint!(MemVal[next] - MemVal[curr]);
                 }
             }
 
@@ -104,6 +124,8 @@ impl ProcessorBaseColumn {
             Cycle.next() - Cycle.curr() - one,
             MemVal.curr() * &mem_val_is_zero,
             MemValInv.curr() * &mem_val_is_zero,
+            (MemVal.curr() - max_mem_val) * &mem_val_is_max,
+            MemValMaxInv.curr() * &mem_val_is_max,
             // dummy has to be zero or one
             (Dummy.next() - one) * Dummy.next(),
             // dummy indicates if the row is padding
@@ -202,8 +224,6 @@ impl ProcessorExtensionColumn {
                 + MemoryBaseColumn::Dummy.curr()
                     * Dummy.curr()
                     * (MemoryExtensionColumn::Permutation.curr() - MemoryPermutation.curr()),
-            // input evaluation:
-            InputEvaluation.curr() - EvaluationArgumentHint::Input.get_hint(),
             // output evaluation:
             OutputEvaluation.curr() - EvaluationArgumentHint::Output.get_hint(),
         ]
@@ -398,6 +418,24 @@ impl InstructionExtensionColumn {
     }
 }
 
+impl InputBaseColumn {
+    /// Range checks the input values to bytes. Reads are bound to these
+    /// values by the input evaluation argument so this also keeps the
+    /// processor from reading values that aren't bytes when the input is
+    /// private. Used as both transition and terminal constraints so every
+    /// row is checked.
+    pub fn byte_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use InputBaseColumn::*;
+        let bits = BitColumns::new(
+            Self::FIRST_TRACE_COL_INDEX + Self::VALUE_BITS.offset,
+            Self::VALUE_BITS.num_bits,
+        );
+        let mut constraints = bits.range_constraints(0);
+        constraints.push(bits.range_check(Value.curr(), 0));
+        constraints
+    }
+}
+
 impl InputExtensionColumn {
     pub fn boundary_constraints<F: GpuField>() -> Vec<Constraint<F>> {
         use InputBaseColumn::*;
@@ -405,15 +443,33 @@ impl InputExtensionColumn {
         vec![Evaluation.curr() - Value.curr()]
     }
 
+    /// Binds the processor's reads and the input table to the public input
     pub fn terminal_constraints<F: GpuField>() -> Vec<Constraint<F>> {
         use InputExtensionColumn::*;
+        use ProcessorExtensionColumn::InputEvaluation;
         vec![
+            InputEvaluation.curr() - EvaluationArgumentHint::Input.get_hint(),
             Evaluation.curr()
                 - EvaluationArgumentHint::Input.get_hint()
                     * EvaluationArgumentHint::InputOffset.get_hint(),
         ]
     }
 
+    /// Binds the processor's reads to the input table when the input is not
+    /// part of the public inputs. The input table evaluates every row
+    /// (including zero padding) so its terminal is the processor's terminal
+    /// times `gamma^(n - input_len)`. Proofs are not zero-knowledge so queried
+    /// trace values can still reveal parts of the input. The length of an
+    /// input of only zero bytes isn't bound since its evaluation is zero.
+    pub fn private_terminal_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use InputExtensionColumn::*;
+        use ProcessorExtensionColumn::InputEvaluation;
+        vec![
+            Evaluation.curr()
+                - InputEvaluation.curr() * EvaluationArgumentHint::InputOffset.get_hint(),
+        ]
+    }
+
     pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
         use Challenge::Gamma;
         use InputBaseColumn::*;
@@ -446,12 +502,9 @@ impl OutputExtensionColumn {
     }
 }
 
+/// returns a polynomial in X that evaluates to 0 for every instruction
 fn instr_zerofier<F: GpuField>(instr: Constraint<F>) -> Constraint<F> {
-    let mut accumulator = Constraint::from(F::one());
-    for opcode in OpCode::VALUES {
-        accumulator *= &instr - F::from(opcode as u64);
-    }
-    accumulator
+    is_one_of(instr, OpCode::VALUES.map(|opcode| F::from(opcode as u64)))
 }
 
 /// returns a polynomial in X that evaluates to 0 in all instructions except
@@ -460,19 +513,13 @@ fn if_not_instr<F: GpuField>(
     instr: OpCode,
     indeterminate: impl Borrow<Constraint<F>>,
 ) -> Constraint<F> {
-    let mut accumulator = Constraint::from(F::one());
-    for opcode in OpCode::VALUES {
-        if opcode != instr {
-            let factor = indeterminate.borrow() - F::from(opcode as u64);
-            accumulator *= factor;
-        }
-    }
-    accumulator
+    let opcodes = OpCode::VALUES.into_iter().filter(|&opcode| opcode != instr);
+    is_one_of(indeterminate, opcodes.map(|opcode| F::from(opcode as u64)))
 }
 
 fn if_instr<F: GpuField>(
     instr: OpCode,
     indeterminate: impl Borrow<Constraint<F>>,
 ) -> Constraint<F> {
-    indeterminate.borrow() - F::from(instr as u64)
+    is_one_of(indeterminate, [F::from(instr as u64)])
 }
@@ -14,6 +14,7 @@ use std::path::PathBuf;
 use std::time::Instant;
 use structopt::StructOpt;
 use trace::BrainfuckTrace;
+use vm::compile;
 use vm::simulate;
 
 mod air;
@@ -24,59 +25,89 @@ mod trace;
 mod vm;
 
 #[derive(StructOpt, Debug)]
-#[structopt(name = "BrainSTARK", about = "miniSTARK brainfuck prover and verifier")]
+#[structopt(name = "bf", about = "miniSTARK brainfuck prover and verifier")]
 enum BrainfuckOptions {
+    /// Executes a program and generates a proof of its execution
     Prove {
+        #[structopt(parse(from_os_str))]
         src: PathBuf,
+        /// file the program reads its input from
         #[structopt(long, parse(from_os_str))]
-        dst: PathBuf,
-        #[structopt(long, default_value = "")]
-        input: String,
+        input: Option<PathBuf>,
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+        /// number of memory cells available to the program
+        #[structopt(long, default_value = "1024")]
+        tape_size: usize,
+        /// keep the input out of the public inputs. Proofs are not
+        /// zero-knowledge so this doesn't hide the input from the verifier.
+        #[structopt(long)]
+        private_input: bool,
     },
+    /// Verifies a proof. The program, input and output are read from the proof
+    /// unless a program is provided
     Verify {
+        #[structopt(parse(from_os_str))]
+        proof: PathBuf,
+        /// program the proof must be for
+        #[structopt(long, parse(from_os_str))]
+        src: Option<PathBuf>,
+    },
+    /// Executes a program without generating a proof
+    Run {
+        #[structopt(parse(from_os_str))]
         src: PathBuf,
+        /// file the program reads its input from
         #[structopt(long, parse(from_os_str))]
-        proof: PathBuf,
-        #[structopt(long, default_value = "")]
-        input: String,
-        #[structopt(long)]
-        output: String,
+        input: Option<PathBuf>,
+        /// number of memory cells available to the program
+        #[structopt(long, default_value = "1024")]
+        tape_size: usize,
     },
 }
 
 fn main() {
     // proof options for 128 bit security level
-    let num_queries = 29;
-    let lde_blowup_factor = 16;
-    let grinding_factor = 16;
-    let fri_folding_factor = 8;
-    let fri_max_remainder_size = 64;
-    let options = ProofOptions::new(
-        num_queries,
-        lde_blowup_factor,
-        grinding_factor,
-        fri_folding_factor,
-        fri_max_remainder_size,
-    );
+    let options = ProofOptions::secure_128::<BrainfuckAir>().unwrap();
 
     // read command-line args
     match BrainfuckOptions::from_args() {
-        BrainfuckOptions::Prove { src, dst, input } => prove(options, src, input, dst),
-        BrainfuckOptions::Verify {
+        BrainfuckOptions::Prove {
+            src,
+            input,
+            out,
+            tape_size,
+            private_input,
+        } => prove(options, src, input, tape_size, private_input, out),
+        BrainfuckOptions::Verify { proof, src } => verify(options, proof, src),
+        BrainfuckOptions::Run {
             src,
-            proof,
             input,
-            output,
-        } => verify(options, src, input, output, proof),
+            tape_size,
+        } => run(src, input, tape_size),
     }
 }
 
-fn prove(options: ProofOptions, source_code_path: PathBuf, input: String, output_path: PathBuf) {
+fn read_input(input_path: Option<PathBuf>) -> Vec<u8> {
+    input_path
+        .map(|path| fs::read(path).unwrap())
+        .unwrap_or_default()
+}
+
+fn prove(
+    options: ProofOptions,
+    source_code_path: PathBuf,
+    input_path: Option<PathBuf>,
+    tape_size: usize,
+    private_input: bool,
+    output_path: PathBuf,
+) {
     let source_code = fs::read_to_string(source_code_path).unwrap();
+    let input = read_input(input_path);
     let mut output = Vec::new();
 
     let now = Instant::now();
-    let trace = simulate(source_code, &mut input.as_bytes(), &mut output);
+    let trace = simulate(source_code, tape_size, &mut input.as_slice(), &mut output);
     println!(
         "Generated execution trace (cols={}, rows={}) in {:.0?}",
         trace.base_columns().num_cols(),
@@ -88,7 +119,10 @@ fn prove(options: ProofOptions, source_code_path: PathBuf, input: String, output
         String::from_utf8(output.clone()).unwrap()
     );
 
-    let prover = prover::BrainfuckProver::new(options);
+    let mut prover = prover::BrainfuckProver::new(options);
+    if private_input {
+        prover = prover.with_private_input();
+    }
     let proof = prover.generate_proof(trace).unwrap();
     println!("Proof generated in: {:.0?}", now.elapsed());
     println!(
@@ -105,22 +139,111 @@ fn prove(options: ProofOptions, source_code_path: PathBuf, input: String, output
     println!("Proof written to {}", output_path.as_path().display());
 }
 
-fn verify(
-    options: ProofOptions,
-    source_code_path: PathBuf,
-    input: String,
-    output: String,
-    proof_path: PathBuf,
-) {
-    let source_code = fs::read_to_string(source_code_path).unwrap();
+fn verify(options: ProofOptions, proof_path: PathBuf, source_code_path: Option<PathBuf>) {
     let proof_bytes = fs::read(proof_path).unwrap();
     let proof: Proof<BrainfuckAir> = Proof::deserialize_compressed(proof_bytes.as_slice()).unwrap();
-    assert_eq!(input.as_bytes(), proof.public_inputs.input);
-    assert_eq!(output.as_bytes(), proof.public_inputs.output);
-    assert_eq!(source_code, proof.public_inputs.source_code);
     assert_eq!(options, proof.options);
+    let mut execution_info = proof.public_inputs.clone().unwrap();
+    if let Some(source_code_path) = source_code_path {
+        // the input and output are still claimed by the proof but the program
+        // (and therefore the instruction evaluation hint) comes from the verifier
+        let source_code = fs::read_to_string(source_code_path).unwrap();
+        execution_info.program = compile(&source_code);
+    }
 
     let now = Instant::now();
-    proof.verify().unwrap();
+    proof
+        .verify_with_public_inputs(execution_info.clone())
+        .unwrap();
     println!("Proof verified in: {:?}", now.elapsed());
+    println!(
+        "Program hash: {}",
+        execution_info
+            .program_hash()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    );
+    match &execution_info.input {
+        Some(input) => println!("Program input: \"{}\"", String::from_utf8_lossy(input)),
+        None => println!(
+            "Program input: private ({} bytes)",
+            execution_info.input_len
+        ),
+    }
+    println!(
+        "Program output: \"{}\"",
+        String::from_utf8_lossy(&execution_info.output)
+    );
+}
+
+fn run(source_code_path: PathBuf, input_path: Option<PathBuf>, tape_size: usize) {
+    let source_code = fs::read_to_string(source_code_path).unwrap();
+    let input = read_input(input_path);
+    let mut output = Vec::new();
+
+    let now = Instant::now();
+    let trace = simulate(source_code, tape_size, &mut input.as_slice(), &mut output);
+    println!(
+        "Executed program (trace rows={}) in {:.0?}",
+        trace.base_columns().num_rows(),
+        now.elapsed(),
+    );
+    println!("Program output: \"{}\"", String::from_utf8_lossy(&output));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpu_poly::fields::p18446744069414584321::Fp;
+
+    fn prove_echo_with_private_input(input: &[u8]) -> Proof<BrainfuckAir> {
+        let options = ProofOptions::secure_128::<BrainfuckAir>().unwrap();
+        let mut output = Vec::new();
+        let trace = simulate(",.,.".to_string(), 16, &mut &*input, &mut output);
+        assert_eq!(input, output);
+        prover::BrainfuckProver::new(options)
+            .with_private_input()
+            .generate_proof(trace)
+            .unwrap()
+    }
+
+    #[test]
+    fn private_input_proof_verifies() {
+        let proof = prove_echo_with_private_input(b"hi");
+        let execution_info = proof.public_inputs.clone().unwrap();
+        assert_eq!(None, execution_info.input);
+        assert_eq!(2, execution_info.input_len);
+
+        proof.verify().unwrap();
+    }
+
+    #[test]
+    fn private_input_that_isnt_a_byte_is_rejected() {
+        let options = ProofOptions::secure_128::<BrainfuckAir>().unwrap();
+        let trace = simulate(",".to_string(), 16, &mut b"a".as_slice(), &mut Vec::new());
+        // reads 353 rather than `a` (97) consistently across the tables so
+        // only the byte range check on the input table fails
+        let from = Fp::from(b'a');
+        let trace = trace.with_read_value_replaced(from, from + Fp::from(256u64));
+
+        let prover = prover::BrainfuckProver::new(options).with_private_input();
+        if let Ok(proof) = prover.generate_proof(trace) {
+            assert!(proof.verify().is_err());
+        }
+    }
+
+    #[test]
+    fn private_input_proof_with_wrong_input_len_is_rejected() {
+        let proof = prove_echo_with_private_input(b"hi");
+        let execution_info = proof.public_inputs.clone().unwrap();
+
+        for input_len in [0, 1, 3, usize::MAX] {
+            let claimed = air::ExecutionInfo {
+                input_len,
+                ..execution_info.clone()
+            };
+            assert!(proof.clone().verify_with_public_inputs(claimed).is_err());
+        }
+    }
 }
@@ -6,7 +6,18 @@ use gpu_poly::fields::p18446744069414584321::Fq3;
 use ministark::ProofOptions;
 use ministark::Prover;
 
-pub struct BrainfuckProver(ProofOptions);
+pub struct BrainfuckProver {
+    options: ProofOptions,
+    private_input: bool,
+}
+
+impl BrainfuckProver {
+    /// Keeps the program input out of the public inputs
+    pub fn with_private_input(mut self) -> Self {
+        self.private_input = true;
+        self
+    }
+}
 
 impl Prover for BrainfuckProver {
     type Fp = Fp;
@@ -15,19 +26,24 @@ impl Prover for BrainfuckProver {
     type Trace = BrainfuckTrace;
 
     fn new(options: ProofOptions) -> Self {
-        BrainfuckProver(options)
+        BrainfuckProver {
+            options,
+            private_input: false,
+        }
     }
 
     fn options(&self) -> ProofOptions {
-        self.0
+        self.options
     }
 
     fn get_pub_inputs(&self, trace: &BrainfuckTrace) -> ExecutionInfo {
         let meta = trace.meta();
-        ExecutionInfo {
-            source_code: meta.source_code.to_string(),
-            input: meta.input.to_vec(),
-            output: meta.output.to_vec(),
+        let execution_info =
+            ExecutionInfo::new(&meta.source_code, meta.input.to_vec(), meta.output.to_vec());
+        if self.private_input {
+            execution_info.with_private_input()
+        } else {
+            execution_info
         }
     }
 }
@@ -1,3 +1,5 @@
+use ministark::gadgets::bits::BitColumns;
+
 pub trait BrainfuckColumn {
     const FIRST_TRACE_COL_INDEX: usize;
     const LAST_TRACE_COL_INDEX: usize;
@@ -40,7 +42,7 @@ impl ministark::constraint::Hint for EvaluationArgumentHint {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum ProcessorBaseColumn {
     Cycle,
     Ip, // instruction pointer
@@ -49,10 +51,11 @@ pub enum ProcessorBaseColumn {
     Mp, // memory pointer
     MemVal,
     MemValInv,
-    Dummy, // indicate if a row is padding
+    MemValMaxInv, // inverse of (MemVal - 255) or zero
+    Dummy,        // indicate if a row is padding
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum ProcessorExtensionColumn {
     InstructionPermutation, // 18
     MemoryPermutation,
@@ -60,7 +63,7 @@ pub enum ProcessorExtensionColumn {
     OutputEvaluation,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum MemoryBaseColumn {
     Cycle,
     Mp,
@@ -68,12 +71,12 @@ pub enum MemoryBaseColumn {
     Dummy,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum MemoryExtensionColumn {
     Permutation,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum InstructionBaseColumn {
     Ip,
     CurrInstr, // 13
@@ -81,28 +84,28 @@ pub enum InstructionBaseColumn {
     // Dummy, // indicate if a row is padding
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum InstructionExtensionColumn {
     ProcessorPermutation,
     ProgramEvaluation,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum InputBaseColumn {
     Value,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum InputExtensionColumn {
     Evaluation,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum OutputBaseColumn {
     Value,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum OutputExtensionColumn {
     Evaluation,
 }
@@ -124,7 +127,13 @@ impl BrainfuckColumn for InstructionBaseColumn {
 
 impl BrainfuckColumn for InputBaseColumn {
     const FIRST_TRACE_COL_INDEX: usize = InstructionBaseColumn::LAST_TRACE_COL_INDEX + 1;
-    const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::Value as usize;
+    const LAST_TRACE_COL_INDEX: usize =
+        Self::FIRST_TRACE_COL_INDEX + Self::VALUE_BITS.offset + Self::VALUE_BITS.num_bits - 1;
+}
+
+impl InputBaseColumn {
+    /// Bits of `Value` within the input table. They follow `Value`.
+    pub const VALUE_BITS: BitColumns = BitColumns::new(InputBaseColumn::Value as usize + 1, 8);
 }
 
 impl BrainfuckColumn for OutputBaseColumn {
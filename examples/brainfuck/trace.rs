@@ -11,18 +11,16 @@ use crate::tables::OutputExtensionColumn;
 use crate::tables::ProcessorBaseColumn;
 use crate::tables::ProcessorExtensionColumn;
 use crate::vm::OpCode;
-use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::PrimeField;
 use ark_ff::UniformRand;
 use ark_ff::Zero;
 use ark_std::rand;
-use gpu_poly::allocator::PageAlignedAllocator;
 use gpu_poly::fields::p18446744069414584321::Fp;
 use gpu_poly::fields::p18446744069414584321::Fq3;
-use gpu_poly::GpuVec;
 use ministark::challenges::Challenges;
 // use ministark::constraint::Challenge as _;
+use ministark::segment_column_names;
 use ministark::Matrix;
 use ministark::Trace;
 
@@ -72,19 +70,140 @@ impl BrainfuckTrace {
     pub fn meta(&self) -> &TraceMeta {
         &self.meta
     }
+
+    /// Replaces the value `from` with `to` everywhere it's read i.e. in the
+    /// `MemVal` columns of the processor and memory tables and in the input
+    /// table's `Value` column. The processor's inverse columns are updated to
+    /// match but the input value's bits are left alone.
+    #[cfg(test)]
+    pub fn with_read_value_replaced(self, from: Fp, to: Fp) -> Self {
+        use ark_ff::Field;
+        let Self {
+            meta,
+            mut processor_base_trace,
+            mut memory_base_trace,
+            instruction_base_trace,
+            mut input_base_trace,
+            output_base_trace,
+            ..
+        } = self;
+        for row in 0..processor_base_trace.num_rows() {
+            use ProcessorBaseColumn::*;
+            if processor_base_trace[MemVal as usize][row] == from {
+                processor_base_trace[MemVal as usize][row] = to;
+                processor_base_trace[MemValInv as usize][row] = to.inverse().unwrap();
+                processor_base_trace[MemValMaxInv as usize][row] =
+                    (to - Fp::from(u8::MAX)).inverse().unwrap();
+            }
+        }
+        let columns = [
+            &mut memory_base_trace[MemoryBaseColumn::MemVal as usize],
+            &mut input_base_trace[InputBaseColumn::Value as usize],
+        ];
+        for column in columns {
+            column
+                .iter_mut()
+                .filter(|value| **value == from)
+                .for_each(|value| *value = to);
+        }
+        BrainfuckTrace::new(
+            meta,
+            processor_base_trace,
+            memory_base_trace,
+            instruction_base_trace,
+            input_base_trace,
+            output_base_trace,
+        )
+    }
 }
 
 impl Trace for BrainfuckTrace {
     type Fp = Fp;
     type Fq = Fq3;
 
-    const NUM_BASE_COLUMNS: usize = 17;
+    const NUM_BASE_COLUMNS: usize = 26;
     const NUM_EXTENSION_COLUMNS: usize = 9;
 
     fn len(&self) -> usize {
         self.base_trace.num_rows()
     }
 
+    fn column_names(&self) -> Vec<String> {
+        use InputBaseColumn as InputBase;
+        use InputExtensionColumn as InputExtension;
+        use InstructionBaseColumn as InstructionBase;
+        use InstructionExtensionColumn as InstructionExtension;
+        use MemoryBaseColumn as MemoryBase;
+        use MemoryExtensionColumn as MemoryExtension;
+        use OutputBaseColumn as OutputBase;
+        use OutputExtensionColumn as OutputExtension;
+        use ProcessorBaseColumn as ProcessorBase;
+        use ProcessorExtensionColumn as ProcessorExtension;
+
+        let mut names = vec![String::new(); Self::NUM_BASE_COLUMNS + Self::NUM_EXTENSION_COLUMNS];
+        segment_column_names(
+            &mut names,
+            "Processor",
+            [
+                ProcessorBase::Cycle,
+                ProcessorBase::Ip,
+                ProcessorBase::CurrInstr,
+                ProcessorBase::NextInstr,
+                ProcessorBase::Mp,
+                ProcessorBase::MemVal,
+                ProcessorBase::MemValInv,
+                ProcessorBase::MemValMaxInv,
+                ProcessorBase::Dummy,
+            ],
+        );
+        segment_column_names(
+            &mut names,
+            "Memory",
+            [
+                MemoryBase::Cycle,
+                MemoryBase::Mp,
+                MemoryBase::MemVal,
+                MemoryBase::Dummy,
+            ],
+        );
+        segment_column_names(
+            &mut names,
+            "Instruction",
+            [
+                InstructionBase::Ip,
+                InstructionBase::CurrInstr,
+                InstructionBase::NextInstr,
+            ],
+        );
+        segment_column_names(&mut names, "Input", [InputBase::Value]);
+        for (i, column) in InputBase::VALUE_BITS.columns().enumerate() {
+            names[InputBase::FIRST_TRACE_COL_INDEX + column] = format!("Input::ValueBit{i}");
+        }
+        segment_column_names(&mut names, "Output", [OutputBase::Value]);
+        segment_column_names(
+            &mut names,
+            "Processor",
+            [
+                ProcessorExtension::InstructionPermutation,
+                ProcessorExtension::MemoryPermutation,
+                ProcessorExtension::InputEvaluation,
+                ProcessorExtension::OutputEvaluation,
+            ],
+        );
+        segment_column_names(&mut names, "Memory", [MemoryExtension::Permutation]);
+        segment_column_names(
+            &mut names,
+            "Instruction",
+            [
+                InstructionExtension::ProcessorPermutation,
+                InstructionExtension::ProgramEvaluation,
+            ],
+        );
+        segment_column_names(&mut names, "Input", [InputExtension::Evaluation]);
+        segment_column_names(&mut names, "Output", [OutputExtension::Evaluation]);
+        names
+    }
+
     fn build_extension_columns(
         &self,
         challenges: &Challenges<Self::Fq>,
@@ -177,7 +296,7 @@ fn gen_processor_ext_matrix(
         extension_rows.push(extension_row);
     }
 
-    Matrix::new(into_columns(extension_rows))
+    Matrix::from_row_arrays(extension_rows)
 }
 
 fn gen_memory_ext_matrix(
@@ -207,7 +326,7 @@ fn gen_memory_ext_matrix(
         extension_rows.push(extension_row);
     }
 
-    Matrix::new(into_columns(extension_rows))
+    Matrix::from_row_arrays(extension_rows)
 }
 
 fn gen_instruction_ext_matrix(
@@ -258,7 +377,7 @@ fn gen_instruction_ext_matrix(
         extension_rows.push(extension_row);
     }
 
-    Matrix::new(into_columns(extension_rows))
+    Matrix::from_row_arrays(extension_rows)
 }
 
 fn gen_input_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>) -> Matrix<Fq3> {
@@ -279,7 +398,7 @@ fn gen_input_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>)
         extension_rows.push(extension_row);
     }
 
-    Matrix::new(into_columns(extension_rows))
+    Matrix::from_row_arrays(extension_rows)
 }
 
 fn gen_output_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>) -> Matrix<Fq3> {
@@ -300,17 +419,5 @@ fn gen_output_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>)
         extension_rows.push(extension_row);
     }
 
-    Matrix::new(into_columns(extension_rows))
-}
-
-pub fn into_columns<F: Field, const N: usize>(rows: Vec<[F; N]>) -> Vec<GpuVec<F>> {
-    let mut cols = (0..N)
-        .map(|_| Vec::new_in(PageAlignedAllocator))
-        .collect::<Vec<_>>();
-    for row in rows {
-        for (col, val) in cols.iter_mut().zip(row) {
-            col.push(val);
-        }
-    }
-    cols
+    Matrix::from_row_arrays(extension_rows)
 }
@@ -4,14 +4,15 @@ use crate::tables::InstructionBaseColumn;
 use crate::tables::MemoryBaseColumn;
 use crate::tables::OutputBaseColumn;
 use crate::tables::ProcessorBaseColumn;
-use crate::trace::into_columns;
 use crate::trace::TraceMeta;
 use crate::BrainfuckTrace;
 use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::Zero;
+use ministark::padding::padded_len;
+use ministark::padding::Padding;
+use ministark::padding::PaddingRules;
 use ministark::Matrix;
-use ministark::TraceInfo;
 
 type Fp = <BrainfuckTrace as ministark::Trace>::Fp;
 
@@ -108,20 +109,23 @@ struct Register {
 }
 
 // Outputs base execution trace
+// Memory cells are bytes with wrapping arithmetic i.e. `255 + 1 = 0`
 pub fn simulate(
     source_code: String,
+    tape_size: usize,
     input: &mut impl std::io::Read,
     output: &mut impl std::io::Write,
 ) -> BrainfuckTrace {
     let program = compile(&source_code);
 
-    let mut tape = [0u8; 1024];
+    let mut tape = vec![0u8; tape_size];
     let mut register = Register {
         curr_instr: program[0],
         next_instr: if program.len() == 1 { 0 } else { program[1] },
         ..Default::default()
     };
 
+    let max_mem_val = Fp::from(u8::MAX);
     let mut input_symbols = Vec::new();
     let mut output_symbols = Vec::new();
 
@@ -155,6 +159,7 @@ pub fn simulate(
             row[Mp as usize] = Fp::from(register.mp as u64);
             row[MemVal as usize] = mem_val;
             row[MemValInv as usize] = mem_val.inverse().unwrap_or_else(Fp::zero);
+            row[MemValMaxInv as usize] = (mem_val - max_mem_val).inverse().unwrap_or_else(Fp::zero);
             row[Dummy as usize] = Fp::from(register.curr_instr == 0);
             row
         });
@@ -183,16 +188,23 @@ pub fn simulate(
             }
         } else if register.curr_instr == OpCode::DecrementPointer as usize {
             register.ip += 1;
-            register.mp -= 1;
+            register.mp = register
+                .mp
+                .checked_sub(1)
+                .expect("memory pointer moved below the start of the tape");
         } else if register.curr_instr == OpCode::IncrementPointer as usize {
             register.ip += 1;
             register.mp += 1;
+            assert!(
+                register.mp < tape_size,
+                "memory pointer moved past the end of the tape (size {tape_size})"
+            );
         } else if register.curr_instr == OpCode::Increment as usize {
             register.ip += 1;
-            tape[register.mp] += 1;
+            tape[register.mp] = tape[register.mp].wrapping_add(1);
         } else if register.curr_instr == OpCode::Decrement as usize {
             register.ip += 1;
-            tape[register.mp] -= 1;
+            tape[register.mp] = tape[register.mp].wrapping_sub(1);
         } else if register.curr_instr == OpCode::Write as usize {
             register.ip += 1;
             let x = &tape[register.mp..register.mp + 1];
@@ -204,7 +216,10 @@ pub fn simulate(
             let mut x = [0u8; 1];
             input.read_exact(&mut x).expect("failed to read input");
             tape[register.mp] = x[0];
-            input_rows.push([x[0].into()]);
+            let mut row = [Fp::zero(); InputBaseColumn::NUM_TRACE_COLUMNS];
+            row[InputBaseColumn::Value as usize] = x[0].into();
+            InputBaseColumn::VALUE_BITS.write(&mut row, x[0].into());
+            input_rows.push(row);
             input_symbols.push(x[0]);
         } else {
             panic!("unrecognized instruction at ip:{}", register.ip);
@@ -228,6 +243,7 @@ pub fn simulate(
         row[Mp as usize] = Fp::from(register.mp as u64);
         row[MemVal as usize] = mem_val;
         row[MemValInv as usize] = mem_val.inverse().unwrap_or_default();
+        row[MemValMaxInv as usize] = (mem_val - max_mem_val).inverse().unwrap_or_default();
         row[Dummy as usize] = Fp::from(register.curr_instr == 0);
         row
     });
@@ -246,32 +262,24 @@ pub fn simulate(
 
     let mut memory_rows = derive_memory_rows(&processor_rows);
 
-    let padding_len = {
-        let max_length = [
-            processor_rows.len(),
-            memory_rows.len(),
-            instruction_rows.len(),
-            input_rows.len(),
-            output_rows.len(),
-            TraceInfo::MIN_TRACE_LENGTH,
-        ]
-        .into_iter()
-        .max()
-        .unwrap();
-        ceil_power_of_two(max_length)
-    };
-
-    pad_processor_rows(&mut processor_rows, padding_len);
-    pad_memory_rows(&mut memory_rows, padding_len);
-    pad_instruction_rows(&mut instruction_rows, padding_len);
-    pad_input_rows(&mut input_rows, padding_len);
-    pad_output_rows(&mut output_rows, padding_len);
-
-    let processor_base_trace = Matrix::new(into_columns(processor_rows));
-    let memory_base_trace = Matrix::new(into_columns(memory_rows));
-    let instruction_base_trace = Matrix::new(into_columns(instruction_rows));
-    let input_base_trace = Matrix::new(into_columns(input_rows));
-    let output_base_trace = Matrix::new(into_columns(output_rows));
+    let padding_len = padded_len([
+        processor_rows.len(),
+        memory_rows.len(),
+        instruction_rows.len(),
+        input_rows.len(),
+        output_rows.len(),
+    ]);
+    processor_padding().pad_rows(&mut processor_rows, padding_len);
+    memory_padding().pad_rows(&mut memory_rows, padding_len);
+    instruction_padding().pad_rows(&mut instruction_rows, padding_len);
+    PaddingRules::new(InputBaseColumn::NUM_TRACE_COLUMNS).pad_rows(&mut input_rows, padding_len);
+    PaddingRules::new(OutputBaseColumn::NUM_TRACE_COLUMNS).pad_rows(&mut output_rows, padding_len);
+
+    let processor_base_trace = Matrix::from_row_arrays(processor_rows);
+    let memory_base_trace = Matrix::from_row_arrays(memory_rows);
+    let instruction_base_trace = Matrix::from_row_arrays(instruction_rows);
+    let input_base_trace = Matrix::from_row_arrays(input_rows);
+    let output_base_trace = Matrix::from_row_arrays(output_rows);
 
     let meta = TraceMeta {
         input: input_symbols,
@@ -289,60 +297,27 @@ pub fn simulate(
     )
 }
 
-fn pad_processor_rows(rows: &mut Vec<[Fp; ProcessorBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
+/// Padding rows keep the processor state and are marked as dummy rows
+fn processor_padding() -> PaddingRules<Fp> {
     use ProcessorBaseColumn::*;
-    while rows.len() < n {
-        let last_row = rows.last().unwrap();
-        let mut new_row = [Fp::zero(); ProcessorBaseColumn::NUM_TRACE_COLUMNS];
-        new_row[Cycle as usize] = last_row[Cycle as usize] + Fp::one();
-        new_row[Ip as usize] = last_row[Ip as usize];
-        new_row[CurrInstr as usize] = Fp::zero();
-        new_row[NextInstr as usize] = Fp::zero();
-        new_row[Mp as usize] = last_row[Mp as usize];
-        new_row[MemVal as usize] = last_row[MemVal as usize];
-        new_row[MemValInv as usize] = last_row[MemValInv as usize];
-        new_row[Dummy as usize] = Fp::one();
-        rows.push(new_row);
-    }
+    PaddingRules::repeat_last(ProcessorBaseColumn::NUM_TRACE_COLUMNS)
+        .with(Cycle as usize, Padding::Increment(Fp::one()))
+        .with(CurrInstr as usize, Padding::Zero)
+        .with(NextInstr as usize, Padding::Zero)
+        .with(Dummy as usize, Padding::Constant(Fp::one()))
 }
 
-fn pad_memory_rows(rows: &mut Vec<[Fp; MemoryBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
+fn memory_padding() -> PaddingRules<Fp> {
     use MemoryBaseColumn::*;
-    while rows.len() < n {
-        let last_row = rows.last().unwrap();
-        let mut new_row = [Fp::zero(); MemoryBaseColumn::NUM_TRACE_COLUMNS];
-        new_row[Cycle as usize] = last_row[Cycle as usize] + Fp::one();
-        new_row[Mp as usize] = last_row[Mp as usize];
-        new_row[MemVal as usize] = last_row[MemVal as usize];
-        new_row[Dummy as usize] = Fp::one();
-        rows.push(new_row);
-    }
+    PaddingRules::repeat_last(MemoryBaseColumn::NUM_TRACE_COLUMNS)
+        .with(Cycle as usize, Padding::Increment(Fp::one()))
+        .with(Dummy as usize, Padding::Constant(Fp::one()))
 }
 
-fn pad_instruction_rows(rows: &mut Vec<[Fp; InstructionBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
+fn instruction_padding() -> PaddingRules<Fp> {
     use InstructionBaseColumn::*;
-    let last_ip = rows.last().unwrap()[Ip as usize];
-    while rows.len() < n {
-        let mut new_row = [Fp::zero(); InstructionBaseColumn::NUM_TRACE_COLUMNS];
-        new_row[Ip as usize] = last_ip;
-        new_row[CurrInstr as usize] = Fp::zero();
-        new_row[NextInstr as usize] = Fp::zero();
-        rows.push(new_row);
-    }
-}
-
-fn pad_input_rows(rows: &mut Vec<[Fp; InputBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
-    while rows.len() < n {
-        let new_row = [Fp::zero(); InputBaseColumn::NUM_TRACE_COLUMNS];
-        rows.push(new_row);
-    }
-}
-
-fn pad_output_rows(rows: &mut Vec<[Fp; OutputBaseColumn::NUM_TRACE_COLUMNS]>, n: usize) {
-    while rows.len() < n {
-        let new_row = [Fp::zero(); OutputBaseColumn::NUM_TRACE_COLUMNS];
-        rows.push(new_row);
-    }
+    PaddingRules::new(InstructionBaseColumn::NUM_TRACE_COLUMNS)
+        .with(Ip as usize, Padding::RepeatLast)
 }
 
 fn derive_memory_rows(
@@ -389,12 +364,3 @@ fn derive_memory_rows(
 
     memory_rows
 }
-
-/// Rounds the input value up the the nearest power of two
-fn ceil_power_of_two(value: usize) -> usize {
-    if value.is_power_of_two() {
-        value
-    } else {
-        value.next_power_of_two()
-    }
-}
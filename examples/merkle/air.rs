@@ -0,0 +1,215 @@
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::constraint::is_binary;
+use ministark::gadgets::rescue;
+use ministark::gadgets::rescue::RescueColumns;
+use ministark::gadgets::rescue::RescuePrime;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::ProofOptions;
+use ministark::TraceInfo;
+
+/// Columns after the Rescue-Prime gadget's columns. Each permutation of the
+/// trace is a level of the authentication path. A cycle is the
+/// [rescue::CYCLE_LENGTH] rows of a permutation. Values in these columns are
+/// constant within a cycle.
+#[derive(Clone, Copy)]
+pub enum MerkleColumn {
+    /// Digest of the node at this level. The leaf on the first level
+    Node(usize),
+    /// One if the node is the right child of its parent and zero otherwise
+    IsRight,
+    /// One once the path has reached the root. The remaining cycles pad the
+    /// trace to a power of two length and keep the root in [MerkleColumn::Node].
+    IsPadding,
+    /// Index of the cycle
+    Level,
+}
+
+impl Column for MerkleColumn {
+    fn index(&self) -> usize {
+        match self {
+            Self::Node(i) => rescue::NUM_COLUMNS + i,
+            Self::IsRight => rescue::NUM_COLUMNS + rescue::DIGEST_SIZE,
+            Self::IsPadding => rescue::NUM_COLUMNS + rescue::DIGEST_SIZE + 1,
+            Self::Level => rescue::NUM_COLUMNS + rescue::DIGEST_SIZE + 2,
+        }
+    }
+}
+
+pub const NUM_BASE_COLUMNS: usize = rescue::NUM_COLUMNS + rescue::DIGEST_SIZE + 3;
+
+pub const RESCUE_COLUMNS: RescueColumns = RescueColumns {
+    trace_offset: 0,
+    periodic_offset: NUM_BASE_COLUMNS,
+};
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct MembershipInfo {
+    pub leaf: Vec<Fp>,
+    pub root: Vec<Fp>,
+    pub depth: u32,
+}
+
+pub struct MerkleAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    info: MembershipInfo,
+    rescue_prime: RescuePrime,
+    boundary_constraints: Vec<Constraint<Fq3>>,
+    transition_constraints: Vec<Constraint<Fq3>>,
+    terminal_constraints: Vec<Constraint<Fq3>>,
+}
+
+/// Constraints binding the input of a level's permutation to its node. The
+/// capacity holds the input length and the node is the left or right half of
+/// the rate depending on [MerkleColumn::IsRight]. The other half is the
+/// sibling which is unconstrained.
+fn merge_input_constraints(
+    state: impl Fn(usize) -> Constraint<Fq3>,
+    node: impl Fn(usize) -> Constraint<Fq3>,
+    is_right: Constraint<Fq3>,
+) -> Vec<Constraint<Fq3>> {
+    let mut constraints = Vec::new();
+    for i in rescue::CAPACITY_RANGE {
+        let expected = if i == rescue::CAPACITY_RANGE.start {
+            Fq3::from((2 * rescue::DIGEST_SIZE) as u64)
+        } else {
+            Fq3::zero()
+        };
+        constraints.push(state(i) - expected);
+    }
+    let is_left = Constraint::from(Fq3::one()) - &is_right;
+    for i in 0..rescue::DIGEST_SIZE {
+        let left = state(rescue::RATE_RANGE.start + i);
+        let right = state(rescue::RATE_RANGE.start + rescue::DIGEST_SIZE + i);
+        constraints.push(&is_left * (left - node(i)) + &is_right * (right - node(i)));
+    }
+    constraints
+}
+
+impl Air for MerkleAir {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = MembershipInfo;
+
+    fn new(trace_info: TraceInfo, info: MembershipInfo, options: ProofOptions) -> Self {
+        use MerkleColumn::*;
+        assert_eq!(info.leaf.len(), rescue::DIGEST_SIZE);
+        assert_eq!(info.root.len(), rescue::DIGEST_SIZE);
+        let num_cycles = trace_info.trace_len / rescue::CYCLE_LENGTH;
+        assert!(info.depth > 0, "path must have at least one level");
+        assert!(
+            (info.depth as usize) < num_cycles,
+            "path doesn't fit in trace"
+        );
+        let rescue_prime = RescuePrime::new();
+        let state = RESCUE_COLUMNS;
+        let one = Fq3::one();
+
+        // the first level hashes the leaf
+        let mut boundary_constraints = vec![
+            is_binary(IsRight.curr::<Fq3>()),
+            IsPadding.curr(),
+            Level.curr(),
+        ];
+        for (i, &v) in info.leaf.iter().enumerate() {
+            boundary_constraints.push(Node(i).curr() - Fq3::from(v));
+        }
+        boundary_constraints.extend(merge_input_constraints(
+            |i| state.state(i).curr(),
+            |i| Node(i).curr(),
+            IsRight.curr(),
+        ));
+
+        let round_flag = state.round_flag().curr::<Fq3>();
+        let is_last_round = Constraint::from(one) - &round_flag;
+        let mut transition_constraints = rescue_prime.transition_constraints(state);
+        transition_constraints.push(is_binary(IsRight.next::<Fq3>()));
+        transition_constraints.push(is_binary(IsPadding.next::<Fq3>()));
+
+        // values other than the Rescue state only change between cycles
+        let mut per_cycle = vec![IsRight, IsPadding, Level];
+        per_cycle.extend((0..rescue::DIGEST_SIZE).map(Node));
+        for column in per_cycle {
+            transition_constraints.push(&round_flag * (column.next() - column.curr()));
+        }
+
+        // between cycles. The parent of a node is the output of the permutation.
+        // Once padding starts the root is carried through to the last row.
+        let is_padding = IsPadding.curr::<Fq3>();
+        let is_path = Constraint::from(one) - &is_padding;
+        for i in 0..rescue::DIGEST_SIZE {
+            let output = state.state(rescue::DIGEST_RANGE.start + i).curr();
+            let parent = &is_path * (Node(i).next() - output)
+                + &is_padding * (Node(i).next() - Node(i).curr());
+            transition_constraints.push(&is_last_round * parent);
+        }
+        let level_increments = Level.next() - Level.curr() - one;
+        transition_constraints.push(&is_last_round * level_increments);
+        // padding can't stop once it starts and must start at level `depth`
+        let stays_padding = &is_padding * (IsPadding.next() - one);
+        transition_constraints.push(&is_last_round * stays_padding);
+        let starts_padding =
+            (IsPadding.next() - &is_padding) * (Level.next() - Fq3::from(info.depth as u64));
+        transition_constraints.push(&is_last_round * starts_padding);
+        // the next level's permutation hashes the parent with its sibling
+        for constraint in merge_input_constraints(
+            |i| state.state(i).next(),
+            |i| Node(i).next(),
+            IsRight.next(),
+        ) {
+            let is_next_path = Constraint::from(one) - IsPadding.next();
+            transition_constraints.push(&is_last_round * is_next_path * constraint);
+        }
+
+        // the path ends at the root
+        let mut terminal_constraints = vec![IsPadding.curr() - one];
+        for (i, &v) in info.root.iter().enumerate() {
+            terminal_constraints.push(Node(i).curr() - Fq3::from(v));
+        }
+
+        MerkleAir {
+            options,
+            trace_info,
+            info,
+            rescue_prime,
+            boundary_constraints,
+            transition_constraints,
+            terminal_constraints,
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.info
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<Fp>> {
+        self.rescue_prime.periodic_columns()
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.terminal_constraints
+    }
+}
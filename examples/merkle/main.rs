@@ -0,0 +1,137 @@
+//! Proves a leaf is in a Merkle tree with a public root without revealing the
+//! leaf's position or the authentication path. Nodes are hashed with the
+//! Rescue-Prime gadget and each level of the path is a single permutation.
+#![feature(allocator_api)]
+
+use air::MembershipInfo;
+use air::MerkleAir;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::gadgets::rescue::RescuePrime;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use std::time::Instant;
+use structopt::StructOpt;
+use trace::AuthPath;
+use trace::Digest;
+use trace::MerkleTrace;
+
+mod air;
+mod trace;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "merkle", about = "miniSTARK Merkle membership prover")]
+struct MerkleOptions {
+    /// depth of the Merkle tree i.e. the tree has `2^depth` leaves
+    #[structopt(long, default_value = "10")]
+    depth: usize,
+    /// index of the leaf to prove membership of
+    #[structopt(long, default_value = "42")]
+    index: usize,
+}
+
+struct MerkleProver(ProofOptions);
+
+impl Prover for MerkleProver {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type Air = MerkleAir;
+    type Trace = MerkleTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        MerkleProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &MerkleTrace) -> MembershipInfo {
+        MembershipInfo {
+            leaf: trace.leaf().to_vec(),
+            root: trace.root().to_vec(),
+            depth: trace.depth() as u32,
+        }
+    }
+}
+
+/// Builds a tree with leaves `H(0), H(1), ..., H(2^depth - 1)`. Returns all
+/// layers of the tree starting with the leaves.
+fn build_tree(rescue_prime: &RescuePrime, depth: usize) -> Vec<Vec<Digest>> {
+    let leaves = (0..1u64 << depth)
+        .map(|i| rescue_prime.hash_elements(&[Fp::from(i)]))
+        .collect::<Vec<Digest>>();
+    let mut layers = vec![leaves];
+    while layers.last().unwrap().len() > 1 {
+        let layer = layers
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| rescue_prime.merge(&pair[0], &pair[1]))
+            .collect();
+        layers.push(layer);
+    }
+    layers
+}
+
+fn auth_path(layers: &[Vec<Digest>], mut index: usize) -> AuthPath {
+    let leaf = layers[0][index];
+    let mut siblings = Vec::new();
+    for layer in &layers[..layers.len() - 1] {
+        siblings.push((layer[index ^ 1], index % 2 == 1));
+        index /= 2;
+    }
+    AuthPath { leaf, siblings }
+}
+
+fn main() {
+    let MerkleOptions { depth, index } = MerkleOptions::from_args();
+    assert!(index < 1 << depth, "leaf index out of range");
+    let options = ProofOptions::secure_128::<MerkleAir>().unwrap();
+    let rescue_prime = RescuePrime::new();
+
+    let now = Instant::now();
+    let layers = build_tree(&rescue_prime, depth);
+    let root = layers.last().unwrap()[0];
+    println!(
+        "Built tree with {} leaves in {:.0?}",
+        1 << depth,
+        now.elapsed()
+    );
+
+    let now = Instant::now();
+    let path = auth_path(&layers, index);
+    let trace = trace::gen_trace(&rescue_prime, &path);
+    assert_eq!(root, trace.root());
+    println!(
+        "Generated trace (cols={}, rows={}) in {:.0?}",
+        trace.base_columns().num_cols(),
+        trace.len(),
+        now.elapsed()
+    );
+
+    let prover = MerkleProver::new(options);
+    let proof = prover.generate_proof(trace).unwrap();
+    println!("Proof generated in: {:.0?}", now.elapsed());
+    println!("Root: {:?}", root);
+
+    // the verifier already knows the leaf and root so they aren't sent
+    let proof = proof.without_public_inputs();
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    println!("Proof size: {:?}KB", proof_bytes.len() / 1024);
+
+    let proof = Proof::<MerkleAir>::deserialize_compressed(proof_bytes.as_slice()).unwrap();
+    let now = Instant::now();
+    let membership_info = MembershipInfo {
+        leaf: path.leaf.to_vec(),
+        root: root.to_vec(),
+        depth: depth as u32,
+    };
+    proof.verify_with_public_inputs(membership_info).unwrap();
+    println!("Proof verified in: {:.0?}", now.elapsed());
+}
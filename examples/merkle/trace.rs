@@ -0,0 +1,137 @@
+use crate::air::MerkleColumn;
+use crate::air::NUM_BASE_COLUMNS;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::gadgets::rescue;
+use ministark::gadgets::rescue::RescuePrime;
+use ministark::Column;
+use ministark::Matrix;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+pub type Digest = [Fp; rescue::DIGEST_SIZE];
+
+/// Sibling of each node on the path from a leaf to the root and whether the
+/// node is the right child of its parent
+pub struct AuthPath {
+    pub leaf: Digest,
+    pub siblings: Vec<(Digest, bool)>,
+}
+
+pub struct MerkleTrace {
+    leaf: Digest,
+    root: Digest,
+    depth: usize,
+    base_columns: Matrix<Fp>,
+}
+
+impl MerkleTrace {
+    pub fn leaf(&self) -> Digest {
+        self.leaf
+    }
+
+    pub fn root(&self) -> Digest {
+        self.root
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Trace for MerkleTrace {
+    type Fp = Fp;
+    type Fq = Fq3;
+
+    const NUM_BASE_COLUMNS: usize = NUM_BASE_COLUMNS;
+
+    fn len(&self) -> usize {
+        self.base_columns.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_columns
+    }
+}
+
+/// Input state of the permutation that merges two digests
+fn merge_input_state(left: &Digest, right: &Digest) -> [Fp; rescue::STATE_WIDTH] {
+    let mut state = [Fp::zero(); rescue::STATE_WIDTH];
+    state[rescue::CAPACITY_RANGE.start] = Fp::from((2 * rescue::DIGEST_SIZE) as u64);
+    state[rescue::DIGEST_RANGE].copy_from_slice(left);
+    state[rescue::DIGEST_RANGE.end..].copy_from_slice(right);
+    state
+}
+
+pub fn gen_trace(rescue_prime: &RescuePrime, path: &AuthPath) -> MerkleTrace {
+    let depth = path.siblings.len();
+    // at least one padding cycle holds the root on the last row
+    let min_cycles = TraceInfo::MIN_TRACE_LENGTH / rescue::CYCLE_LENGTH;
+    let num_cycles = std::cmp::max((depth + 1).next_power_of_two(), min_cycles);
+    let trace_len = num_cycles * rescue::CYCLE_LENGTH;
+    let mut columns = (0..NUM_BASE_COLUMNS)
+        .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+
+    // padding cycles permute an all zero state
+    let padding_rows = rescue_prime.permutation_trace([Fp::zero(); rescue::STATE_WIDTH]);
+
+    let mut node = path.leaf;
+    for level in 0..num_cycles {
+        let (rescue_rows, is_right, is_padding) = match path.siblings.get(level) {
+            Some((sibling, is_right)) => {
+                let state = if *is_right {
+                    merge_input_state(sibling, &node)
+                } else {
+                    merge_input_state(&node, sibling)
+                };
+                (rescue_prime.permutation_trace(state), *is_right, false)
+            }
+            None => (padding_rows.clone(), false, true),
+        };
+
+        for rescue_row in &rescue_rows {
+            let mut row = vec![Fp::zero(); NUM_BASE_COLUMNS];
+            row[..rescue::NUM_COLUMNS].copy_from_slice(rescue_row);
+            for (i, &v) in node.iter().enumerate() {
+                row[MerkleColumn::Node(i).index()] = v;
+            }
+            row[MerkleColumn::IsRight.index()] = Fp::from(is_right);
+            row[MerkleColumn::IsPadding.index()] = Fp::from(is_padding);
+            row[MerkleColumn::Level.index()] = Fp::from(level as u64);
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+
+        if !is_padding {
+            let output = rescue_rows.last().unwrap();
+            node = output[rescue::DIGEST_RANGE].try_into().unwrap();
+        }
+    }
+
+    // sanity check the trace against the hash function
+    let mut expected = path.leaf;
+    for (sibling, is_right) in &path.siblings {
+        expected = if *is_right {
+            rescue_prime.merge(sibling, &expected)
+        } else {
+            rescue_prime.merge(&expected, sibling)
+        };
+    }
+    assert_eq!(expected, node);
+    assert!(columns[MerkleColumn::IsPadding.index()]
+        .last()
+        .unwrap()
+        .is_one());
+
+    MerkleTrace {
+        leaf: path.leaf,
+        root: node,
+        depth,
+        base_columns: Matrix::new(columns),
+    }
+}
@@ -0,0 +1,177 @@
+//! Minimal end-to-end example. Proves knowledge of the `n`th Fibonacci number
+//! using a single table with two columns:
+//!
+//! | row   | A    | B           |
+//! |-------|------|-------------|
+//! | 0     | 1    | 1           |
+//! | 1     | 1    | 2           |
+//! | i + 1 | B[i] | A[i] + B[i] |
+//!
+//! The trace is over a 64-bit field. Constraints and verifier challenges use
+//! its cubic extension since a 64-bit field alone can't reach 96 bits of
+//! security.
+#![feature(allocator_api)]
+
+use ark_ff::One;
+use ark_ff_optimized::fp64::Fp;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+use std::time::Instant;
+
+/// Columns of the execution trace
+#[derive(Clone, Copy)]
+enum FibColumn {
+    A,
+    B,
+}
+
+impl Column for FibColumn {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Execution trace. Holds the columns of the table above.
+struct FibTrace(Matrix<Fp>);
+
+impl Trace for FibTrace {
+    type Fp = Fp;
+    type Fq = Fq3;
+
+    const NUM_BASE_COLUMNS: usize = 2;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+/// Builds a trace with `n` rows. `n` must be a power of two.
+fn gen_trace(n: usize) -> FibTrace {
+    assert!(n.is_power_of_two());
+    assert!(n >= TraceInfo::MIN_TRACE_LENGTH);
+
+    let mut a = Vec::with_capacity_in(n, PageAlignedAllocator);
+    let mut b = Vec::with_capacity_in(n, PageAlignedAllocator);
+    let (mut curr, mut next) = (Fp::one(), Fp::one());
+    for _ in 0..n {
+        a.push(curr);
+        b.push(next);
+        (curr, next) = (next, curr + next);
+    }
+
+    FibTrace(Matrix::new(vec![a, b]))
+}
+
+/// The constraints a valid trace must satisfy. The claimed result is a public
+/// input which both the prover and verifier know.
+struct FibAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    result: Fp,
+    boundary_constraints: Vec<Constraint<Fq3>>,
+    transition_constraints: Vec<Constraint<Fq3>>,
+    terminal_constraints: Vec<Constraint<Fq3>>,
+}
+
+impl Air for FibAir {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        use FibColumn::*;
+        FibAir {
+            options,
+            trace_info,
+            result,
+            // constraints on the first row
+            boundary_constraints: vec![A.curr() - Fq3::one(), B.curr() - Fq3::one()],
+            // constraints between every row and the next
+            transition_constraints: vec![A.next() - B.curr(), B.next() - A.curr() - B.curr()],
+            // constraints on the last row
+            terminal_constraints: vec![B.curr() - Fq3::from(result)],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.result
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.terminal_constraints
+    }
+}
+
+/// Ties the trace to the AIR. The prover reads public inputs from the trace.
+struct FibProver(ProofOptions);
+
+impl Prover for FibProver {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type Air = FibAir;
+    type Trace = FibTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        FibProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibTrace) -> Fp {
+        *trace.0[FibColumn::B as usize].last().unwrap()
+    }
+}
+
+fn main() {
+    let options = ProofOptions::secure_96::<FibAir>().unwrap();
+    let prover = FibProver::new(options);
+    let trace = gen_trace(1 << 16);
+
+    let now = Instant::now();
+    let proof = prover.generate_proof(trace).unwrap();
+    println!("Proof generated in: {:.0?}", now.elapsed());
+    println!("Result: {}", proof.public_inputs.as_ref().unwrap());
+
+    // send the proof to the verifier as bytes
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    println!("Proof size: {:?}KB", proof_bytes.len() / 1024);
+
+    let proof = Proof::<FibAir>::deserialize_compressed(proof_bytes.as_slice()).unwrap();
+    let now = Instant::now();
+    proof.verify().unwrap();
+    println!("Proof verified in: {:.0?}", now.elapsed());
+}
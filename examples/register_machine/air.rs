@@ -0,0 +1,182 @@
+use crate::tables;
+use crate::tables::Challenge;
+use crate::tables::PublicInputHint;
+use crate::trace::compress_instruction;
+use crate::vm::assemble;
+use crate::vm::Instruction;
+use crate::vm::NUM_REGISTERS;
+use ark_ff::Field;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::constraint::Hint;
+use ministark::hints::Hints;
+use ministark::Air;
+use ministark::Constraint;
+use ministark::ProofOptions;
+use ministark::TraceInfo;
+
+#[derive(CanonicalSerialize, Clone)]
+pub struct ExecutionInfo {
+    pub source_code: String,
+    pub initial_registers: Vec<Fp>,
+    pub final_registers: Vec<Fp>,
+}
+
+impl Valid for ExecutionInfo {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.initial_registers.len() != NUM_REGISTERS
+            || self.final_registers.len() != NUM_REGISTERS
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(())
+    }
+}
+
+// execution info is read from untrusted proofs so register lengths are
+// checked here rather than panicking in `RegisterMachineAir::new`
+impl CanonicalDeserialize for ExecutionInfo {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let execution_info = ExecutionInfo {
+            source_code: String::deserialize_with_mode(&mut reader, compress, validate)?,
+            initial_registers: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            final_registers: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        if validate == Validate::Yes {
+            execution_info.check()?;
+        }
+        Ok(execution_info)
+    }
+}
+
+pub struct RegisterMachineAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    execution_info: ExecutionInfo,
+    boundary_constraints: Vec<Constraint<Fq3>>,
+    transition_constraints: Vec<Constraint<Fq3>>,
+    terminal_constraints: Vec<Constraint<Fq3>>,
+}
+
+impl Air for RegisterMachineAir {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = ExecutionInfo;
+
+    /// Panics if the execution info has the wrong number of registers.
+    /// Execution info read from a proof is checked during deserialization.
+    fn new(trace_info: TraceInfo, execution_info: ExecutionInfo, options: ProofOptions) -> Self {
+        assert_eq!(execution_info.initial_registers.len(), NUM_REGISTERS);
+        assert_eq!(execution_info.final_registers.len(), NUM_REGISTERS);
+        RegisterMachineAir {
+            options,
+            trace_info,
+            execution_info,
+            transition_constraints: vec![
+                tables::ProcessorBaseColumn::transition_constraints(),
+                tables::ProcessorExtensionColumn::transition_constraints(),
+                tables::ProgramExtensionColumn::transition_constraints(),
+                tables::MemoryBaseColumn::transition_constraints(),
+                tables::MemoryExtensionColumn::transition_constraints(),
+            ]
+            .concat(),
+            boundary_constraints: vec![
+                tables::ProcessorBaseColumn::boundary_constraints(),
+                tables::ProcessorExtensionColumn::boundary_constraints(),
+                tables::ProgramExtensionColumn::boundary_constraints(),
+                tables::MemoryBaseColumn::boundary_constraints(),
+                tables::MemoryExtensionColumn::boundary_constraints(),
+            ]
+            .concat(),
+            terminal_constraints: vec![
+                tables::ProcessorBaseColumn::terminal_constraints(),
+                tables::ProcessorExtensionColumn::terminal_constraints(),
+                tables::ProgramExtensionColumn::terminal_constraints(),
+            ]
+            .concat(),
+        }
+    }
+
+    fn get_hints(&self, challenges: &Challenges<Self::Fq>) -> Hints<Self::Fq> {
+        let ExecutionInfo {
+            source_code,
+            initial_registers,
+            final_registers,
+        } = &self.execution_info;
+        let program = assemble(source_code);
+        let trace_len = self.trace_info().trace_len;
+
+        let mut hints = vec![
+            (
+                PublicInputHint::ProgramLength.index(),
+                Fq3::from(program.len() as u64),
+            ),
+            (
+                PublicInputHint::ProgramEvaluation.index(),
+                compute_program_evaluation_argument(&program, challenges, trace_len),
+            ),
+        ];
+        for (i, (&initial, &last)) in initial_registers.iter().zip(final_registers).enumerate() {
+            hints.push((
+                PublicInputHint::InitialRegister(i).index(),
+                Fq3::from(initial),
+            ));
+            hints.push((PublicInputHint::FinalRegister(i).index(), Fq3::from(last)));
+        }
+        Hints::new(hints)
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.execution_info
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.transition_constraints
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.boundary_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.terminal_constraints
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+}
+
+// Computes the evaluation terminal for the program table
+fn compute_program_evaluation_argument(
+    program: &[Instruction],
+    challenges: &Challenges<Fq3>,
+    trace_len: usize,
+) -> Fq3 {
+    let mut acc = Fq3::zero();
+    for (pc, instruction) in program.iter().enumerate() {
+        let [opcode, dst, src1, src2, imm] = instruction.fields();
+        let fields = [pc as u64, opcode, dst, src1, src2, imm].map(Fp::from);
+        acc = acc * challenges[Challenge::Eta] + compress_instruction(challenges, fields);
+    }
+    // padding rows are zero so each one only multiplies the running evaluation by
+    // `eta`. Multiply by `eta^(trace_len - program_len)` to get the terminal.
+    acc * challenges[Challenge::Eta].pow([(trace_len - program.len()) as u64])
+}
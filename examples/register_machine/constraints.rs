@@ -0,0 +1,349 @@
+use crate::tables::Challenge;
+use crate::tables::MemoryBaseColumn;
+use crate::tables::MemoryExtensionColumn;
+use crate::tables::ProcessorBaseColumn;
+use crate::tables::ProcessorExtensionColumn;
+use crate::tables::ProgramBaseColumn;
+use crate::tables::ProgramExtensionColumn;
+use crate::tables::PublicInputHint;
+use crate::vm::OpCode;
+use crate::vm::NUM_REGISTERS;
+use ark_ff::Field;
+use ark_ff::Zero;
+use gpu_poly::GpuField;
+use ministark::constraint::is_binary;
+use ministark::constraint::Challenge as _;
+use ministark::constraint::Hint;
+use ministark::Column;
+use ministark::Constraint;
+
+impl ProcessorBaseColumn {
+    pub fn boundary_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProcessorBaseColumn::*;
+        let mut constraints = vec![Cycle.curr(), Pc.curr()];
+        for (i, register) in Self::REGISTERS.into_iter().enumerate() {
+            constraints.push(register.curr() - PublicInputHint::InitialRegister(i).get_hint());
+        }
+        // transition constraints check row-local constraints on all rows but the first
+        constraints.extend(processor_row_constraints(|column| column.curr()));
+        constraints
+    }
+
+    pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProcessorBaseColumn::*;
+        let one = F::one();
+        let active = is_active(|column| column.curr());
+        let lhs_is_non_zero = Lhs.curr() * LhsInv.curr();
+
+        // value written to register `Dst` by ADD, SUB and LOAD
+        let writes_dst = IsAdd.curr() + IsSub.curr() + IsLoad.curr();
+        let result = IsAdd.curr() * (Lhs.curr() + Rhs.curr())
+            + IsSub.curr() * (Lhs.curr() - Rhs.curr())
+            + IsLoad.curr() * MemVal.curr();
+
+        let mut constraints = vec![
+            Cycle.next() - Cycle.curr() - one,
+            // a halted machine stays halted
+            (&active - one) * is_active(|column| column.next()),
+            // the program counter increments unless a jump is taken or the machine has halted
+            Pc.next()
+                - Pc.curr()
+                - &active
+                - IsJmp.curr() * lhs_is_non_zero * (Imm.curr() - Pc.curr() - one),
+        ];
+        constraints.extend(processor_row_constraints(|column| column.next()));
+
+        // max degree: 5
+        for (i, register) in Self::REGISTERS.into_iter().enumerate() {
+            let is_dst = register_selector(i, &Dst.curr());
+            constraints.push(
+                register.next()
+                    - register.curr()
+                    - is_dst * (&result - &writes_dst * register.curr()),
+            );
+        }
+
+        constraints
+    }
+
+    pub fn terminal_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProcessorBaseColumn::*;
+        let mut constraints = vec![
+            // execution must finish by moving past the last instruction
+            is_active(|column| column.curr()),
+            Pc.curr() - PublicInputHint::ProgramLength.get_hint(),
+        ];
+        for (i, register) in Self::REGISTERS.into_iter().enumerate() {
+            constraints.push(register.curr() - PublicInputHint::FinalRegister(i).get_hint());
+        }
+        constraints
+    }
+}
+
+impl ProcessorExtensionColumn {
+    pub fn boundary_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProcessorBaseColumn::*;
+        use ProcessorExtensionColumn::*;
+        vec![
+            InstructionLookup.curr()
+                * (Challenge::Alpha.get_challenge()
+                    - processor_instruction(|column| column.curr()))
+                - is_active(|column| column.curr()),
+            MemoryPermutation.curr() - processor_memory_factor(|column| column.curr()),
+            CycleLookup.curr() * (Challenge::Gamma.get_challenge() - Cycle.curr())
+                - ClkMultiplicity.curr(),
+        ]
+    }
+
+    pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProcessorBaseColumn::*;
+        use ProcessorExtensionColumn::*;
+        vec![
+            // lookup of executed instructions into the program table
+            (InstructionLookup.next() - InstructionLookup.curr())
+                * (Challenge::Alpha.get_challenge()
+                    - processor_instruction(|column| column.next()))
+                - is_active(|column| column.next()),
+            // running product for memory table permutation
+            MemoryPermutation.next()
+                - MemoryPermutation.curr() * processor_memory_factor(|column| column.next()),
+            // range check table for the memory clock gaps
+            (CycleLookup.next() - CycleLookup.curr())
+                * (Challenge::Gamma.get_challenge() - Cycle.next())
+                - ClkMultiplicity.next(),
+        ]
+    }
+
+    pub fn terminal_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProcessorExtensionColumn::*;
+        vec![
+            InstructionLookup.curr() - ProgramExtensionColumn::Lookup.curr(),
+            MemoryPermutation.curr() - MemoryExtensionColumn::Permutation.curr(),
+            CycleLookup.curr() - MemoryExtensionColumn::ClkLookup.curr(),
+        ]
+    }
+}
+
+impl ProgramExtensionColumn {
+    pub fn boundary_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProgramBaseColumn::*;
+        use ProgramExtensionColumn::*;
+        vec![
+            Evaluation.curr() - program_instruction(|column| column.curr()),
+            Lookup.curr()
+                * (Challenge::Alpha.get_challenge() - program_instruction(|column| column.curr()))
+                - Multiplicity.curr(),
+        ]
+    }
+
+    pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProgramBaseColumn::*;
+        use ProgramExtensionColumn::*;
+        vec![
+            // the evaluation argument binds every row of the table to the public program
+            Evaluation.next()
+                - Evaluation.curr() * Challenge::Eta.get_challenge()
+                - program_instruction(|column| column.next()),
+            (Lookup.next() - Lookup.curr())
+                * (Challenge::Alpha.get_challenge() - program_instruction(|column| column.next()))
+                - Multiplicity.next(),
+        ]
+    }
+
+    pub fn terminal_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use ProgramExtensionColumn::*;
+        vec![Evaluation.curr() - PublicInputHint::ProgramEvaluation.get_hint()]
+    }
+}
+
+impl MemoryBaseColumn {
+    pub fn boundary_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use MemoryBaseColumn::*;
+        let mut constraints = vec![
+            Addr.curr(),
+            // memory is zero initialized
+            (IsWrite.curr() - F::one()) * Value.curr(),
+        ];
+        constraints.extend(memory_row_constraints(|column| column.curr()));
+        constraints
+    }
+
+    pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use MemoryBaseColumn::*;
+        let one = F::one();
+        let addr_diff = Addr.next() - Addr.curr();
+        let mut constraints = vec![
+            // 1. memory address increases by one or zero
+            // note: remember table is sorted by address then cycle
+            &addr_diff * (&addr_diff - one),
+            // 2. the first access to an address reads zero
+            &addr_diff * (IsWrite.next() - one) * Value.next(),
+            // 3. reading an address returns the last value written
+            (&addr_diff - one) * (IsWrite.next() - one) * (Value.next() - Value.curr()),
+            // 4. accesses to the same address are ordered by cycle. `ClkDiff` is range
+            // checked against the processor's cycle column
+            (&addr_diff - one) * (ClkDiff.curr() - Cycle.next() + Cycle.curr() + one),
+        ];
+        constraints.extend(memory_row_constraints(|column| column.next()));
+        constraints
+    }
+}
+
+impl MemoryExtensionColumn {
+    pub fn boundary_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use MemoryBaseColumn::*;
+        use MemoryExtensionColumn::*;
+        vec![
+            Permutation.curr() - memory_factor(|column| column.curr()),
+            ClkLookup.curr() * (Challenge::Gamma.get_challenge() - ClkDiff.curr()) - F::one(),
+        ]
+    }
+
+    pub fn transition_constraints<F: GpuField>() -> Vec<Constraint<F>> {
+        use MemoryBaseColumn::*;
+        use MemoryExtensionColumn::*;
+        vec![
+            // only progress permutation if dummy != 1
+            Permutation.next() - Permutation.curr() * memory_factor(|column| column.next()),
+            (ClkLookup.next() - ClkLookup.curr())
+                * (Challenge::Gamma.get_challenge() - ClkDiff.next())
+                - F::one(),
+        ]
+    }
+}
+
+// Constraints that only involve a single row
+fn processor_row_constraints<F: GpuField>(
+    row: impl Fn(ProcessorBaseColumn) -> Constraint<F>,
+) -> Vec<Constraint<F>> {
+    use ProcessorBaseColumn::*;
+    let mut opcode = Constraint::zero();
+    let mut constraints = Vec::new();
+    for instr in OpCode::VALUES {
+        let flag = row(ProcessorBaseColumn::flag(instr));
+        opcode += &flag * F::from(instr as u64);
+        constraints.push(is_binary(flag));
+    }
+
+    let lhs_is_zero = row(Lhs) * row(LhsInv) - F::one();
+    let mut lhs = row(Lhs);
+    let mut rhs = row(Rhs);
+    for (i, register) in ProcessorBaseColumn::REGISTERS.into_iter().enumerate() {
+        lhs -= register_selector(i, &row(Src1)) * row(register);
+        rhs -= register_selector(i, &row(Src2)) * row(register);
+    }
+
+    constraints.extend([
+        // at most one instruction flag is set
+        is_binary(is_active(&row)),
+        row(Opcode) - opcode,
+        // operands are read from registers `Src1` and `Src2`
+        lhs,
+        rhs,
+        row(Lhs) * &lhs_is_zero,
+        row(LhsInv) * &lhs_is_zero,
+        // stores write the value of register `Src2`
+        row(IsStore) * (row(MemVal) - row(Rhs)),
+    ]);
+    constraints
+}
+
+fn memory_row_constraints<F: GpuField>(
+    row: impl Fn(MemoryBaseColumn) -> Constraint<F>,
+) -> Vec<Constraint<F>> {
+    use MemoryBaseColumn::*;
+    vec![
+        is_binary(row(IsWrite)),
+        is_binary(row(Dummy)),
+        // dummy rows are reads so they can't change memory
+        row(Dummy) * row(IsWrite),
+    ]
+}
+
+/// Returns one if the processor executes an instruction and zero if halted
+fn is_active<F: GpuField>(row: impl Fn(ProcessorBaseColumn) -> Constraint<F>) -> Constraint<F> {
+    let mut accumulator = Constraint::zero();
+    for instr in OpCode::VALUES {
+        accumulator += row(ProcessorBaseColumn::flag(instr));
+    }
+    accumulator
+}
+
+/// Lagrange basis polynomial that is one if `index` selects register `i` and
+/// zero for all other registers
+fn register_selector<F: GpuField>(i: usize, index: &Constraint<F>) -> Constraint<F> {
+    let mut numerator = Constraint::from(F::one());
+    let mut denominator = F::one();
+    for j in (0..NUM_REGISTERS).filter(|&j| j != i) {
+        numerator *= index - F::from(j as u64);
+        denominator *= F::from(i as u64) - F::from(j as u64);
+    }
+    numerator * denominator.inverse().unwrap()
+}
+
+fn compress_instruction<F: GpuField>(
+    [pc, opcode, dst, src1, src2, imm]: [Constraint<F>; 6],
+) -> Constraint<F> {
+    Challenge::A.get_challenge() * pc
+        + Challenge::B.get_challenge() * opcode
+        + Challenge::C.get_challenge() * dst
+        + Challenge::D.get_challenge() * src1
+        + Challenge::E.get_challenge() * src2
+        + Challenge::F.get_challenge() * imm
+}
+
+fn processor_instruction<F: GpuField>(
+    row: impl Fn(ProcessorBaseColumn) -> Constraint<F>,
+) -> Constraint<F> {
+    use ProcessorBaseColumn::*;
+    compress_instruction([
+        row(Pc),
+        row(Opcode),
+        row(Dst),
+        row(Src1),
+        row(Src2),
+        row(Imm),
+    ])
+}
+
+fn program_instruction<F: GpuField>(
+    row: impl Fn(ProgramBaseColumn) -> Constraint<F>,
+) -> Constraint<F> {
+    use ProgramBaseColumn::*;
+    compress_instruction([
+        row(Pc),
+        row(Opcode),
+        row(Dst),
+        row(Src1),
+        row(Src2),
+        row(Imm),
+    ])
+}
+
+fn compress_memory_access<F: GpuField>(
+    [cycle, addr, value, is_write]: [Constraint<F>; 4],
+) -> Constraint<F> {
+    Challenge::G.get_challenge() * cycle
+        + Challenge::H.get_challenge() * addr
+        + Challenge::I.get_challenge() * value
+        + Challenge::J.get_challenge() * is_write
+}
+
+// Factor of the memory permutation for a processor row. Rows that don't
+// access memory have a factor of one.
+fn processor_memory_factor<F: GpuField>(
+    row: impl Fn(ProcessorBaseColumn) -> Constraint<F>,
+) -> Constraint<F> {
+    use ProcessorBaseColumn::*;
+    let is_memory_access = row(IsLoad) + row(IsStore);
+    let access = compress_memory_access([row(Cycle), row(Lhs), row(MemVal), row(IsStore)]);
+    &is_memory_access * (Challenge::Beta.get_challenge() - access) - is_memory_access + F::one()
+}
+
+// Factor of the memory permutation for a memory row. Dummy rows have a factor
+// of one.
+fn memory_factor<F: GpuField>(row: impl Fn(MemoryBaseColumn) -> Constraint<F>) -> Constraint<F> {
+    use MemoryBaseColumn::*;
+    let access = compress_memory_access([row(Cycle), row(Addr), row(Value), row(IsWrite)]);
+    row(Dummy) - (row(Dummy) - F::one()) * (Challenge::Beta.get_challenge() - access)
+}
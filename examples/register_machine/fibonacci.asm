@@ -0,0 +1,8 @@
+; Computes the fibonacci numbers up to F(n+1) and stores each one in memory
+; run with registers: r0 = n, r1 = 1, r2 = 0, r3 = 1
+add r1 r1 r2   ; r1 = F(k+2)
+sub r2 r1 r2   ; r2 = F(k+1)
+sub r0 r0 r3   ; decrement counter
+store r1 r0    ; mem[counter] = F(k+2)
+jmp r0 0       ; loop while the counter is non-zero
+load r2 r0     ; r2 = mem[0]
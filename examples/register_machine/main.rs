@@ -0,0 +1,152 @@
+#![feature(allocator_api)]
+
+use air::RegisterMachineAir;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Instant;
+use structopt::StructOpt;
+use trace::RegisterMachineTrace;
+use vm::simulate;
+use vm::NUM_REGISTERS;
+
+mod air;
+mod constraints;
+mod prover;
+mod tables;
+mod trace;
+mod vm;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "rm", about = "miniSTARK register machine prover and verifier")]
+enum RegisterMachineOptions {
+    /// Executes a program and generates a proof of its execution
+    Prove {
+        #[structopt(parse(from_os_str))]
+        src: PathBuf,
+        /// initial values of registers r0 to r3
+        #[structopt(long, use_delimiter = true, default_value = "0,0,0,0")]
+        registers: Vec<u64>,
+        /// number of memory words available to the program
+        #[structopt(long, default_value = "1024")]
+        memory_size: usize,
+        #[structopt(long, parse(from_os_str))]
+        out: PathBuf,
+    },
+    /// Verifies a proof. The program and registers are read from the proof
+    Verify {
+        #[structopt(parse(from_os_str))]
+        proof: PathBuf,
+    },
+    /// Executes a program without generating a proof
+    Run {
+        #[structopt(parse(from_os_str))]
+        src: PathBuf,
+        /// initial values of registers r0 to r3
+        #[structopt(long, use_delimiter = true, default_value = "0,0,0,0")]
+        registers: Vec<u64>,
+        /// number of memory words available to the program
+        #[structopt(long, default_value = "1024")]
+        memory_size: usize,
+    },
+}
+
+fn main() {
+    // proof options for 128 bit security level
+    let options = ProofOptions::secure_128::<RegisterMachineAir>().unwrap();
+
+    // read command-line args
+    match RegisterMachineOptions::from_args() {
+        RegisterMachineOptions::Prove {
+            src,
+            registers,
+            memory_size,
+            out,
+        } => prove(options, src, parse_registers(registers), memory_size, out),
+        RegisterMachineOptions::Verify { proof } => verify(options, proof),
+        RegisterMachineOptions::Run {
+            src,
+            registers,
+            memory_size,
+        } => run(src, parse_registers(registers), memory_size),
+    }
+}
+
+fn parse_registers(registers: Vec<u64>) -> [Fp; NUM_REGISTERS] {
+    let registers: [u64; NUM_REGISTERS] = registers
+        .try_into()
+        .unwrap_or_else(|_| panic!("expected {NUM_REGISTERS} register values"));
+    registers.map(Fp::from)
+}
+
+fn prove(
+    options: ProofOptions,
+    source_code_path: PathBuf,
+    registers: [Fp; NUM_REGISTERS],
+    memory_size: usize,
+    output_path: PathBuf,
+) {
+    let source_code = fs::read_to_string(source_code_path).unwrap();
+
+    let now = Instant::now();
+    let trace = simulate(source_code, registers, memory_size);
+    println!(
+        "Generated execution trace (cols={}, rows={}) in {:.0?}",
+        trace.base_columns().num_cols(),
+        trace.base_columns().num_rows(),
+        now.elapsed(),
+    );
+    println!("Final registers: {:?}", trace.meta().final_registers);
+
+    let prover = prover::RegisterMachineProver::new(options);
+    let proof = prover.generate_proof(trace).unwrap();
+    println!("Proof generated in: {:.0?}", now.elapsed());
+    println!(
+        "Proof security (conjectured): {}bit",
+        proof.conjectured_security_level()
+    );
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    println!("Proof size: {:?}KB", proof_bytes.len() / 1024);
+    let mut f = File::create(&output_path).unwrap();
+    f.write_all(proof_bytes.as_slice()).unwrap();
+    f.flush().unwrap();
+    println!("Proof written to {}", output_path.as_path().display());
+}
+
+fn verify(options: ProofOptions, proof_path: PathBuf) {
+    let proof_bytes = fs::read(proof_path).unwrap();
+    let proof: Proof<RegisterMachineAir> =
+        Proof::deserialize_compressed(proof_bytes.as_slice()).unwrap();
+    assert_eq!(options, proof.options);
+    let execution_info = proof.public_inputs.clone().unwrap();
+
+    let now = Instant::now();
+    proof.verify().unwrap();
+    println!("Proof verified in: {:?}", now.elapsed());
+    println!("Program source:\n{}", execution_info.source_code);
+    println!("Initial registers: {:?}", execution_info.initial_registers);
+    println!("Final registers: {:?}", execution_info.final_registers);
+}
+
+fn run(source_code_path: PathBuf, registers: [Fp; NUM_REGISTERS], memory_size: usize) {
+    let source_code = fs::read_to_string(source_code_path).unwrap();
+
+    let now = Instant::now();
+    let trace = simulate(source_code, registers, memory_size);
+    println!(
+        "Executed program (trace rows={}) in {:.0?}",
+        trace.base_columns().num_rows(),
+        now.elapsed(),
+    );
+    println!("Final registers: {:?}", trace.meta().final_registers);
+}
@@ -0,0 +1,33 @@
+use crate::air::ExecutionInfo;
+use crate::air::RegisterMachineAir;
+use crate::trace::RegisterMachineTrace;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::ProofOptions;
+use ministark::Prover;
+
+pub struct RegisterMachineProver(ProofOptions);
+
+impl Prover for RegisterMachineProver {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type Air = RegisterMachineAir;
+    type Trace = RegisterMachineTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        RegisterMachineProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &RegisterMachineTrace) -> ExecutionInfo {
+        let meta = trace.meta();
+        ExecutionInfo {
+            source_code: meta.source_code.to_string(),
+            initial_registers: meta.initial_registers.to_vec(),
+            final_registers: meta.final_registers.to_vec(),
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use crate::vm::OpCode;
+use crate::vm::NUM_REGISTERS;
+
+pub trait RegisterMachineColumn {
+    const FIRST_TRACE_COL_INDEX: usize;
+    const LAST_TRACE_COL_INDEX: usize;
+    const NUM_TRACE_COLUMNS: usize = Self::LAST_TRACE_COL_INDEX - Self::FIRST_TRACE_COL_INDEX + 1;
+}
+
+#[derive(Clone, Copy)]
+pub enum Challenge {
+    // instruction compression
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    // memory access compression
+    G,
+    H,
+    I,
+    J,
+    Alpha, // instruction lookup
+    Beta,  // memory permutation
+    Gamma, // clock range check
+    Eta,   // program evaluation
+}
+
+impl ministark::constraint::Challenge for Challenge {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum PublicInputHint {
+    ProgramLength,
+    ProgramEvaluation,
+    InitialRegister(usize),
+    FinalRegister(usize),
+}
+
+impl ministark::constraint::Hint for PublicInputHint {
+    fn index(&self) -> usize {
+        match self {
+            PublicInputHint::ProgramLength => 0,
+            PublicInputHint::ProgramEvaluation => 1,
+            PublicInputHint::InitialRegister(i) => 2 + i,
+            PublicInputHint::FinalRegister(i) => 2 + NUM_REGISTERS + i,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum ProcessorBaseColumn {
+    Cycle,
+    Pc, // program counter
+    Opcode,
+    Dst,
+    Src1,
+    Src2,
+    Imm,
+    IsAdd,
+    IsSub,
+    IsJmp,
+    IsLoad,
+    IsStore,
+    R0,
+    R1,
+    R2,
+    R3,
+    Lhs,             // value of register `Src1`
+    Rhs,             // value of register `Src2`
+    LhsInv,          // inverse of `Lhs` or zero
+    MemVal,          // value loaded or stored
+    ClkMultiplicity, // number of memory clock gaps equal to `Cycle`
+}
+
+impl ProcessorBaseColumn {
+    pub const REGISTERS: [Self; NUM_REGISTERS] = [Self::R0, Self::R1, Self::R2, Self::R3];
+
+    /// Returns the column flagging rows that execute `opcode`
+    pub fn flag(opcode: OpCode) -> Self {
+        match opcode {
+            OpCode::Add => Self::IsAdd,
+            OpCode::Sub => Self::IsSub,
+            OpCode::Jmp => Self::IsJmp,
+            OpCode::Load => Self::IsLoad,
+            OpCode::Store => Self::IsStore,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum ProcessorExtensionColumn {
+    InstructionLookup,
+    MemoryPermutation,
+    CycleLookup,
+}
+
+#[derive(Clone, Copy)]
+pub enum ProgramBaseColumn {
+    Pc,
+    Opcode,
+    Dst,
+    Src1,
+    Src2,
+    Imm,
+    Multiplicity, // number of times the instruction is executed
+}
+
+#[derive(Clone, Copy)]
+pub enum ProgramExtensionColumn {
+    Evaluation,
+    Lookup,
+}
+
+#[derive(Clone, Copy)]
+pub enum MemoryBaseColumn {
+    Cycle,
+    Addr,
+    Value,
+    IsWrite,
+    Dummy,   // indicate if a row is padding
+    ClkDiff, // cycles between consecutive accesses to the same address minus one
+}
+
+#[derive(Clone, Copy)]
+pub enum MemoryExtensionColumn {
+    Permutation,
+    ClkLookup,
+}
+
+impl RegisterMachineColumn for ProcessorBaseColumn {
+    const FIRST_TRACE_COL_INDEX: usize = 0;
+    const LAST_TRACE_COL_INDEX: usize = Self::ClkMultiplicity as usize;
+}
+
+impl RegisterMachineColumn for ProgramBaseColumn {
+    const FIRST_TRACE_COL_INDEX: usize = ProcessorBaseColumn::LAST_TRACE_COL_INDEX + 1;
+    const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::Multiplicity as usize;
+}
+
+impl RegisterMachineColumn for MemoryBaseColumn {
+    const FIRST_TRACE_COL_INDEX: usize = ProgramBaseColumn::LAST_TRACE_COL_INDEX + 1;
+    const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::ClkDiff as usize;
+}
+
+impl RegisterMachineColumn for ProcessorExtensionColumn {
+    const FIRST_TRACE_COL_INDEX: usize = MemoryBaseColumn::LAST_TRACE_COL_INDEX + 1;
+    const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::CycleLookup as usize;
+}
+
+impl RegisterMachineColumn for ProgramExtensionColumn {
+    const FIRST_TRACE_COL_INDEX: usize = ProcessorExtensionColumn::LAST_TRACE_COL_INDEX + 1;
+    const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::Lookup as usize;
+}
+
+impl RegisterMachineColumn for MemoryExtensionColumn {
+    const FIRST_TRACE_COL_INDEX: usize = ProgramExtensionColumn::LAST_TRACE_COL_INDEX + 1;
+    const LAST_TRACE_COL_INDEX: usize = Self::FIRST_TRACE_COL_INDEX + Self::ClkLookup as usize;
+}
+
+macro_rules! impl_column {
+    ($t:ty) => {
+        impl ministark::constraint::Column for $t {
+            fn index(&self) -> usize {
+                Self::FIRST_TRACE_COL_INDEX + *self as usize
+            }
+        }
+    };
+}
+
+impl_column!(ProcessorBaseColumn);
+impl_column!(ProcessorExtensionColumn);
+
+impl_column!(ProgramBaseColumn);
+impl_column!(ProgramExtensionColumn);
+
+impl_column!(MemoryBaseColumn);
+impl_column!(MemoryExtensionColumn);
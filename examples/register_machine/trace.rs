@@ -0,0 +1,253 @@
+use crate::tables::Challenge;
+use crate::tables::MemoryBaseColumn;
+use crate::tables::MemoryExtensionColumn;
+use crate::tables::ProcessorBaseColumn;
+use crate::tables::ProcessorExtensionColumn;
+use crate::tables::ProgramBaseColumn;
+use crate::tables::ProgramExtensionColumn;
+use crate::tables::RegisterMachineColumn;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::Matrix;
+use ministark::Trace;
+
+pub struct TraceMeta {
+    pub source_code: String,
+    pub initial_registers: Vec<Fp>,
+    pub final_registers: Vec<Fp>,
+}
+
+pub struct RegisterMachineTrace {
+    meta: TraceMeta,
+    processor_base_trace: Matrix<Fp>,
+    program_base_trace: Matrix<Fp>,
+    memory_base_trace: Matrix<Fp>,
+    base_trace: Matrix<Fp>,
+}
+
+impl RegisterMachineTrace {
+    pub fn new(
+        meta: TraceMeta,
+        processor_base_trace: Matrix<Fp>,
+        program_base_trace: Matrix<Fp>,
+        memory_base_trace: Matrix<Fp>,
+    ) -> Self {
+        let base_trace = Matrix::join(vec![
+            processor_base_trace.clone(),
+            program_base_trace.clone(),
+            memory_base_trace.clone(),
+        ]);
+        RegisterMachineTrace {
+            meta,
+            processor_base_trace,
+            program_base_trace,
+            memory_base_trace,
+            base_trace,
+        }
+    }
+
+    pub fn meta(&self) -> &TraceMeta {
+        &self.meta
+    }
+}
+
+impl Trace for RegisterMachineTrace {
+    type Fp = Fp;
+    type Fq = Fq3;
+
+    const NUM_BASE_COLUMNS: usize = 34;
+    const NUM_EXTENSION_COLUMNS: usize = 7;
+
+    fn len(&self) -> usize {
+        self.base_trace.num_rows()
+    }
+
+    fn build_extension_columns(
+        &self,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        let processor_matrix = gen_processor_ext_matrix(challenges, &self.processor_base_trace);
+        let program_matrix = gen_program_ext_matrix(challenges, &self.program_base_trace);
+        let memory_matrix = gen_memory_ext_matrix(challenges, &self.memory_base_trace);
+        Some(Matrix::join(vec![
+            processor_matrix,
+            program_matrix,
+            memory_matrix,
+        ]))
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_trace
+    }
+}
+
+/// Compresses `[pc, opcode, dst, src1, src2, imm]` into a single value
+pub fn compress_instruction(challenges: &Challenges<Fq3>, instruction: [Fp; 6]) -> Fq3 {
+    use Challenge::*;
+    let [pc, opcode, dst, src1, src2, imm] = instruction;
+    challenges[A] * pc
+        + challenges[B] * opcode
+        + challenges[C] * dst
+        + challenges[D] * src1
+        + challenges[E] * src2
+        + challenges[F] * imm
+}
+
+/// Compresses `[cycle, addr, value, is_write]` into a single value
+fn compress_memory_access(challenges: &Challenges<Fq3>, access: [Fp; 4]) -> Fq3 {
+    use Challenge::*;
+    let [cycle, addr, value, is_write] = access;
+    challenges[G] * cycle + challenges[H] * addr + challenges[I] * value + challenges[J] * is_write
+}
+
+// Running values include the current row
+fn gen_processor_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>) -> Matrix<Fq3> {
+    use Challenge::*;
+    use ProcessorBaseColumn::*;
+    use ProcessorExtensionColumn::*;
+
+    // prepare
+    let mut instruction_lookup = Fq3::zero();
+    let mut memory_permutation = Fq3::one();
+    let mut cycle_lookup = Fq3::zero();
+
+    // loop over all rows
+    let mut extension_rows = Vec::new();
+    for row in 0..base_matrix.num_rows() {
+        let base_row = base_matrix.get_row(row).unwrap();
+        let mut extension_row = [Fq3::zero(); ProcessorExtensionColumn::NUM_TRACE_COLUMNS];
+
+        // halted rows don't execute an instruction
+        let is_active = base_row[IsAdd as usize]
+            + base_row[IsSub as usize]
+            + base_row[IsJmp as usize]
+            + base_row[IsLoad as usize]
+            + base_row[IsStore as usize];
+        if !is_active.is_zero() {
+            let instruction = compress_instruction(
+                challenges,
+                [
+                    base_row[Pc as usize],
+                    base_row[Opcode as usize],
+                    base_row[Dst as usize],
+                    base_row[Src1 as usize],
+                    base_row[Src2 as usize],
+                    base_row[Imm as usize],
+                ],
+            );
+            instruction_lookup += (challenges[Alpha] - instruction).inverse().unwrap();
+        }
+        extension_row[InstructionLookup as usize] = instruction_lookup;
+
+        if !(base_row[IsLoad as usize] + base_row[IsStore as usize]).is_zero() {
+            let access = compress_memory_access(
+                challenges,
+                [
+                    base_row[Cycle as usize],
+                    base_row[Lhs as usize],
+                    base_row[MemVal as usize],
+                    base_row[IsStore as usize],
+                ],
+            );
+            memory_permutation *= challenges[Beta] - access;
+        }
+        extension_row[MemoryPermutation as usize] = memory_permutation;
+
+        let clk_multiplicity = base_row[ClkMultiplicity as usize];
+        if !clk_multiplicity.is_zero() {
+            let cycle = base_row[Cycle as usize];
+            cycle_lookup +=
+                (challenges[Gamma] - Fq3::from(cycle)).inverse().unwrap() * clk_multiplicity;
+        }
+        extension_row[CycleLookup as usize] = cycle_lookup;
+
+        extension_rows.push(extension_row);
+    }
+
+    Matrix::from_row_arrays(extension_rows)
+}
+
+fn gen_program_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>) -> Matrix<Fq3> {
+    use Challenge::*;
+    use ProgramBaseColumn::*;
+    use ProgramExtensionColumn::*;
+
+    // prepare
+    let mut evaluation = Fq3::zero();
+    let mut lookup = Fq3::zero();
+
+    // loop over all rows
+    let mut extension_rows = Vec::new();
+    for row in 0..base_matrix.num_rows() {
+        let base_row = base_matrix.get_row(row).unwrap();
+        let mut extension_row = [Fq3::zero(); ProgramExtensionColumn::NUM_TRACE_COLUMNS];
+        let instruction = compress_instruction(
+            challenges,
+            [
+                base_row[Pc as usize],
+                base_row[Opcode as usize],
+                base_row[Dst as usize],
+                base_row[Src1 as usize],
+                base_row[Src2 as usize],
+                base_row[Imm as usize],
+            ],
+        );
+
+        evaluation = evaluation * challenges[Eta] + instruction;
+        extension_row[Evaluation as usize] = evaluation;
+
+        let multiplicity = base_row[Multiplicity as usize];
+        if !multiplicity.is_zero() {
+            lookup += (challenges[Alpha] - instruction).inverse().unwrap() * multiplicity;
+        }
+        extension_row[Lookup as usize] = lookup;
+
+        extension_rows.push(extension_row);
+    }
+
+    Matrix::from_row_arrays(extension_rows)
+}
+
+fn gen_memory_ext_matrix(challenges: &Challenges<Fq3>, base_matrix: &Matrix<Fp>) -> Matrix<Fq3> {
+    use Challenge::*;
+    use MemoryBaseColumn::*;
+    use MemoryExtensionColumn::*;
+
+    // prepare
+    let mut permutation = Fq3::one();
+    let mut clk_lookup = Fq3::zero();
+
+    // loop over all rows
+    let mut extension_rows = Vec::new();
+    for row in 0..base_matrix.num_rows() {
+        let base_row = base_matrix.get_row(row).unwrap();
+        let mut extension_row = [Fq3::zero(); MemoryExtensionColumn::NUM_TRACE_COLUMNS];
+
+        if base_row[Dummy as usize].is_zero() {
+            let access = compress_memory_access(
+                challenges,
+                [
+                    base_row[Cycle as usize],
+                    base_row[Addr as usize],
+                    base_row[Value as usize],
+                    base_row[IsWrite as usize],
+                ],
+            );
+            permutation *= challenges[Beta] - access;
+        }
+        extension_row[Permutation as usize] = permutation;
+
+        clk_lookup += (challenges[Gamma] - Fq3::from(base_row[ClkDiff as usize]))
+            .inverse()
+            .unwrap();
+        extension_row[ClkLookup as usize] = clk_lookup;
+
+        extension_rows.push(extension_row);
+    }
+
+    Matrix::from_row_arrays(extension_rows)
+}
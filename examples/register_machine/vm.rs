@@ -0,0 +1,356 @@
+use crate::tables::MemoryBaseColumn;
+use crate::tables::ProcessorBaseColumn;
+use crate::tables::ProgramBaseColumn;
+use crate::tables::RegisterMachineColumn;
+use crate::trace::TraceMeta;
+use crate::RegisterMachineTrace;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::PrimeField;
+use ark_ff::Zero;
+use ministark::padding::padded_len;
+use ministark::padding::Padding;
+use ministark::padding::PaddingRules;
+use ministark::Matrix;
+
+type Fp = <RegisterMachineTrace as ministark::Trace>::Fp;
+
+pub const NUM_REGISTERS: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpCode {
+    Add = 1,
+    Sub = 2,
+    Jmp = 3,
+    Load = 4,
+    Store = 5,
+}
+
+impl OpCode {
+    pub const VALUES: [OpCode; 5] = [
+        OpCode::Add,
+        OpCode::Sub,
+        OpCode::Jmp,
+        OpCode::Load,
+        OpCode::Store,
+    ];
+}
+
+/// Instructions operate on registers `r0` to `r3` and word addressed memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// `add dst lhs rhs` sets `dst = lhs + rhs`
+    Add { dst: usize, lhs: usize, rhs: usize },
+    /// `sub dst lhs rhs` sets `dst = lhs - rhs`
+    Sub { dst: usize, lhs: usize, rhs: usize },
+    /// `jmp cond target` jumps to `target` if `cond` is non-zero
+    Jmp { cond: usize, target: usize },
+    /// `load dst addr` sets `dst = mem[addr]`
+    Load { dst: usize, addr: usize },
+    /// `store src addr` sets `mem[addr] = src`
+    Store { src: usize, addr: usize },
+}
+
+impl Instruction {
+    pub fn opcode(&self) -> OpCode {
+        match self {
+            Instruction::Add { .. } => OpCode::Add,
+            Instruction::Sub { .. } => OpCode::Sub,
+            Instruction::Jmp { .. } => OpCode::Jmp,
+            Instruction::Load { .. } => OpCode::Load,
+            Instruction::Store { .. } => OpCode::Store,
+        }
+    }
+
+    /// Returns the encoded instruction `[opcode, dst, src1, src2, imm]`
+    pub fn fields(&self) -> [u64; 5] {
+        let opcode = self.opcode() as u64;
+        match *self {
+            Instruction::Add { dst, lhs, rhs } | Instruction::Sub { dst, lhs, rhs } => {
+                [opcode, dst as u64, lhs as u64, rhs as u64, 0]
+            }
+            Instruction::Jmp { cond, target } => [opcode, 0, cond as u64, 0, target as u64],
+            Instruction::Load { dst, addr } => [opcode, dst as u64, addr as u64, 0, 0],
+            Instruction::Store { src, addr } => [opcode, 0, addr as u64, src as u64, 0],
+        }
+    }
+}
+
+fn parse_register(token: &str) -> usize {
+    let register = token
+        .strip_prefix('r')
+        .and_then(|index| index.parse::<usize>().ok())
+        .unwrap_or_else(|| panic!("invalid register \"{token}\""));
+    assert!(register < NUM_REGISTERS, "invalid register \"{token}\"");
+    register
+}
+
+/// Assembles source code into a program. Each line holds one instruction and
+/// anything following a `;` is a comment.
+pub fn assemble(source: &str) -> Vec<Instruction> {
+    let mut program = Vec::new();
+    for line in source.lines() {
+        let line = line.split(';').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let tokens = line.split_whitespace().collect::<Vec<&str>>();
+        let instruction = match tokens.as_slice() {
+            ["add", dst, lhs, rhs] => Instruction::Add {
+                dst: parse_register(dst),
+                lhs: parse_register(lhs),
+                rhs: parse_register(rhs),
+            },
+            ["sub", dst, lhs, rhs] => Instruction::Sub {
+                dst: parse_register(dst),
+                lhs: parse_register(lhs),
+                rhs: parse_register(rhs),
+            },
+            ["jmp", cond, target] => Instruction::Jmp {
+                cond: parse_register(cond),
+                target: target.parse().expect("invalid jump target"),
+            },
+            ["load", dst, addr] => Instruction::Load {
+                dst: parse_register(dst),
+                addr: parse_register(addr),
+            },
+            ["store", src, addr] => Instruction::Store {
+                src: parse_register(src),
+                addr: parse_register(addr),
+            },
+            _ => panic!("invalid instruction \"{line}\""),
+        };
+        program.push(instruction);
+    }
+
+    // jumping to the end of the program halts the machine
+    for instruction in &program {
+        if let Instruction::Jmp { target, .. } = instruction {
+            assert!(
+                *target <= program.len(),
+                "jump target {target} out of range"
+            );
+        }
+    }
+
+    program
+}
+
+// Outputs base execution trace
+// Memory is zero initialized and has `memory_size` words
+pub fn simulate(
+    source_code: String,
+    initial_registers: [Fp; NUM_REGISTERS],
+    memory_size: usize,
+) -> RegisterMachineTrace {
+    let program = assemble(&source_code);
+    assert!(!program.is_empty(), "program has no instructions");
+
+    let mut registers = initial_registers;
+    let mut memory = vec![Fp::zero(); memory_size];
+    let mut pc = 0;
+    let mut cycle = 0;
+
+    let mut processor_rows = Vec::new();
+    let mut memory_accesses = Vec::new();
+    let mut multiplicities = vec![0u64; program.len()];
+
+    while let Some(&instruction) = program.get(pc) {
+        use ProcessorBaseColumn::*;
+        let mut row = processor_row(cycle, pc, instruction.fields(), &registers);
+        row[ProcessorBaseColumn::flag(instruction.opcode()) as usize] = Fp::one();
+        multiplicities[pc] += 1;
+
+        let lhs = row[Lhs as usize];
+        let rhs = row[Rhs as usize];
+        pc += 1;
+        match instruction {
+            Instruction::Add { dst, .. } => registers[dst] = lhs + rhs,
+            Instruction::Sub { dst, .. } => registers[dst] = lhs - rhs,
+            Instruction::Jmp { target, .. } => {
+                if !lhs.is_zero() {
+                    pc = target;
+                }
+            }
+            Instruction::Load { dst, .. } => {
+                let addr = address(lhs, memory_size);
+                registers[dst] = memory[addr];
+                row[MemVal as usize] = memory[addr];
+                memory_accesses.push((addr, cycle, memory[addr], false));
+            }
+            Instruction::Store { .. } => {
+                let addr = address(lhs, memory_size);
+                memory[addr] = rhs;
+                row[MemVal as usize] = rhs;
+                memory_accesses.push((addr, cycle, rhs, true));
+            }
+        }
+
+        processor_rows.push(row);
+        cycle += 1;
+    }
+
+    // the machine halts once the program counter moves past the last instruction
+    let final_registers = registers;
+    processor_rows.push(processor_row(cycle, pc, [0; 5], &registers));
+
+    let mut program_rows = program
+        .iter()
+        .zip(multiplicities)
+        .enumerate()
+        .map(|(pc, (instruction, multiplicity))| {
+            use ProgramBaseColumn::*;
+            let [opcode, dst, src1, src2, imm] = instruction.fields();
+            let mut row = [Fp::zero(); ProgramBaseColumn::NUM_TRACE_COLUMNS];
+            row[Pc as usize] = Fp::from(pc as u64);
+            row[Opcode as usize] = Fp::from(opcode);
+            row[Dst as usize] = Fp::from(dst);
+            row[Src1 as usize] = Fp::from(src1);
+            row[Src2 as usize] = Fp::from(src2);
+            row[Imm as usize] = Fp::from(imm);
+            row[Multiplicity as usize] = Fp::from(multiplicity);
+            row
+        })
+        .collect::<Vec<_>>();
+
+    let mut memory_rows = derive_memory_rows(memory_accesses);
+
+    let padding_len = padded_len([processor_rows.len(), program_rows.len(), memory_rows.len()]);
+
+    processor_padding().pad_rows(&mut processor_rows, padding_len);
+    PaddingRules::new(ProgramBaseColumn::NUM_TRACE_COLUMNS)
+        .pad_rows(&mut program_rows, padding_len);
+    memory_padding().pad_rows(&mut memory_rows, padding_len);
+    fill_clock_range_check(&mut processor_rows, &mut memory_rows);
+
+    let processor_base_trace = Matrix::from_row_arrays(processor_rows);
+    let program_base_trace = Matrix::from_row_arrays(program_rows);
+    let memory_base_trace = Matrix::from_row_arrays(memory_rows);
+
+    let meta = TraceMeta {
+        source_code,
+        initial_registers: initial_registers.to_vec(),
+        final_registers: final_registers.to_vec(),
+    };
+
+    RegisterMachineTrace::new(
+        meta,
+        processor_base_trace,
+        program_base_trace,
+        memory_base_trace,
+    )
+}
+
+fn processor_row(
+    cycle: usize,
+    pc: usize,
+    [opcode, dst, src1, src2, imm]: [u64; 5],
+    registers: &[Fp; NUM_REGISTERS],
+) -> [Fp; ProcessorBaseColumn::NUM_TRACE_COLUMNS] {
+    use ProcessorBaseColumn::*;
+    let mut row = [Fp::zero(); ProcessorBaseColumn::NUM_TRACE_COLUMNS];
+    row[Cycle as usize] = Fp::from(cycle as u64);
+    row[Pc as usize] = Fp::from(pc as u64);
+    row[Opcode as usize] = Fp::from(opcode);
+    row[Dst as usize] = Fp::from(dst);
+    row[Src1 as usize] = Fp::from(src1);
+    row[Src2 as usize] = Fp::from(src2);
+    row[Imm as usize] = Fp::from(imm);
+    for (column, value) in ProcessorBaseColumn::REGISTERS.into_iter().zip(registers) {
+        row[column as usize] = *value;
+    }
+    let lhs = registers[src1 as usize];
+    row[Lhs as usize] = lhs;
+    row[Rhs as usize] = registers[src2 as usize];
+    row[LhsInv as usize] = lhs.inverse().unwrap_or_default();
+    row
+}
+
+fn address(value: Fp, memory_size: usize) -> usize {
+    let addr = value.into_bigint().0[0] as usize;
+    assert!(addr < memory_size, "memory address {addr} out of bounds");
+    addr
+}
+
+fn memory_row(
+    cycle: usize,
+    addr: usize,
+    value: Fp,
+    is_write: bool,
+    is_dummy: bool,
+) -> [Fp; MemoryBaseColumn::NUM_TRACE_COLUMNS] {
+    use MemoryBaseColumn::*;
+    let mut row = [Fp::zero(); MemoryBaseColumn::NUM_TRACE_COLUMNS];
+    row[Cycle as usize] = Fp::from(cycle as u64);
+    row[Addr as usize] = Fp::from(addr as u64);
+    row[Value as usize] = value;
+    row[IsWrite as usize] = Fp::from(is_write);
+    row[Dummy as usize] = Fp::from(is_dummy);
+    row
+}
+
+// Sorts memory accesses by address then cycle. Addresses must increase by at
+// most one between rows so dummy rows are inserted for untouched addresses.
+fn derive_memory_rows(
+    mut accesses: Vec<(usize, usize, Fp, bool)>,
+) -> Vec<[Fp; MemoryBaseColumn::NUM_TRACE_COLUMNS]> {
+    accesses.sort_by_key(|&(addr, cycle, _, _)| (addr, cycle));
+
+    let mut rows = Vec::new();
+    let mut next_addr = 0;
+    for (addr, cycle, value, is_write) in accesses {
+        while next_addr < addr {
+            rows.push(memory_row(0, next_addr, Fp::zero(), false, true));
+            next_addr += 1;
+        }
+        rows.push(memory_row(cycle, addr, value, is_write, false));
+        next_addr = addr + 1;
+    }
+
+    if rows.is_empty() {
+        rows.push(memory_row(0, 0, Fp::zero(), false, true));
+    }
+
+    rows
+}
+
+/// Padding rows keep the registers and count cycles
+fn processor_padding() -> PaddingRules<Fp> {
+    use ProcessorBaseColumn::*;
+    PaddingRules::repeat_last(ProcessorBaseColumn::NUM_TRACE_COLUMNS)
+        .with(Cycle as usize, Padding::Increment(Fp::one()))
+}
+
+/// Padding rows are dummy accesses to the last address
+fn memory_padding() -> PaddingRules<Fp> {
+    use MemoryBaseColumn::*;
+    PaddingRules::new(MemoryBaseColumn::NUM_TRACE_COLUMNS)
+        .with(Cycle as usize, Padding::Increment(Fp::one()))
+        .with(Addr as usize, Padding::RepeatLast)
+        .with(Value as usize, Padding::RepeatLast)
+        .with(Dummy as usize, Padding::Constant(Fp::one()))
+}
+
+// Accesses to the same address must be ordered by cycle. This is enforced by
+// looking up each clock gap in the processor's cycle column which contains
+// every value in `0..trace_len`.
+fn fill_clock_range_check(
+    processor_rows: &mut [[Fp; ProcessorBaseColumn::NUM_TRACE_COLUMNS]],
+    memory_rows: &mut [[Fp; MemoryBaseColumn::NUM_TRACE_COLUMNS]],
+) {
+    use MemoryBaseColumn::*;
+    for i in 0..memory_rows.len() - 1 {
+        let [curr, next] = [memory_rows[i], memory_rows[i + 1]];
+        if curr[Addr as usize] == next[Addr as usize] {
+            memory_rows[i][ClkDiff as usize] =
+                next[Cycle as usize] - curr[Cycle as usize] - Fp::one();
+        }
+    }
+
+    for row in memory_rows.iter() {
+        let clk_diff = row[ClkDiff as usize].into_bigint().0[0] as usize;
+        let processor_row = &mut processor_rows[clk_diff];
+        processor_row[ProcessorBaseColumn::ClkMultiplicity as usize] += Fp::one();
+    }
+}
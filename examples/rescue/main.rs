@@ -0,0 +1,244 @@
+//! Proves a hash chain `result = H(H(...H(seed)))` using the Rescue-Prime
+//! gadget. Each hash is a single permutation that occupies 8 rows of the
+//! trace. Between permutations the digest is copied into the rate of the next
+//! permutation's input state.
+#![feature(allocator_api)]
+
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::gadgets::rescue;
+use ministark::gadgets::rescue::RescueColumns;
+use ministark::gadgets::rescue::RescuePrime;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+use std::time::Instant;
+use structopt::StructOpt;
+
+const COLUMNS: RescueColumns = RescueColumns {
+    trace_offset: 0,
+    periodic_offset: HashChainTrace::NUM_BASE_COLUMNS,
+};
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "rescue", about = "miniSTARK Rescue-Prime hash chain")]
+struct RescueOptions {
+    /// number of times to hash the seed. Must be a power of two
+    #[structopt(long, default_value = "1024")]
+    num_hashes: usize,
+}
+
+/// Input state of the permutation that hashes a single digest
+fn hash_input_state(digest: &[Fp]) -> [Fp; rescue::STATE_WIDTH] {
+    let mut state = [Fp::zero(); rescue::STATE_WIDTH];
+    state[rescue::CAPACITY_RANGE.start] = Fp::from(rescue::DIGEST_SIZE as u64);
+    state[rescue::DIGEST_RANGE].copy_from_slice(digest);
+    state
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+struct HashChainInfo {
+    seed: Vec<Fp>,
+    result: Vec<Fp>,
+}
+
+struct HashChainTrace {
+    info: HashChainInfo,
+    base_columns: Matrix<Fp>,
+}
+
+impl Trace for HashChainTrace {
+    type Fp = Fp;
+    type Fq = Fq3;
+
+    const NUM_BASE_COLUMNS: usize = rescue::NUM_COLUMNS;
+
+    fn len(&self) -> usize {
+        self.base_columns.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_columns
+    }
+}
+
+fn gen_trace(
+    rescue_prime: &RescuePrime,
+    seed: [Fp; rescue::DIGEST_SIZE],
+    n: usize,
+) -> HashChainTrace {
+    assert!(n.is_power_of_two());
+    let trace_len = n * rescue::CYCLE_LENGTH;
+    let mut columns = (0..rescue::NUM_COLUMNS)
+        .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+
+    let mut digest = seed.to_vec();
+    for _ in 0..n {
+        let rows = rescue_prime.permutation_trace(hash_input_state(&digest));
+        for row in &rows {
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(*value);
+            }
+        }
+        digest = rows.last().unwrap()[rescue::DIGEST_RANGE].to_vec();
+    }
+
+    HashChainTrace {
+        info: HashChainInfo {
+            seed: seed.to_vec(),
+            result: digest,
+        },
+        base_columns: Matrix::new(columns),
+    }
+}
+
+struct HashChainAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    info: HashChainInfo,
+    rescue_prime: RescuePrime,
+    boundary_constraints: Vec<Constraint<Fq3>>,
+    transition_constraints: Vec<Constraint<Fq3>>,
+    terminal_constraints: Vec<Constraint<Fq3>>,
+}
+
+impl Air for HashChainAir {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = HashChainInfo;
+
+    fn new(trace_info: TraceInfo, info: HashChainInfo, options: ProofOptions) -> Self {
+        assert_eq!(info.seed.len(), rescue::DIGEST_SIZE);
+        assert_eq!(info.result.len(), rescue::DIGEST_SIZE);
+        let rescue_prime = RescuePrime::new();
+
+        // the first permutation hashes the seed
+        let boundary_constraints = hash_input_state(&info.seed)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| COLUMNS.state(i).curr() - Fq3::from(v))
+            .collect();
+
+        // the output of one permutation is hashed by the next
+        let is_last_round = Constraint::from(Fq3::one()) - COLUMNS.round_flag().curr();
+        let mut transition_constraints = rescue_prime.transition_constraints(COLUMNS);
+        for i in 0..rescue::STATE_WIDTH {
+            let next = COLUMNS.state(i).next::<Fq3>();
+            let constraint = if rescue::DIGEST_RANGE.contains(&i) {
+                next - COLUMNS.state(i).curr()
+            } else if i == rescue::CAPACITY_RANGE.start {
+                next - Fq3::from(rescue::DIGEST_SIZE as u64)
+            } else {
+                next
+            };
+            transition_constraints.push(constraint * &is_last_round);
+        }
+
+        // the output of the last permutation is the result
+        let terminal_constraints = rescue::DIGEST_RANGE
+            .zip(&info.result)
+            .map(|(i, &v)| COLUMNS.state(i).curr() - Fq3::from(v))
+            .collect();
+
+        HashChainAir {
+            options,
+            trace_info,
+            info,
+            rescue_prime,
+            boundary_constraints,
+            transition_constraints,
+            terminal_constraints,
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.info
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<Fp>> {
+        self.rescue_prime.periodic_columns()
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.terminal_constraints
+    }
+}
+
+struct HashChainProver(ProofOptions);
+
+impl Prover for HashChainProver {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type Air = HashChainAir;
+    type Trace = HashChainTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        HashChainProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &HashChainTrace) -> HashChainInfo {
+        trace.info.clone()
+    }
+}
+
+fn main() {
+    let RescueOptions { num_hashes } = RescueOptions::from_args();
+    let options = ProofOptions::secure_128::<HashChainAir>().unwrap();
+    let rescue_prime = RescuePrime::new();
+    let seed = [1u64, 2, 3, 4].map(Fp::from);
+
+    let now = Instant::now();
+    let trace = gen_trace(&rescue_prime, seed, num_hashes);
+    println!(
+        "Generated trace (cols={}, rows={}) in {:.0?}",
+        trace.base_columns().num_cols(),
+        trace.len(),
+        now.elapsed()
+    );
+
+    let prover = HashChainProver::new(options);
+    let proof = prover.generate_proof(trace).unwrap();
+    println!("Proof generated in: {:.0?}", now.elapsed());
+    println!("Result: {:?}", proof.public_inputs.as_ref().unwrap().result);
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    println!("Proof size: {:?}KB", proof_bytes.len() / 1024);
+
+    let proof = Proof::<HashChainAir>::deserialize_compressed(proof_bytes.as_slice()).unwrap();
+    let now = Instant::now();
+    proof.verify().unwrap();
+    println!("Proof verified in: {:.0?}", now.elapsed());
+}
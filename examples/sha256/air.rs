@@ -0,0 +1,285 @@
+use crate::layout::*;
+use crate::trace::IV;
+use crate::trace::K;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::assertion::Assertion;
+use ministark::gadgets::bits::pack;
+use ministark::gadgets::bits::rotate_right;
+use ministark::gadgets::bits::shift_right;
+use ministark::gadgets::bits::xor;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::ProofOptions;
+use ministark::TraceInfo;
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct Sha256Info {
+    pub digest: Vec<u32>,
+    /// Number of blocks in the padded message. Blocks after these only pad
+    /// the trace to its minimum length and don't affect the digest.
+    pub num_blocks: usize,
+}
+
+pub struct Sha256Air {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    info: Sha256Info,
+    boundary_constraints: Vec<Constraint<Fq3>>,
+    transition_constraints: Vec<Constraint<Fq3>>,
+    terminal_constraints: Vec<Constraint<Fq3>>,
+}
+
+/// Bits of state word `word` on the row `row` rows after the current row
+fn word_bits(word: usize, row: usize) -> Vec<Constraint<Fq3>> {
+    state_bits(word).bits(row)
+}
+
+/// Value of state word `word` on the row `row` rows after the current row
+fn word_value(word: usize, row: usize) -> Constraint<Fq3> {
+    match word {
+        3 => D.offset(row),
+        7 => H.offset(row),
+        _ => state_bits(word).value(row),
+    }
+}
+
+/// Packed XOR of three words given as bits
+fn xor3(x: &[Constraint<Fq3>], y: &[Constraint<Fq3>], z: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    let bits = x
+        .iter()
+        .zip(y)
+        .zip(z)
+        .map(|((x, y), z)| xor(&xor(x, y), z))
+        .collect::<Vec<_>>();
+    pack(&bits)
+}
+
+fn big_sigma0(x: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    xor3(
+        &rotate_right(x, 2),
+        &rotate_right(x, 13),
+        &rotate_right(x, 22),
+    )
+}
+
+fn big_sigma1(x: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    xor3(
+        &rotate_right(x, 6),
+        &rotate_right(x, 11),
+        &rotate_right(x, 25),
+    )
+}
+
+fn small_sigma0(x: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    xor3(
+        &rotate_right(x, 7),
+        &rotate_right(x, 18),
+        &shift_right(x, 3),
+    )
+}
+
+fn small_sigma1(x: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    xor3(
+        &rotate_right(x, 17),
+        &rotate_right(x, 19),
+        &shift_right(x, 10),
+    )
+}
+
+fn ch(e: &[Constraint<Fq3>], f: &[Constraint<Fq3>], g: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    let bits = e
+        .iter()
+        .zip(f)
+        .zip(g)
+        .map(|((e, f), g)| e * f + g - e * g)
+        .collect::<Vec<_>>();
+    pack(&bits)
+}
+
+fn maj(a: &[Constraint<Fq3>], b: &[Constraint<Fq3>], c: &[Constraint<Fq3>]) -> Constraint<Fq3> {
+    let bits = a
+        .iter()
+        .zip(b)
+        .zip(c)
+        .map(|((a, b), c)| a * b + a * c + b * c - (a * b * c) * Fq3::from(2u64))
+        .collect::<Vec<_>>();
+    pack(&bits)
+}
+
+/// The values each state word is set to by a round (before reducing modulo
+/// `2^32`). Only depends on the current row. The chaining value is added on
+/// the last round of a block.
+fn round_sums() -> Vec<Constraint<Fq3>> {
+    let [a, b, c, e, f, g] = [0, 1, 2, 4, 5, 6].map(|word| word_bits(word, 0));
+    let w = SCHEDULE.value(0);
+    let t1 = word_value(7, 0) + big_sigma1(&e) + ch(&e, &f, &g) + ROUND_CONSTANT.curr() + w;
+    let t2 = big_sigma0(&a) + maj(&a, &b, &c);
+    let is_last_round = IS_LAST_ROUND.curr::<Fq3>();
+    [
+        &t1 + t2,
+        pack(&a),
+        pack(&b),
+        pack(&c),
+        word_value(3, 0) + &t1,
+        pack(&e),
+        pack(&f),
+        pack(&g),
+    ]
+    .into_iter()
+    .enumerate()
+    .map(|(word, sum)| sum + &is_last_round * (CHAINING_VALUE + word).curr())
+    .collect()
+}
+
+fn boundary_constraints() -> Vec<Constraint<Fq3>> {
+    let mut constraints = bit_columns()
+        .flat_map(|bits| bits.range_constraints(0))
+        .collect::<Vec<_>>();
+    // the first block starts from the initial hash value
+    for (word, &iv) in IV.iter().enumerate() {
+        constraints.push(word_value(word, 0) - Fq3::from(iv as u64));
+        constraints.push((CHAINING_VALUE + word).curr() - Fq3::from(iv as u64));
+    }
+    constraints
+}
+
+/// Transition constraints. All of them only look at the next row except the
+/// message schedule recurrence which comes last and looks 16 rows ahead.
+fn transition_constraints() -> Vec<Constraint<Fq3>> {
+    // bits are checked on the first row by the boundary constraints
+    let mut constraints = bit_columns()
+        .flat_map(|bits| bits.range_constraints(1))
+        .collect::<Vec<_>>();
+
+    // round function and the chaining value addition of the last round
+    for (word, sum) in round_sums().into_iter().enumerate() {
+        let carry = state_carry(word).value(0) * Fq3::from(1u64 << WORD_BITS);
+        constraints.push(word_value(word, 1) + carry - sum);
+    }
+
+    // the chaining value is constant within a block and is the state at the start
+    let is_last_round = IS_LAST_ROUND.curr::<Fq3>();
+    let is_not_last_round = Constraint::from(Fq3::one()) - &is_last_round;
+    for word in 0..STATE_WORDS {
+        let chaining_value = CHAINING_VALUE + word;
+        constraints.push(&is_not_last_round * (chaining_value.next() - chaining_value.curr()));
+        constraints.push(&is_last_round * (chaining_value.next() - word_value(word, 1)));
+    }
+
+    // `W[t] = σ1(W[t-2]) + W[t-7] + σ0(W[t-15]) + W[t-16]` for `t >= 16` checked
+    // on row `t - 16`
+    let w = SCHEDULE.value(16);
+    let carry = SCHEDULE_CARRY.value(16) * Fq3::from(1u64 << WORD_BITS);
+    let sum = small_sigma1(&SCHEDULE.bits(14))
+        + SCHEDULE.value(9)
+        + small_sigma0(&SCHEDULE.bits(1))
+        + SCHEDULE.value(0);
+    constraints.push(IS_SCHEDULED_AHEAD.curr() * (w + carry - sum));
+
+    constraints
+}
+
+/// Checks the digest on the last row. Only used when the trace has no padding
+/// blocks otherwise the digest is asserted to be the chaining value of the
+/// first padding block.
+fn terminal_constraints(digest: &[u32]) -> Vec<Constraint<Fq3>> {
+    round_sums()
+        .into_iter()
+        .zip(digest)
+        .enumerate()
+        .map(|(word, (sum, &expected))| {
+            let carry = state_carry(word).value(0) * Fq3::from(1u64 << WORD_BITS);
+            carry + Fq3::from(expected as u64) - sum
+        })
+        .collect()
+}
+
+impl Air for Sha256Air {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = Sha256Info;
+
+    fn new(trace_info: TraceInfo, info: Sha256Info, options: ProofOptions) -> Self {
+        assert_eq!(info.digest.len(), STATE_WORDS);
+        let is_padded = info.num_blocks.saturating_mul(NUM_ROUNDS) != trace_info.trace_len;
+        Sha256Air {
+            options,
+            trace_info,
+            boundary_constraints: boundary_constraints(),
+            transition_constraints: transition_constraints(),
+            terminal_constraints: if is_padded {
+                Vec::new()
+            } else {
+                terminal_constraints(&info.digest)
+            },
+            info,
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.info
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn transition_exemptions(&self) -> Vec<usize> {
+        // only the message schedule recurrence looks more than one row ahead
+        let mut exemptions = vec![1; self.transition_constraints.len()];
+        *exemptions.last_mut().unwrap() = 16;
+        exemptions
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<Fp>> {
+        let round_constants = K.iter().map(|&k| Fp::from(k)).collect();
+        let mut is_last_round = vec![Fp::zero(); NUM_ROUNDS];
+        is_last_round[NUM_ROUNDS - 1] = Fp::one();
+        let is_scheduled_ahead = (0..NUM_ROUNDS)
+            .map(|t| {
+                if t < NUM_ROUNDS - 16 {
+                    Fp::one()
+                } else {
+                    Fp::zero()
+                }
+            })
+            .collect();
+        vec![round_constants, is_last_round, is_scheduled_ahead]
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        // the chaining value of the first padding block is the digest
+        let step = self.info.num_blocks.saturating_mul(NUM_ROUNDS);
+        if step == self.trace_len() {
+            return Vec::new();
+        }
+        self.info
+            .digest
+            .iter()
+            .enumerate()
+            .map(|(word, &value)| Assertion::single(CHAINING_VALUE + word, step, Fp::from(value)))
+            .collect()
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Fq3>] {
+        &self.terminal_constraints
+    }
+}
@@ -0,0 +1,97 @@
+//! Column layout of the SHA-256 trace. Each row is one round of the
+//! compression function and each block of the message takes 64 rows.
+//!
+//! State words that take part in bitwise operations are decomposed into bits.
+//! `d` and `h` are only ever added so they're stored packed. Additions are
+//! checked over the integers with explicit carries which is sound because the
+//! sums never come close to the field modulus. A word that's copied from a
+//! bit decomposed word on the last round of a block can be off by a multiple
+//! of `2^32` since its carry is only a bit but every relation it takes part in
+//! is modulo `2^32` so this doesn't change what's proven.
+//!
+//! The message schedule `W[t]` is stored as bits once per row. The recurrence
+//! `W[t] = σ1(W[t-2]) + W[t-7] + σ0(W[t-15]) + W[t-16]` is checked on row
+//! `t - 16` by looking 16 rows ahead rather than keeping delay lines of
+//! earlier words.
+use ministark::gadgets::bits::BitColumns;
+
+/// Number of rounds in a compression. Also the number of rows per block.
+pub const NUM_ROUNDS: usize = 64;
+
+pub const WORD_BITS: usize = 32;
+
+/// Number of words in the hash state `a..h`
+pub const STATE_WORDS: usize = 8;
+
+/// State words `a, b, c, e, f, g` that are stored as bits
+const BIT_WORDS: [usize; 6] = [0, 1, 2, 4, 5, 6];
+
+/// Bits of the state words `a, b, c, e, f, g` before the round
+pub const STATE_BITS: usize = 0;
+
+/// Packed state word `d` before the round
+pub const D: usize = STATE_BITS + BIT_WORDS.len() * WORD_BITS;
+
+/// Packed state word `h` before the round
+pub const H: usize = D + 1;
+
+/// Carries of the additions that produce the state words of the next row.
+/// `a` and `e` sum up to seven words so their carries have three bits. Other
+/// words are copied and only have the chaining value added on the last round
+/// so their carries are a bit.
+pub const STATE_CARRIES: usize = H + 1;
+
+/// Bits of the carry of `a` and `e`
+pub const SUM_CARRY_BITS: usize = 3;
+
+/// Chaining value of the current block i.e. the state before the first round
+pub const CHAINING_VALUE: usize = STATE_CARRIES + 2 * SUM_CARRY_BITS + 6;
+
+/// Bits of the message schedule word `W[t]` used in the round
+pub const SCHEDULE: BitColumns = BitColumns::new(CHAINING_VALUE + STATE_WORDS, WORD_BITS);
+
+/// Carry of the message schedule addition that produces `W[t]`. It sums four
+/// words.
+pub const SCHEDULE_CARRY: BitColumns = BitColumns::new(SCHEDULE.offset + WORD_BITS, 2);
+
+pub const NUM_BASE_COLUMNS: usize = SCHEDULE_CARRY.offset + SCHEDULE_CARRY.num_bits;
+
+/// Periodic column holding the round constant `K[t]`
+pub const ROUND_CONSTANT: usize = NUM_BASE_COLUMNS;
+
+/// Periodic column that is one on the last round of each block
+pub const IS_LAST_ROUND: usize = NUM_BASE_COLUMNS + 1;
+
+/// Periodic column that is one on rounds `t` where `W[t + 16]` comes from the
+/// message schedule recurrence i.e. `t < 48`
+pub const IS_SCHEDULED_AHEAD: usize = NUM_BASE_COLUMNS + 2;
+
+/// Bits of state word `word` where words `0..8` are `a..h`. Panics for `d` and
+/// `h` which are stored packed.
+pub fn state_bits(word: usize) -> BitColumns {
+    let i = BIT_WORDS
+        .iter()
+        .position(|&w| w == word)
+        .expect("state word isn't stored as bits");
+    BitColumns::new(STATE_BITS + i * WORD_BITS, WORD_BITS)
+}
+
+/// Bits of the carry of the addition producing state word `word`
+pub fn state_carry(word: usize) -> BitColumns {
+    match word {
+        0 => BitColumns::new(STATE_CARRIES, SUM_CARRY_BITS),
+        4 => BitColumns::new(STATE_CARRIES + SUM_CARRY_BITS, SUM_CARRY_BITS),
+        _ => {
+            let copied = [1, 2, 3, 5, 6, 7];
+            let i = copied.iter().position(|&w| w == word).unwrap();
+            BitColumns::new(STATE_CARRIES + 2 * SUM_CARRY_BITS + i, 1)
+        }
+    }
+}
+
+/// Every column holding a bit
+pub fn bit_columns() -> impl Iterator<Item = BitColumns> {
+    let state = BIT_WORDS.into_iter().map(state_bits);
+    let carries = (0..STATE_WORDS).map(state_carry);
+    state.chain(carries).chain([SCHEDULE, SCHEDULE_CARRY])
+}
@@ -0,0 +1,137 @@
+//! Proves knowledge of a message with a given SHA-256 digest. The message is
+//! private and only the digest and number of blocks are public. The AIR checks
+//! the compression of every block but leaves the message padding to the
+//! prover i.e. the statement is knowledge of blocks whose compressions,
+//! starting from the SHA-256 initial hash value, result in the digest.
+#![feature(allocator_api)]
+
+use air::Sha256Air;
+use air::Sha256Info;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use sha2::Digest;
+use std::time::Instant;
+use structopt::StructOpt;
+use trace::Sha256Trace;
+
+mod air;
+mod layout;
+mod trace;
+
+#[derive(StructOpt, Debug)]
+#[structopt(name = "sha256", about = "miniSTARK SHA-256 preimage prover")]
+struct Sha256Options {
+    /// number of 512-bit blocks in the padded message. The trace is padded to
+    /// a power of two number of blocks that meets the minimum trace length.
+    #[structopt(long, default_value = "32")]
+    num_blocks: usize,
+}
+
+struct Sha256Prover(ProofOptions);
+
+impl Prover for Sha256Prover {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type Air = Sha256Air;
+    type Trace = Sha256Trace;
+
+    fn new(options: ProofOptions) -> Self {
+        Sha256Prover(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &Sha256Trace) -> Sha256Info {
+        Sha256Info {
+            digest: trace.digest().to_vec(),
+            num_blocks: trace.num_blocks(),
+        }
+    }
+}
+
+/// Longest message that pads to `num_blocks` blocks. Padding appends at least
+/// 9 bytes (the 0x80 byte and 64-bit length).
+fn message_of_blocks(num_blocks: usize) -> Vec<u8> {
+    (0..num_blocks * 64 - 9).map(|i| (i % 251) as u8).collect()
+}
+
+fn main() {
+    let Sha256Options { num_blocks } = Sha256Options::from_args();
+    let options = ProofOptions::secure_128::<Sha256Air>().unwrap();
+
+    let message = message_of_blocks(num_blocks);
+
+    let now = Instant::now();
+    let trace = trace::gen_trace(&message);
+    println!(
+        "Generated trace (cols={}, rows={}) in {:.0?}",
+        trace.base_columns().num_cols(),
+        trace.len(),
+        now.elapsed()
+    );
+    let expected_digest = sha2::Sha256::digest(&message);
+    let digest_bytes = trace
+        .digest()
+        .iter()
+        .flat_map(|word| word.to_be_bytes())
+        .collect::<Vec<u8>>();
+    assert_eq!(expected_digest.as_slice(), digest_bytes);
+
+    let prover = Sha256Prover::new(options);
+    let proof = prover.generate_proof(trace).unwrap();
+    println!("Proof generated in: {:.0?}", now.elapsed());
+    println!(
+        "Digest of {} byte message: {}",
+        message.len(),
+        digest_bytes
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>()
+    );
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    println!("Proof size: {:?}KB", proof_bytes.len() / 1024);
+
+    let proof = Proof::<Sha256Air>::deserialize_compressed(proof_bytes.as_slice()).unwrap();
+    let now = Instant::now();
+    proof.verify().unwrap();
+    println!("Proof verified in: {:.0?}", now.elapsed());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_block_proof_verifies() {
+        let message = message_of_blocks(1);
+        let trace = trace::gen_trace(&message);
+        let expected_digest = sha2::Sha256::digest(&message);
+        let digest_bytes = trace
+            .digest()
+            .iter()
+            .flat_map(|word| word.to_be_bytes())
+            .collect::<Vec<u8>>();
+        assert_eq!(expected_digest.as_slice(), digest_bytes);
+        assert!(trace.base_columns().num_cols() <= ministark::TraceInfo::MAX_TRACE_WIDTH);
+
+        let proof = Sha256Prover::new(ProofOptions::fast_dev())
+            .generate_proof(trace)
+            .unwrap();
+        let mut info = proof.public_inputs.clone().unwrap();
+        assert_eq!(1, info.num_blocks);
+        proof.clone().verify().unwrap();
+
+        info.digest[0] ^= 1;
+        assert!(proof.verify_with_public_inputs(info).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::layout::*;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::Matrix;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+pub const IV: [u32; STATE_WORDS] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+pub const K: [u32; NUM_ROUNDS] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+pub fn big_sigma0(x: u32) -> u32 {
+    x.rotate_right(2) ^ x.rotate_right(13) ^ x.rotate_right(22)
+}
+
+pub fn big_sigma1(x: u32) -> u32 {
+    x.rotate_right(6) ^ x.rotate_right(11) ^ x.rotate_right(25)
+}
+
+pub fn small_sigma0(x: u32) -> u32 {
+    x.rotate_right(7) ^ x.rotate_right(18) ^ (x >> 3)
+}
+
+pub fn small_sigma1(x: u32) -> u32 {
+    x.rotate_right(17) ^ x.rotate_right(19) ^ (x >> 10)
+}
+
+fn ch(e: u32, f: u32, g: u32) -> u32 {
+    (e & f) ^ (!e & g)
+}
+
+fn maj(a: u32, b: u32, c: u32) -> u32 {
+    (a & b) ^ (a & c) ^ (b & c)
+}
+
+/// Pads a message and splits it into 512-bit blocks of big-endian words
+pub fn pad_message(message: &[u8]) -> Vec<[u32; 16]> {
+    let bit_len = (message.len() as u64) * 8;
+    let mut bytes = message.to_vec();
+    bytes.push(0x80);
+    while bytes.len() % 64 != 56 {
+        bytes.push(0);
+    }
+    bytes.extend(bit_len.to_be_bytes());
+    bytes
+        .chunks(64)
+        .map(|block| {
+            let mut words = [0; 16];
+            for (word, chunk) in words.iter_mut().zip(block.chunks(4)) {
+                *word = u32::from_be_bytes(chunk.try_into().unwrap());
+            }
+            words
+        })
+        .collect()
+}
+
+pub struct Sha256Trace {
+    digest: [u32; STATE_WORDS],
+    num_blocks: usize,
+    base_columns: Matrix<Fp>,
+}
+
+impl Sha256Trace {
+    pub fn digest(&self) -> [u32; STATE_WORDS] {
+        self.digest
+    }
+
+    /// Number of blocks in the padded message
+    pub fn num_blocks(&self) -> usize {
+        self.num_blocks
+    }
+}
+
+impl Trace for Sha256Trace {
+    type Fp = Fp;
+    type Fq = Fq3;
+
+    const NUM_BASE_COLUMNS: usize = NUM_BASE_COLUMNS;
+
+    fn len(&self) -> usize {
+        self.base_columns.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_columns
+    }
+}
+
+/// Generates a trace of the SHA-256 compressions of a padded message. Blocks
+/// of zeros are compressed after the message until the trace has a power of
+/// two length that's at least the minimum trace length. They don't change the
+/// digest which is the hash value after the last block of the message.
+pub fn gen_trace(message: &[u8]) -> Sha256Trace {
+    let mut blocks = pad_message(message);
+    let num_blocks = blocks.len();
+    let min_blocks = TraceInfo::MIN_TRACE_LENGTH / NUM_ROUNDS;
+    blocks.resize(num_blocks.next_power_of_two().max(min_blocks), [0; 16]);
+    let trace_len = blocks.len() * NUM_ROUNDS;
+    let mut columns = (0..NUM_BASE_COLUMNS)
+        .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+
+    let mut hash = IV;
+    let mut digest = IV;
+    for (i, block) in blocks.into_iter().enumerate() {
+        let mut w = [0; NUM_ROUNDS];
+        let mut schedule_carries = [0; NUM_ROUNDS];
+        w[..16].copy_from_slice(&block);
+        for t in 16..NUM_ROUNDS {
+            let sum = small_sigma1(w[t - 2]) as u64
+                + w[t - 7] as u64
+                + small_sigma0(w[t - 15]) as u64
+                + w[t - 16] as u64;
+            w[t] = sum as u32;
+            schedule_carries[t] = sum >> WORD_BITS;
+        }
+
+        let mut state = hash;
+        for t in 0..NUM_ROUNDS {
+            let mut row = vec![Fp::zero(); NUM_BASE_COLUMNS];
+            for (word, &value) in state.iter().enumerate() {
+                match word {
+                    3 => row[D] = Fp::from(value),
+                    7 => row[H] = Fp::from(value),
+                    _ => state_bits(word).write(&mut row, value as u64),
+                }
+                row[CHAINING_VALUE + word] = Fp::from(hash[word]);
+            }
+            SCHEDULE.write(&mut row, w[t] as u64);
+            SCHEDULE_CARRY.write(&mut row, schedule_carries[t]);
+
+            // the last round also adds the chaining value to the state
+            let [a, b, c, d, e, f, g, h] = state.map(|word| word as u64);
+            let t1 = h
+                + big_sigma1(e as u32) as u64
+                + ch(e as u32, f as u32, g as u32) as u64
+                + K[t] as u64
+                + w[t] as u64;
+            let t2 = big_sigma0(a as u32) as u64 + maj(a as u32, b as u32, c as u32) as u64;
+            let mut sums = [t1 + t2, a, b, c, d + t1, e, f, g];
+            if t == NUM_ROUNDS - 1 {
+                sums.iter_mut()
+                    .zip(hash)
+                    .for_each(|(sum, word)| *sum += word as u64);
+            }
+            for (word, sum) in sums.iter().enumerate() {
+                state_carry(word).write(&mut row, sum >> WORD_BITS);
+            }
+            state = sums.map(|sum| sum as u32);
+
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+        hash = state;
+        if i + 1 == num_blocks {
+            digest = hash;
+        }
+    }
+
+    Sha256Trace {
+        digest,
+        num_blocks,
+        base_columns: Matrix::new(columns),
+    }
+}
@@ -6,18 +6,68 @@ use std::alloc::Allocator;
 use std::alloc::Global;
 use std::alloc::Layout;
 use std::ptr::NonNull;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
 
 pub static PAGE_SIZE: Lazy<usize> =
     Lazy::new(|| unsafe { sysconf(_SC_PAGESIZE).try_into().unwrap() });
 
+/// Where [PageAlignedAllocator] gets its memory from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum AllocationMode {
+    /// Regular memory that the OS is free to page out
+    #[default]
+    Pageable = 0,
+    /// Memory locked into RAM with `mlock` so the OS won't swap its pages
+    /// out. Allocations fall back to pageable memory if the lock limit
+    /// (`ulimit -l`) is reached.
+    Pinned = 1,
+}
+
+static ALLOCATION_MODE: AtomicU8 = AtomicU8::new(AllocationMode::Pageable as u8);
+
+// set once any allocation has been pinned so deallocations only pay for the
+// unlock syscall when there is something that could need unlocking
+static HAS_PINNED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the mode used by all future [PageAlignedAllocator] allocations.
+/// Existing allocations are not affected.
+pub fn set_allocation_mode(mode: AllocationMode) {
+    ALLOCATION_MODE.store(mode as u8, Ordering::Relaxed);
+}
+
+pub fn allocation_mode() -> AllocationMode {
+    match ALLOCATION_MODE.load(Ordering::Relaxed) {
+        0 => AllocationMode::Pageable,
+        1 => AllocationMode::Pinned,
+        _ => unreachable!(),
+    }
+}
+
 pub struct PageAlignedAllocator;
 
 unsafe impl Allocator for PageAlignedAllocator {
     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        Global.allocate(layout.align_to(*PAGE_SIZE).unwrap().pad_to_align())
+        let layout = layout.align_to(*PAGE_SIZE).unwrap().pad_to_align();
+        let ptr = Global.allocate(layout)?;
+        if allocation_mode() == AllocationMode::Pinned && layout.size() != 0 {
+            // failing to lock isn't fatal. The memory is still usable.
+            let addr = ptr.as_ptr() as *const libc::c_void;
+            if unsafe { libc::mlock(addr, layout.size()) } == 0 {
+                HAS_PINNED.store(true, Ordering::Relaxed);
+            }
+        }
+        Ok(ptr)
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
-        Global.deallocate(ptr, layout.align_to(*PAGE_SIZE).unwrap().pad_to_align())
+        let layout = layout.align_to(*PAGE_SIZE).unwrap().pad_to_align();
+        if HAS_PINNED.load(Ordering::Relaxed) && layout.size() != 0 {
+            // unlocking pages that were never locked is a no-op
+            libc::munlock(ptr.as_ptr() as *const libc::c_void, layout.size());
+        }
+        Global.deallocate(ptr, layout)
     }
 }
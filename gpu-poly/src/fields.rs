@@ -23,6 +23,10 @@ pub mod p18446744069414584321 {
         fn field_name() -> String {
             "p18446744069414584321_fp".to_string()
         }
+
+        fn metal_type() -> Option<String> {
+            Some("p18446744069414584321::Fp".to_string())
+        }
     }
 
     impl GpuMulAssign<fp64::Fp> for fp64::Fp {}
@@ -106,5 +110,9 @@ pub mod p18446744069414584321 {
         fn field_name() -> String {
             "p18446744069414584321_fq3".to_string()
         }
+
+        fn metal_type() -> Option<String> {
+            Some("p18446744069414584321::Fq3".to_string())
+        }
     }
 }
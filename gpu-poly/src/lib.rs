@@ -14,6 +14,9 @@ pub mod prelude;
 pub mod stage;
 pub mod utils;
 
+#[doc(hidden)]
+pub use zeroize;
+
 /// A marker trait to be implemented if the field can be used for FFTs on the
 /// GPU.
 pub trait GpuFftField: GpuField<FftField = Self> + ark_ff::FftField {}
@@ -36,8 +39,18 @@ pub trait GpuField:
 {
     type FftField: GpuFftField;
 
+    /// False for fields without GPU kernels e.g. those declared with
+    /// [cpu_field!]. Operations on these fields always run on the CPU.
+    const HAS_GPU_KERNELS: bool = true;
+
     // Used to select which GPU kernel to call.
     fn field_name() -> String;
+
+    /// Name of the field's type in the Metal headers. Needed to generate
+    /// kernels at runtime. `None` if the headers don't declare the field.
+    fn metal_type() -> Option<String> {
+        None
+    }
 }
 
 /// Shared vec between GPU and CPU.
@@ -3,9 +3,16 @@
 #[macro_export]
 macro_rules! wrap_field {
     ($outer:ident; $inner:ty) => {
-        #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash, zeroize::Zeroize)]
+        #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
         pub struct $outer($inner);
 
+        // implemented by hand so crates using the macro don't need zeroize
+        impl $crate::zeroize::Zeroize for $outer {
+            fn zeroize(&mut self) {
+                $crate::zeroize::Zeroize::zeroize(&mut self.0)
+            }
+        }
+
         impl std::fmt::Display for $outer {
             fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
                 write!(f, "{}", self.0)
@@ -430,3 +437,51 @@ macro_rules! wrap_field {
         }
     };
 }
+
+// Implements `GpuField` and `GpuFftField` for a field without GPU kernels so
+// it meets the bounds of APIs that take a `GpuFftField`. The bounds themselves
+// stay as they are, CPU-only code paths included, so a plain arkworks field
+// can't be used without going through this macro. Everything runs on the CPU
+// for these fields. A field defined in the calling crate is registered
+// directly. Fields from other crates (such as an arkworks
+// `Fp64<MontBackend<..>>`) have to be wrapped first which is done by giving
+// the macro a name for the wrapper.
+#[macro_export]
+macro_rules! cpu_field {
+    ($field:ty) => {
+        impl $crate::GpuField for $field {
+            type FftField = Self;
+
+            const HAS_GPU_KERNELS: bool = false;
+
+            fn field_name() -> String {
+                stringify!($field).to_string()
+            }
+        }
+
+        impl $crate::GpuMulAssign<$field> for $field {}
+
+        impl $crate::GpuFftField for $field {}
+    };
+    ($outer:ident; $inner:ty) => {
+        $crate::wrap_field!($outer; $inner);
+
+        impl ark_ff::FftField for $outer {
+            const GENERATOR: Self = $outer(<$inner as ark_ff::FftField>::GENERATOR);
+            const TWO_ADICITY: u32 = <$inner as ark_ff::FftField>::TWO_ADICITY;
+            const TWO_ADIC_ROOT_OF_UNITY: Self =
+                $outer(<$inner as ark_ff::FftField>::TWO_ADIC_ROOT_OF_UNITY);
+            const SMALL_SUBGROUP_BASE: Option<u32> =
+                <$inner as ark_ff::FftField>::SMALL_SUBGROUP_BASE;
+            const SMALL_SUBGROUP_BASE_ADICITY: Option<u32> =
+                <$inner as ark_ff::FftField>::SMALL_SUBGROUP_BASE_ADICITY;
+            const LARGE_SUBGROUP_ROOT_OF_UNITY: Option<Self> =
+                match <$inner as ark_ff::FftField>::LARGE_SUBGROUP_ROOT_OF_UNITY {
+                    Some(root) => Some($outer(root)),
+                    None => None,
+                };
+        }
+
+        $crate::cpu_field!($outer);
+    };
+}
@@ -51,6 +51,31 @@ namespace p18446744069414584321
             return res;
         }
         
+        // Computes `x^(p-2)` i.e. the inverse of non-zero `x`
+        Fp inverse() const
+        {
+            unsigned long exp = N - 2;
+            Fp base = *this;
+            Fp res = ONE;
+
+            while (exp > 0)
+            {
+                if (exp & 1)
+                {
+                    res = res * base;
+                }
+                exp >>= 1;
+                base = base * base;
+            }
+
+            return res;
+        }
+
+        constexpr bool is_zero() const
+        {
+            return inner == 0;
+        }
+
         // 1 in Montgomery representation
         constexpr static const constant unsigned long ONE = 4294967295;
 
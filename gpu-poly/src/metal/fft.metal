@@ -119,6 +119,37 @@ GenerateTwiddles(device FieldT *dst [[ buffer(0) ]],
     dst[i] = tmp.pow(ri);
 }
 
+// Inverts each non-zero value using Montgomery's trick. Each thread inverts a
+// contiguous chunk of values with a single field inversion. `scratch` holds the
+// running products and must be the same length as `values`. Zeros are skipped.
+template<typename FieldT> kernel void
+BatchInverse(device FieldT *values [[ buffer(0) ]],
+        device FieldT *scratch [[ buffer(1) ]],
+        constant unsigned &chunk_size [[ buffer(2) ]],
+        unsigned global_tid [[ thread_position_in_grid ]]) {
+    unsigned start = global_tid * chunk_size;
+    unsigned end = start + chunk_size;
+
+    FieldT acc = FieldT::ONE;
+    for (unsigned i = start; i < end; i++) {
+        scratch[i] = acc;
+        FieldT value = values[i];
+        if (!value.is_zero()) {
+            acc = acc * value;
+        }
+    }
+
+    acc = acc.inverse();
+
+    for (unsigned i = end; i-- > start;) {
+        FieldT value = values[i];
+        if (!value.is_zero()) {
+            values[i] = acc * scratch[i];
+            acc = acc * value;
+        }
+    }
+}
+
 // FFT size that each threadgroup computes.
 // Always a power of two.
 constant unsigned THREADGROUP_FFT_SIZE [[ function_constant(2) ]];
@@ -217,6 +248,12 @@ FillBuff<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
         constant p18446744069414584321::Fp&,
         unsigned);
+template [[ host_name("batch_inverse_p18446744069414584321_fp") ]] kernel void
+BatchInverse<p18446744069414584321::Fp>(
+        device p18446744069414584321::Fp*,
+        device p18446744069414584321::Fp*,
+        constant unsigned&,
+        unsigned);
 template [[ host_name("generate_twiddles_p18446744069414584321_fp") ]] kernel void
 GenerateTwiddles<p18446744069414584321::Fp>(
         device p18446744069414584321::Fp*,
@@ -13,10 +13,23 @@ use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
 use once_cell::sync::Lazy;
+use std::any::Any;
+use std::any::TypeId;
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::RwLock;
 
 const LIBRARY_DATA: &[u8] = include_bytes!("metal/fft.metallib");
 
+/// Field headers in dependency order. Prepended to sources compiled at runtime
+/// so generated kernels can use the field types.
+const FIELD_HEADERS: [&str; 3] = [
+    include_str!("metal/u128.h.metal"),
+    include_str!("metal/felt_u128.h.metal"),
+    include_str!("metal/felt_u64.h.metal"),
+];
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 enum FftDirection {
     /// FFT
@@ -25,9 +38,11 @@ enum FftDirection {
     Inverse,
 }
 
-pub struct FftEncoder<'a, F: GpuField> {
+/// Everything needed to encode an FFT over a domain besides the command
+/// buffer. Plans are built once per domain and direction by a [Planner] and
+/// shared by every FFT over that domain.
+struct FftPlan<F: GpuField> {
     n: usize,
-    command_queue: Arc<metal::CommandQueue>,
     // twiddles_buffer references this memory
     // field exists to keep the memory around
     _twiddles: GpuVec<F::FftField>,
@@ -35,6 +50,24 @@ pub struct FftEncoder<'a, F: GpuField> {
     scale_and_normalize_stage: Option<ScaleAndNormalizeGpuStage<F>>,
     butterfly_stages: Vec<FftGpuStage<F>>,
     bit_reverse_stage: BitReverseGpuStage<F>,
+}
+
+// https://github.com/gfx-rs/metal-rs/issues/40
+unsafe impl<F: GpuField> Send for FftPlan<F> {}
+unsafe impl<F: GpuField> Sync for FftPlan<F> {}
+
+/// Cached plans of a single field keyed by direction and domain
+type FftPlans<F> = HashMap<
+    (
+        FftDirection,
+        Radix2EvaluationDomain<<F as GpuField>::FftField>,
+    ),
+    Arc<FftPlan<F>>,
+>;
+
+pub struct FftEncoder<'a, F: GpuField> {
+    command_queue: Arc<metal::CommandQueue>,
+    plan: Arc<FftPlan<F>>,
     command_buffer: &'a metal::CommandBufferRef,
 }
 
@@ -44,18 +77,23 @@ unsafe impl<'a, F: GpuField> Sync for FftEncoder<'a, F> {}
 
 impl<'a, F: GpuField> FftEncoder<'a, F> {
     fn encode_butterfly_stages(&self, input_buffer: &mut metal::Buffer) {
-        for stage in &self.butterfly_stages {
-            stage.encode(self.command_buffer, input_buffer, &self.twiddles_buffer);
+        for stage in &self.plan.butterfly_stages {
+            stage.encode(
+                self.command_buffer,
+                input_buffer,
+                &self.plan.twiddles_buffer,
+            );
         }
     }
 
     fn encode_bit_reverse_stage(&self, input_buffer: &mut metal::Buffer) {
-        self.bit_reverse_stage
+        self.plan
+            .bit_reverse_stage
             .encode(self.command_buffer, input_buffer);
     }
 
     fn encode_scale_stage(&self, input_buffer: &mut metal::Buffer) {
-        if let Some(scale_stage) = &self.scale_and_normalize_stage {
+        if let Some(scale_stage) = &self.plan.scale_and_normalize_stage {
             scale_stage.encode(self.command_buffer, input_buffer);
         }
     }
@@ -73,8 +111,9 @@ impl<'a, F: GpuField> GpuFft<'a, F> {
     pub const MIN_SIZE: usize = 2048;
 
     pub fn encode(&mut self, buffer: &mut GpuVec<F>) {
-        assert!(self.0.n >= buffer.len());
-        buffer.resize(self.0.n, F::zero());
+        let n = self.0.plan.n;
+        assert!(n >= buffer.len());
+        buffer.resize(n, F::zero());
         let mut input_buffer = utils::buffer_mut_no_copy(self.0.command_queue.device(), buffer);
         self.0.encode_scale_stage(&mut input_buffer);
         self.0.encode_butterfly_stages(&mut input_buffer);
@@ -98,7 +137,7 @@ impl<'a, F: GpuField> GpuIfft<'a, F> {
     pub const MIN_SIZE: usize = 2048;
 
     pub fn encode(&mut self, input: &mut GpuVec<F>) {
-        assert_eq!(self.0.n, input.len());
+        assert_eq!(self.0.plan.n, input.len());
         let mut input_buffer = utils::buffer_mut_no_copy(self.0.command_queue.device(), input);
         self.0.encode_butterfly_stages(&mut input_buffer);
         self.0.encode_bit_reverse_stage(&mut input_buffer);
@@ -118,9 +157,69 @@ impl<'a, F: GpuField> From<Radix2EvaluationDomain<F::FftField>> for GpuIfft<'a,
 
 pub static PLANNER: Lazy<Planner> = Lazy::new(Planner::default);
 
+static DEVICE_PLANNERS: Lazy<RwLock<Vec<&'static Planner>>> =
+    Lazy::new(|| RwLock::new(vec![&*PLANNER]));
+
+/// Planners created by [set_devices] keyed by device index. Planners are
+/// reused so calling [set_devices] again doesn't allocate new ones.
+static PLANNERS_BY_INDEX: Lazy<Mutex<HashMap<usize, &'static Planner>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Error returned by [set_devices] if there's no device at an index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoDeviceError(pub usize);
+
+impl std::fmt::Display for NoDeviceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no device at index {}", self.0)
+    }
+}
+
+impl std::error::Error for NoDeviceError {}
+
+/// Splits GPU work across the devices at `indices` of [metal::Device::all].
+/// Only the system default device is used if this is never called. The
+/// devices in use are left unchanged if any index is invalid.
+pub fn set_devices(indices: &[usize]) -> Result<(), NoDeviceError> {
+    assert!(!indices.is_empty(), "at least one device is required");
+    let devices = metal::Device::all();
+    if let Some(&i) = indices.iter().find(|&&i| i >= devices.len()) {
+        return Err(NoDeviceError(i));
+    }
+    let mut planners_by_index = PLANNERS_BY_INDEX.lock().unwrap();
+    let planners = indices
+        .iter()
+        .map(|&i| {
+            // planners live for the rest of the program
+            *planners_by_index
+                .entry(i)
+                .or_insert_with(|| Box::leak(Box::new(Planner::new(&devices[i]))))
+        })
+        .collect();
+    *DEVICE_PLANNERS.write().unwrap() = planners;
+    Ok(())
+}
+
+/// Returns true if there's a default Metal device for [PLANNER] to use
+pub fn has_gpu_device() -> bool {
+    metal::Device::system_default().is_some()
+}
+
+/// Returns a planner for each device set with [set_devices]
+pub fn device_planners() -> Vec<&'static Planner> {
+    DEVICE_PLANNERS.read().unwrap().clone()
+}
+
 pub struct Planner {
     pub library: metal::Library,
     pub command_queue: Arc<metal::CommandQueue>,
+    /// [FftPlans] of each field keyed by the field's type
+    fft_plans: Mutex<HashMap<TypeId, Box<dyn Any + Send>>>,
+    /// Compiled pipelines keyed by kernel name and function constants
+    pipelines: Mutex<HashMap<(String, Vec<u32>), metal::ComputePipelineState>>,
+    /// Pipelines compiled from runtime generated sources keyed by source,
+    /// kernel name and function constants
+    source_pipelines: Mutex<HashMap<(String, String, Vec<u32>), metal::ComputePipelineState>>,
 }
 
 unsafe impl Send for Planner {}
@@ -133,7 +232,105 @@ impl Planner {
         Self {
             library,
             command_queue,
+            fft_plans: Mutex::new(HashMap::new()),
+            pipelines: Mutex::new(HashMap::new()),
+            source_pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the compute pipeline of kernel `name` specialized with `u32`
+    /// function constants at indices `0..constants.len()`. Compiling a
+    /// pipeline takes milliseconds so they're compiled on first use and kept
+    /// for as long as the planner lives. Returns `None` if the library has no
+    /// kernel called `name`.
+    pub fn pipeline(&self, name: &str, constants: &[u32]) -> Option<metal::ComputePipelineState> {
+        let key = (name.to_string(), constants.to_vec());
+        let mut pipelines = self.pipelines.lock().unwrap();
+        if let Some(pipeline) = pipelines.get(&key) {
+            return Some(pipeline.clone());
+        }
+
+        let func = self
+            .library
+            .get_function(name, function_constant_values(constants))
+            .ok()?;
+        let pipeline = self
+            .library
+            .device()
+            .new_compute_pipeline_state_with_function(&func)
+            .unwrap();
+        pipelines.insert(key, pipeline.clone());
+        Some(pipeline)
+    }
+
+    /// Compiles kernel `name` from Metal `source` generated at runtime. The
+    /// field headers are included before the source. Like [Planner::pipeline]
+    /// the result is kept so each source is only compiled once. Returns the
+    /// compiler's message if the source doesn't compile.
+    pub fn pipeline_from_source(
+        &self,
+        source: &str,
+        name: &str,
+        constants: &[u32],
+    ) -> Result<metal::ComputePipelineState, String> {
+        let key = (source.to_string(), name.to_string(), constants.to_vec());
+        let mut pipelines = self.source_pipelines.lock().unwrap();
+        if let Some(pipeline) = pipelines.get(&key) {
+            return Ok(pipeline.clone());
+        }
+
+        let mut full_source = "#include <metal_stdlib>\nusing namespace metal;\n".to_string();
+        for header in FIELD_HEADERS {
+            // headers are concatenated so local includes are dropped
+            for line in header
+                .lines()
+                .filter(|line| !line.starts_with("#include \""))
+            {
+                full_source.push_str(line);
+                full_source.push('\n');
+            }
         }
+        full_source.push_str(source);
+
+        let device = self.library.device();
+        let library =
+            device.new_library_with_source(&full_source, &metal::CompileOptions::new())?;
+        let func = library.get_function(name, function_constant_values(constants))?;
+        let pipeline = device.new_compute_pipeline_state_with_function(&func)?;
+        pipelines.insert(key, pipeline.clone());
+        Ok(pipeline)
+    }
+
+    /// Number of distinct pipelines compiled by this planner
+    pub fn num_pipelines(&self) -> usize {
+        self.pipelines.lock().unwrap().len() + self.source_pipelines.lock().unwrap().len()
+    }
+
+    /// Drops the FFT plans built so far. A plan holds the twiddles and
+    /// pipelines of a domain and is kept for as long as the planner lives so
+    /// that repeated FFTs over the same domain, e.g. one per proof in a
+    /// proving service, skip setup. Processes that see many different domains
+    /// can call this to release the memory.
+    pub fn clear_fft_plans(&self) {
+        self.fft_plans.lock().unwrap().clear();
+    }
+
+    /// Returns the cached plan for the domain or builds a new one
+    fn fft_plan<F: GpuField>(
+        &self,
+        direction: FftDirection,
+        domain: Radix2EvaluationDomain<F::FftField>,
+    ) -> Arc<FftPlan<F>> {
+        let mut fft_plans = self.fft_plans.lock().unwrap();
+        let plans = fft_plans
+            .entry(TypeId::of::<F>())
+            .or_insert_with(|| Box::new(FftPlans::<F>::new()))
+            .downcast_mut::<FftPlans<F>>()
+            .unwrap();
+        let plan = plans
+            .entry((direction, domain))
+            .or_insert_with(|| Arc::new(self.create_fft_plan(direction, domain)));
+        Arc::clone(plan)
     }
 
     pub fn plan_fft<F: GpuField>(&self, domain: Radix2EvaluationDomain<F::FftField>) -> GpuFft<F> {
@@ -149,12 +346,23 @@ impl Planner {
         GpuIfft(self.create_fft_encoder(FftDirection::Inverse, domain))
     }
 
-    // TODO: move to FftEncoder struct
     fn create_fft_encoder<F: GpuField>(
         &self,
         direction: FftDirection,
         domain: Radix2EvaluationDomain<F::FftField>,
     ) -> FftEncoder<F> {
+        FftEncoder {
+            plan: self.fft_plan(direction, domain),
+            command_queue: Arc::clone(&self.command_queue),
+            command_buffer: self.command_queue.new_command_buffer(),
+        }
+    }
+
+    fn create_fft_plan<F: GpuField>(
+        &self,
+        direction: FftDirection,
+        domain: Radix2EvaluationDomain<F::FftField>,
+    ) -> FftPlan<F> {
         let n = domain.size();
         let device = self.command_queue.device();
 
@@ -171,7 +379,7 @@ impl Planner {
         let twiddles_buffer = utils::buffer_mut_no_copy(device, &mut _twiddles);
 
         // in-place FFT requires a bit reversal
-        let bit_reverse_stage = BitReverseGpuStage::new(&self.library, n);
+        let bit_reverse_stage = BitReverseGpuStage::new(self, n);
 
         // scale and normalise
         let scale_and_normalize_stage = if direction == FftDirection::Forward {
@@ -179,8 +387,7 @@ impl Planner {
                 None
             } else {
                 Some(ScaleAndNormalizeGpuStage::new(
-                    &self.library,
-                    &self.command_queue,
+                    self,
                     n,
                     domain.offset.into(),
                     F::one(),
@@ -188,8 +395,7 @@ impl Planner {
             }
         } else {
             Some(ScaleAndNormalizeGpuStage::new(
-                &self.library,
-                &self.command_queue,
+                self,
                 n,
                 domain.offset_inv.into(),
                 domain.size_inv.into(),
@@ -209,7 +415,7 @@ impl Planner {
             };
 
             butterfly_stages.push(FftGpuStage::new(
-                &self.library,
+                self,
                 n,
                 1 << stage,
                 variant,
@@ -221,15 +427,13 @@ impl Planner {
             }
         }
 
-        FftEncoder {
+        FftPlan {
             n,
             _twiddles,
             twiddles_buffer,
             scale_and_normalize_stage,
             butterfly_stages,
             bit_reverse_stage,
-            command_queue: Arc::clone(&self.command_queue),
-            command_buffer: self.command_queue.new_command_buffer(),
         }
     }
 }
@@ -239,3 +443,20 @@ impl Default for Planner {
         Planner::new(&metal::Device::system_default().expect("no device found"))
     }
 }
+
+/// Function constants of type `u32` at indices `0..constants.len()`
+fn function_constant_values(constants: &[u32]) -> Option<metal::FunctionConstantValues> {
+    if constants.is_empty() {
+        return None;
+    }
+
+    let values = metal::FunctionConstantValues::new();
+    for (i, constant) in constants.iter().enumerate() {
+        values.set_constant_value_at_index(
+            utils::void_ptr(constant),
+            metal::MTLDataType::UInt,
+            i as u64,
+        );
+    }
+    Some(values)
+}
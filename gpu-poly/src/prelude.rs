@@ -1,15 +1,29 @@
+pub use crate::allocator::set_allocation_mode;
+pub use crate::allocator::AllocationMode;
 pub use crate::allocator::PageAlignedAllocator;
 #[cfg(target_arch = "aarch64")]
+pub use crate::plan::device_planners;
+#[cfg(target_arch = "aarch64")]
+pub use crate::plan::has_gpu_device;
+#[cfg(target_arch = "aarch64")]
+pub use crate::plan::set_devices;
+#[cfg(target_arch = "aarch64")]
 pub use crate::plan::GpuFft;
 #[cfg(target_arch = "aarch64")]
 pub use crate::plan::GpuIfft;
 #[cfg(target_arch = "aarch64")]
+pub use crate::plan::NoDeviceError;
+#[cfg(target_arch = "aarch64")]
 pub use crate::plan::PLANNER;
 #[cfg(target_arch = "aarch64")]
 pub use crate::stage::AddAssignStage;
 #[cfg(target_arch = "aarch64")]
+pub use crate::stage::BatchInverseStage;
+#[cfg(target_arch = "aarch64")]
 pub use crate::stage::FillBuffStage;
 #[cfg(target_arch = "aarch64")]
+pub use crate::stage::GeneratedKernelStage;
+#[cfg(target_arch = "aarch64")]
 pub use crate::stage::MulPowStage;
 #[cfg(target_arch = "aarch64")]
 pub use crate::utils::buffer_mut_no_copy;
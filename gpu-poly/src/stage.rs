@@ -2,6 +2,7 @@
 
 use super::GpuField;
 use crate::allocator::PageAlignedAllocator;
+use crate::plan::Planner;
 use crate::utils::buffer_no_copy;
 use crate::utils::distribute_powers;
 use crate::utils::void_ptr;
@@ -39,13 +40,12 @@ pub struct FftGpuStage<E> {
 
 impl<F: GpuField> FftGpuStage<F> {
     pub fn new(
-        library: &metal::LibraryRef,
+        planner: &Planner,
         n: usize,
         num_boxes: usize,
         variant: Variant,
         threadgroup_fft_size: usize,
     ) -> FftGpuStage<F> {
-        use metal::MTLDataType::UInt;
         assert!(n.is_power_of_two());
         assert!(num_boxes.is_power_of_two());
         assert!(threadgroup_fft_size.is_power_of_two());
@@ -53,19 +53,11 @@ impl<F: GpuField> FftGpuStage<F> {
         assert!((2048..=1073741824).contains(&n));
 
         // Create the compute pipeline
-        let fft_constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        let num_boxes = num_boxes as u32;
         let tg_fft_size = threadgroup_fft_size as u32;
-        fft_constants.set_constant_value_at_index(void_ptr(&n), UInt, 0);
-        fft_constants.set_constant_value_at_index(void_ptr(&num_boxes), UInt, 1);
-        fft_constants.set_constant_value_at_index(void_ptr(&tg_fft_size), UInt, 2);
-        let func = library
-            .get_function(&fft_kernel_name::<F>(variant), Some(fft_constants))
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
+        let constants = [n, num_boxes as u32, tg_fft_size];
+        let pipeline = planner
+            .pipeline(&fft_kernel_name::<F>(variant), &constants)
             .unwrap();
         let max_threadgroup_threads = pipeline.max_total_threads_per_threadgroup();
         assert!(threadgroup_fft_size / 2 <= max_threadgroup_threads as usize);
@@ -115,27 +107,17 @@ pub struct ScaleAndNormalizeGpuStage<F> {
 }
 
 impl<F: GpuField> ScaleAndNormalizeGpuStage<F> {
-    pub fn new(
-        library: &metal::LibraryRef,
-        command_queue: &metal::CommandQueue,
-        n: usize,
-        scale_factor: F,
-        norm_factor: F,
-    ) -> Self {
+    pub fn new(planner: &Planner, n: usize, scale_factor: F, norm_factor: F) -> Self {
         // Create the compute pipeline
         let kernel_name = format!("mul_assign_LHS_{}_RHS_{}", F::field_name(), F::field_name());
-        let func = library.get_function(&kernel_name, None).unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = planner.pipeline(&kernel_name, &[]).unwrap();
 
         let mut _scale_factors = Vec::with_capacity_in(n, PageAlignedAllocator);
         _scale_factors.resize(n, norm_factor);
         if !scale_factor.is_one() {
             distribute_powers(&mut _scale_factors, scale_factor);
         }
-        let scale_factors_buffer = buffer_no_copy(command_queue.device(), &_scale_factors);
+        let scale_factors_buffer = buffer_no_copy(planner.command_queue.device(), &_scale_factors);
 
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
         let grid_dim = metal::MTLSize::new(n.try_into().unwrap(), 1, 1);
@@ -174,25 +156,15 @@ pub struct BitReverseGpuStage<F> {
 }
 
 impl<F: GpuField> BitReverseGpuStage<F> {
-    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
-        use metal::MTLDataType::UInt;
+    pub fn new(planner: &Planner, n: usize) -> Self {
         assert!(n.is_power_of_two());
         assert!((2048..=1073741824).contains(&n));
 
         // Create the compute pipeline
-        let fft_constants = metal::FunctionConstantValues::new();
         let n = n as u32;
         let num_boxes = 5u32;
-        fft_constants.set_constant_value_at_index(void_ptr(&n), UInt, 0);
-        fft_constants.set_constant_value_at_index(void_ptr(&num_boxes), UInt, 1);
         let kernel_name = format!("bit_reverse_{}", F::field_name());
-        let func = library
-            .get_function(&kernel_name, Some(fft_constants))
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = planner.pipeline(&kernel_name, &[n, num_boxes]).unwrap();
 
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
         let grid_dim = metal::MTLSize::new(n.try_into().unwrap(), 1, 1);
@@ -230,21 +202,15 @@ impl<LhsF: GpuField, RhsF: GpuField> MulPowStage<LhsF, RhsF>
 where
     LhsF: GpuMulAssign<RhsF>,
 {
-    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+    pub fn new(planner: &Planner, n: usize) -> Self {
         // Create the compute pipeline
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(void_ptr(&n), metal::MTLDataType::UInt, 0);
         let kernel_name = format!(
             "mul_pow_LHS_{}_RHS_{}",
             LhsF::field_name(),
             RhsF::field_name()
         );
-        let func = library.get_function(&kernel_name, Some(constants)).unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let pipeline = planner.pipeline(&kernel_name, &[n]).unwrap();
 
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
         let grid_dim = metal::MTLSize::new(n.try_into().unwrap(), 1, 1);
@@ -287,18 +253,10 @@ pub struct AddAssignStage<F> {
 }
 
 impl<F: GpuField> AddAssignStage<F> {
-    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+    pub fn new(planner: &Planner, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(
-                &format!("add_assign_LHS_{}_RHS_{}", F::field_name(), F::field_name()),
-                None,
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let kernel_name = format!("add_assign_LHS_{}_RHS_{}", F::field_name(), F::field_name());
+        let pipeline = planner.pipeline(&kernel_name, &[]).unwrap();
 
         let n = n as u32;
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
@@ -337,15 +295,10 @@ pub struct FillBuffStage<F> {
 }
 
 impl<F: GpuField> FillBuffStage<F> {
-    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+    pub fn new(planner: &Planner, n: usize) -> Self {
         // Create the compute pipeline
-        let func = library
-            .get_function(&format!("fill_buff_{}", F::field_name()), None)
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let kernel_name = format!("fill_buff_{}", F::field_name());
+        let pipeline = planner.pipeline(&kernel_name, &[]).unwrap();
 
         let n = n as u32;
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
@@ -375,6 +328,63 @@ impl<F: GpuField> FillBuffStage<F> {
     }
 }
 
+/// Inverts values in place. Only some fields have a kernel.
+pub struct BatchInverseStage<F> {
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: GpuField> BatchInverseStage<F> {
+    /// Number of values each GPU thread inverts with a single field inversion
+    pub const CHUNK_SIZE: usize = 64;
+
+    /// Returns `None` if the field has no batch inversion kernel or `n` isn't
+    /// a multiple of [Self::CHUNK_SIZE].
+    pub fn new(planner: &Planner, n: usize) -> Option<Self> {
+        if n == 0 || n % Self::CHUNK_SIZE != 0 {
+            return None;
+        }
+
+        // Create the compute pipeline
+        let kernel_name = format!("batch_inverse_{}", F::field_name());
+        let pipeline = planner.pipeline(&kernel_name, &[])?;
+
+        let num_threads = (n / Self::CHUNK_SIZE) as u64;
+        let threadgroup_dim = metal::MTLSize::new(std::cmp::min(num_threads, 1024), 1, 1);
+        let grid_dim = metal::MTLSize::new(num_threads, 1, 1);
+
+        Some(BatchInverseStage {
+            threadgroup_dim,
+            pipeline,
+            grid_dim,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn encode(
+        &self,
+        command_buffer: &metal::CommandBufferRef,
+        values_buffer: &mut metal::BufferRef,
+        scratch_buffer: &mut metal::BufferRef,
+    ) {
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        command_encoder.set_buffer(0, Some(values_buffer), 0);
+        command_encoder.set_buffer(1, Some(scratch_buffer), 0);
+        let chunk_size = Self::CHUNK_SIZE as u32;
+        command_encoder.set_bytes(
+            2,
+            size_of::<u32>().try_into().unwrap(),
+            void_ptr(&chunk_size),
+        );
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.memory_barrier_with_resources(&[values_buffer]);
+        command_encoder.end_encoding()
+    }
+}
+
 pub struct GenerateTwiddlesStage<F> {
     pipeline: metal::ComputePipelineState,
     threadgroup_dim: metal::MTLSize,
@@ -383,25 +393,11 @@ pub struct GenerateTwiddlesStage<F> {
 }
 
 impl<F: GpuField> GenerateTwiddlesStage<F> {
-    pub fn new(library: &metal::LibraryRef, n: usize) -> Self {
+    pub fn new(planner: &Planner, n: usize) -> Self {
         // Create the compute pipeline
-        let constants = metal::FunctionConstantValues::new();
         let n = n as u32;
-        constants.set_constant_value_at_index(
-            &n as *const u32 as *const std::ffi::c_void,
-            metal::MTLDataType::UInt,
-            0,
-        );
-        let func = library
-            .get_function(
-                &format!("generate_twiddles_{}", F::field_name()),
-                Some(constants),
-            )
-            .unwrap();
-        let pipeline = library
-            .device()
-            .new_compute_pipeline_state_with_function(&func)
-            .unwrap();
+        let kernel_name = format!("generate_twiddles_{}", F::field_name());
+        let pipeline = planner.pipeline(&kernel_name, &[n]).unwrap();
 
         let threadgroup_dim = metal::MTLSize::new(1024, 1, 1);
         let grid_dim = metal::MTLSize::new(n.try_into().unwrap(), 1, 1);
@@ -429,3 +425,52 @@ impl<F: GpuField> GenerateTwiddlesStage<F> {
         command_encoder.end_encoding()
     }
 }
+
+/// Runs a kernel compiled from source generated at runtime with one thread
+/// per row. Function constant `0` is the number of rows.
+pub struct GeneratedKernelStage {
+    pipeline: metal::ComputePipelineState,
+    threadgroup_dim: metal::MTLSize,
+    grid_dim: metal::MTLSize,
+}
+
+impl GeneratedKernelStage {
+    /// Maximum number of buffers a kernel can bind
+    pub const MAX_BUFFERS: usize = 31;
+
+    /// Compiles kernel `name` from `source`. `constants` are the function
+    /// constants after the number of rows `n`.
+    pub fn new(
+        planner: &Planner,
+        source: &str,
+        name: &str,
+        n: usize,
+        constants: &[u32],
+    ) -> Result<Self, String> {
+        let n = n as u32;
+        let constants = [&[n], constants].concat();
+        let pipeline = planner.pipeline_from_source(source, name, &constants)?;
+
+        let threadgroup_size = pipeline.max_total_threads_per_threadgroup().min(n.into());
+        let threadgroup_dim = metal::MTLSize::new(threadgroup_size, 1, 1);
+        let grid_dim = metal::MTLSize::new(n.into(), 1, 1);
+
+        Ok(GeneratedKernelStage {
+            pipeline,
+            threadgroup_dim,
+            grid_dim,
+        })
+    }
+
+    /// Binds `buffers[i]` to buffer index `i`
+    pub fn encode(&self, command_buffer: &metal::CommandBufferRef, buffers: &[metal::Buffer]) {
+        assert!(buffers.len() <= Self::MAX_BUFFERS);
+        let command_encoder = command_buffer.new_compute_command_encoder();
+        command_encoder.set_compute_pipeline_state(&self.pipeline);
+        for (i, buffer) in buffers.iter().enumerate() {
+            command_encoder.set_buffer(i as u64, Some(buffer), 0);
+        }
+        command_encoder.dispatch_threads(self.grid_dim, self.threadgroup_dim);
+        command_encoder.end_encoding()
+    }
+}
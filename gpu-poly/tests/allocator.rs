@@ -0,0 +1,25 @@
+#![feature(allocator_api)]
+
+use gpu_poly::allocator::allocation_mode;
+use gpu_poly::prelude::*;
+
+#[test]
+fn pinned_allocations_are_usable() {
+    set_allocation_mode(AllocationMode::Pinned);
+    assert_eq!(AllocationMode::Pinned, allocation_mode());
+    let mut pinned = Vec::with_capacity_in(1 << 12, PageAlignedAllocator);
+    pinned.extend(0..1u64 << 12);
+    // growing reallocates through the allocator
+    pinned.extend(0..1u64 << 12);
+    set_allocation_mode(AllocationMode::Pageable);
+
+    let mut pageable = pinned.to_vec_in(PageAlignedAllocator);
+    pageable.truncate(1 << 12);
+    drop(pinned);
+
+    assert!(pageable.iter().copied().eq(0..1u64 << 12));
+    assert_eq!(
+        0,
+        pageable.as_ptr() as usize % *gpu_poly::allocator::PAGE_SIZE
+    );
+}
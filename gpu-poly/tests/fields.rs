@@ -2,6 +2,7 @@
 #![feature(allocator_api)]
 
 use ark_ff::UniformRand;
+use gpu_poly::plan::Planner;
 use gpu_poly::prelude::PageAlignedAllocator;
 use gpu_poly::prelude::PLANNER;
 use gpu_poly::stage::MulPowStage;
@@ -43,7 +44,7 @@ pub mod p18446744069414584321 {
             let b_buffer = buffer_no_copy(command_queue.device(), &b);
             let command_buffer = command_queue.new_command_buffer();
 
-            let multiplier = MulPowStage::<Fp>::new(&PLANNER.library, n);
+            let multiplier = MulPowStage::<Fp>::new(&PLANNER, n);
             multiplier.encode(command_buffer, &mut a_buffer, &b_buffer, 1, 0);
             command_buffer.commit();
             command_buffer.wait_until_completed();
@@ -54,6 +55,21 @@ pub mod p18446744069414584321 {
         });
     }
 
+    #[test]
+    fn stages_share_compiled_pipelines() {
+        autoreleasepool(|| {
+            let planner = Planner::default();
+
+            let _ = MulPowStage::<Fp>::new(&planner, 2048);
+            let _ = MulPowStage::<Fp>::new(&planner, 2048);
+            let _ = MulPowStage::<Fq3, Fp>::new(&planner, 2048);
+            assert_eq!(planner.num_pipelines(), 2);
+
+            let _ = MulPowStage::<Fp>::new(&planner, 4096);
+            assert_eq!(planner.num_pipelines(), 3);
+        });
+    }
+
     #[test]
     fn mul_pow_fq3_by_fp() {
         autoreleasepool(|| {
@@ -82,7 +98,7 @@ pub mod p18446744069414584321 {
             let b_buffer = buffer_no_copy(command_queue.device(), &b);
             let command_buffer = command_queue.new_command_buffer();
 
-            let multiplier = MulPowStage::<Fq3, Fp>::new(&PLANNER.library, n);
+            let multiplier = MulPowStage::<Fq3, Fp>::new(&PLANNER, n);
             multiplier.encode(command_buffer, &mut a_buffer, &b_buffer, 1, 0);
             command_buffer.commit();
             command_buffer.wait_until_completed();
@@ -124,7 +140,7 @@ pub mod p18446744069414584321 {
             let b_buffer = buffer_no_copy(command_queue.device(), &b);
             let command_buffer = command_queue.new_command_buffer();
 
-            let multiplier = MulPowStage::<Fq3>::new(&PLANNER.library, n);
+            let multiplier = MulPowStage::<Fq3>::new(&PLANNER, n);
             multiplier.encode(command_buffer, &mut a_buffer, &b_buffer, 3, 0);
             command_buffer.commit();
             command_buffer.wait_until_completed();
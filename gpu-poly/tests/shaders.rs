@@ -86,6 +86,27 @@ fn ifft() {
     });
 }
 
+#[test]
+fn repeated_ffts_share_a_plan() {
+    autoreleasepool(|| {
+        let domain = Radix2EvaluationDomain::new_coset(2048, Fp::GENERATOR).unwrap();
+        let poly = DensePolynomial::rand(domain.size() - 1, &mut ark_std::test_rng());
+        let expected = poly.evaluate_over_domain_by_ref(domain).evals;
+
+        for clear in [false, false, true, false] {
+            if clear {
+                PLANNER.clear_fft_plans();
+            }
+            let mut evals = poly.coeffs.to_vec_in(PageAlignedAllocator);
+            let mut fft = GpuFft::from(domain);
+            fft.encode(&mut evals);
+            fft.execute();
+
+            assert_eq!(expected, evals.to_vec());
+        }
+    });
+}
+
 // TODO: evaluate_over_domain_by_ref needs to support DomainCoeff
 // #[test]
 // fn ifft_with_extension_field() {
@@ -0,0 +1,503 @@
+//! Aggregation proves `N` executions of the same AIR with a single proof.
+//!
+//! The executions are stacked vertically with their rows interleaved. Row
+//! `t` of execution `k` is row `t * N + k` of the aggregate trace so the
+//! aggregate has the width of a single trace and `N` times as many rows. All
+//! executions share one transcript, one set of commitments and one low-degree
+//! proof. Verifying the aggregate costs a single FRI (or STIR) check and
+//! `num_queries` Merkle paths per commitment rather than `N` of each and the
+//! number of trace values opened at each query doesn't grow with `N`.
+//!
+//! Constraints of the executions are moved onto the interleaved rows:
+//!
+//! - transition constraints look `N` rows ahead for each row the execution's
+//!   constraint looks ahead and are exempt on `N` times as many rows
+//! - boundary constraints apply to the first `N` rows and terminal
+//!   constraints apply to the last `N` rows. They are gated by selector
+//!   columns and checked like transition constraints. Terminal constraints of
+//!   the last execution are also terminal constraints of the aggregate since
+//!   transition constraints don't apply to the last row.
+//! - a constraint that differs between executions (e.g. it references a hint
+//!   or a public input) is checked as `Σ sel_k(x) * c_k` where `sel_k` is a
+//!   periodic column that is one on the rows of execution `k`
+//!
+//! Periodic and linear columns of the executions are interleaved into
+//! periodic columns of the aggregate. Challenges are shared by all
+//! executions. Hints are concatenated in execution order. Assertions are moved
+//! to the steps of their execution and lowered by the aggregate. All traces
+//! must have the same length and `N` must be a power of two.
+use crate::assertion::Assertion;
+use crate::challenges::Challenges;
+use crate::constraint::Element;
+use crate::hints::Hints;
+use crate::random::PublicCoin;
+use crate::Air;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::ProofOptions;
+use crate::Prover;
+use crate::Trace;
+use crate::TraceInfo;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use digest::Digest;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuField;
+
+/// `N` traces of the same type with their rows interleaved. The interleaved
+/// base columns are built when the aggregate is created.
+pub struct AggregateTrace<T: Trace, const N: usize> {
+    traces: [T; N],
+    base_columns: Matrix<T::Fp>,
+}
+
+impl<T: Trace, const N: usize> AggregateTrace<T, N> {
+    /// Panics if `N` isn't a power of two or the traces have different
+    /// lengths or metadata
+    pub fn new(traces: [T; N]) -> Self {
+        assert!(N.is_power_of_two(), "must aggregate a power of two traces");
+        let trace_len = traces[0].len();
+        for (i, trace) in traces.iter().enumerate() {
+            assert_eq!(trace.len(), trace_len, "trace {i} has a different length");
+            assert!(
+                trace.meta() == traces[0].meta(),
+                "trace {i} has different metadata"
+            );
+        }
+        let base_columns = interleave(traces.iter().map(T::base_columns).collect());
+        AggregateTrace {
+            traces,
+            base_columns,
+        }
+    }
+
+    pub fn traces(&self) -> &[T; N] {
+        &self.traces
+    }
+}
+
+impl<T: Trace, const N: usize> Trace for AggregateTrace<T, N> {
+    const NUM_BASE_COLUMNS: usize = T::NUM_BASE_COLUMNS;
+    const NUM_EXTENSION_COLUMNS: usize = T::NUM_EXTENSION_COLUMNS;
+    type Fp = T::Fp;
+    type Fq = T::Fq;
+
+    fn len(&self) -> usize {
+        self.base_columns.num_rows()
+    }
+
+    fn meta(&self) -> Option<&[u8]> {
+        self.traces[0].meta()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.traces[0].column_names()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_columns
+    }
+
+    fn build_extension_columns(
+        &self,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        self.build_round_columns(0, challenges)
+    }
+
+    fn build_round_columns(
+        &self,
+        round: usize,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        let columns = self
+            .traces
+            .iter()
+            .map(|trace| trace.build_round_columns(round, challenges))
+            .collect::<Option<Vec<Matrix<Self::Fq>>>>()?;
+        Some(interleave(columns.iter().collect()))
+    }
+}
+
+/// Interleaves the rows of matrices with the same shape. Row `t` of matrix
+/// `k` becomes row `t * matrices.len() + k`.
+fn interleave<F: GpuField>(matrices: Vec<&Matrix<F>>) -> Matrix<F> {
+    let n = matrices.len();
+    let num_rows = matrices[0].num_rows();
+    let columns = (0..matrices[0].num_cols())
+        .map(|i| {
+            let mut column = Vec::with_capacity_in(n * num_rows, PageAlignedAllocator);
+            for row in 0..num_rows {
+                column.extend(matrices.iter().map(|matrix| matrix.0[i][row]));
+            }
+            column
+        })
+        .collect();
+    Matrix::new(columns)
+}
+
+/// AIR for `N` executions of the AIR `A`. Public inputs are the public
+/// inputs of each execution in order.
+pub struct AggregateAir<A: Air, const N: usize> {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    pub_inputs: Vec<A::PublicInputs>,
+    instances: Vec<A>,
+    num_hints: usize,
+    transition_constraints: Vec<Constraint<A::Fq>>,
+    transition_exemptions: Vec<usize>,
+    terminal_constraints: Vec<Constraint<A::Fq>>,
+    periodic_columns: Vec<Vec<A::Fp>>,
+    assertions: Vec<Assertion<A::Fp>>,
+}
+
+impl<A: Air, const N: usize> AggregateAir<A, N> {
+    /// The AIR of each execution
+    pub fn instances(&self) -> &[A] {
+        &self.instances
+    }
+}
+
+impl<A: Air, const N: usize> Air for AggregateAir<A, N> {
+    type Fp = A::Fp;
+    type Fq = A::Fq;
+    type PublicInputs = Vec<A::PublicInputs>;
+
+    fn new(trace_info: TraceInfo, pub_inputs: Vec<A::PublicInputs>, options: ProofOptions) -> Self {
+        assert!(
+            N.is_power_of_two(),
+            "must aggregate a power of two executions"
+        );
+        assert_eq!(
+            pub_inputs.len(),
+            N,
+            "expected public inputs for {N} executions"
+        );
+        // not built with `TraceInfo::new` since that panics on executions
+        // shorter than `TraceInfo::MIN_TRACE_LENGTH`. Only the aggregate's
+        // length is checked by the verifier.
+        let instance_info = TraceInfo {
+            trace_len: trace_info.trace_len / N,
+            ..trace_info.clone()
+        };
+        let instances = pub_inputs
+            .iter()
+            .map(|inputs| A::new(instance_info.clone(), inputs.clone(), options))
+            .collect::<Vec<A>>();
+
+        let num_hints = instances
+            .iter()
+            .flat_map(|instance| instance.all_constraint_elements())
+            .filter_map(|element| match element {
+                Element::Hint(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+
+        // periodic and linear columns of the executions are interleaved. Linear
+        // columns become periodic columns with a period of the execution's
+        // trace length.
+        let instance_columns = instances
+            .iter()
+            .map(|instance| {
+                let trace_domain = instance.trace_domain();
+                let linear_columns = instance.linear_columns().into_iter().map(|(a, b)| {
+                    trace_domain
+                        .elements()
+                        .map(|x| a + b * x)
+                        .collect::<Vec<A::Fp>>()
+                });
+                instance
+                    .periodic_columns()
+                    .into_iter()
+                    .chain(linear_columns)
+                    .collect::<Vec<Vec<A::Fp>>>()
+            })
+            .collect::<Vec<_>>();
+        let num_instance_columns = instance_columns[0].len();
+        let periods = |columns: &Vec<Vec<A::Fp>>| columns.iter().map(Vec::len).collect::<Vec<_>>();
+        assert!(
+            instance_columns
+                .iter()
+                .all(|columns| periods(columns) == periods(&instance_columns[0])),
+            "executions must have the same periodic columns"
+        );
+        let mut periodic_columns = (0..num_instance_columns)
+            .map(|i| {
+                let period = instance_columns[0][i].len();
+                (0..N * period)
+                    .map(|j| instance_columns[j % N][i][j / N])
+                    .collect::<Vec<A::Fp>>()
+            })
+            .collect::<Vec<Vec<A::Fp>>>();
+
+        // selectors of the rows of each execution and of the first and last rows
+        // of all executions
+        let num_trace_columns = trace_info.num_base_columns + trace_info.num_extension_columns;
+        let first_selector = num_trace_columns + periodic_columns.len();
+        for k in 0..N {
+            let mut column = vec![Self::Fp::zero(); N];
+            column[k] = Self::Fp::one();
+            periodic_columns.push(column);
+        }
+        let trace_len = trace_info.trace_len;
+        let mut is_first_rows = vec![Self::Fp::zero(); trace_len];
+        is_first_rows[..N].fill(Self::Fp::one());
+        let mut is_last_rows = vec![Self::Fp::zero(); trace_len];
+        is_last_rows[trace_len - N..].fill(Self::Fp::one());
+        periodic_columns.push(is_first_rows);
+        periodic_columns.push(is_last_rows);
+        let execution_selector = |k: usize| (first_selector + k).curr::<A::Fq>();
+        let is_first_rows = (first_selector + N).curr::<A::Fq>();
+        let is_last_rows = (first_selector + N + 1).curr::<A::Fq>();
+
+        // moves the elements of a constraint of execution `k` to the interleaved
+        // rows
+        let interleave_elements = |k: usize| {
+            move |element: Element| match element {
+                Element::Curr(i) => Element::Curr(i),
+                Element::Next(i) => row_offset(i, N),
+                Element::Offset(i, offset) => row_offset(i, offset * N),
+                Element::Hint(i) => Element::Hint(k * num_hints + i),
+                Element::Challenge(i) => Element::Challenge(i),
+            }
+        };
+        // a single constraint that applies `constraints[k]` to execution `k`
+        let combine = |constraints: Vec<&Constraint<A::Fq>>| {
+            let is_shared = constraints[1..]
+                .iter()
+                .all(|c| c.terms() == constraints[0].terms())
+                && !constraints[0]
+                    .get_elements()
+                    .iter()
+                    .any(|element| matches!(element, Element::Hint(_)));
+            if is_shared {
+                constraints[0].map_elements(interleave_elements(0))
+            } else {
+                constraints.into_iter().enumerate().fold(
+                    Constraint::zero(),
+                    |acc, (k, constraint)| {
+                        acc + execution_selector(k)
+                            * constraint.map_elements(interleave_elements(k))
+                    },
+                )
+            }
+        };
+        let boundary_constraints = instances
+            .iter()
+            .map(A::boundary_constraints)
+            .collect::<Vec<_>>();
+        let transition_constraints = instances
+            .iter()
+            .map(A::transition_constraints)
+            .collect::<Vec<_>>();
+        let terminal_constraints = instances
+            .iter()
+            .map(A::terminal_constraints)
+            .collect::<Vec<_>>();
+        let num_boundary_constraints = boundary_constraints[0].len();
+        let num_transition_constraints = transition_constraints[0].len();
+        let num_terminal_constraints = terminal_constraints[0].len();
+        for constraints in [
+            &boundary_constraints,
+            &transition_constraints,
+            &terminal_constraints,
+        ] {
+            assert!(
+                constraints.iter().all(|c| c.len() == constraints[0].len()),
+                "executions must have the same number of constraints"
+            );
+        }
+
+        let mut air = AggregateAir {
+            options,
+            trace_info,
+            pub_inputs,
+            num_hints,
+            transition_constraints: Vec::new(),
+            transition_exemptions: Vec::new(),
+            terminal_constraints: Vec::new(),
+            periodic_columns,
+            assertions: Vec::new(),
+            instances: Vec::new(),
+        };
+        // an execution's constraint is exempt on its last rows which are the last
+        // `N` times as many rows of the aggregate
+        let exemptions = instances
+            .iter()
+            .map(A::transition_exemptions)
+            .reduce(|a, b| a.into_iter().zip(b).map(|(a, b)| a.max(b)).collect())
+            .unwrap();
+        for j in 0..num_transition_constraints {
+            let constraint = combine(nth_constraints(&transition_constraints, j));
+            air.transition_constraints.push(constraint);
+            air.transition_exemptions.push(exemptions[j] * N);
+        }
+        for j in 0..num_boundary_constraints {
+            let constraint = combine(nth_constraints(&boundary_constraints, j));
+            air.transition_constraints.push(&is_first_rows * constraint);
+            air.transition_exemptions.push(1);
+        }
+        for j in 0..num_terminal_constraints {
+            let constraint = combine(nth_constraints(&terminal_constraints, j));
+            air.transition_constraints.push(&is_last_rows * constraint);
+            air.transition_exemptions.push(1);
+            let last_constraint = &terminal_constraints[N - 1][j];
+            air.terminal_constraints
+                .push(last_constraint.map_elements(interleave_elements(N - 1)));
+        }
+        for (k, instance) in instances.iter().enumerate() {
+            air.assertions.extend(
+                instance
+                    .assertions()
+                    .into_iter()
+                    .map(|assertion| interleave_assertion(assertion, k, N)),
+            );
+        }
+        air.instances = instances;
+        air
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.pub_inputs
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn domain_offset(&self) -> Self::Fp {
+        self.instances[0].domain_offset()
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.terminal_constraints
+    }
+
+    fn transition_exemptions(&self) -> Vec<usize> {
+        self.transition_exemptions.clone()
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<Self::Fp>> {
+        self.periodic_columns.clone()
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Self::Fp>> {
+        self.assertions.clone()
+    }
+
+    fn get_challenges(&self, public_coin: &mut PublicCoin<impl Digest>) -> Challenges<Self::Fq> {
+        self.instances[0].get_challenges(public_coin)
+    }
+
+    fn num_interaction_rounds(&self) -> usize {
+        self.instances[0].num_interaction_rounds()
+    }
+
+    fn num_round_extension_columns(&self, round: usize) -> usize {
+        self.instances[0].num_round_extension_columns(round)
+    }
+
+    fn get_round_challenges(
+        &self,
+        round: usize,
+        public_coin: &mut PublicCoin<impl Digest>,
+    ) -> Challenges<Self::Fq> {
+        self.instances[0].get_round_challenges(round, public_coin)
+    }
+
+    fn get_hints(&self, challenges: &Challenges<Self::Fq>) -> Hints<Self::Fq> {
+        let num_hints = self.num_hints;
+        let mut hints = Vec::with_capacity(N * num_hints);
+        for (k, instance) in self.instances.iter().enumerate() {
+            let instance_hints = instance.get_hints(challenges);
+            for i in 0..num_hints {
+                let hint = instance_hints
+                    .get(i)
+                    .copied()
+                    .unwrap_or_else(Self::Fq::zero);
+                hints.push((k * num_hints + i, hint));
+            }
+        }
+        Hints::new(hints)
+    }
+}
+
+/// Constraint `j` of each execution
+fn nth_constraints<'a, F>(constraints: &[&'a [Constraint<F>]], j: usize) -> Vec<&'a Constraint<F>> {
+    constraints.iter().map(|c| &c[j]).collect()
+}
+
+/// Column `column` on the row `offset` rows after the current row
+fn row_offset(column: usize, offset: usize) -> Element {
+    match offset {
+        0 => Element::Curr(column),
+        1 => Element::Next(column),
+        _ => Element::Offset(column, offset),
+    }
+}
+
+/// Moves an assertion of execution `k` of `n` interleaved executions to the
+/// steps of the execution
+fn interleave_assertion<F>(assertion: Assertion<F>, k: usize, n: usize) -> Assertion<F> {
+    match assertion {
+        Assertion::Single {
+            column,
+            step,
+            value,
+        } => Assertion::single(column, step * n + k, value),
+        Assertion::Periodic {
+            column,
+            first_step,
+            stride,
+            value,
+        } => Assertion::periodic(column, first_step * n + k, stride * n, value),
+        Assertion::Sequence {
+            column,
+            first_step,
+            stride,
+            values,
+        } => Assertion::sequence(column, first_step * n + k, stride * n, values),
+    }
+}
+
+/// Proves `N` traces with the prover `P` in a single proof
+pub struct AggregateProver<P: Prover, const N: usize>(pub P);
+
+impl<P: Prover, const N: usize> Prover for AggregateProver<P, N> {
+    type Fp = P::Fp;
+    type Fq = P::Fq;
+    type Air = AggregateAir<P::Air, N>;
+    type Trace = AggregateTrace<P::Trace, N>;
+
+    fn new(options: ProofOptions) -> Self {
+        AggregateProver(P::new(options))
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0.options()
+    }
+
+    fn max_memory(&self) -> Option<usize> {
+        self.0.max_memory()
+    }
+
+    fn get_pub_inputs(&self, trace: &Self::Trace) -> Vec<<P::Air as Air>::PublicInputs> {
+        trace
+            .traces
+            .iter()
+            .map(|trace| self.0.get_pub_inputs(trace))
+            .collect()
+    }
+}
@@ -1,3 +1,5 @@
+use crate::assertion::Assertion;
+use crate::assertion::AssertionConstraints;
 use crate::challenges::Challenges;
 use crate::composer::DeepCompositionCoeffs;
 use crate::constraint::Element;
@@ -6,14 +8,15 @@ use crate::random::PublicCoin;
 use crate::utils;
 use crate::utils::fill_vanishing_polynomial;
 use crate::Constraint;
+use crate::Matrix;
+use crate::ProofHeader;
 use crate::ProofOptions;
+use crate::ProvingError;
 use crate::StarkExtensionOf;
 use crate::TraceInfo;
-use ark_ff::batch_inversion;
 use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::One;
-use ark_ff::UniformRand;
 use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
 use ark_poly::Radix2EvaluationDomain;
@@ -41,6 +44,14 @@ pub trait Air {
 
     fn options(&self) -> &ProofOptions;
 
+    /// Identifies the AIR in proof headers. Defaults to the
+    /// [ProofHeader::id] of the AIR's type name so proofs are rejected if the
+    /// type is renamed or moved. Override with a fixed value to keep proofs
+    /// verifiable across such changes.
+    fn air_id() -> u64 {
+        ProofHeader::id(std::any::type_name::<Self>().as_bytes())
+    }
+
     fn domain_offset(&self) -> Self::Fp {
         Self::Fp::GENERATOR
     }
@@ -53,7 +64,7 @@ pub trait Air {
     /// Must be a power of two.
     fn ce_blowup_factor(&self) -> usize {
         let max_boundary_constraint_degree = self
-            .boundary_constraints()
+            .all_boundary_constraints()
             .iter()
             .map(|constraint| constraint.degree())
             .max()
@@ -61,28 +72,33 @@ pub trait Air {
         let boundary_ce_blowup_factor = utils::ceil_power_of_two(max_boundary_constraint_degree);
 
         let max_terminal_constraint_degree = self
-            .terminal_constraints()
+            .all_terminal_constraints()
             .iter()
             .map(|constraint| constraint.degree())
             .max()
             .unwrap_or(0);
         let terminal_ce_blowup_factor = utils::ceil_power_of_two(max_terminal_constraint_degree);
 
-        let max_transition_constraint_degree = self
-            .transition_constraints()
-            .iter()
-            .map(|constraint| constraint.degree())
-            .max()
-            .unwrap_or(0);
         // TODO: improve explanation of why we negate these constraint degrees by 1
         // Transition constraints must evaluate to zero in all execution trace rows
-        // except the last. These rows are divided out from the transition constraint
-        // evaluations which has the effect of reducing the overall degree of the
-        // transition constraint evaluations by `trace_len - 1`. Therefore the
+        // except their last `num_exempt_rows` rows. These rows are divided out from
+        // the transition constraint evaluations which has the effect of reducing the
+        // overall degree of the transition constraint evaluations by
+        // `trace_len - num_exempt_rows`. With the usual exemption of one row the
         // total constraint evaluation degree is `constraint_degree * (trace_len - 1) -
         // (trace_len - 1) = (constraint_degree - 1) * (trace_len - 1)`
+        let trace_len = self.trace_len();
+        let transition_evaluation_degree = self
+            .all_transition_constraints()
+            .iter()
+            .map(|(constraint, num_exempt_rows)| {
+                (constraint.degree() * (trace_len - 1)).saturating_sub(trace_len - num_exempt_rows)
+            })
+            .max()
+            .unwrap_or(0);
+        // smallest blowup factor `b` such that `transition_evaluation_degree < b * trace_len`
         let transition_ce_blowup_factor =
-            utils::ceil_power_of_two(max_transition_constraint_degree.saturating_sub(1));
+            utils::ceil_power_of_two((transition_evaluation_degree + trace_len) / trace_len);
 
         [
             transition_ce_blowup_factor,
@@ -94,6 +110,48 @@ pub trait Air {
         .unwrap()
     }
 
+    /// Number of columns the composition polynomial is split into. Must be a
+    /// power of two no larger than [Air::ce_blowup_factor]. Fewer columns
+    /// means fewer values are opened at each query but the DEEP composition
+    /// polynomial has a higher degree (see [Air::deep_composition_len]) so the
+    /// low-degree test runs with a smaller blowup factor.
+    fn num_composition_columns(&self) -> usize {
+        self.ce_blowup_factor()
+    }
+
+    /// Number of coefficients of the DEEP composition polynomial. This is the
+    /// length of each composition column which is at least the trace length.
+    fn deep_composition_len(&self) -> usize {
+        self.trace_len() * self.ce_blowup_factor() / self.num_composition_columns()
+    }
+
+    /// Blowup factor of the low-degree test i.e. the LDE domain size divided
+    /// by [Air::deep_composition_len]
+    fn ldt_blowup_factor(&self) -> usize {
+        self.trace_len() * self.lde_blowup_factor() / self.deep_composition_len()
+    }
+
+    /// Returns the largest row offset referenced by a transition constraint.
+    /// Always at least one.
+    fn max_row_offset(&self) -> usize {
+        self.transition_constraints()
+            .iter()
+            .map(|constraint| constraint.max_row_offset())
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    /// Number of rows at the end of the trace that each transition constraint
+    /// doesn't apply to. In the same order as [Air::transition_constraints].
+    /// A constraint must be exempt on at least one row and at least as many
+    /// rows as its largest row offset. Larger exemptions suit constraints
+    /// that don't hold on the padding of a table. By default all constraints
+    /// are exempt on the last [Air::max_row_offset] rows.
+    fn transition_exemptions(&self) -> Vec<usize> {
+        vec![self.max_row_offset(); self.transition_constraints().len()]
+    }
+
     /// Returns a degree that all constraints polynomials must be normalized to.
     fn composition_degree(&self) -> usize {
         let trace_len = self.trace_len();
@@ -105,15 +163,66 @@ pub trait Air {
         self.options().lde_blowup_factor as usize
     }
 
-    /// Validate properties of this air
-    fn validate(&self) {
+    /// Validate properties of this air. Constraints of too high a degree for
+    /// the proof options are an error. Other problems are bugs in the AIR and
+    /// panic.
+    fn validate(&self) -> Result<(), ProvingError> {
         let ce_blowup_factor = self.ce_blowup_factor();
         let lde_blowup_factor = self.lde_blowup_factor();
+        if ce_blowup_factor > lde_blowup_factor {
+            return Err(ProvingError::DegreeOverflow {
+                ce_blowup_factor,
+                lde_blowup_factor,
+            });
+        }
+        let num_composition_columns = self.num_composition_columns();
+        assert!(
+            num_composition_columns.is_power_of_two()
+                && num_composition_columns <= ce_blowup_factor,
+            "number of composition columns {num_composition_columns} must be a power of two that
+            is at most the constraint evaluation blowup factor {ce_blowup_factor}"
+        );
+        let ldt_blowup_factor = self.ldt_blowup_factor();
         assert!(
-            ce_blowup_factor <= lde_blowup_factor,
-            "constraint evaluation blowup factor {ce_blowup_factor} is 
-            larger than the lde blowup factor {lde_blowup_factor}"
+            ldt_blowup_factor >= ProofOptions::MIN_BLOWUP_FACTOR.into(),
+            "low-degree test blowup factor {ldt_blowup_factor} is too small. Use more
+            composition columns or a larger lde blowup factor"
         );
+        let trace_len = self.trace_len();
+        let transition_exemptions = self.transition_exemptions();
+        assert_eq!(
+            transition_exemptions.len(),
+            self.transition_constraints().len(),
+            "expected an exemption for every transition constraint"
+        );
+        for (i, (constraint, num_exempt_rows)) in self
+            .transition_constraints()
+            .iter()
+            .zip(transition_exemptions)
+            .enumerate()
+        {
+            let min_exempt_rows = constraint.max_row_offset().max(1);
+            assert!(
+                (min_exempt_rows..trace_len).contains(&num_exempt_rows),
+                "transition constraint {i} is exempt on {num_exempt_rows} rows. Must be at least
+                {min_exempt_rows} and less than the trace length {trace_len}"
+            );
+        }
+        for (i, column) in self.periodic_columns().iter().enumerate() {
+            let period = column.len();
+            assert!(
+                period.is_power_of_two() && period <= trace_len,
+                "periodic column {i} has period {period} which must be a power of two that is
+                at most the trace length {trace_len}"
+            );
+        }
+        for (i, assertion) in self.assertions().iter().enumerate() {
+            assert!(
+                assertion.fits_trace(self.trace_info()),
+                "assertion {i} is not on a trace column or has steps outside the trace"
+            );
+        }
+        Ok(())
     }
 
     fn trace_domain(&self) -> Radix2EvaluationDomain<Self::Fp> {
@@ -149,10 +258,153 @@ pub trait Air {
         &[]
     }
 
-    fn transition_constraint_divisor(&self) -> Divisor<Self::Fp> {
+    /// Boundary constraints followed by the boundary constraints of
+    /// [Air::assertion_constraints]
+    fn all_boundary_constraints(&self) -> Vec<Constraint<Self::Fq>> {
+        let assertion_constraints = self.assertion_constraints().boundary_constraints;
+        [self.boundary_constraints(), &assertion_constraints].concat()
+    }
+
+    /// Transition constraints and their number of exempt rows followed by the
+    /// transition constraints of [Air::assertion_constraints]. Assertions only
+    /// reference the current row so their constraints are exempt on the last
+    /// row.
+    fn all_transition_constraints(&self) -> Vec<(Constraint<Self::Fq>, usize)> {
+        let assertion_constraints = self.assertion_constraints().transition_constraints;
+        self.transition_constraints()
+            .iter()
+            .cloned()
+            .zip(self.transition_exemptions())
+            .chain(assertion_constraints.into_iter().map(|c| (c, 1)))
+            .collect()
+    }
+
+    /// Terminal constraints followed by the terminal constraints of
+    /// [Air::assertion_constraints]
+    fn all_terminal_constraints(&self) -> Vec<Constraint<Self::Fq>> {
+        let assertion_constraints = self.assertion_constraints().terminal_constraints;
+        [self.terminal_constraints(), &assertion_constraints].concat()
+    }
+
+    /// Columns of public values that repeat every `period` rows where `period`
+    /// is the length of the column. Periodic columns aren't committed to. Both
+    /// the prover and verifier derive them from the AIR. In constraints they
+    /// are indexed after the execution trace columns i.e. periodic column `i`
+    /// has index `num_base_columns + num_extension_columns + i`.
+    fn periodic_columns(&self) -> Vec<Vec<Self::Fp>> {
+        Vec::new()
+    }
+
+    /// Periodic columns repeated over all rows of the execution trace
+    fn periodic_column_trace(&self) -> Option<Matrix<Self::Fp>> {
+        repeat_periodic_columns(self.periodic_columns(), self.trace_len())
+    }
+
+    /// Evaluations of the periodic columns over the LDE domain
+    fn periodic_column_lde(&self) -> Option<Matrix<Self::Fp>> {
+        let trace_domain = self.trace_domain();
+        let lde_domain = self.lde_domain();
+        self.periodic_column_trace().map(|trace| {
+            trace
+                .into_polynomials(trace_domain)
+                .into_evaluations(lde_domain)
+        })
+    }
+
+    /// Evaluates the periodic columns at `x`. A column with period `p` is
+    /// interpolated over a domain of size `p` which gives a polynomial `q`.
+    /// The column's polynomial over the trace domain is `q(x^(trace_len / p))`.
+    fn evaluate_periodic_columns_at(&self, x: Self::Fq) -> Vec<Self::Fq> {
+        evaluate_periodic_columns(self.periodic_columns(), self.trace_len(), x)
+    }
+
+    /// Columns of public values that are a polynomial of degree at most one in
+    /// the row's trace domain element `x` i.e. column `(a, b)` has value
+    /// `a + b * x`. Constant columns have `b = 0`. Like periodic columns they
+    /// aren't committed to. The prover evaluates them over the LDE domain
+    /// directly rather than interpolating and the verifier evaluates them at
+    /// the out-of-domain point. In constraints they are indexed after the
+    /// periodic columns i.e. linear column `i` has index `num_base_columns +
+    /// num_extension_columns + num_periodic_columns + i`.
+    fn linear_columns(&self) -> Vec<(Self::Fp, Self::Fp)> {
+        Vec::new()
+    }
+
+    /// Linear columns over all rows of the execution trace
+    fn linear_column_trace(&self) -> Option<Matrix<Self::Fp>> {
+        let trace_domain = self.trace_domain();
+        evaluate_linear_columns(&self.linear_columns(), trace_domain)
+    }
+
+    /// Evaluations of the linear columns over the LDE domain
+    fn linear_column_lde(&self) -> Option<Matrix<Self::Fp>> {
+        let lde_domain = self.lde_domain();
+        evaluate_linear_columns(&self.linear_columns(), lde_domain)
+    }
+
+    /// Evaluates the linear columns at `x`
+    fn evaluate_linear_columns_at(&self, x: Self::Fq) -> Vec<Self::Fq> {
+        self.linear_columns()
+            .into_iter()
+            .map(|(a, b)| x * b + a)
+            .collect()
+    }
+
+    /// Assertions that execution trace values equal public values. These
+    /// usually come from the public inputs e.g. the input and output of a
+    /// program. The prover and verifier both lower them with
+    /// [Air::assertion_constraints] and enforce the result alongside the
+    /// AIR's own constraints so tying the trace to public inputs doesn't need
+    /// hints or hand written boundary constraints.
+    fn assertions(&self) -> Vec<Assertion<Self::Fp>> {
+        Vec::new()
+    }
+
+    /// Constraints and periodic columns that enforce [Air::assertions]. The
+    /// periodic columns of the lowering are indexed after the linear columns.
+    fn assertion_constraints(&self) -> AssertionConstraints<Self::Fp, Self::Fq> {
+        let trace_info = self.trace_info();
+        let first_periodic_column = trace_info.num_base_columns
+            + trace_info.num_extension_columns
+            + self.periodic_columns().len()
+            + self.linear_columns().len();
+        AssertionConstraints::new(&self.assertions(), self.trace_len(), first_periodic_column)
+    }
+
+    /// Periodic columns of [Air::assertion_constraints] repeated over all rows
+    /// of the execution trace
+    fn assertion_column_trace(&self) -> Option<Matrix<Self::Fp>> {
+        let columns = self.assertion_constraints().periodic_columns;
+        repeat_periodic_columns(columns, self.trace_len())
+    }
+
+    /// Evaluations of the periodic columns of [Air::assertion_constraints]
+    /// over the LDE domain
+    fn assertion_column_lde(&self) -> Option<Matrix<Self::Fp>> {
+        let trace_domain = self.trace_domain();
+        let lde_domain = self.lde_domain();
+        self.assertion_column_trace().map(|trace| {
+            trace
+                .into_polynomials(trace_domain)
+                .into_evaluations(lde_domain)
+        })
+    }
+
+    /// Evaluates the periodic columns of [Air::assertion_constraints] at `x`
+    fn evaluate_assertion_columns_at(&self, x: Self::Fq) -> Vec<Self::Fq> {
+        let columns = self.assertion_constraints().periodic_columns;
+        evaluate_periodic_columns(columns, self.trace_len(), x)
+    }
+
+    /// Divisor of transition constraints that are exempt on the last
+    /// `num_exempt_rows` rows
+    fn transition_constraint_divisor(&self, num_exempt_rows: usize) -> Divisor<Self::Fp> {
         let trace_domain = self.trace_domain();
-        let last_trace_x = trace_domain.group_gen_inv;
-        let degree = trace_domain.size() - 1;
+        // the last `num_exempt_rows` elements of the trace domain
+        let excluded_trace_xs = (1..=num_exempt_rows)
+            .map(|i| trace_domain.group_gen_inv.pow([i as u64]))
+            .collect::<Vec<Self::Fp>>();
+        let degree = trace_domain.size() - num_exempt_rows;
         let lde_domain = self.lde_domain();
         let n = lde_domain.size();
 
@@ -164,11 +416,11 @@ pub trait Air {
 
         // invert the vanishing polynomial evaluations
         // i.e. evaluations of `1 / (x - t_0)(x - t_1)...(x - t_n-1)`
-        batch_inversion(&mut lde);
+        utils::batch_inverse_gpu_vec(&mut lde);
 
-        // transition constraints apply to all rows except the last
-        // multiplies out the last term of the vanishing polynomial
-        // i.e. evaluations of `1 / (x - t_0)(x - t_1)...(x - t_n-2)`
+        // transition constraints apply to all rows except the last `num_exempt_rows`
+        // rows. Multiplies out the last terms of the vanishing polynomial
+        // i.e. evaluations of `1 / (x - t_0)(x - t_1)...(x - t_n-2)` for an offset of 1
         // Note: `t^(n-1) = t^(-1)`
         #[cfg(feature = "parallel")]
         let chunk_size = std::cmp::max(n / rayon::current_num_threads(), 1024);
@@ -179,7 +431,9 @@ pub trait Air {
             .for_each(|(i, chunk)| {
                 let mut lde_x = lde_domain.element(i * chunk_size);
                 chunk.iter_mut().for_each(|coeff| {
-                    *coeff *= lde_x - last_trace_x;
+                    for &trace_x in &excluded_trace_xs {
+                        *coeff *= lde_x - trace_x;
+                    }
                     lde_x *= &lde_domain.group_gen
                 })
             });
@@ -212,7 +466,7 @@ pub trait Air {
 
         // invert the evaluations
         // i.e. evaluations of `1 / (x - t_0)`
-        batch_inversion(&mut lde);
+        utils::batch_inverse_gpu_vec(&mut lde);
 
         Divisor { lde, degree: 1 }
     }
@@ -243,7 +497,7 @@ pub trait Air {
 
         // invert the evaluations
         // i.e. evaluations of `1 / (x - t_n-1)`
-        batch_inversion(&mut lde);
+        utils::batch_inverse_gpu_vec(&mut lde);
 
         Divisor { lde, degree: 1 }
     }
@@ -264,8 +518,41 @@ pub trait Air {
         if num_challenges == 0 {
             Challenges::default()
         } else {
-            let mut rng = public_coin.draw_rng();
-            Challenges::new(&mut rng, num_challenges)
+            Challenges::draw(public_coin, num_challenges)
+        }
+    }
+
+    /// Number of rounds of interaction between the prover and verifier. Each
+    /// round the verifier draws challenges and the prover commits to extension
+    /// columns built from the challenges of that round and all earlier rounds.
+    /// Arguments like LogUp-GKR need more than one round.
+    fn num_interaction_rounds(&self) -> usize {
+        1
+    }
+
+    /// Number of extension columns committed to in an interaction round. The
+    /// columns of a round follow the columns of the previous round. All
+    /// extension columns are committed to in the first round by default.
+    fn num_round_extension_columns(&self, round: usize) -> usize {
+        if round == 0 {
+            self.trace_info().num_extension_columns
+        } else {
+            0
+        }
+    }
+
+    /// Draws the challenges of an interaction round. Challenges are indexed
+    /// after the challenges of earlier rounds. All challenges are drawn in the
+    /// first round by default.
+    fn get_round_challenges(
+        &self,
+        round: usize,
+        public_coin: &mut PublicCoin<impl Digest>,
+    ) -> Challenges<Self::Fq> {
+        if round == 0 {
+            self.get_challenges(public_coin)
+        } else {
+            Challenges::default()
         }
     }
 
@@ -273,101 +560,144 @@ pub trait Air {
         Hints::default()
     }
 
-    // TODO: make this generic
+    /// Returns true if `z` can be used as the out-of-domain point. Constraint
+    /// divisors aren't defined if `z` is in the trace domain and DEEP quotients
+    /// aren't defined if `z^num_composition_columns` or any point `z * g^k` of the
+    /// evaluation frame are in the LDE domain (`g` is the generator of the
+    /// trace domain).
+    fn is_valid_ood_point(&self, z: Self::Fq) -> bool {
+        let trace_domain = self.trace_domain();
+        let lde_domain = self.lde_domain();
+        let in_trace_domain = |x: Self::Fq| x.pow([trace_domain.size]).is_one();
+        let in_lde_domain = |x: Self::Fq| {
+            let x = x * Self::Fq::from(lde_domain.offset_inv);
+            x.pow([lde_domain.size]).is_one()
+        };
+        let g = Self::Fq::from(trace_domain.group_gen);
+        let z_n = z.pow([self.num_composition_columns() as u64]);
+        let mut frame_z = z;
+        for _ in 0..=self.max_row_offset() {
+            if in_lde_domain(frame_z) {
+                return false;
+            }
+            frame_z *= g;
+        }
+        !z.is_zero() && !in_trace_domain(z) && !in_lde_domain(z_n)
+    }
+
+    /// Draws the out-of-domain point. Points that aren't valid (see
+    /// [Air::is_valid_ood_point]) are rejected and a new point is drawn.
+    fn get_ood_point(&self, public_coin: &mut PublicCoin<impl Digest>) -> Self::Fq {
+        loop {
+            let z = public_coin.draw::<Self::Fq>();
+            if self.is_valid_ood_point(z) {
+                return z;
+            }
+        }
+    }
+
     fn get_constraint_composition_coeffs(
         &self,
         public_coin: &mut PublicCoin<impl Digest>,
     ) -> Vec<(Self::Fq, Self::Fq)> {
-        let mut rng = public_coin.draw_rng();
         (0..self.num_constraints())
-            .map(|_| (Self::Fq::rand(&mut rng), Self::Fq::rand(&mut rng)))
+            .map(|_| {
+                (
+                    public_coin.draw::<Self::Fq>(),
+                    public_coin.draw::<Self::Fq>(),
+                )
+            })
             .collect()
     }
 
-    // TODO: make this generic
     /// Output is of the form `(trace_coeffs, composition_coeffs,
     /// degree_adjustment_coeffs)`
     fn get_deep_composition_coeffs(
         &self,
         public_coin: &mut PublicCoin<impl Digest>,
     ) -> DeepCompositionCoeffs<Self::Fq> {
-        let mut rng = public_coin.draw_rng();
-
-        // execution trace coeffs
+        // execution trace coeffs. One for each row of the evaluation frame
         let trace_info = self.trace_info();
+        let frame_size = self.max_row_offset() + 1;
         let mut base_trace_coeffs = Vec::new();
         for _ in 0..trace_info.num_base_columns {
-            base_trace_coeffs.push((
-                Self::Fq::rand(&mut rng),
-                Self::Fq::rand(&mut rng),
-                Self::Fq::rand(&mut rng),
-            ));
+            base_trace_coeffs.push(
+                (0..frame_size)
+                    .map(|_| public_coin.draw::<Self::Fq>())
+                    .collect(),
+            );
         }
 
         let mut extension_trace_coeffs = Vec::new();
         for _ in 0..trace_info.num_extension_columns {
-            extension_trace_coeffs.push((
-                Self::Fq::rand(&mut rng),
-                Self::Fq::rand(&mut rng),
-                Self::Fq::rand(&mut rng),
-            ));
+            extension_trace_coeffs.push(
+                (0..frame_size)
+                    .map(|_| public_coin.draw::<Self::Fq>())
+                    .collect(),
+            );
         }
 
         // composition trace coeffs
-        let num_composition_trace_cols = self.ce_blowup_factor();
+        let num_composition_trace_cols = self.num_composition_columns();
         let mut composition_trace_coeffs = Vec::new();
         for _ in 0..num_composition_trace_cols {
-            composition_trace_coeffs.push(Self::Fq::rand(&mut rng));
+            composition_trace_coeffs.push(public_coin.draw::<Self::Fq>());
         }
 
         DeepCompositionCoeffs {
             base_trace: base_trace_coeffs,
             extension_trace: extension_trace_coeffs,
             constraints: composition_trace_coeffs,
-            degree: (Self::Fq::rand(&mut rng), Self::Fq::rand(&mut rng)),
+            degree: (
+                public_coin.draw::<Self::Fq>(),
+                public_coin.draw::<Self::Fq>(),
+            ),
         }
     }
 
     fn all_constraint_elements(&self) -> Vec<Element> {
         // TODO: change get_challenge_indices to a constraint iterator and extract the
         // constraint with the highest index
-        let mut indicies: Vec<Element> = [
-            self.boundary_constraints(),
-            self.transition_constraints(),
-            self.terminal_constraints(),
-        ]
-        .into_iter()
-        .flatten()
-        .flat_map(|constraint| constraint.get_elements())
-        .collect();
+        let transition_constraints = self.all_transition_constraints();
+        let mut indicies: Vec<Element> = self
+            .all_boundary_constraints()
+            .iter()
+            .chain(
+                transition_constraints
+                    .iter()
+                    .map(|(constraint, _)| constraint),
+            )
+            .chain(&self.all_terminal_constraints())
+            .flat_map(|constraint| constraint.get_elements())
+            .collect();
         indicies.sort();
         indicies.dedup();
         indicies
     }
 
-    #[cfg(debug_assertions)]
+    /// Checks the execution trace satisfies every constraint. The prover only
+    /// runs this in debug builds since it evaluates each constraint on every
+    /// row. Tests can run it directly with `test_utils::check_constraints`
+    /// which is enabled with the `test-utils` feature.
     fn validate_constraints(
         &self,
         challenges: &Challenges<Self::Fq>,
         hints: &Hints<Self::Fq>,
-        base_trace: &crate::Matrix<Self::Fp>,
-        extension_trace: Option<&crate::Matrix<Self::Fq>>,
-    ) {
-        use crate::matrix::GroupItem;
-        use crate::matrix::MatrixGroup;
-
-        let mut execution_trace = MatrixGroup::new(vec![GroupItem::Fp(base_trace)]);
-        if let Some(extension_trace) = extension_trace.as_ref() {
-            execution_trace.append(GroupItem::Fq(extension_trace))
-        }
+        base_trace: &Matrix<Self::Fp>,
+        extension_trace: Option<&Matrix<Self::Fq>>,
+    ) -> Result<(), ProvingError> {
+        let trace_rows = execution_trace_rows(self, base_trace, extension_trace);
+        let num_cols = trace_rows.first().map_or(0, Vec::len);
 
-        let mut col_indicies = vec![false; execution_trace.num_cols()];
+        let mut col_indicies = vec![false; num_cols];
         let mut challenge_indicies = vec![false; challenges.len()];
         let mut hint_indicies = vec![false; hints.len()];
 
         for element in self.all_constraint_elements() {
             match element {
-                Element::Curr(i) | Element::Next(i) => col_indicies[i] = true,
+                Element::Curr(i) | Element::Next(i) | Element::Offset(i, _) => {
+                    col_indicies[i] = true
+                }
                 Element::Challenge(i) => challenge_indicies[i] = true,
                 Element::Hint(i) => hint_indicies[i] = true,
             }
@@ -376,7 +706,8 @@ pub trait Air {
         for (index, exists) in col_indicies.into_iter().enumerate() {
             if !exists {
                 // TODO: make assertion
-                println!("WARN: no constraints for column {index}");
+                let name = self.trace_info().column_name(index);
+                println!("WARN: no constraints for column {index} ({name})");
             }
         }
 
@@ -394,37 +725,113 @@ pub trait Air {
             }
         }
 
-        let trace_rows = execution_trace.rows();
+        // names of the columns a constraint references for failure messages
+        let column_names = |constraint: &Constraint<Self::Fq>| {
+            let mut columns = constraint
+                .get_elements()
+                .into_iter()
+                .filter_map(|element| match element {
+                    Element::Curr(i) | Element::Next(i) | Element::Offset(i, _) => Some(i),
+                    Element::Challenge(_) | Element::Hint(_) => None,
+                })
+                .collect::<Vec<usize>>();
+            columns.sort();
+            columns.dedup();
+            columns
+                .into_iter()
+                .map(|i| self.trace_info().column_name(i))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+
         let first_row = trace_rows.first().unwrap();
         let last_row = trace_rows.last().unwrap();
 
         // check boundary constraints
-        for (i, constraint) in self.boundary_constraints().iter().enumerate() {
+        for (i, constraint) in self.all_boundary_constraints().iter().enumerate() {
             let eval = constraint.evaluate(challenges, hints, first_row, &[]);
-            assert!(eval.is_zero(), "boundary {i} mismatch");
+            if !eval.is_zero() {
+                return Err(ProvingError::UnsatisfiedConstraint {
+                    kind: "boundary",
+                    constraint: i,
+                    row: 0,
+                    columns: column_names(constraint),
+                });
+            }
         }
 
         // check terminal constraints
-        for (i, constraint) in self.terminal_constraints().iter().enumerate() {
+        for (i, constraint) in self.all_terminal_constraints().iter().enumerate() {
             let eval = constraint.evaluate(challenges, hints, last_row, &[]);
-            assert!(eval.is_zero(), "terminal {i} mismatch");
+            if !eval.is_zero() {
+                return Err(ProvingError::UnsatisfiedConstraint {
+                    kind: "terminal",
+                    constraint: i,
+                    row: trace_rows.len() - 1,
+                    columns: column_names(constraint),
+                });
+            }
         }
 
-        // check transition constraints
-        for (i, [curr, next]) in trace_rows.array_windows::<2>().enumerate() {
-            for (j, constraint) in self.transition_constraints().iter().enumerate() {
-                let eval = constraint.evaluate(challenges, hints, curr, next);
-                assert!(eval.is_zero(), "transition {j} mismatch at row {i}");
+        // check transition constraints on the rows they aren't exempt on
+        for (j, (constraint, num_exempt_rows)) in
+            self.all_transition_constraints().into_iter().enumerate()
+        {
+            let frame_size = constraint.max_row_offset() + 1;
+            for i in 0..trace_rows.len() - num_exempt_rows {
+                let frame = trace_rows[i..i + frame_size]
+                    .iter()
+                    .map(|row| row.as_slice())
+                    .collect::<Vec<_>>();
+                let eval = constraint.evaluate_frame(challenges, hints, &frame);
+                if !eval.is_zero() {
+                    return Err(ProvingError::UnsatisfiedConstraint {
+                        kind: "transition",
+                        constraint: j,
+                        row: i,
+                        columns: column_names(&constraint),
+                    });
+                }
             }
         }
+
+        Ok(())
     }
 
     fn num_constraints(&self) -> usize {
-        //Vec<(Self::Fp, Self::Fp)> {
-        self.boundary_constraints().len()
-            + self.transition_constraints().len()
-            + self.terminal_constraints().len()
+        self.all_boundary_constraints().len()
+            + self.all_transition_constraints().len()
+            + self.all_terminal_constraints().len()
+    }
+}
+
+/// Rows of the execution trace followed by the periodic, linear and assertion
+/// columns i.e. every column constraints can reference
+pub(crate) fn execution_trace_rows<A: Air + ?Sized>(
+    air: &A,
+    base_trace: &Matrix<A::Fp>,
+    extension_trace: Option<&Matrix<A::Fq>>,
+) -> Vec<Vec<A::Fq>> {
+    use crate::matrix::GroupItem;
+    use crate::matrix::MatrixGroup;
+
+    let mut execution_trace = MatrixGroup::new(vec![GroupItem::Fp(base_trace)]);
+    if let Some(extension_trace) = extension_trace {
+        execution_trace.append(GroupItem::Fq(extension_trace))
     }
+    let periodic_trace = air.periodic_column_trace();
+    if let Some(periodic_trace) = periodic_trace.as_ref() {
+        execution_trace.append(GroupItem::Fp(periodic_trace))
+    }
+    let linear_trace = air.linear_column_trace();
+    if let Some(linear_trace) = linear_trace.as_ref() {
+        execution_trace.append(GroupItem::Fp(linear_trace))
+    }
+    let assertion_trace = air.assertion_column_trace();
+    if let Some(assertion_trace) = assertion_trace.as_ref() {
+        execution_trace.append(GroupItem::Fp(assertion_trace))
+    }
+    execution_trace.rows()
 }
 
 pub struct Divisor<F> {
@@ -439,3 +846,52 @@ impl<F: GpuField> Deref for Divisor<F> {
         &self.lde
     }
 }
+
+/// Repeats each periodic column over `trace_len` rows
+fn repeat_periodic_columns<F: GpuField>(
+    columns: Vec<Vec<F>>,
+    trace_len: usize,
+) -> Option<Matrix<F>> {
+    let columns = columns
+        .into_iter()
+        .map(|values| {
+            let mut column = Vec::with_capacity_in(trace_len, PageAlignedAllocator);
+            column.extend(values.iter().cycle().take(trace_len));
+            column
+        })
+        .collect::<Vec<_>>();
+    (!columns.is_empty()).then(|| Matrix::new(columns))
+}
+
+/// Evaluates periodic columns of a trace of length `trace_len` at `x`
+fn evaluate_periodic_columns<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+    columns: Vec<Vec<Fp>>,
+    trace_len: usize,
+    x: Fq,
+) -> Vec<Fq> {
+    columns
+        .into_iter()
+        .map(|mut coeffs| {
+            let period = coeffs.len();
+            let domain = Radix2EvaluationDomain::<Fp>::new(period).unwrap();
+            domain.ifft_in_place(&mut coeffs);
+            utils::horner_evaluate(&coeffs, &x.pow([(trace_len / period) as u64]))
+        })
+        .collect()
+}
+
+/// Evaluates linear columns `(a, b)` at every element `x` of `domain`
+fn evaluate_linear_columns<F: GpuFftField>(
+    columns: &[(F, F)],
+    domain: Radix2EvaluationDomain<F>,
+) -> Option<Matrix<F>> {
+    let columns = columns
+        .iter()
+        .map(|&(a, b)| {
+            let mut column = Vec::with_capacity_in(domain.size(), PageAlignedAllocator);
+            column.extend(domain.elements().map(|x| a + b * x));
+            column
+        })
+        .collect::<Vec<_>>();
+    (!columns.is_empty()).then(|| Matrix::new(columns))
+}
@@ -0,0 +1,245 @@
+//! Assertions on execution trace values.
+//!
+//! An assertion states that a column equals some value at a set of steps.
+//! Ministark only has boundary (first row) and terminal (last row)
+//! constraints so assertions on any other steps are lowered to transition constraints gated by periodic selector columns:
+//!
+//! ```text
+//! single:   col[s] = v                  =>  sel(x) * (col - v)
+//! periodic: col[s + k * stride] = v     =>  sel(x) * (col - v)
+//! sequence: col[s + k * stride] = v_k   =>  sel(x) * col - val(x)
+//! ```
+//!
+//! `sel` is a periodic column that is one on the asserted steps and zero
+//! everywhere else and `val` is a periodic column holding `v_k` on the asserted
+//! steps. Transition constraints don't apply to the last row so an assertion
+//! that includes the last step also adds a terminal constraint.
+//!
+//! AIRs usually return their assertions from
+//! [Air::assertions](crate::Air::assertions) which has the prover and verifier
+//! lower and enforce them alongside the AIR's own constraints.
+use crate::Column;
+use crate::Constraint;
+use crate::StarkExtensionOf;
+use crate::TraceInfo;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::GpuFftField;
+use std::collections::BTreeMap;
+
+/// An assertion that a column of the execution trace equals some value(s)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Assertion<F> {
+    /// `column` equals `value` at `step`
+    Single {
+        column: usize,
+        step: usize,
+        value: F,
+    },
+    /// `column` equals `value` at steps `first_step + k * stride`
+    Periodic {
+        column: usize,
+        first_step: usize,
+        stride: usize,
+        value: F,
+    },
+    /// `column` equals `values[k]` at steps `first_step + k * stride`. There
+    /// must be one value for every `stride` steps of the trace.
+    Sequence {
+        column: usize,
+        first_step: usize,
+        stride: usize,
+        values: Vec<F>,
+    },
+}
+
+impl<F> Assertion<F> {
+    pub fn single(column: usize, step: usize, value: F) -> Self {
+        Assertion::Single {
+            column,
+            step,
+            value,
+        }
+    }
+
+    pub fn periodic(column: usize, first_step: usize, stride: usize, value: F) -> Self {
+        assert!(stride.is_power_of_two(), "stride must be a power of two");
+        assert!(
+            first_step < stride,
+            "first step must be less than the stride"
+        );
+        Assertion::Periodic {
+            column,
+            first_step,
+            stride,
+            value,
+        }
+    }
+
+    pub fn sequence(column: usize, first_step: usize, stride: usize, values: Vec<F>) -> Self {
+        assert!(stride.is_power_of_two(), "stride must be a power of two");
+        assert!(
+            first_step < stride,
+            "first step must be less than the stride"
+        );
+        assert!(!values.is_empty(), "sequence must have at least one value");
+        Assertion::Sequence {
+            column,
+            first_step,
+            stride,
+            values,
+        }
+    }
+
+    pub fn column(&self) -> usize {
+        match self {
+            Assertion::Single { column, .. }
+            | Assertion::Periodic { column, .. }
+            | Assertion::Sequence { column, .. } => *column,
+        }
+    }
+
+    /// Moves the assertion to column `f(column)`
+    pub fn map_column(mut self, f: impl FnOnce(usize) -> usize) -> Self {
+        match &mut self {
+            Assertion::Single { column, .. }
+            | Assertion::Periodic { column, .. }
+            | Assertion::Sequence { column, .. } => *column = f(*column),
+        }
+        self
+    }
+
+    /// Returns true if the assertion is on an execution trace column and its
+    /// steps are within the trace. Assertions that don't fit can't be lowered
+    /// to constraints.
+    pub fn fits_trace(&self, trace_info: &TraceInfo) -> bool {
+        let num_columns = trace_info.num_base_columns + trace_info.num_extension_columns;
+        let trace_len = trace_info.trace_len;
+        self.column() < num_columns
+            && match self {
+                Assertion::Single { step, .. } => *step < trace_len,
+                Assertion::Periodic { stride, .. } => *stride <= trace_len,
+                Assertion::Sequence { stride, values, .. } => {
+                    values.len().checked_mul(*stride) == Some(trace_len)
+                }
+            }
+    }
+}
+
+/// Constraints and periodic columns that enforce a set of assertions
+#[derive(Clone, Debug)]
+pub struct AssertionConstraints<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>> {
+    pub boundary_constraints: Vec<Constraint<Fq>>,
+    pub transition_constraints: Vec<Constraint<Fq>>,
+    pub terminal_constraints: Vec<Constraint<Fq>>,
+    /// Selector and value columns. Must be returned from
+    /// [Air::periodic_columns](crate::Air::periodic_columns) starting at
+    /// periodic column index `first_periodic_column` (relative to the
+    /// execution trace columns) that was passed to [Self::new].
+    pub periodic_columns: Vec<Vec<Fp>>,
+}
+
+impl<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>> AssertionConstraints<Fp, Fq> {
+    /// Lowers assertions on a trace of length `trace_len` to constraints.
+    /// `first_periodic_column` is the column index the first periodic column
+    /// created by the lowering has in constraints i.e. the number of execution
+    /// trace columns plus any periodic columns the AIR already has.
+    ///
+    /// Assertions that share steps share a selector column.
+    pub fn new(
+        assertions: &[Assertion<Fp>],
+        trace_len: usize,
+        first_periodic_column: usize,
+    ) -> Self {
+        assert!(
+            trace_len.is_power_of_two(),
+            "trace length must be a power of two"
+        );
+        let last_step = trace_len - 1;
+        let mut res = AssertionConstraints {
+            boundary_constraints: Vec::new(),
+            transition_constraints: Vec::new(),
+            terminal_constraints: Vec::new(),
+            periodic_columns: Vec::new(),
+        };
+        // maps `(first_step, stride)` to the column index of its selector
+        let mut selectors = BTreeMap::new();
+        let mut selector = |res: &mut Self, first_step: usize, stride: usize| {
+            *selectors.entry((first_step, stride)).or_insert_with(|| {
+                let mut column = vec![Fp::zero(); stride];
+                column[first_step] = Fp::one();
+                res.periodic_columns.push(column);
+                first_periodic_column + res.periodic_columns.len() - 1
+            })
+        };
+
+        for assertion in assertions {
+            match assertion {
+                &Assertion::Single {
+                    column,
+                    step,
+                    value,
+                } => {
+                    assert!(step < trace_len, "step {step} is outside the trace");
+                    let constraint = column.curr() - Fq::from(value);
+                    if step == 0 {
+                        res.boundary_constraints.push(constraint);
+                    } else if step == last_step {
+                        res.terminal_constraints.push(constraint);
+                    } else {
+                        let selector = selector(&mut res, step, trace_len);
+                        res.transition_constraints
+                            .push(selector.curr() * constraint);
+                    }
+                }
+                &Assertion::Periodic {
+                    column,
+                    first_step,
+                    stride,
+                    value,
+                } => {
+                    assert!(
+                        stride <= trace_len,
+                        "stride {stride} exceeds the trace length"
+                    );
+                    let constraint = column.curr() - Fq::from(value);
+                    if first_step == stride - 1 {
+                        res.terminal_constraints.push(constraint.clone());
+                    }
+                    let selector = selector(&mut res, first_step, stride);
+                    res.transition_constraints
+                        .push(selector.curr() * constraint);
+                }
+                Assertion::Sequence {
+                    column,
+                    first_step,
+                    stride,
+                    values,
+                } => {
+                    let (column, first_step, stride) = (*column, *first_step, *stride);
+                    assert_eq!(
+                        values.len() * stride,
+                        trace_len,
+                        "expected {} values for a stride of {stride}",
+                        trace_len / stride
+                    );
+                    if first_step == stride - 1 {
+                        let last_value = Fq::from(*values.last().unwrap());
+                        res.terminal_constraints.push(column.curr() - last_value);
+                    }
+                    let selector = selector(&mut res, first_step, stride);
+                    let mut value_column = vec![Fp::zero(); trace_len];
+                    for (i, value) in values.iter().enumerate() {
+                        value_column[first_step + i * stride] = *value;
+                    }
+                    res.periodic_columns.push(value_column);
+                    let value_column = first_periodic_column + res.periodic_columns.len() - 1;
+                    res.transition_constraints
+                        .push(selector.curr() * column.curr() - value_column.curr());
+                }
+            }
+        }
+
+        res
+    }
+}
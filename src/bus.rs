@@ -0,0 +1,173 @@
+//! Declarative communication between tables of an execution trace.
+//!
+//! A [Bus] is a multiset of tuples. Tables send tuples to the bus and receive
+//! tuples from the bus on rows chosen by a selector. Each interaction gets an
+//! extension column with a running product over its rows:
+//!
+//! ```text
+//! p[0] = 1
+//! p[i+1] = p[i] * (1 - s[i] + s[i] * (z - Σ_k α_k * v_k[i]))
+//! ```
+//!
+//! The terminal constraint checks the product of all sends equals the
+//! product of all receives which (with high probability over `z` and `α`)
+//! means the multisets of sent and received tuples are equal.
+use crate::challenges::Challenges;
+use crate::constraint::Challenge;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+
+/// Tuples a table sends to or receives from a bus
+#[derive(Clone, Debug)]
+pub struct Interaction<F: GpuField> {
+    /// One on rows that take part in the interaction and zero otherwise. Can
+    /// only reference base and periodic columns on the current row.
+    pub selector: Constraint<F>,
+    /// Elements of the tuple. Can only reference base and periodic columns on
+    /// the current row.
+    pub values: Vec<Constraint<F>>,
+}
+
+impl<F: GpuField> Interaction<F> {
+    pub fn new(selector: Constraint<F>, values: Vec<Constraint<F>>) -> Self {
+        Interaction { selector, values }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Bus<F: GpuField> {
+    sends: Vec<Interaction<F>>,
+    receives: Vec<Interaction<F>>,
+    /// Index of the first of the bus's extension columns. Extension columns
+    /// are indexed after all base columns.
+    extension_offset: usize,
+    /// Index of the first of the bus's challenges
+    challenge_offset: usize,
+}
+
+impl<F: GpuField> Bus<F> {
+    pub fn new(extension_offset: usize, challenge_offset: usize) -> Self {
+        Bus {
+            sends: Vec::new(),
+            receives: Vec::new(),
+            extension_offset,
+            challenge_offset,
+        }
+    }
+
+    pub fn with_send(mut self, interaction: Interaction<F>) -> Self {
+        self.sends.push(interaction);
+        self
+    }
+
+    pub fn with_receive(mut self, interaction: Interaction<F>) -> Self {
+        self.receives.push(interaction);
+        self
+    }
+
+    /// Number of extension columns used by the bus. One per interaction.
+    pub fn num_extension_columns(&self) -> usize {
+        self.sends.len() + self.receives.len()
+    }
+
+    /// Number of challenges used by the bus
+    pub fn num_challenges(&self) -> usize {
+        let max_width = self
+            .interactions()
+            .map(|interaction| interaction.values.len())
+            .max()
+            .unwrap_or(0);
+        1 + max_width
+    }
+
+    /// Sends followed by receives. Interaction `i` uses extension column
+    /// `extension_offset + i`.
+    fn interactions(&self) -> impl Iterator<Item = &Interaction<F>> {
+        self.sends.iter().chain(&self.receives)
+    }
+
+    /// Factor a row contributes to the running product of an interaction
+    fn factor(&self, interaction: &Interaction<F>) -> Constraint<F> {
+        let mut term = self.challenge_offset.get_challenge::<F>();
+        for (i, value) in interaction.values.iter().enumerate() {
+            term -= (self.challenge_offset + 1 + i).get_challenge::<F>() * value;
+        }
+        let selector = &interaction.selector;
+        Constraint::from(F::one()) - selector + selector * term
+    }
+
+    pub fn boundary_constraints(&self) -> Vec<Constraint<F>> {
+        (0..self.num_extension_columns())
+            .map(|i| (self.extension_offset + i).curr() - F::one())
+            .collect()
+    }
+
+    pub fn transition_constraints(&self) -> Vec<Constraint<F>> {
+        self.interactions()
+            .enumerate()
+            .map(|(i, interaction)| {
+                let column = self.extension_offset + i;
+                column.next() - column.curr() * self.factor(interaction)
+            })
+            .collect()
+    }
+
+    pub fn terminal_constraints(&self) -> Vec<Constraint<F>> {
+        let mut columns = self.extension_offset..;
+        let mut side_product = |interactions: &[Interaction<F>]| {
+            interactions
+                .iter()
+                .fold(Constraint::from(F::one()), |acc, interaction| {
+                    acc * columns.next().unwrap().curr() * self.factor(interaction)
+                })
+        };
+        let sent = side_product(&self.sends);
+        let received = side_product(&self.receives);
+        vec![sent - received]
+    }
+
+    /// Generates the running product columns of the bus. `base_columns` are
+    /// all base columns of the trace and periodic columns are at indices
+    /// `periodic_offset..`.
+    pub fn gen_extension_columns<Fp: GpuFftField>(
+        &self,
+        base_columns: &Matrix<Fp>,
+        periodic_columns: &[Vec<Fp>],
+        periodic_offset: usize,
+        challenges: &Challenges<F>,
+    ) -> Matrix<F>
+    where
+        F: StarkExtensionOf<Fp>,
+    {
+        let num_rows = base_columns.num_rows();
+        let factors = self
+            .interactions()
+            .map(|interaction| self.factor(interaction))
+            .collect::<Vec<Constraint<F>>>();
+        let mut products = vec![F::one(); factors.len()];
+        let mut columns = (0..factors.len())
+            .map(|_| Vec::with_capacity_in(num_rows, PageAlignedAllocator))
+            .collect::<Vec<_>>();
+        let mut row = vec![F::zero(); periodic_offset + periodic_columns.len()];
+        for i in 0..num_rows {
+            for (j, column) in base_columns.iter().enumerate() {
+                row[j] = F::from(column[i]);
+            }
+            for (j, column) in periodic_columns.iter().enumerate() {
+                row[periodic_offset + j] = F::from(column[i % column.len()]);
+            }
+            for ((column, product), factor) in columns.iter_mut().zip(&mut products).zip(&factors) {
+                column.push(*product);
+                *product *= factor.evaluate(challenges, &[], &row, &[]);
+            }
+        }
+        Matrix::new(columns)
+    }
+}
@@ -0,0 +1,133 @@
+//! Generates Cairo 1 code that evaluates an AIR's constraints.
+//!
+//! This is a constraint evaluator generator, not a verifier generator. Given
+//! the out-of-domain frame, challenges and hints the generated functions
+//! return each constraint's evaluation. Nothing else a StarkNet verifier
+//! contract needs is generated: there's no transcript (ministark's transcript
+//! uses the proof's hash function rather than the Poseidon or Pedersen
+//! builtins), no Merkle or FRI checks and no composition polynomial check. The
+//! generated code can't settle a proof on StarkNet by itself.
+//!
+//! Field arithmetic is emulated with `u128` reduced by the AIR's modulus so
+//! only prime fields with moduli below `2^64` are supported i.e. products of
+//! two reduced values never overflow. The generated code expects:
+//!
+//! - `frame`: the frame's rows where row `k` is the trace `k` rows after the
+//!   current row. Periodic and then linear column values follow the trace
+//!   columns in each row.
+//! - `challenges`: the verifier challenges in order
+//! - `hints`: the AIR's hints in order
+use crate::constraint::Element;
+use crate::constraint::Term;
+use crate::Air;
+use crate::Constraint;
+use ark_ff::BigInteger;
+use ark_ff::Field;
+use ark_ff::PrimeField;
+use gpu_poly::GpuField;
+use std::fmt::Write;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CairoGenError {
+    #[error("extension fields of degree {0} are not supported")]
+    ExtensionField(u64),
+    #[error("modulus of {0} bits is too large. Must be at most 64 bits")]
+    ModulusTooLarge(u32),
+}
+
+/// Returns Cairo 1 source code with functions that evaluate the boundary,
+/// transition and terminal constraints of an AIR
+pub fn generate_constraint_evaluator<A: Air>(air: &A) -> Result<String, CairoGenError> {
+    let extension_degree = A::Fq::extension_degree();
+    if extension_degree != 1 {
+        return Err(CairoGenError::ExtensionField(extension_degree));
+    }
+    let modulus = <A::Fq as Field>::BasePrimeField::MODULUS;
+    let modulus_bits = modulus.num_bits();
+    if modulus_bits > 64 {
+        return Err(CairoGenError::ModulusTooLarge(modulus_bits));
+    }
+
+    let mut code = String::new();
+    writeln!(code, "// Generated by ministark. Do not edit.").unwrap();
+    writeln!(code, "use core::array::ArrayTrait;").unwrap();
+    writeln!(code, "use core::array::SpanTrait;").unwrap();
+    writeln!(code).unwrap();
+    writeln!(code, "const MODULUS: u128 = {};", modulus.as_ref()[0]).unwrap();
+    code.push_str(ARITHMETIC);
+    for (name, constraints) in [
+        ("boundary", air.boundary_constraints()),
+        ("transition", air.transition_constraints()),
+        ("terminal", air.terminal_constraints()),
+    ] {
+        writeln!(code).unwrap();
+        write_evaluator(&mut code, name, constraints);
+    }
+    Ok(code)
+}
+
+const ARITHMETIC: &str = "
+fn add(a: u128, b: u128) -> u128 {
+    (a + b) % MODULUS
+}
+
+fn mul(a: u128, b: u128) -> u128 {
+    (a * b) % MODULUS
+}
+
+fn pow(base: u128, exponent: u32) -> u128 {
+    if exponent == 0 {
+        return 1;
+    }
+    let half = pow(mul(base, base), exponent / 2);
+    if exponent % 2 == 0 {
+        half
+    } else {
+        mul(base, half)
+    }
+}
+";
+
+fn write_evaluator<F: GpuField>(code: &mut String, name: &str, constraints: &[Constraint<F>]) {
+    writeln!(
+        code,
+        "fn evaluate_{name}_constraints(\n    frame: Span<Span<u128>>, challenges: Span<u128>, hints: Span<u128>\n) -> Array<u128> {{"
+    )
+    .unwrap();
+    writeln!(code, "    let mut res = ArrayTrait::new();").unwrap();
+    for (i, constraint) in constraints.iter().enumerate() {
+        writeln!(code, "    // constraint {i}").unwrap();
+        writeln!(code, "    let mut acc = 0;").unwrap();
+        for Term(coeff, variables) in constraint.0.iter() {
+            let mut term = element_value(coeff);
+            for &(element, power) in &variables.0 {
+                let value = match element {
+                    Element::Curr(column) => format!("*(*frame.at(0)).at({column})"),
+                    Element::Next(column) => format!("*(*frame.at(1)).at({column})"),
+                    Element::Offset(column, offset) => {
+                        format!("*(*frame.at({offset})).at({column})")
+                    }
+                    Element::Challenge(index) => format!("*challenges.at({index})"),
+                    Element::Hint(index) => format!("*hints.at({index})"),
+                };
+                let value = if power == 1 {
+                    value
+                } else {
+                    format!("pow({value}, {power})")
+                };
+                term = format!("mul({term}, {value})");
+            }
+            writeln!(code, "    acc = add(acc, {term});").unwrap();
+        }
+        writeln!(code, "    res.append(acc);").unwrap();
+    }
+    writeln!(code, "    res").unwrap();
+    writeln!(code, "}}").unwrap();
+}
+
+/// Returns the canonical value of a prime field element
+fn element_value<F: Field>(element: &F) -> u64 {
+    let component = element.to_base_prime_field_elements().next().unwrap();
+    component.into_bigint().as_ref()[0]
+}
@@ -1,5 +1,7 @@
 use crate::constraint::Challenge;
+use crate::random::PublicCoin;
 use ark_std::rand::Rng;
+use digest::Digest;
 use gpu_poly::GpuField;
 use std::ops::Deref;
 use std::ops::Index;
@@ -11,6 +13,16 @@ impl<F: GpuField> Challenges<F> {
     pub fn new<R: Rng + ?Sized>(rng: &mut R, num_challenges: usize) -> Self {
         Challenges((0..num_challenges).map(|_| F::rand(rng)).collect())
     }
+
+    /// Draws challenges from the public coin
+    pub fn draw(public_coin: &mut PublicCoin<impl Digest>, num_challenges: usize) -> Self {
+        Challenges((0..num_challenges).map(|_| public_coin.draw()).collect())
+    }
+
+    /// Appends challenges drawn in a later round of interaction
+    pub fn append(&mut self, mut other: Challenges<F>) {
+        self.0.append(&mut other.0)
+    }
 }
 
 impl<F: GpuField> Deref for Challenges<F> {
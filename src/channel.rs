@@ -1,32 +1,92 @@
+use crate::challenges::Challenges;
+use crate::composer::DeepCompositionCoeffs;
 use crate::fri;
-use crate::fri::FriProof;
+use crate::grinding::grind;
+use crate::ldt::LowDegreeProof;
+use crate::merkle::cap_bytes;
 use crate::random::PublicCoin;
+use crate::random::TranscriptLabel;
+use crate::stir;
 use crate::trace::Queries;
 use crate::Air;
 use crate::Proof;
+use crate::ProofHeader;
 use ark_serialize::CanonicalSerialize;
-use ark_std::rand::Rng;
 use digest::Digest;
 use digest::Output;
-use gpu_poly::GpuField;
-#[cfg(feature = "parallel")]
-use rayon::prelude::*;
 use std::ops::Deref;
 
-pub struct ProverChannel<'a, A: Air, D: Digest> {
+/// Prover side of the Fiat-Shamir transcript. Everything the prover sends to
+/// the verifier and every challenge it receives goes through the channel so
+/// the proving pipeline works with any transcript e.g. a different sponge or
+/// a channel that records each message before passing it on. The FRI and
+/// STIR channels must use the same digest (see [ChannelDigest]).
+pub trait ProverChannel<A: Air>:
+    fri::ProverChannel<A::Fq>
+    + stir::ProverChannel<A::Fq, Digest = <Self as fri::ProverChannel<A::Fq>>::Digest>
+{
+    /// Commitments are Merkle caps (see
+    /// [MerkleTree::cap](crate::merkle::MerkleTree::cap)). A cap of height
+    /// zero is just the root.
+    fn commit_base_trace(&mut self, cap: &[Output<<Self as fri::ProverChannel<A::Fq>>::Digest>]);
+
+    /// Commits to the extension columns of an interaction round
+    fn commit_extension_trace(
+        &mut self,
+        cap: &[Output<<Self as fri::ProverChannel<A::Fq>>::Digest>],
+    );
+
+    /// Draws the verifier challenges of an interaction round
+    fn draw_round_challenges(&mut self, round: usize) -> Challenges<A::Fq>;
+
+    fn draw_constraint_composition_coeffs(&mut self) -> Vec<(A::Fq, A::Fq)>;
+
+    fn commit_composition_trace(
+        &mut self,
+        cap: &[Output<<Self as fri::ProverChannel<A::Fq>>::Digest>],
+    );
+
+    fn get_ood_point(&mut self) -> A::Fq;
+
+    /// Sends the trace evaluations at `z * g^k` for each row `k` of the
+    /// evaluation frame
+    fn send_ood_trace_states(&mut self, frame: &[Vec<A::Fq>]);
+
+    fn send_ood_constraint_evaluations(&mut self, evals: &[A::Fq]);
+
+    fn draw_deep_composition_coeffs(&mut self) -> DeepCompositionCoeffs<A::Fq>;
+
+    fn grind_fri_commitments(&mut self);
+
+    fn get_fri_query_positions(&mut self) -> Vec<usize>;
+
+    fn build_proof(
+        self,
+        trace_queries: Queries<A::Fp, A::Fq>,
+        low_degree_proof: LowDegreeProof<A::Fq>,
+    ) -> Proof<A>;
+}
+
+/// Digest the channel `C` of AIR `A` commits with
+pub type ChannelDigest<A, C> = <C as fri::ProverChannel<<A as Air>::Fq>>::Digest;
+
+/// Channel backed by a [PublicCoin]. This is the transcript the verifier
+/// expects if `D` is the digest of the proof's
+/// [HashFunction](crate::HashFunction).
+pub struct DefaultProverChannel<'a, A: Air, D: Digest> {
     air: &'a A,
     pub public_coin: PublicCoin<D>,
-    base_trace_commitment: Output<D>,
-    extension_trace_commitment: Option<Output<D>>,
-    composition_trace_commitment: Output<D>,
-    fri_layer_commitments: Vec<Output<D>>,
-    ood_trace_states: (Vec<A::Fq>, Vec<A::Fq>),
-    ood_constraint_evaluations: Vec<A::Fq>,
+    base_trace_commitment: Vec<u8>,
+    extension_trace_commitments: Vec<Vec<u8>>,
+    composition_trace_commitment: Vec<u8>,
+    fri_layer_commitments: Vec<Vec<u8>>,
+    stir_round_commitments: Vec<Output<D>>,
+    ood_trace_states: Vec<Vec<Vec<A::Fq>>>,
+    ood_constraint_evaluations: Vec<Vec<A::Fq>>,
     pow_nonce: u64,
 }
 
-// impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
-impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
+impl<'a, A: Air, D: Digest> DefaultProverChannel<'a, A, D> {
     pub fn new(air: &'a A) -> Self {
         let mut seed = Vec::new();
         // Seed the public coin with:
@@ -37,111 +97,166 @@ impl<'a, A: Air, D: Digest> ProverChannel<'a, A, D> {
         air.trace_info().serialize_compressed(&mut seed).unwrap();
         air.options().serialize_compressed(&mut seed).unwrap();
         let public_coin = PublicCoin::<D>::new(&seed);
-        ProverChannel {
+        DefaultProverChannel {
             air,
             public_coin,
-            extension_trace_commitment: None,
+            extension_trace_commitments: Vec::new(),
             base_trace_commitment: Default::default(),
             composition_trace_commitment: Default::default(),
             ood_trace_states: Default::default(),
             ood_constraint_evaluations: Default::default(),
             fri_layer_commitments: Default::default(),
+            stir_round_commitments: Default::default(),
             pow_nonce: 0,
         }
     }
+}
 
-    pub fn commit_base_trace(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.base_trace_commitment = commitment.clone();
+impl<'a, A: Air, D: Digest + Send> ProverChannel<A> for DefaultProverChannel<'a, A, D> {
+    fn commit_base_trace(&mut self, cap: &[Output<D>]) {
+        let commitment = cap_bytes::<D>(cap);
+        self.public_coin
+            .reseed(TranscriptLabel::BaseTraceCommitment, &commitment);
+        self.base_trace_commitment = commitment;
     }
 
-    pub fn commit_extension_trace(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.extension_trace_commitment = Some(commitment.clone());
+    fn commit_extension_trace(&mut self, cap: &[Output<D>]) {
+        let commitment = cap_bytes::<D>(cap);
+        self.public_coin
+            .reseed(TranscriptLabel::ExtensionTraceCommitment, &commitment);
+        self.extension_trace_commitments.push(commitment);
     }
 
-    pub fn commit_composition_trace(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.composition_trace_commitment = commitment.clone();
+    fn draw_round_challenges(&mut self, round: usize) -> Challenges<A::Fq> {
+        self.air.get_round_challenges(round, &mut self.public_coin)
     }
 
-    pub fn get_ood_point<F: GpuField>(&mut self) -> F {
-        self.public_coin.draw()
+    fn draw_constraint_composition_coeffs(&mut self) -> Vec<(A::Fq, A::Fq)> {
+        self.air
+            .get_constraint_composition_coeffs(&mut self.public_coin)
     }
 
-    pub fn send_ood_trace_states(&mut self, evals: &[A::Fq], next_evals: &[A::Fq]) {
-        assert_eq!(evals.len(), next_evals.len());
-        self.public_coin.reseed(&evals);
-        self.public_coin.reseed(&next_evals);
-        self.ood_trace_states = (evals.to_vec(), next_evals.to_vec());
+    fn commit_composition_trace(&mut self, cap: &[Output<D>]) {
+        let commitment = cap_bytes::<D>(cap);
+        self.public_coin
+            .reseed(TranscriptLabel::CompositionTraceCommitment, &commitment);
+        self.composition_trace_commitment = commitment;
     }
 
-    pub fn send_ood_constraint_evaluations(&mut self, evals: &[A::Fq]) {
-        self.public_coin.reseed(&evals);
-        self.ood_constraint_evaluations = evals.to_vec();
+    fn get_ood_point(&mut self) -> A::Fq {
+        self.air.get_ood_point(&mut self.public_coin)
+    }
+
+    fn send_ood_trace_states(&mut self, frame: &[Vec<A::Fq>]) {
+        for evals in frame {
+            assert_eq!(frame[0].len(), evals.len());
+            self.public_coin
+                .reseed(TranscriptLabel::OodTraceStates, evals);
+        }
+        self.ood_trace_states.push(frame.to_vec());
     }
 
-    pub fn grind_fri_commitments(&mut self) {
-        let grinding_factor = self.air.options().grinding_factor as u32;
+    fn send_ood_constraint_evaluations(&mut self, evals: &[A::Fq]) {
+        self.public_coin
+            .reseed(TranscriptLabel::OodConstraintEvaluations, &evals);
+        self.ood_constraint_evaluations.push(evals.to_vec());
+    }
+
+    fn draw_deep_composition_coeffs(&mut self) -> DeepCompositionCoeffs<A::Fq> {
+        self.air.get_deep_composition_coeffs(&mut self.public_coin)
+    }
+
+    fn grind_fri_commitments(&mut self) {
+        let options = self.air.options();
+        let grinding_factor = options.grinding_factor as u32;
         if grinding_factor == 0 {
             // skip if there is no grinding required
             return;
         }
 
-        #[cfg(not(feature = "parallel"))]
-        let nonce = (1..u64::MAX)
-            .find(|&nonce| self.public_coin.check_leading_zeros(nonce) >= grinding_factor);
-
-        #[cfg(feature = "parallel")]
-        let nonce = (1..u64::MAX)
-            .into_par_iter()
-            .find_any(|&nonce| self.public_coin.check_leading_zeros(nonce) >= grinding_factor);
-
+        let seed = &self.public_coin.seed;
+        let nonce_encoding = options.nonce_encoding;
+        let grinding_hash = options.grinding_hash.unwrap_or(options.hash_function);
+        let nonce = with_digest!(grinding_hash, G => {
+            grind::<G>(seed, grinding_factor, nonce_encoding)
+        });
         self.pow_nonce = nonce.expect("nonce not found");
-        self.public_coin.reseed(&self.pow_nonce);
+        self.public_coin
+            .reseed(TranscriptLabel::PowNonce, &self.pow_nonce);
     }
 
-    pub fn get_fri_query_positions(&mut self) -> Vec<usize> {
+    fn get_fri_query_positions(&mut self) -> Vec<usize> {
         // TODO: voulnerability if multiple positions are the same
         let num_queries = self.air.options().num_queries;
         let lde_domain_size = self.air.trace_len() * self.air.lde_blowup_factor();
-        let mut rng = self.public_coin.draw_rng();
-        (0..num_queries)
-            .map(|_| rng.gen_range(0..lde_domain_size))
-            .collect()
+        self.public_coin
+            .draw_positions(num_queries, lde_domain_size)
     }
 
-    pub fn build_proof(
+    fn build_proof(
         self,
         trace_queries: Queries<A::Fp, A::Fq>,
-        fri_proof: FriProof<A::Fq>,
+        low_degree_proof: LowDegreeProof<A::Fq>,
     ) -> Proof<A> {
+        let options = *self.air.options();
         Proof {
-            options: *self.air.options(),
+            header: ProofHeader::new::<A>(options.hash_function),
+            options,
             trace_info: self.air.trace_info().clone(),
-            base_trace_commitment: self.base_trace_commitment.to_vec(),
-            extension_trace_commitment: self.extension_trace_commitment.map(|o| o.to_vec()),
-            composition_trace_commitment: self.composition_trace_commitment.to_vec(),
-            public_inputs: self.air.pub_inputs().clone(),
+            base_trace_commitment: self.base_trace_commitment,
+            extension_trace_commitments: self.extension_trace_commitments,
+            composition_trace_commitment: self.composition_trace_commitment,
+            public_inputs: Some(self.air.pub_inputs().clone()),
             ood_trace_states: self.ood_trace_states,
             ood_constraint_evaluations: self.ood_constraint_evaluations,
             pow_nonce: self.pow_nonce,
-            fri_proof,
+            low_degree_proof,
             trace_queries,
         }
     }
 }
 
 // FRI prover channel implementation
-impl<'a, A: Air, D: Digest> fri::ProverChannel<A::Fq> for ProverChannel<'a, A, D> {
+impl<'a, A: Air, D: Digest + Send> fri::ProverChannel<A::Fq> for DefaultProverChannel<'a, A, D> {
     type Digest = D;
 
-    fn commit_fri_layer(&mut self, commitment: &Output<D>) {
-        self.public_coin.reseed(&commitment.deref());
-        self.fri_layer_commitments.push(commitment.clone());
+    fn commit_fri_layer(&mut self, cap: &[Output<D>]) {
+        let commitment = cap_bytes::<D>(cap);
+        self.public_coin
+            .reseed(TranscriptLabel::FriLayerCommitment, &commitment);
+        self.fri_layer_commitments.push(commitment);
     }
 
     fn draw_fri_alpha(&mut self) -> A::Fq {
         self.public_coin.draw()
     }
+
+    fn send_fri_remainder(&mut self, coeffs: &[A::Fq]) {
+        self.public_coin
+            .reseed(TranscriptLabel::FriRemainder, &coeffs);
+    }
+}
+
+// STIR prover channel implementation
+impl<'a, A: Air, D: Digest + Send> stir::ProverChannel<A::Fq> for DefaultProverChannel<'a, A, D> {
+    type Digest = D;
+
+    fn commit_stir_round(&mut self, commitment: &Output<D>) {
+        self.public_coin
+            .reseed(TranscriptLabel::StirRoundCommitment, &commitment.deref());
+        self.stir_round_commitments.push(commitment.clone());
+    }
+
+    fn draw_stir_challenge(&mut self) -> A::Fq {
+        self.public_coin.draw()
+    }
+
+    fn send_stir_evaluations(&mut self, evals: &[A::Fq]) {
+        self.public_coin
+            .reseed(TranscriptLabel::StirEvaluations, &evals);
+    }
+
+    fn draw_stir_positions(&mut self, num_positions: usize, domain_size: usize) -> Vec<usize> {
+        stir::draw_positions(&mut self.public_coin, num_positions, domain_size)
+    }
 }
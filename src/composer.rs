@@ -1,5 +1,6 @@
 use crate::challenges::Challenges;
 use crate::hints::Hints;
+use crate::job::check_aborted;
 use crate::matrix::GroupItem;
 use crate::matrix::MatrixGroup;
 use crate::merkle::MerkleTree;
@@ -8,20 +9,25 @@ use crate::Air;
 use crate::Column;
 use crate::Constraint;
 use crate::Matrix;
+use crate::ProvingError;
 use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
+use digest::Digest;
 use gpu_poly::prelude::*;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use sha2::Sha256;
 use std::collections::BTreeMap;
+use std::mem::size_of;
 use std::ops::Mul;
 
 pub struct ConstraintComposer<'a, A: Air> {
     air: &'a A,
     composition_coeffs: Vec<(A::Fq, A::Fq)>,
+    /// Approximate number of bytes the constraint evaluation may use on top of
+    /// the trace LDEs and divisors. Unbounded if `None`.
+    max_memory: Option<usize>,
 }
 
 impl<'a, A: Air> ConstraintComposer<'a, A> {
@@ -29,16 +35,22 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         ConstraintComposer {
             air,
             composition_coeffs,
+            max_memory: None,
         }
     }
 
+    pub fn with_max_memory(mut self, max_memory: Option<usize>) -> Self {
+        self.max_memory = max_memory;
+        self
+    }
+
     pub fn evaluate(
         &mut self,
         challenges: &Challenges<A::Fq>,
         hints: &Hints<A::Fq>,
         base_trace_lde: &Matrix<A::Fp>,
         extension_trace_lde: Option<&Matrix<A::Fq>>,
-    ) -> Matrix<A::Fq> {
+    ) -> Result<Matrix<A::Fq>, ProvingError> {
         // create a matrix group with all the LDEs we need for composition
         let mut lde_columns = MatrixGroup::default();
 
@@ -48,7 +60,25 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
             lde_columns.append(GroupItem::Fq(extension_trace_lde))
         }
 
-        let boundary_constraints = self.air.boundary_constraints();
+        // add periodic column LDEs
+        let periodic_column_lde = self.air.periodic_column_lde();
+        if let Some(periodic_column_lde) = periodic_column_lde.as_ref() {
+            lde_columns.append(GroupItem::Fp(periodic_column_lde))
+        }
+
+        // linear columns are evaluated directly over the LDE domain
+        let linear_column_lde = self.air.linear_column_lde();
+        if let Some(linear_column_lde) = linear_column_lde.as_ref() {
+            lde_columns.append(GroupItem::Fp(linear_column_lde))
+        }
+
+        // add the selector and value columns of the AIR's assertions
+        let assertion_column_lde = self.air.assertion_column_lde();
+        if let Some(assertion_column_lde) = assertion_column_lde.as_ref() {
+            lde_columns.append(GroupItem::Fp(assertion_column_lde))
+        }
+
+        let boundary_constraints = self.air.all_boundary_constraints();
         let boundary_divisor_idx = lde_columns.num_cols();
         let boundary_divisor = self.air.boundary_constraint_divisor();
         let _boundary_divisor_matrix = Matrix::new(vec![boundary_divisor.lde]);
@@ -58,17 +88,30 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
             .iter()
             .map(|c| (c, boundary_divisor_idx.curr(), boundary_divisor.degree));
 
-        let transition_constraints = self.air.transition_constraints();
-        let transition_divisor_idx = lde_columns.num_cols();
-        let transition_divisor = self.air.transition_constraint_divisor();
-        let _transition_divisor_matrix = Matrix::new(vec![transition_divisor.lde]);
-        // add transition constraint divisor LDE
-        lde_columns.append(GroupItem::Fp(&_transition_divisor_matrix));
-        let transition_iter = transition_constraints
+        let transition_constraints = self.air.all_transition_constraints();
+        let mut num_exempt_rows = transition_constraints
             .iter()
-            .map(|c| (c, transition_divisor_idx.curr(), transition_divisor.degree));
+            .map(|&(_, k)| k)
+            .collect::<Vec<usize>>();
+        num_exempt_rows.sort_unstable();
+        num_exempt_rows.dedup();
+        let _transition_divisor_matrices = num_exempt_rows
+            .into_iter()
+            .map(|k| (k, self.air.transition_constraint_divisor(k)))
+            .map(|(k, divisor)| (k, divisor.degree, Matrix::new(vec![divisor.lde])))
+            .collect::<Vec<_>>();
+        // add a transition constraint divisor LDE for each distinct exemption
+        let mut transition_divisors = BTreeMap::new();
+        for (k, degree, matrix) in &_transition_divisor_matrices {
+            transition_divisors.insert(*k, (lde_columns.num_cols(), *degree));
+            lde_columns.append(GroupItem::Fp(matrix));
+        }
+        let transition_iter = transition_constraints.iter().map(|(c, k)| {
+            let (divisor_idx, divisor_degree) = transition_divisors[k];
+            (c, divisor_idx.curr(), divisor_degree)
+        });
 
-        let terminal_constraints = self.air.terminal_constraints();
+        let terminal_constraints = self.air.all_terminal_constraints();
         let terminal_divisor_idx = lde_columns.num_cols();
         let terminal_divisor = self.air.terminal_constraint_divisor();
         let _terminal_divisor_matrix = Matrix::new(vec![terminal_divisor.lde]);
@@ -78,20 +121,38 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
             .iter()
             .map(|c| (c, terminal_divisor_idx.curr(), terminal_divisor.degree));
 
+        if let Some(max_memory) = self.max_memory {
+            let constraints = boundary_iter.chain(transition_iter).chain(terminal_iter);
+            return self.evaluate_in_chunks(
+                &lde_columns,
+                constraints,
+                challenges,
+                hints,
+                max_memory,
+            );
+        }
+
         // add degree adjustment LDEs
         let trace_degree = self.air.trace_len() - 1;
         let composition_degree = self.air.composition_degree();
         let lde_domain = self.air.lde_domain();
         let mut degree_adjustment_matricies = Vec::new();
         let mut degree_adjustment_map = BTreeMap::<usize, Constraint<A::Fq>>::new();
-        for (constraint, _, divisor_degree) in boundary_iter
+        let mut degree_adjustments = Vec::new();
+        for (index, (constraint, _, divisor_degree)) in boundary_iter
             .clone()
             .chain(transition_iter.clone())
             .chain(terminal_iter.clone())
+            .enumerate()
         {
-            let evaluation_degree = constraint.degree() * trace_degree - divisor_degree;
-            assert!(evaluation_degree <= composition_degree);
-            let degree_adjustment = composition_degree - evaluation_degree;
+            let degree_adjustment = composition_degree_adjustment(
+                index,
+                constraint,
+                divisor_degree,
+                trace_degree,
+                composition_degree,
+            )?;
+            degree_adjustments.push(degree_adjustment);
 
             degree_adjustment_map
                 .entry(degree_adjustment)
@@ -118,12 +179,11 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         }
 
         let mut composition_constraint = Constraint::zero();
-        for (constraint, divisor, divisor_degree) in
-            boundary_iter.chain(transition_iter).chain(terminal_iter)
+        for ((constraint, divisor, _), degree_adjustment) in boundary_iter
+            .chain(transition_iter)
+            .chain(terminal_iter)
+            .zip(degree_adjustments)
         {
-            let evaluation_degree = constraint.degree() * trace_degree - divisor_degree;
-            assert!(evaluation_degree <= composition_degree);
-            let degree_adjustment = composition_degree - evaluation_degree;
             let degree_adjustor = degree_adjustment_map.get(&degree_adjustment).unwrap();
 
             let (alpha, beta) = self.composition_coeffs.pop().unwrap();
@@ -133,7 +193,78 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         }
 
         let lde_step = self.air.lde_blowup_factor();
-        lde_columns.evaluate_symbolic(&[composition_constraint], challenges, hints, lde_step)
+        Ok(lde_columns.evaluate_symbolic(&[composition_constraint], challenges, hints, lde_step))
+    }
+
+    /// Evaluates the composition in chunks of rows so the evaluation stays
+    /// within `max_memory` bytes. Degree adjustments `x^k` are computed per row
+    /// rather than stored as LDE columns. Stops between chunks if the proof is
+    /// aborted.
+    fn evaluate_in_chunks<'b>(
+        &mut self,
+        lde_columns: &MatrixGroup<A::Fp, A::Fq>,
+        constraints: impl Iterator<Item = (&'b Constraint<A::Fq>, Constraint<A::Fq>, usize)>,
+        challenges: &Challenges<A::Fq>,
+        hints: &Hints<A::Fq>,
+        max_memory: usize,
+    ) -> Result<Matrix<A::Fq>, ProvingError> {
+        let trace_degree = self.air.trace_len() - 1;
+        let composition_degree = self.air.composition_degree();
+        // constraints sharing a degree adjustment `k` are combined so the
+        // composition is `Σ_k x^k * alpha_k(x) + beta_k(x)`
+        let mut groups = BTreeMap::<usize, (Constraint<A::Fq>, Constraint<A::Fq>)>::new();
+        for (index, (constraint, divisor, divisor_degree)) in constraints.enumerate() {
+            let degree_adjustment = composition_degree_adjustment(
+                index,
+                constraint,
+                divisor_degree,
+                trace_degree,
+                composition_degree,
+            )?;
+
+            let (alpha, beta) = self.composition_coeffs.pop().unwrap();
+            let quotient = constraint * divisor;
+            let (alpha_sum, beta_sum) = groups
+                .entry(degree_adjustment)
+                .or_insert_with(|| (Constraint::zero(), Constraint::zero()));
+            *alpha_sum += &quotient * alpha;
+            *beta_sum += quotient * beta;
+        }
+        let degree_adjustments = groups.keys().map(|&k| k as u64).collect::<Vec<u64>>();
+        let group_constraints = groups
+            .into_values()
+            .flat_map(|(alpha_sum, beta_sum)| [alpha_sum, beta_sum])
+            .collect::<Vec<Constraint<A::Fq>>>();
+
+        // every row of a chunk holds an evaluation of each group constraint
+        // and scratch space for the base and extension field
+        let lde_domain = self.air.lde_domain();
+        let n = lde_domain.size();
+        let bytes_per_row = size_of::<A::Fp>() + (group_constraints.len() + 1) * size_of::<A::Fq>();
+        let chunk_size = (max_memory / bytes_per_row).clamp(1, n);
+        let lde_step = self.air.lde_blowup_factor();
+        let mut result = Vec::with_capacity_in(n, PageAlignedAllocator);
+        for start in (0..n).step_by(chunk_size) {
+            check_aborted()?;
+            let rows = start..n.min(start + chunk_size);
+            let evaluations = lde_columns.evaluate_symbolic_rows(
+                &group_constraints,
+                challenges,
+                hints,
+                lde_step,
+                rows.clone(),
+            );
+            let mut x = lde_domain.element(start);
+            for i in 0..rows.len() {
+                let mut acc = A::Fq::zero();
+                for (&k, evals) in degree_adjustments.iter().zip(evaluations.chunks(2)) {
+                    acc += evals[0][i] * &x.pow([k]) + evals[1][i];
+                }
+                result.push(acc);
+                x *= lde_domain.group_gen;
+            }
+        }
+        Ok(Matrix::new(vec![result]))
     }
 
     fn trace_polys(&self, composed_evaluations: Matrix<A::Fq>) -> Matrix<A::Fq> {
@@ -145,11 +276,12 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
         assert_eq!(composition_poly_degree, self.air.composition_degree());
         composition_poly.0[0].truncate(composition_poly_degree + 1);
 
-        let num_composition_trace_cols = self.air.ce_blowup_factor();
         assert_eq!(
             composition_poly.num_rows() / self.air.trace_len(),
-            num_composition_trace_cols
+            self.air.ce_blowup_factor()
         );
+        // coefficient `i` goes to column `i mod num_composition_trace_cols`
+        let num_composition_trace_cols = self.air.num_composition_columns();
         let composition_trace_poly = if num_composition_trace_cols == 1 {
             composition_poly
         } else {
@@ -166,22 +298,42 @@ impl<'a, A: Air> ConstraintComposer<'a, A> {
 
     /// builds a commitment to the composed trace polynomial.
     /// Output is of the form `(lde, poly, lde_merkle_tree)`
-    pub fn build_commitment(
+    pub fn build_commitment<D: Digest + Send>(
         mut self,
         challenges: &Challenges<A::Fq>,
         hints: &Hints<A::Fq>,
         base_trace_lde: &Matrix<A::Fp>,
         extension_trace_lde: Option<&Matrix<A::Fq>>,
-    ) -> (Matrix<A::Fq>, Matrix<A::Fq>, MerkleTree<Sha256>) {
+    ) -> Result<(Matrix<A::Fq>, Matrix<A::Fq>, MerkleTree<D>), ProvingError> {
         let composed_evaluations =
-            self.evaluate(challenges, hints, base_trace_lde, extension_trace_lde);
+            self.evaluate(challenges, hints, base_trace_lde, extension_trace_lde)?;
         let composition_trace_polys = self.trace_polys(composed_evaluations);
-        let composition_trace_lde = composition_trace_polys.evaluate(self.air.lde_domain());
-        let merkle_tree = composition_trace_lde.commit_to_rows();
-        (composition_trace_lde, composition_trace_polys, merkle_tree)
+        let (composition_trace_lde, merkle_tree) =
+            composition_trace_polys.evaluate_and_commit(self.air.lde_domain());
+        Ok((composition_trace_lde, composition_trace_polys, merkle_tree))
     }
 }
 
+/// Returns the degree `k` that lifts the evaluation degree of a constraint
+/// quotient to the composition degree. Fails if the quotient's degree is
+/// already larger.
+fn composition_degree_adjustment<F: GpuField>(
+    index: usize,
+    constraint: &Constraint<F>,
+    divisor_degree: usize,
+    trace_degree: usize,
+    composition_degree: usize,
+) -> Result<usize, ProvingError> {
+    let evaluation_degree = (constraint.degree() * trace_degree).saturating_sub(divisor_degree);
+    composition_degree
+        .checked_sub(evaluation_degree)
+        .ok_or(ProvingError::ConstraintDegreeTooHigh {
+            index,
+            degree: evaluation_degree,
+            max: composition_degree,
+        })
+}
+
 pub struct DeepPolyComposer<'a, A: Air> {
     air: &'a A,
     composition_coeffs: DeepCompositionCoeffs<A::Fq>,
@@ -200,53 +352,65 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         }
     }
 
+    /// Adds the DEEP quotients of the execution trace. `ood_frame[k]` holds the
+    /// evaluations of all trace columns at `z * g^k`.
     pub fn add_execution_trace_polys(
         &mut self,
-        base_trace_polys: Matrix<A::Fp>,
-        extension_trace_polys: Option<Matrix<A::Fq>>,
-        ood_evals: Vec<A::Fq>,
-        ood_evals_next: Vec<A::Fq>,
+        base_trace_polys: &Matrix<A::Fp>,
+        extension_trace_polys: Option<&Matrix<A::Fq>>,
+        ood_frame: &[Vec<A::Fq>],
     ) {
         assert!(self.poly.is_empty());
 
         let trace_domain = self.air.trace_domain();
-        let next_z = self.z * A::Fq::from(trace_domain.group_gen());
+        let g = A::Fq::from(trace_domain.group_gen());
         let n = trace_domain.size();
 
-        let mut t1_composition = Vec::with_capacity_in(n, PageAlignedAllocator);
-        t1_composition.resize(n, A::Fq::zero());
-        let mut t2_composition = Vec::with_capacity_in(n, PageAlignedAllocator);
-        t2_composition.resize(n, A::Fq::zero());
+        // one composition for each row of the evaluation frame
+        let mut compositions = ood_frame
+            .iter()
+            .map(|_| {
+                let mut composition = Vec::with_capacity_in(n, PageAlignedAllocator);
+                composition.resize(n, A::Fq::zero());
+                composition
+            })
+            .collect::<Vec<_>>();
 
         // TODO: clean up code
         for (i, poly) in base_trace_polys.iter().enumerate() {
-            let (alpha, beta, _) = self.composition_coeffs.base_trace[i];
-            let ood_eval = ood_evals[i];
-            let ood_eval_next = ood_evals_next[i];
-
-            acc_trace_poly(&mut t1_composition, poly, ood_eval, alpha);
-            acc_trace_poly(&mut t2_composition, poly, ood_eval_next, beta);
+            let coeffs = &self.composition_coeffs.base_trace[i];
+            for ((composition, ood_evals), &coeff) in
+                compositions.iter_mut().zip(ood_frame).zip(coeffs)
+            {
+                acc_trace_poly(composition, poly, ood_evals[i], coeff);
+            }
         }
 
         if let Some(extension_trace_polys) = extension_trace_polys {
             // TODO: not a huge fan of this of this num_base_column business
             let num_base_columns = self.air.trace_info().num_base_columns;
             for (i, poly) in extension_trace_polys.iter().enumerate() {
-                let (alpha, beta, _) = self.composition_coeffs.extension_trace[i];
-                let ood_eval = ood_evals[num_base_columns + i];
-                let ood_eval_next = ood_evals_next[num_base_columns + i];
-
-                acc_trace_poly(&mut t1_composition, poly, ood_eval, alpha);
-                acc_trace_poly(&mut t2_composition, poly, ood_eval_next, beta);
+                let coeffs = &self.composition_coeffs.extension_trace[i];
+                for ((composition, ood_evals), &coeff) in
+                    compositions.iter_mut().zip(ood_frame).zip(coeffs)
+                {
+                    acc_trace_poly(composition, poly, ood_evals[num_base_columns + i], coeff);
+                }
             }
         }
 
         // TODO: multithread
-        synthetic_divide(&mut t1_composition, 1, self.z);
-        synthetic_divide(&mut t2_composition, 1, next_z);
+        let mut x = self.z;
+        for composition in &mut compositions {
+            synthetic_divide(composition, 1, x);
+            x *= g;
+        }
 
-        for (t1, t2) in t1_composition.into_iter().zip(t2_composition) {
-            self.poly.push(t1 + t2)
+        self.poly.resize(n, A::Fq::zero());
+        for composition in compositions {
+            for (lhs, rhs) in self.poly.iter_mut().zip(composition) {
+                *lhs += rhs;
+            }
         }
 
         // TODO:
@@ -254,14 +418,22 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
         assert!(self.poly.last().unwrap().is_zero());
     }
 
-    pub fn add_composition_trace_polys(&mut self, mut polys: Matrix<A::Fq>, ood_evals: Vec<A::Fq>) {
+    pub fn add_composition_trace_polys(&mut self, polys: &Matrix<A::Fq>, ood_evals: &[A::Fq]) {
         assert!(!self.poly.is_empty());
 
+        // composition columns are longer than the trace if there are fewer
+        // columns than the constraint evaluation blowup factor
+        if self.poly.len() < polys.num_rows() {
+            self.poly.resize(polys.num_rows(), A::Fq::zero());
+        }
+
+        // polys are shared by the composers of each out-of-domain point
+        let mut polys = polys.clone();
         let z_n = self.z.pow([polys.num_cols() as u64]);
 
         ark_std::cfg_iter_mut!(polys.0)
             .zip(ood_evals)
-            .for_each(|(poly, ood_eval)| {
+            .for_each(|(poly, &ood_eval)| {
                 poly[0] -= ood_eval;
                 synthetic_divide(poly, 1, z_n);
             });
@@ -297,10 +469,12 @@ impl<'a, A: Air> DeepPolyComposer<'a, A> {
 }
 
 pub struct DeepCompositionCoeffs<F> {
-    /// Base trace polynomial composition coefficients
-    pub base_trace: Vec<(F, F, F)>,
-    /// Base trace polynomial composition coefficients
-    pub extension_trace: Vec<(F, F, F)>,
+    /// Base trace polynomial composition coefficients. Each column has a
+    /// coefficient for every row of the evaluation frame.
+    pub base_trace: Vec<Vec<F>>,
+    /// Extension trace polynomial composition coefficients. Each column has a
+    /// coefficient for every row of the evaluation frame.
+    pub extension_trace: Vec<Vec<F>>,
     /// Composition poly trace column composition coefficients
     pub constraints: Vec<F>,
     /// Degree adjustment composition coefficients
@@ -12,19 +12,32 @@ use std::ops::MulAssign;
 use std::ops::Neg;
 use std::ops::Sub;
 use std::ops::SubAssign;
+use std::sync::Arc;
 
 /// A constraint element can represent several things:
 /// - a column in the current cycle
 /// - a column in the next cycle
+/// - a column `k` cycles after the current cycle for `k >= 2`
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub enum Element {
     Curr(usize),
     Next(usize),
     Challenge(usize),
     Hint(usize),
+    Offset(usize, usize),
 }
 
 impl Element {
+    /// Returns the column index and the row offset of a trace element
+    pub fn column_offset(&self) -> Option<(usize, usize)> {
+        match *self {
+            Element::Curr(index) => Some((index, 0)),
+            Element::Next(index) => Some((index, 1)),
+            Element::Offset(index, offset) => Some((index, offset)),
+            Element::Challenge(_) | Element::Hint(_) => None,
+        }
+    }
+
     pub fn pow<F: GpuField>(&self, exponent: usize) -> Constraint<F> {
         Constraint::new(vec![Term(
             F::one(),
@@ -61,6 +74,12 @@ pub trait Challenge {
     }
 }
 
+impl Hint for usize {
+    fn index(&self) -> usize {
+        *self
+    }
+}
+
 impl Challenge for usize {
     fn index(&self) -> usize {
         *self
@@ -82,6 +101,17 @@ pub trait Column {
     fn next<F: GpuField>(&self) -> Constraint<F> {
         Constraint::from(Element::Next(self.index()))
     }
+
+    /// Create a constraint element for the cycle `offset` cycles after the
+    /// current cycle. `offset(0)` and `offset(1)` are the same as `curr()` and
+    /// `next()`.
+    fn offset<F: GpuField>(&self, offset: usize) -> Constraint<F> {
+        match offset {
+            0 => self.curr(),
+            1 => self.next(),
+            _ => Constraint::from(Element::Offset(self.index(), offset)),
+        }
+    }
 }
 
 impl Column for usize {
@@ -153,6 +183,7 @@ impl core::fmt::Debug for Variables {
                 Element::Next(index) => write!(f, "x'_{}", index)?,
                 Element::Challenge(index) => write!(f, "c_{}", index)?,
                 Element::Hint(index) => write!(f, "h_{}", index)?,
+                Element::Offset(index, offset) => write!(f, "x^({})_{}", offset, index)?,
             };
             if !power.is_one() {
                 write!(f, "^{power}")?;
@@ -223,6 +254,27 @@ impl<F: GpuField> Term<F> {
     fn degree(&self) -> usize {
         self.1.degree(false)
     }
+
+    fn evaluate_frame(&self, challenges: &[F], hints: &[F], frame: &[&[F]]) -> F {
+        let mut result = self.0;
+        for &(element, power) in &(self.1).0 {
+            let val = match element {
+                Element::Curr(index) => frame[0][index],
+                Element::Next(index) => frame[1][index],
+                Element::Offset(index, offset) => frame[offset][index],
+                Element::Challenge(index) => challenges[index],
+                Element::Hint(index) => hints[index],
+            };
+            result *= val.pow([power as u64]);
+        }
+        result
+    }
+}
+
+impl<F: GpuField> core::fmt::Debug for Term<F> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
+        write!(f, "{}{:?}", self.0, self.1)
+    }
 }
 
 impl<'a, 'b, F: GpuField> Mul<&'a Term<F>> for &'b Term<F> {
@@ -235,12 +287,22 @@ impl<'a, 'b, F: GpuField> Mul<&'a Term<F>> for &'b Term<F> {
     }
 }
 
-/// A multivariate constraint polynomial
+/// A multivariate constraint polynomial. The terms are shared between clones
+/// so cloning a constraint to use it in several places is cheap. Operations
+/// that change a constraint copy its terms only if they're shared.
 #[derive(Clone)]
-pub struct Constraint<F>(pub(crate) Vec<Term<F>>);
+pub struct Constraint<F>(pub(crate) Arc<Vec<Term<F>>>);
 
 impl<F: GpuField> Constraint<F> {
+    /// Raises the constraint to the power `exp` by square-and-multiply. The
+    /// result has `exp` times the degree of the constraint. Monomials like
+    /// `c * x^k` are raised directly rather than multiplied out.
     pub fn pow(&self, mut exp: usize) -> Self {
+        if let [Term(coeff, variables)] = self.0.as_slice() {
+            let variables = variables.0.iter().map(|&(e, power)| (e, power * exp));
+            let term = Term(coeff.pow([exp as u64]), Variables::new(variables.collect()));
+            return Constraint::new(vec![term]);
+        }
         let mut res = Constraint::from(F::one());
         let mut acc = self.clone();
         while exp > 0 {
@@ -264,10 +326,24 @@ impl<F: GpuField> Constraint<F> {
         indices
     }
 
+    /// Brings the constraint to its normal form: constants are folded into
+    /// coefficients, like terms are combined (so `x - x` cancels) and terms
+    /// with a zero coefficient are dropped. Arithmetic on constraints already
+    /// keeps them normalized since products are expanded into a flat sum of
+    /// terms. This is for constraints assembled from terms some other way.
+    pub fn simplify(&self) -> Self {
+        Self::new(
+            self.0
+                .iter()
+                .map(|Term(coeff, variables)| Term(*coeff, Variables::new(variables.0.clone())))
+                .collect(),
+        )
+    }
+
     pub fn new(mut terms: Vec<Term<F>>) -> Self {
         terms.sort_by(|a, b| a.1.cmp(&b.1));
 
-        let mut constraint = Constraint(Self::combine_terms(terms));
+        let mut constraint = Constraint(Arc::new(Self::combine_terms(terms)));
         constraint.remove_zeros();
         constraint
     }
@@ -296,7 +372,9 @@ impl<F: GpuField> Constraint<F> {
                                 (term.1).0.into_iter().filter(|v| v.0 != element).collect(),
                             ),
                         );
-                        (Constraint::new(vec![new_term]) * substitution.pow(substitution_power)).0
+                        (Constraint::new(vec![new_term]) * substitution.pow(substitution_power))
+                            .0
+                            .to_vec()
                     } else {
                         vec![term]
                     }
@@ -305,12 +383,36 @@ impl<F: GpuField> Constraint<F> {
         );
     }
 
+    /// Replaces every element with `f(element)` e.g. to move a constraint's
+    /// columns to different indices
+    pub fn map_elements(&self, f: impl Fn(Element) -> Element) -> Self {
+        Self::new(
+            self.0
+                .iter()
+                .map(|Term(coeff, variables)| {
+                    let variables = variables.0.iter().map(|&(e, power)| (f(e), power));
+                    Term(*coeff, Variables::new(variables.collect()))
+                })
+                .collect(),
+        )
+    }
+
     pub fn degree(&self) -> usize {
         self.0.iter().map(|term| term.degree()).max().unwrap_or(0)
     }
 
+    /// Returns the largest row offset of any trace element in the constraint
+    pub fn max_row_offset(&self) -> usize {
+        self.get_elements()
+            .iter()
+            .filter_map(|element| element.column_offset())
+            .map(|(_, offset)| offset)
+            .max()
+            .unwrap_or(0)
+    }
+
     fn remove_zeros(&mut self) {
-        self.0.retain(|Term(coeff, _)| !coeff.is_zero());
+        Arc::make_mut(&mut self.0).retain(|Term(coeff, _)| !coeff.is_zero());
     }
 
     fn combine_terms(terms: Vec<Term<F>>) -> Vec<Term<F>> {
@@ -338,48 +440,77 @@ impl<F: GpuField> Constraint<F> {
     }
 
     pub fn evaluate(&self, challenges: &[F], hints: &[F], current_row: &[F], next_row: &[F]) -> F {
+        self.evaluate_frame(challenges, hints, &[current_row, next_row])
+    }
+
+    /// Evaluates the constraint on consecutive rows of a trace. `frame[k]` is
+    /// the row `k` cycles after the current row.
+    pub fn evaluate_frame(&self, challenges: &[F], hints: &[F], frame: &[&[F]]) -> F {
         let mut result = F::zero();
-        for Term(coeff, vars) in self.0.iter() {
-            let mut scratch = *coeff;
-            for &(element, power) in &vars.0 {
-                let val = match element {
-                    Element::Curr(index) => current_row[index],
-                    Element::Next(index) => next_row[index],
-                    Element::Challenge(index) => challenges[index],
-                    Element::Hint(index) => hints[index],
-                };
-                scratch *= val.pow([power as u64]);
-            }
-            result += scratch;
+        for term in self.0.iter() {
+            result += term.evaluate_frame(challenges, hints, frame);
         }
         result
     }
+
+    /// Evaluates each term of the constraint on a frame. The evaluations sum
+    /// to [Constraint::evaluate_frame].
+    pub fn evaluate_terms(&self, challenges: &[F], hints: &[F], frame: &[&[F]]) -> Vec<F> {
+        self.0
+            .iter()
+            .map(|term| term.evaluate_frame(challenges, hints, frame))
+            .collect()
+    }
+
+    /// Terms of the constraint with like terms combined
+    pub fn terms(&self) -> &[Term<F>] {
+        &self.0
+    }
+
+    /// Evaluates to `a` when `flag` is one and `b` when `flag` is zero. Only
+    /// behaves like a branch if `flag` is constrained to be binary e.g. with
+    /// [is_binary]. The degree is the degree of `flag` plus the larger degree
+    /// of `a` and `b`.
+    pub fn select(
+        flag: impl Borrow<Constraint<F>>,
+        a: impl Borrow<Constraint<F>>,
+        b: impl Borrow<Constraint<F>>,
+    ) -> Self {
+        let flag = flag.borrow();
+        flag * a.borrow() + (Constraint::from(F::one()) - flag) * b.borrow()
+    }
 }
 
 impl<F: GpuField> core::fmt::Debug for Constraint<F> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> Result<(), core::fmt::Error> {
         // for (coeff, term) in self.0.iter().filter(|(c, _)| !c.is_zero()) {
-        for Term(coeff, variables) in self.0.iter() {
-            if variables.0.is_empty() {
-                write!(f, "\n{}", coeff)?;
-            } else {
-                write!(f, "\n{}{:?}", coeff, variables)?;
-            }
+        for term in self.0.iter() {
+            write!(f, "\n{:?}", term)?;
         }
         Ok(())
     }
 }
 
+impl<F: GpuField> Constraint<F> {
+    /// Constant polynomial. Used by [constraint!] for integer literals.
+    pub fn constant(value: u64) -> Self {
+        Constraint::from(F::from(value))
+    }
+}
+
 impl<F: GpuField> From<F> for Constraint<F> {
     fn from(element: F) -> Self {
-        Constraint(vec![Term::new(element, Variables::default())])
+        if element.is_zero() {
+            return Constraint::zero();
+        }
+        Constraint(Arc::new(vec![Term::new(element, Variables::default())]))
     }
 }
 
 impl<F: GpuField> Zero for Constraint<F> {
     /// Returns the zero polynomial.
     fn zero() -> Self {
-        Self(vec![])
+        Self(Arc::new(Vec::new()))
     }
 
     /// Checks if the given polynomial is zero.
@@ -421,6 +552,11 @@ impl<F: GpuField> Add<&Constraint<F>> for &Constraint<F> {
     type Output = Constraint<F>;
 
     fn add(self, rhs: &Constraint<F>) -> Self::Output {
+        if self.0.is_empty() {
+            return rhs.clone();
+        } else if rhs.0.is_empty() {
+            return self.clone();
+        }
         let mut result_terms = Vec::new();
         let mut lhs_iter = self.0.iter().peekable();
         let mut rhs_iter = rhs.0.iter().peekable();
@@ -451,7 +587,7 @@ impl<F: GpuField> Add<&Constraint<F>> for &Constraint<F> {
             };
             result_terms.push(smallest);
         }
-        Constraint(result_terms)
+        Constraint(Arc::new(result_terms))
     }
 }
 
@@ -468,7 +604,7 @@ impl<F: GpuField> Neg for Constraint<F> {
     type Output = Constraint<F>;
 
     fn neg(mut self) -> Self::Output {
-        for Term(coeff, _) in &mut self.0 {
+        for Term(coeff, _) in Arc::make_mut(&mut self.0) {
             *coeff = -*coeff;
         }
         self
@@ -506,7 +642,10 @@ impl<F: GpuField> Mul<F> for Constraint<F> {
     type Output = Constraint<F>;
 
     fn mul(mut self, rhs: F) -> Self::Output {
-        for Term(coeff, _) in &mut self.0 {
+        if rhs.is_zero() {
+            return Constraint::zero();
+        }
+        for Term(coeff, _) in Arc::make_mut(&mut self.0) {
             *coeff *= rhs;
         }
         self
@@ -634,7 +773,94 @@ pub fn is_one<F: GpuField>(a: impl Borrow<Constraint<F>>) -> Constraint<F> {
     a.borrow() - F::one()
 }
 
+/// Returns zero only when a is one of `values`. The degree is the number of
+/// values times the degree of `a`. Handy for restricting a column to a set of
+/// opcodes or as a selector that's zero for every opcode but one.
+pub fn is_one_of<F: GpuField>(
+    a: impl Borrow<Constraint<F>>,
+    values: impl IntoIterator<Item = F>,
+) -> Constraint<F> {
+    let a = a.borrow();
+    values
+        .into_iter()
+        .fold(Constraint::from(F::one()), |product, value| {
+            product * (a - value)
+        })
+}
+
 /// Returns zero only when a = zero || a == one.
 pub fn is_binary<F: GpuField>(a: impl Borrow<Constraint<F>>) -> Constraint<F> {
     a.borrow() * a.borrow() - a.borrow()
 }
+
+/// Builds a [Constraint] from near-mathematical syntax. Trace elements are
+/// written `Col[curr]`, `Col[next]` or `Col[k]` for `k` cycles after the
+/// current cycle. Challenges and hints are written `Alpha[challenge]` and
+/// `Gamma[hint]`. Integer literals become constants. Everything else (field
+/// elements, other constraints, function and method calls) is passed through
+/// unchanged.
+///
+/// ```text
+/// constraint!(Ip[next] - Ip[curr] - 1)
+/// // expands to
+/// Ip.next() - Ip.curr() - Constraint::constant(1)
+///
+/// constraint!(MemVal[curr] * (MemVal[curr] - max_mem_val) * MemValMaxInv[curr])
+/// ```
+///
+/// Column names can be paths e.g. `ProcessorBaseColumn::Ip[curr]`. Each token
+/// is a level of macro recursion so very long expressions may need a higher
+/// `recursion_limit`.
+#[macro_export]
+macro_rules! constraint {
+    (@elem $e:expr, curr) => {
+        $crate::constraint::Column::curr(&$e)
+    };
+    (@elem $e:expr, next) => {
+        $crate::constraint::Column::next(&$e)
+    };
+    (@elem $e:expr, challenge) => {
+        $crate::constraint::Challenge::get_challenge(&$e)
+    };
+    (@elem $e:expr, hint) => {
+        $crate::constraint::Hint::get_hint(&$e)
+    };
+    (@elem $e:expr, $offset:literal) => {
+        $crate::constraint::Column::offset(&$e, $offset)
+    };
+    (@munch [$($out:tt)*]) => {
+        $($out)*
+    };
+    (@munch [$($out:tt)*] $($e:ident)::+ [$at:tt] $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* $crate::constraint!(@elem $($e)::+, $at)] $($rest)*)
+    };
+    (@munch [$($out:tt)*] $e:literal [$at:tt] $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* $crate::constraint!(@elem $e, $at)] $($rest)*)
+    };
+    // function calls are passed through so their arguments stay plain Rust
+    (@munch [$($out:tt)*] $f:ident ($($args:tt)*) $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* $f($($args)*)] $($rest)*)
+    };
+    (@munch [$($out:tt)*] ($($inner:tt)*) $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* ($crate::constraint!(@munch [] $($inner)*))] $($rest)*)
+    };
+    // operators have to be matched before literals since `- 1` is a literal
+    (@munch [$($out:tt)*] + $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* +] $($rest)*)
+    };
+    (@munch [$($out:tt)*] - $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* -] $($rest)*)
+    };
+    (@munch [$($out:tt)*] * $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* *] $($rest)*)
+    };
+    (@munch [$($out:tt)*] $value:literal $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* $crate::Constraint::constant($value)] $($rest)*)
+    };
+    (@munch [$($out:tt)*] $other:tt $($rest:tt)*) => {
+        $crate::constraint!(@munch [$($out)* $other] $($rest)*)
+    };
+    ($($tokens:tt)+) => {
+        $crate::constraint!(@munch [] $($tokens)+)
+    };
+}
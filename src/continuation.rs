@@ -0,0 +1,152 @@
+//! Continuations split a long execution into segments that are proven
+//! separately. Each segment exposes the machine state at its boundaries in its
+//! public inputs. Since public inputs seed the proof transcript the boundary
+//! states are bound to each segment proof and the chain composes if the final
+//! state of each segment matches the initial state of the next.
+use crate::prover::ProvingError;
+use crate::verifier::VerificationError;
+use crate::Air;
+use crate::Proof;
+use crate::Prover;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use digest::Digest;
+use std::fmt::Debug;
+use thiserror::Error;
+
+/// An AIR for a single segment of a continued execution
+pub trait SegmentAir: Air {
+    /// Machine state carried between segments e.g. registers and a digest of
+    /// memory. See [state_digest].
+    type State: CanonicalSerialize + Clone + PartialEq + Debug;
+
+    fn initial_state(pub_inputs: &Self::PublicInputs) -> Self::State;
+
+    fn final_state(pub_inputs: &Self::PublicInputs) -> Self::State;
+}
+
+/// Hashes serialized state. Useful for committing to large machine state
+/// (like memory) that is carried between segments.
+pub fn state_digest<D: Digest>(state: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(state.compressed_size());
+    state.serialize_compressed(&mut bytes).unwrap();
+    D::new_with_prefix(&bytes).finalize().to_vec()
+}
+
+#[derive(Error, Debug)]
+pub enum ContinuationError {
+    #[error("a continuation must contain at least one segment")]
+    NoSegments,
+    #[error("initial state of segment {0} does not match the final state of segment {1}")]
+    BoundaryMismatch(usize, usize),
+    #[error("initial state of the first segment does not match the expected state")]
+    InitialStateMismatch,
+    #[error("final state of the last segment does not match the expected state")]
+    FinalStateMismatch,
+    #[error("segment {0} does not contain public inputs")]
+    MissingPublicInputs(usize),
+    #[error("verification of segment {0} failed")]
+    SegmentVerification(usize, #[source] VerificationError),
+    #[error("proving segment {0} failed")]
+    SegmentProving(usize, #[source] ProvingError),
+}
+
+/// Proofs for consecutive segments of an execution
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct ContinuationProof<A: SegmentAir> {
+    pub segments: Vec<Proof<A>>,
+}
+
+impl<A: SegmentAir> ContinuationProof<A> {
+    pub fn initial_state(&self) -> Option<A::State> {
+        let first_segment = self.segments.first()?;
+        first_segment.public_inputs.as_ref().map(A::initial_state)
+    }
+
+    pub fn final_state(&self) -> Option<A::State> {
+        let last_segment = self.segments.last()?;
+        last_segment.public_inputs.as_ref().map(A::final_state)
+    }
+
+    /// Checks the segments link together without verifying each proof
+    pub fn check_boundaries(&self) -> Result<(), ContinuationError> {
+        if self.segments.is_empty() {
+            return Err(ContinuationError::NoSegments);
+        }
+
+        // boundary states are read from the public inputs of each segment
+        let mut pub_inputs = Vec::with_capacity(self.segments.len());
+        for (i, segment) in self.segments.iter().enumerate() {
+            match &segment.public_inputs {
+                Some(segment_pub_inputs) => pub_inputs.push(segment_pub_inputs),
+                None => return Err(ContinuationError::MissingPublicInputs(i)),
+            }
+        }
+
+        for (i, [prev, next]) in pub_inputs.array_windows().enumerate() {
+            let prev_final_state = A::final_state(prev);
+            let next_initial_state = A::initial_state(next);
+            if prev_final_state != next_initial_state {
+                return Err(ContinuationError::BoundaryMismatch(i + 1, i));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies every segment and that the execution starts in `initial_state`
+    /// and ends in `final_state`
+    pub fn verify(
+        self,
+        initial_state: &A::State,
+        final_state: &A::State,
+    ) -> Result<(), ContinuationError> {
+        self.check_boundaries()?;
+
+        if self.initial_state().as_ref() != Some(initial_state) {
+            return Err(ContinuationError::InitialStateMismatch);
+        }
+
+        if self.final_state().as_ref() != Some(final_state) {
+            return Err(ContinuationError::FinalStateMismatch);
+        }
+
+        for (i, segment) in self.segments.into_iter().enumerate() {
+            segment
+                .verify()
+                .map_err(|err| ContinuationError::SegmentVerification(i, err))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Proves each segment trace in order
+pub fn prove_segments<P: Prover>(
+    prover: &P,
+    traces: impl IntoIterator<Item = P::Trace>,
+) -> Result<ContinuationProof<P::Air>, ContinuationError>
+where
+    P::Air: SegmentAir,
+{
+    let mut segments: Vec<Proof<P::Air>> = Vec::new();
+    let mut prev_final_state = None;
+    for (i, trace) in traces.into_iter().enumerate() {
+        let pub_inputs = prover.get_pub_inputs(&trace);
+        // catch a broken chain before spending time on the proof
+        let initial_state = P::Air::initial_state(&pub_inputs);
+        if prev_final_state.is_some() && prev_final_state != Some(initial_state) {
+            return Err(ContinuationError::BoundaryMismatch(i, i - 1));
+        }
+        prev_final_state = Some(P::Air::final_state(&pub_inputs));
+
+        let proof = prover
+            .generate_proof(trace)
+            .map_err(|err| ContinuationError::SegmentProving(i, err))?;
+        segments.push(proof);
+    }
+
+    let proof = ContinuationProof { segments };
+    proof.check_boundaries()?;
+    Ok(proof)
+}
@@ -0,0 +1,204 @@
+use crate::grinding::NonceEncoding;
+use crate::ldt::LowDegreeProof;
+use crate::Air;
+use crate::Proof;
+use crate::ProofHeader;
+use crate::ProofOptions;
+use crate::TraceInfo;
+use ark_serialize::CanonicalSerialize;
+use std::fmt::Display;
+
+/// Human readable summary of a proof returned by [Proof::describe]. Useful
+/// for checking what a proof contains without verifying it.
+#[derive(Debug, Clone)]
+pub struct ProofDescription {
+    pub header: ProofHeader,
+    pub options: ProofOptions,
+    pub trace_info: TraceInfo,
+    pub conjectured_security_level: usize,
+    /// Label and digest of each commitment in the order they're absorbed by
+    /// the public coin
+    pub commitments: Vec<(String, Vec<u8>)>,
+    /// Number of FRI layers (or STIR rounds) the options call for
+    pub expected_ldt_layers: usize,
+    /// Number of FRI layers (or STIR rounds) in the proof
+    pub ldt_layers: usize,
+    pub pow_nonce: u64,
+    /// Compressed size in bytes of each field of the proof
+    pub section_sizes: Vec<(&'static str, usize)>,
+}
+
+impl ProofDescription {
+    /// Compressed size in bytes of the whole proof
+    pub fn size(&self) -> usize {
+        self.section_sizes.iter().map(|(_, size)| size).sum()
+    }
+}
+
+impl Display for ProofDescription {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex =
+            |bytes: &[u8]| -> String { bytes.iter().map(|byte| format!("{byte:02x}")).collect() };
+        let ProofHeader {
+            version: [major, minor],
+            field_id,
+            air_id,
+            ..
+        } = self.header;
+        let ProofOptions {
+            num_queries,
+            lde_blowup_factor,
+            grinding_factor,
+            fri_folding_factor,
+            fri_max_remainder_size,
+            low_degree_test,
+            num_ood_points,
+            merkle_cap_height,
+            hash_function,
+            fri_folding_schedule,
+            fri_remainder_coefficients,
+            grinding_hash,
+            nonce_encoding,
+        } = self.options;
+        let TraceInfo {
+            num_base_columns,
+            num_extension_columns,
+            trace_len,
+            ..
+        } = self.trace_info;
+        writeln!(f, "version: {major}.{minor}")?;
+        writeln!(f, "field id: {field_id:016x}")?;
+        writeln!(f, "air id: {air_id:016x}")?;
+        writeln!(f, "options:")?;
+        writeln!(f, "  queries: {num_queries}")?;
+        writeln!(f, "  lde blowup factor: {lde_blowup_factor}")?;
+        writeln!(f, "  grinding factor: {grinding_factor}")?;
+        if let Some(grinding_hash) = grinding_hash {
+            writeln!(f, "  grinding hash: {grinding_hash:?}")?;
+        }
+        if nonce_encoding != NonceEncoding::default() {
+            writeln!(f, "  nonce encoding: {nonce_encoding:?}")?;
+        }
+        writeln!(f, "  folding factor: {fri_folding_factor}")?;
+        if !fri_folding_schedule.is_empty() {
+            writeln!(
+                f,
+                "  folding schedule: {:?}",
+                fri_folding_schedule.factors()
+            )?;
+        }
+        writeln!(f, "  max remainder size: {fri_max_remainder_size}")?;
+        if fri_remainder_coefficients {
+            writeln!(f, "  remainder sent as coefficients")?;
+        }
+        writeln!(f, "  low-degree test: {low_degree_test:?}")?;
+        writeln!(f, "  ood points: {num_ood_points}")?;
+        writeln!(f, "  merkle cap height: {merkle_cap_height}")?;
+        writeln!(f, "  hash function: {hash_function:?}")?;
+        writeln!(
+            f,
+            "trace: {trace_len} rows, {num_base_columns} base columns, {num_extension_columns} extension columns"
+        )?;
+        writeln!(
+            f,
+            "conjectured security: {} bits",
+            self.conjectured_security_level
+        )?;
+        writeln!(
+            f,
+            "{low_degree_test:?} layers: {} (expected {})",
+            self.ldt_layers, self.expected_ldt_layers
+        )?;
+        writeln!(f, "pow nonce: {}", self.pow_nonce)?;
+        writeln!(f, "commitments:")?;
+        for (label, digest) in &self.commitments {
+            writeln!(f, "  {label}: {}", hex(digest))?;
+        }
+        writeln!(f, "sizes:")?;
+        for (section, size) in &self.section_sizes {
+            writeln!(f, "  {section}: {size} bytes")?;
+        }
+        write!(f, "  total: {} bytes", self.size())
+    }
+}
+
+impl<A: Air> Proof<A> {
+    /// Summarizes the parameters, commitments and size of the proof
+    pub fn describe(&self) -> ProofDescription {
+        let mut commitments = vec![("base trace".to_string(), self.base_trace_commitment.clone())];
+        for (i, commitment) in self.extension_trace_commitments.iter().enumerate() {
+            commitments.push((format!("extension trace {i}"), commitment.clone()));
+        }
+        commitments.push((
+            "composition trace".to_string(),
+            self.composition_trace_commitment.clone(),
+        ));
+
+        let (expected_ldt_layers, ldt_layers) = match &self.low_degree_proof {
+            LowDegreeProof::Fri(proof) => {
+                for (i, commitment) in proof.layer_commitments().enumerate() {
+                    commitments.push((format!("fri layer {i}"), commitment.to_vec()));
+                }
+                if !self.options.fri_remainder_coefficients {
+                    commitments.push((
+                        "fri remainder".to_string(),
+                        proof.remainder_commitment().to_vec(),
+                    ));
+                }
+                let options = self.options.into_fri_options();
+                let lde_domain_size =
+                    self.trace_info.trace_len * usize::from(self.options.lde_blowup_factor);
+                (options.num_layers(lde_domain_size), proof.num_layers())
+            }
+            LowDegreeProof::Stir(proof) => {
+                for (i, commitment) in proof.round_commitments().enumerate() {
+                    commitments.push((format!("stir round {i}"), commitment.to_vec()));
+                }
+                let options = self.options.into_stir_options();
+                (
+                    options.num_rounds(self.trace_info.trace_len),
+                    proof.num_rounds(),
+                )
+            }
+        };
+
+        let section_sizes = vec![
+            ("header", self.header.compressed_size()),
+            ("options", self.options.compressed_size()),
+            ("trace info", self.trace_info.compressed_size()),
+            (
+                "base trace commitment",
+                self.base_trace_commitment.compressed_size(),
+            ),
+            (
+                "extension trace commitments",
+                self.extension_trace_commitments.compressed_size(),
+            ),
+            (
+                "composition trace commitment",
+                self.composition_trace_commitment.compressed_size(),
+            ),
+            ("low-degree proof", self.low_degree_proof.compressed_size()),
+            ("pow nonce", self.pow_nonce.compressed_size()),
+            ("trace queries", self.trace_queries.compressed_size()),
+            ("public inputs", self.public_inputs.compressed_size()),
+            ("ood trace states", self.ood_trace_states.compressed_size()),
+            (
+                "ood constraint evaluations",
+                self.ood_constraint_evaluations.compressed_size(),
+            ),
+        ];
+
+        ProofDescription {
+            header: self.header,
+            options: self.options,
+            trace_info: self.trace_info.clone(),
+            conjectured_security_level: self.conjectured_security_level(),
+            commitments,
+            expected_ldt_layers,
+            ldt_layers,
+            pow_nonce: self.pow_nonce,
+            section_sizes,
+        }
+    }
+}
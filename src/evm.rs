@@ -0,0 +1,231 @@
+//! Proof encoding for cheap parsing by EVM verifier contracts.
+//!
+//! Every value takes up one or more 32-byte words so a contract can read any
+//! value with a single `calldataload`. There is no varint or compressed
+//! framing like the ark-serialize format uses:
+//!
+//! - integers are big-endian `uint256` words
+//! - hashes are `bytes32` words. Only 32-byte digests are supported.
+//! - field elements are a `uint256` word per base prime field component in the
+//!   order of [Field::to_base_prime_field_elements]
+//! - byte strings are a word holding the length in bytes followed by the bytes
+//!   padded with zeros to a multiple of 32 bytes
+//! - arrays are a word holding the number of items followed by the items
+//!
+//! A proof starts with a fixed size head followed by its variable size
+//! sections. The head has fixed offsets (in words):
+//!
+//! ```text
+//! 0..9    proof options in declaration order
+//! 9..12   num_base_columns, num_extension_columns, trace_len
+//! 12      base trace commitment
+//! 13      composition trace commitment
+//! 14      proof-of-work nonce
+//! 15..21  byte offsets from the start of the proof of the trace meta,
+//!         extension trace commitments, ood trace states, ood constraint
+//!         evaluations, trace queries and low degree proof sections
+//! ```
+//!
+//! Extension trace commitments are an array with a `bytes32` word for each
+//! interaction round that has extension columns. Commitments must be Merkle
+//! roots i.e. proofs with a non-zero Merkle cap height can't be encoded. FRI
+//! layers must all fold by the same factor so proofs with a folding schedule
+//! can't be encoded either, nor can proofs that send the FRI remainder as
+//! coefficients or change the grinding hash or nonce encoding.
+//!
+//! Public inputs are not encoded. Verifier contracts receive them separately
+//! in whatever format suits the AIR.
+use crate::grinding::NonceEncoding;
+use crate::Air;
+use crate::Proof;
+use crate::ProofOptions;
+use ark_ff::BigInteger;
+use ark_ff::Field;
+use ark_ff::PrimeField;
+
+/// Size of an EVM word in bytes
+pub const WORD_SIZE: usize = 32;
+
+/// Number of words in the fixed size head of an encoded proof
+pub const PROOF_HEAD_WORDS: usize = 21;
+
+/// Types that can be encoded for an EVM verifier
+pub trait EvmEncode {
+    fn encode_evm(&self, encoder: &mut EvmEncoder);
+
+    fn to_evm_bytes(&self) -> Vec<u8> {
+        let mut encoder = EvmEncoder::new();
+        self.encode_evm(&mut encoder);
+        encoder.into_bytes()
+    }
+}
+
+/// Writes values as 32-byte words
+#[derive(Clone, Debug, Default)]
+pub struct EvmEncoder(Vec<u8>);
+
+impl EvmEncoder {
+    pub fn new() -> Self {
+        EvmEncoder(Vec::new())
+    }
+
+    /// Number of bytes written so far. Always a multiple of the word size.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn write_uint(&mut self, value: u64) {
+        let mut word = [0; WORD_SIZE];
+        word[WORD_SIZE - 8..].copy_from_slice(&value.to_be_bytes());
+        self.0.extend_from_slice(&word);
+    }
+
+    /// Writes a hash as a `bytes32` word. Panics if the hash is larger than a
+    /// word. Smaller hashes are padded on the right like Solidity's `bytesN`.
+    pub fn write_hash(&mut self, hash: &[u8]) {
+        assert!(
+            hash.len() <= WORD_SIZE,
+            "hash of {} bytes doesn't fit in a word",
+            hash.len()
+        );
+        let mut word = [0; WORD_SIZE];
+        word[..hash.len()].copy_from_slice(hash);
+        self.0.extend_from_slice(&word);
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.write_uint(bytes.len() as u64);
+        self.0.extend_from_slice(bytes);
+        self.pad();
+    }
+
+    pub fn write_element<F: Field>(&mut self, element: &F) {
+        for component in element.to_base_prime_field_elements() {
+            let bytes = component.into_bigint().to_bytes_be();
+            assert!(bytes.len() <= WORD_SIZE, "prime field is too large");
+            let mut word = [0; WORD_SIZE];
+            word[WORD_SIZE - bytes.len()..].copy_from_slice(&bytes);
+            self.0.extend_from_slice(&word);
+        }
+    }
+
+    pub fn write_elements<F: Field>(&mut self, elements: &[F]) {
+        self.write_uint(elements.len() as u64);
+        elements
+            .iter()
+            .for_each(|element| self.write_element(element));
+    }
+
+    pub fn write_array<T: EvmEncode>(&mut self, items: &[T]) {
+        self.write_uint(items.len() as u64);
+        items.iter().for_each(|item| item.encode_evm(self));
+    }
+
+    /// Pads the output with zeros to a multiple of the word size
+    fn pad(&mut self) {
+        let padded_len = (self.0.len() + WORD_SIZE - 1) / WORD_SIZE * WORD_SIZE;
+        self.0.resize(padded_len, 0);
+    }
+}
+
+impl<T: EvmEncode> EvmEncode for Vec<T> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_array(self)
+    }
+}
+
+impl<A: Air> EvmEncode for Proof<A> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        let start = encoder.len();
+        let ProofOptions {
+            num_queries,
+            lde_blowup_factor,
+            grinding_factor,
+            fri_folding_factor,
+            fri_max_remainder_size,
+            low_degree_test,
+            num_ood_points,
+            merkle_cap_height,
+            hash_function,
+            fri_folding_schedule,
+            fri_remainder_coefficients,
+            grinding_hash,
+            nonce_encoding,
+        } = self.options;
+        assert_eq!(
+            merkle_cap_height, 0,
+            "proofs with Merkle caps can't be encoded for the EVM"
+        );
+        assert!(
+            fri_folding_schedule.is_empty(),
+            "proofs with a FRI folding schedule can't be encoded for the EVM"
+        );
+        assert!(
+            !fri_remainder_coefficients,
+            "proofs with FRI remainder coefficients can't be encoded for the EVM"
+        );
+        assert!(
+            grinding_hash.is_none() && nonce_encoding == NonceEncoding::default(),
+            "proofs with custom grinding options can't be encoded for the EVM"
+        );
+        for value in [
+            num_queries,
+            lde_blowup_factor,
+            grinding_factor,
+            fri_folding_factor,
+            fri_max_remainder_size,
+            low_degree_test as u8,
+            num_ood_points,
+            merkle_cap_height,
+            hash_function as u8,
+        ] {
+            encoder.write_uint(value.into());
+        }
+
+        let trace_info = &self.trace_info;
+        encoder.write_uint(trace_info.num_base_columns as u64);
+        encoder.write_uint(trace_info.num_extension_columns as u64);
+        encoder.write_uint(trace_info.trace_len as u64);
+
+        encoder.write_hash(&self.base_trace_commitment);
+        encoder.write_hash(&self.composition_trace_commitment);
+        encoder.write_uint(self.pow_nonce);
+
+        let mut sections: [EvmEncoder; 6] = std::array::from_fn(|_| EvmEncoder::new());
+        sections[0].write_bytes(&trace_info.meta);
+        let extension_trace_commitments = &self.extension_trace_commitments;
+        sections[1].write_uint(extension_trace_commitments.len() as u64);
+        for commitment in extension_trace_commitments {
+            sections[1].write_hash(commitment);
+        }
+        sections[2].write_uint(self.ood_trace_states.len() as u64);
+        for frame in &self.ood_trace_states {
+            sections[2].write_uint(frame.len() as u64);
+            frame.iter().for_each(|row| sections[2].write_elements(row));
+        }
+        sections[3].write_uint(self.ood_constraint_evaluations.len() as u64);
+        for evaluations in &self.ood_constraint_evaluations {
+            sections[3].write_elements(evaluations);
+        }
+        self.trace_queries.encode_evm(&mut sections[4]);
+        self.low_degree_proof.encode_evm(&mut sections[5]);
+
+        // section offsets are relative to the start of the proof
+        let mut offset = encoder.len() - start + sections.len() * WORD_SIZE;
+        for section in &sections {
+            encoder.write_uint(offset as u64);
+            offset += section.len();
+        }
+        for section in sections {
+            encoder.0.extend(section.into_bytes());
+        }
+    }
+}
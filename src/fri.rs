@@ -1,8 +1,28 @@
+use crate::evm::EvmEncode;
+use crate::evm::EvmEncoder;
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "json")]
+use crate::json::JsonEncode;
+use crate::merkle::cap_bytes;
+use crate::merkle::cap_len;
+use crate::merkle::hash_leaf;
+use crate::merkle::parse_cap;
+use crate::merkle::path_len;
 use crate::merkle::MerkleProof;
 use crate::merkle::MerkleTree;
 use crate::merkle::MerkleTreeError;
 use crate::random::PublicCoin;
+use crate::random::TranscriptLabel;
+#[cfg(feature = "gpu")]
+use crate::schedule::use_gpu_fft;
+#[cfg(feature = "spill")]
+use crate::spill::Spilled;
+#[cfg(feature = "arbitrary")]
+use crate::utils::arbitrary_elements;
+use crate::utils::horner_evaluate;
 use crate::utils::interleave;
+use ark_ff::batch_inversion;
 use ark_ff::FftField;
 use ark_ff::Field;
 use ark_poly::univariate::DensePolynomial;
@@ -14,42 +34,146 @@ use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use digest::Digest;
 use digest::Output;
+#[cfg(feature = "gpu")]
+use gpu_poly::plan::Planner;
 use gpu_poly::prelude::*;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use std::ops::Deref;
+use std::borrow::Cow;
+use std::io;
+#[cfg(feature = "spill")]
+use std::path::PathBuf;
 use thiserror::Error;
 
+/// Folding factors of the first FRI layers e.g. `[8, 4, 4, 2]`. Folding
+/// early layers aggressively means fewer Merkle trees while later layers stay
+/// cheap to open. Layers past the end of the schedule fold by the folding
+/// factor in the options. The schedule is empty by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, CanonicalSerialize, CanonicalDeserialize)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FoldingSchedule([u8; FoldingSchedule::MAX_LEN]);
+
+impl FoldingSchedule {
+    pub const MAX_LEN: usize = 8;
+
+    /// Panics if there are more than [FoldingSchedule::MAX_LEN] factors or a
+    /// factor isn't one of 2, 4, 8 or 16
+    pub fn new(factors: &[u8]) -> Self {
+        assert!(
+            factors.len() <= Self::MAX_LEN,
+            "folding schedule is too long"
+        );
+        let mut schedule = [0; Self::MAX_LEN];
+        for (entry, &factor) in schedule.iter_mut().zip(factors) {
+            assert!(
+                matches!(factor, 2 | 4 | 8 | 16),
+                "folding factor {factor} is not supported"
+            );
+            *entry = factor;
+        }
+        FoldingSchedule(schedule)
+    }
+
+    /// The scheduled folding factors. The schedule ends at the first zero.
+    pub fn factors(&self) -> &[u8] {
+        let len = self.0.iter().position(|&factor| factor == 0);
+        &self.0[..len.unwrap_or(Self::MAX_LEN)]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.factors().is_empty()
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct FriOptions {
     folding_factor: usize,
+    folding_schedule: FoldingSchedule,
     max_remainder_size: usize,
+    remainder_coefficients: bool,
     blowup_factor: usize,
+    merkle_cap_height: usize,
 }
 
 impl FriOptions {
     pub fn new(blowup_factor: usize, folding_factor: usize, max_remainder_size: usize) -> Self {
         FriOptions {
             folding_factor,
+            folding_schedule: FoldingSchedule::default(),
             max_remainder_size,
+            remainder_coefficients: false,
             blowup_factor,
+            merkle_cap_height: 0,
         }
     }
 
-    pub fn num_layers(&self, mut domain_size: usize) -> usize {
-        let mut num_layers = 0;
-        while domain_size > self.max_remainder_size {
-            domain_size /= self.folding_factor;
-            num_layers += 1;
+    /// Folds the first layers by the factors in `folding_schedule`
+    pub fn with_folding_schedule(mut self, folding_schedule: FoldingSchedule) -> Self {
+        self.folding_schedule = folding_schedule;
+        self
+    }
+
+    /// Folding factor of layer `layer`. The remainder is committed in chunks
+    /// of the folding factor in the options regardless of the schedule.
+    pub fn folding_factor(&self, layer: usize) -> usize {
+        match self.folding_schedule.factors().get(layer) {
+            Some(&factor) => factor.into(),
+            None => self.folding_factor,
+        }
+    }
+
+    /// Stops folding once the degree bound drops to the max remainder size
+    /// and sends the remainder's coefficients rather than its evaluations.
+    /// The verifier evaluates the remainder at the query positions so
+    /// neither the remainder nor the last few layers are committed to.
+    pub fn with_remainder_coefficients(mut self) -> Self {
+        self.remainder_coefficients = true;
+        self
+    }
+
+    /// Layers are committed to with Merkle caps of this height
+    pub fn with_merkle_cap_height(mut self, merkle_cap_height: usize) -> Self {
+        self.merkle_cap_height = merkle_cap_height;
+        self
+    }
+
+    /// Overrides the blowup factor. The low-degree test of a DEEP composition
+    /// polynomial longer than the trace has a smaller blowup factor than the LDE.
+    pub fn with_blowup_factor(mut self, blowup_factor: usize) -> Self {
+        self.blowup_factor = blowup_factor;
+        self
+    }
+
+    pub fn num_layers(&self, domain_size: usize) -> usize {
+        self.fold(domain_size).0
+    }
+
+    /// Number of remainder evaluations or, if the remainder is sent as
+    /// coefficients, the number of coefficients
+    pub fn remainder_size(&self, domain_size: usize) -> usize {
+        let (_, remainder_domain_size) = self.fold(domain_size);
+        if self.remainder_coefficients {
+            remainder_domain_size / self.blowup_factor
+        } else {
+            remainder_domain_size
         }
-        num_layers
     }
 
-    pub fn remainder_size(&self, mut domain_size: usize) -> usize {
-        while domain_size > self.max_remainder_size {
-            domain_size /= self.folding_factor;
+    /// Returns the number of layers and the size of the remainder's domain
+    fn fold(&self, mut domain_size: usize) -> (usize, usize) {
+        let max_size = if self.remainder_coefficients {
+            // compare the degree bound rather than the codeword length
+            self.max_remainder_size * self.blowup_factor
+        } else {
+            self.max_remainder_size
+        };
+        let mut num_layers = 0;
+        while domain_size > max_size {
+            domain_size /= self.folding_factor(num_layers);
+            num_layers += 1;
         }
-        domain_size
+        (num_layers, domain_size)
     }
 
     pub fn domain_offset<F: GpuField>(&self) -> F::FftField {
@@ -60,10 +184,54 @@ impl FriOptions {
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct FriProof<F: GpuField> {
     layers: Vec<FriProofLayer<F>>,
+    /// Evaluations of the remainder or its coefficients if the options call
+    /// for [FriOptions::with_remainder_coefficients]
     remainder: Vec<F>,
+    /// Empty if the remainder is sent as coefficients
     remainder_commitment: Vec<u8>,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, F: GpuField> arbitrary::Arbitrary<'a> for FriProof<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(FriProof {
+            layers: u.arbitrary()?,
+            remainder: arbitrary_elements(u)?,
+            remainder_commitment: u.arbitrary()?,
+        })
+    }
+}
+
+impl<F: GpuField> EvmEncode for FriProof<F> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_array(&self.layers);
+        encoder.write_elements(&self.remainder);
+        encoder.write_hash(&self.remainder_commitment);
+    }
+}
+
+#[cfg(feature = "json")]
+impl<F: GpuField> JsonEncode for FriProof<F> {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "layers": self.layers.encode_json(),
+            "remainder_commitment": json::bytes_to_json(&self.remainder_commitment),
+            "remainder": json::elements_to_json(&self.remainder),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        Ok(FriProof {
+            layers: Vec::decode_json(json::field(value, "layers")?)?,
+            remainder_commitment: json::bytes_from_json(json::field(
+                value,
+                "remainder_commitment",
+            )?)?,
+            remainder: json::elements_from_json(json::field(value, "remainder")?)?,
+        })
+    }
+}
+
 impl<F: GpuField> FriProof<F> {
     pub fn new(
         layers: Vec<FriProofLayer<F>>,
@@ -76,16 +244,59 @@ impl<F: GpuField> FriProof<F> {
             remainder,
         }
     }
+
+    pub fn num_layers(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Commitments to each folded layer in the order they were committed
+    pub fn layer_commitments(&self) -> impl Iterator<Item = &[u8]> {
+        self.layers.iter().map(|layer| layer.commitment.as_slice())
+    }
+
+    pub fn remainder_commitment(&self) -> &[u8] {
+        &self.remainder_commitment
+    }
 }
 
 pub struct FriProver<F: GpuField, D: Digest> {
     options: FriOptions,
     layers: Vec<FriLayer<F, D>>,
+    remainder_coeffs: Vec<F>,
+    #[cfg(feature = "spill")]
+    spill_dir: Option<PathBuf>,
 }
 
 struct FriLayer<F: GpuField, D: Digest> {
     tree: MerkleTree<D>,
-    evaluations: Vec<F>,
+    evaluations: LayerEvaluations<F>,
+}
+
+/// Evaluations of a committed layer. Layers are only read again when the
+/// proof is assembled so they can wait on disk until then.
+enum LayerEvaluations<F> {
+    InMemory(Vec<F>),
+    #[cfg(feature = "spill")]
+    Spilled(Spilled<F>),
+}
+
+impl<F: GpuField> LayerEvaluations<F> {
+    fn len(&self) -> usize {
+        match self {
+            LayerEvaluations::InMemory(evaluations) => evaluations.len(),
+            #[cfg(feature = "spill")]
+            LayerEvaluations::Spilled(spilled) => spilled.num_values(),
+        }
+    }
+
+    /// Reads spilled evaluations back into memory
+    fn load(&self) -> io::Result<Cow<[F]>> {
+        Ok(match self {
+            LayerEvaluations::InMemory(evaluations) => Cow::Borrowed(evaluations),
+            #[cfg(feature = "spill")]
+            LayerEvaluations::Spilled(spilled) => Cow::Owned(spilled.read_vec()?),
+        })
+    }
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
@@ -95,6 +306,44 @@ pub struct FriProofLayer<F: GpuField> {
     commitment: Vec<u8>,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, F: GpuField> arbitrary::Arbitrary<'a> for FriProofLayer<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(FriProofLayer {
+            values: arbitrary_elements(u)?,
+            proofs: u.arbitrary()?,
+            commitment: u.arbitrary()?,
+        })
+    }
+}
+
+impl<F: GpuField> EvmEncode for FriProofLayer<F> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_elements(&self.values);
+        encoder.write_array(&self.proofs);
+        encoder.write_hash(&self.commitment);
+    }
+}
+
+#[cfg(feature = "json")]
+impl<F: GpuField> JsonEncode for FriProofLayer<F> {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "values": json::elements_to_json(&self.values),
+            "proofs": self.proofs.encode_json(),
+            "commitment": json::bytes_to_json(&self.commitment),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        Ok(FriProofLayer {
+            values: json::elements_from_json(json::field(value, "values")?)?,
+            proofs: Vec::decode_json(json::field(value, "proofs")?)?,
+            commitment: json::bytes_from_json(json::field(value, "commitment")?)?,
+        })
+    }
+}
+
 impl<F: GpuField> FriProofLayer<F> {
     pub fn new<const N: usize>(
         values: Vec<[F; N]>,
@@ -112,6 +361,7 @@ impl<F: GpuField> FriProofLayer<F> {
     pub fn verify<D: Digest, const N: usize>(
         &self,
         positions: &[usize],
+        depth: usize,
     ) -> Result<(), MerkleTreeError> {
         let commitment = Output::<D>::from_slice(&self.commitment);
         // TODO: could check raminder is empty but not critical
@@ -119,18 +369,16 @@ impl<F: GpuField> FriProofLayer<F> {
         let (chunks, _remainder) = &self.values.as_chunks::<N>();
         // zip chains could be dangerous
         for (i, position) in positions.iter().enumerate() {
-            let proof = self.proofs[i].parse::<D>();
+            let proof = self.proofs[i].parse::<D>()?;
             let expected_leaf = &proof[0];
-            let mut chunk_bytes = Vec::with_capacity(chunks.compressed_size());
-            chunks.serialize_compressed(&mut chunk_bytes).unwrap();
-            let actual_leaf = D::new_with_prefix(chunk_bytes).finalize();
+            let actual_leaf = hash_leaf::<D, F>(&chunks[i]);
 
             if *expected_leaf != actual_leaf {
                 print!("CMCMDKSMCKMDSKCMK {i}");
                 return Err(MerkleTreeError::InvalidProof);
             }
 
-            MerkleTree::<D>::verify(commitment, &proof, *position / 4)?;
+            MerkleTree::<D>::verify(commitment, &proof, *position / 4, depth)?;
         }
         Ok(())
     }
@@ -141,33 +389,79 @@ impl<F: GpuField, D: Digest> FriProver<F, D> {
         FriProver {
             options,
             layers: Vec::new(),
+            remainder_coeffs: Vec::new(),
+            #[cfg(feature = "spill")]
+            spill_dir: None,
         }
     }
 
-    pub fn into_proof(self, positions: &[usize]) -> FriProof<F> {
-        let folding_factor = self.options.folding_factor;
-        let (last_layer, initial_layers) = self.layers.split_last().unwrap();
-        let mut domain_size = self.layers[0].evaluations.len();
+    /// Writes committed layers to compressed files in `dir` until the proof
+    /// is built. Layers that fail to spill are kept in memory.
+    #[cfg(feature = "spill")]
+    pub fn with_spill_dir(mut self, dir: PathBuf) -> Self {
+        self.spill_dir = Some(dir);
+        self
+    }
+
+    fn store_evaluations(&self, evaluations: Vec<F>) -> LayerEvaluations<F> {
+        #[cfg(feature = "spill")]
+        if let Some(dir) = &self.spill_dir {
+            if let Ok(spilled) = Spilled::write(dir, [evaluations.as_slice()]) {
+                return LayerEvaluations::Spilled(spilled);
+            }
+        }
+        LayerEvaluations::InMemory(evaluations)
+    }
+
+    /// Builds the proof for the queried positions. Errors if a spilled layer
+    /// can't be read back.
+    pub fn into_proof(self, positions: &[usize]) -> io::Result<FriProof<F>> {
+        let cap_height = self.options.merkle_cap_height;
+        let (queried_layers, last_layer) = if self.options.remainder_coefficients {
+            (self.layers.as_slice(), None)
+        } else {
+            let (last_layer, initial_layers) = self.layers.split_last().unwrap();
+            (initial_layers, Some(last_layer))
+        };
+        let mut domain_size = self
+            .layers
+            .first()
+            .map_or(0, |layer| layer.evaluations.len());
         let mut proof_layers = Vec::new();
         let mut positions = positions.to_vec();
-        for layer in initial_layers {
+        for (i, layer) in queried_layers.iter().enumerate() {
+            let folding_factor = self.options.folding_factor(i);
             let num_eval_chunks = domain_size / folding_factor;
             positions = fold_positions(&positions, num_eval_chunks);
             domain_size = num_eval_chunks;
 
+            let tree = &layer.tree;
+            let evals = layer.evaluations.load()?;
             proof_layers.push(match folding_factor {
-                2 => query_layer::<F, D, 2>(layer, &positions),
-                4 => query_layer::<F, D, 4>(layer, &positions),
-                6 => query_layer::<F, D, 6>(layer, &positions),
-                8 => query_layer::<F, D, 8>(layer, &positions),
-                16 => query_layer::<F, D, 16>(layer, &positions),
+                2 => query_layer::<F, D, 2>(tree, cap_height, &evals, &positions),
+                4 => query_layer::<F, D, 4>(tree, cap_height, &evals, &positions),
+                6 => query_layer::<F, D, 6>(tree, cap_height, &evals, &positions),
+                8 => query_layer::<F, D, 8>(tree, cap_height, &evals, &positions),
+                16 => query_layer::<F, D, 16>(tree, cap_height, &evals, &positions),
                 _ => unimplemented!("folding factor {folding_factor} is not supported"),
             });
         }
 
+        let last_layer = match last_layer {
+            Some(last_layer) => last_layer,
+            None => {
+                return Ok(FriProof::new(
+                    proof_layers,
+                    Vec::new(),
+                    self.remainder_coeffs,
+                ))
+            }
+        };
+
         // layers store interlaved evaluations so they need to be un-interleaved
-        let remainder_commitment = last_layer.tree.root().to_vec();
-        let last_evals = &last_layer.evaluations;
+        let remainder_commitment = cap_bytes::<D>(last_layer.tree.cap(cap_height));
+        let last_evals = last_layer.evaluations.load()?;
+        let folding_factor = self.options.folding_factor;
         let mut remainder = vec![F::zero(); last_evals.len()];
         let num_eval_chunks = last_evals.len() / folding_factor;
         for i in 0..num_eval_chunks {
@@ -176,7 +470,7 @@ impl<F: GpuField, D: Digest> FriProver<F, D> {
             }
         }
 
-        FriProof::new(proof_layers, remainder_commitment, remainder)
+        Ok(FriProof::new(proof_layers, remainder_commitment, remainder))
     }
 
     pub fn build_layers(
@@ -187,8 +481,27 @@ impl<F: GpuField, D: Digest> FriProver<F, D> {
         assert!(self.layers.is_empty());
         // let codeword = evaluations.0[0];
 
-        for _ in 0..self.options.num_layers(evaluations.len()) + 1 {
-            evaluations = match self.options.folding_factor {
+        let num_layers = self.options.num_layers(evaluations.len());
+        let remainder_size = self.options.remainder_size(evaluations.len());
+        let remainder_coefficients = self.options.remainder_coefficients;
+        if remainder_coefficients {
+            assert!(remainder_size > 0, "folded past the degree bound");
+        } else {
+            assert!(
+                remainder_size >= self.options.folding_factor,
+                "remainder of size {remainder_size} can't be split into chunks of {}",
+                self.options.folding_factor
+            );
+        }
+        // the remainder is committed to like a layer unless its coefficients are sent
+        let num_committed_layers = num_layers + usize::from(!remainder_coefficients);
+        for i in 0..num_committed_layers {
+            let folding_factor = if i < num_layers {
+                self.options.folding_factor(i)
+            } else {
+                self.options.folding_factor
+            };
+            evaluations = match folding_factor {
                 2 => self.build_layer::<2>(channel, evaluations),
                 4 => self.build_layer::<4>(channel, evaluations),
                 8 => self.build_layer::<8>(channel, evaluations),
@@ -196,6 +509,17 @@ impl<F: GpuField, D: Digest> FriProver<F, D> {
                 folding_factor => unreachable!("folding factor {folding_factor} not supported"),
             }
         }
+
+        if remainder_coefficients {
+            let domain_offset = self.options.domain_offset::<F>();
+            let domain =
+                Radix2EvaluationDomain::new_coset(evaluations.len(), domain_offset).unwrap();
+            let mut coeffs = ifft(evaluations, domain, num_committed_layers).to_vec();
+            // coefficients past the degree bound are zero for a valid codeword
+            coeffs.truncate(remainder_size);
+            channel.send_fri_remainder(&coeffs);
+            self.remainder_coeffs = coeffs;
+        }
     }
 
     /// Builds a single layer of the FRI protocol
@@ -213,27 +537,23 @@ impl<F: GpuField, D: Digest> FriProver<F, D> {
         // only require a single merkle path for their decommitment.
         let interleaved_evals: Vec<[F; N]> = interleave(&evaluations);
         let hashed_evals = ark_std::cfg_iter!(interleaved_evals)
-            .map(|chunk| {
-                let mut buff = Vec::with_capacity(chunk.compressed_size());
-                chunk.serialize_compressed(&mut buff).unwrap();
-                D::new_with_prefix(&buff).finalize()
-            })
+            .map(|chunk| hash_leaf::<D, F>(chunk))
             .collect();
 
         let evals_merkle_tree = MerkleTree::new(hashed_evals).unwrap();
-        channel.commit_fri_layer(evals_merkle_tree.root());
+        channel.commit_fri_layer(evals_merkle_tree.cap(self.options.merkle_cap_height));
 
+        // layers depend on the previous fold so they can't run concurrently.
+        // Each layer uses the next GPU to spread memory use across devices.
         let alpha = channel.draw_fri_alpha();
-        evaluations = apply_drp(
-            evaluations,
-            self.options.domain_offset::<F>(),
-            alpha,
-            self.options.folding_factor,
-        );
+        let device = self.layers.len();
+        let domain_offset = self.options.domain_offset::<F>();
+        evaluations = apply_drp_on_device(evaluations, domain_offset, alpha, N, device);
 
+        let evaluations = self.store_evaluations(interleaved_evals.into_flattened());
         self.layers.push(FriLayer {
             tree: evals_merkle_tree,
-            evaluations: interleaved_evals.into_flattened(),
+            evaluations,
         });
 
         evaluations
@@ -258,11 +578,18 @@ pub enum VerificationError {
     RemainderDegreeMismatch(usize),
     #[error("degree-respecting projection is invalid at the last layer")]
     InvalidRemainderDegreeRespectingProjection,
+    #[error("folding factor {0} is not supported")]
+    UnsupportedFoldingFactor(usize),
+    #[error("expected {0} layers but the proof contains {1}")]
+    NumLayersMismatch(usize, usize),
+    #[error("expected {0} remainder values but the proof contains {1}")]
+    RemainderSizeMismatch(usize, usize),
 }
 
 pub struct FriVerifier<F: GpuField, D: Digest> {
     options: FriOptions,
-    layer_commitments: Vec<Output<D>>,
+    /// Merkle cap of each layer and the remainder
+    layer_commitments: Vec<Vec<Output<D>>>,
     layer_alphas: Vec<F>,
     proof: FriProof<F>,
     domain: Radix2EvaluationDomain<F::FftField>,
@@ -275,39 +602,95 @@ impl<F: GpuField, D: Digest> FriVerifier<F, D> {
         proof: FriProof<F>,
         max_poly_degree: usize,
     ) -> Result<Self, VerificationError> {
-        let folding_factor = options.folding_factor;
+        use VerificationError::*;
         let domain_offset = options.domain_offset::<F>();
         let domain_size = max_poly_degree.next_power_of_two() * options.blowup_factor;
         let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
 
+        // factors are checked before they're used to count layers
+        let scheduled_factors = options.folding_schedule.factors().iter();
+        let folding_factors = scheduled_factors.map(|&factor| factor.into());
+        for folding_factor in folding_factors.chain([options.folding_factor]) {
+            if !matches!(folding_factor, 2 | 4 | 8 | 16) {
+                return Err(UnsupportedFoldingFactor(folding_factor));
+            }
+        }
+
+        // the shape of the proof is checked upfront so later indexing can't panic
+        let num_layers = options.num_layers(domain_size);
+        if proof.layers.len() != num_layers {
+            return Err(NumLayersMismatch(num_layers, proof.layers.len()));
+        }
+        let remainder_size = options.remainder_size(domain_size);
+        if proof.remainder.len() != remainder_size {
+            return Err(RemainderSizeMismatch(remainder_size, proof.remainder.len()));
+        }
+        if !options.remainder_coefficients && remainder_size < options.folding_factor {
+            return Err(CodewordTruncation(
+                remainder_size,
+                options.folding_factor,
+                num_layers,
+            ));
+        }
+        // layer trees have a leaf for every `folding_factor` evaluations
+        let digest_size = <D as digest::OutputSizeUser>::output_size();
+        let cap_size = |codeword_len: usize, folding_factor: usize| {
+            let num_leaves = codeword_len / folding_factor;
+            cap_len(num_leaves, options.merkle_cap_height) * digest_size
+        };
+        let mut codeword_len = domain_size;
+        for (i, layer) in proof.layers.iter().enumerate() {
+            let folding_factor = options.folding_factor(i);
+            if layer.commitment.len() != cap_size(codeword_len, folding_factor) {
+                return Err(LayerCommitmentInvalid(i));
+            }
+            // paths of any other length could open inner nodes as leaves
+            let depth = path_len(codeword_len / folding_factor, options.merkle_cap_height);
+            if !layer.proofs.iter().all(|proof| proof.has_depth::<D>(depth)) {
+                return Err(LayerCommitmentInvalid(i));
+            }
+            codeword_len /= folding_factor;
+        }
+        let remainder_commitment_size = if options.remainder_coefficients {
+            0
+        } else {
+            cap_size(remainder_size, options.folding_factor)
+        };
+        if proof.remainder_commitment.len() != remainder_commitment_size {
+            return Err(RemainderCommitmentInvalid);
+        }
+
         let mut layer_alphas = Vec::new();
         let mut layer_commitments = Vec::new();
         let mut layer_codeword_len = domain_size;
         for (i, layer) in proof.layers.iter().enumerate() {
             // TODO: batch merkle tree proofs
             // get the merkle root from the first merkle path
-            let layer_commitment = Output::<D>::from_slice(&layer.commitment).clone();
-            public_coin.reseed(&layer_commitment.deref());
+            public_coin.reseed(TranscriptLabel::FriLayerCommitment, &layer.commitment);
             let alpha = public_coin.draw();
             layer_alphas.push(alpha);
-            layer_commitments.push(layer_commitment);
+            // the length check above ensures the cap is made of whole digests
+            layer_commitments.push(parse_cap::<D>(&layer.commitment).unwrap());
 
+            let folding_factor = options.folding_factor(i);
             if i != proof.layers.len() - 1 && layer_codeword_len % folding_factor != 0 {
-                return Err(VerificationError::CodewordTruncation(
-                    layer_codeword_len,
-                    folding_factor,
-                    i,
-                ));
+                return Err(CodewordTruncation(layer_codeword_len, folding_factor, i));
             }
 
             layer_codeword_len /= folding_factor;
         }
 
-        let remainder_root = Output::<D>::from_slice(&proof.remainder_commitment).clone();
-        public_coin.reseed(&remainder_root.deref());
-        let remainder_alpha = public_coin.draw();
-        layer_alphas.push(remainder_alpha);
-        layer_commitments.push(remainder_root);
+        if options.remainder_coefficients {
+            public_coin.reseed(TranscriptLabel::FriRemainder, &proof.remainder);
+        } else {
+            public_coin.reseed(
+                TranscriptLabel::FriLayerCommitment,
+                &proof.remainder_commitment,
+            );
+            let remainder_alpha = public_coin.draw();
+            layer_alphas.push(remainder_alpha);
+            layer_commitments.push(parse_cap::<D>(&proof.remainder_commitment).unwrap());
+        }
 
         Ok(FriVerifier {
             options,
@@ -318,102 +701,158 @@ impl<F: GpuField, D: Digest> FriVerifier<F, D> {
         })
     }
 
-    pub fn verify_generic<const N: usize>(
-        self,
-        positions: &[usize],
-        evaluations: &[F],
-    ) -> Result<(), VerificationError> {
-        let domain_offset = self.domain.coset_offset();
-        let folding_domain = Radix2EvaluationDomain::new(N).unwrap();
+    pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
+        if positions.len() != evaluations.len() {
+            return Err(VerificationError::NumPositionEvaluationMismatch);
+        }
 
-        let mut layers = self.proof.layers.into_iter();
+        let domain_offset = self.domain.coset_offset();
         let mut layer_alphas = self.layer_alphas.into_iter();
         let mut layer_commitments = self.layer_commitments.into_iter();
-        let mut positions = positions.to_vec();
-        let mut evaluations = evaluations.to_vec();
+
+        // query positions of every layer are known upfront so the coset
+        // offsets of all queried chunks can be inverted in a single batch
+        let mut layer_positions = Vec::new();
+        let mut offset_invs = Vec::new();
         let mut domain_size = self.domain.size();
-        let mut domain_generator = self.domain.group_gen();
+        let mut folded_positions = positions.to_vec();
+        for i in 0..self.proof.layers.len() {
+            let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
+            domain_size /= self.options.folding_factor(i);
+            folded_positions = fold_positions(&folded_positions, domain_size);
+            offset_invs.extend(folded_positions.iter().map(|&p| domain.element(p)));
+            layer_positions.push(folded_positions.clone());
+        }
+        batch_inversion(&mut offset_invs);
 
         // verify all layers
-        for i in 0..self.options.num_layers(domain_size) {
-            let folded_positions = fold_positions(&positions, domain_size / N);
+        let mut positions = positions;
+        let mut evaluations = evaluations.to_vec();
+        let mut domain_size = self.domain.size();
+        let mut remaining_offset_invs = offset_invs.as_slice();
+        for (i, layer) in self.proof.layers.iter().enumerate() {
             let layer_alpha = layer_alphas.next().unwrap();
             let layer_commitment = layer_commitments.next().unwrap();
+            let folded_positions: &[usize] = &layer_positions[i];
+            let (offset_invs, rest) = remaining_offset_invs.split_at(folded_positions.len());
+            remaining_offset_invs = rest;
+            let folding_factor = self.options.folding_factor(i);
+            let verify_layer = match folding_factor {
+                2 => verify_layer::<F, D, 2>,
+                4 => verify_layer::<F, D, 4>,
+                8 => verify_layer::<F, D, 8>,
+                16 => verify_layer::<F, D, 16>,
+                folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+            };
+            evaluations = verify_layer(
+                i,
+                layer,
+                &layer_commitment,
+                self.options.merkle_cap_height,
+                layer_alpha,
+                positions,
+                folded_positions,
+                offset_invs,
+                &evaluations,
+                domain_size,
+            )?;
+            positions = folded_positions;
+            domain_size /= folding_factor;
+        }
 
-            // TODO: change assert to error. Check remainder
-            let layer = layers.next().unwrap();
-            let (chunks, _) = &layer.values.as_chunks::<N>();
-            assert_eq!(chunks.len(), folded_positions.len());
-
-            // verify the layer values against the layer's commitment
-            for (j, position) in folded_positions.iter().enumerate() {
-                let proof = layer.proofs[j].parse::<D>();
-                let expected_leaf = &proof[0];
-                let chunk = chunks[j];
-                let mut chunk_bytes = Vec::with_capacity(chunk.compressed_size());
-                chunk.serialize_compressed(&mut chunk_bytes).unwrap();
-                let actual_leaf = D::new_with_prefix(&chunk_bytes).finalize();
-
-                if *expected_leaf != actual_leaf {
-                    return Err(VerificationError::LayerCommitmentInvalid(i));
+        if self.options.remainder_coefficients {
+            // the number of coefficients was checked against the degree bound
+            let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
+            for (&position, evaluation) in positions.iter().zip(evaluations) {
+                let x = F::from(domain.element(position));
+                if horner_evaluate(&self.proof.remainder, &x) != evaluation {
+                    return Err(VerificationError::InvalidRemainderDegreeRespectingProjection);
                 }
-
-                MerkleTree::<D>::verify(&layer_commitment, &proof, *position)
-                    .map_err(|_| VerificationError::LayerCommitmentInvalid(i))?
-            }
-
-            let query_values = get_query_values(chunks, &positions, &folded_positions, domain_size);
-            if evaluations != query_values {
-                return Err(VerificationError::InvalidDegreeRespectingProjection(i));
             }
-
-            let polys = chunks
-                .iter()
-                .zip(&folded_positions)
-                .map(|(chunk, position)| {
-                    let offset = domain_offset * domain_generator.pow([*position as u64]);
-                    let domain = folding_domain.get_coset(offset).unwrap();
-                    DensePolynomial::from_coefficients_vec(domain.ifft(chunk))
-                });
-
-            // prepare for next layer
-            evaluations = polys.map(|poly| poly.evaluate(&layer_alpha)).collect();
-            positions = folded_positions;
-            domain_generator = domain_generator.pow([N as u64]);
-            domain_size /= N;
+            return Ok(());
         }
 
-        for (position, evaluation) in positions.into_iter().zip(evaluations) {
+        for (&position, evaluation) in positions.iter().zip(evaluations) {
             if self.proof.remainder[position] != evaluation {
                 return Err(VerificationError::InvalidRemainderDegreeRespectingProjection);
             }
         }
 
-        verify_remainder::<F, D, N>(
+        let verify_remainder = match self.options.folding_factor {
+            2 => verify_remainder::<F, D, 2>,
+            4 => verify_remainder::<F, D, 4>,
+            8 => verify_remainder::<F, D, 8>,
+            16 => verify_remainder::<F, D, 16>,
+            folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+        };
+        verify_remainder(
             layer_commitments.next().unwrap(),
+            self.options.merkle_cap_height,
             self.proof.remainder,
             domain_size - 1,
         )
     }
+}
 
-    pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
-        if positions.len() != evaluations.len() {
-            return Err(VerificationError::NumPositionEvaluationMismatch);
-        }
+/// Checks the queried values of a layer folded by `N` against the layer's
+/// commitment and the evaluations from the previous layer. Queries are
+/// opened at `folded_positions` and the inverse of the coset offset of each
+/// opened chunk is given by `offset_invs`. Returns the evaluations of the
+/// queries in the next layer.
+#[allow(clippy::too_many_arguments)]
+fn verify_layer<F: GpuField, D: Digest, const N: usize>(
+    i: usize,
+    layer: &FriProofLayer<F>,
+    layer_commitment: &[Output<D>],
+    cap_height: usize,
+    layer_alpha: F,
+    positions: &[usize],
+    folded_positions: &[usize],
+    offset_invs: &[F::FftField],
+    evaluations: &[F],
+    domain_size: usize,
+) -> Result<Vec<F>, VerificationError> {
+    let folding_domain = Radix2EvaluationDomain::new(N).unwrap();
+
+    let (chunks, remainder) = &layer.values.as_chunks::<N>();
+    if !remainder.is_empty()
+        || chunks.len() != folded_positions.len()
+        || layer.proofs.len() != folded_positions.len()
+    {
+        return Err(VerificationError::LayerCommitmentInvalid(i));
+    }
 
-        match self.options.folding_factor {
-            2 => self.verify_generic::<2>(positions, evaluations),
-            4 => self.verify_generic::<4>(positions, evaluations),
-            8 => self.verify_generic::<8>(positions, evaluations),
-            16 => self.verify_generic::<16>(positions, evaluations),
-            // TODO: move this to options
-            folding_factor => unreachable!("folding factor {folding_factor} not supported"),
-        }
+    // verify the layer values against the layer's commitment
+    MerkleProof::verify_batch_with_cap::<D, F, _>(
+        &layer.proofs,
+        layer_commitment,
+        chunks,
+        folded_positions,
+        path_len(domain_size / N, cap_height),
+    )
+    .map_err(|_| VerificationError::LayerCommitmentInvalid(i))?;
+
+    let query_values = get_query_values(chunks, positions, folded_positions, domain_size);
+    if evaluations != query_values {
+        return Err(VerificationError::InvalidDegreeRespectingProjection(i));
     }
+
+    // interpolating over the subgroup rather than the coset of each chunk
+    // gives `p(x / offset)` so no inversions are needed here
+    let evaluations = chunks
+        .iter()
+        .zip(offset_invs)
+        .map(|(chunk, &offset_inv)| {
+            let coeffs = folding_domain.ifft(chunk);
+            horner_evaluate(&coeffs, &(layer_alpha * F::from(offset_inv)))
+        })
+        .collect();
+    Ok(evaluations)
 }
 
 fn verify_remainder<F: GpuField, D: Digest, const N: usize>(
-    commitment: Output<D>,
+    cap: Vec<Output<D>>,
+    cap_height: usize,
     mut remainder_evals: Vec<F>,
     max_degree: usize,
 ) -> Result<(), VerificationError> {
@@ -424,15 +863,12 @@ fn verify_remainder<F: GpuField, D: Digest, const N: usize>(
     let interleaved_evals: Vec<[F; N]> = interleave(&remainder_evals);
     let hashed_evals = interleaved_evals
         .into_iter()
-        .map(|chunk| {
-            let mut buff = Vec::with_capacity(chunk.compressed_size());
-            chunk.serialize_compressed(&mut buff).unwrap();
-            D::new_with_prefix(&buff).finalize()
-        })
+        .map(|chunk| hash_leaf::<D, F>(chunk))
         .collect();
-    let remainder_merkle_tree = MerkleTree::<D>::new(hashed_evals).unwrap();
+    let remainder_merkle_tree = MerkleTree::<D>::new(hashed_evals)
+        .map_err(|_| VerificationError::RemainderCommitmentInvalid)?;
 
-    if commitment != *remainder_merkle_tree.root() {
+    if cap != remainder_merkle_tree.cap(cap_height) {
         return Err(VerificationError::RemainderCommitmentInvalid);
     }
 
@@ -456,11 +892,15 @@ fn verify_remainder<F: GpuField, D: Digest, const N: usize>(
 }
 
 pub trait ProverChannel<F: GpuField> {
-    type Digest: Digest;
+    type Digest: Digest + Send;
 
-    fn commit_fri_layer(&mut self, layer_root: &Output<Self::Digest>);
+    /// Commits to a layer with the cap of its Merkle tree
+    fn commit_fri_layer(&mut self, layer_cap: &[Output<Self::Digest>]);
 
     fn draw_fri_alpha(&mut self) -> F;
+
+    /// Sends the coefficients of the remainder in the clear
+    fn send_fri_remainder(&mut self, coeffs: &[F]);
 }
 
 /// Performs a degree respecting projection (drp) on polynomial evaluations.
@@ -502,10 +942,22 @@ pub fn apply_drp<F: GpuField>(
     domain_offset: F::FftField,
     alpha: F,
     folding_factor: usize,
+) -> GpuVec<F> {
+    apply_drp_on_device(evals, domain_offset, alpha, folding_factor, 0)
+}
+
+/// Like [apply_drp] but FFTs run on GPU `device` (modulo the number of
+/// devices) set with `gpu_poly::prelude::set_devices`
+fn apply_drp_on_device<F: GpuField>(
+    evals: GpuVec<F>,
+    domain_offset: F::FftField,
+    alpha: F,
+    folding_factor: usize,
+    device: usize,
 ) -> GpuVec<F> {
     let n = evals.len();
     let domain = Radix2EvaluationDomain::new_coset(n, domain_offset).unwrap();
-    let coeffs = ifft(evals, domain);
+    let coeffs = ifft(evals, domain, device);
 
     let alpha_powers = (0..folding_factor)
         .map(|i| alpha.pow([i as u64]))
@@ -526,14 +978,26 @@ pub fn apply_drp<F: GpuField>(
     let drp_domain = Radix2EvaluationDomain::new_coset(n / folding_factor, drp_offset).unwrap();
 
     // return the drp evals
-    fft(drp_coeffs, drp_domain)
+    fft(drp_coeffs, drp_domain, device)
 }
 
-fn ifft<F: GpuField>(evals: GpuVec<F>, domain: Radix2EvaluationDomain<F::FftField>) -> GpuVec<F> {
+/// Returns the planner of GPU `device` modulo the number of devices
+#[cfg(feature = "gpu")]
+fn device_planner(device: usize) -> &'static Planner {
+    let planners = device_planners();
+    planners[device % planners.len()]
+}
+
+#[allow(unused_variables)]
+fn ifft<F: GpuField>(
+    evals: GpuVec<F>,
+    domain: Radix2EvaluationDomain<F::FftField>,
+    device: usize,
+) -> GpuVec<F> {
     #[cfg(feature = "gpu")]
-    if domain.size() >= GpuFft::<F>::MIN_SIZE {
+    if use_gpu_fft::<F>(domain.size()) {
         let mut coeffs = evals;
-        let mut ifft = GpuIfft::from(domain);
+        let mut ifft = device_planner(device).plan_ifft(domain);
         ifft.encode(&mut coeffs);
         ifft.execute();
         return coeffs;
@@ -543,11 +1007,16 @@ fn ifft<F: GpuField>(evals: GpuVec<F>, domain: Radix2EvaluationDomain<F::FftFiel
     coeffs.to_vec_in(PageAlignedAllocator)
 }
 
-fn fft<F: GpuField>(coeffs: GpuVec<F>, domain: Radix2EvaluationDomain<F::FftField>) -> GpuVec<F> {
+#[allow(unused_variables)]
+fn fft<F: GpuField>(
+    coeffs: GpuVec<F>,
+    domain: Radix2EvaluationDomain<F::FftField>,
+    device: usize,
+) -> GpuVec<F> {
     #[cfg(feature = "gpu")]
-    if domain.size() >= GpuFft::<F>::MIN_SIZE {
+    if use_gpu_fft::<F>(domain.size()) {
         let mut evals = coeffs;
-        let mut fft = GpuFft::from(domain);
+        let mut fft = device_planner(device).plan_fft(domain);
         fft.encode(&mut evals);
         fft.execute();
         return evals;
@@ -557,7 +1026,7 @@ fn fft<F: GpuField>(coeffs: GpuVec<F>, domain: Radix2EvaluationDomain<F::FftFiel
     evals.to_vec_in(PageAlignedAllocator)
 }
 
-fn fold_positions(positions: &[usize], max: usize) -> Vec<usize> {
+pub(crate) fn fold_positions(positions: &[usize], max: usize) -> Vec<usize> {
     let mut res = positions
         .iter()
         .map(|pos| pos % max)
@@ -568,7 +1037,7 @@ fn fold_positions(positions: &[usize], max: usize) -> Vec<usize> {
 }
 
 // from winterfell
-fn get_query_values<F: GpuField, const N: usize>(
+pub(crate) fn get_query_values<F: GpuField, const N: usize>(
     chunks: &[[F; N]],
     positions: &[usize],
     folded_positions: &[usize],
@@ -579,8 +1048,7 @@ fn get_query_values<F: GpuField, const N: usize>(
         .iter()
         .map(|position| {
             let i = folded_positions
-                .iter()
-                .position(|&v| v == position % stride_len)
+                .binary_search(&(position % stride_len))
                 .unwrap();
             chunks[i][position / stride_len]
         })
@@ -588,23 +1056,23 @@ fn get_query_values<F: GpuField, const N: usize>(
 }
 
 fn query_layer<F: GpuField, D: Digest, const N: usize>(
-    layer: &FriLayer<F, D>,
+    tree: &MerkleTree<D>,
+    cap_height: usize,
+    evaluations: &[F],
     positions: &[usize],
 ) -> FriProofLayer<F> {
     let proofs = positions
         .iter()
         .map(|pos| {
-            layer
-                .tree
-                .prove(*pos)
+            tree.prove_with_cap(*pos, cap_height)
                 .expect("failed to generate Merkle proof")
         })
         .collect::<Vec<MerkleProof>>();
     let mut values: Vec<[F; N]> = Vec::new();
     for &position in positions {
         let i = position * N;
-        let chunk = &layer.evaluations[i..i + N];
+        let chunk = &evaluations[i..i + N];
         values.push(chunk.try_into().unwrap());
     }
-    FriProofLayer::new(values, proofs, layer.tree.root().to_vec())
+    FriProofLayer::new(values, proofs, cap_bytes::<D>(tree.cap(cap_height)))
 }
@@ -0,0 +1,104 @@
+//! Bit decomposition and range checks of values stored in the trace.
+//!
+//! A [BitColumns] instance stores a value as `num_bits` consecutive columns
+//! that each hold one bit, least significant bit first. Constraining every
+//! column to be binary range checks the value to `[0, 2^num_bits)`:
+//!
+//! ```text
+//! b_i * (b_i - 1) = 0
+//! value = Σ 2^i * b_i
+//! ```
+//!
+//! Bits of a word can be combined with [xor], [rotate_right] and
+//! [shift_right] and packed back into a value with [pack]. The value of a
+//! packed word is a linear combination of its bits so it doesn't need a
+//! column of its own.
+use crate::constraint::is_binary;
+use crate::Column;
+use crate::Constraint;
+use ark_ff::Zero;
+use gpu_poly::GpuField;
+use std::ops::Range;
+
+/// Location of the bits of a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitColumns {
+    /// Column holding the least significant bit
+    pub offset: usize,
+    pub num_bits: usize,
+}
+
+impl BitColumns {
+    pub const fn new(offset: usize, num_bits: usize) -> Self {
+        BitColumns { offset, num_bits }
+    }
+
+    /// Columns holding the bits
+    pub fn columns(&self) -> Range<usize> {
+        self.offset..self.offset + self.num_bits
+    }
+
+    /// The bits on the row `row` rows after the current row
+    pub fn bits<F: GpuField>(&self, row: usize) -> Vec<Constraint<F>> {
+        self.columns().map(|column| column.offset(row)).collect()
+    }
+
+    /// The value packed from its bits on the row `row` rows after the current
+    /// row
+    pub fn value<F: GpuField>(&self, row: usize) -> Constraint<F> {
+        pack(&self.bits(row))
+    }
+
+    /// Constrains every bit on the row `row` rows after the current row to be
+    /// zero or one
+    pub fn range_constraints<F: GpuField>(&self, row: usize) -> Vec<Constraint<F>> {
+        self.bits(row).iter().map(is_binary).collect()
+    }
+
+    /// Constrains `value` to equal the bits on the row `row` rows after the
+    /// current row. Together with [BitColumns::range_constraints] this range
+    /// checks `value` to `[0, 2^num_bits)`.
+    pub fn range_check<F: GpuField>(&self, value: Constraint<F>, row: usize) -> Constraint<F> {
+        value - self.value(row)
+    }
+
+    /// Writes the bits of `value` to the instance's cells of `row`
+    pub fn write<F: GpuField>(&self, row: &mut [F], value: u64) {
+        assert!(
+            self.num_bits >= 64 || value >> self.num_bits == 0,
+            "{value} doesn't fit in {} bits",
+            self.num_bits
+        );
+        for (i, cell) in row[self.columns()].iter_mut().enumerate() {
+            *cell = F::from((value >> i) & 1);
+        }
+    }
+}
+
+/// Packs little-endian bits into a single value
+pub fn pack<F: GpuField>(bits: &[Constraint<F>]) -> Constraint<F> {
+    bits.iter()
+        .enumerate()
+        .fold(Constraint::zero(), |acc, (i, bit)| {
+            acc + bit * F::from(1u64 << i)
+        })
+}
+
+/// XOR of two bits. Has degree two.
+pub fn xor<F: GpuField>(a: &Constraint<F>, b: &Constraint<F>) -> Constraint<F> {
+    a + b - (a * b) * F::from(2u64)
+}
+
+/// Bits of a word rotated right by `n`
+pub fn rotate_right<F: GpuField>(bits: &[Constraint<F>], n: usize) -> Vec<Constraint<F>> {
+    (0..bits.len())
+        .map(|i| bits[(i + n) % bits.len()].clone())
+        .collect()
+}
+
+/// Bits of a word shifted right by `n`
+pub fn shift_right<F: GpuField>(bits: &[Constraint<F>], n: usize) -> Vec<Constraint<F>> {
+    (0..bits.len())
+        .map(|i| bits.get(i + n).cloned().unwrap_or_else(Constraint::zero))
+        .collect()
+}
@@ -0,0 +1,426 @@
+//! Chiplet computing AND, XOR and OR of 32-bit values.
+//!
+//! An operation occupies [CYCLE_LENGTH] rows. Each row decomposes four bits
+//! of both operands starting from the most significant bits and accumulates
+//! the operands and result:
+//!
+//! ```text
+//! a' = 16 * a + Σ 2^i * a_i'
+//! b' = 16 * b + Σ 2^i * b_i'
+//! c' = 16 * c + Σ 2^i * op(a_i', b_i')
+//! ```
+//!
+//! The last row of a cycle holds the full operands and result. Rows of other
+//! tables request operations and a running product links the requests to the
+//! results of the chiplet. Cycles that aren't requested are marked as
+//! padding.
+use crate::challenges::Challenges;
+use crate::constraint::is_binary;
+use crate::constraint::Challenge;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+
+/// Number of operand bits decomposed on each row
+pub const BITS_PER_ROW: usize = 4;
+
+/// Number of trace rows used by a single operation
+pub const CYCLE_LENGTH: usize = 32 / BITS_PER_ROW;
+
+/// Number of base trace columns used by the chiplet
+pub const NUM_BASE_COLUMNS: usize = 6 + 2 * BITS_PER_ROW;
+
+/// Number of periodic columns used by the chiplet
+pub const NUM_PERIODIC_COLUMNS: usize = 2;
+
+/// Number of extension trace columns used by the chiplet
+pub const NUM_EXTENSION_COLUMNS: usize = 2;
+
+/// Number of challenges used by the chiplet
+pub const NUM_CHALLENGES: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseOp {
+    And = 0,
+    Xor = 1,
+    Or = 2,
+}
+
+impl BitwiseOp {
+    pub fn apply(&self, a: u32, b: u32) -> u32 {
+        match self {
+            Self::And => a & b,
+            Self::Xor => a ^ b,
+            Self::Or => a | b,
+        }
+    }
+}
+
+/// Columns of the table requesting operations
+#[derive(Clone, Copy, Debug)]
+pub struct RequestColumns {
+    /// One on rows that request an operation and zero otherwise
+    pub is_request: usize,
+    /// The [BitwiseOp] as a field element
+    pub op: usize,
+    pub a: usize,
+    pub b: usize,
+    pub result: usize,
+}
+
+/// Location of the chiplet's columns and challenges
+#[derive(Clone, Copy, Debug)]
+pub struct BitwiseColumns {
+    pub requests: RequestColumns,
+    /// Index of the first of the chiplet's [NUM_BASE_COLUMNS] base columns
+    pub base_offset: usize,
+    /// Index of the first of the [NUM_PERIODIC_COLUMNS] periodic columns
+    pub periodic_offset: usize,
+    /// Index of the first of the [NUM_EXTENSION_COLUMNS] extension columns.
+    /// Extension columns are indexed after all base columns.
+    pub extension_offset: usize,
+    /// Index of the first of the [NUM_CHALLENGES] challenges
+    pub challenge_offset: usize,
+}
+
+impl BitwiseColumns {
+    /// One for XOR operations. Constant within a cycle.
+    pub fn is_xor(&self) -> usize {
+        self.base_offset
+    }
+
+    /// One for OR operations. Constant within a cycle.
+    pub fn is_or(&self) -> usize {
+        self.base_offset + 1
+    }
+
+    /// One for cycles that aren't requested. Constant within a cycle.
+    pub fn is_padding(&self) -> usize {
+        self.base_offset + 2
+    }
+
+    /// Accumulated value of the first operand
+    pub fn a(&self) -> usize {
+        self.base_offset + 3
+    }
+
+    /// Accumulated value of the second operand
+    pub fn b(&self) -> usize {
+        self.base_offset + 4
+    }
+
+    /// Accumulated value of the result
+    pub fn result(&self) -> usize {
+        self.base_offset + 5
+    }
+
+    /// Bit `i` of the first operand's bits on this row
+    pub fn a_bit(&self, i: usize) -> usize {
+        assert!(i < BITS_PER_ROW);
+        self.base_offset + 6 + i
+    }
+
+    /// Bit `i` of the second operand's bits on this row
+    pub fn b_bit(&self, i: usize) -> usize {
+        assert!(i < BITS_PER_ROW);
+        self.base_offset + 6 + BITS_PER_ROW + i
+    }
+
+    /// Periodic column that is one on the first row of each cycle
+    pub fn is_first_row(&self) -> usize {
+        self.periodic_offset
+    }
+
+    /// Periodic column that is one on the last row of each cycle
+    pub fn is_last_row(&self) -> usize {
+        self.periodic_offset + 1
+    }
+
+    /// Extension column holding the running product of requests
+    pub fn request_permutation(&self) -> usize {
+        self.extension_offset
+    }
+
+    /// Extension column holding the running product of the chiplet's results
+    pub fn chiplet_permutation(&self) -> usize {
+        self.extension_offset + 1
+    }
+
+    /// Challenge `z` of the permutation argument
+    pub fn z(&self) -> usize {
+        self.challenge_offset
+    }
+
+    /// Challenges used to combine the operation, operands and result
+    pub fn alphas(&self) -> [usize; 4] {
+        [1, 2, 3, 4].map(|i| self.challenge_offset + i)
+    }
+
+    pub fn periodic_columns<F: GpuField>(&self) -> Vec<Vec<F>> {
+        let mut is_first_row = vec![F::zero(); CYCLE_LENGTH];
+        let mut is_last_row = vec![F::zero(); CYCLE_LENGTH];
+        is_first_row[0] = F::one();
+        is_last_row[CYCLE_LENGTH - 1] = F::one();
+        vec![is_first_row, is_last_row]
+    }
+
+    /// `z - (α_0 * op + α_1 * a + α_2 * b + α_3 * result)`
+    fn lookup_term<F: GpuField>(
+        &self,
+        op: Constraint<F>,
+        a: Constraint<F>,
+        b: Constraint<F>,
+        result: Constraint<F>,
+    ) -> Constraint<F> {
+        let [op_alpha, a_alpha, b_alpha, result_alpha] =
+            self.alphas().map(|i| i.get_challenge::<F>());
+        self.z().get_challenge::<F>()
+            - op_alpha * op
+            - a_alpha * a
+            - b_alpha * b
+            - result_alpha * result
+    }
+
+    /// Factor the current row contributes to the request running product
+    fn request_factor<F: GpuField>(&self) -> Constraint<F> {
+        let RequestColumns {
+            is_request,
+            op,
+            a,
+            b,
+            result,
+        } = self.requests;
+        let is_request = is_request.curr::<F>();
+        let term = self.lookup_term(op.curr(), a.curr(), b.curr(), result.curr());
+        Constraint::from(F::one()) - &is_request + is_request * term
+    }
+
+    /// Factor the current row contributes to the chiplet running product
+    fn chiplet_factor<F: GpuField>(&self) -> Constraint<F> {
+        let one = F::one();
+        let op = self.is_xor().curr::<F>() + self.is_or().curr::<F>() * (one + one);
+        let term = self.lookup_term(op, self.a().curr(), self.b().curr(), self.result().curr());
+        let is_result =
+            self.is_last_row().curr::<F>() * (Constraint::from(one) - self.is_padding().curr());
+        Constraint::from(one) - &is_result + is_result * term
+    }
+
+    /// Packs the bits of a row as a little-endian value
+    fn pack<F: GpuField>(bits: impl Iterator<Item = Constraint<F>>) -> Constraint<F> {
+        bits.enumerate().fold(Constraint::zero(), |acc, (i, bit)| {
+            acc + bit * F::from(1u64 << i)
+        })
+    }
+
+    /// Packed result of the operation on the bits of a row
+    fn result_bits<F: GpuField>(&self, next: bool) -> Constraint<F> {
+        let cell = |column: usize| -> Constraint<F> {
+            if next {
+                column.next()
+            } else {
+                column.curr()
+            }
+        };
+        let is_xor = cell(self.is_xor());
+        let is_or = cell(self.is_or());
+        Self::pack((0..BITS_PER_ROW).map(|i| {
+            let a = cell(self.a_bit(i));
+            let b = cell(self.b_bit(i));
+            let and = &a * &b;
+            // XOR is `a + b - 2ab` and OR is `a + b - ab`
+            &and + &is_xor * (&a + &b - &and * F::from(3u64))
+                + &is_or * (a + b - and * F::from(2u64))
+        }))
+    }
+
+    fn bit_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..BITS_PER_ROW).flat_map(|i| [self.a_bit(i), self.b_bit(i)])
+    }
+
+    pub fn boundary_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let mut constraints = self
+            .bit_columns()
+            .chain([self.is_xor(), self.is_or(), self.is_padding()])
+            .map(|column| is_binary(column.curr::<F>()))
+            .collect::<Vec<_>>();
+        constraints.push(self.is_xor().curr() * self.is_or().curr());
+        constraints.push(self.request_permutation().curr() - F::one());
+        constraints.push(self.chiplet_permutation().curr() - F::one());
+        constraints
+    }
+
+    pub fn transition_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let one = F::one();
+        let sixteen = F::from(1u64 << BITS_PER_ROW);
+        let is_first_row = self.is_first_row().curr::<F>();
+        let is_last_row = self.is_last_row().curr::<F>();
+        let is_not_last_row = Constraint::from(one) - &is_last_row;
+
+        let mut constraints = self
+            .bit_columns()
+            .chain([self.is_xor(), self.is_or(), self.is_padding()])
+            .map(|column| is_binary(column.next::<F>()))
+            .collect::<Vec<_>>();
+        constraints.push(self.is_xor().next() * self.is_or().next());
+
+        // the operation is the same for every row of a cycle
+        for column in [self.is_xor(), self.is_or(), self.is_padding()] {
+            constraints.push(&is_not_last_row * (column.next() - column.curr()));
+        }
+
+        // accumulate the operands and result. The first row of a cycle starts
+        // from the bits of that row.
+        let a_bits = |next: bool| {
+            Self::pack((0..BITS_PER_ROW).map(|i| {
+                if next {
+                    self.a_bit(i).next()
+                } else {
+                    self.a_bit(i).curr()
+                }
+            }))
+        };
+        let b_bits = |next: bool| {
+            Self::pack((0..BITS_PER_ROW).map(|i| {
+                if next {
+                    self.b_bit(i).next()
+                } else {
+                    self.b_bit(i).curr()
+                }
+            }))
+        };
+        constraints.push(&is_first_row * (self.a().curr() - a_bits(false)));
+        constraints.push(&is_first_row * (self.b().curr() - b_bits(false)));
+        constraints.push(&is_first_row * (self.result().curr() - self.result_bits(false)));
+        constraints
+            .push(&is_not_last_row * (self.a().next() - self.a().curr() * sixteen - a_bits(true)));
+        constraints
+            .push(&is_not_last_row * (self.b().next() - self.b().curr() * sixteen - b_bits(true)));
+        constraints.push(
+            &is_not_last_row
+                * (self.result().next() - self.result().curr() * sixteen - self.result_bits(true)),
+        );
+
+        // running products
+        constraints.push(
+            self.request_permutation().next()
+                - self.request_permutation().curr() * self.request_factor(),
+        );
+        constraints.push(
+            self.chiplet_permutation().next()
+                - self.chiplet_permutation().curr() * self.chiplet_factor(),
+        );
+        constraints
+    }
+
+    pub fn terminal_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        vec![
+            self.request_permutation().curr() * self.request_factor()
+                - self.chiplet_permutation().curr() * self.chiplet_factor(),
+        ]
+    }
+}
+
+/// Generates the chiplet's [NUM_BASE_COLUMNS] base columns for the requested
+/// operations. Cycles after the requests are padding.
+pub fn gen_base_columns<F: GpuField>(
+    requests: &[(BitwiseOp, u32, u32)],
+    trace_len: usize,
+) -> Matrix<F> {
+    assert!(trace_len % CYCLE_LENGTH == 0);
+    let num_cycles = trace_len / CYCLE_LENGTH;
+    assert!(
+        requests.len() <= num_cycles,
+        "{} operations don't fit in a trace of length {trace_len}",
+        requests.len()
+    );
+
+    let mut columns = (0..NUM_BASE_COLUMNS)
+        .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for cycle in 0..num_cycles {
+        let (op, a, b, is_padding) = match requests.get(cycle) {
+            Some(&(op, a, b)) => (op, a, b, false),
+            None => (BitwiseOp::And, 0, 0, true),
+        };
+        let result = op.apply(a, b);
+        for row in 0..CYCLE_LENGTH {
+            // number of bits accumulated after this row
+            let shift = 32 - BITS_PER_ROW * (row + 1);
+            let mask = (1u64 << BITS_PER_ROW) - 1;
+            let a_bits = (a as u64 >> shift) & mask;
+            let b_bits = (b as u64 >> shift) & mask;
+            let mut values = vec![
+                F::from(op == BitwiseOp::Xor),
+                F::from(op == BitwiseOp::Or),
+                F::from(is_padding),
+                F::from(a as u64 >> shift),
+                F::from(b as u64 >> shift),
+                F::from(result as u64 >> shift),
+            ];
+            values.extend((0..BITS_PER_ROW).map(|i| F::from((a_bits >> i) & 1)));
+            values.extend((0..BITS_PER_ROW).map(|i| F::from((b_bits >> i) & 1)));
+            for (column, value) in columns.iter_mut().zip(values) {
+                column.push(value);
+            }
+        }
+    }
+    Matrix::new(columns)
+}
+
+/// Generates the running product columns of the chiplet. `base_columns` are
+/// all base columns of the trace including the requesting table's and the
+/// chiplet's.
+pub fn gen_extension_columns<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+    columns: BitwiseColumns,
+    base_columns: &Matrix<Fp>,
+    challenges: &Challenges<Fq>,
+) -> Matrix<Fq> {
+    let num_rows = base_columns.num_rows();
+    let z = challenges[columns.z()];
+    let alphas = columns.alphas().map(|i| challenges[i]);
+    let term = |values: [Fp; 4]| {
+        let mut term = z;
+        for (alpha, value) in alphas.iter().zip(&values) {
+            term -= *alpha * value;
+        }
+        term
+    };
+
+    let RequestColumns {
+        is_request,
+        op,
+        a,
+        b,
+        result,
+    } = columns.requests;
+    let mut request_product = Fq::one();
+    let mut chiplet_product = Fq::one();
+    let mut request_permutation = Vec::with_capacity_in(num_rows, PageAlignedAllocator);
+    let mut chiplet_permutation = Vec::with_capacity_in(num_rows, PageAlignedAllocator);
+    for row in 0..num_rows {
+        request_permutation.push(request_product);
+        chiplet_permutation.push(chiplet_product);
+        let cell = |column: usize| base_columns[column][row];
+        if cell(is_request).is_one() {
+            request_product *= term([op, a, b, result].map(cell));
+        }
+        let is_last_row = row % CYCLE_LENGTH == CYCLE_LENGTH - 1;
+        if is_last_row && cell(columns.is_padding()).is_zero() {
+            let op = cell(columns.is_xor()) + cell(columns.is_or()).double();
+            chiplet_product *= term([
+                op,
+                cell(columns.a()),
+                cell(columns.b()),
+                cell(columns.result()),
+            ]);
+        }
+    }
+    Matrix::new(vec![request_permutation, chiplet_permutation])
+}
@@ -0,0 +1,314 @@
+//! Chiplet hashing [RATE] field elements with Rescue-Prime.
+//!
+//! Each hash is a Rescue-Prime permutation that occupies [CYCLE_LENGTH] rows
+//! (see [rescue]). The chiplet computes [RescuePrime::hash_elements] of the
+//! [RATE] inputs which are kept in extra columns for the whole cycle so the
+//! last row holds the inputs and the digest. Rows of other tables request
+//! hashes and a running product links the requests to the digests computed
+//! by the chiplet. This way an AIR can hash in any table with a single
+//! running product factor instead of the permutation's constraints.
+//!
+//! [rescue]: crate::gadgets::rescue
+use crate::challenges::Challenges;
+use crate::constraint::is_binary;
+use crate::constraint::Challenge;
+use crate::gadgets::rescue;
+use crate::gadgets::rescue::RescueColumns;
+use crate::gadgets::rescue::RescuePrime;
+use crate::gadgets::rescue::CAPACITY_RANGE;
+use crate::gadgets::rescue::DIGEST_RANGE;
+use crate::gadgets::rescue::DIGEST_SIZE;
+use crate::gadgets::rescue::RATE;
+use crate::gadgets::rescue::RATE_RANGE;
+use crate::gadgets::rescue::STATE_WIDTH;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+
+/// Number of trace rows used by a single hash
+pub const CYCLE_LENGTH: usize = rescue::CYCLE_LENGTH;
+
+/// Number of base trace columns used by the chiplet
+pub const NUM_BASE_COLUMNS: usize = rescue::NUM_COLUMNS + RATE + 1;
+
+/// Number of periodic columns used by the chiplet
+pub const NUM_PERIODIC_COLUMNS: usize = rescue::NUM_PERIODIC_COLUMNS;
+
+/// Number of extension trace columns used by the chiplet
+pub const NUM_EXTENSION_COLUMNS: usize = 2;
+
+/// Number of challenges used by the chiplet
+pub const NUM_CHALLENGES: usize = 1 + RATE + DIGEST_SIZE;
+
+/// Columns of the table requesting hashes
+#[derive(Clone, Copy, Debug)]
+pub struct HashRequestColumns {
+    /// One on rows that request a hash and zero otherwise
+    pub is_request: usize,
+    pub inputs: [usize; RATE],
+    pub digest: [usize; DIGEST_SIZE],
+}
+
+/// Location of the chiplet's columns and challenges
+#[derive(Clone, Copy, Debug)]
+pub struct HashColumns {
+    pub requests: HashRequestColumns,
+    /// Index of the first of the chiplet's [NUM_BASE_COLUMNS] base columns
+    pub base_offset: usize,
+    /// Index of the first of the [NUM_PERIODIC_COLUMNS] periodic columns
+    pub periodic_offset: usize,
+    /// Index of the first of the [NUM_EXTENSION_COLUMNS] extension columns.
+    /// Extension columns are indexed after all base columns.
+    pub extension_offset: usize,
+    /// Index of the first of the [NUM_CHALLENGES] challenges
+    pub challenge_offset: usize,
+}
+
+impl HashColumns {
+    /// Columns of the permutation
+    pub fn rescue(&self) -> RescueColumns {
+        RescueColumns {
+            trace_offset: self.base_offset,
+            periodic_offset: self.periodic_offset,
+        }
+    }
+
+    /// Column holding input `i` of the hash. Constant within a cycle.
+    pub fn input(&self, i: usize) -> usize {
+        assert!(i < RATE);
+        self.base_offset + rescue::NUM_COLUMNS + i
+    }
+
+    /// One for cycles that aren't requested. Constant within a cycle.
+    pub fn is_padding(&self) -> usize {
+        self.base_offset + rescue::NUM_COLUMNS + RATE
+    }
+
+    /// Extension column holding the running product of requests
+    pub fn request_permutation(&self) -> usize {
+        self.extension_offset
+    }
+
+    /// Extension column holding the running product of the chiplet's digests
+    pub fn chiplet_permutation(&self) -> usize {
+        self.extension_offset + 1
+    }
+
+    /// Challenge `z` of the permutation argument
+    pub fn z(&self) -> usize {
+        self.challenge_offset
+    }
+
+    /// Challenges used to combine the inputs and digest
+    pub fn alphas(&self) -> [usize; RATE + DIGEST_SIZE] {
+        let mut alphas = [0; RATE + DIGEST_SIZE];
+        for (i, alpha) in alphas.iter_mut().enumerate() {
+            *alpha = self.challenge_offset + 1 + i;
+        }
+        alphas
+    }
+
+    /// `z - Σ α_i * v_i` for the inputs followed by the digest
+    fn lookup_term<Fq: StarkExtensionOf<Fp>>(
+        &self,
+        values: impl IntoIterator<Item = Constraint<Fq>>,
+    ) -> Constraint<Fq> {
+        let mut term = self.z().get_challenge::<Fq>();
+        for (alpha, value) in self.alphas().iter().zip(values) {
+            term -= alpha.get_challenge::<Fq>() * value;
+        }
+        term
+    }
+
+    /// Factor the current row contributes to the request running product
+    fn request_factor<Fq: StarkExtensionOf<Fp>>(&self) -> Constraint<Fq> {
+        let HashRequestColumns {
+            is_request,
+            inputs,
+            digest,
+        } = self.requests;
+        let values = inputs.into_iter().chain(digest).map(|column| column.curr());
+        let is_request = is_request.curr::<Fq>();
+        Constraint::from(Fq::one()) - &is_request + is_request * self.lookup_term(values)
+    }
+
+    /// Factor the current row contributes to the chiplet running product
+    fn chiplet_factor<Fq: StarkExtensionOf<Fp>>(&self) -> Constraint<Fq> {
+        let one = Fq::one();
+        let rescue = self.rescue();
+        let values = (0..RATE)
+            .map(|i| self.input(i))
+            .chain(DIGEST_RANGE.map(|i| rescue.state(i)))
+            .map(|column| column.curr());
+        let is_last_round = Constraint::from(one) - rescue.round_flag().curr();
+        let is_digest = is_last_round * (Constraint::from(one) - self.is_padding().curr());
+        Constraint::from(one) - &is_digest + is_digest * self.lookup_term(values)
+    }
+
+    /// Constraints on the input state of a cycle. The capacity holds the
+    /// number of inputs and the rate holds the inputs.
+    fn input_constraints<Fq: StarkExtensionOf<Fp>>(&self, next: bool) -> Vec<Constraint<Fq>> {
+        let cell = |column: usize| -> Constraint<Fq> {
+            if next {
+                column.next()
+            } else {
+                column.curr()
+            }
+        };
+        let rescue = self.rescue();
+        let mut constraints = Vec::new();
+        for i in CAPACITY_RANGE {
+            let expected = if i == CAPACITY_RANGE.start {
+                Fq::from(RATE as u64)
+            } else {
+                Fq::zero()
+            };
+            constraints.push(cell(rescue.state(i)) - expected);
+        }
+        for (i, state_index) in RATE_RANGE.enumerate() {
+            constraints.push(cell(rescue.state(state_index)) - cell(self.input(i)));
+        }
+        constraints
+    }
+
+    pub fn boundary_constraints<Fq: StarkExtensionOf<Fp>>(&self) -> Vec<Constraint<Fq>> {
+        let mut constraints = self.input_constraints(false);
+        constraints.push(is_binary(self.is_padding().curr::<Fq>()));
+        constraints.push(self.request_permutation().curr() - Fq::one());
+        constraints.push(self.chiplet_permutation().curr() - Fq::one());
+        constraints
+    }
+
+    pub fn transition_constraints<Fq: StarkExtensionOf<Fp>>(
+        &self,
+        rescue_prime: &RescuePrime,
+    ) -> Vec<Constraint<Fq>> {
+        let rescue = self.rescue();
+        let round_flag = rescue.round_flag().curr::<Fq>();
+        let is_last_round = Constraint::from(Fq::one()) - &round_flag;
+        let mut constraints = rescue_prime.transition_constraints(rescue);
+
+        // the inputs and padding flag are constant within a cycle
+        let per_cycle = (0..RATE).map(|i| self.input(i)).chain([self.is_padding()]);
+        for column in per_cycle {
+            constraints.push(&round_flag * (column.next() - column.curr()));
+        }
+
+        // the next cycle starts a new hash
+        constraints.push(is_binary(self.is_padding().next::<Fq>()));
+        for constraint in self.input_constraints(true) {
+            constraints.push(&is_last_round * constraint);
+        }
+
+        // running products
+        constraints.push(
+            self.request_permutation().next()
+                - self.request_permutation().curr() * self.request_factor(),
+        );
+        constraints.push(
+            self.chiplet_permutation().next()
+                - self.chiplet_permutation().curr() * self.chiplet_factor(),
+        );
+        constraints
+    }
+
+    pub fn terminal_constraints<Fq: StarkExtensionOf<Fp>>(&self) -> Vec<Constraint<Fq>> {
+        vec![
+            self.request_permutation().curr() * self.request_factor()
+                - self.chiplet_permutation().curr() * self.chiplet_factor(),
+        ]
+    }
+}
+
+/// Generates the chiplet's [NUM_BASE_COLUMNS] base columns for the requested
+/// hashes. Cycles after the requests are padding and hash zeros.
+pub fn gen_base_columns(
+    rescue_prime: &RescuePrime,
+    requests: &[[Fp; RATE]],
+    trace_len: usize,
+) -> Matrix<Fp> {
+    assert!(trace_len % CYCLE_LENGTH == 0);
+    let num_cycles = trace_len / CYCLE_LENGTH;
+    assert!(
+        requests.len() <= num_cycles,
+        "{} hashes don't fit in a trace of length {trace_len}",
+        requests.len()
+    );
+
+    let mut columns = (0..NUM_BASE_COLUMNS)
+        .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for cycle in 0..num_cycles {
+        let (inputs, is_padding) = match requests.get(cycle) {
+            Some(inputs) => (*inputs, false),
+            None => ([Fp::zero(); RATE], true),
+        };
+        let mut state = [Fp::zero(); STATE_WIDTH];
+        state[CAPACITY_RANGE.start] = Fp::from(RATE as u64);
+        state[RATE_RANGE].copy_from_slice(&inputs);
+        for rescue_row in rescue_prime.permutation_trace(state) {
+            let row = rescue_row
+                .into_iter()
+                .chain(inputs)
+                .chain([Fp::from(is_padding)]);
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+    }
+    Matrix::new(columns)
+}
+
+/// Generates the running product columns of the chiplet. `base_columns` are
+/// all base columns of the trace including the requesting table's and the
+/// chiplet's.
+pub fn gen_extension_columns<Fq: StarkExtensionOf<Fp>>(
+    columns: HashColumns,
+    base_columns: &Matrix<Fp>,
+    challenges: &Challenges<Fq>,
+) -> Matrix<Fq> {
+    let num_rows = base_columns.num_rows();
+    let z = challenges[columns.z()];
+    let alphas = columns.alphas().map(|i| challenges[i]);
+    let term = |values: &[Fp]| {
+        let mut term = z;
+        for (alpha, value) in alphas.iter().zip(values) {
+            term -= *alpha * value;
+        }
+        term
+    };
+
+    let HashRequestColumns {
+        is_request,
+        inputs,
+        digest,
+    } = columns.requests;
+    let rescue = columns.rescue();
+    let chiplet_values = (0..RATE)
+        .map(|i| columns.input(i))
+        .chain(DIGEST_RANGE.map(|i| rescue.state(i)))
+        .collect::<Vec<usize>>();
+    let request_values = inputs.into_iter().chain(digest).collect::<Vec<usize>>();
+    let mut request_product = Fq::one();
+    let mut chiplet_product = Fq::one();
+    let mut request_permutation = Vec::with_capacity_in(num_rows, PageAlignedAllocator);
+    let mut chiplet_permutation = Vec::with_capacity_in(num_rows, PageAlignedAllocator);
+    for row in 0..num_rows {
+        request_permutation.push(request_product);
+        chiplet_permutation.push(chiplet_product);
+        let cell = |column: &usize| base_columns[*column][row];
+        if cell(&is_request).is_one() {
+            request_product *= term(&request_values.iter().map(cell).collect::<Vec<Fp>>());
+        }
+        let is_last_row = row % CYCLE_LENGTH == CYCLE_LENGTH - 1;
+        if is_last_row && cell(&columns.is_padding()).is_zero() {
+            chiplet_product *= term(&chiplet_values.iter().map(cell).collect::<Vec<Fp>>());
+        }
+    }
+    Matrix::new(vec![request_permutation, chiplet_permutation])
+}
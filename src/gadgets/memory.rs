@@ -0,0 +1,265 @@
+//! Read-only memory with a public segment in the style of Cairo.
+//!
+//! Memory accesses are `(address, value)` pairs. The execution writes them to
+//! the trace in the order they happen and the gadget adds a copy sorted by
+//! address. Constraints on the sorted copy check that addresses are
+//! contiguous and each address holds a single value:
+//!
+//! ```text
+//! (a'[i+1] - a'[i]) * (a'[i+1] - a'[i] - 1) = 0
+//! (v'[i+1] - v'[i]) * (a'[i+1] - a'[i] - 1) = 0
+//! ```
+//!
+//! A running product over the extension field checks the sorted copy is a
+//! permutation of the accesses:
+//!
+//! ```text
+//! p[i] = Π_{j<=i} (z - (a[j] + α * v[j])) / (z - (a'[j] + α * v'[j]))
+//! ```
+//!
+//! Cells of the public memory are written to the execution order columns as
+//! `(0, 0)` and only appear with their real address and value in the sorted
+//! columns. If there are `n` public cells the last element of the running
+//! product is `z^n / Π (z - (a + α * v))` over the public cells. The verifier
+//! computes this from the public inputs (see [PublicMemory::hint]) and it's
+//! supplied to the terminal constraint as a hint. This proves the public cells
+//! are in memory without revealing any of the other accesses.
+use crate::challenges::Challenges;
+use crate::constraint::Challenge;
+use crate::constraint::Hint;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_ff::batch_inversion;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+
+/// Number of base trace columns used by the gadget
+pub const NUM_BASE_COLUMNS: usize = 4;
+
+/// Number of extension trace columns used by the gadget
+pub const NUM_EXTENSION_COLUMNS: usize = 1;
+
+/// Number of challenges used by the gadget
+pub const NUM_CHALLENGES: usize = 2;
+
+/// Location of the gadget's columns, challenges and hint
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryColumns {
+    /// Index of the first of the [NUM_BASE_COLUMNS] base trace columns
+    pub base_offset: usize,
+    /// Index of the running product column. Extension columns are indexed
+    /// after all base columns.
+    pub extension_offset: usize,
+    /// Index of the first of the [NUM_CHALLENGES] challenges
+    pub challenge_offset: usize,
+    /// Index of the hint holding [PublicMemory::hint]
+    pub hint: usize,
+}
+
+impl MemoryColumns {
+    /// Column holding the address of each access in execution order
+    pub fn address(&self) -> usize {
+        self.base_offset
+    }
+
+    /// Column holding the value of each access in execution order
+    pub fn value(&self) -> usize {
+        self.base_offset + 1
+    }
+
+    /// Column holding the addresses of all accesses sorted by address
+    pub fn sorted_address(&self) -> usize {
+        self.base_offset + 2
+    }
+
+    /// Column holding the values of all accesses sorted by address
+    pub fn sorted_value(&self) -> usize {
+        self.base_offset + 3
+    }
+
+    /// Extension column holding the running product of the permutation
+    /// argument
+    pub fn permutation(&self) -> usize {
+        self.extension_offset
+    }
+
+    /// Challenge `z` of the permutation argument
+    pub fn z(&self) -> usize {
+        self.challenge_offset
+    }
+
+    /// Challenge `α` used to combine an address and value
+    pub fn alpha(&self) -> usize {
+        self.challenge_offset + 1
+    }
+
+    /// `z - (address + α * value)` for an access
+    fn access_term<F: GpuField>(
+        &self,
+        address: Constraint<F>,
+        value: Constraint<F>,
+    ) -> Constraint<F> {
+        self.z().get_challenge::<F>() - address - self.alpha().get_challenge::<F>() * value
+    }
+
+    pub fn boundary_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let access = self.access_term(self.address().curr(), self.value().curr());
+        let sorted_access =
+            self.access_term(self.sorted_address().curr(), self.sorted_value().curr());
+        vec![self.permutation().curr() * sorted_access - access]
+    }
+
+    pub fn transition_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let one = F::one();
+        let address_delta = self.sorted_address().next() - self.sorted_address().curr();
+        let value_delta = self.sorted_value().next() - self.sorted_value().curr();
+        let access = self.access_term(self.address().next(), self.value().next());
+        let sorted_access =
+            self.access_term(self.sorted_address().next(), self.sorted_value().next());
+        vec![
+            // addresses increase by zero or one
+            &address_delta * (&address_delta - one),
+            // values can only change when the address changes
+            value_delta * (address_delta - one),
+            // running product
+            self.permutation().next() * sorted_access - self.permutation().curr() * access,
+        ]
+    }
+
+    pub fn terminal_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        vec![self.permutation().curr() - self.hint.get_hint()]
+    }
+}
+
+/// Memory cells known to the verifier
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PublicMemory<F: Field> {
+    cells: Vec<(F, F)>,
+}
+
+impl<F: Field> PublicMemory<F> {
+    /// Creates a public memory from `(address, value)` pairs. Address zero is
+    /// reserved for the placeholders of public cells.
+    pub fn new(cells: Vec<(F, F)>) -> Self {
+        assert!(
+            cells.iter().all(|(address, _)| !address.is_zero()),
+            "public memory can't use address zero"
+        );
+        PublicMemory { cells }
+    }
+
+    pub fn cells(&self) -> &[(F, F)] {
+        &self.cells
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Value of the running product on the last row of the trace
+    pub fn hint<Fq: StarkExtensionOf<F>>(
+        &self,
+        columns: MemoryColumns,
+        challenges: &Challenges<Fq>,
+    ) -> Fq
+    where
+        F: GpuFftField,
+    {
+        let z = challenges[columns.z()];
+        let alpha = challenges[columns.alpha()];
+        let mut denominator = Fq::one();
+        for (address, value) in &self.cells {
+            denominator *= z - (alpha * value + address);
+        }
+        z.pow([self.cells.len() as u64]) * denominator.inverse().unwrap()
+    }
+
+    /// Generates the [NUM_BASE_COLUMNS] base columns of the gadget from the
+    /// accesses made during an execution. Every address between the lowest
+    /// and highest address (including public cells) must be accessed. Rows
+    /// after the accesses and public placeholders repeat the access with the
+    /// highest address.
+    pub fn gen_base_columns(&self, accesses: &[(F, F)], trace_len: usize) -> Matrix<F>
+    where
+        F: GpuField,
+    {
+        let num_rows = accesses.len() + self.cells.len();
+        assert!(num_rows > 0, "memory has no accesses");
+        assert!(
+            num_rows <= trace_len,
+            "{num_rows} memory accesses don't fit in a trace of length {trace_len}"
+        );
+
+        let mut sorted = [accesses, &self.cells].concat();
+        sorted.sort_by_key(|(address, _)| *address);
+        for [(a, v), (next_a, next_v)] in sorted.array_windows() {
+            let is_same_address = a == next_a;
+            assert!(
+                is_same_address || *next_a == *a + F::one(),
+                "memory address {next_a} follows {a}"
+            );
+            assert!(
+                !is_same_address || v == next_v,
+                "memory address {a} has multiple values"
+            );
+        }
+
+        let mut unsorted = accesses.to_vec();
+        unsorted.resize(num_rows, (F::zero(), F::zero()));
+        let padding = *sorted.last().unwrap();
+        unsorted.resize(trace_len, padding);
+        sorted.resize(trace_len, padding);
+
+        let mut columns = (0..NUM_BASE_COLUMNS)
+            .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+            .collect::<Vec<_>>();
+        for ((address, value), (sorted_address, sorted_value)) in unsorted.into_iter().zip(sorted) {
+            columns[0].push(address);
+            columns[1].push(value);
+            columns[2].push(sorted_address);
+            columns[3].push(sorted_value);
+        }
+        Matrix::new(columns)
+    }
+}
+
+/// Generates the running product column of the gadget. `base_columns` are the
+/// columns returned by [PublicMemory::gen_base_columns].
+pub fn gen_extension_columns<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+    columns: MemoryColumns,
+    base_columns: &Matrix<Fp>,
+    challenges: &Challenges<Fq>,
+) -> Matrix<Fq> {
+    let z = challenges[columns.z()];
+    let alpha = challenges[columns.alpha()];
+    let access_terms = |address: &[Fp], value: &[Fp]| {
+        address
+            .iter()
+            .zip(value)
+            .map(|(a, v)| z - (alpha * v + a))
+            .collect::<Vec<Fq>>()
+    };
+    let numerators = access_terms(&base_columns[0], &base_columns[1]);
+    let mut denominators = access_terms(&base_columns[2], &base_columns[3]);
+    batch_inversion(&mut denominators);
+
+    let mut running_product = Fq::one();
+    let mut permutation = Vec::with_capacity_in(base_columns.num_rows(), PageAlignedAllocator);
+    for (numerator, denominator_inv) in numerators.into_iter().zip(denominators) {
+        running_product *= numerator * denominator_inv;
+        permutation.push(running_product);
+    }
+    Matrix::new(vec![permutation])
+}
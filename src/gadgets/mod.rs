@@ -0,0 +1,9 @@
+//! Reusable pieces of AIR that can be embedded in larger execution traces
+pub mod bits;
+pub mod bitwise;
+pub mod hash;
+pub mod memory;
+pub mod ram;
+pub mod rescue;
+pub mod rom;
+pub mod u32_arithmetic;
@@ -0,0 +1,277 @@
+//! Read-write memory checked against the accesses of a processor.
+//!
+//! Each processor row that isn't padding makes one access `(clk, address,
+//! value, is_write)` where `value` is the value in memory after the access.
+//! The gadget's table holds the same accesses sorted by address then clock.
+//! Constraints on the table check:
+//!
+//! - addresses increase by zero or one
+//! - the clock increases by one between rows with the same address
+//! - a read returns the value of the previous access to the same address
+//! - the first access to an address reads zero i.e. memory starts zeroed
+//!
+//! Accesses to the same address are rarely on consecutive cycles so dummy
+//! rows fill the gaps in the clock. Dummy rows are reads and aren't part of
+//! the permutation argument. Running products over the extension field of
+//! the processor's accesses and the table's non-dummy rows must be equal on
+//! the last row.
+//!
+//! This is the memory table of the brainfuck example with writes made
+//! explicit.
+use crate::challenges::Challenges;
+use crate::constraint::is_binary;
+use crate::constraint::Challenge;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+
+/// Number of base trace columns used by the table
+pub const NUM_BASE_COLUMNS: usize = 5;
+
+/// Number of extension trace columns used by the gadget
+pub const NUM_EXTENSION_COLUMNS: usize = 2;
+
+/// Number of challenges used by the gadget
+pub const NUM_CHALLENGES: usize = 5;
+
+/// Processor columns describing the access made on each row
+#[derive(Clone, Copy, Debug)]
+pub struct AccessColumns {
+    pub clk: usize,
+    pub address: usize,
+    /// Value in memory after the access
+    pub value: usize,
+    /// One if the access writes `value` and zero if it reads it
+    pub is_write: usize,
+    /// One on rows that don't access memory
+    pub is_padding: usize,
+}
+
+/// Location of the gadget's columns and challenges
+#[derive(Clone, Copy, Debug)]
+pub struct RamColumns {
+    pub processor: AccessColumns,
+    /// Index of the first of the table's [NUM_BASE_COLUMNS] base columns
+    pub base_offset: usize,
+    /// Index of the first of the [NUM_EXTENSION_COLUMNS] extension columns.
+    /// Extension columns are indexed after all base columns.
+    pub extension_offset: usize,
+    /// Index of the first of the [NUM_CHALLENGES] challenges
+    pub challenge_offset: usize,
+}
+
+impl RamColumns {
+    /// Table columns holding the accesses sorted by address then clock
+    pub fn table(&self) -> AccessColumns {
+        AccessColumns {
+            clk: self.base_offset,
+            address: self.base_offset + 1,
+            value: self.base_offset + 2,
+            is_write: self.base_offset + 3,
+            is_padding: self.is_dummy(),
+        }
+    }
+
+    /// Table column that is one on rows filling gaps in the clock
+    pub fn is_dummy(&self) -> usize {
+        self.base_offset + 4
+    }
+
+    /// Extension column holding the running product of the processor's
+    /// accesses
+    pub fn processor_permutation(&self) -> usize {
+        self.extension_offset
+    }
+
+    /// Extension column holding the running product of the table's accesses
+    pub fn table_permutation(&self) -> usize {
+        self.extension_offset + 1
+    }
+
+    /// Challenge `z` of the permutation argument
+    pub fn z(&self) -> usize {
+        self.challenge_offset
+    }
+
+    /// Challenges used to combine the clock, address, value and write flag
+    pub fn alphas(&self) -> [usize; 4] {
+        [1, 2, 3, 4].map(|i| self.challenge_offset + i)
+    }
+
+    /// Factor an access contributes to a running product. One for padding.
+    fn access_factor<F: GpuField>(
+        &self,
+        access: AccessColumns,
+        cell: impl Fn(usize) -> Constraint<F>,
+    ) -> Constraint<F> {
+        let [clk, address, value, is_write] = self.alphas().map(|i| i.get_challenge::<F>());
+        let term = self.z().get_challenge::<F>()
+            - clk * cell(access.clk)
+            - address * cell(access.address)
+            - value * cell(access.value)
+            - is_write * cell(access.is_write);
+        let is_padding = cell(access.is_padding);
+        &is_padding + (Constraint::from(F::one()) - is_padding) * term
+    }
+
+    pub fn boundary_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let one = F::one();
+        let table = self.table();
+        let curr = |column: usize| column.curr::<F>();
+        vec![
+            is_binary(self.is_dummy().curr::<F>()),
+            self.is_dummy().curr() * table.is_write.curr(),
+            // the first access reads zero
+            (table.is_write.curr() - one) * table.value.curr(),
+            self.processor_permutation().curr() - self.access_factor(self.processor, curr),
+            self.table_permutation().curr() - self.access_factor(table, curr),
+        ]
+    }
+
+    pub fn transition_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let one = F::one();
+        let table = self.table();
+        let next = |column: usize| column.next::<F>();
+        let address_delta = table.address.next() - table.address.curr();
+        let clk_delta = table.clk.next() - table.clk.curr();
+        let value_delta = table.value.next() - table.value.curr();
+        let is_next_read = Constraint::from(one) - table.is_write.next();
+        vec![
+            // addresses increase by zero or one
+            &address_delta * (&address_delta - one),
+            // the clock increments if the address is the same
+            (&address_delta - one) * (clk_delta - one),
+            is_binary(self.is_dummy().next::<F>()),
+            is_binary(table.is_write.next::<F>()),
+            // dummy rows are reads
+            self.is_dummy().next() * table.is_write.next(),
+            // reads return the value of the previous access to the address
+            &is_next_read * (&address_delta - one) * value_delta,
+            // the first access to an address reads zero
+            is_next_read * address_delta * table.value.next(),
+            // running products
+            self.processor_permutation().next()
+                - self.processor_permutation().curr() * self.access_factor(self.processor, next),
+            self.table_permutation().next()
+                - self.table_permutation().curr() * self.access_factor(table, next),
+        ]
+    }
+
+    pub fn terminal_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        vec![self.processor_permutation().curr() - self.table_permutation().curr()]
+    }
+}
+
+/// A memory access made by the processor
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Access<F> {
+    pub clk: u64,
+    pub address: F,
+    /// Value in memory after the access
+    pub value: F,
+    pub is_write: bool,
+}
+
+/// Generates the table's [NUM_BASE_COLUMNS] base columns from the accesses
+/// made by the processor. Dummy rows are inserted between accesses to the
+/// same address, for addresses that aren't accessed and after the last access.
+pub fn gen_base_columns<F: GpuField>(accesses: &[Access<F>], trace_len: usize) -> Matrix<F> {
+    let mut accesses = accesses.to_vec();
+    accesses.sort_by_key(|access| (access.address, access.clk));
+    assert!(!accesses.is_empty(), "memory has no accesses");
+
+    let mut rows = Vec::with_capacity(trace_len);
+    for (i, &access) in accesses.iter().enumerate() {
+        if let Some(prev) = i.checked_sub(1).map(|i| accesses[i]) {
+            if prev.address == access.address {
+                assert!(
+                    prev.clk < access.clk,
+                    "multiple accesses to {} at clock {}",
+                    access.address,
+                    access.clk
+                );
+                for clk in prev.clk + 1..access.clk {
+                    rows.push(dummy_row(clk, &prev));
+                }
+            } else {
+                let mut address = prev.address + F::one();
+                while address != access.address {
+                    rows.push([F::from(prev.clk), address, F::zero(), F::zero(), F::one()]);
+                    address += F::one();
+                }
+            }
+        }
+        rows.push([
+            F::from(access.clk),
+            access.address,
+            access.value,
+            F::from(access.is_write),
+            F::zero(),
+        ]);
+    }
+    assert!(
+        rows.len() <= trace_len,
+        "memory table with {} rows doesn't fit in a trace of length {trace_len}",
+        rows.len()
+    );
+    let last = *accesses.last().unwrap();
+    for clk in last.clk + 1.. {
+        if rows.len() == trace_len {
+            break;
+        }
+        rows.push(dummy_row(clk, &last));
+    }
+
+    let mut columns = (0..NUM_BASE_COLUMNS)
+        .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for row in rows {
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    Matrix::new(columns)
+}
+
+/// Reads the value of `prev` at clock `clk`
+fn dummy_row<F: GpuField>(clk: u64, prev: &Access<F>) -> [F; NUM_BASE_COLUMNS] {
+    [F::from(clk), prev.address, prev.value, F::zero(), F::one()]
+}
+
+/// Generates the running product columns of the gadget. `base_columns` are
+/// all base columns of the trace including the processor's and the table's.
+pub fn gen_extension_columns<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+    columns: RamColumns,
+    base_columns: &Matrix<Fp>,
+    challenges: &Challenges<Fq>,
+) -> Matrix<Fq> {
+    let z = challenges[columns.z()];
+    let alphas = columns.alphas().map(|i| challenges[i]);
+    let running_product = |access: AccessColumns| {
+        let mut acc = Fq::one();
+        let mut res = Vec::with_capacity_in(base_columns.num_rows(), PageAlignedAllocator);
+        for row in 0..base_columns.num_rows() {
+            if base_columns[access.is_padding][row].is_zero() {
+                let values = [access.clk, access.address, access.value, access.is_write]
+                    .map(|column| base_columns[column][row]);
+                let mut term = z;
+                for (alpha, value) in alphas.iter().zip(&values) {
+                    term -= *alpha * value;
+                }
+                acc *= term;
+            }
+            res.push(acc);
+        }
+        res
+    };
+    Matrix::new(vec![
+        running_product(columns.processor),
+        running_product(columns.table()),
+    ])
+}
@@ -0,0 +1,268 @@
+//! Rescue-Prime permutation over the 64-bit prime field `2^64 - 2^32 + 1`.
+//!
+//! Uses the same parameters as Rp64_256 (state width 12, rate 8, S-box `x^7`
+//! and 7 rounds) but derives the MDS matrix and round constants with SHA-256
+//! (see [RescuePrime::new]) so digests aren't compatible with other
+//! implementations.
+//!
+//! A permutation occupies [CYCLE_LENGTH] rows of the execution trace. Row `r`
+//! holds the state before round `r` and an intermediate state `w` such that:
+//!
+//! ```text
+//! w^7 = MDS * state^7 + ark1[r]
+//! next_state = MDS * w + ark2[r]
+//! ```
+//!
+//! Expressing the inverse S-box this way keeps the constraint degree at 7.
+//! The last row of a cycle holds the permutation output. Round constants are
+//! supplied to constraints as periodic columns (see [Air::periodic_columns]).
+//!
+//! [Air::periodic_columns]: crate::Air::periodic_columns
+use crate::Column;
+use crate::Constraint;
+use crate::StarkExtensionOf;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use sha2::Sha256;
+use std::ops::Range;
+
+/// Number of field elements in the permutation state
+pub const STATE_WIDTH: usize = 12;
+
+/// Number of field elements absorbed by each permutation when hashing
+pub const RATE: usize = 8;
+
+/// Elements of the state reserved for the capacity
+pub const CAPACITY_RANGE: Range<usize> = 0..4;
+
+/// Elements of the state that elements are absorbed into
+pub const RATE_RANGE: Range<usize> = 4..12;
+
+/// Elements of the state that make up a digest
+pub const DIGEST_RANGE: Range<usize> = 4..8;
+
+/// Number of field elements in a digest
+pub const DIGEST_SIZE: usize = 4;
+
+pub const NUM_ROUNDS: usize = 7;
+
+/// S-box exponent
+pub const ALPHA: u64 = 7;
+
+/// Inverse S-box exponent i.e. `ALPHA^(-1) mod (p - 1)`
+pub const INV_ALPHA: u64 = 10540996611094048183;
+
+/// Number of trace rows used by a single permutation
+pub const CYCLE_LENGTH: usize = NUM_ROUNDS + 1;
+
+/// Number of trace columns used by the gadget
+pub const NUM_COLUMNS: usize = 2 * STATE_WIDTH;
+
+/// Number of periodic columns used by the gadget
+pub const NUM_PERIODIC_COLUMNS: usize = 1 + 2 * STATE_WIDTH;
+
+/// Location of the gadget's columns within an execution trace
+#[derive(Clone, Copy, Debug)]
+pub struct RescueColumns {
+    /// Index of the first of the [NUM_COLUMNS] trace columns
+    pub trace_offset: usize,
+    /// Index of the first of the [NUM_PERIODIC_COLUMNS] periodic columns
+    pub periodic_offset: usize,
+}
+
+impl RescueColumns {
+    /// Column holding element `i` of the state before each round
+    pub fn state(&self, i: usize) -> usize {
+        assert!(i < STATE_WIDTH);
+        self.trace_offset + i
+    }
+
+    /// Column holding element `i` of the intermediate state of each round
+    pub fn intermediate(&self, i: usize) -> usize {
+        assert!(i < STATE_WIDTH);
+        self.trace_offset + STATE_WIDTH + i
+    }
+
+    /// Periodic column that is one on rows that apply a round and zero on the
+    /// last row of each cycle
+    pub fn round_flag(&self) -> usize {
+        self.periodic_offset
+    }
+
+    fn ark1(&self, i: usize) -> usize {
+        self.periodic_offset + 1 + i
+    }
+
+    fn ark2(&self, i: usize) -> usize {
+        self.periodic_offset + 1 + STATE_WIDTH + i
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RescuePrime {
+    mds: [[Fp; STATE_WIDTH]; STATE_WIDTH],
+    ark1: [[Fp; STATE_WIDTH]; NUM_ROUNDS],
+    ark2: [[Fp; STATE_WIDTH]; NUM_ROUNDS],
+}
+
+impl RescuePrime {
+    /// The MDS matrix is the Cauchy matrix `M[i][j] = 1 / (i + j + 12)`. Round
+    /// constants are the first 8 bytes (little-endian) of
+    /// `SHA256("ministark rescue-prime" || counter)` reduced into the field.
+    pub fn new() -> Self {
+        let mut mds = [[Fp::zero(); STATE_WIDTH]; STATE_WIDTH];
+        for (i, row) in mds.iter_mut().enumerate() {
+            for (j, element) in row.iter_mut().enumerate() {
+                *element = Fp::from((i + j + STATE_WIDTH) as u64).inverse().unwrap();
+            }
+        }
+
+        let mut counter = 0u64;
+        let mut next_constant = || {
+            let mut hasher = Sha256::new_with_prefix(b"ministark rescue-prime");
+            hasher.update(counter.to_le_bytes());
+            counter += 1;
+            let digest = hasher.finalize();
+            Fp::from(u64::from_le_bytes(digest[0..8].try_into().unwrap()))
+        };
+
+        let mut ark1 = [[Fp::zero(); STATE_WIDTH]; NUM_ROUNDS];
+        let mut ark2 = [[Fp::zero(); STATE_WIDTH]; NUM_ROUNDS];
+        for (round_ark1, round_ark2) in ark1.iter_mut().zip(&mut ark2) {
+            *round_ark1 = [(); STATE_WIDTH].map(|_| next_constant());
+            *round_ark2 = [(); STATE_WIDTH].map(|_| next_constant());
+        }
+
+        RescuePrime { mds, ark1, ark2 }
+    }
+
+    fn apply_mds(&self, state: &[Fp; STATE_WIDTH]) -> [Fp; STATE_WIDTH] {
+        self.mds
+            .map(|row| row.iter().zip(state).map(|(&m, &s)| m * s).sum())
+    }
+
+    /// Returns the intermediate state of a round. Rows that don't apply a round
+    /// i.e. `round == NUM_ROUNDS` use zero for the round constants.
+    fn intermediate_state(&self, state: &[Fp; STATE_WIDTH], round: usize) -> [Fp; STATE_WIDTH] {
+        let mut res = self.apply_mds(&state.map(|s| s.pow([ALPHA])));
+        if let Some(ark1) = self.ark1.get(round) {
+            res.iter_mut().zip(ark1).for_each(|(r, c)| *r += c);
+        }
+        res.map(|r| r.pow([INV_ALPHA]))
+    }
+
+    fn apply_round(&self, state: &mut [Fp; STATE_WIDTH], round: usize) {
+        let intermediate = self.intermediate_state(state, round);
+        *state = self.apply_mds(&intermediate);
+        state
+            .iter_mut()
+            .zip(&self.ark2[round])
+            .for_each(|(s, c)| *s += c);
+    }
+
+    pub fn permute(&self, state: &mut [Fp; STATE_WIDTH]) {
+        for round in 0..NUM_ROUNDS {
+            self.apply_round(state, round);
+        }
+    }
+
+    /// Sponge hash of a sequence of field elements. The number of elements is
+    /// written into the capacity so inputs of different lengths are separated.
+    pub fn hash_elements(&self, elements: &[Fp]) -> [Fp; DIGEST_SIZE] {
+        let mut state = [Fp::zero(); STATE_WIDTH];
+        state[CAPACITY_RANGE.start] = Fp::from(elements.len() as u64);
+        if elements.is_empty() {
+            self.permute(&mut state);
+        }
+        for chunk in elements.chunks(RATE) {
+            state[RATE_RANGE]
+                .iter_mut()
+                .zip(chunk)
+                .for_each(|(s, e)| *s += e);
+            self.permute(&mut state);
+        }
+        state[DIGEST_RANGE].try_into().unwrap()
+    }
+
+    /// Hashes two digests into one. Used for building Merkle trees.
+    pub fn merge(&self, a: &[Fp; DIGEST_SIZE], b: &[Fp; DIGEST_SIZE]) -> [Fp; DIGEST_SIZE] {
+        self.hash_elements(&[a.as_slice(), b.as_slice()].concat())
+    }
+
+    /// Returns the [CYCLE_LENGTH] trace rows of a permutation of `state`. Each
+    /// row has [NUM_COLUMNS] elements. The last row holds the output.
+    pub fn permutation_trace(&self, mut state: [Fp; STATE_WIDTH]) -> Vec<[Fp; NUM_COLUMNS]> {
+        let mut rows = Vec::with_capacity(CYCLE_LENGTH);
+        for round in 0..CYCLE_LENGTH {
+            let intermediate = self.intermediate_state(&state, round);
+            let mut row = [Fp::zero(); NUM_COLUMNS];
+            row[..STATE_WIDTH].copy_from_slice(&state);
+            row[STATE_WIDTH..].copy_from_slice(&intermediate);
+            rows.push(row);
+            if round < NUM_ROUNDS {
+                self.apply_round(&mut state, round);
+            }
+        }
+        rows
+    }
+
+    /// Values of the [NUM_PERIODIC_COLUMNS] periodic columns. Each column has
+    /// period [CYCLE_LENGTH]. The order is the round flag, then the first and
+    /// second round constants for each state element.
+    pub fn periodic_columns(&self) -> Vec<Vec<Fp>> {
+        let mut round_flag = vec![Fp::one(); NUM_ROUNDS];
+        round_flag.push(Fp::zero());
+
+        let constant_column = |constants: &[[Fp; STATE_WIDTH]; NUM_ROUNDS], i: usize| {
+            let mut column = constants.iter().map(|c| c[i]).collect::<Vec<Fp>>();
+            column.push(Fp::zero());
+            column
+        };
+
+        let mut columns = vec![round_flag];
+        columns.extend((0..STATE_WIDTH).map(|i| constant_column(&self.ark1, i)));
+        columns.extend((0..STATE_WIDTH).map(|i| constant_column(&self.ark2, i)));
+        columns
+    }
+
+    /// Transition constraints enforcing the rounds of the permutation. They
+    /// don't constrain the transition from the last row of a cycle. AIRs
+    /// chaining permutations can use [RescueColumns::round_flag] for that.
+    pub fn transition_constraints<Fq: StarkExtensionOf<Fp>>(
+        &self,
+        columns: RescueColumns,
+    ) -> Vec<Constraint<Fq>> {
+        let round_flag = columns.round_flag().curr::<Fq>();
+        let mut constraints = Vec::new();
+
+        // `w^7 = MDS * state^7 + ark1`
+        for (i, mds_row) in self.mds.iter().enumerate() {
+            let mut constraint = columns.intermediate(i).curr::<Fq>().pow(ALPHA as usize);
+            constraint -= columns.ark1(i).curr();
+            for (j, &m) in mds_row.iter().enumerate() {
+                constraint -= columns.state(j).curr().pow(ALPHA as usize) * Fq::from(m);
+            }
+            constraints.push(constraint);
+        }
+
+        // `next_state = MDS * w + ark2`
+        for (i, mds_row) in self.mds.iter().enumerate() {
+            let mut constraint = columns.state(i).next::<Fq>() - columns.ark2(i).curr();
+            for (j, &m) in mds_row.iter().enumerate() {
+                constraint -= columns.intermediate(j).curr() * Fq::from(m);
+            }
+            constraints.push(constraint * &round_flag);
+        }
+
+        constraints
+    }
+}
+
+impl Default for RescuePrime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,333 @@
+//! Read-only lookup table e.g. a program ROM or a table of constants.
+//!
+//! The table is public. Entry `i` is at address `start + i` and has `width`
+//! values. Lookups are written to the trace in execution order and the gadget
+//! adds a copy of the lookups and the table sorted by address. Constraints on
+//! the sorted copy check that addresses start at `start`, end at the last
+//! address of the table, increase by zero or one and that an address always
+//! has the same values. A running product over the extension field checks the
+//! sorted copy is a permutation of the lookups and the table:
+//!
+//! ```text
+//! p[i] = Π_{j<=i} (z - (a[j] + Σ_k α_k * v_k[j])) / (z - (a'[j] + Σ_k α_k * v'_k[j]))
+//! ```
+//!
+//! Each table entry has a placeholder row in the execution order columns with
+//! the address `start - 1` and zero values. The address is outside the table
+//! so the sorted columns can't contain it. The verifier computes the last
+//! value of the running product from the table (see [Rom::hint]) and it's
+//! supplied to the terminal constraint as a hint.
+use crate::challenges::Challenges;
+use crate::constraint::Challenge;
+use crate::constraint::Hint;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_ff::batch_inversion;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+use std::collections::HashMap;
+
+/// Location of the gadget's columns, challenges and hint
+#[derive(Clone, Copy, Debug)]
+pub struct RomColumns {
+    /// Number of values in each table entry
+    pub width: usize,
+    /// Index of the first of the [RomColumns::num_base_columns] base trace
+    /// columns
+    pub base_offset: usize,
+    /// Index of the running product column. Extension columns are indexed
+    /// after all base columns.
+    pub extension_offset: usize,
+    /// Index of the first of the [RomColumns::num_challenges] challenges
+    pub challenge_offset: usize,
+    /// Index of the hint holding [Rom::hint]
+    pub hint: usize,
+}
+
+impl RomColumns {
+    pub fn num_base_columns(&self) -> usize {
+        2 * (self.width + 1)
+    }
+
+    pub fn num_challenges(&self) -> usize {
+        self.width + 1
+    }
+
+    /// Column holding the address of each lookup in execution order
+    pub fn address(&self) -> usize {
+        self.base_offset
+    }
+
+    /// Column holding value `i` of each lookup in execution order
+    pub fn value(&self, i: usize) -> usize {
+        assert!(i < self.width);
+        self.base_offset + 1 + i
+    }
+
+    /// Column holding the addresses of the lookups and table sorted by address
+    pub fn sorted_address(&self) -> usize {
+        self.base_offset + self.width + 1
+    }
+
+    /// Column holding value `i` of the lookups and table sorted by address
+    pub fn sorted_value(&self, i: usize) -> usize {
+        assert!(i < self.width);
+        self.base_offset + self.width + 2 + i
+    }
+
+    /// Extension column holding the running product of the permutation
+    /// argument
+    pub fn permutation(&self) -> usize {
+        self.extension_offset
+    }
+
+    /// Challenge `z` of the permutation argument
+    pub fn z(&self) -> usize {
+        self.challenge_offset
+    }
+
+    /// Challenge `α_i` used to combine value `i` with the address
+    pub fn alpha(&self, i: usize) -> usize {
+        assert!(i < self.width);
+        self.challenge_offset + 1 + i
+    }
+
+    /// `z - (address + Σ α_i * value_i)` for an entry
+    fn entry_term<F: GpuField>(
+        &self,
+        address: Constraint<F>,
+        value: impl Fn(usize) -> Constraint<F>,
+    ) -> Constraint<F> {
+        let mut term = self.z().get_challenge::<F>() - address;
+        for i in 0..self.width {
+            term -= self.alpha(i).get_challenge::<F>() * value(i);
+        }
+        term
+    }
+
+    fn lookup_term<F: GpuField>(&self, next: bool) -> Constraint<F> {
+        let cell = |column: usize| -> Constraint<F> {
+            if next {
+                column.next()
+            } else {
+                column.curr()
+            }
+        };
+        self.entry_term(cell(self.address()), |i| cell(self.value(i)))
+    }
+
+    fn sorted_term<F: GpuField>(&self, next: bool) -> Constraint<F> {
+        let cell = |column: usize| -> Constraint<F> {
+            if next {
+                column.next()
+            } else {
+                column.curr()
+            }
+        };
+        self.entry_term(cell(self.sorted_address()), |i| cell(self.sorted_value(i)))
+    }
+
+    pub fn boundary_constraints<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        rom: &Rom<Fp>,
+    ) -> Vec<Constraint<Fq>> {
+        vec![
+            self.sorted_address().curr() - Fq::from(rom.start),
+            self.permutation().curr() * self.sorted_term(false) - self.lookup_term(false),
+        ]
+    }
+
+    pub fn transition_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        let one = F::one();
+        let address_delta = self.sorted_address().next() - self.sorted_address().curr();
+        // addresses increase by zero or one
+        let mut constraints = vec![&address_delta * (&address_delta - one)];
+        // values can only change when the address changes
+        for i in 0..self.width {
+            let value_delta = self.sorted_value(i).next() - self.sorted_value(i).curr();
+            constraints.push(value_delta * (&address_delta - one));
+        }
+        // running product
+        constraints.push(
+            self.permutation().next() * self.sorted_term(true)
+                - self.permutation().curr() * self.lookup_term(true),
+        );
+        constraints
+    }
+
+    pub fn terminal_constraints<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+        &self,
+        rom: &Rom<Fp>,
+    ) -> Vec<Constraint<Fq>> {
+        vec![
+            self.sorted_address().curr() - Fq::from(rom.end()),
+            self.permutation().curr() - self.hint.get_hint(),
+        ]
+    }
+}
+
+/// Contents of a read-only table
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Rom<F: Field> {
+    start: F,
+    entries: Vec<Vec<F>>,
+}
+
+impl<F: Field> Rom<F> {
+    /// Creates a table with `entries[i]` at address `start + i`
+    pub fn new(start: F, entries: Vec<Vec<F>>) -> Self {
+        assert!(!entries.is_empty(), "table has no entries");
+        let width = entries[0].len();
+        assert!(
+            entries.iter().all(|entry| entry.len() == width),
+            "table entries have different widths"
+        );
+        Rom { start, entries }
+    }
+
+    pub fn start(&self) -> F {
+        self.start
+    }
+
+    /// Address of the last entry
+    pub fn end(&self) -> F {
+        self.start + F::from((self.entries.len() - 1) as u64)
+    }
+
+    pub fn entries(&self) -> &[Vec<F>] {
+        &self.entries
+    }
+
+    pub fn width(&self) -> usize {
+        self.entries[0].len()
+    }
+
+    /// Address of the placeholder rows
+    fn placeholder_address(&self) -> F {
+        self.start - F::one()
+    }
+
+    /// Value of the running product on the last row of the trace
+    pub fn hint<Fq: StarkExtensionOf<F>>(
+        &self,
+        columns: RomColumns,
+        challenges: &Challenges<Fq>,
+    ) -> Fq
+    where
+        F: GpuFftField,
+    {
+        assert_eq!(columns.width, self.width());
+        let z = challenges[columns.z()];
+        let placeholder = z - Fq::from(self.placeholder_address());
+        let mut denominator = Fq::one();
+        for (address, entry) in self.addresses().zip(&self.entries) {
+            denominator *= entry_term(columns, challenges, address, entry);
+        }
+        placeholder.pow([self.entries.len() as u64]) * denominator.inverse().unwrap()
+    }
+
+    fn addresses(&self) -> impl Iterator<Item = F> + '_ {
+        (0..self.entries.len() as u64).map(|i| self.start + F::from(i))
+    }
+
+    /// Generates the [RomColumns::num_base_columns] base columns of the gadget
+    /// from the addresses looked up during an execution. Rows after the
+    /// lookups and placeholders repeat the last entry of the table.
+    pub fn gen_base_columns(&self, lookups: &[F], trace_len: usize) -> Matrix<F>
+    where
+        F: GpuField,
+    {
+        let num_rows = lookups.len() + self.entries.len();
+        assert!(
+            num_rows <= trace_len,
+            "{num_rows} lookups and entries don't fit in a trace of length {trace_len}"
+        );
+
+        let table = self
+            .addresses()
+            .zip(self.entries.iter().cloned())
+            .collect::<Vec<(F, Vec<F>)>>();
+        let entries = table.iter().cloned().collect::<HashMap<F, Vec<F>>>();
+        let mut unsorted = Vec::with_capacity(trace_len);
+        for &address in lookups {
+            let entry = entries
+                .get(&address)
+                .unwrap_or_else(|| panic!("address {address} isn't in the table"));
+            unsorted.push((address, entry.clone()));
+        }
+        let mut sorted = [unsorted.as_slice(), &table].concat();
+        sorted.sort_by_key(|(address, _)| *address);
+        let placeholder = (self.placeholder_address(), vec![F::zero(); self.width()]);
+        unsorted.resize(num_rows, placeholder);
+        let padding = sorted.last().unwrap().clone();
+        unsorted.resize(trace_len, padding.clone());
+        sorted.resize(trace_len, padding);
+
+        let num_columns = 2 * (self.width() + 1);
+        let mut columns = (0..num_columns)
+            .map(|_| Vec::with_capacity_in(trace_len, PageAlignedAllocator))
+            .collect::<Vec<_>>();
+        for ((address, entry), (sorted_address, sorted_entry)) in unsorted.into_iter().zip(sorted) {
+            let row = [vec![address], entry, vec![sorted_address], sorted_entry].concat();
+            for (column, value) in columns.iter_mut().zip(row) {
+                column.push(value);
+            }
+        }
+        Matrix::new(columns)
+    }
+}
+
+/// `z - (address + Σ α_i * value_i)` for an entry
+fn entry_term<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+    columns: RomColumns,
+    challenges: &Challenges<Fq>,
+    address: Fp,
+    entry: &[Fp],
+) -> Fq {
+    let mut term = challenges[columns.z()] - Fq::from(address);
+    for (i, value) in entry.iter().enumerate() {
+        term -= challenges[columns.alpha(i)] * value;
+    }
+    term
+}
+
+/// Generates the running product column of the gadget. `base_columns` are the
+/// columns returned by [Rom::gen_base_columns].
+pub fn gen_extension_columns<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>(
+    columns: RomColumns,
+    base_columns: &Matrix<Fp>,
+    challenges: &Challenges<Fq>,
+) -> Matrix<Fq> {
+    let width = columns.width;
+    let num_rows = base_columns.num_rows();
+    let entry_terms = |offset: usize| {
+        (0..num_rows)
+            .map(|row| {
+                let address = base_columns[offset][row];
+                let entry = (0..width)
+                    .map(|i| base_columns[offset + 1 + i][row])
+                    .collect::<Vec<Fp>>();
+                entry_term(columns, challenges, address, &entry)
+            })
+            .collect::<Vec<Fq>>()
+    };
+    let numerators = entry_terms(0);
+    let mut denominators = entry_terms(width + 1);
+    batch_inversion(&mut denominators);
+
+    let mut running_product = Fq::one();
+    let mut permutation = Vec::with_capacity_in(num_rows, PageAlignedAllocator);
+    for (numerator, denominator_inv) in numerators.into_iter().zip(denominators) {
+        running_product *= numerator * denominator_inv;
+        permutation.push(running_product);
+    }
+    Matrix::new(vec![permutation])
+}
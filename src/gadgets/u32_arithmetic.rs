@@ -0,0 +1,200 @@
+//! Addition, subtraction and multiplication of 32-bit values.
+//!
+//! An instance of the gadget computes one operation per row on two columns of
+//! the trace that hold 32-bit values. The result is stored as 16-bit limbs
+//! and each limb is range checked by decomposing it into bits:
+//!
+//! ```text
+//! add: a + b = c + carry * 2^32
+//! sub: a - b = c - borrow * 2^32
+//! mul: a * b = c + d * 2^32
+//! ```
+//!
+//! where `c` and `d` are 32-bit values and `carry`/`borrow` is a bit. The
+//! product of two 32-bit values is less than the field modulus
+//! `2^64 - 2^32 + 1` so it can't wrap but `c + d * 2^32` can. With
+//! `d = 2^32 - 1` the sum is `c + p - 1` so a prover could claim
+//! `c = a * b + 1`. Multiplication has an extra column `m` and the constraint
+//!
+//! ```text
+//! c * ((d - (2^32 - 1)) * m - 1) = 0
+//! ```
+//!
+//! which forces `c = 0` when the high word is all ones. No product has that
+//! high word so `m` is always the inverse of `d - (2^32 - 1)` in an honest
+//! trace. The operands aren't range checked by
+//! the gadget and must be constrained to 32 bits elsewhere (for example as
+//! the result of another instance). Several instances can be placed side by
+//! side in a trace.
+use crate::constraint::is_binary;
+use crate::Column;
+use crate::Constraint;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::GpuField;
+
+/// Number of bits in a limb
+pub const LIMB_BITS: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum U32Op {
+    Add,
+    Sub,
+    Mul,
+}
+
+impl U32Op {
+    /// Returns the low and high 32-bit words of the result. The high word is
+    /// the carry for addition and the borrow for subtraction.
+    pub fn apply(&self, a: u32, b: u32) -> (u32, u32) {
+        match self {
+            Self::Add => {
+                let (c, carry) = a.overflowing_add(b);
+                (c, carry as u32)
+            }
+            Self::Sub => {
+                let (c, borrow) = a.overflowing_sub(b);
+                (c, borrow as u32)
+            }
+            Self::Mul => {
+                let product = a as u64 * b as u64;
+                (product as u32, (product >> 32) as u32)
+            }
+        }
+    }
+
+    /// Number of bit columns holding the result
+    fn num_result_bits(&self) -> usize {
+        match self {
+            Self::Add | Self::Sub => 32,
+            Self::Mul => 64,
+        }
+    }
+}
+
+/// Location of an instance of the gadget
+#[derive(Clone, Copy, Debug)]
+pub struct U32Columns {
+    pub op: U32Op,
+    /// Column holding the first operand
+    pub a: usize,
+    /// Column holding the second operand
+    pub b: usize,
+    /// Index of the first of the [U32Columns::num_columns] trace columns
+    pub offset: usize,
+}
+
+impl U32Columns {
+    /// Number of trace columns used by the instance
+    pub fn num_columns(&self) -> usize {
+        match self.op {
+            U32Op::Add | U32Op::Sub => 33,
+            U32Op::Mul => 65,
+        }
+    }
+
+    /// Column holding bit `i` of the result. Bits `32..64` are the high word
+    /// of a product.
+    pub fn bit(&self, i: usize) -> usize {
+        assert!(i < self.op.num_result_bits());
+        self.offset + i
+    }
+
+    /// Column holding the carry of an addition or borrow of a subtraction
+    pub fn carry(&self) -> usize {
+        assert!(self.op != U32Op::Mul, "multiplication has no carry");
+        self.offset + 32
+    }
+
+    /// Column holding the inverse of the high word of a product minus
+    /// `2^32 - 1`. See the [module docs](self) for why it's needed.
+    pub fn high_inv(&self) -> usize {
+        assert!(self.op == U32Op::Mul, "only multiplication has a high word");
+        self.offset + 64
+    }
+
+    /// Limb `i` of the result packed from its bits. Limbs `2` and `3` are the
+    /// high word of a product.
+    pub fn limb<F: GpuField>(&self, i: usize, next: bool) -> Constraint<F> {
+        (0..LIMB_BITS).fold(Constraint::zero(), |acc, j| {
+            let bit = self.bit(i * LIMB_BITS + j);
+            let bit = if next { bit.next() } else { bit.curr() };
+            acc + bit * F::from(1u64 << j)
+        })
+    }
+
+    /// The 32-bit result packed from its limbs
+    pub fn result<F: GpuField>(&self, next: bool) -> Constraint<F> {
+        self.limb(0, next) + self.limb(1, next) * F::from(1u64 << LIMB_BITS)
+    }
+
+    /// The high word of a product packed from its limbs
+    pub fn high<F: GpuField>(&self, next: bool) -> Constraint<F> {
+        assert!(self.op == U32Op::Mul, "only multiplication has a high word");
+        self.limb(2, next) + self.limb(3, next) * F::from(1u64 << LIMB_BITS)
+    }
+
+    fn constraints<F: GpuField>(&self, next: bool) -> Vec<Constraint<F>> {
+        let cell = |column: usize| -> Constraint<F> {
+            if next {
+                column.next()
+            } else {
+                column.curr()
+            }
+        };
+        let mut constraints = (0..self.op.num_result_bits())
+            .map(|i| is_binary(cell(self.bit(i))))
+            .collect::<Vec<_>>();
+        let two_pow_32 = F::from(1u64 << 32);
+        let (a, b) = (cell(self.a), cell(self.b));
+        let c = self.result(next);
+        match self.op {
+            U32Op::Add => {
+                constraints.push(is_binary(cell(self.carry())));
+                constraints.push(a + b - c - cell(self.carry()) * two_pow_32);
+            }
+            U32Op::Sub => {
+                constraints.push(is_binary(cell(self.carry())));
+                constraints.push(a - b - c + cell(self.carry()) * two_pow_32);
+            }
+            U32Op::Mul => {
+                let high = self.high(next);
+                let high_inv = cell(self.high_inv());
+                let all_ones = F::from(u32::MAX as u64);
+                constraints.push(a * b - c.clone() - high.clone() * two_pow_32);
+                // the result can only wrap the field when the high word is all ones
+                constraints.push(c * ((high - all_ones) * high_inv - F::one()));
+            }
+        }
+        constraints
+    }
+
+    /// Constraints for the first row of the trace
+    pub fn boundary_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        self.constraints(false)
+    }
+
+    /// Constraints for all rows after the first. They only reference the next
+    /// row.
+    pub fn transition_constraints<F: GpuField>(&self) -> Vec<Constraint<F>> {
+        self.constraints(true)
+    }
+
+    /// Values of the instance's columns for a row with operands `a` and `b`
+    pub fn gen_row<F: GpuField>(&self, a: u32, b: u32) -> Vec<F> {
+        let (low, high) = self.op.apply(a, b);
+        let mut row = (0..32)
+            .map(|i| F::from((low >> i) & 1 == 1))
+            .collect::<Vec<F>>();
+        match self.op {
+            U32Op::Add | U32Op::Sub => row.push(F::from(high as u64)),
+            U32Op::Mul => {
+                row.extend((0..32).map(|i| F::from((high >> i) & 1 == 1)));
+                let high_inv = (F::from(high as u64) - F::from(u32::MAX as u64)).inverse();
+                row.push(high_inv.expect("the high word of a product is never all ones"));
+            }
+        }
+        row
+    }
+}
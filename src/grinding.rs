@@ -0,0 +1,162 @@
+//! Proof-of-work grinding.
+//!
+//! Before query positions are drawn the prover searches for a nonce where
+//! `hash(seed || label || nonce)` has at least `grinding_factor` leading zero
+//! bits. `seed` is the seed of the public coin, `label` is
+//! [TranscriptLabel::PowNonce] encoded as a byte slice and `nonce` is encoded
+//! with the proof's [NonceEncoding]. The hash is the proof's hash function
+//! unless another is chosen with
+//! [ProofOptions::with_grinding_hash](crate::ProofOptions::with_grinding_hash).
+//! The nonce is then absorbed by the public coin like any other item.
+//!
+//! With the default options the proof of work is the leading zeros of the
+//! public coin's seed after it absorbs the nonce.
+use crate::random::leading_zeros;
+use crate::TranscriptLabel;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
+use digest::Digest;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::ops::Range;
+
+/// Width and byte order of the nonce hashed by the proof of work. Narrow
+/// nonces are cheaper to hash in verifiers with 32-bit words.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NonceEncoding {
+    #[default]
+    U64Le = 0,
+    U64Be = 1,
+    U32Le = 2,
+    U32Be = 3,
+}
+
+impl NonceEncoding {
+    /// Largest nonce the encoding can represent
+    pub const fn max_nonce(self) -> u64 {
+        match self {
+            Self::U64Le | Self::U64Be => u64::MAX,
+            Self::U32Le | Self::U32Be => u32::MAX as u64,
+        }
+    }
+
+    /// Returns `None` if the nonce is too wide for the encoding
+    pub fn encode(self, nonce: u64) -> Option<Vec<u8>> {
+        if nonce > self.max_nonce() {
+            return None;
+        }
+        Some(match self {
+            Self::U64Le => nonce.to_le_bytes().to_vec(),
+            Self::U64Be => nonce.to_be_bytes().to_vec(),
+            Self::U32Le => (nonce as u32).to_le_bytes().to_vec(),
+            Self::U32Be => (nonce as u32).to_be_bytes().to_vec(),
+        })
+    }
+}
+
+impl CanonicalSerialize for NonceEncoding {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        (*self as u8).serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        (*self as u8).serialized_size(compress)
+    }
+}
+
+impl Valid for NonceEncoding {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for NonceEncoding {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(NonceEncoding::U64Le),
+            1 => Ok(NonceEncoding::U64Be),
+            2 => Ok(NonceEncoding::U32Le),
+            3 => Ok(NonceEncoding::U32Be),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// Nonces searched before moving on to the next batch. Batches are searched
+/// in order so threads don't hash nonces far past the smallest valid one.
+const GRINDING_BATCH_SIZE: u64 = 1 << 14;
+
+/// Returns the number of leading zero bits of `hash(seed || label || nonce)`
+/// or `None` if the nonce is too wide for the encoding
+pub fn proof_of_work<D: Digest>(seed: &[u8], nonce: u64, encoding: NonceEncoding) -> Option<u32> {
+    Some(hash_leading_zeros::<D>(
+        seed,
+        &pow_label_bytes(),
+        &encoding.encode(nonce)?,
+    ))
+}
+
+/// Returns the smallest nonce (starting from 1) whose proof of work has at
+/// least `difficulty` leading zero bits. Returns `None` if every nonce the
+/// encoding can represent falls short. Nonces are hashed on every thread when
+/// the `parallel` feature is enabled.
+pub fn grind<D: Digest>(seed: &[u8], difficulty: u32, encoding: NonceEncoding) -> Option<u64> {
+    let label_bytes = pow_label_bytes();
+    let is_valid = |&nonce: &u64| {
+        let nonce_bytes = encoding.encode(nonce).unwrap();
+        hash_leading_zeros::<D>(seed, &label_bytes, &nonce_bytes) >= difficulty
+    };
+
+    // the smallest nonce is taken so proofs don't depend on thread scheduling
+    let max_nonce = encoding.max_nonce();
+    (1..max_nonce)
+        .step_by(GRINDING_BATCH_SIZE as usize)
+        .find_map(|start| {
+            let end = start.saturating_add(GRINDING_BATCH_SIZE).min(max_nonce);
+            find_first(start..end, is_valid)
+        })
+}
+
+#[cfg(not(feature = "parallel"))]
+fn find_first(mut nonces: Range<u64>, is_valid: impl Fn(&u64) -> bool) -> Option<u64> {
+    nonces.find(is_valid)
+}
+
+#[cfg(feature = "parallel")]
+fn find_first(nonces: Range<u64>, is_valid: impl Fn(&u64) -> bool + Sync + Send) -> Option<u64> {
+    nonces.into_par_iter().find_first(is_valid)
+}
+
+fn pow_label_bytes() -> Vec<u8> {
+    let mut label_bytes = Vec::new();
+    TranscriptLabel::PowNonce
+        .as_str()
+        .as_bytes()
+        .serialize_compressed(&mut label_bytes)
+        .unwrap();
+    label_bytes
+}
+
+fn hash_leading_zeros<D: Digest>(seed: &[u8], label_bytes: &[u8], nonce_bytes: &[u8]) -> u32 {
+    let mut hasher = D::new();
+    hasher.update(seed);
+    hasher.update(label_bytes);
+    hasher.update(nonce_bytes);
+    leading_zeros(&hasher.finalize())
+}
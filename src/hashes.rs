@@ -0,0 +1,367 @@
+//! Hash functions used for commitments and the Fiat-Shamir transcript.
+//!
+//! The hash is chosen per proof with [HashFunction]. SHA-256 is the default.
+//! Blake2b is aligned with EIP-152 so EVM verifiers can use the BLAKE2b `F`
+//! precompile rather than hashing in contract code.
+//!
+//! Commitments that will be verified inside another STARK should avoid
+//! SHA-256 because proving a bitwise hash costs far more trace rows than a
+//! hash defined over the field. [RescueDigest] and [GriffinDigest] are
+//! sponges over the 64-bit prime field `2^64 - 2^32 + 1` with width 12, rate 8
+//! and a 4 element (32 byte) output. They can be used anywhere a [Digest] is
+//! expected, such as `MerkleTree<RescueDigest>`.
+//!
+//! Input bytes are packed 7 at a time into field elements so every chunk is
+//! below the modulus. A block of [BLOCK_SIZE] bytes is absorbed per
+//! permutation. The message is padded with a single `1` byte followed by
+//! zeros. Digest bytes are the little-endian canonical form of each output
+//! element.
+use crate::gadgets::rescue;
+use crate::gadgets::rescue::RescuePrime;
+use crate::gadgets::rescue::DIGEST_RANGE;
+use crate::gadgets::rescue::RATE;
+use crate::gadgets::rescue::RATE_RANGE;
+use crate::gadgets::rescue::STATE_WIDTH;
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::PrimeField;
+use ark_ff::Zero;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
+use digest::consts::U32;
+use digest::Digest;
+use digest::FixedOutput;
+use digest::FixedOutputReset;
+use digest::HashMarker;
+use digest::Output;
+use digest::OutputSizeUser;
+use digest::Reset;
+use digest::Update;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use once_cell::sync::Lazy;
+use sha2::Sha256;
+
+/// Hash function used for a proof's commitments and transcript. Every
+/// supported hash has a 32 byte digest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HashFunction {
+    #[default]
+    Sha256 = 0,
+    /// Blake2b with a 32 byte digest (see [Blake2b256])
+    Blake2b = 1,
+    Blake2s = 2,
+    Rescue = 3,
+    Griffin = 4,
+}
+
+impl HashFunction {
+    /// Bits of collision resistance of the hash. The byte oriented hashes
+    /// have 256 bit digests. [RescueDigest] and [GriffinDigest] digests are
+    /// four elements below `p < 2^64` so there are slightly fewer than
+    /// `2^256` of them and the sponge capacity is the same four elements.
+    /// Packing 7 bytes into each rate element is injective so it doesn't
+    /// lose anything on top of that.
+    pub const fn collision_resistance(self) -> usize {
+        match self {
+            HashFunction::Sha256 | HashFunction::Blake2b | HashFunction::Blake2s => 128,
+            HashFunction::Rescue | HashFunction::Griffin => 127,
+        }
+    }
+}
+
+impl CanonicalSerialize for HashFunction {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        (*self as u8).serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        (*self as u8).serialized_size(compress)
+    }
+}
+
+impl Valid for HashFunction {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for HashFunction {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(HashFunction::Sha256),
+            1 => Ok(HashFunction::Blake2b),
+            2 => Ok(HashFunction::Blake2s),
+            3 => Ok(HashFunction::Rescue),
+            4 => Ok(HashFunction::Griffin),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+/// Blake2b with a 32 byte digest. Its compression function is the one EIP-152
+/// exposes as a precompile. The digest length is part of the parameter block
+/// so digests differ from truncated Blake2b-512 digests.
+pub type Blake2b256 = blake2::Blake2b<U32>;
+
+/// Number of bytes packed into each field element
+const BYTES_PER_ELEMENT: usize = 7;
+
+/// Number of bytes absorbed by each permutation
+pub const BLOCK_SIZE: usize = RATE * BYTES_PER_ELEMENT;
+
+/// A permutation of the sponge state
+pub trait Permutation: Send + Sync + 'static {
+    /// Instance shared by all hashers. Round constants are derived once.
+    fn shared() -> &'static Self;
+
+    fn permute(&self, state: &mut [Fp; STATE_WIDTH]);
+}
+
+static RESCUE_PRIME: Lazy<RescuePrime> = Lazy::new(RescuePrime::new);
+
+impl Permutation for RescuePrime {
+    fn shared() -> &'static Self {
+        &RESCUE_PRIME
+    }
+
+    fn permute(&self, state: &mut [Fp; STATE_WIDTH]) {
+        RescuePrime::permute(self, state)
+    }
+}
+
+/// Number of rounds of the Griffin permutation
+pub const GRIFFIN_NUM_ROUNDS: usize = 9;
+
+/// `(p - 1) / 2` used to test for quadratic residues
+const LEGENDRE_EXPONENT: u64 = 0x7FFFFFFF80000000;
+
+/// Griffin-π permutation with state width 12 and S-box degree [rescue::ALPHA].
+///
+/// Like [RescuePrime] the constants are derived with SHA-256 (see
+/// [Griffin::new]) so digests aren't compatible with other implementations.
+#[derive(Clone, Debug)]
+pub struct Griffin {
+    alpha: [Fp; STATE_WIDTH],
+    beta: [Fp; STATE_WIDTH],
+    round_constants: [[Fp; STATE_WIDTH]; GRIFFIN_NUM_ROUNDS - 1],
+}
+
+impl Griffin {
+    /// Constants are the first 8 bytes (little-endian) of
+    /// `SHA256("ministark griffin" || counter)` reduced into the field. `α_2`
+    /// and `β_2` are drawn first, retrying until `α_2^2 - 4β_2` is a quadratic
+    /// non-residue, followed by the round constants. The remaining `α_i` and
+    /// `β_i` are `(i - 1)α_2` and `(i - 1)^2β_2`.
+    pub fn new() -> Self {
+        let mut counter = 0u64;
+        let mut next_constant = || {
+            let mut hasher = Sha256::new_with_prefix(b"ministark griffin");
+            Digest::update(&mut hasher, counter.to_le_bytes());
+            counter += 1;
+            let digest = hasher.finalize();
+            Fp::from(u64::from_le_bytes(digest[0..8].try_into().unwrap()))
+        };
+
+        let (alpha2, beta2) = loop {
+            let (alpha2, beta2) = (next_constant(), next_constant());
+            let discriminant = alpha2.square() - beta2.double().double();
+            if !discriminant.is_zero() && !discriminant.pow([LEGENDRE_EXPONENT]).is_one() {
+                break (alpha2, beta2);
+            }
+        };
+
+        let mut alpha = [Fp::zero(); STATE_WIDTH];
+        let mut beta = [Fp::zero(); STATE_WIDTH];
+        for i in 2..STATE_WIDTH {
+            let multiple = Fp::from((i - 1) as u64);
+            alpha[i] = multiple * alpha2;
+            beta[i] = multiple.square() * beta2;
+        }
+
+        let mut round_constants = [[Fp::zero(); STATE_WIDTH]; GRIFFIN_NUM_ROUNDS - 1];
+        for round_constants in &mut round_constants {
+            *round_constants = [(); STATE_WIDTH].map(|_| next_constant());
+        }
+
+        Griffin {
+            alpha,
+            beta,
+            round_constants,
+        }
+    }
+
+    fn apply_nonlinear_layer(&self, state: &mut [Fp; STATE_WIDTH]) {
+        let x = *state;
+        state[0] = x[0].pow([rescue::INV_ALPHA]);
+        state[1] = x[1].pow([rescue::ALPHA]);
+        for i in 2..STATE_WIDTH {
+            let gamma = Fp::from((i - 1) as u64);
+            let mut l = gamma * state[0] + state[1];
+            if i > 2 {
+                l += x[i - 1];
+            }
+            state[i] = x[i] * (l.square() + self.alpha[i] * l + self.beta[i]);
+        }
+    }
+
+    /// Multiplies the state by `circ(2M, M, M)` where `M` is the 4x4 matrix
+    /// from the Griffin paper
+    fn apply_linear_layer(state: &mut [Fp; STATE_WIDTH]) {
+        for chunk in state.array_chunks_mut::<4>() {
+            *chunk = Self::apply_m4(chunk);
+        }
+        let mut sums = [Fp::zero(); 4];
+        for chunk in state.array_chunks::<4>() {
+            sums.iter_mut().zip(chunk).for_each(|(s, &v)| *s += v);
+        }
+        for chunk in state.array_chunks_mut::<4>() {
+            chunk.iter_mut().zip(sums).for_each(|(v, s)| *v += s);
+        }
+    }
+
+    /// `[[5, 7, 1, 3], [4, 6, 1, 1], [1, 3, 5, 7], [1, 1, 4, 6]] * x`
+    fn apply_m4(x: &[Fp; 4]) -> [Fp; 4] {
+        let t0 = x[0] + x[1];
+        let t1 = x[2] + x[3];
+        let t2 = x[1].double() + t1;
+        let t3 = x[3].double() + t0;
+        let t4 = t1.double().double() + t3;
+        let t5 = t0.double().double() + t2;
+        let t6 = t3 + t5;
+        let t7 = t2 + t4;
+        [t6, t5, t7, t4]
+    }
+
+    pub fn permute(&self, state: &mut [Fp; STATE_WIDTH]) {
+        Self::apply_linear_layer(state);
+        for round in 0..GRIFFIN_NUM_ROUNDS {
+            self.apply_nonlinear_layer(state);
+            Self::apply_linear_layer(state);
+            if let Some(round_constants) = self.round_constants.get(round) {
+                state
+                    .iter_mut()
+                    .zip(round_constants)
+                    .for_each(|(s, c)| *s += c);
+            }
+        }
+    }
+}
+
+impl Default for Griffin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+static GRIFFIN: Lazy<Griffin> = Lazy::new(Griffin::new);
+
+impl Permutation for Griffin {
+    fn shared() -> &'static Self {
+        &GRIFFIN
+    }
+
+    fn permute(&self, state: &mut [Fp; STATE_WIDTH]) {
+        Griffin::permute(self, state)
+    }
+}
+
+/// Sponge construction over the permutation `P` that hashes bytes
+#[derive(Clone)]
+pub struct SpongeDigest<P: 'static> {
+    permutation: &'static P,
+    state: [Fp; STATE_WIDTH],
+    block: [u8; BLOCK_SIZE],
+    block_len: usize,
+}
+
+pub type RescueDigest = SpongeDigest<RescuePrime>;
+
+pub type GriffinDigest = SpongeDigest<Griffin>;
+
+impl<P: Permutation> SpongeDigest<P> {
+    fn absorb_block(&mut self) {
+        let elements = self.block.array_chunks::<BYTES_PER_ELEMENT>();
+        for (s, bytes) in self.state[RATE_RANGE].iter_mut().zip(elements) {
+            let mut element_bytes = [0; 8];
+            element_bytes[..BYTES_PER_ELEMENT].copy_from_slice(bytes);
+            *s += Fp::from(u64::from_le_bytes(element_bytes));
+        }
+        self.permutation.permute(&mut self.state);
+        self.block_len = 0;
+    }
+
+    fn finalize_state(&mut self, out: &mut Output<Self>) {
+        self.block[self.block_len..].fill(0);
+        self.block[self.block_len] = 1;
+        self.absorb_block();
+        for (bytes, element) in out.chunks_mut(8).zip(&self.state[DIGEST_RANGE]) {
+            bytes.copy_from_slice(&element.into_bigint().0[0].to_le_bytes());
+        }
+    }
+}
+
+impl<P: Permutation> Default for SpongeDigest<P> {
+    fn default() -> Self {
+        SpongeDigest {
+            permutation: P::shared(),
+            state: [Fp::zero(); STATE_WIDTH],
+            block: [0; BLOCK_SIZE],
+            block_len: 0,
+        }
+    }
+}
+
+impl<P: Permutation> HashMarker for SpongeDigest<P> {}
+
+impl<P: Permutation> OutputSizeUser for SpongeDigest<P> {
+    type OutputSize = U32;
+}
+
+impl<P: Permutation> Update for SpongeDigest<P> {
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let n = data.len().min(BLOCK_SIZE - self.block_len);
+            self.block[self.block_len..self.block_len + n].copy_from_slice(&data[..n]);
+            self.block_len += n;
+            data = &data[n..];
+            if self.block_len == BLOCK_SIZE {
+                self.absorb_block();
+            }
+        }
+    }
+}
+
+impl<P: Permutation> FixedOutput for SpongeDigest<P> {
+    fn finalize_into(mut self, out: &mut Output<Self>) {
+        self.finalize_state(out);
+    }
+}
+
+impl<P: Permutation> Reset for SpongeDigest<P> {
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+impl<P: Permutation> FixedOutputReset for SpongeDigest<P> {
+    fn finalize_into_reset(&mut self, out: &mut Output<Self>) {
+        self.finalize_state(out);
+        Reset::reset(self);
+    }
+}
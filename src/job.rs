@@ -0,0 +1,233 @@
+//! Proofs generated in the background.
+//!
+//! [Prover::generate_proof_async] moves proving onto a background thread and
+//! returns a [ProofFuture]. The proving thread reports each phase of the
+//! pipeline as it starts and wakes the task awaiting the future, so services
+//! can await proofs (and report progress) without blocking a runtime worker.
+//!
+//! An [AbortHandle] stops a proof at the next phase boundary, interaction
+//! round or chunk of constraint evaluations. Proving returns
+//! [ProvingError::Aborted] and dropping its state frees the LDEs (GPU buffers
+//! included) and removes spilled files.
+//!
+//! [Prover::generate_proof_async]: crate::Prover::generate_proof_async
+use crate::Air;
+use crate::Proof;
+use crate::ProvingError;
+use std::cell::RefCell;
+use std::future::Future;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Waker;
+
+/// Phases of the proving pipeline in the order they run
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ProvingPhase {
+    /// Waiting for a thread of the pool to pick up the proof
+    Queued,
+    /// Validating the trace and building the AIR
+    Setup,
+    /// Committing to the base and extension columns
+    Trace,
+    /// Evaluating constraints and committing to the composition trace
+    Composition,
+    /// Evaluating the trace and composition polynomials out of domain
+    OutOfDomain,
+    /// Building the DEEP composition polynomial
+    DeepComposition,
+    /// Committing to the layers of the low-degree test
+    LowDegree,
+    /// Searching for the proof of work nonce
+    Grinding,
+    /// Opening the commitments at the query positions
+    Queries,
+    /// The proof (or an error) is ready
+    Done,
+}
+
+#[derive(Debug)]
+struct JobState {
+    phase: ProvingPhase,
+    waker: Option<Waker>,
+}
+
+impl JobState {
+    fn enter(&mut self, phase: ProvingPhase) {
+        self.phase = phase;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Stops an in-flight proof. Clones share the same flag.
+#[derive(Clone, Debug, Default)]
+pub struct AbortHandle(Arc<AtomicBool>);
+
+impl AbortHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the proof to stop at its next boundary. Returns immediately.
+    pub fn abort(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Progress and abort flag of the proof running on a thread
+#[derive(Default)]
+struct Job {
+    state: Option<Arc<Mutex<JobState>>>,
+    abort: Option<AbortHandle>,
+}
+
+thread_local! {
+    static CURRENT_JOB: RefCell<Job> = RefCell::default();
+}
+
+/// Restores the previous job of the thread even if proving panics
+struct JobGuard(Option<Job>);
+
+impl Drop for JobGuard {
+    fn drop(&mut self) {
+        let previous = self.0.take().unwrap();
+        CURRENT_JOB.with(|job| *job.borrow_mut() = previous);
+    }
+}
+
+fn with_job<T>(job: Job, f: impl FnOnce() -> T) -> T {
+    let _guard = JobGuard(Some(CURRENT_JOB.with(|current| current.replace(job))));
+    f()
+}
+
+/// Runs `f` on this thread with proving steps checking `abort`
+pub(crate) fn with_abort_handle<T>(abort: AbortHandle, f: impl FnOnce() -> T) -> T {
+    with_job(
+        Job {
+            state: None,
+            abort: Some(abort),
+        },
+        f,
+    )
+}
+
+/// Fails with [ProvingError::Aborted] if the proof running on this thread has
+/// been aborted
+pub(crate) fn check_aborted() -> Result<(), ProvingError> {
+    CURRENT_JOB.with(|job| match &job.borrow().abort {
+        Some(abort) if abort.is_aborted() => Err(ProvingError::Aborted),
+        _ => Ok(()),
+    })
+}
+
+/// Records the start of a phase for the job running on this thread. Fails if
+/// the job has been aborted. Proofs generated synchronously without an
+/// [AbortHandle] have no job so this always succeeds.
+pub(crate) fn enter_phase(phase: ProvingPhase) -> Result<(), ProvingError> {
+    check_aborted()?;
+    CURRENT_JOB.with(|job| {
+        if let Some(state) = &job.borrow().state {
+            state.lock().unwrap().enter(phase);
+        }
+    });
+    Ok(())
+}
+
+/// Proof being generated on a background thread. Resolves once proving
+/// finishes. The task awaiting the future is also woken each time proving
+/// moves on to the next [ProvingPhase].
+pub struct ProofFuture<A: Air> {
+    state: Arc<Mutex<JobState>>,
+    result: Arc<Mutex<Option<Result<Proof<A>, ProvingError>>>>,
+    abort: AbortHandle,
+}
+
+impl<A: Air> ProofFuture<A> {
+    /// Returns the future and the job to run on the background thread
+    pub(crate) fn new() -> (Self, ProofJob<A>) {
+        let state = Arc::new(Mutex::new(JobState {
+            phase: ProvingPhase::Queued,
+            waker: None,
+        }));
+        let result = Arc::new(Mutex::new(None));
+        let abort = AbortHandle::new();
+        let job = ProofJob {
+            state: Arc::clone(&state),
+            result: Arc::clone(&result),
+            abort: abort.clone(),
+        };
+        (
+            ProofFuture {
+                state,
+                result,
+                abort,
+            },
+            job,
+        )
+    }
+
+    /// Phase the proving thread is currently in
+    pub fn phase(&self) -> ProvingPhase {
+        self.state.lock().unwrap().phase
+    }
+
+    /// Handle for stopping the proof. The future then resolves with
+    /// [ProvingError::Aborted]. Dropping the future doesn't stop proving.
+    pub fn abort_handle(&self) -> AbortHandle {
+        self.abort.clone()
+    }
+}
+
+impl<A: Air> Future for ProofFuture<A> {
+    type Output = Result<Proof<A>, ProvingError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // the job stores its result before taking the state lock to finish so
+        // holding the lock here means a result can't be missed
+        let mut state = self.state.lock().unwrap();
+        if let Some(result) = self.result.lock().unwrap().take() {
+            return Poll::Ready(result);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Proving thread's half of a [ProofFuture]
+pub(crate) struct ProofJob<A: Air> {
+    state: Arc<Mutex<JobState>>,
+    result: Arc<Mutex<Option<Result<Proof<A>, ProvingError>>>>,
+    abort: AbortHandle,
+}
+
+impl<A: Air> ProofJob<A> {
+    /// Runs `prove` on the current thread reporting its phases to the future.
+    /// A panic while proving resolves the future with [ProvingError::Fail]
+    /// rather than leaving it pending forever.
+    pub(crate) fn run(self, prove: impl FnOnce() -> Result<Proof<A>, ProvingError>) {
+        let job = Job {
+            state: Some(Arc::clone(&self.state)),
+            abort: Some(self.abort),
+        };
+        let run = || {
+            with_job(job, || {
+                enter_phase(ProvingPhase::Setup).and_then(|_| prove())
+            })
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(run)).unwrap_or(Err(ProvingError::Fail));
+        *self.result.lock().unwrap() = Some(result);
+        self.state.lock().unwrap().enter(ProvingPhase::Done);
+    }
+}
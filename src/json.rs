@@ -0,0 +1,207 @@
+//! Human readable JSON encoding of proofs.
+//!
+//! Meant for test fixtures and for diffing proofs from different
+//! implementations. The binary ark-serialize format is much smaller and
+//! should be used everywhere else. Every section of a proof is a named field:
+//!
+//! - byte strings (commitments, Merkle proofs, public inputs) are lowercase
+//!   hex strings
+//! - field elements are hex strings of their compressed ark-serialize bytes
+//! - arrays are JSON arrays
+//! - the proof header and options are encoded with their serde representation
+//!
+//! The low-degree proof is an object with a single `fri` or `stir` field
+//! holding the proof of that low-degree test.
+use crate::Air;
+use crate::Proof;
+use crate::ProofHeader;
+use crate::ProofOptions;
+use crate::TraceInfo;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::SerializationError;
+use serde_json::json;
+use serde_json::Value;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum JsonError {
+    #[error("invalid json")]
+    Syntax(#[from] serde_json::Error),
+    #[error("missing field `{0}`")]
+    MissingField(&'static str),
+    #[error("expected {0}")]
+    UnexpectedType(&'static str),
+    #[error("invalid hex string")]
+    InvalidHex,
+    #[error("value could not be deserialized")]
+    Deserialization(#[from] SerializationError),
+}
+
+/// Types that can be converted to and from JSON
+pub trait JsonEncode: Sized {
+    fn encode_json(&self) -> Value;
+
+    fn decode_json(value: &Value) -> Result<Self, JsonError>;
+
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.encode_json()).unwrap()
+    }
+
+    fn from_json(json: &str) -> Result<Self, JsonError> {
+        Self::decode_json(&serde_json::from_str(json)?)
+    }
+}
+
+pub fn bytes_to_json(bytes: &[u8]) -> Value {
+    Value::String(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+pub fn bytes_from_json(value: &Value) -> Result<Vec<u8>, JsonError> {
+    let hex = value
+        .as_str()
+        .ok_or(JsonError::UnexpectedType("a hex string"))?;
+    if hex.len() % 2 != 0 {
+        return Err(JsonError::InvalidHex);
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or(JsonError::InvalidHex)
+        })
+        .collect()
+}
+
+pub fn element_to_json<F: CanonicalSerialize>(element: &F) -> Value {
+    let mut bytes = Vec::new();
+    element.serialize_compressed(&mut bytes).unwrap();
+    bytes_to_json(&bytes)
+}
+
+pub fn element_from_json<F: CanonicalDeserialize>(value: &Value) -> Result<F, JsonError> {
+    let bytes = bytes_from_json(value)?;
+    Ok(F::deserialize_compressed(bytes.as_slice())?)
+}
+
+pub fn elements_to_json<F: CanonicalSerialize>(elements: &[F]) -> Value {
+    Value::Array(elements.iter().map(element_to_json).collect())
+}
+
+pub fn elements_from_json<F: CanonicalDeserialize>(value: &Value) -> Result<Vec<F>, JsonError> {
+    array_from_json(value, element_from_json)
+}
+
+pub fn uint_from_json(value: &Value) -> Result<u64, JsonError> {
+    value
+        .as_u64()
+        .ok_or(JsonError::UnexpectedType("an unsigned integer"))
+}
+
+/// Decodes each item of a JSON array with `decode`
+pub fn array_from_json<T>(
+    value: &Value,
+    decode: impl FnMut(&Value) -> Result<T, JsonError>,
+) -> Result<Vec<T>, JsonError> {
+    value
+        .as_array()
+        .ok_or(JsonError::UnexpectedType("an array"))?
+        .iter()
+        .map(decode)
+        .collect()
+}
+
+/// Returns the field `name` of a JSON object
+pub fn field<'a>(value: &'a Value, name: &'static str) -> Result<&'a Value, JsonError> {
+    value
+        .as_object()
+        .ok_or(JsonError::UnexpectedType("an object"))?
+        .get(name)
+        .ok_or(JsonError::MissingField(name))
+}
+
+impl<T: JsonEncode> JsonEncode for Vec<T> {
+    fn encode_json(&self) -> Value {
+        Value::Array(self.iter().map(T::encode_json).collect())
+    }
+
+    fn decode_json(value: &Value) -> Result<Self, JsonError> {
+        array_from_json(value, T::decode_json)
+    }
+}
+
+impl<A: Air> JsonEncode for Proof<A> {
+    fn encode_json(&self) -> Value {
+        let public_inputs = self.public_inputs.as_ref().map(|public_inputs| {
+            let mut bytes = Vec::new();
+            public_inputs.serialize_compressed(&mut bytes).unwrap();
+            bytes_to_json(&bytes)
+        });
+        let ood_trace_states = self
+            .ood_trace_states
+            .iter()
+            .map(|frame| frame.iter().map(|row| elements_to_json(row)).collect())
+            .collect::<Vec<Value>>();
+        let ood_constraint_evaluations = self
+            .ood_constraint_evaluations
+            .iter()
+            .map(|evaluations| elements_to_json(evaluations))
+            .collect::<Vec<Value>>();
+        json!({
+            "header": self.header,
+            "options": self.options,
+            "trace_info": self.trace_info.encode_json(),
+            "base_trace_commitment": bytes_to_json(&self.base_trace_commitment),
+            "extension_trace_commitments": self
+                .extension_trace_commitments
+                .iter()
+                .map(|commitment| bytes_to_json(commitment))
+                .collect::<Vec<Value>>(),
+            "composition_trace_commitment": bytes_to_json(&self.composition_trace_commitment),
+            "low_degree_proof": self.low_degree_proof.encode_json(),
+            "pow_nonce": self.pow_nonce,
+            "trace_queries": self.trace_queries.encode_json(),
+            "public_inputs": public_inputs,
+            "ood_trace_states": ood_trace_states,
+            "ood_constraint_evaluations": ood_constraint_evaluations,
+        })
+    }
+
+    fn decode_json(value: &Value) -> Result<Self, JsonError> {
+        let header: ProofHeader = serde_json::from_value(field(value, "header")?.clone())?;
+        let options: ProofOptions = serde_json::from_value(field(value, "options")?.clone())?;
+        let public_inputs = match field(value, "public_inputs")? {
+            Value::Null => None,
+            public_inputs => {
+                let bytes = bytes_from_json(public_inputs)?;
+                Some(A::PublicInputs::deserialize_compressed(bytes.as_slice())?)
+            }
+        };
+        Ok(Proof {
+            header,
+            options,
+            trace_info: TraceInfo::decode_json(field(value, "trace_info")?)?,
+            base_trace_commitment: bytes_from_json(field(value, "base_trace_commitment")?)?,
+            extension_trace_commitments: array_from_json(
+                field(value, "extension_trace_commitments")?,
+                bytes_from_json,
+            )?,
+            composition_trace_commitment: bytes_from_json(field(
+                value,
+                "composition_trace_commitment",
+            )?)?,
+            low_degree_proof: JsonEncode::decode_json(field(value, "low_degree_proof")?)?,
+            pow_nonce: uint_from_json(field(value, "pow_nonce")?)?,
+            trace_queries: JsonEncode::decode_json(field(value, "trace_queries")?)?,
+            public_inputs,
+            ood_trace_states: array_from_json(field(value, "ood_trace_states")?, |frame| {
+                array_from_json(frame, elements_from_json)
+            })?,
+            ood_constraint_evaluations: array_from_json(
+                field(value, "ood_constraint_evaluations")?,
+                elements_from_json,
+            )?,
+        })
+    }
+}
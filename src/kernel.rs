@@ -0,0 +1,497 @@
+//! Compiled constraint evaluation.
+//!
+//! Constraints are stored as sums of terms `c * x_0^e_0 * ... * x_k^e_k`.
+//! Evaluating them term by term recomputes a power like `x_0^3` for every term
+//! it appears in, which adds up for high degree constraints whose expansion
+//! has hundreds of terms sharing the same few variables. A [ConstraintKernel]
+//! lowers the constraints once into a register bytecode ([Op]) that evaluates
+//! all of them in a single pass:
+//!
+//! - challenges and hints are folded into the coefficients and terms left with
+//!   the same variables are merged
+//! - every distinct `(column, row offset)` is loaded once
+//! - every distinct power is computed once by multiplying up from the next
+//!   smaller power of the same value
+//! - terms are sorted so that neighbouring terms share leading factors and only
+//!   the factors after the shared prefix are multiplied
+//!
+//! The bytecode is run over LDE columns by the prover, over a single frame of
+//! out-of-domain values by the verifier and, with the `gpu` feature, emitted as
+//! the Metal source of a single compute kernel. The source only depends on the
+//! shape of the constraints (coefficients are passed in a buffer) so the
+//! planner compiles it once per AIR and later proofs reuse the pipeline.
+use crate::constraint::Element;
+use crate::constraint::Term;
+use crate::matrix::Col;
+use crate::matrix::MatrixGroup;
+use crate::Constraint;
+use ark_ff::One;
+use ark_ff::Zero;
+#[cfg(feature = "gpu")]
+use gpu_poly::prelude::*;
+use gpu_poly::GpuField;
+use gpu_poly::GpuMulAssign;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+#[cfg(feature = "gpu")]
+use std::fmt::Write;
+
+/// Instruction of a compiled constraint program. There are three register
+/// files: `powers` holding powers of column values, `partials` holding the
+/// running product of the current term (`partials[0]` is one) and a single
+/// accumulator.
+#[derive(Clone, Copy, Debug)]
+enum Op {
+    /// `powers[dst] = powers[base] * load^exponent` or `load^exponent` if
+    /// there's no base. Only appears before any other op.
+    Pow {
+        dst: usize,
+        base: Option<usize>,
+        load: usize,
+        exponent: usize,
+    },
+    /// `partials[depth + 1] = partials[depth] * powers[power]`
+    Mul { depth: usize, power: usize },
+    /// `acc += partials[depth] * coeffs[coeff]`
+    Accumulate { depth: usize, coeff: usize },
+    /// Writes `acc` as the evaluation of `constraint` and resets it to zero
+    Store { constraint: usize },
+}
+
+pub struct ConstraintKernel<F> {
+    /// Distinct `(column, row offset)` pairs the constraints depend on
+    loads: Vec<(usize, usize)>,
+    /// Index into `loads` of the value each power is a power of
+    power_loads: Vec<usize>,
+    coeffs: Vec<F>,
+    ops: Vec<Op>,
+    num_constraints: usize,
+    /// Number of factors of the largest term
+    max_depth: usize,
+}
+
+impl<F: GpuField> ConstraintKernel<F> {
+    /// Lowers constraints to bytecode. Challenges and hints are substituted
+    /// with their values.
+    pub fn new<'a>(
+        constraints: impl IntoIterator<Item = &'a Constraint<F>>,
+        challenges: &[F],
+        hints: &[F],
+    ) -> Self {
+        // fold constants. Terms are keyed by their `(load, exponent)` factors
+        let mut load_indices = BTreeMap::new();
+        let mut folded_constraints = Vec::new();
+        for constraint in constraints {
+            let mut terms = BTreeMap::<Vec<(usize, usize)>, F>::new();
+            for Term(coeff, variables) in constraint.0.iter() {
+                let mut coeff = *coeff;
+                let mut factors = Vec::new();
+                for &(element, exponent) in &variables.0 {
+                    let exp = [exponent as u64];
+                    match element {
+                        Element::Challenge(index) => coeff *= challenges[index].pow(exp),
+                        Element::Hint(index) => coeff *= hints[index].pow(exp),
+                        _ => {
+                            let column_offset = element.column_offset().unwrap();
+                            let num_loads = load_indices.len();
+                            let load = *load_indices.entry(column_offset).or_insert(num_loads);
+                            factors.push((load, exponent));
+                        }
+                    }
+                }
+                factors.sort_unstable();
+                *terms.entry(factors).or_insert_with(F::zero) += coeff;
+            }
+            terms.retain(|_, coeff| !coeff.is_zero());
+            folded_constraints.push(terms);
+        }
+
+        let mut loads = vec![(0, 0); load_indices.len()];
+        for (column_offset, load) in load_indices {
+            loads[load] = column_offset;
+        }
+
+        // powers of the same load are sorted by exponent so each one can be
+        // computed from the one before it
+        let load_powers = folded_constraints
+            .iter()
+            .flat_map(|terms| terms.keys().flatten().copied())
+            .collect::<BTreeSet<(usize, usize)>>();
+        let mut ops = Vec::new();
+        let mut power_loads = Vec::new();
+        let mut power_indices = BTreeMap::new();
+        let mut prev: Option<(usize, usize)> = None;
+        for &(load, exponent) in &load_powers {
+            let dst = power_loads.len();
+            ops.push(match prev {
+                Some((prev_load, prev_exponent)) if prev_load == load => Op::Pow {
+                    dst,
+                    base: Some(dst - 1),
+                    load,
+                    exponent: exponent - prev_exponent,
+                },
+                _ => Op::Pow {
+                    dst,
+                    base: None,
+                    load,
+                    exponent,
+                },
+            });
+            power_indices.insert((load, exponent), dst);
+            power_loads.push(load);
+            prev = Some((load, exponent));
+        }
+
+        // power indices increase with `(load, exponent)` so the terms are
+        // already sorted by their factors
+        let mut coeffs = Vec::new();
+        let mut max_depth = 0;
+        let num_constraints = folded_constraints.len();
+        for (constraint, terms) in folded_constraints.iter().enumerate() {
+            let mut prev_factors: &[(usize, usize)] = &[];
+            for (factors, coeff) in terms {
+                let shared = prev_factors
+                    .iter()
+                    .zip(factors)
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                for (depth, factor) in factors.iter().enumerate().skip(shared) {
+                    let power = power_indices[factor];
+                    ops.push(Op::Mul { depth, power });
+                }
+                ops.push(Op::Accumulate {
+                    depth: factors.len(),
+                    coeff: coeffs.len(),
+                });
+                coeffs.push(*coeff);
+                max_depth = max_depth.max(factors.len());
+                prev_factors = factors;
+            }
+            ops.push(Op::Store { constraint });
+        }
+
+        ConstraintKernel {
+            loads,
+            power_loads,
+            coeffs,
+            ops,
+            num_constraints,
+            max_depth,
+        }
+    }
+
+    /// Evaluates the constraints on a frame of rows. `frame[k]` is the row `k`
+    /// cycles after the current row.
+    pub fn evaluate_frame(&self, frame: &[&[F]]) -> Vec<F> {
+        let mut powers = vec![F::zero(); self.power_loads.len()];
+        let mut partials = vec![F::one(); self.max_depth + 1];
+        let mut acc = F::zero();
+        let mut results = vec![F::zero(); self.num_constraints];
+        for &op in &self.ops {
+            match op {
+                Op::Pow {
+                    dst,
+                    base,
+                    load,
+                    exponent,
+                } => {
+                    let (column, row_offset) = self.loads[load];
+                    let value = frame[row_offset][column].pow([exponent as u64]);
+                    powers[dst] = base.map_or(value, |base| powers[base] * value);
+                }
+                Op::Mul { depth, power } => partials[depth + 1] = partials[depth] * powers[power],
+                Op::Accumulate { depth, coeff } => acc += partials[depth] * self.coeffs[coeff],
+                Op::Store { constraint } => {
+                    results[constraint] = acc;
+                    acc = F::zero();
+                }
+            }
+        }
+        results
+    }
+
+    /// Evaluates the constraints on the rows starting at `offset`. Row `i`
+    /// of `results[j]` receives the evaluation of constraint `j` on row
+    /// `offset + i`. Column values at row offset `k` are read `k * step` rows
+    /// further down, wrapping around at the end of the columns.
+    ///
+    /// Rows are evaluated [BATCH_LEN] at a time. Every register holds one
+    /// value per row of the batch so decoding an op and looking up its column
+    /// is done once per batch rather than once per row.
+    pub fn evaluate_rows<Fp: GpuField>(
+        &self,
+        columns: &MatrixGroup<Fp, F>,
+        step: usize,
+        offset: usize,
+        results: &mut [&mut [F]],
+    ) where
+        F: GpuMulAssign<Fp>,
+    {
+        assert_eq!(results.len(), self.num_constraints);
+        let num_rows = results.first().map_or(0, |result| result.len());
+        let load_columns = self
+            .loads
+            .iter()
+            .map(|&(column, _)| columns.get_column(column))
+            .collect::<Vec<Col<Fp, F>>>();
+        let power_columns = self
+            .power_loads
+            .iter()
+            .map(|&load| &load_columns[load])
+            .collect::<Vec<&Col<Fp, F>>>();
+
+        // only one of the two powers is used depending on the column's field
+        let mut fp_powers = vec![[Fp::zero(); BATCH_LEN]; self.power_loads.len()];
+        let mut fq_powers = vec![[F::zero(); BATCH_LEN]; self.power_loads.len()];
+        let mut partials = vec![[F::one(); BATCH_LEN]; self.max_depth + 1];
+        for batch_start in (0..num_rows).step_by(BATCH_LEN) {
+            // rows past the end of the last batch hold stale values that are
+            // computed on but never written out
+            let batch_len = BATCH_LEN.min(num_rows - batch_start);
+            let row = offset + batch_start;
+            let mut acc = [F::zero(); BATCH_LEN];
+            for &op in &self.ops {
+                match op {
+                    Op::Pow {
+                        dst,
+                        base,
+                        load,
+                        exponent,
+                    } => {
+                        let start = row + self.loads[load].1 * step;
+                        let exponent = [exponent as u64];
+                        match load_columns[load] {
+                            Col::Fp(column) => {
+                                let values = gather::<Fp>(column, start, batch_len, exponent);
+                                fp_powers[dst] = match base {
+                                    Some(base) => mul_batch(fp_powers[base], &values),
+                                    None => values,
+                                };
+                            }
+                            Col::Fq(column) => {
+                                let values = gather::<F>(column, start, batch_len, exponent);
+                                fq_powers[dst] = match base {
+                                    Some(base) => mul_batch(fq_powers[base], &values),
+                                    None => values,
+                                };
+                            }
+                        }
+                    }
+                    Op::Mul { depth, power } => {
+                        partials[depth + 1] = match power_columns[power] {
+                            Col::Fp(_) => mul_batch(partials[depth], &fp_powers[power]),
+                            Col::Fq(_) => mul_batch(partials[depth], &fq_powers[power]),
+                        };
+                    }
+                    Op::Accumulate { depth, coeff } => {
+                        let coeff = self.coeffs[coeff];
+                        for (acc, product) in acc.iter_mut().zip(&partials[depth]) {
+                            *acc += *product * coeff;
+                        }
+                    }
+                    Op::Store { constraint } => {
+                        let rows = batch_start..batch_start + batch_len;
+                        results[constraint][rows].copy_from_slice(&acc[..batch_len]);
+                        acc = [F::zero(); BATCH_LEN];
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Number of rows [ConstraintKernel::evaluate_rows] evaluates at once
+const BATCH_LEN: usize = 8;
+
+/// Reads `column[start + i]^exponent` for the first `batch_len` rows of a
+/// batch, wrapping around at the end of the column
+fn gather<F: GpuField>(
+    column: &[F],
+    start: usize,
+    batch_len: usize,
+    exponent: [u64; 1],
+) -> [F; BATCH_LEN] {
+    let n = column.len();
+    let mut values = [F::one(); BATCH_LEN];
+    for (i, value) in values.iter_mut().enumerate().take(batch_len) {
+        *value = column[(start + i) % n].pow(exponent);
+    }
+    values
+}
+
+/// Row-wise `lhs[i] * rhs[i]`
+fn mul_batch<Lhs: GpuMulAssign<Rhs> + Copy, Rhs: Copy>(
+    mut lhs: [Lhs; BATCH_LEN],
+    rhs: &[Rhs; BATCH_LEN],
+) -> [Lhs; BATCH_LEN] {
+    for (lhs, rhs) in lhs.iter_mut().zip(rhs) {
+        *lhs *= *rhs;
+    }
+    lhs
+}
+
+#[cfg(feature = "gpu")]
+impl<F: GpuField> ConstraintKernel<F> {
+    /// Name of the kernel in [ConstraintKernel::metal_source]
+    const METAL_KERNEL_NAME: &str = "evaluate_constraints";
+
+    /// Distinct columns the constraints depend on. Column `columns[i]` is
+    /// bound to buffer `i` of the Metal kernel.
+    fn columns(&self) -> Vec<usize> {
+        let mut columns = self
+            .loads
+            .iter()
+            .map(|&(column, _)| column)
+            .collect::<Vec<usize>>();
+        columns.sort_unstable();
+        columns.dedup();
+        columns
+    }
+
+    /// Coefficients in the order the Metal kernel reads them. The first two
+    /// entries are one and zero since the generic Metal source has no way to
+    /// construct field elements.
+    fn metal_coeffs(&self) -> Vec<F> {
+        [&[F::one(), F::zero()], self.coeffs.as_slice()].concat()
+    }
+
+    /// Generates the Metal source of a kernel evaluating the constraints on
+    /// one row per thread. `column_types[i]` is the Metal type of column
+    /// `columns()[i]` and `fq` is the Metal type of the evaluations.
+    fn metal_source(&self, column_types: &[String], fq: &str) -> String {
+        let columns = self.columns();
+        let column_buffer = |column: usize| columns.binary_search(&column).unwrap();
+
+        let mut src = String::new();
+        writeln!(src, "constant unsigned N [[ function_constant(0) ]];").unwrap();
+        writeln!(src, "constant unsigned STEP [[ function_constant(1) ]];").unwrap();
+        writeln!(src, "kernel void {}(", Self::METAL_KERNEL_NAME).unwrap();
+        for (i, column_type) in column_types.iter().enumerate() {
+            writeln!(
+                src,
+                "        constant {column_type} *col{i} [[ buffer({i}) ]],"
+            )
+            .unwrap();
+        }
+        let mut buffer = columns.len();
+        for i in 0..self.num_constraints {
+            writeln!(src, "        device {fq} *res{i} [[ buffer({buffer}) ]],").unwrap();
+            buffer += 1;
+        }
+        writeln!(src, "        constant {fq} *coeffs [[ buffer({buffer}) ]],").unwrap();
+        writeln!(src, "        unsigned i [[ thread_position_in_grid ]]) {{").unwrap();
+
+        for (i, &(column, row_offset)) in self.loads.iter().enumerate() {
+            let buffer = column_buffer(column);
+            let load_type = &column_types[buffer];
+            writeln!(
+                src,
+                "    {load_type} l{i} = col{buffer}[(i + {row_offset} * STEP) % N];"
+            )
+            .unwrap();
+        }
+        // partial products of the current term. q0 is one.
+        writeln!(src, "    {fq} q0 = coeffs[0];").unwrap();
+        for depth in 1..=self.max_depth {
+            writeln!(src, "    {fq} q{depth};").unwrap();
+        }
+        writeln!(src, "    {fq} acc = coeffs[1];").unwrap();
+
+        for &op in &self.ops {
+            let line = match op {
+                Op::Pow {
+                    dst,
+                    base,
+                    load,
+                    exponent,
+                } => {
+                    let power_type = &column_types[column_buffer(self.loads[load].0)];
+                    let value = match exponent {
+                        1 => format!("l{load}"),
+                        exponent => format!("{power_type}(l{load}).pow({exponent})"),
+                    };
+                    let value = match base {
+                        Some(base) => format!("p{base} * {value}"),
+                        None => value,
+                    };
+                    format!("{power_type} p{dst} = {value};")
+                }
+                Op::Mul { depth, power } => format!("q{} = q{depth} * p{power};", depth + 1),
+                // the one and zero at the start of the buffer are skipped
+                Op::Accumulate { depth, coeff } => {
+                    format!("acc = acc + q{depth} * coeffs[{}];", coeff + 2)
+                }
+                Op::Store { constraint } => format!("res{constraint}[i] = acc; acc = coeffs[1];"),
+            };
+            writeln!(src, "    {line}").unwrap();
+        }
+        writeln!(src, "}}").unwrap();
+        src
+    }
+
+    /// Evaluates the constraints on every row with a kernel generated for
+    /// these constraints. Returns `false` without evaluating if the kernel
+    /// can't be generated e.g. if the fields have no Metal types, the kernel
+    /// needs more buffers than Metal can bind or the generated source fails
+    /// to compile. The caller then falls back to evaluating the constraints
+    /// symbolically.
+    pub fn evaluate_gpu<Fp: GpuField>(
+        &self,
+        columns: &MatrixGroup<Fp, F>,
+        step: usize,
+        results: &mut [GpuVec<F>],
+    ) -> bool
+    where
+        F: GpuMulAssign<Fp>,
+    {
+        let kernel_columns = self.columns();
+        let num_buffers = kernel_columns.len() + self.num_constraints + 1;
+        if num_buffers > GeneratedKernelStage::MAX_BUFFERS {
+            return false;
+        }
+        let (fp, fq) = match (Fp::metal_type(), F::metal_type()) {
+            (Some(fp), Some(fq)) => (fp, fq),
+            _ => return false,
+        };
+        let column_types = kernel_columns
+            .iter()
+            .map(|&column| match columns.get_column(column) {
+                Col::Fp(_) => fp.clone(),
+                Col::Fq(_) => fq.clone(),
+            })
+            .collect::<Vec<String>>();
+
+        let n = columns.num_rows();
+        let source = self.metal_source(&column_types, &fq);
+        let stage = match GeneratedKernelStage::new(
+            &PLANNER,
+            &source,
+            Self::METAL_KERNEL_NAME,
+            n,
+            &[step as u32],
+        ) {
+            Ok(stage) => stage,
+            Err(_) => return false,
+        };
+
+        let device = PLANNER.command_queue.device();
+        let coeffs = self.metal_coeffs().to_vec_in(PageAlignedAllocator);
+        let mut buffers = Vec::with_capacity(num_buffers);
+        for &column in &kernel_columns {
+            buffers.push(match columns.get_column(column) {
+                Col::Fp(column) => buffer_no_copy(device, column),
+                Col::Fq(column) => buffer_no_copy(device, column),
+            });
+        }
+        for result in results.iter_mut() {
+            buffers.push(buffer_mut_no_copy(device, result));
+        }
+        buffers.push(buffer_no_copy(device, &coeffs));
+
+        let command_buffer = PLANNER.command_queue.new_command_buffer();
+        stage.encode(command_buffer, &buffers);
+        command_buffer.commit();
+        command_buffer.wait_until_completed();
+        true
+    }
+}
@@ -0,0 +1,277 @@
+//! Dispatches between the supported low-degree tests
+use crate::evm::EvmEncode;
+use crate::evm::EvmEncoder;
+use crate::fri;
+use crate::fri::FriProof;
+use crate::fri::FriProver;
+use crate::fri::FriVerifier;
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "json")]
+use crate::json::JsonEncode;
+use crate::random::PublicCoin;
+use crate::stir;
+use crate::stir::StirProof;
+use crate::stir::StirProver;
+use crate::stir::StirVerifier;
+use crate::verifier::VerificationError;
+use crate::ProofOptions;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
+use digest::Digest;
+use gpu_poly::GpuField;
+use gpu_poly::GpuVec;
+use std::io;
+
+/// Protocol used to prove the DEEP composition polynomial is low-degree
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LowDegreeTest {
+    #[default]
+    Fri = 0,
+    Stir = 1,
+}
+
+impl CanonicalSerialize for LowDegreeTest {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        (*self as u8).serialize_with_mode(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        (*self as u8).serialized_size(compress)
+    }
+}
+
+impl Valid for LowDegreeTest {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+impl CanonicalDeserialize for LowDegreeTest {
+    fn deserialize_with_mode<R: Read>(
+        reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(reader, compress, validate)? {
+            0 => Ok(LowDegreeTest::Fri),
+            1 => Ok(LowDegreeTest::Stir),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum LowDegreeProof<F: GpuField> {
+    Fri(FriProof<F>),
+    Stir(StirProof<F>),
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, F: GpuField> arbitrary::Arbitrary<'a> for LowDegreeProof<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(match u.arbitrary()? {
+            LowDegreeTest::Fri => LowDegreeProof::Fri(u.arbitrary()?),
+            LowDegreeTest::Stir => LowDegreeProof::Stir(u.arbitrary()?),
+        })
+    }
+}
+
+impl<F: GpuField> LowDegreeProof<F> {
+    pub fn low_degree_test(&self) -> LowDegreeTest {
+        match self {
+            LowDegreeProof::Fri(_) => LowDegreeTest::Fri,
+            LowDegreeProof::Stir(_) => LowDegreeTest::Stir,
+        }
+    }
+}
+
+impl<F: GpuField> EvmEncode for LowDegreeProof<F> {
+    /// Encodes the low-degree test as a word followed by the proof
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_uint(self.low_degree_test() as u64);
+        match self {
+            LowDegreeProof::Fri(proof) => proof.encode_evm(encoder),
+            LowDegreeProof::Stir(proof) => proof.encode_evm(encoder),
+        }
+    }
+}
+
+/// Encodes the proof as an object with a single `fri` or `stir` field
+#[cfg(feature = "json")]
+impl<F: GpuField> JsonEncode for LowDegreeProof<F> {
+    fn encode_json(&self) -> serde_json::Value {
+        match self {
+            LowDegreeProof::Fri(proof) => serde_json::json!({ "fri": proof.encode_json() }),
+            LowDegreeProof::Stir(proof) => serde_json::json!({ "stir": proof.encode_json() }),
+        }
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        if let Ok(proof) = json::field(value, "fri") {
+            return Ok(LowDegreeProof::Fri(FriProof::decode_json(proof)?));
+        }
+        let proof = json::field(value, "stir")?;
+        Ok(LowDegreeProof::Stir(StirProof::decode_json(proof)?))
+    }
+}
+
+impl<F: GpuField> CanonicalSerialize for LowDegreeProof<F> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.low_degree_test()
+            .serialize_with_mode(&mut writer, compress)?;
+        match self {
+            LowDegreeProof::Fri(proof) => proof.serialize_with_mode(writer, compress),
+            LowDegreeProof::Stir(proof) => proof.serialize_with_mode(writer, compress),
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.low_degree_test().serialized_size(compress)
+            + match self {
+                LowDegreeProof::Fri(proof) => proof.serialized_size(compress),
+                LowDegreeProof::Stir(proof) => proof.serialized_size(compress),
+            }
+    }
+}
+
+impl<F: GpuField> Valid for LowDegreeProof<F> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            LowDegreeProof::Fri(proof) => proof.check(),
+            LowDegreeProof::Stir(proof) => proof.check(),
+        }
+    }
+}
+
+impl<F: GpuField> CanonicalDeserialize for LowDegreeProof<F> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(
+            match LowDegreeTest::deserialize_with_mode(&mut reader, compress, validate)? {
+                LowDegreeTest::Fri => LowDegreeProof::Fri(FriProof::deserialize_with_mode(
+                    reader, compress, validate,
+                )?),
+                LowDegreeTest::Stir => LowDegreeProof::Stir(StirProof::deserialize_with_mode(
+                    reader, compress, validate,
+                )?),
+            },
+        )
+    }
+}
+
+pub enum LowDegreeProver<F: GpuField, D: Digest> {
+    Fri(FriProver<F, D>),
+    Stir(StirProver<F, D>),
+}
+
+impl<F: GpuField, D: Digest> LowDegreeProver<F, D> {
+    /// `blowup_factor` is the ratio of the evaluation domain size to the
+    /// degree bound of the polynomial being tested
+    pub fn new(options: &ProofOptions, blowup_factor: usize) -> Self {
+        match options.low_degree_test {
+            LowDegreeTest::Fri => {
+                let options = options.into_fri_options().with_blowup_factor(blowup_factor);
+                LowDegreeProver::Fri(FriProver::new(options))
+            }
+            LowDegreeTest::Stir => {
+                let options = options
+                    .into_stir_options()
+                    .with_blowup_factor(blowup_factor);
+                LowDegreeProver::Stir(StirProver::new(options))
+            }
+        }
+    }
+
+    /// Spills committed FRI layers to `dir`. STIR keeps its rounds in memory.
+    #[cfg(feature = "spill")]
+    pub fn with_spill_dir(self, dir: std::path::PathBuf) -> Self {
+        match self {
+            LowDegreeProver::Fri(prover) => LowDegreeProver::Fri(prover.with_spill_dir(dir)),
+            stir => stir,
+        }
+    }
+
+    pub fn build(
+        &mut self,
+        channel: &mut (impl fri::ProverChannel<F, Digest = D> + stir::ProverChannel<F, Digest = D>),
+        evaluations: GpuVec<F>,
+    ) {
+        match self {
+            LowDegreeProver::Fri(prover) => prover.build_layers(channel, evaluations),
+            LowDegreeProver::Stir(prover) => prover.build_rounds(channel, evaluations),
+        }
+    }
+
+    pub fn into_proof(self, positions: &[usize]) -> io::Result<LowDegreeProof<F>> {
+        Ok(match self {
+            LowDegreeProver::Fri(prover) => LowDegreeProof::Fri(prover.into_proof(positions)?),
+            LowDegreeProver::Stir(prover) => LowDegreeProof::Stir(prover.into_proof(positions)),
+        })
+    }
+}
+
+pub enum LowDegreeVerifier<F: GpuField, D: Digest> {
+    Fri(FriVerifier<F, D>),
+    Stir(StirVerifier<F, D>),
+}
+
+impl<F: GpuField, D: Digest> LowDegreeVerifier<F, D> {
+    pub fn new(
+        public_coin: &mut PublicCoin<impl Digest>,
+        options: &ProofOptions,
+        proof: LowDegreeProof<F>,
+        max_poly_degree: usize,
+        blowup_factor: usize,
+    ) -> Result<Self, VerificationError> {
+        Ok(match (options.low_degree_test, proof) {
+            (LowDegreeTest::Fri, LowDegreeProof::Fri(proof)) => {
+                let options = options.into_fri_options().with_blowup_factor(blowup_factor);
+                LowDegreeVerifier::Fri(FriVerifier::new(
+                    public_coin,
+                    options,
+                    proof,
+                    max_poly_degree,
+                )?)
+            }
+            (LowDegreeTest::Stir, LowDegreeProof::Stir(proof)) => {
+                let options = options
+                    .into_stir_options()
+                    .with_blowup_factor(blowup_factor);
+                LowDegreeVerifier::Stir(StirVerifier::new(
+                    public_coin,
+                    options,
+                    proof,
+                    max_poly_degree,
+                )?)
+            }
+            _ => return Err(VerificationError::LowDegreeTestMismatch),
+        })
+    }
+
+    pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
+        match self {
+            LowDegreeVerifier::Fri(verifier) => Ok(verifier.verify(positions, evaluations)?),
+            LowDegreeVerifier::Stir(verifier) => Ok(verifier.verify(positions, evaluations)?),
+        }
+    }
+}
@@ -10,53 +10,125 @@
 
 #[macro_use]
 mod macros;
+pub mod aggregate;
 mod air;
+pub mod assertion;
+pub mod bus;
+pub mod cairo;
 pub mod challenges;
 mod channel;
 mod composer;
 pub mod constraint;
+pub mod continuation;
+mod describe;
+pub mod evm;
 pub mod fri;
+pub mod gadgets;
+pub mod grinding;
+pub mod hashes;
 pub mod hints;
+pub mod job;
+#[cfg(feature = "json")]
+pub mod json;
+mod kernel;
+pub mod ldt;
 pub mod matrix;
 pub mod merkle;
+pub mod padding;
 mod prover;
 mod random;
+pub mod schedule;
+#[cfg(feature = "spill")]
+pub mod spill;
+pub mod stir;
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+#[cfg(feature = "test-vectors")]
+pub mod test_vectors;
 mod trace;
+mod transcript;
 pub mod utils;
 mod verifier;
 
 pub use air::Air;
 use ark_ff::BigInteger;
+use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::PrimeField;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+pub use channel::ChannelDigest;
+pub use channel::DefaultProverChannel;
+pub use channel::ProverChannel;
+pub use composer::DeepCompositionCoeffs;
 pub use constraint::Column;
 pub use constraint::Constraint;
+pub use describe::ProofDescription;
+use digest::Digest;
+use fri::FoldingSchedule;
 use fri::FriOptions;
-use fri::FriProof;
 use gpu_poly::GpuFftField;
 use gpu_poly::GpuField;
 use gpu_poly::GpuMulAssign;
+use grinding::NonceEncoding;
+pub use hashes::HashFunction;
+use ldt::LowDegreeProof;
+pub use ldt::LowDegreeTest;
 pub use matrix::Matrix;
+pub use prover::CompositionTrace;
+pub use prover::ExecutionTrace;
+pub use prover::OodEvaluation;
 pub use prover::Prover;
+pub use prover::ProvingError;
+pub use random::PublicCoin;
+pub use random::TranscriptLabel;
+use sha2::Sha256;
 use std::ops::Add;
 use std::ops::Mul;
-use trace::Queries;
+use stir::StirOptions;
+use thiserror::Error;
+pub use trace::segment_column_names;
+pub use trace::ColumnDiff;
+pub use trace::Queries;
 pub use trace::Trace;
+pub use trace::TraceDiff;
+pub use trace::TraceError;
 pub use trace::TraceInfo;
+pub use transcript::Transcript;
+pub use transcript::TranscriptEntry;
+pub use verifier::VerificationError;
+pub use verifier::VerificationEvent;
+
+/// Returned when the AIR's field can't reach a preset's security level e.g.
+/// a 64-bit field without an extension field
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("field only supports {supported} bits of security but {requested} were requested")]
+pub struct InsufficientSecurityError {
+    pub requested: usize,
+    pub supported: usize,
+}
 
 // TODO: include ability to specify:
 // - base field
 // - extension field
-// - hashing function
 #[derive(Debug, Clone, Copy, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ProofOptions {
     pub num_queries: u8,
     pub lde_blowup_factor: u8,
     pub grinding_factor: u8,
     pub fri_folding_factor: u8,
     pub fri_max_remainder_size: u8,
+    pub low_degree_test: LowDegreeTest,
+    pub num_ood_points: u8,
+    pub merkle_cap_height: u8,
+    pub hash_function: HashFunction,
+    pub fri_folding_schedule: FoldingSchedule,
+    pub fri_remainder_coefficients: bool,
+    /// Hash used for the proof of work. The hash function is used if unset.
+    pub grinding_hash: Option<HashFunction>,
+    pub nonce_encoding: NonceEncoding,
 }
 
 impl ProofOptions {
@@ -65,6 +137,8 @@ impl ProofOptions {
     pub const MIN_BLOWUP_FACTOR: u8 = 2;
     pub const MAX_BLOWUP_FACTOR: u8 = 64;
     pub const MAX_GRINDING_FACTOR: u8 = 32;
+    pub const MAX_NUM_OOD_POINTS: u8 = 8;
+    pub const MAX_MERKLE_CAP_HEIGHT: u8 = 16;
 
     pub fn new(
         num_queries: u8,
@@ -85,15 +159,179 @@ impl ProofOptions {
             grinding_factor,
             fri_folding_factor,
             fri_max_remainder_size,
+            low_degree_test: LowDegreeTest::Fri,
+            num_ood_points: 1,
+            merkle_cap_height: 0,
+            hash_function: HashFunction::Sha256,
+            fri_folding_schedule: FoldingSchedule::default(),
+            fri_remainder_coefficients: false,
+            grinding_hash: None,
+            nonce_encoding: NonceEncoding::U64Le,
+        }
+    }
+
+    /// Options for quick iteration during development. Proofs generated with
+    /// these options are NOT secure.
+    pub fn fast_dev() -> Self {
+        ProofOptions::new(8, 4, 0, 8, 64)
+    }
+
+    /// Options targeting 96 bits of conjectured security.
+    /// Returns an error if the AIR's field is too small to reach this level.
+    pub fn secure_96<A: Air>() -> Result<Self, InsufficientSecurityError> {
+        ProofOptions::new(27, 8, 16, 8, 64).check_field_supports_security_level::<A>(96)
+    }
+
+    /// Options targeting 128 bits of conjectured security.
+    /// Returns an error if the AIR's field is too small to reach this level.
+    pub fn secure_128<A: Air>() -> Result<Self, InsufficientSecurityError> {
+        ProofOptions::new(29, 16, 16, 8, 64).check_field_supports_security_level::<A>(128)
+    }
+
+    /// Returns the conjectured security level of a proof for a trace of length
+    /// `trace_len` over the AIR's field. The level is capped by the collision
+    /// resistance of the hash function and of the grinding hash.
+    pub fn conjectured_security_level<A: Air>(&self, trace_len: usize) -> usize {
+        let prime_field_bits = <<A::Fq as Field>::BasePrimeField as PrimeField>::MODULUS.num_bits();
+        let mut fq_bits = prime_field_bits as usize * A::Fq::extension_degree() as usize;
+        if self.low_degree_test == LowDegreeTest::Stir {
+            // STIR's out-of-domain points and combination randomness are
+            // sampled against polynomials of `folding_factor` times the degree
+            // of a FRI layer which costs `log2(folding_factor)` bits
+            fq_bits -= self.fri_folding_factor.ilog2() as usize;
         }
+        let grinding_hash = self.grinding_hash.unwrap_or(self.hash_function);
+        let hash_collision_resistance_security = self
+            .hash_function
+            .collision_resistance()
+            .min(grinding_hash.collision_resistance());
+        utils::conjectured_security_level(
+            fq_bits,
+            hash_collision_resistance_security,
+            self.lde_blowup_factor.into(),
+            trace_len,
+            self.num_queries.into(),
+            self.grinding_factor.into(),
+        )
+    }
+
+    /// Returns the longest trace whose LDE domain fits in the largest
+    /// power-of-two subgroup of `F`
+    pub fn max_trace_len<F: FftField>(&self) -> usize {
+        let max_lde_log_size = F::TWO_ADICITY.min(usize::BITS - 1);
+        let max_lde_size = 1usize << max_lde_log_size;
+        max_lde_size / usize::from(self.lde_blowup_factor).max(1)
+    }
+
+    // Checks the security level holds for the largest trace the field allows
+    fn check_field_supports_security_level<A: Air>(
+        self,
+        requested: usize,
+    ) -> Result<Self, InsufficientSecurityError> {
+        let max_trace_len = self.max_trace_len::<A::Fp>();
+        let supported = self.conjectured_security_level::<A>(max_trace_len);
+        if supported < requested {
+            return Err(InsufficientSecurityError {
+                requested,
+                supported,
+            });
+        }
+        Ok(self)
+    }
+
+    /// Selects the protocol used to prove the DEEP composition polynomial
+    /// is low-degree. FRI is used by default.
+    pub fn with_low_degree_test(mut self, low_degree_test: LowDegreeTest) -> Self {
+        self.low_degree_test = low_degree_test;
+        self
+    }
+
+    /// Sets the number of out-of-domain points the constraints are checked at.
+    /// Each point is checked independently so the chance a cheating prover
+    /// passes the out-of-domain check shrinks exponentially with the number of
+    /// points. Useful when the extension field is small. One point is used by
+    /// default.
+    pub fn with_num_ood_points(mut self, num_ood_points: u8) -> Self {
+        assert!(num_ood_points >= 1);
+        assert!(num_ood_points <= Self::MAX_NUM_OOD_POINTS);
+        self.num_ood_points = num_ood_points;
+        self
+    }
+
+    /// Commits to the `2^k` nodes `k` levels below the root of each Merkle
+    /// tree rather than just the root. Every authentication path is `k` nodes
+    /// shorter at the cost of `2^k - 1` extra digests per commitment. Trees
+    /// with fewer levels commit to the level above their leaves. Applies to
+    /// the trace commitments and FRI layers. Roots are used by default.
+    pub fn with_merkle_cap_height(mut self, merkle_cap_height: u8) -> Self {
+        assert!(merkle_cap_height <= Self::MAX_MERKLE_CAP_HEIGHT);
+        self.merkle_cap_height = merkle_cap_height;
+        self
+    }
+
+    /// Selects the hash function used for commitments and the transcript.
+    /// SHA-256 is used by default.
+    pub fn with_hash_function(mut self, hash_function: HashFunction) -> Self {
+        self.hash_function = hash_function;
+        self
+    }
+
+    /// Folds the first FRI layers by the factors in `folding_schedule` e.g.
+    /// `[8, 4, 4, 2]` rather than by the FRI folding factor. Layers after the
+    /// schedule and the remainder use the FRI folding factor. Ignored by STIR.
+    pub fn with_fri_folding_schedule(mut self, folding_schedule: &[u8]) -> Self {
+        self.fri_folding_schedule = FoldingSchedule::new(folding_schedule);
+        self
+    }
+
+    /// Ends FRI once the degree bound of the folded polynomial is at most
+    /// the max remainder size and sends the remainder's coefficients. Proofs
+    /// skip the layers that fold the codeword down to the max remainder size
+    /// and the remainder commitment. Ignored by STIR.
+    pub fn with_fri_remainder_coefficients(mut self) -> Self {
+        self.fri_remainder_coefficients = true;
+        self
+    }
+
+    /// Selects the hash used for the proof of work. Useful when grinding
+    /// with the transcript hash is slow e.g. [HashFunction::Rescue]. The hash
+    /// function is used by default.
+    pub fn with_grinding_hash(mut self, grinding_hash: HashFunction) -> Self {
+        self.grinding_hash = Some(grinding_hash);
+        self
+    }
+
+    /// Selects the width and byte order of the proof-of-work nonce. A
+    /// little-endian `u64` is used by default.
+    pub fn with_nonce_encoding(mut self, nonce_encoding: NonceEncoding) -> Self {
+        self.nonce_encoding = nonce_encoding;
+        self
     }
 
     pub fn into_fri_options(self) -> FriOptions {
         // TODO: move fri params into struct
-        FriOptions::new(
+        let options = FriOptions::new(
+            self.lde_blowup_factor.into(),
+            self.fri_folding_factor.into(),
+            self.fri_max_remainder_size.into(),
+        )
+        .with_folding_schedule(self.fri_folding_schedule)
+        .with_merkle_cap_height(self.merkle_cap_height.into());
+        if self.fri_remainder_coefficients {
+            options.with_remainder_coefficients()
+        } else {
+            options
+        }
+    }
+
+    /// STIR reuses the FRI folding parameters. The maximum remainder size is
+    /// the degree bound of the final polynomial sent in the clear.
+    pub fn into_stir_options(self) -> StirOptions {
+        StirOptions::new(
             self.lde_blowup_factor.into(),
             self.fri_folding_factor.into(),
             self.fri_max_remainder_size.into(),
+            self.num_queries.into(),
         )
     }
 }
@@ -101,32 +339,144 @@ impl ProofOptions {
 /// A proof generated by a mini-stark prover
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct Proof<A: Air> {
+    pub header: ProofHeader,
     pub options: ProofOptions,
     pub trace_info: TraceInfo,
     pub base_trace_commitment: Vec<u8>,
-    pub extension_trace_commitment: Option<Vec<u8>>,
+    /// Commitment to the extension columns of each interaction round that
+    /// has extension columns
+    pub extension_trace_commitments: Vec<Vec<u8>>,
     pub composition_trace_commitment: Vec<u8>,
-    pub fri_proof: FriProof<A::Fq>,
+    pub low_degree_proof: LowDegreeProof<A::Fq>,
     pub pow_nonce: u64,
     pub trace_queries: Queries<A::Fp, A::Fq>,
-    pub public_inputs: A::PublicInputs,
-    pub ood_trace_states: (Vec<A::Fq>, Vec<A::Fq>),
-    pub ood_constraint_evaluations: Vec<A::Fq>,
+    /// Public inputs the proof was generated for. Can be removed with
+    /// [Proof::without_public_inputs] if the verifier already knows them.
+    pub public_inputs: Option<A::PublicInputs>,
+    /// Trace evaluations at `z * g^k` for each out-of-domain point `z` and
+    /// each row `k` of the evaluation frame
+    pub ood_trace_states: Vec<Vec<Vec<A::Fq>>>,
+    /// Composition trace evaluations at each out-of-domain point
+    pub ood_constraint_evaluations: Vec<Vec<A::Fq>>,
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, A: Air> arbitrary::Arbitrary<'a> for Proof<A>
+where
+    A::PublicInputs: arbitrary::Arbitrary<'a>,
+{
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        use utils::arbitrary_elements;
+        use utils::arbitrary_vec;
+        Ok(Proof {
+            header: u.arbitrary()?,
+            options: u.arbitrary()?,
+            trace_info: u.arbitrary()?,
+            base_trace_commitment: u.arbitrary()?,
+            extension_trace_commitments: u.arbitrary()?,
+            composition_trace_commitment: u.arbitrary()?,
+            low_degree_proof: u.arbitrary()?,
+            pow_nonce: u.arbitrary()?,
+            trace_queries: u.arbitrary()?,
+            public_inputs: u.arbitrary()?,
+            ood_trace_states: arbitrary_vec(u, |u| arbitrary_vec(u, arbitrary_elements))?,
+            ood_constraint_evaluations: arbitrary_vec(u, arbitrary_elements)?,
+        })
+    }
+}
+
+/// Proofs go through serde as the bytes of their compressed
+/// [CanonicalSerialize] encoding
+#[cfg(feature = "serde")]
+impl<A: Air> serde::Serialize for Proof<A> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut bytes = Vec::new();
+        self.serialize_compressed(&mut bytes)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, A: Air> serde::Deserialize<'de> for Proof<A> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = deserializer.deserialize_byte_buf(utils::BytesVisitor)?;
+        Proof::deserialize_compressed(&*bytes).map_err(serde::de::Error::custom)
+    }
 }
 
 impl<A: Air> Proof<A> {
     pub fn conjectured_security_level(&self) -> usize {
-        let prime_field_bits = <<A::Fq as Field>::BasePrimeField as PrimeField>::MODULUS.num_bits();
-        let fq_bits = prime_field_bits as usize * A::Fq::extension_degree() as usize;
-        let sha256_collision_resistance_security = 128;
-        utils::conjectured_security_level(
-            fq_bits,
-            sha256_collision_resistance_security,
-            self.options.lde_blowup_factor.into(),
-            self.trace_info.trace_len,
-            self.options.num_queries.into(),
-            self.options.grinding_factor.into(),
-        )
+        self.options
+            .conjectured_security_level::<A>(self.trace_info.trace_len)
+    }
+
+    /// Removes the public inputs from the proof. The proof can then only be
+    /// verified with [Proof::verify_with_public_inputs].
+    pub fn without_public_inputs(mut self) -> Self {
+        self.public_inputs = None;
+        self
+    }
+}
+
+/// The parts of a proof needed to route it before it's verified. Read with
+/// [Proof::peek_public_inputs].
+pub struct ProofSummary<A: Air> {
+    pub header: ProofHeader,
+    pub options: ProofOptions,
+    pub trace_info: TraceInfo,
+    /// `None` if the proof was serialized without its public inputs
+    pub public_inputs: Option<A::PublicInputs>,
+}
+
+/// Identifies what a proof was generated with. Serialized at the start of a
+/// proof so a verifier can reject proofs from another version of the crate or
+/// for another field, hash function or AIR before replaying the transcript.
+#[derive(Debug, Clone, Copy, CanonicalSerialize, CanonicalDeserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofHeader {
+    /// Major and minor version of the crate. Patch releases don't change the
+    /// proof format.
+    pub version: [u16; 2],
+    /// [ProofHeader::id] of the base prime field modulus and the extension
+    /// degrees of `Fp` and `Fq`
+    pub field_id: u64,
+    /// Must match the hash function in the proof options
+    pub hash_function: HashFunction,
+    /// [Air::air_id] of the AIR
+    pub air_id: u64,
+}
+
+impl ProofHeader {
+    /// Header of proofs for the AIR `A` generated by this version of the crate
+    pub fn new<A: Air>(hash_function: HashFunction) -> Self {
+        ProofHeader {
+            version: Self::crate_version(),
+            field_id: Self::field_id::<A::Fp, A::Fq>(),
+            hash_function,
+            air_id: A::air_id(),
+        }
+    }
+
+    pub fn crate_version() -> [u16; 2] {
+        [
+            env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap(),
+            env!("CARGO_PKG_VERSION_MINOR").parse().unwrap(),
+        ]
+    }
+
+    pub fn field_id<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>>() -> u64 {
+        let modulus = <<Fp as Field>::BasePrimeField as PrimeField>::MODULUS.to_bytes_le();
+        let fp_degree = Fp::extension_degree().to_le_bytes();
+        let fq_degree = Fq::extension_degree().to_le_bytes();
+        Self::id(&[modulus.as_slice(), &fp_degree, &fq_degree].concat())
+    }
+
+    /// Short identifier of `data` i.e. the first 8 bytes of its SHA-256 hash
+    pub fn id(data: &[u8]) -> u64 {
+        let hash = Sha256::digest(data);
+        u64::from_le_bytes(hash[..8].try_into().unwrap())
     }
 }
 
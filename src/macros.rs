@@ -21,3 +21,32 @@ macro_rules! forward_ref_binop {
         }
     };
 }
+
+// Evaluates `$body` with `$digest` bound to the digest type of a
+// `HashFunction`. Used by the prover and verifier to pick the hash at runtime.
+macro_rules! with_digest {
+    ($hash_function:expr, $digest:ident => $body:expr) => {
+        match $hash_function {
+            $crate::hashes::HashFunction::Sha256 => {
+                type $digest = sha2::Sha256;
+                $body
+            }
+            $crate::hashes::HashFunction::Blake2b => {
+                type $digest = $crate::hashes::Blake2b256;
+                $body
+            }
+            $crate::hashes::HashFunction::Blake2s => {
+                type $digest = blake2::Blake2s256;
+                $body
+            }
+            $crate::hashes::HashFunction::Rescue => {
+                type $digest = $crate::hashes::RescueDigest;
+                $body
+            }
+            $crate::hashes::HashFunction::Griffin => {
+                type $digest = $crate::hashes::GriffinDigest;
+                $body
+            }
+        }
+    };
+}
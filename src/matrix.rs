@@ -1,16 +1,21 @@
-use crate::constraint::Element;
 use crate::constraint::Term;
+use crate::kernel::ConstraintKernel;
+use crate::merkle::update_leaf;
 use crate::merkle::MerkleTree;
+#[cfg(feature = "gpu")]
+use crate::schedule::use_gpu_fft;
 use crate::utils::horner_evaluate;
+#[cfg(feature = "gpu")]
+use crate::utils::round_robin;
 use crate::Column;
 use crate::Constraint;
 use ark_ff::Field;
 use ark_ff::Zero;
 use ark_poly::domain::Radix2EvaluationDomain;
-#[cfg(not(feature = "gpu"))]
 use ark_poly::EvaluationDomain;
-use ark_serialize::CanonicalSerialize;
 use digest::Digest;
+#[cfg(feature = "gpu")]
+use gpu_poly::plan::Planner;
 use gpu_poly::prelude::*;
 use gpu_poly::GpuMulAssign;
 #[cfg(feature = "parallel")]
@@ -21,6 +26,9 @@ use std::ops::Deref;
 use std::ops::DerefMut;
 use std::ops::Index;
 use std::ops::IndexMut;
+use std::ops::Range;
+use std::sync::mpsc::sync_channel;
+use std::thread;
 
 /// Matrix is an array of columns.
 pub struct Matrix<F>(pub Vec<GpuVec<F>>);
@@ -46,6 +54,42 @@ impl<F: GpuField> Matrix<F> {
         Matrix::new(cols)
     }
 
+    /// Like [Matrix::from_rows] for rows of a fixed width. The matrix has `N`
+    /// columns even if there are no rows.
+    pub fn from_row_arrays<const N: usize>(rows: Vec<[F; N]>) -> Self {
+        let mut cols = (0..N)
+            .map(|_| Vec::with_capacity_in(rows.len(), PageAlignedAllocator))
+            .collect::<Vec<GpuVec<F>>>();
+        for row in rows {
+            for (col, value) in cols.iter_mut().zip(row) {
+                col.push(value)
+            }
+        }
+        Matrix::new(cols)
+    }
+
+    /// Builds a matrix from blocks of rows as they are generated. Each block
+    /// is moved into the columns and dropped before the next block is pulled
+    /// so only one block is held in row-major form at a time.
+    pub fn from_row_blocks(
+        num_cols: usize,
+        num_rows: usize,
+        blocks: impl IntoIterator<Item = Vec<Vec<F>>>,
+    ) -> Self {
+        let mut cols = (0..num_cols)
+            .map(|_| Vec::with_capacity_in(num_rows, PageAlignedAllocator))
+            .collect::<Vec<GpuVec<F>>>();
+        for block in blocks {
+            for row in block {
+                assert_eq!(row.len(), num_cols, "row has the wrong number of columns");
+                for (col, value) in cols.iter_mut().zip(row) {
+                    col.push(value)
+                }
+            }
+        }
+        Matrix::new(cols)
+    }
+
     // TODO: perhaps bring naming of rows and cols in line with
     // how the trace is names i.e. len and width.
     pub fn num_rows(&self) -> usize {
@@ -82,20 +126,29 @@ impl<F: GpuField> Matrix<F> {
         self.num_rows() == 0
     }
 
+    /// Splits the columns into a group for each GPU device
     #[cfg(feature = "gpu")]
-    fn into_polynomials_gpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self {
-        let mut ifft = GpuIfft::from(domain);
-
-        for column in &mut self.0 {
-            ifft.encode(column);
-        }
+    fn columns_per_device(&mut self) -> Vec<(&'static Planner, Vec<&mut GpuVec<F>>)> {
+        round_robin(&device_planners(), self.0.iter_mut())
+    }
 
-        ifft.execute();
+    #[cfg(feature = "gpu")]
+    fn into_polynomials_gpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self {
+        thread::scope(|s| {
+            for (planner, columns) in self.columns_per_device() {
+                s.spawn(move || {
+                    let mut ifft = planner.plan_ifft(domain);
+                    for column in columns {
+                        ifft.encode(column);
+                    }
+                    ifft.execute();
+                });
+            }
+        });
 
         self
     }
 
-    #[cfg(not(feature = "gpu"))]
     fn into_polynomials_cpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self {
         self.0.iter_mut().for_each(|col| domain.ifft_in_place(col));
         self
@@ -106,10 +159,11 @@ impl<F: GpuField> Matrix<F> {
         // TODO: using the newtype pattern for type safety would be cool
         // i.e. take as input Matrix<Evaluations> and return Matrix<Polynomials>
         // https://doc.rust-lang.org/book/ch19-04-advanced-types.html
-        #[cfg(not(feature = "gpu"))]
-        return self.into_polynomials_cpu(domain);
         #[cfg(feature = "gpu")]
-        return self.into_polynomials_gpu(domain);
+        if use_gpu_fft::<F>(domain.size()) {
+            return self.into_polynomials_gpu(domain);
+        }
+        self.into_polynomials_cpu(domain)
     }
 
     /// Interpolates the columns of the matrix over the domain
@@ -117,7 +171,6 @@ impl<F: GpuField> Matrix<F> {
         self.clone().into_polynomials(domain)
     }
 
-    #[cfg(not(feature = "gpu"))]
     fn into_evaluations_cpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self {
         for column in &mut self.0 {
             domain.fft_in_place(column);
@@ -127,13 +180,17 @@ impl<F: GpuField> Matrix<F> {
 
     #[cfg(feature = "gpu")]
     fn into_evaluations_gpu(mut self, domain: Radix2EvaluationDomain<F::FftField>) -> Self {
-        let mut fft = GpuFft::from(domain);
-
-        for column in &mut self.0 {
-            fft.encode(column);
-        }
-
-        fft.execute();
+        thread::scope(|s| {
+            for (planner, columns) in self.columns_per_device() {
+                s.spawn(move || {
+                    let mut fft = planner.plan_fft(domain);
+                    for column in columns {
+                        fft.encode(column);
+                    }
+                    fft.execute();
+                });
+            }
+        });
 
         self
     }
@@ -143,10 +200,11 @@ impl<F: GpuField> Matrix<F> {
         // TODO: using the newtype pattern for type safety would be cool
         // i.e. take as input Matrix<Polynomials> and return Matrix<Evaluations>
         // https://doc.rust-lang.org/book/ch19-04-advanced-types.html
-        #[cfg(not(feature = "gpu"))]
-        return self.into_evaluations_cpu(domain);
         #[cfg(feature = "gpu")]
-        return self.into_evaluations_gpu(domain);
+        if use_gpu_fft::<F>(domain.size()) {
+            return self.into_evaluations_gpu(domain);
+        }
+        self.into_evaluations_cpu(domain)
     }
 
     /// Evaluates the columns of the matrix
@@ -154,7 +212,6 @@ impl<F: GpuField> Matrix<F> {
         self.clone().into_evaluations(domain)
     }
 
-    #[cfg(not(feature = "gpu"))]
     pub fn sum_columns_cpu(&self) -> Matrix<F> {
         let n = self.num_rows();
         let mut accumulator = Vec::with_capacity_in(n, PageAlignedAllocator);
@@ -192,12 +249,11 @@ impl<F: GpuField> Matrix<F> {
 
         if !self.num_cols().is_zero() {
             // TODO: could improve
-            let library = &PLANNER.library;
             let command_queue = &PLANNER.command_queue;
             let device = command_queue.device();
             let command_buffer = command_queue.new_command_buffer();
             let mut accumulator_buffer = buffer_mut_no_copy(device, &mut accumulator);
-            let adder = AddAssignStage::<F>::new(library, n);
+            let adder = AddAssignStage::<F>::new(&PLANNER, n);
             for column in &self.0 {
                 let column_buffer = buffer_no_copy(command_queue.device(), column);
                 adder.encode(command_buffer, &mut accumulator_buffer, &column_buffer);
@@ -211,10 +267,53 @@ impl<F: GpuField> Matrix<F> {
 
     /// Sums columns into a single column matrix
     pub fn sum_columns(&self) -> Matrix<F> {
-        #[cfg(not(feature = "gpu"))]
-        return self.sum_columns_cpu();
         #[cfg(feature = "gpu")]
-        return self.sum_columns_gpu();
+        if F::HAS_GPU_KERNELS {
+            return self.sum_columns_gpu();
+        }
+        self.sum_columns_cpu()
+    }
+
+    /// Evaluates the columns over `domain` and commits to the rows of the
+    /// evaluations. Columns are evaluated one at a time on a separate thread so
+    /// hashing an evaluated column overlaps evaluating the next one. The
+    /// commitment is the same as [Matrix::evaluate] followed by
+    /// [Matrix::commit_to_rows].
+    pub fn evaluate_and_commit<D: Digest + Send>(
+        &self,
+        domain: Radix2EvaluationDomain<F::FftField>,
+    ) -> (Self, MerkleTree<D>) {
+        let mut row_hashers = (0..domain.size as usize)
+            .map(|_| D::new())
+            .collect::<Vec<D>>();
+
+        // only one evaluated column waits to be hashed at a time
+        let (sender, receiver) = sync_channel::<GpuVec<F>>(1);
+        let columns = thread::scope(|s| {
+            s.spawn(move || {
+                for column in &self.0 {
+                    let column = column.to_vec_in(PageAlignedAllocator);
+                    let mut evaluations = Matrix::new(vec![column]).into_evaluations(domain);
+                    if sender.send(evaluations.0.pop().unwrap()).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            receiver
+                .into_iter()
+                .map(|column| {
+                    ark_std::cfg_iter_mut!(row_hashers)
+                        .zip(ark_std::cfg_iter!(column))
+                        .for_each(|(hasher, value)| update_leaf(hasher, value));
+                    column
+                })
+                .collect::<Vec<GpuVec<F>>>()
+        });
+
+        let row_hashes = row_hashers.into_iter().map(D::finalize).collect();
+        let tree = MerkleTree::new(row_hashes).expect("failed to construct Merkle tree");
+        (Matrix::new(columns), tree)
     }
 
     pub fn commit_to_rows<D: Digest>(&self) -> MerkleTree<D> {
@@ -230,19 +329,17 @@ impl<F: GpuField> Matrix<F> {
             128,
         );
 
+        // rows are hashed straight from the columns (see [update_leaf])
         ark_std::cfg_chunks_mut!(row_hashes, chunk_size)
             .enumerate()
             .for_each(|(chunk_offset, chunk)| {
                 let offset = chunk_size * chunk_offset;
-
-                let mut row_buffer = vec![F::zero(); self.num_cols()];
-                let mut row_bytes = Vec::with_capacity(row_buffer.compressed_size());
-
                 for (i, row_hash) in chunk.iter_mut().enumerate() {
-                    row_bytes.clear();
-                    self.read_row(offset + i, &mut row_buffer);
-                    row_buffer.serialize_compressed(&mut row_bytes).unwrap();
-                    *row_hash = D::new_with_prefix(&row_bytes).finalize();
+                    let mut hasher = D::new();
+                    for column in &self.0 {
+                        update_leaf(&mut hasher, &column[offset + i]);
+                    }
+                    *row_hash = hasher.finalize();
                 }
             });
 
@@ -266,18 +363,55 @@ impl<F: GpuField> Matrix<F> {
         }
     }
 
-    fn read_row(&self, row_idx: usize, row: &mut [F]) {
-        for (column, value) in self.0.iter().zip(row.iter_mut()) {
-            *value = column[row_idx]
-        }
-    }
-
     pub fn rows(&self) -> Vec<Vec<F>> {
         (0..self.num_rows())
             .map(|row| self.get_row(row).unwrap())
             .collect()
     }
 
+    /// Iterates over the rows without copying them
+    pub fn iter_rows(&self) -> impl ExactSizeIterator<Item = Row<'_, F>> {
+        self.view().iter_rows()
+    }
+
+    /// View of the whole matrix
+    pub fn view(&self) -> MatrixView<'_, F> {
+        MatrixView {
+            columns: &self.0,
+            start: 0,
+            end: self.num_rows(),
+        }
+    }
+
+    /// View of the columns in `cols` limited to the rows in `rows`
+    pub fn slice(&self, cols: Range<usize>, rows: Range<usize>) -> MatrixView<'_, F> {
+        self.view().slice(cols, rows)
+    }
+
+    /// Returns the matrix with rows and columns swapped
+    pub fn transpose(&self) -> Matrix<F> {
+        let num_rows = self.num_rows();
+        let mut cols = (0..num_rows)
+            .map(|_| Vec::with_capacity_in(self.num_cols(), PageAlignedAllocator))
+            .collect::<Vec<GpuVec<F>>>();
+        for column in &self.0 {
+            for (col, value) in cols.iter_mut().zip(column.iter()) {
+                col.push(*value)
+            }
+        }
+        Matrix::new(cols)
+    }
+
+    /// Multiplies every value by `scalar`
+    pub fn mul_scalar(&mut self, scalar: F) {
+        ark_std::cfg_iter_mut!(self.0).for_each(|col| col.iter_mut().for_each(|v| *v *= scalar));
+    }
+
+    /// Adds `scalar` to every value
+    pub fn add_scalar(&mut self, scalar: F) {
+        ark_std::cfg_iter_mut!(self.0).for_each(|col| col.iter_mut().for_each(|v| *v += scalar));
+    }
+
     pub fn column_degrees(&self) -> Vec<usize> {
         self.0
             .iter()
@@ -344,7 +478,339 @@ impl<F: GpuField> TryInto<GpuVec<F>> for Matrix<F> {
     }
 }
 
-enum Col<'a, Fp, Fq> {
+/// Borrowed block of a [Matrix]. Rows and columns are indexed relative to
+/// the block. Nothing is copied until [MatrixView::to_matrix] is called.
+#[derive(Clone, Copy)]
+pub struct MatrixView<'a, F> {
+    columns: &'a [GpuVec<F>],
+    start: usize,
+    end: usize,
+}
+
+impl<'a, F: GpuField> MatrixView<'a, F> {
+    pub fn num_rows(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn column(&self, col: impl Column) -> &'a [F] {
+        &self.columns[col.index()][self.start..self.end]
+    }
+
+    /// Panics if `row` is out of bounds
+    pub fn row(&self, row: usize) -> Row<'a, F> {
+        assert!(row < self.num_rows(), "row {row} is out of bounds");
+        Row {
+            columns: self.columns,
+            row: self.start + row,
+        }
+    }
+
+    pub fn iter_rows(&self) -> impl ExactSizeIterator<Item = Row<'a, F>> {
+        let columns = self.columns;
+        (self.start..self.end).map(move |row| Row { columns, row })
+    }
+
+    /// View of the columns in `cols` limited to the rows in `rows`
+    pub fn slice(&self, cols: Range<usize>, rows: Range<usize>) -> MatrixView<'a, F> {
+        assert!(rows.start <= rows.end && rows.end <= self.num_rows());
+        MatrixView {
+            columns: &self.columns[cols],
+            start: self.start + rows.start,
+            end: self.start + rows.end,
+        }
+    }
+
+    /// Copies the view into a new matrix
+    pub fn to_matrix(&self) -> Matrix<F> {
+        Matrix::new(
+            (0..self.num_cols())
+                .map(|col| self.column(col).to_vec_in(PageAlignedAllocator))
+                .collect(),
+        )
+    }
+}
+
+/// Borrowed row of a [Matrix]
+#[derive(Clone, Copy)]
+pub struct Row<'a, F> {
+    columns: &'a [GpuVec<F>],
+    row: usize,
+}
+
+impl<'a, F: GpuField> Row<'a, F> {
+    pub fn len(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.columns.is_empty()
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a F> {
+        let row = self.row;
+        self.columns.iter().map(move |col| &col[row])
+    }
+
+    pub fn to_vec(&self) -> Vec<F> {
+        self.iter().copied().collect()
+    }
+}
+
+impl<'a, F: GpuField, C: Column> Index<C> for Row<'a, F> {
+    type Output = F;
+
+    fn index(&self, col: C) -> &F {
+        &self.columns[col.index()][self.row]
+    }
+}
+
+/// Column values in a compact representation. Execution traces often have
+/// columns that are mostly a single value (e.g. zero padding of input/output
+/// tables) which are cheaper to keep compact until a dense column is needed.
+#[derive(Debug)]
+pub enum CompactColumn<F> {
+    Dense(GpuVec<F>),
+    /// `len` copies of `value`
+    Constant {
+        value: F,
+        len: usize,
+    },
+    /// `default` on every row except the rows in `entries`. Entries are
+    /// `(row, value)` pairs sorted by row.
+    Sparse {
+        default: F,
+        len: usize,
+        entries: Vec<(usize, F)>,
+    },
+    /// Runs of equal values as `(end, value)` pairs where `end` is the
+    /// (exclusive) row the run ends on. Runs are sorted by `end`.
+    RunLength(Vec<(usize, F)>),
+}
+
+impl<F: GpuField> CompactColumn<F> {
+    pub fn constant(value: F, len: usize) -> Self {
+        CompactColumn::Constant { value, len }
+    }
+
+    /// Panics if the entries aren't sorted by row or are outside the column
+    pub fn sparse(default: F, len: usize, entries: Vec<(usize, F)>) -> Self {
+        assert!(
+            entries.array_windows().all(|[(a, _), (b, _)]| a < b),
+            "sparse entries must be sorted by row"
+        );
+        if let Some(&(row, _)) = entries.last() {
+            assert!(row < len, "sparse entry at row {row} is outside the column");
+        }
+        CompactColumn::Sparse {
+            default,
+            len,
+            entries,
+        }
+    }
+
+    /// Column of `(value, run_length)` runs in row order
+    pub fn run_length(runs: impl IntoIterator<Item = (F, usize)>) -> Self {
+        let mut end = 0;
+        let runs = runs
+            .into_iter()
+            .filter(|&(_, run_length)| run_length != 0)
+            .map(|(value, run_length)| {
+                end += run_length;
+                (end, value)
+            })
+            .collect();
+        CompactColumn::RunLength(runs)
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            CompactColumn::Dense(column) => column.len(),
+            CompactColumn::Constant { len, .. } | CompactColumn::Sparse { len, .. } => *len,
+            CompactColumn::RunLength(runs) => runs.last().map_or(0, |&(end, _)| end),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Value at `row` without densifying the column
+    pub fn get(&self, row: usize) -> Option<F> {
+        if row >= self.len() {
+            return None;
+        }
+        Some(match self {
+            CompactColumn::Dense(column) => column[row],
+            CompactColumn::Constant { value, .. } => *value,
+            CompactColumn::Sparse {
+                default, entries, ..
+            } => match entries.binary_search_by_key(&row, |&(row, _)| row) {
+                Ok(i) => entries[i].1,
+                Err(_) => *default,
+            },
+            CompactColumn::RunLength(runs) => runs[runs.partition_point(|&(end, _)| end <= row)].1,
+        })
+    }
+
+    pub fn is_dense(&self) -> bool {
+        matches!(self, CompactColumn::Dense(_))
+    }
+
+    /// Expands the column into all its values. Dense columns are returned
+    /// without copying.
+    pub fn into_dense(self) -> GpuVec<F> {
+        let mut column = Vec::with_capacity_in(self.len(), PageAlignedAllocator);
+        match self {
+            CompactColumn::Dense(dense) => return dense,
+            CompactColumn::Constant { value, len } => column.resize(len, value),
+            CompactColumn::Sparse {
+                default,
+                len,
+                entries,
+            } => {
+                column.resize(len, default);
+                for (row, value) in entries {
+                    column[row] = value;
+                }
+            }
+            CompactColumn::RunLength(runs) => {
+                for (end, value) in runs {
+                    column.resize(end, value);
+                }
+            }
+        }
+        column
+    }
+
+    /// Densifies the column in place and returns its values
+    pub fn densify(&mut self) -> &mut GpuVec<F> {
+        if !self.is_dense() {
+            let column = std::mem::replace(self, CompactColumn::RunLength(Vec::new()));
+            *self = CompactColumn::Dense(column.into_dense());
+        }
+        match self {
+            CompactColumn::Dense(column) => column,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<F: Clone> Clone for CompactColumn<F> {
+    fn clone(&self) -> Self {
+        match self {
+            CompactColumn::Dense(column) => {
+                CompactColumn::Dense(column.to_vec_in(PageAlignedAllocator))
+            }
+            CompactColumn::Constant { value, len } => CompactColumn::Constant {
+                value: value.clone(),
+                len: *len,
+            },
+            CompactColumn::Sparse {
+                default,
+                len,
+                entries,
+            } => CompactColumn::Sparse {
+                default: default.clone(),
+                len: *len,
+                entries: entries.clone(),
+            },
+            CompactColumn::RunLength(runs) => CompactColumn::RunLength(runs.clone()),
+        }
+    }
+}
+
+impl<F: GpuField> From<GpuVec<F>> for CompactColumn<F> {
+    fn from(column: GpuVec<F>) -> Self {
+        CompactColumn::Dense(column)
+    }
+}
+
+/// Matrix of columns that are kept compact until they are needed in dense
+/// form. Columns are densified individually with [Self::column] or all at
+/// once with [Self::into_dense].
+#[derive(Clone, Debug, Default)]
+pub struct CompactMatrix<F>(pub Vec<CompactColumn<F>>);
+
+impl<F: GpuField> CompactMatrix<F> {
+    pub fn new(cols: Vec<CompactColumn<F>>) -> Self {
+        CompactMatrix(cols)
+    }
+
+    pub fn num_rows(&self) -> usize {
+        if self.0.is_empty() {
+            return 0;
+        }
+        let expected_len = self.0[0].len();
+        for (i, col) in self.0.iter().enumerate() {
+            assert_eq!(expected_len, col.len(), "length of column {i} is invalid")
+        }
+        expected_len
+    }
+
+    pub fn num_cols(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_rows() == 0
+    }
+
+    pub fn get_row(&self, row: usize) -> Option<Vec<F>> {
+        self.0.iter().map(|col| col.get(row)).collect()
+    }
+
+    /// Densifies column `col` in place. Other columns stay compact.
+    pub fn column(&mut self, col: impl Column) -> &GpuVec<F> {
+        self.0[col.index()].densify()
+    }
+
+    /// Number of columns that haven't been densified
+    pub fn num_compact_cols(&self) -> usize {
+        self.0.iter().filter(|col| !col.is_dense()).count()
+    }
+
+    /// Interpolates the columns over the domain. Columns are densified one
+    /// at a time straight into the buffer of their polynomial so no dense
+    /// copy of the whole matrix is made.
+    pub fn interpolate(&self, domain: Radix2EvaluationDomain<F::FftField>) -> Matrix<F> {
+        // panics if the columns have different lengths
+        self.num_rows();
+        let mut polys = Matrix::new(Vec::new());
+        for column in &self.0 {
+            let column = Matrix::new(vec![column.clone().into_dense()]);
+            polys.append(column.into_polynomials(domain));
+        }
+        polys
+    }
+
+    pub fn into_dense(self) -> Matrix<F> {
+        // panics if the columns have different lengths
+        self.num_rows();
+        Matrix::new(
+            ark_std::cfg_into_iter!(self.0)
+                .map(CompactColumn::into_dense)
+                .collect(),
+        )
+    }
+}
+
+impl<F: GpuField> From<Matrix<F>> for CompactMatrix<F> {
+    fn from(matrix: Matrix<F>) -> Self {
+        CompactMatrix(matrix.0.into_iter().map(CompactColumn::Dense).collect())
+    }
+}
+
+impl<F: GpuField> From<CompactMatrix<F>> for Matrix<F> {
+    fn from(matrix: CompactMatrix<F>) -> Self {
+        matrix.into_dense()
+    }
+}
+
+pub(crate) enum Col<'a, Fp, Fq> {
     Fp(&'a GpuVec<Fp>),
     Fq(&'a GpuVec<Fq>),
 }
@@ -375,7 +841,7 @@ impl<'a, Fp: GpuField, Fq: GpuField> MatrixGroup<'a, Fp, Fq> {
         self.0.push(item)
     }
 
-    fn num_rows(&self) -> usize {
+    pub(crate) fn num_rows(&self) -> usize {
         if self.0.is_empty() {
             return 0;
         }
@@ -397,7 +863,7 @@ impl<'a, Fp: GpuField, Fq: GpuField> MatrixGroup<'a, Fp, Fq> {
         self.0.iter().map(|m| map!(m, num_cols)).sum()
     }
 
-    fn get_column(&self, index: usize) -> Col<'a, Fp, Fq> {
+    pub(crate) fn get_column(&self, index: usize) -> Col<'a, Fp, Fq> {
         let mut column_count = 0;
         for matrix in &self.0 {
             let num_cols = map!(matrix, num_cols);
@@ -427,17 +893,16 @@ where
         step: usize,
     ) {
         let n = self.num_rows();
-        let library = &PLANNER.library;
         let command_queue = &PLANNER.command_queue;
         let device = command_queue.device();
         let command_buffer = command_queue.new_command_buffer();
 
-        let mul_fp = MulPowStage::<Fp>::new(library, n);
-        let mul_fq = MulPowStage::<Fq>::new(library, n);
-        let mul_fq_by_fp = MulPowStage::<Fq, Fp>::new(library, n);
-        let fill_fq = FillBuffStage::<Fq>::new(library, n);
-        let fill_fp = FillBuffStage::<Fp>::new(library, n);
-        let add_fq = AddAssignStage::<Fq>::new(library, n);
+        let mul_fp = MulPowStage::<Fp>::new(&PLANNER, n);
+        let mul_fq = MulPowStage::<Fq>::new(&PLANNER, n);
+        let mul_fq_by_fp = MulPowStage::<Fq, Fp>::new(&PLANNER, n);
+        let fill_fq = FillBuffStage::<Fq>::new(&PLANNER, n);
+        let fill_fp = FillBuffStage::<Fp>::new(&PLANNER, n);
+        let add_fq = AddAssignStage::<Fq>::new(&PLANNER, n);
 
         let mut res_buffers = results
             .iter_mut()
@@ -465,17 +930,14 @@ where
                 fill_fp.encode(command_buffer, &mut scratch_fp_buffer, Fp::one());
                 fill_fq.encode(command_buffer, &mut scratch_fq_buffer, *coeff);
                 for (element, power) in &variables.0 {
-                    let (col_index, shift) = match element {
-                        Element::Curr(col_index) => (col_index, 0),
-                        Element::Next(col_index) => (col_index, step),
-                        _ => unreachable!(),
-                    };
-                    match self.get_column(*col_index) {
+                    let (col_index, row_offset) = element.column_offset().unwrap();
+                    let shift = row_offset * step;
+                    match self.get_column(col_index) {
                         Col::Fp(_) => {
                             mul_fp.encode(
                                 command_buffer,
                                 &mut scratch_fp_buffer,
-                                &col_buffers[*col_index],
+                                &col_buffers[col_index],
                                 *power,
                                 shift,
                             );
@@ -484,7 +946,7 @@ where
                             mul_fq.encode(
                                 command_buffer,
                                 &mut scratch_fq_buffer,
-                                &col_buffers[*col_index],
+                                &col_buffers[col_index],
                                 *power,
                                 shift,
                             );
@@ -506,11 +968,10 @@ where
         command_buffer.wait_until_completed();
     }
 
-    #[cfg(not(feature = "gpu"))]
     fn evaluate_symbolic_cpu(
         &self,
         results: &mut [GpuVec<Fq>],
-        constraints: &[Constraint<Fq>],
+        kernel: &ConstraintKernel<Fq>,
         step: usize,
     ) {
         let n = self.num_rows();
@@ -519,60 +980,40 @@ where
         #[cfg(feature = "parallel")]
         let chunk_size = std::cmp::max(n / rayon::current_num_threads().next_power_of_two(), 1024);
 
-        for (result, constraint) in results.iter_mut().zip(constraints) {
-            ark_std::cfg_chunks_mut!(result, chunk_size)
-                .enumerate()
-                .for_each(|(chunk_offset, chunk)| {
-                    let offset = chunk_offset * chunk_size;
-
-                    let mut scratch_fp = Vec::with_capacity(chunk.len());
-                    scratch_fp.resize(chunk.len(), Fp::zero());
-
-                    let mut scratch_fq = Vec::with_capacity(chunk.len());
-                    scratch_fq.resize(chunk.len(), Fq::zero());
-
-                    for (i, Term(coeff, variables)) in constraint.0.iter().enumerate() {
-                        scratch_fp.fill(Fp::one());
-                        scratch_fq.fill(*coeff);
-                        for (element, power) in &variables.0 {
-                            let (col_index, shift) = match element {
-                                Element::Curr(col_index) => (col_index, 0),
-                                Element::Next(col_index) => (col_index, step),
-                                _ => unreachable!(),
-                            };
-
-                            // TODO: map like macro could help here
-                            match self.get_column(*col_index) {
-                                Col::Fp(col) => {
-                                    for (i, scratch) in scratch_fp.iter_mut().enumerate() {
-                                        *scratch *=
-                                            col[(offset + shift + i) % n].pow([*power as u64])
-                                    }
-                                }
-                                Col::Fq(col) => {
-                                    for (i, scratch) in scratch_fq.iter_mut().enumerate() {
-                                        *scratch *=
-                                            col[(offset + shift + i) % n].pow([*power as u64])
-                                    }
-                                }
-                            }
-                        }
-                        scratch_fq
-                            .iter_mut()
-                            .zip(&scratch_fp)
-                            .for_each(|(s_fq, s_fp)| *s_fq *= *s_fp);
-                        if i == 0 {
-                            for (result, scratch) in chunk.iter_mut().zip(&scratch_fq) {
-                                *result = *scratch;
-                            }
-                        } else {
-                            for (result, scratch) in chunk.iter_mut().zip(&scratch_fq) {
-                                *result += scratch;
-                            }
-                        }
-                    }
-                });
+        // chunks[i][j] holds rows of chunk i of constraint j
+        let num_chunks = (n + chunk_size - 1) / chunk_size;
+        let mut chunks = (0..num_chunks)
+            .map(|_| Vec::with_capacity(results.len()))
+            .collect::<Vec<Vec<&mut [Fq]>>>();
+        for result in results.iter_mut() {
+            for (chunk, rows) in chunks.iter_mut().zip(result.chunks_mut(chunk_size)) {
+                chunk.push(rows);
+            }
         }
+        ark_std::cfg_iter_mut!(chunks)
+            .enumerate()
+            .for_each(|(i, chunk)| kernel.evaluate_rows(self, step, i * chunk_size, chunk));
+    }
+
+    /// Evaluates constraints on a range of rows. Unlike
+    /// [MatrixGroup::evaluate_symbolic] only `rows.len()` evaluations of each
+    /// constraint are held in memory. Always runs on the CPU.
+    pub fn evaluate_symbolic_rows(
+        &self,
+        constraints: &[Constraint<Fq>],
+        challenges: &[Fq],
+        hints: &[Fq],
+        step: usize,
+        rows: Range<usize>,
+    ) -> Vec<Vec<Fq>> {
+        let kernel = ConstraintKernel::new(constraints, challenges, hints);
+        let mut results = vec![vec![Fq::zero(); rows.len()]; constraints.len()];
+        let mut result_rows = results
+            .iter_mut()
+            .map(|result| result.as_mut_slice())
+            .collect::<Vec<&mut [Fq]>>();
+        kernel.evaluate_rows(self, step, rows.start, &mut result_rows);
+        results
     }
 
     // TODO: step is related to constraints. Needs refactor
@@ -584,11 +1025,7 @@ where
         step: usize,
     ) -> Matrix<Fq> {
         let n = self.num_rows();
-        let constraints_without_challenges: Vec<Constraint<Fq>> = constraints
-            .iter()
-            .map(|c| c.evaluate_constants(challenges, hints))
-            .collect();
-        if constraints_without_challenges.is_empty() {
+        if constraints.is_empty() {
             return Matrix::new(vec![]);
         }
 
@@ -603,10 +1040,19 @@ where
                 .collect(),
         );
 
+        let kernel = ConstraintKernel::new(constraints, challenges, hints);
         #[cfg(feature = "gpu")]
-        self.evaluate_symbolic_gpu(&mut results, &constraints_without_challenges, step);
-        #[cfg(not(feature = "gpu"))]
-        self.evaluate_symbolic_cpu(&mut results, &constraints_without_challenges, step);
+        if Fp::HAS_GPU_KERNELS && Fq::HAS_GPU_KERNELS {
+            if !kernel.evaluate_gpu(self, step, &mut results) {
+                let constraints_without_challenges = constraints
+                    .iter()
+                    .map(|c| c.evaluate_constants(challenges, hints))
+                    .collect::<Vec<Constraint<Fq>>>();
+                self.evaluate_symbolic_gpu(&mut results, &constraints_without_challenges, step);
+            }
+            return results;
+        }
+        self.evaluate_symbolic_cpu(&mut results, &kernel, step);
 
         results
     }
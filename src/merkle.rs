@@ -1,5 +1,14 @@
 //! Use arkwork_rs or re make this. Just used for personal education.
+use crate::evm::EvmEncode;
+use crate::evm::EvmEncoder;
+use crate::evm::WORD_SIZE;
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "json")]
+use crate::json::JsonEncode;
 use anyhow::Result;
+use ark_ff::Field;
+use ark_ff::PrimeField;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
 use digest::Digest;
@@ -17,9 +26,16 @@ pub enum MerkleTreeError {
     LeafIndexOutOfBounds(usize, usize),
     #[error("proof is invalid")]
     InvalidProof,
+    #[error("`{0}` openings were provided but at most `{1}` are accepted")]
+    TooManyOpenings(usize, usize),
+    #[error("path of `{0}` nodes exceeds the maximum depth of `{1}`")]
+    PathTooLong(usize, usize),
+    #[error("path of `{0}` nodes does not match the tree depth of `{1}`")]
+    PathLengthMismatch(usize, usize),
 }
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MerkleProof(Vec<u8>);
 
 impl MerkleProof {
@@ -27,14 +43,294 @@ impl MerkleProof {
         MerkleProof(merkle_path.into_iter().flatten().collect())
     }
 
-    pub fn parse<D: Digest>(&self) -> Vec<Output<D>> {
+    /// Returns the nodes of the proof. Errors if the proof isn't made up of a
+    /// whole number of non-zero digests.
+    pub fn parse<D: Digest>(&self) -> Result<Vec<Output<D>>, MerkleTreeError> {
         // TODO: would be great if this whole thing could be better.
         let chunk_size = <D as digest::OutputSizeUser>::output_size();
+        if self.0.is_empty() || self.0.len() % chunk_size != 0 {
+            return Err(MerkleTreeError::InvalidProof);
+        }
         let chunks = self.0.chunks(chunk_size);
-        chunks
+        Ok(chunks
             .map(|chunk| Output::<D>::from_slice(chunk).clone())
-            .collect()
+            .collect())
+    }
+
+    /// Returns true if the proof is a leaf hash followed by `depth` sibling
+    /// nodes. See [path_len] for the depth of a tree.
+    pub fn has_depth<D: Digest>(&self, depth: usize) -> bool {
+        self.0.len() == (depth + 1) * <D as digest::OutputSizeUser>::output_size()
+    }
+
+    /// Checks the proof opens the leaf with `values` at `position` of the tree
+    /// with root `root` and `depth` levels below the root. Unlike
+    /// [MerkleProof::parse] and [MerkleTree::verify] this never allocates.
+    /// Nodes are read from the proof bytes in place and the values are encoded
+    /// straight into the hasher (see [hash_leaf]).
+    pub fn verify_leaf<D: Digest, F: Field>(
+        &self,
+        root: &Output<D>,
+        values: &[F],
+        position: usize,
+        depth: usize,
+    ) -> Result<(), MerkleTreeError> {
+        self.verify_leaf_with_cap::<D, F>(std::slice::from_ref(root), values, position, depth)
+    }
+
+    /// Like [MerkleProof::verify_leaf] for a tree committed to with a cap (see
+    /// [MerkleTree::cap]). The proof's path ends at the cap rather than the
+    /// root so `depth` is the number of levels below the cap.
+    pub fn verify_leaf_with_cap<D: Digest, F: Field>(
+        &self,
+        cap: &[Output<D>],
+        values: &[F],
+        position: usize,
+        depth: usize,
+    ) -> Result<(), MerkleTreeError> {
+        let chunk_size = <D as digest::OutputSizeUser>::output_size();
+        if self.0.is_empty() || self.0.len() % chunk_size != 0 {
+            return Err(MerkleTreeError::InvalidProof);
+        }
+        let mut nodes = self.0.chunks(chunk_size).map(Output::<D>::from_slice);
+        let expected_leaf_hash = nodes.next().unwrap();
+
+        let leaf_hash = hash_leaf::<D, F>(values);
+        if leaf_hash != *expected_leaf_hash {
+            return Err(MerkleTreeError::InvalidProof);
+        }
+
+        verify_path::<D>(cap, leaf_hash, nodes, position, depth)
+    }
+
+    /// Checks `proofs` open the leaves with `values` at `positions` of the
+    /// tree committed to with `cap` and `depth` levels below the cap.
+    /// Equivalent to calling [MerkleProof::verify_leaf_with_cap] for each proof
+    /// but paths are hashed a level at a time so nodes shared by several paths
+    /// are hashed once. Positions must be sorted and distinct.
+    pub fn verify_batch_with_cap<D: Digest, F: Field, L: AsRef<[F]>>(
+        proofs: &[MerkleProof],
+        cap: &[Output<D>],
+        values: &[L],
+        positions: &[usize],
+        depth: usize,
+    ) -> Result<(), MerkleTreeError> {
+        debug_assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+        let chunk_size = <D as digest::OutputSizeUser>::output_size();
+        if proofs.len() != positions.len() || values.len() != positions.len() {
+            return Err(MerkleTreeError::InvalidProof);
+        }
+
+        // (proof index, node position, node) of each node at the current level
+        let mut level = Vec::with_capacity(positions.len());
+        for (i, (proof, &position)) in proofs.iter().zip(positions).enumerate() {
+            if !proof.has_depth::<D>(depth) {
+                let path_len = (proof.0.len() / chunk_size).saturating_sub(1);
+                return Err(MerkleTreeError::PathLengthMismatch(path_len, depth));
+            }
+            let leaf_hash = hash_leaf::<D, F>(values[i].as_ref());
+            if leaf_hash.as_slice() != &proof.0[..chunk_size] {
+                return Err(MerkleTreeError::InvalidProof);
+            }
+            level.push((i, position, leaf_hash));
+        }
+
+        for node_index in 1..=depth {
+            let mut parents = Vec::with_capacity(level.len());
+            let mut nodes = level.into_iter().peekable();
+            while let Some((i, position, node)) = nodes.next() {
+                // siblings that are both on a path are hashed together once
+                let sibling = match nodes.next_if(|(_, next, _)| *next == position ^ 1) {
+                    Some((_, _, sibling)) => sibling,
+                    None => {
+                        let start = node_index * chunk_size;
+                        let sibling = &proofs[i].0[start..start + chunk_size];
+                        Output::<D>::from_slice(sibling).clone()
+                    }
+                };
+                let mut hasher = D::new();
+                if position % 2 == 0 {
+                    hasher.update(node);
+                    hasher.update(sibling);
+                } else {
+                    hasher.update(sibling);
+                    hasher.update(node);
+                }
+                parents.push((i, position >> 1, hasher.finalize()));
+            }
+            level = parents;
+        }
+
+        for (_, position, node) in level {
+            if cap.get(position) != Some(&node) {
+                return Err(MerkleTreeError::InvalidProof);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EvmEncode for MerkleProof {
+    /// Encodes the sibling nodes as an array of `bytes32` words
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        assert_eq!(
+            self.0.len() % WORD_SIZE,
+            0,
+            "only 32-byte digests can be encoded"
+        );
+        let nodes = self.0.chunks(WORD_SIZE);
+        encoder.write_uint(nodes.len() as u64);
+        nodes.for_each(|node| encoder.write_hash(node));
+    }
+}
+
+#[cfg(feature = "json")]
+impl JsonEncode for MerkleProof {
+    /// Encodes the sibling nodes as a single hex string
+    fn encode_json(&self) -> serde_json::Value {
+        json::bytes_to_json(&self.0)
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        Ok(MerkleProof(json::bytes_from_json(value)?))
+    }
+}
+
+/// Hashes a leaf made up of `values`. See [update_leaf] for the encoding.
+pub fn hash_leaf<D: Digest, F: Field>(values: &[F]) -> Output<D> {
+    let mut hasher = D::new();
+    values
+        .iter()
+        .for_each(|value| update_leaf(&mut hasher, value));
+    hasher.finalize()
+}
+
+/// Feeds the fixed width leaf encoding of `value` to `hasher`. Each base prime
+/// field component of the value is written as its canonical (non-Montgomery)
+/// little-endian limbs. Every value of a field has the same width so a leaf is
+/// just its values back to back without a length prefix. This lets the prover
+/// hash rows straight out of column memory without serializing them first.
+pub fn update_leaf<D: Digest, F: Field>(hasher: &mut D, value: &F) {
+    for component in value.to_base_prime_field_elements() {
+        for limb in component.into_bigint().as_ref() {
+            hasher.update(limb.to_le_bytes());
+        }
+    }
+}
+
+/// Hashes `leaf_hash` up the tree with the sibling `nodes` and checks the
+/// result is the node of `cap` the path ends at. A path must have a node for
+/// each of the `depth` levels below the cap otherwise a shorter path could
+/// open an inner node as if it were a leaf.
+fn verify_path<'a, D: Digest>(
+    cap: &[Output<D>],
+    leaf_hash: Output<D>,
+    nodes: impl ExactSizeIterator<Item = &'a Output<D>>,
+    mut position: usize,
+    depth: usize,
+) -> Result<(), MerkleTreeError> {
+    if nodes.len() != depth {
+        return Err(MerkleTreeError::PathLengthMismatch(nodes.len(), depth));
+    }
+    let mut running_hash = leaf_hash;
+    for node in nodes {
+        let mut hasher = D::new();
+        if position % 2 == 0 {
+            hasher.update(running_hash);
+            hasher.update(node);
+        } else {
+            hasher.update(node);
+            hasher.update(running_hash);
+        }
+        running_hash = hasher.finalize();
+        position >>= 1;
+    }
+
+    if cap.get(position) == Some(&running_hash) {
+        Ok(())
+    } else {
+        Err(MerkleTreeError::InvalidProof)
+    }
+}
+
+/// Checks the leaves with `values` at `positions` open to `cap` of a tree with
+/// `depth` levels below the cap using only buffers provided by the caller.
+/// `paths` holds the `depth` sibling nodes of each opening back to back, leaf
+/// level first, and `scratch` holds the running hash of each opening. Levels
+/// are hashed one at a time so a node shared by several openings is hashed
+/// once. Positions must be sorted and distinct.
+///
+/// At most `MAX_OPENINGS` openings of at most `MAX_DEPTH` levels are accepted
+/// so the hashing done for an untrusted proof is bounded at compile time.
+/// Nothing is allocated which makes this suitable for verifiers on embedded
+/// targets.
+pub fn verify_leaves_bounded<
+    D: Digest,
+    F: Field,
+    L: AsRef<[F]>,
+    const MAX_OPENINGS: usize,
+    const MAX_DEPTH: usize,
+>(
+    cap: &[Output<D>],
+    values: &[L],
+    positions: &[usize],
+    paths: &[Output<D>],
+    depth: usize,
+    scratch: &mut [Output<D>; MAX_OPENINGS],
+) -> Result<(), MerkleTreeError> {
+    let num_openings = positions.len();
+    if num_openings > MAX_OPENINGS {
+        return Err(MerkleTreeError::TooManyOpenings(num_openings, MAX_OPENINGS));
+    }
+    if depth > MAX_DEPTH || depth >= usize::BITS as usize {
+        return Err(MerkleTreeError::PathTooLong(depth, MAX_DEPTH));
+    }
+    if values.len() != num_openings || !positions.windows(2).all(|pair| pair[0] < pair[1]) {
+        return Err(MerkleTreeError::InvalidProof);
+    }
+    if paths.len() != num_openings * depth {
+        let path_len = paths.len() / num_openings.max(1);
+        return Err(MerkleTreeError::PathLengthMismatch(path_len, depth));
+    }
+
+    let scratch = &mut scratch[..num_openings];
+    for (running_hash, leaf_values) in scratch.iter_mut().zip(values) {
+        *running_hash = hash_leaf::<D, F>(leaf_values.as_ref());
+    }
+
+    for level in 0..depth {
+        for i in 0..num_openings {
+            let position = positions[i] >> level;
+            if i > 0 && positions[i - 1] >> (level + 1) == position >> 1 {
+                // the previous opening already hashed the parent. If the nodes
+                // are siblings the previous opening's path must hold this node.
+                let is_sibling = positions[i - 1] >> level != position;
+                if is_sibling && paths[(i - 1) * depth + level] != scratch[i] {
+                    return Err(MerkleTreeError::InvalidProof);
+                }
+                scratch[i] = scratch[i - 1].clone();
+                continue;
+            }
+
+            let sibling = &paths[i * depth + level];
+            let mut hasher = D::new();
+            if position % 2 == 0 {
+                hasher.update(&scratch[i]);
+                hasher.update(sibling);
+            } else {
+                hasher.update(sibling);
+                hasher.update(&scratch[i]);
+            }
+            scratch[i] = hasher.finalize();
+        }
+    }
+
+    for (running_hash, &position) in scratch.iter().zip(positions) {
+        if cap.get(position >> depth) != Some(running_hash) {
+            return Err(MerkleTreeError::InvalidProof);
+        }
     }
+    Ok(())
 }
 
 /// Merkle tree implemented as a full power-of-two arity tree.
@@ -71,7 +367,25 @@ impl<D: Digest> MerkleTree<D> {
         &self.nodes[1]
     }
 
+    /// Nodes `cap_height` levels below the root. Committing to the `2^k` nodes
+    /// of a cap rather than the root shortens every proof by `k` nodes. The
+    /// height is capped at the level above the leaf nodes.
+    pub fn cap(&self, cap_height: usize) -> &[Output<D>] {
+        let cap_len = cap_len(self.leaf_nodes.len(), cap_height);
+        &self.nodes[cap_len..2 * cap_len]
+    }
+
     pub fn prove(&self, index: usize) -> Result<MerkleProof, MerkleTreeError> {
+        self.prove_with_cap(index, 0)
+    }
+
+    /// Proof of the leaf at `index` that ends at the tree's cap of height
+    /// `cap_height`
+    pub fn prove_with_cap(
+        &self,
+        index: usize,
+        cap_height: usize,
+    ) -> Result<MerkleProof, MerkleTreeError> {
         if index >= self.leaf_nodes.len() {
             return Err(MerkleTreeError::LeafIndexOutOfBounds(
                 self.leaf_nodes.len(),
@@ -86,8 +400,9 @@ impl<D: Digest> MerkleTree<D> {
             self.leaf_nodes[index ^ 1].clone(),
         ];
 
+        let cap_len = cap_len(self.leaf_nodes.len(), cap_height);
         let mut index = (index + self.nodes.len()) >> 1;
-        while index > 1 {
+        while index >= 2 * cap_len {
             path.push(self.nodes[index ^ 1].clone());
             index >>= 1;
         }
@@ -95,32 +410,50 @@ impl<D: Digest> MerkleTree<D> {
         Ok(MerkleProof::new::<D>(path))
     }
 
+    /// Checks `proof` opens `position` of the tree with root `root` and `depth`
+    /// levels below the root
     pub fn verify(
         root: &Output<D>,
         proof: &[Output<D>],
-        mut position: usize,
+        position: usize,
+        depth: usize,
     ) -> Result<(), MerkleTreeError> {
-        let mut proof_iter = proof.iter();
-        let mut running_hash = proof_iter.next().unwrap().clone();
-        for node in proof_iter {
-            let mut hasher = D::new();
-            if position % 2 == 0 {
-                hasher.update(running_hash);
-                hasher.update(node);
-            } else {
-                hasher.update(node);
-                hasher.update(running_hash);
-            }
-            running_hash = hasher.finalize();
-            position >>= 1;
-        }
+        let (leaf_hash, nodes) = proof.split_first().ok_or(MerkleTreeError::InvalidProof)?;
+        let cap = std::slice::from_ref(root);
+        verify_path::<D>(cap, leaf_hash.clone(), nodes.iter(), position, depth)
+    }
+}
 
-        if *root == running_hash {
-            Ok(())
-        } else {
-            Err(MerkleTreeError::InvalidProof)
-        }
+/// Number of nodes in the cap of height `cap_height` of a tree with
+/// `num_leaves` leaves
+pub fn cap_len(num_leaves: usize, cap_height: usize) -> usize {
+    let max_cap_height = (num_leaves.trailing_zeros() as usize).saturating_sub(1);
+    1 << cap_height.min(max_cap_height)
+}
+
+/// Number of sibling nodes in a proof of a leaf of a tree with `num_leaves`
+/// leaves that ends at the cap of height `cap_height`
+pub fn path_len(num_leaves: usize, cap_height: usize) -> usize {
+    let cap_len = cap_len(num_leaves, cap_height);
+    (num_leaves.trailing_zeros() - cap_len.trailing_zeros()) as usize
+}
+
+/// Concatenates the nodes of a cap. This is how caps appear in proofs.
+pub fn cap_bytes<D: Digest>(cap: &[Output<D>]) -> Vec<u8> {
+    cap.iter().flatten().copied().collect()
+}
+
+/// Splits the bytes of a cap into its nodes. Errors if the bytes aren't a
+/// whole number of digests.
+pub fn parse_cap<D: Digest>(bytes: &[u8]) -> Result<Vec<Output<D>>, MerkleTreeError> {
+    let chunk_size = <D as digest::OutputSizeUser>::output_size();
+    if bytes.is_empty() || bytes.len() % chunk_size != 0 {
+        return Err(MerkleTreeError::InvalidProof);
     }
+    Ok(bytes
+        .chunks(chunk_size)
+        .map(|chunk| Output::<D>::from_slice(chunk).clone())
+        .collect())
 }
 
 #[cfg(feature = "parallel")]
@@ -0,0 +1,121 @@
+//! Padding tables of an execution trace to a power of two length.
+//!
+//! Tables rarely have a power of two number of rows so they're padded before
+//! proving. Padding rows have to satisfy the table's constraints which
+//! usually means each column follows a simple rule: a cycle counter keeps
+//! counting, registers repeat their last value and a dummy flag marks the row
+//! as padding. A table declares these rules once with [PaddingRules] and all
+//! tables are padded to a common [padded_len].
+//!
+//! ```text
+//! cycle | ip | instr | dummy        cycle | ip | instr | dummy
+//!   0   |  0 |   +   |   0            0   |  0 |   +   |   0
+//!   1   |  1 |   .   |   0     =>     1   |  1 |   .   |   0
+//!   2   |  2 |   0   |   0            2   |  2 |   0   |   0
+//!                                     3   |  2 |   0   |   1
+//! ```
+use crate::utils::ceil_power_of_two;
+use crate::Matrix;
+use crate::TraceInfo;
+use ark_ff::Zero;
+use gpu_poly::GpuField;
+
+/// How a column is filled in padding rows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Padding<F> {
+    /// Zero in every padding row
+    Zero,
+    /// The same value in every padding row e.g. one for a dummy flag
+    Constant(F),
+    /// The value of the last row of the table
+    RepeatLast,
+    /// The value of the previous row plus `step` e.g. a cycle counter
+    Increment(F),
+}
+
+/// Padding rules for the columns of a table. Columns without a rule are
+/// filled with zeros. The last row of an empty table is taken to be all
+/// zeros.
+#[derive(Clone, Debug)]
+pub struct PaddingRules<F> {
+    rules: Vec<Padding<F>>,
+}
+
+impl<F: GpuField> PaddingRules<F> {
+    /// Rules that zero fill all `num_columns` columns
+    pub fn new(num_columns: usize) -> Self {
+        PaddingRules {
+            rules: vec![Padding::Zero; num_columns],
+        }
+    }
+
+    /// Rules that repeat the last row in all `num_columns` columns
+    pub fn repeat_last(num_columns: usize) -> Self {
+        PaddingRules {
+            rules: vec![Padding::RepeatLast; num_columns],
+        }
+    }
+
+    /// Sets the rule of the column at index `column` of the table
+    pub fn with(mut self, column: usize, padding: Padding<F>) -> Self {
+        self.rules[column] = padding;
+        self
+    }
+
+    pub fn num_columns(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Values of the column with rule `padding` in `n` padding rows
+    fn fill(padding: Padding<F>, last: F, n: usize) -> impl Iterator<Item = F> {
+        let mut value = last;
+        (0..n).map(move |_| {
+            value = match padding {
+                Padding::Zero => F::zero(),
+                Padding::Constant(constant) => constant,
+                Padding::RepeatLast => last,
+                Padding::Increment(step) => value + step,
+            };
+            value
+        })
+    }
+
+    /// Pads the columns of a table to `len` rows
+    pub fn pad(&self, table: &mut Matrix<F>, len: usize) {
+        assert_eq!(
+            table.num_cols(),
+            self.num_columns(),
+            "expected a rule for each column"
+        );
+        for (column, &padding) in table.0.iter_mut().zip(&self.rules) {
+            let last = column.last().copied().unwrap_or_else(F::zero);
+            let n = len.saturating_sub(column.len());
+            column.extend(Self::fill(padding, last, n));
+        }
+    }
+
+    /// Pads a table of rows to `len` rows
+    pub fn pad_rows<const N: usize>(&self, rows: &mut Vec<[F; N]>, len: usize) {
+        assert_eq!(N, self.num_columns(), "expected a rule for each column");
+        let last_row = rows.last().copied().unwrap_or([F::zero(); N]);
+        let n = len.saturating_sub(rows.len());
+        let mut padding_rows = vec![[F::zero(); N]; n];
+        for (i, &padding) in self.rules.iter().enumerate() {
+            let values = Self::fill(padding, last_row[i], n);
+            for (row, value) in padding_rows.iter_mut().zip(values) {
+                row[i] = value;
+            }
+        }
+        rows.extend(padding_rows);
+    }
+}
+
+/// Length tables of the given lengths are padded to. This is the smallest
+/// power of two that fits the longest table and is at least
+/// [TraceInfo::MIN_TRACE_LENGTH].
+pub fn padded_len(table_lens: impl IntoIterator<Item = usize>) -> usize {
+    let max_len = table_lens
+        .into_iter()
+        .fold(TraceInfo::MIN_TRACE_LENGTH, usize::max);
+    ceil_power_of_two(max_len)
+}
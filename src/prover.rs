@@ -1,24 +1,123 @@
+use crate::challenges::Challenges;
+use crate::channel::ChannelDigest;
+use crate::channel::DefaultProverChannel;
 use crate::channel::ProverChannel;
 use crate::composer::ConstraintComposer;
 use crate::composer::DeepPolyComposer;
-use crate::fri::FriProver;
+use crate::hints::Hints;
+use crate::job::check_aborted;
+use crate::job::enter_phase;
+use crate::job::with_abort_handle;
+use crate::job::AbortHandle;
+use crate::job::ProofFuture;
+use crate::job::ProvingPhase;
+use crate::ldt::LowDegreeProver;
 use crate::matrix::GroupItem;
 use crate::matrix::MatrixGroup;
+use crate::merkle::MerkleTree;
+#[cfg(feature = "spill")]
+use crate::spill::SpilledLdes;
 use crate::trace::Queries;
+use crate::trace::TraceError;
 use crate::Air;
+use crate::Matrix;
 use crate::Proof;
 use crate::ProofOptions;
 use crate::StarkExtensionOf;
 use crate::Trace;
 use ark_ff::Field;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use digest::Digest;
 use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
 use sha2::Sha256;
+#[cfg(feature = "spill")]
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
 
 /// Errors that can occur during the proving stage
-#[derive(Debug)]
+#[derive(Error, Debug)]
 pub enum ProvingError {
+    #[error("proof generation failed")]
     Fail,
-    // TODO
+    #[error("trace length {trace_len} exceeds the maximum of {max} for this field and blowup factor. Use a field with larger two-adicity or a smaller blowup factor")]
+    DomainTooLarge { trace_len: usize, max: usize },
+    #[error("failed to spill prover data to disk or read it back: {0}")]
+    Spill(#[from] std::io::Error),
+    #[error("invalid trace: {0}")]
+    InvalidTrace(#[from] TraceError),
+    #[error("{kind} constraint {constraint} is not satisfied at row {row}. Constraint references columns {columns}")]
+    UnsatisfiedConstraint {
+        kind: &'static str,
+        constraint: usize,
+        row: usize,
+        columns: String,
+    },
+    #[error("constraint evaluation blowup factor {ce_blowup_factor} exceeds the lde blowup factor {lde_blowup_factor}. Use a larger blowup factor or lower degree constraints")]
+    DegreeOverflow {
+        ce_blowup_factor: usize,
+        lde_blowup_factor: usize,
+    },
+    #[error("failed to construct an evaluation domain of size {0}")]
+    DomainConstruction(usize),
+    #[error("round {round} has {actual} extension columns but the AIR expects {expected}")]
+    RoundColumns {
+        round: usize,
+        expected: usize,
+        actual: usize,
+    },
+    #[error("gpu backend failed: {0}")]
+    Gpu(String),
+    #[error("proof generation was aborted")]
+    Aborted,
+    #[error("{0} out-of-domain points were requested but between 1 and {max} are supported", max = ProofOptions::MAX_NUM_OOD_POINTS)]
+    InvalidNumOodPoints(u8),
+    #[error("constraint {index} has evaluation degree {degree} which exceeds the composition degree {max}")]
+    ConstraintDegreeTooHigh {
+        index: usize,
+        degree: usize,
+        max: usize,
+    },
+}
+
+/// Serialized as the error message
+#[cfg(feature = "serde")]
+impl serde::Serialize for ProvingError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Execution trace polynomials, their low-degree extensions and commitments
+pub struct ExecutionTrace<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>, D: Digest = Sha256> {
+    pub base_trace_polys: Matrix<Fp>,
+    pub base_trace_lde: Matrix<Fp>,
+    pub base_trace_tree: MerkleTree<D>,
+    /// Extension columns of all interaction rounds side by side
+    pub extension_trace_polys: Option<Matrix<Fq>>,
+    pub extension_trace_lde: Option<Matrix<Fq>>,
+    /// One tree for each interaction round with extension columns
+    pub extension_trace_trees: Vec<MerkleTree<D>>,
+    pub challenges: Challenges<Fq>,
+    pub hints: Hints<Fq>,
+}
+
+/// Composition trace polynomials, their low-degree extension and commitment
+pub struct CompositionTrace<Fq: GpuField, D: Digest = Sha256> {
+    pub polys: Matrix<Fq>,
+    pub lde: Matrix<Fq>,
+    pub tree: MerkleTree<D>,
+}
+
+/// Evaluations sent to the verifier for an out-of-domain point `z`
+pub struct OodEvaluation<Fq> {
+    pub z: Fq,
+    /// Execution trace evaluated at `z * g^i` for each row `i` of the frame
+    pub frame: Vec<Vec<Fq>>,
+    /// Composition trace columns evaluated at `z^n`
+    pub composition_evals: Vec<Fq>,
 }
 
 pub trait Prover {
@@ -33,95 +132,419 @@ pub trait Prover {
 
     fn options(&self) -> ProofOptions;
 
+    /// Approximate number of bytes constraint evaluation may use on top of the
+    /// trace LDEs. Constraints are evaluated in chunks of rows that fit this
+    /// budget. Unbounded by default.
+    fn max_memory(&self) -> Option<usize> {
+        None
+    }
+
+    /// Directory for spilling the trace LDEs and FRI layers to compressed
+    /// files while the low-degree test runs. Everything stays in memory if
+    /// `None`.
+    #[cfg(feature = "spill")]
+    fn working_dir(&self) -> Option<PathBuf> {
+        None
+    }
+
+    /// Builds the auxiliary (extension) columns of an interaction round once
+    /// the verifier's challenges for the round have been drawn. Overriding
+    /// this lets a prover build columns from state the trace doesn't hold.
+    /// Delegates to [Trace::build_round_columns] by default.
+    fn build_aux_trace(
+        &self,
+        trace: &Self::Trace,
+        round: usize,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        trace.build_round_columns(round, challenges)
+    }
+
+    /// Thread pool used by [Prover::generate_proof_in_pool]. Rayon's global
+    /// pool is used if `None`.
+    #[cfg(feature = "parallel")]
+    fn thread_pool(&self) -> Option<&rayon::ThreadPool> {
+        None
+    }
+
+    /// Generates a proof with all parallel work running on the prover's
+    /// [Prover::thread_pool] so proving can share a machine with other work
+    #[cfg(feature = "parallel")]
+    fn generate_proof_in_pool(&self, trace: Self::Trace) -> Result<Proof<Self::Air>, ProvingError>
+    where
+        Self: Sync,
+        Self::Trace: Send,
+        <Self::Air as Air>::PublicInputs: Send,
+    {
+        match self.thread_pool() {
+            Some(pool) => pool.install(|| self.generate_proof(trace)),
+            None => self.generate_proof(trace),
+        }
+    }
+
+    /// Generates a proof on a background thread so async services can await
+    /// it without blocking a runtime worker. Proving runs on the prover's
+    /// [Prover::thread_pool] (or rayon's global pool) with the `parallel`
+    /// feature and on a new thread otherwise. The returned future can be
+    /// polled for the current [ProvingPhase] and wakes its task at the start
+    /// of each phase.
+    fn generate_proof_async(self: Arc<Self>, trace: Self::Trace) -> ProofFuture<Self::Air>
+    where
+        Self: Send + Sync + 'static,
+        Self::Trace: Send + 'static,
+        Self::Air: 'static,
+        Proof<Self::Air>: Send,
+    {
+        let (future, job) = ProofFuture::new();
+        let prover = Arc::clone(&self);
+        let prove = move || job.run(|| prover.generate_proof(trace));
+        #[cfg(feature = "parallel")]
+        match self.thread_pool() {
+            Some(pool) => pool.spawn(prove),
+            None => rayon::spawn(prove),
+        }
+        #[cfg(not(feature = "parallel"))]
+        std::thread::spawn(prove);
+        future
+    }
+
+    /// Generates a proof that stops with [ProvingError::Aborted] at the next
+    /// phase, interaction round or chunk of constraint evaluations after
+    /// `abort` is triggered from another thread. Intermediate state,
+    /// including GPU buffers and spilled files, is released on the way out.
+    fn generate_proof_abortable(
+        &self,
+        trace: Self::Trace,
+        abort: &AbortHandle,
+    ) -> Result<Proof<Self::Air>, ProvingError> {
+        with_abort_handle(abort.clone(), || {
+            check_aborted()?;
+            self.generate_proof(trace)
+        })
+    }
+
+    /// Generates a proof committing with the hash function of the prover's
+    /// options
     fn generate_proof(&self, trace: Self::Trace) -> Result<Proof<Self::Air>, ProvingError> {
+        let air = self.build_air(&trace)?;
+        with_digest!(air.options().hash_function, D => {
+            let channel = DefaultProverChannel::<Self::Air, D>::new(&air);
+            self.generate_proof_with_channel(&air, trace, channel)
+        })
+    }
+
+    /// Creates the AIR for a trace. Fails if the trace is malformed (see
+    /// [Trace::validate]), too long for the field and proof options or
+    /// doesn't have the extension columns the AIR expects.
+    fn build_air(&self, trace: &Self::Trace) -> Result<Self::Air, ProvingError> {
+        trace.validate()?;
         let options = self.options();
+        // options fields are public so `with_num_ood_points` may be bypassed
+        let num_ood_points = options.num_ood_points;
+        if num_ood_points == 0 || num_ood_points > ProofOptions::MAX_NUM_OOD_POINTS {
+            return Err(ProvingError::InvalidNumOodPoints(num_ood_points));
+        }
         let trace_info = trace.info();
-        let pub_inputs = self.get_pub_inputs(&trace);
+        let max_trace_len = options.max_trace_len::<Self::Fp>();
+        if trace_info.trace_len > max_trace_len {
+            return Err(ProvingError::DomainTooLarge {
+                trace_len: trace_info.trace_len,
+                max: max_trace_len,
+            });
+        }
+        let pub_inputs = self.get_pub_inputs(trace);
         let air = Self::Air::new(trace_info, pub_inputs, options);
-        air.validate();
-        let mut channel = ProverChannel::<Self::Air, Sha256>::new(&air);
+        let num_round_columns = (0..air.num_interaction_rounds())
+            .map(|round| air.num_round_extension_columns(round))
+            .sum::<usize>();
+        let num_extension_columns = air.trace_info().num_extension_columns;
+        if num_round_columns != num_extension_columns {
+            return Err(TraceError::NumExtensionColumns {
+                air: num_round_columns,
+                trace: num_extension_columns,
+            }
+            .into());
+        }
+        let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
+        if Radix2EvaluationDomain::<Self::Fp>::new(lde_domain_size).is_none() {
+            return Err(ProvingError::DomainConstruction(lde_domain_size));
+        }
+        // the GPU planner panics on first use if there's no device
+        #[cfg(target_arch = "aarch64")]
+        if Self::Fp::HAS_GPU_KERNELS && !gpu_poly::prelude::has_gpu_device() {
+            return Err(ProvingError::Gpu("no Metal device found".to_string()));
+        }
+        air.validate()?;
+        Ok(air)
+    }
+
+    /// Generates a proof with a custom transcript. Proofs only verify with
+    /// [Proof::verify] if the channel draws the same challenges as
+    /// [DefaultProverChannel] with the digest of the options' hash function.
+    fn generate_proof_with_channel(
+        &self,
+        air: &Self::Air,
+        trace: Self::Trace,
+        mut channel: impl ProverChannel<Self::Air>,
+    ) -> Result<Proof<Self::Air>, ProvingError> {
+        enter_phase(ProvingPhase::Trace)?;
+        #[cfg_attr(not(feature = "spill"), allow(unused_mut))]
+        let mut execution_trace = self.commit_trace(air, &mut channel, &trace)?;
+        drop(trace);
+        enter_phase(ProvingPhase::Composition)?;
+        #[cfg_attr(not(feature = "spill"), allow(unused_mut))]
+        let mut composition_trace =
+            self.commit_composition_trace(air, &mut channel, &execution_trace)?;
+        // the LDEs aren't needed again until the queries are built
+        #[cfg(feature = "spill")]
+        let spilled_ldes = match self.working_dir() {
+            Some(dir) => Some(SpilledLdes::spill(
+                &dir,
+                &mut execution_trace,
+                &mut composition_trace,
+            )?),
+            None => None,
+        };
+        enter_phase(ProvingPhase::OutOfDomain)?;
+        let ood_evaluations =
+            self.evaluate_out_of_domain(air, &mut channel, &execution_trace, &composition_trace);
+        enter_phase(ProvingPhase::DeepComposition)?;
+        let deep_composition_poly = self.build_deep_composition_poly(
+            air,
+            &mut channel,
+            &execution_trace,
+            &composition_trace,
+            ood_evaluations,
+        );
+        let deep_composition_lde = deep_composition_poly.into_evaluations(air.lde_domain());
+        enter_phase(ProvingPhase::LowDegree)?;
+        let ldt_prover = self.commit_low_degree(air, &mut channel, deep_composition_lde);
+        enter_phase(ProvingPhase::Grinding)?;
+        self.grind(&mut channel);
+        let query_positions = channel.get_fri_query_positions();
+        let low_degree_proof = ldt_prover.into_proof(&query_positions)?;
+        #[cfg(feature = "spill")]
+        if let Some(spilled_ldes) = spilled_ldes {
+            spilled_ldes.restore(&mut execution_trace, &mut composition_trace)?;
+        }
+        enter_phase(ProvingPhase::Queries)?;
+        let queries = self.build_queries(execution_trace, composition_trace, &query_positions);
+        Ok(channel.build_proof(queries, low_degree_proof))
+    }
 
+    /// Commits to the base columns then runs each round of interaction. A
+    /// round draws the round's challenges and commits to the extension
+    /// columns built from them.
+    fn commit_trace<C: ProverChannel<Self::Air>>(
+        &self,
+        air: &Self::Air,
+        channel: &mut C,
+        trace: &Self::Trace,
+    ) -> Result<ExecutionTrace<Self::Fp, Self::Fq, ChannelDigest<Self::Air, C>>, ProvingError> {
         let trace_xs = air.trace_domain();
         let lde_xs = air.lde_domain();
-        let base_trace = trace.base_columns();
-        let base_trace_polys = base_trace.interpolate(trace_xs);
+        let cap_height = air.options().merkle_cap_height.into();
+        let base_trace_polys = match trace.compact_base_columns() {
+            Some(base_trace) => base_trace.interpolate(trace_xs),
+            None => trace.base_columns().interpolate(trace_xs),
+        };
         assert_eq!(Self::Trace::NUM_BASE_COLUMNS, base_trace_polys.num_cols());
-        let base_trace_lde = base_trace_polys.evaluate(lde_xs);
-        let base_trace_lde_tree = base_trace_lde.commit_to_rows();
-        channel.commit_base_trace(base_trace_lde_tree.root());
-        let challenges = air.get_challenges(&mut channel.public_coin);
+        let (base_trace_lde, base_trace_tree) = base_trace_polys.evaluate_and_commit(lde_xs);
+        channel.commit_base_trace(base_trace_tree.cap(cap_height));
+
+        let mut challenges = Challenges::default();
+        let mut extension_rounds = Vec::new();
+        for round in 0..air.num_interaction_rounds() {
+            check_aborted()?;
+            challenges.append(channel.draw_round_challenges(round));
+            let columns = self.build_aux_trace(trace, round, &challenges);
+            let num_columns = columns.as_ref().map_or(0, |columns| columns.num_cols());
+            let expected = air.num_round_extension_columns(round);
+            if num_columns != expected {
+                return Err(ProvingError::RoundColumns {
+                    round,
+                    expected,
+                    actual: num_columns,
+                });
+            }
+            if let Some(columns) = columns {
+                let polys = columns.interpolate(trace_xs);
+                let (lde, tree) = polys.evaluate_and_commit(lde_xs);
+                channel.commit_extension_trace(tree.cap(cap_height));
+                extension_rounds.push((columns, polys, lde, tree));
+            }
+        }
         let hints = air.get_hints(&challenges);
 
-        let extension_trace = trace.build_extension_columns(&challenges);
+        // columns of all rounds are treated as a single extension trace from here
+        let mut extension_columns = Vec::new();
+        let mut extension_polys = Vec::new();
+        let mut extension_ldes = Vec::new();
+        let mut extension_trace_trees = Vec::new();
+        for (columns, polys, lde, tree) in extension_rounds {
+            extension_columns.push(columns);
+            extension_polys.push(polys);
+            extension_ldes.push(lde);
+            extension_trace_trees.push(tree);
+        }
+        let join = |matrices: Vec<Matrix<Self::Fq>>| {
+            (!matrices.is_empty()).then(|| Matrix::join(matrices))
+        };
+        let extension_trace = join(extension_columns);
         let num_extension_columns = extension_trace.as_ref().map_or(0, |t| t.num_cols());
         assert_eq!(Self::Trace::NUM_EXTENSION_COLUMNS, num_extension_columns);
-        let extension_trace_polys = extension_trace.as_ref().map(|t| t.interpolate(trace_xs));
-        let extension_trace_lde = extension_trace_polys.as_ref().map(|p| p.evaluate(lde_xs));
-        let extension_trace_tree = extension_trace_lde.as_ref().map(|lde| lde.commit_to_rows());
-        if let Some(t) = extension_trace_tree.as_ref() {
-            channel.commit_extension_trace(t.root())
-        }
 
         #[cfg(debug_assertions)]
-        air.validate_constraints(&challenges, &hints, base_trace, extension_trace.as_ref());
-        drop((base_trace, extension_trace));
-
-        let composition_coeffs = air.get_constraint_composition_coeffs(&mut channel.public_coin);
-        let constraint_coposer = ConstraintComposer::new(&air, composition_coeffs);
-        // TODO: move commitment here
-        let (composition_trace_lde, composition_trace_polys, composition_trace_lde_tree) =
-            constraint_coposer.build_commitment(
-                &challenges,
-                &hints,
-                &base_trace_lde,
-                extension_trace_lde.as_ref(),
-            );
-        channel.commit_composition_trace(composition_trace_lde_tree.root());
+        air.validate_constraints(
+            &challenges,
+            &hints,
+            trace.base_columns(),
+            extension_trace.as_ref(),
+        )?;
+
+        Ok(ExecutionTrace {
+            base_trace_polys,
+            base_trace_lde,
+            base_trace_tree,
+            extension_trace_polys: join(extension_polys),
+            extension_trace_lde: join(extension_ldes),
+            extension_trace_trees,
+            challenges,
+            hints,
+        })
+    }
+
+    /// Evaluates the constraints over the LDE domain and commits to the
+    /// composition trace
+    fn commit_composition_trace<C: ProverChannel<Self::Air>>(
+        &self,
+        air: &Self::Air,
+        channel: &mut C,
+        execution_trace: &ExecutionTrace<Self::Fp, Self::Fq, ChannelDigest<Self::Air, C>>,
+    ) -> Result<CompositionTrace<Self::Fq, ChannelDigest<Self::Air, C>>, ProvingError> {
+        let composition_coeffs = channel.draw_constraint_composition_coeffs();
+        let constraint_composer =
+            ConstraintComposer::new(air, composition_coeffs).with_max_memory(self.max_memory());
+        let (lde, polys, tree) = constraint_composer.build_commitment(
+            &execution_trace.challenges,
+            &execution_trace.hints,
+            &execution_trace.base_trace_lde,
+            execution_trace.extension_trace_lde.as_ref(),
+        )?;
+        channel.commit_composition_trace(tree.cap(air.options().merkle_cap_height.into()));
+        Ok(CompositionTrace { polys, lde, tree })
+    }
 
-        let g = &trace_xs.group_gen;
-        let z = channel.get_ood_point();
-        let mut execution_trace_polys = MatrixGroup::new(vec![GroupItem::Fp(&base_trace_polys)]);
-        if let Some(extension_trace_polys) = extension_trace_polys.as_ref() {
+    /// Evaluates the execution trace and composition trace at each
+    /// out-of-domain point and sends the evaluations to the verifier
+    fn evaluate_out_of_domain<D: Digest>(
+        &self,
+        air: &Self::Air,
+        channel: &mut impl ProverChannel<Self::Air>,
+        execution_trace: &ExecutionTrace<Self::Fp, Self::Fq, D>,
+        composition_trace: &CompositionTrace<Self::Fq, D>,
+    ) -> Vec<OodEvaluation<Self::Fq>> {
+        let g = &air.trace_domain().group_gen;
+        let mut execution_trace_polys =
+            MatrixGroup::new(vec![GroupItem::Fp(&execution_trace.base_trace_polys)]);
+        if let Some(extension_trace_polys) = execution_trace.extension_trace_polys.as_ref() {
             execution_trace_polys.append(GroupItem::Fq(extension_trace_polys))
         }
-        let ood_execution_trace_evals = execution_trace_polys.evaluate_at(z);
-        let ood_execution_trace_evals_next = execution_trace_polys.evaluate_at(z * g);
-        channel.send_ood_trace_states(&ood_execution_trace_evals, &ood_execution_trace_evals_next);
-        let z_n = z.pow([composition_trace_polys.num_cols() as u64]);
-        let ood_composition_trace_evals = composition_trace_polys.evaluate_at(z_n);
-        channel.send_ood_constraint_evaluations(&ood_composition_trace_evals);
-
-        let deep_coeffs = air.get_deep_composition_coeffs(&mut channel.public_coin);
-        let mut deep_poly_composer = DeepPolyComposer::new(&air, deep_coeffs, z);
-        deep_poly_composer.add_execution_trace_polys(
-            base_trace_polys,
-            extension_trace_polys,
-            ood_execution_trace_evals,
-            ood_execution_trace_evals_next,
-        );
-        deep_poly_composer
-            .add_composition_trace_polys(composition_trace_polys, ood_composition_trace_evals);
-        let deep_composition_poly = deep_poly_composer.into_deep_poly();
-        let deep_composition_lde = deep_composition_poly.into_evaluations(lde_xs);
+        let composition_trace_polys = &composition_trace.polys;
+        let frame_size = air.max_row_offset() + 1;
+        let mut ood_evaluations = Vec::new();
+        for _ in 0..air.options().num_ood_points {
+            let z = channel.get_ood_point();
+            let mut x = z;
+            let mut frame = Vec::new();
+            for _ in 0..frame_size {
+                frame.push(execution_trace_polys.evaluate_at(x));
+                x *= g;
+            }
+            channel.send_ood_trace_states(&frame);
+            let z_n = z.pow([composition_trace_polys.num_cols() as u64]);
+            let composition_evals = composition_trace_polys.evaluate_at(z_n);
+            channel.send_ood_constraint_evaluations(&composition_evals);
+            ood_evaluations.push(OodEvaluation {
+                z,
+                frame,
+                composition_evals,
+            });
+        }
+        ood_evaluations
+    }
 
-        let mut fri_prover = FriProver::<Self::Fq, Sha256>::new(air.options().into_fri_options());
-        fri_prover.build_layers(&mut channel, deep_composition_lde.try_into().unwrap());
+    /// Builds the DEEP composition polynomial. Sums the quotients of every
+    /// out-of-domain point.
+    fn build_deep_composition_poly<D: Digest>(
+        &self,
+        air: &Self::Air,
+        channel: &mut impl ProverChannel<Self::Air>,
+        execution_trace: &ExecutionTrace<Self::Fp, Self::Fq, D>,
+        composition_trace: &CompositionTrace<Self::Fq, D>,
+        ood_evaluations: Vec<OodEvaluation<Self::Fq>>,
+    ) -> Matrix<Self::Fq> {
+        let mut deep_composition_poly: Option<Matrix<Self::Fq>> = None;
+        for ood_evaluation in ood_evaluations {
+            let deep_coeffs = channel.draw_deep_composition_coeffs();
+            let mut deep_poly_composer = DeepPolyComposer::new(air, deep_coeffs, ood_evaluation.z);
+            deep_poly_composer.add_execution_trace_polys(
+                &execution_trace.base_trace_polys,
+                execution_trace.extension_trace_polys.as_ref(),
+                &ood_evaluation.frame,
+            );
+            deep_poly_composer.add_composition_trace_polys(
+                &composition_trace.polys,
+                &ood_evaluation.composition_evals,
+            );
+            let poly = deep_poly_composer.into_deep_poly();
+            if let Some(acc) = deep_composition_poly.as_mut() {
+                acc[0].iter_mut().zip(&poly[0]).for_each(|(a, b)| *a += b);
+            } else {
+                deep_composition_poly = Some(poly);
+            }
+        }
+        deep_composition_poly.unwrap()
+    }
 
-        channel.grind_fri_commitments();
+    /// Commits to the layers (FRI) or rounds (STIR) of the low-degree test of
+    /// the DEEP composition polynomial's evaluations
+    fn commit_low_degree<C: ProverChannel<Self::Air>>(
+        &self,
+        air: &Self::Air,
+        channel: &mut C,
+        deep_composition_lde: Matrix<Self::Fq>,
+    ) -> LowDegreeProver<Self::Fq, ChannelDigest<Self::Air, C>> {
+        let mut ldt_prover = LowDegreeProver::new(air.options(), air.ldt_blowup_factor());
+        #[cfg(feature = "spill")]
+        if let Some(dir) = self.working_dir() {
+            ldt_prover = ldt_prover.with_spill_dir(dir);
+        }
+        ldt_prover.build(channel, deep_composition_lde.try_into().unwrap());
+        ldt_prover
+    }
 
-        let query_positions = channel.get_fri_query_positions();
-        let fri_proof = fri_prover.into_proof(&query_positions);
-
-        let queries = Queries::new(
-            &base_trace_lde,
-            extension_trace_lde.as_ref(),
-            &composition_trace_lde,
-            base_trace_lde_tree,
-            extension_trace_tree,
-            composition_trace_lde_tree,
-            &query_positions,
-        );
+    /// Finds the proof-of-work nonce for the low-degree test commitments
+    fn grind(&self, channel: &mut impl ProverChannel<Self::Air>) {
+        channel.grind_fri_commitments();
+    }
 
-        Ok(channel.build_proof(queries, fri_proof))
+    /// Opens the trace and composition trace commitments at the query positions
+    fn build_queries<D: Digest>(
+        &self,
+        execution_trace: ExecutionTrace<Self::Fp, Self::Fq, D>,
+        composition_trace: CompositionTrace<Self::Fq, D>,
+        positions: &[usize],
+    ) -> Queries<Self::Fp, Self::Fq> {
+        Queries::new(
+            &execution_trace.base_trace_lde,
+            execution_trace.extension_trace_lde.as_ref(),
+            &composition_trace.lde,
+            execution_trace.base_trace_tree,
+            execution_trace.extension_trace_trees,
+            composition_trace.tree,
+            positions,
+            self.options().merkle_cap_height.into(),
+        )
     }
 }
@@ -1,14 +1,92 @@
+//! Fiat-Shamir public coin shared by the prover and verifier.
+//!
+//! Proofs must verify on every target so everything absorbed into the coin
+//! has a single canonical encoding. Items are encoded with arkworks'
+//! compressed serialization:
+//!
+//! - field elements are their canonical (not Montgomery) value as
+//!   little-endian limbs. Extension field elements are their coefficients in
+//!   order.
+//! - integers, including `usize`, are little-endian `u64`s
+//! - slices and `Vec`s are a `u64` length followed by each item
+//! - commitments are absorbed as a slice of digest bytes. A Merkle cap is
+//!   the bytes of its nodes concatenated.
+//! - proof options are one byte per option in declaration order
+//!
+//! Each absorb hashes `seed || label || item` where `label` is the
+//! [TranscriptLabel] of the item encoded as a byte slice. Labels keep items
+//! from different sections of a proof from being confused with each other.
+//! Each draw hashes `seed || counter` where `counter` is a big-endian `u64`.
+//! [PublicCoin::draw] samples field elements by rejection so every element is
+//! equally likely. The hash outputs of consecutive draws are concatenated into
+//! a byte stream and each base field coefficient (in order) is sampled as:
+//!
+//! 1. take the next `ceil(b / 8)` bytes of the stream where `b` is the bit
+//!    size of the base field modulus `p`
+//! 2. read them as a little-endian integer and keep the low `b` bits
+//! 3. accept the integer if it is less than `p` otherwise go back to 1
+//!
+//! Bytes left in the stream after the last coefficient are discarded. Since
+//! `p >= 2^(b-1)` each attempt is accepted with probability at least 1/2.
+//!
+//! [PublicCoin::draw_positions] samples query positions in `0..n` the same
+//! way from a fresh byte stream with `b` the bit size of `n - 1` and `n` in
+//! place of `p`. Candidates are read as `u64`s so positions are the same on
+//! 32-bit and 64-bit targets. Evaluation domains have a power of two size so
+//! in practice every candidate is accepted and a position is the low
+//! `log2(n)` bits of its bytes.
+use crate::grinding::proof_of_work;
+use crate::grinding::NonceEncoding;
+use crate::verifier::VerificationEvent;
+use ark_ff::BigInteger;
 use ark_ff::Field;
+use ark_ff::PrimeField;
 use ark_serialize::CanonicalSerialize;
 use digest::Digest;
 use digest::Output;
-use rand_chacha::rand_core::SeedableRng;
-use rand_chacha::ChaCha20Rng;
+
+/// Domain-separation tag hashed into the public coin ahead of each absorbed
+/// item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptLabel {
+    BaseTraceCommitment,
+    ExtensionTraceCommitment,
+    CompositionTraceCommitment,
+    OodTraceStates,
+    OodConstraintEvaluations,
+    PowNonce,
+    /// Commitment to a FRI layer including the remainder layer
+    FriLayerCommitment,
+    /// FRI remainder sent as coefficients
+    FriRemainder,
+    StirRoundCommitment,
+    /// STIR out-of-domain evaluations or final polynomial
+    StirEvaluations,
+}
+
+impl TranscriptLabel {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::BaseTraceCommitment => "base_trace_commitment",
+            Self::ExtensionTraceCommitment => "extension_trace_commitment",
+            Self::CompositionTraceCommitment => "composition_trace_commitment",
+            Self::OodTraceStates => "ood_trace_states",
+            Self::OodConstraintEvaluations => "ood_constraint_evaluations",
+            Self::PowNonce => "pow_nonce",
+            Self::FriLayerCommitment => "fri_layer_commitment",
+            Self::FriRemainder => "fri_remainder",
+            Self::StirRoundCommitment => "stir_round_commitment",
+            Self::StirEvaluations => "stir_evaluations",
+        }
+    }
+}
 
 // TODO: refactor public coin/channel stuff
 pub struct PublicCoin<D: Digest> {
     pub seed: Output<D>,
     counter: usize,
+    /// Absorbs and draws are appended here when recording is enabled
+    events: Option<Vec<VerificationEvent>>,
 }
 
 impl<D: Digest> PublicCoin<D> {
@@ -16,41 +94,123 @@ impl<D: Digest> PublicCoin<D> {
         PublicCoin {
             seed: D::new_with_prefix(seed).finalize(),
             counter: 0,
+            events: None,
+        }
+    }
+
+    /// Starts recording every absorb and draw
+    pub fn record_events(&mut self) {
+        self.events.get_or_insert_with(Vec::new);
+    }
+
+    /// Records an event if recording is enabled
+    pub fn record(&mut self, event: VerificationEvent) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
         }
     }
 
-    pub fn reseed(&mut self, item: &impl CanonicalSerialize) {
+    pub fn is_recording(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Returns the recorded events and stops recording
+    pub fn take_events(&mut self) -> Vec<VerificationEvent> {
+        self.events.take().unwrap_or_default()
+    }
+
+    /// Absorbs `item` tagged with `label`
+    pub fn reseed(&mut self, label: TranscriptLabel, item: &impl CanonicalSerialize) {
         let mut data = Vec::new();
         item.serialize_compressed(&mut data).unwrap();
-        let mut hasher = D::new();
-        hasher.update(&self.seed);
-        hasher.update(data);
-        self.seed = hasher.finalize();
+        let seed = self.absorb_hash(label, &data);
+        self.record(VerificationEvent::Label(label.as_str()));
+        self.record(VerificationEvent::Absorb(data));
+        self.seed = seed;
         self.counter = 0;
+        self.record(VerificationEvent::Seed(self.seed.to_vec()));
     }
 
     pub fn seed_leading_zeros(&self) -> u32 {
         leading_zeros(&self.seed)
     }
 
+    /// Returns the leading zeros of the seed the coin would have after
+    /// absorbing `nonce` as the proof-of-work nonce. Matches the proof of
+    /// work of proofs with the default grinding options.
     pub fn check_leading_zeros(&self, nonce: u64) -> u32 {
-        let mut nonce_bytes = Vec::with_capacity(nonce.compressed_size());
-        nonce.serialize_compressed(&mut nonce_bytes).unwrap();
+        proof_of_work::<D>(&self.seed, nonce, NonceEncoding::U64Le).unwrap()
+    }
+
+    /// Returns hash(seed || label || data)
+    fn absorb_hash(&self, label: TranscriptLabel, data: &[u8]) -> Output<D> {
+        let mut label_bytes = Vec::new();
+        label
+            .as_str()
+            .as_bytes()
+            .serialize_compressed(&mut label_bytes)
+            .unwrap();
         let mut hasher = D::new();
         hasher.update(&self.seed);
-        hasher.update(&nonce_bytes);
-        leading_zeros(&hasher.finalize())
+        hasher.update(&label_bytes);
+        hasher.update(data);
+        hasher.finalize()
     }
 
+    /// Draws a uniformly random field element by rejection sampling each of
+    /// its base field coefficients. See the module docs for the exact
+    /// algorithm.
     pub fn draw<F: Field>(&mut self) -> F {
-        F::rand(&mut self.draw_rng())
+        let num_bits = F::BasePrimeField::MODULUS_BIT_SIZE as usize;
+        let num_bytes = (num_bits + 7) / 8;
+        let mut stream = Vec::new();
+        let mut coeffs = Vec::new();
+        while coeffs.len() < F::extension_degree() as usize {
+            let bits = self
+                .next_bytes(&mut stream, num_bytes)
+                .into_iter()
+                .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+                .take(num_bits)
+                .collect::<Vec<bool>>();
+            let candidate = <F::BasePrimeField as PrimeField>::BigInt::from_bits_le(&bits);
+            if let Some(coeff) = F::BasePrimeField::from_bigint(candidate) {
+                coeffs.push(coeff);
+            }
+        }
+        F::from_base_prime_field_elems(&coeffs).unwrap()
     }
 
-    // TODO: make this generic
-    pub fn draw_rng(&mut self) -> ChaCha20Rng {
-        let mut seed: [u8; 32] = Default::default();
-        seed.copy_from_slice(&self.next()[0..32]);
-        ChaCha20Rng::from_seed(seed)
+    /// Draws `num_positions` positions in `0..domain_size` by rejection
+    /// sampling. See the module docs for the exact algorithm.
+    pub fn draw_positions(&mut self, num_positions: usize, domain_size: usize) -> Vec<usize> {
+        assert!(
+            domain_size != 0,
+            "can't draw positions from an empty domain"
+        );
+        let domain_size = domain_size as u64;
+        let num_bits = (u64::BITS - (domain_size - 1).leading_zeros()) as usize;
+        let num_bytes = (num_bits + 7) / 8;
+        let mask = u64::MAX >> (64 - num_bits.max(1));
+        let mut stream = Vec::new();
+        let mut positions = Vec::new();
+        while positions.len() < num_positions {
+            let mut bytes = [0; 8];
+            bytes[..num_bytes].copy_from_slice(&self.next_bytes(&mut stream, num_bytes));
+            let candidate = u64::from_le_bytes(bytes) & mask;
+            if candidate < domain_size {
+                positions.push(candidate as usize);
+            }
+        }
+        positions
+    }
+
+    /// Takes the next `num_bytes` bytes of `stream`. Hash outputs of new
+    /// draws are appended to the stream when it runs out.
+    fn next_bytes(&mut self, stream: &mut Vec<u8>, num_bytes: usize) -> Vec<u8> {
+        while stream.len() < num_bytes {
+            stream.extend(self.next());
+        }
+        stream.drain(..num_bytes).collect()
     }
 
     /// Updates the state by incrementing the counter and returns hash(seed ||
@@ -59,12 +219,14 @@ impl<D: Digest> PublicCoin<D> {
         self.counter += 1;
         let mut hasher = D::new();
         hasher.update(&self.seed);
-        hasher.update(self.counter.to_be_bytes());
-        hasher.finalize()
+        hasher.update((self.counter as u64).to_be_bytes());
+        let output = hasher.finalize();
+        self.record(VerificationEvent::Draw(output.to_vec()));
+        output
     }
 }
 
-fn leading_zeros(hash: &[u8]) -> u32 {
+pub(crate) fn leading_zeros(hash: &[u8]) -> u32 {
     let mut zeros = 0;
     for byte in hash {
         let leading_zeros = byte.leading_zeros();
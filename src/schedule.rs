@@ -0,0 +1,108 @@
+//! Decides whether FFTs run on CPU threads or on the GPU. Every GPU dispatch
+//! has a fixed cost (command buffer submission) so small FFTs finish sooner
+//! on the CPU while large FFTs are much faster on the GPU. The size where the
+//! GPU starts winning depends on the machine and can be measured with
+//! [calibrate_gpu_fft_crossover].
+//!
+//! Twiddles and pipelines of a GPU FFT are built the first time a domain is
+//! used and kept for later FFTs over the same domain. A process that proves
+//! many traces of the same size only pays for them once. They can be released
+//! with [clear_gpu_fft_plans].
+#[cfg(feature = "gpu")]
+use ark_poly::EvaluationDomain;
+#[cfg(feature = "gpu")]
+use ark_poly::Radix2EvaluationDomain;
+#[cfg(feature = "gpu")]
+use gpu_poly::prelude::*;
+#[cfg(feature = "gpu")]
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+#[cfg(feature = "gpu")]
+use std::time::Instant;
+
+/// Crossover used until one is set or measured
+pub const DEFAULT_GPU_FFT_CROSSOVER: usize = 1 << 14;
+
+/// Largest FFT size timed by [calibrate_gpu_fft_crossover]
+#[cfg(feature = "gpu")]
+const MAX_CALIBRATION_SIZE: usize = 1 << 20;
+
+static GPU_FFT_CROSSOVER: AtomicUsize = AtomicUsize::new(DEFAULT_GPU_FFT_CROSSOVER);
+
+/// Sets the smallest FFT size that runs on the GPU
+pub fn set_gpu_fft_crossover(size: usize) {
+    GPU_FFT_CROSSOVER.store(size, Ordering::Relaxed);
+}
+
+/// Returns the smallest FFT size that runs on the GPU
+pub fn gpu_fft_crossover() -> usize {
+    GPU_FFT_CROSSOVER.load(Ordering::Relaxed)
+}
+
+/// Returns true if `size` is at least the crossover and the smallest FFT size
+/// the GPU supports
+pub fn exceeds_gpu_fft_crossover(size: usize, min_gpu_size: usize) -> bool {
+    size >= std::cmp::max(min_gpu_size, gpu_fft_crossover())
+}
+
+/// Returns true if an FFT of `size` should run on the GPU
+#[allow(unused_variables)]
+pub fn use_gpu_fft<F: GpuField>(size: usize) -> bool {
+    #[cfg(feature = "gpu")]
+    return F::HAS_GPU_KERNELS && exceeds_gpu_fft_crossover(size, GpuFft::<F>::MIN_SIZE);
+    #[cfg(not(feature = "gpu"))]
+    return false;
+}
+
+/// Times FFTs of increasing size on the CPU and GPU and sets the crossover to
+/// the first size where the GPU is faster. Returns the new crossover.
+#[cfg(feature = "gpu")]
+pub fn calibrate_gpu_fft_crossover<F: GpuFftField>() -> usize {
+    let mut rng = ark_std::test_rng();
+    let mut n = GpuFft::<F>::MIN_SIZE;
+    while n <= MAX_CALIBRATION_SIZE {
+        let domain = Radix2EvaluationDomain::<F>::new(n).unwrap();
+        let mut cpu_column = (0..n)
+            .map(|_| F::rand(&mut rng))
+            .collect::<Vec<F>>()
+            .to_vec_in(PageAlignedAllocator);
+        let mut gpu_column = cpu_column.clone();
+
+        let start = Instant::now();
+        domain.fft_in_place(&mut cpu_column);
+        let cpu_time = start.elapsed();
+
+        // plans are cached so only the first FFT over a domain builds one.
+        // Build it before timing so the GPU time doesn't include it.
+        let mut warmup_column = gpu_column.clone();
+        let mut fft = GpuFft::from(domain);
+        fft.encode(&mut warmup_column);
+        fft.execute();
+
+        let mut fft = GpuFft::from(domain);
+        let start = Instant::now();
+        fft.encode(&mut gpu_column);
+        fft.execute();
+        let gpu_time = start.elapsed();
+
+        if gpu_time < cpu_time {
+            break;
+        }
+
+        n *= 2;
+    }
+
+    set_gpu_fft_crossover(n);
+    n
+}
+
+/// Drops the cached twiddles and pipelines of every GPU FFT planned so far
+#[cfg(feature = "gpu")]
+pub fn clear_gpu_fft_plans() {
+    PLANNER.clear_fft_plans();
+    for planner in device_planners() {
+        planner.clear_fft_plans();
+    }
+}
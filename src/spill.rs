@@ -0,0 +1,154 @@
+//! Compressed spilling of large prover data to disk.
+//!
+//! The trace and composition LDEs are the largest allocations of a proof but
+//! they're only read again when the queries are opened at the very end. With a
+//! [Prover::working_dir](crate::Prover::working_dir) set the prover writes them
+//! to zstd compressed files in that directory while the low-degree test runs
+//! and reads them back to build the queries. FRI layers are spilled the same
+//! way until the proof is assembled. Spill files are removed when dropped.
+use crate::CompositionTrace;
+use crate::ExecutionTrace;
+use crate::Matrix;
+use crate::StarkExtensionOf;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use digest::Digest;
+use gpu_poly::prelude::*;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// zstd compression level. Field elements are close to uniformly random so
+/// higher levels cost time without saving much space.
+const COMPRESSION_LEVEL: i32 = 1;
+
+/// Columns of field elements written to a compressed file
+pub struct Spilled<F> {
+    path: PathBuf,
+    column_lens: Vec<usize>,
+    _phantom: PhantomData<F>,
+}
+
+impl<F: GpuField> Spilled<F> {
+    /// Writes the columns to a new file in `dir`
+    pub fn write<'a>(dir: &Path, columns: impl IntoIterator<Item = &'a [F]>) -> io::Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("ministark-{}-{id}.zst", std::process::id()));
+        let mut spilled = Spilled {
+            path,
+            column_lens: Vec::new(),
+            _phantom: PhantomData,
+        };
+        let file = BufWriter::new(File::create(&spilled.path)?);
+        let mut encoder = zstd::Encoder::new(file, COMPRESSION_LEVEL)?;
+        for column in columns {
+            for value in column {
+                value
+                    .serialize_uncompressed(&mut encoder)
+                    .map_err(invalid_data)?;
+            }
+            spilled.column_lens.push(column.len());
+        }
+        encoder.finish()?.flush()?;
+        Ok(spilled)
+    }
+
+    /// Total number of values in all columns
+    pub fn num_values(&self) -> usize {
+        self.column_lens.iter().sum()
+    }
+
+    pub fn write_matrix(dir: &Path, matrix: &Matrix<F>) -> io::Result<Self> {
+        Self::write(dir, matrix.iter().map(|column| column.as_slice()))
+    }
+
+    /// Reads the columns back. The file is kept until `self` is dropped.
+    pub fn read_matrix(&self) -> io::Result<Matrix<F>> {
+        let mut decoder = zstd::Decoder::new(File::open(&self.path)?)?;
+        let mut columns = Vec::new();
+        for &len in &self.column_lens {
+            let mut column = Vec::with_capacity_in(len, PageAlignedAllocator);
+            for _ in 0..len {
+                column.push(
+                    F::deserialize_uncompressed_unchecked(&mut decoder).map_err(invalid_data)?,
+                );
+            }
+            columns.push(column);
+        }
+        Ok(Matrix::new(columns))
+    }
+
+    /// Reads back a single spilled column
+    pub fn read_vec(&self) -> io::Result<Vec<F>> {
+        let mut matrix = self.read_matrix()?;
+        assert_eq!(matrix.num_cols(), 1, "expected a single column");
+        Ok(matrix.0.pop().unwrap().to_vec())
+    }
+}
+
+impl<F> Drop for Spilled<F> {
+    fn drop(&mut self) {
+        // the file may never have been created
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Trace and composition LDEs spilled while the low-degree test runs
+pub struct SpilledLdes<Fp, Fq> {
+    base_trace_lde: Spilled<Fp>,
+    extension_trace_lde: Option<Spilled<Fq>>,
+    composition_trace_lde: Spilled<Fq>,
+}
+
+impl<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>> SpilledLdes<Fp, Fq> {
+    /// Moves the LDEs to `dir`. Leaves empty matrices in their place.
+    pub fn spill<D: Digest>(
+        dir: &Path,
+        execution_trace: &mut ExecutionTrace<Fp, Fq, D>,
+        composition_trace: &mut CompositionTrace<Fq, D>,
+    ) -> io::Result<Self> {
+        let spilled = SpilledLdes {
+            base_trace_lde: Spilled::write_matrix(dir, &execution_trace.base_trace_lde)?,
+            extension_trace_lde: execution_trace
+                .extension_trace_lde
+                .as_ref()
+                .map(|lde| Spilled::write_matrix(dir, lde))
+                .transpose()?,
+            composition_trace_lde: Spilled::write_matrix(dir, &composition_trace.lde)?,
+        };
+        execution_trace.base_trace_lde = Matrix::new(Vec::new());
+        execution_trace.extension_trace_lde = execution_trace
+            .extension_trace_lde
+            .as_ref()
+            .map(|_| Matrix::new(Vec::new()));
+        composition_trace.lde = Matrix::new(Vec::new());
+        Ok(spilled)
+    }
+
+    /// Reads the LDEs back and removes their files
+    pub fn restore<D: Digest>(
+        self,
+        execution_trace: &mut ExecutionTrace<Fp, Fq, D>,
+        composition_trace: &mut CompositionTrace<Fq, D>,
+    ) -> io::Result<()> {
+        execution_trace.base_trace_lde = self.base_trace_lde.read_matrix()?;
+        if let Some(extension_trace_lde) = &self.extension_trace_lde {
+            execution_trace.extension_trace_lde = Some(extension_trace_lde.read_matrix()?);
+        }
+        composition_trace.lde = self.composition_trace_lde.read_matrix()?;
+        Ok(())
+    }
+}
+
+fn invalid_data(error: ark_serialize::SerializationError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
@@ -0,0 +1,796 @@
+//! STIR low-degree test from "STIR: Reed–Solomon Proximity Testing with Fewer
+//! Queries" <https://eprint.iacr.org/2024/390>.
+//!
+//! Each round folds the committed oracle by the folding factor (like FRI) but
+//! the folded polynomial is committed over a domain that only shrinks by a
+//! factor of two. The improved rate lets later rounds use fewer queries.
+use crate::evm::EvmEncode;
+use crate::evm::EvmEncoder;
+use crate::fri::fold_positions;
+use crate::fri::get_query_values;
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "json")]
+use crate::json::JsonEncode;
+use crate::merkle::hash_leaf;
+use crate::merkle::path_len;
+use crate::merkle::MerkleProof;
+use crate::merkle::MerkleTree;
+use crate::merkle::MerkleTreeError;
+use crate::random::PublicCoin;
+use crate::random::TranscriptLabel;
+#[cfg(feature = "arbitrary")]
+use crate::utils::arbitrary_elements;
+use crate::utils::batch_inverse;
+use crate::utils::horner_evaluate;
+use crate::utils::interleave;
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::Zero;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::DenseUVPolynomial;
+use ark_poly::EvaluationDomain;
+use ark_poly::Polynomial;
+use ark_poly::Radix2EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use digest::Digest;
+use digest::Output;
+use gpu_poly::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::ops::Deref;
+use thiserror::Error;
+
+#[derive(Clone, Copy)]
+pub struct StirOptions {
+    folding_factor: usize,
+    max_remainder_degree: usize,
+    blowup_factor: usize,
+    num_queries: usize,
+}
+
+impl StirOptions {
+    pub fn new(
+        blowup_factor: usize,
+        folding_factor: usize,
+        max_remainder_degree: usize,
+        num_queries: usize,
+    ) -> Self {
+        StirOptions {
+            folding_factor,
+            max_remainder_degree,
+            blowup_factor,
+            num_queries,
+        }
+    }
+
+    pub fn with_blowup_factor(mut self, blowup_factor: usize) -> Self {
+        self.blowup_factor = blowup_factor;
+        self
+    }
+
+    /// Returns the number of queries needed in `round` to match the security
+    /// of `num_queries` queries at the initial rate. Each round improves the
+    /// rate by a factor of `folding_factor / 2`.
+    pub fn num_queries(&self, round: usize) -> usize {
+        let initial_bits = self.blowup_factor.ilog2() as usize;
+        let round_bits = initial_bits + round * (self.folding_factor.ilog2() as usize - 1);
+        let target_bits = initial_bits * self.num_queries;
+        (target_bits + round_bits - 1) / round_bits
+    }
+
+    /// Returns the number of folding rounds for a polynomial with `degree`
+    /// coefficients. The last round sends the folded polynomial directly.
+    pub fn num_rounds(&self, mut degree: usize) -> usize {
+        let mut round = 0;
+        loop {
+            degree /= self.folding_factor;
+            // the quotient in the next round must have a non-negative degree
+            if degree <= self.max_remainder_degree || degree <= self.num_queries(round) + 1 {
+                return round + 1;
+            }
+            round += 1;
+        }
+    }
+
+    pub fn domain_offset<F: GpuField>(&self) -> F::FftField {
+        F::FftField::GENERATOR
+    }
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct StirQueries<F: GpuField> {
+    values: Vec<F>,
+    proofs: Vec<MerkleProof>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct StirProofRound<F: GpuField> {
+    commitment: Vec<u8>,
+    ood_evals: Vec<F>,
+    queries: StirQueries<F>,
+}
+
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
+pub struct StirProof<F: GpuField> {
+    rounds: Vec<StirProofRound<F>>,
+    final_poly: Vec<F>,
+    initial_queries: StirQueries<F>,
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, F: GpuField> arbitrary::Arbitrary<'a> for StirQueries<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(StirQueries {
+            values: arbitrary_elements(u)?,
+            proofs: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, F: GpuField> arbitrary::Arbitrary<'a> for StirProofRound<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(StirProofRound {
+            commitment: u.arbitrary()?,
+            ood_evals: arbitrary_elements(u)?,
+            queries: u.arbitrary()?,
+        })
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a, F: GpuField> arbitrary::Arbitrary<'a> for StirProof<F> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(StirProof {
+            rounds: u.arbitrary()?,
+            final_poly: arbitrary_elements(u)?,
+            initial_queries: u.arbitrary()?,
+        })
+    }
+}
+
+impl<F: GpuField> EvmEncode for StirQueries<F> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_elements(&self.values);
+        encoder.write_array(&self.proofs);
+    }
+}
+
+impl<F: GpuField> EvmEncode for StirProofRound<F> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_hash(&self.commitment);
+        encoder.write_elements(&self.ood_evals);
+        self.queries.encode_evm(encoder);
+    }
+}
+
+#[cfg(feature = "json")]
+impl<F: GpuField> JsonEncode for StirQueries<F> {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "values": json::elements_to_json(&self.values),
+            "proofs": self.proofs.encode_json(),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        Ok(StirQueries {
+            values: json::elements_from_json(json::field(value, "values")?)?,
+            proofs: Vec::decode_json(json::field(value, "proofs")?)?,
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl<F: GpuField> JsonEncode for StirProofRound<F> {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "commitment": json::bytes_to_json(&self.commitment),
+            "ood_evals": json::elements_to_json(&self.ood_evals),
+            "queries": self.queries.encode_json(),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        Ok(StirProofRound {
+            commitment: json::bytes_from_json(json::field(value, "commitment")?)?,
+            ood_evals: json::elements_from_json(json::field(value, "ood_evals")?)?,
+            queries: StirQueries::decode_json(json::field(value, "queries")?)?,
+        })
+    }
+}
+
+#[cfg(feature = "json")]
+impl<F: GpuField> JsonEncode for StirProof<F> {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "rounds": self.rounds.encode_json(),
+            "final_poly": json::elements_to_json(&self.final_poly),
+            "initial_queries": self.initial_queries.encode_json(),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        Ok(StirProof {
+            rounds: Vec::decode_json(json::field(value, "rounds")?)?,
+            final_poly: json::elements_from_json(json::field(value, "final_poly")?)?,
+            initial_queries: StirQueries::decode_json(json::field(value, "initial_queries")?)?,
+        })
+    }
+}
+
+impl<F: GpuField> StirProof<F> {
+    pub fn num_rounds(&self) -> usize {
+        self.rounds.len()
+    }
+
+    /// Commitments to the folded oracle of each round
+    pub fn round_commitments(&self) -> impl Iterator<Item = &[u8]> {
+        self.rounds.iter().map(|round| round.commitment.as_slice())
+    }
+
+    /// Coefficients of the polynomial sent in the last round
+    pub fn final_poly(&self) -> &[F] {
+        &self.final_poly
+    }
+}
+
+impl<F: GpuField> EvmEncode for StirProof<F> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_array(&self.rounds);
+        encoder.write_elements(&self.final_poly);
+        self.initial_queries.encode_evm(encoder);
+    }
+}
+
+pub struct StirProver<F: GpuField, D: Digest> {
+    options: StirOptions,
+    rounds: Vec<StirRound<F, D>>,
+    final_poly: Vec<F>,
+}
+
+struct StirRound<F: GpuField, D: Digest> {
+    tree: MerkleTree<D>,
+    /// interleaved evaluations of the round's oracle
+    evaluations: Vec<F>,
+    ood_evals: Vec<F>,
+    positions: Vec<usize>,
+}
+
+impl<F: GpuField, D: Digest> StirProver<F, D> {
+    pub fn new(options: StirOptions) -> Self {
+        StirProver {
+            options,
+            rounds: Vec::new(),
+            final_poly: Vec::new(),
+        }
+    }
+
+    pub fn into_proof(self, positions: &[usize]) -> StirProof<F> {
+        let folding_factor = self.options.folding_factor;
+        let first_round = &self.rounds[0];
+        let num_eval_chunks = first_round.evaluations.len() / folding_factor;
+        let initial_positions = fold_positions(positions, num_eval_chunks);
+        let initial_queries = query_round(first_round, &initial_positions, folding_factor);
+
+        let rounds = self
+            .rounds
+            .iter()
+            .map(|round| StirProofRound {
+                commitment: round.tree.root().to_vec(),
+                ood_evals: round.ood_evals.clone(),
+                queries: query_round(round, &round.positions, folding_factor),
+            })
+            .collect();
+
+        StirProof {
+            rounds,
+            final_poly: self.final_poly,
+            initial_queries,
+        }
+    }
+
+    pub fn build_rounds(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        evaluations: GpuVec<F>,
+    ) {
+        assert!(self.rounds.is_empty());
+        let evaluations = evaluations.to_vec();
+        match self.options.folding_factor {
+            2 => self.build_rounds_generic::<2>(channel, evaluations),
+            4 => self.build_rounds_generic::<4>(channel, evaluations),
+            8 => self.build_rounds_generic::<8>(channel, evaluations),
+            16 => self.build_rounds_generic::<16>(channel, evaluations),
+            folding_factor => unreachable!("folding factor {folding_factor} not supported"),
+        }
+    }
+
+    fn build_rounds_generic<const N: usize>(
+        &mut self,
+        channel: &mut impl ProverChannel<F, Digest = D>,
+        mut evaluations: Vec<F>,
+    ) {
+        let domain_offset = self.options.domain_offset::<F>();
+        let mut domain =
+            Radix2EvaluationDomain::new_coset(evaluations.len(), domain_offset).unwrap();
+        let mut degree = evaluations.len() / self.options.blowup_factor;
+        let num_rounds = self.options.num_rounds(degree);
+
+        let (mut tree, mut interleaved_evals) = commit_evaluations::<F, D, N>(&evaluations);
+        channel.commit_stir_round(tree.root());
+
+        for round in 0..num_rounds {
+            let alpha = channel.draw_stir_challenge();
+            let coeffs = domain.ifft(&evaluations);
+            let folded_coeffs = coeffs
+                .chunks(N)
+                .map(|chunk| horner_evaluate(chunk, &alpha))
+                .collect::<Vec<F>>();
+            degree /= N;
+            let folded_domain_size = domain.size() / N;
+            let num_queries = self.options.num_queries(round);
+
+            if round == num_rounds - 1 {
+                let mut final_poly = folded_coeffs;
+                debug_assert!(final_poly[degree..].iter().all(Zero::is_zero));
+                final_poly.truncate(degree);
+                channel.send_stir_evaluations(&final_poly);
+                let positions = channel.draw_stir_positions(num_queries, folded_domain_size);
+                self.final_poly = final_poly;
+                self.rounds.push(StirRound {
+                    tree,
+                    evaluations: interleaved_evals,
+                    ood_evals: Vec::new(),
+                    positions,
+                });
+                return;
+            }
+
+            // commit to the folded polynomial over a domain half the size
+            let next_domain =
+                Radix2EvaluationDomain::new_coset(domain.size() / 2, domain_offset).unwrap();
+            let next_evaluations = next_domain.fft(&folded_coeffs);
+            let (next_tree, next_interleaved_evals) =
+                commit_evaluations::<F, D, N>(&next_evaluations);
+            channel.commit_stir_round(next_tree.root());
+
+            let ood_point = channel.draw_stir_challenge();
+            let ood_eval = horner_evaluate(&folded_coeffs, &ood_point);
+            channel.send_stir_evaluations(&[ood_eval]);
+
+            let positions = channel.draw_stir_positions(num_queries, folded_domain_size);
+            let folded_offset = domain_offset.pow([N as u64]);
+            let folded_generator = domain.group_gen().pow([N as u64]);
+            let mut points = vec![ood_point];
+            let mut answers = vec![ood_eval];
+            for &position in &positions {
+                let y = F::from(folded_offset * folded_generator.pow([position as u64]));
+                points.push(y);
+                answers.push(horner_evaluate(&folded_coeffs, &y));
+            }
+            let comb_randomness = channel.draw_stir_challenge();
+
+            let answer_coeffs = interpolate(&points, &answers).unwrap();
+            evaluations = quotient_evaluations(
+                &next_evaluations,
+                next_domain,
+                &points,
+                &answer_coeffs,
+                comb_randomness,
+            );
+
+            self.rounds.push(StirRound {
+                tree,
+                evaluations: interleaved_evals,
+                ood_evals: vec![ood_eval],
+                positions,
+            });
+            tree = next_tree;
+            interleaved_evals = next_interleaved_evals;
+            domain = next_domain;
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum VerificationError {
+    #[error("expected {0} rounds but the proof contains {1}")]
+    NumRoundsMismatch(usize, usize),
+    #[error("commitment for round {0} is malformed")]
+    MalformedCommitment(usize),
+    #[error("unexpected number of out-of-domain evaluations in round {0}")]
+    OodEvaluationsMismatch(usize),
+    #[error("final polynomial exceeds the degree bound {0}")]
+    FinalPolyTooLarge(usize),
+    #[error("the number of query positions does not match the number of evaluations")]
+    NumPositionEvaluationMismatch,
+    #[error("initial queries do not resolve to their commitment")]
+    InitialCommitmentInvalid,
+    #[error("initial queries do not match the provided evaluations")]
+    InitialEvaluationsMismatch,
+    #[error("queries do not resolve to their commitment in round {0}")]
+    RoundCommitmentInvalid(usize),
+    #[error("quotient is undefined at a queried point in round {0}")]
+    QuotientUndefined(usize),
+    #[error("final polynomial is inconsistent with the folded queries")]
+    FinalPolyMismatch,
+    #[error("folding factor {0} is not supported")]
+    UnsupportedFoldingFactor(usize),
+}
+
+pub struct StirVerifier<F: GpuField, D: Digest> {
+    options: StirOptions,
+    proof: StirProof<F>,
+    domain: Radix2EvaluationDomain<F::FftField>,
+    commitments: Vec<Output<D>>,
+    alphas: Vec<F>,
+    ood_points: Vec<F>,
+    comb_randomness: Vec<F>,
+    positions: Vec<Vec<usize>>,
+}
+
+impl<F: GpuField, D: Digest> StirVerifier<F, D> {
+    pub fn new(
+        public_coin: &mut PublicCoin<impl Digest>,
+        options: StirOptions,
+        proof: StirProof<F>,
+        max_poly_degree: usize,
+    ) -> Result<Self, VerificationError> {
+        use VerificationError::*;
+        let folding_factor = options.folding_factor;
+        if !matches!(folding_factor, 2 | 4 | 8 | 16) {
+            return Err(UnsupportedFoldingFactor(folding_factor));
+        }
+        let domain_offset = options.domain_offset::<F>();
+        let domain_size = max_poly_degree.next_power_of_two() * options.blowup_factor;
+        let domain = Radix2EvaluationDomain::new_coset(domain_size, domain_offset).unwrap();
+
+        let mut degree = domain_size / options.blowup_factor;
+        let num_rounds = options.num_rounds(degree);
+        if proof.rounds.len() != num_rounds {
+            return Err(NumRoundsMismatch(num_rounds, proof.rounds.len()));
+        }
+
+        let commitments = proof
+            .rounds
+            .iter()
+            .enumerate()
+            .map(|(i, round)| {
+                if round.commitment.len() != <D as digest::OutputSizeUser>::output_size() {
+                    return Err(MalformedCommitment(i));
+                }
+                Ok(Output::<D>::from_slice(&round.commitment).clone())
+            })
+            .collect::<Result<Vec<Output<D>>, VerificationError>>()?;
+
+        // paths of any other length could open inner nodes as leaves
+        let has_depth = |queries: &StirQueries<F>, domain_size: usize| {
+            let depth = path_len(domain_size / folding_factor, 0);
+            queries
+                .proofs
+                .iter()
+                .all(|proof| proof.has_depth::<D>(depth))
+        };
+        if !has_depth(&proof.initial_queries, domain_size) {
+            return Err(InitialCommitmentInvalid);
+        }
+
+        let mut alphas = Vec::new();
+        let mut ood_points = Vec::new();
+        let mut comb_randomness = Vec::new();
+        let mut positions = Vec::new();
+        let mut round_domain_size = domain_size;
+        public_coin.reseed(
+            TranscriptLabel::StirRoundCommitment,
+            &commitments[0].deref(),
+        );
+        for (i, round) in proof.rounds.iter().enumerate() {
+            if !has_depth(&round.queries, round_domain_size) {
+                return Err(RoundCommitmentInvalid(i));
+            }
+            alphas.push(public_coin.draw());
+            degree /= folding_factor;
+            let folded_domain_size = round_domain_size / folding_factor;
+            let num_queries = options.num_queries(i);
+
+            if i == num_rounds - 1 {
+                if !round.ood_evals.is_empty() {
+                    return Err(OodEvaluationsMismatch(i));
+                }
+                if proof.final_poly.len() > degree {
+                    return Err(FinalPolyTooLarge(degree));
+                }
+                public_coin.reseed(TranscriptLabel::StirEvaluations, &proof.final_poly);
+                positions.push(draw_positions(public_coin, num_queries, folded_domain_size));
+                break;
+            }
+
+            if round.ood_evals.len() != 1 {
+                return Err(OodEvaluationsMismatch(i));
+            }
+            public_coin.reseed(
+                TranscriptLabel::StirRoundCommitment,
+                &commitments[i + 1].deref(),
+            );
+            ood_points.push(public_coin.draw());
+            public_coin.reseed(TranscriptLabel::StirEvaluations, &round.ood_evals);
+            positions.push(draw_positions(public_coin, num_queries, folded_domain_size));
+            comb_randomness.push(public_coin.draw());
+            round_domain_size /= 2;
+        }
+
+        Ok(StirVerifier {
+            options,
+            proof,
+            domain,
+            commitments,
+            alphas,
+            ood_points,
+            comb_randomness,
+            positions,
+        })
+    }
+
+    pub fn verify_generic<const N: usize>(
+        self,
+        positions: &[usize],
+        evaluations: &[F],
+    ) -> Result<(), VerificationError> {
+        use VerificationError::*;
+        let domain_offset = self.domain.coset_offset();
+        let folding_domain = Radix2EvaluationDomain::new(N).unwrap();
+        let mut domain_size = self.domain.size();
+        let mut domain_generator = self.domain.group_gen();
+
+        // check the first oracle is consistent with the provided evaluations
+        let initial_positions = fold_positions(positions, domain_size / N);
+        let initial_chunks = verify_queries::<F, D, N>(
+            &self.commitments[0],
+            &initial_positions,
+            &self.proof.initial_queries,
+            path_len(domain_size / N, 0),
+        )
+        .map_err(|_| InitialCommitmentInvalid)?;
+        let query_values =
+            get_query_values(&initial_chunks, positions, &initial_positions, domain_size);
+        if evaluations != query_values {
+            return Err(InitialEvaluationsMismatch);
+        }
+
+        // (points, answer polynomial, degree correction randomness) of the
+        // previous round. Needed to compute the current round's virtual oracle
+        let mut quotient: Option<(Vec<F>, Vec<F>, F)> = None;
+        let num_rounds = self.proof.rounds.len();
+        for (i, round) in self.proof.rounds.iter().enumerate() {
+            let round_positions = &self.positions[i];
+            let depth = path_len(domain_size / N, 0);
+            let chunks = verify_queries::<F, D, N>(
+                &self.commitments[i],
+                round_positions,
+                &round.queries,
+                depth,
+            )
+            .map_err(|_| RoundCommitmentInvalid(i))?;
+
+            let mut folded_evals = Vec::with_capacity(chunks.len());
+            for (chunk, &position) in chunks.iter().zip(round_positions) {
+                let offset = domain_offset * domain_generator.pow([position as u64]);
+                let coset = folding_domain.get_coset(offset).unwrap();
+                let mut values = chunk.to_vec();
+                if let Some((points, answer_coeffs, comb_randomness)) = &quotient {
+                    for (value, x) in values.iter_mut().zip(coset.elements()) {
+                        *value = quotient_evaluation(
+                            F::from(x),
+                            *value,
+                            points,
+                            answer_coeffs,
+                            *comb_randomness,
+                        )
+                        .ok_or(QuotientUndefined(i))?;
+                    }
+                }
+                let poly = DensePolynomial::from_coefficients_vec(coset.ifft(&values));
+                folded_evals.push(poly.evaluate(&self.alphas[i]));
+            }
+
+            let folded_offset = domain_offset.pow([N as u64]);
+            let folded_generator = domain_generator.pow([N as u64]);
+            let folded_points = round_positions
+                .iter()
+                .map(|&position| F::from(folded_offset * folded_generator.pow([position as u64])));
+
+            if i == num_rounds - 1 {
+                for (y, folded_eval) in folded_points.zip(folded_evals) {
+                    if horner_evaluate(&self.proof.final_poly, &y) != folded_eval {
+                        return Err(FinalPolyMismatch);
+                    }
+                }
+                break;
+            }
+
+            let mut points = vec![self.ood_points[i]];
+            points.extend(folded_points);
+            let mut answers = round.ood_evals.clone();
+            answers.extend(folded_evals);
+            let answer_coeffs = interpolate(&points, &answers).ok_or(QuotientUndefined(i))?;
+            quotient = Some((points, answer_coeffs, self.comb_randomness[i]));
+
+            // prepare for next round
+            domain_generator.square_in_place();
+            domain_size /= 2;
+        }
+
+        Ok(())
+    }
+
+    pub fn verify(self, positions: &[usize], evaluations: &[F]) -> Result<(), VerificationError> {
+        if positions.len() != evaluations.len() {
+            return Err(VerificationError::NumPositionEvaluationMismatch);
+        }
+
+        match self.options.folding_factor {
+            2 => self.verify_generic::<2>(positions, evaluations),
+            4 => self.verify_generic::<4>(positions, evaluations),
+            8 => self.verify_generic::<8>(positions, evaluations),
+            16 => self.verify_generic::<16>(positions, evaluations),
+            folding_factor => Err(VerificationError::UnsupportedFoldingFactor(folding_factor)),
+        }
+    }
+}
+
+pub trait ProverChannel<F: GpuField> {
+    type Digest: Digest + Send;
+
+    fn commit_stir_round(&mut self, round_root: &Output<Self::Digest>);
+
+    fn draw_stir_challenge(&mut self) -> F;
+
+    fn send_stir_evaluations(&mut self, evals: &[F]);
+
+    fn draw_stir_positions(&mut self, num_positions: usize, domain_size: usize) -> Vec<usize>;
+}
+
+/// Draws sorted and deduplicated query positions from the public coin
+pub fn draw_positions(
+    public_coin: &mut PublicCoin<impl Digest>,
+    num_positions: usize,
+    domain_size: usize,
+) -> Vec<usize> {
+    let mut positions = public_coin.draw_positions(num_positions, domain_size);
+    positions.sort();
+    positions.dedup();
+    positions
+}
+
+fn commit_evaluations<F: GpuField, D: Digest, const N: usize>(
+    evaluations: &[F],
+) -> (MerkleTree<D>, Vec<F>) {
+    let interleaved_evals: Vec<[F; N]> = interleave(evaluations);
+    let hashed_evals = ark_std::cfg_iter!(interleaved_evals)
+        .map(|chunk| hash_leaf::<D, F>(chunk))
+        .collect();
+    let tree = MerkleTree::new(hashed_evals).unwrap();
+    (tree, interleaved_evals.into_flattened())
+}
+
+fn query_round<F: GpuField, D: Digest>(
+    round: &StirRound<F, D>,
+    positions: &[usize],
+    folding_factor: usize,
+) -> StirQueries<F> {
+    let proofs = positions
+        .iter()
+        .map(|pos| {
+            round
+                .tree
+                .prove(*pos)
+                .expect("failed to generate Merkle proof")
+        })
+        .collect();
+    let mut values = Vec::new();
+    for &position in positions {
+        let i = position * folding_factor;
+        values.extend_from_slice(&round.evaluations[i..i + folding_factor]);
+    }
+    StirQueries { values, proofs }
+}
+
+fn verify_queries<F: GpuField, D: Digest, const N: usize>(
+    commitment: &Output<D>,
+    positions: &[usize],
+    queries: &StirQueries<F>,
+    depth: usize,
+) -> Result<Vec<[F; N]>, MerkleTreeError> {
+    let (chunks, remainder) = queries.values.as_chunks::<N>();
+    if !remainder.is_empty()
+        || chunks.len() != positions.len()
+        || queries.proofs.len() != positions.len()
+    {
+        return Err(MerkleTreeError::InvalidProof);
+    }
+
+    for ((chunk, proof), &position) in chunks.iter().zip(&queries.proofs).zip(positions) {
+        proof.verify_leaf::<D, _>(commitment, chunk, position, depth)?;
+    }
+
+    Ok(chunks.to_vec())
+}
+
+/// Returns the coefficients of the polynomial of least degree passing through
+/// the points `(xs[i], ys[i])`. Returns `None` if any of the `xs` repeat.
+fn interpolate<F: Field>(xs: &[F], ys: &[F]) -> Option<Vec<F>> {
+    let mut coeffs = vec![F::zero(); xs.len()];
+    for (i, (&x_i, &y_i)) in xs.iter().zip(ys).enumerate() {
+        let mut basis = vec![F::one()];
+        let mut denominator = F::one();
+        for (j, &x_j) in xs.iter().enumerate() {
+            if i == j {
+                continue;
+            }
+            // multiply the basis polynomial by `(x - x_j)`
+            let mut next = vec![F::zero(); basis.len() + 1];
+            for (k, &c) in basis.iter().enumerate() {
+                next[k + 1] += c;
+                next[k] -= c * x_j;
+            }
+            basis = next;
+            denominator *= x_i - x_j;
+        }
+        let scale = y_i * denominator.inverse()?;
+        for (coeff, b) in coeffs.iter_mut().zip(basis) {
+            *coeff += b * scale;
+        }
+    }
+    Some(coeffs)
+}
+
+/// Evaluates `1 + rx + (rx)^2 + ... + (rx)^e`. Multiplying a polynomial of
+/// degree `d - e` by this raises its degree to exactly `d`.
+fn degree_correction<F: Field>(x: F, comb_randomness: F, e: usize) -> F {
+    let rx = comb_randomness * x;
+    let mut acc = F::one();
+    let mut power = F::one();
+    for _ in 0..e {
+        power *= rx;
+        acc += power;
+    }
+    acc
+}
+
+/// Evaluates the degree corrected quotient `(g(x) - ans(x)) / ∏(x - p_i)`
+fn quotient_evaluation<F: Field>(
+    x: F,
+    g_x: F,
+    points: &[F],
+    answer_coeffs: &[F],
+    comb_randomness: F,
+) -> Option<F> {
+    let vanishing_eval: F = points.iter().map(|&p| x - p).product();
+    let quotient = (g_x - horner_evaluate(answer_coeffs, &x)) * vanishing_eval.inverse()?;
+    Some(quotient * degree_correction(x, comb_randomness, points.len()))
+}
+
+fn quotient_evaluations<F: GpuField>(
+    evaluations: &[F],
+    domain: Radix2EvaluationDomain<F::FftField>,
+    points: &[F],
+    answer_coeffs: &[F],
+    comb_randomness: F,
+) -> Vec<F> {
+    let xs = domain.elements().map(F::from).collect::<Vec<F>>();
+    let mut vanishing_evals = ark_std::cfg_iter!(xs)
+        .map(|&x| points.iter().map(|&p| x - p).product())
+        .collect::<Vec<F>>();
+    batch_inverse(&mut vanishing_evals);
+    ark_std::cfg_iter!(xs)
+        .zip(evaluations)
+        .zip(vanishing_evals)
+        .map(|((&x, &g_x), vanishing_inv)| {
+            let quotient = (g_x - horner_evaluate(answer_coeffs, &x)) * vanishing_inv;
+            quotient * degree_correction(x, comb_randomness, points.len())
+        })
+        .collect()
+}
@@ -0,0 +1,525 @@
+//! Helpers for unit testing AIRs without a prove and verify round trip.
+//! Enabled with the `test-utils` feature.
+//!
+//! [check_constraints] runs the interaction rounds of a trace with challenges
+//! drawn from a fixed seed and evaluates every constraint on every row.
+//! [MockAir] and [MockTrace] make it quick to check a handful of constraints
+//! against a hand-written or generated trace:
+//!
+//! ```text
+//! let trace = MockTrace::<Fp, 2>::from_fn(2048, |row, col| Fp::from((row + col) as u64));
+//! let air = MockAir::for_trace(&trace).with_transition_constraints(vec![0.next() - 1.curr()]);
+//! assert_constraints_satisfied(&air, &trace);
+//! ```
+//!
+//! A [TraceMutation] changes a valid trace in a small targeted way.
+//! [assert_rejects_mutation] checks an AIR doesn't accept the mutated trace,
+//! which catches under-constrained columns. With the `proptest` feature
+//! [mutations] generates mutations as a proptest strategy.
+//!
+//! [constraint_coverage] reports how many rows each constraint actually
+//! constrains. A selector that's never set leaves its constraints vacuously
+//! satisfied which [check_constraints] can't notice.
+use crate::air::execution_trace_rows;
+use crate::challenges::Challenges;
+use crate::hints::Hints;
+use crate::random::PublicCoin;
+use crate::Air;
+use crate::Constraint;
+use crate::Matrix;
+use crate::ProofOptions;
+use crate::ProvingError;
+use crate::StarkExtensionOf;
+use crate::Trace;
+use crate::TraceInfo;
+use ark_ff::Zero;
+use ark_std::rand::Rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::GpuFftField;
+use gpu_poly::GpuField;
+use sha2::Sha256;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Checks `trace` satisfies every constraint of `air`. Extension columns are
+/// built from challenges drawn from a fixed seed rather than a transcript.
+pub fn check_constraints<A: Air, T: Trace<Fp = A::Fp, Fq = A::Fq>>(
+    air: &A,
+    trace: &T,
+) -> Result<(), ProvingError> {
+    let (challenges, hints, extension_trace) = run_interaction_rounds(air, trace)?;
+    air.validate_constraints(
+        &challenges,
+        &hints,
+        trace.base_columns(),
+        extension_trace.as_ref(),
+    )
+}
+
+/// Builds the extension columns of every interaction round with challenges
+/// drawn from a fixed seed
+#[allow(clippy::type_complexity)]
+fn run_interaction_rounds<A: Air, T: Trace<Fp = A::Fp, Fq = A::Fq>>(
+    air: &A,
+    trace: &T,
+) -> Result<(Challenges<A::Fq>, Hints<A::Fq>, Option<Matrix<A::Fq>>), ProvingError> {
+    trace.validate()?;
+    let mut public_coin = PublicCoin::<Sha256>::new(b"ministark test utils");
+    let mut challenges = Challenges::default();
+    let mut extension_columns = Vec::new();
+    for round in 0..air.num_interaction_rounds() {
+        challenges.append(air.get_round_challenges(round, &mut public_coin));
+        if let Some(columns) = trace.build_round_columns(round, &challenges) {
+            extension_columns.push(columns);
+        }
+    }
+    let hints = air.get_hints(&challenges);
+    let extension_trace = (!extension_columns.is_empty()).then(|| Matrix::join(extension_columns));
+    Ok((challenges, hints, extension_trace))
+}
+
+/// Panics with the first unsatisfied constraint if `trace` doesn't satisfy
+/// the constraints of `air`. See [check_constraints].
+#[track_caller]
+pub fn assert_constraints_satisfied<A: Air, T: Trace<Fp = A::Fp, Fq = A::Fq>>(air: &A, trace: &T) {
+    if let Err(error) = check_constraints(air, trace) {
+        panic!("{error}");
+    }
+}
+
+/// AIR with whatever constraints and periodic columns it's given. Has no
+/// constraints to begin with.
+pub struct MockAir<Fp: GpuFftField, Fq = Fp> {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundary_constraints: Vec<Constraint<Fq>>,
+    transition_constraints: Vec<Constraint<Fq>>,
+    terminal_constraints: Vec<Constraint<Fq>>,
+    periodic_columns: Vec<Vec<Fp>>,
+}
+
+impl<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>> MockAir<Fp, Fq> {
+    /// AIR for traces with the shape of `trace`
+    pub fn for_trace(trace: &impl Trace<Fp = Fp, Fq = Fq>) -> Self {
+        Self::new(trace.info(), (), ProofOptions::new(32, 8, 0, 4, 16))
+    }
+
+    pub fn with_options(mut self, options: ProofOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn with_boundary_constraints(mut self, constraints: Vec<Constraint<Fq>>) -> Self {
+        self.boundary_constraints = constraints;
+        self
+    }
+
+    pub fn with_transition_constraints(mut self, constraints: Vec<Constraint<Fq>>) -> Self {
+        self.transition_constraints = constraints;
+        self
+    }
+
+    pub fn with_terminal_constraints(mut self, constraints: Vec<Constraint<Fq>>) -> Self {
+        self.terminal_constraints = constraints;
+        self
+    }
+
+    /// Periodic columns are referenced after the trace columns
+    pub fn with_periodic_columns(mut self, columns: Vec<Vec<Fp>>) -> Self {
+        self.periodic_columns = columns;
+        self
+    }
+}
+
+impl<Fp: GpuFftField, Fq: StarkExtensionOf<Fp>> Air for MockAir<Fp, Fq> {
+    type Fp = Fp;
+    type Fq = Fq;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        MockAir {
+            options,
+            trace_info,
+            boundary_constraints: Vec::new(),
+            transition_constraints: Vec::new(),
+            terminal_constraints: Vec::new(),
+            periodic_columns: Vec::new(),
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.terminal_constraints
+    }
+
+    fn periodic_columns(&self) -> Vec<Vec<Self::Fp>> {
+        self.periodic_columns.clone()
+    }
+}
+
+/// Trace of `N` base columns
+pub struct MockTrace<F: GpuFftField, const N: usize>(Matrix<F>);
+
+impl<F: GpuFftField, const N: usize> MockTrace<F, N> {
+    pub fn new(columns: Matrix<F>) -> Self {
+        MockTrace(columns)
+    }
+
+    pub fn from_columns(columns: Vec<Vec<F>>) -> Self {
+        let columns = columns
+            .into_iter()
+            .map(|column| column.to_vec_in(PageAlignedAllocator))
+            .collect();
+        MockTrace(Matrix::new(columns))
+    }
+
+    /// Trace of `len` rows where `f(row, col)` is the value of column `col` at
+    /// row `row`
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize, usize) -> F) -> Self {
+        let mut columns = (0..N)
+            .map(|_| Vec::with_capacity_in(len, PageAlignedAllocator))
+            .collect::<Vec<_>>();
+        for row in 0..len {
+            for (col, column) in columns.iter_mut().enumerate() {
+                column.push(f(row, col));
+            }
+        }
+        MockTrace(Matrix::new(columns))
+    }
+
+    /// Trace of `len` rows of random values
+    pub fn random<R: Rng + ?Sized>(len: usize, rng: &mut R) -> Self {
+        Self::from_fn(len, |_, _| F::rand(rng))
+    }
+}
+
+impl<F: GpuFftField, const N: usize> Trace for MockTrace<F, N> {
+    const NUM_BASE_COLUMNS: usize = N;
+    type Fp = F;
+    type Fq = F;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+/// Targeted change to the base columns of a trace
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceMutation {
+    /// Adds one to a cell
+    FlipCell { row: usize, col: usize },
+    /// Overwrites a cell with another cell of the same column. Breaks
+    /// permutation and lookup arguments since the column's multiset changes.
+    CopyCell { col: usize, from: usize, to: usize },
+    /// Swaps two rows of every base column
+    SwapRows(usize, usize),
+}
+
+impl TraceMutation {
+    /// Picks a mutation of a trace with `num_rows` rows and `num_cols` base
+    /// columns uniformly at random
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, num_rows: usize, num_cols: usize) -> Self {
+        let row = rng.gen_range(0..num_rows);
+        let other_row = rng.gen_range(0..num_rows);
+        let col = rng.gen_range(0..num_cols);
+        match rng.gen_range(0..3) {
+            0 => TraceMutation::FlipCell { row, col },
+            1 => TraceMutation::CopyCell {
+                col,
+                from: other_row,
+                to: row,
+            },
+            _ => TraceMutation::SwapRows(row, other_row),
+        }
+    }
+
+    /// Applies the mutation to `columns`. Returns false if the columns are
+    /// unchanged e.g. a copied cell already held the value.
+    pub fn apply<F: GpuField>(&self, columns: &mut Matrix<F>) -> bool {
+        match *self {
+            TraceMutation::FlipCell { row, col } => {
+                columns[col][row] += F::one();
+                true
+            }
+            TraceMutation::CopyCell { col, from, to } => {
+                let value = columns[col][from];
+                let changed = columns[col][to] != value;
+                columns[col][to] = value;
+                changed
+            }
+            TraceMutation::SwapRows(a, b) => {
+                let mut changed = false;
+                for column in columns.iter_mut() {
+                    changed |= column[a] != column[b];
+                    column.swap(a, b);
+                }
+                changed
+            }
+        }
+    }
+}
+
+/// Proptest strategy generating mutations of a trace with `num_rows` rows and
+/// `num_cols` base columns
+#[cfg(feature = "proptest")]
+pub fn mutations(
+    num_rows: usize,
+    num_cols: usize,
+) -> impl proptest::strategy::Strategy<Value = TraceMutation> {
+    use proptest::prelude::*;
+    prop_oneof![
+        (0..num_rows, 0..num_cols).prop_map(|(row, col)| TraceMutation::FlipCell { row, col }),
+        (0..num_cols, 0..num_rows, 0..num_rows)
+            .prop_map(|(col, from, to)| TraceMutation::CopyCell { col, from, to }),
+        (0..num_rows, 0..num_rows).prop_map(|(a, b)| TraceMutation::SwapRows(a, b)),
+    ]
+}
+
+/// Trace with mutated base columns. Extension columns are still built by the
+/// original trace so interaction constraints must tie them to the base
+/// columns for the mutation to be caught.
+pub struct MutatedTrace<'a, T: Trace> {
+    trace: &'a T,
+    base_columns: Matrix<T::Fp>,
+}
+
+impl<'a, T: Trace> MutatedTrace<'a, T> {
+    /// Returns `None` if the mutation leaves the trace unchanged
+    pub fn new(trace: &'a T, mutation: TraceMutation) -> Option<Self> {
+        let mut base_columns = trace.base_columns().clone();
+        mutation.apply(&mut base_columns).then_some(MutatedTrace {
+            trace,
+            base_columns,
+        })
+    }
+}
+
+impl<'a, T: Trace> Trace for MutatedTrace<'a, T> {
+    const NUM_BASE_COLUMNS: usize = T::NUM_BASE_COLUMNS;
+    const NUM_EXTENSION_COLUMNS: usize = T::NUM_EXTENSION_COLUMNS;
+    type Fp = T::Fp;
+    type Fq = T::Fq;
+
+    fn len(&self) -> usize {
+        self.trace.len()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.base_columns
+    }
+
+    fn build_round_columns(
+        &self,
+        round: usize,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        self.trace.build_round_columns(round, challenges)
+    }
+
+    fn meta(&self) -> Option<&[u8]> {
+        self.trace.meta()
+    }
+
+    fn column_names(&self) -> Vec<String> {
+        self.trace.column_names()
+    }
+}
+
+/// Panics if `air` accepts `trace` after applying `mutation`. Mutations that
+/// leave the trace unchanged are ignored. The prover checks constraints the
+/// same way in debug builds so an accepted mutation means a proof of the
+/// mutated trace could be generated.
+#[track_caller]
+pub fn assert_rejects_mutation<A: Air, T: Trace<Fp = A::Fp, Fq = A::Fq>>(
+    air: &A,
+    trace: &T,
+    mutation: TraceMutation,
+) {
+    if let Some(mutated) = MutatedTrace::new(trace, mutation) {
+        if check_constraints(air, &mutated).is_ok() {
+            panic!("AIR accepts the trace after mutation {mutation:?}");
+        }
+    }
+}
+
+/// How often a constraint is active over a trace. Constraints are stored as a
+/// sum of terms so a constraint `s * (a - b)` gated by a selector `s` becomes
+/// `s * a - s * b`. A row where every term is zero is vacuously satisfied and
+/// counts as inactive.
+#[derive(Debug, Clone)]
+pub struct ConstraintCoverage {
+    /// "boundary", "transition" or "terminal"
+    pub kind: &'static str,
+    pub index: usize,
+    /// Number of rows the constraint is evaluated on
+    pub num_rows: usize,
+    /// Number of rows where at least one term is non-zero
+    pub active_rows: usize,
+    /// Terms that are zero on every row
+    pub zero_terms: Vec<String>,
+}
+
+impl ConstraintCoverage {
+    /// Fraction of rows the constraint is active on
+    pub fn activity(&self) -> f64 {
+        if self.num_rows == 0 {
+            return 0.0;
+        }
+        self.active_rows as f64 / self.num_rows as f64
+    }
+
+    /// Returns true if the constraint isn't active on any row
+    pub fn is_dead(&self) -> bool {
+        self.active_rows == 0
+    }
+}
+
+/// Coverage of every constraint of an AIR returned by [constraint_coverage].
+/// Displays as a report with one line per constraint.
+#[derive(Debug, Clone)]
+pub struct CoverageReport {
+    /// Boundary constraints followed by the transition and terminal
+    /// constraints (assertion constraints included)
+    pub constraints: Vec<ConstraintCoverage>,
+}
+
+impl CoverageReport {
+    /// Constraints that aren't active on any row
+    pub fn dead_constraints(&self) -> impl Iterator<Item = &ConstraintCoverage> {
+        self.constraints
+            .iter()
+            .filter(|coverage| coverage.is_dead())
+    }
+
+    /// Returns the coverage of a constraint e.g. `get("transition", 3)`
+    pub fn get(&self, kind: &str, index: usize) -> Option<&ConstraintCoverage> {
+        self.constraints
+            .iter()
+            .find(|coverage| coverage.kind == kind && coverage.index == index)
+    }
+}
+
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for coverage in &self.constraints {
+            let ConstraintCoverage {
+                kind,
+                index,
+                num_rows,
+                active_rows,
+                zero_terms,
+            } = coverage;
+            let percent = coverage.activity() * 100.0;
+            write!(
+                f,
+                "{kind} constraint {index}: active on {active_rows}/{num_rows} rows ({percent:.1}%)"
+            )?;
+            if coverage.is_dead() {
+                write!(f, " DEAD")?;
+            }
+            writeln!(f)?;
+            for term in zero_terms {
+                writeln!(f, "  term is always zero: {term}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates every term of every constraint of `air` on `trace` and reports
+/// the fraction of rows each constraint is active on. Dead constraints and
+/// terms that are always zero usually point to a selector that's never set
+/// or set on the wrong rows.
+pub fn constraint_coverage<A: Air, T: Trace<Fp = A::Fp, Fq = A::Fq>>(
+    air: &A,
+    trace: &T,
+) -> Result<CoverageReport, ProvingError> {
+    let (challenges, hints, extension_trace) = run_interaction_rounds(air, trace)?;
+    let rows = execution_trace_rows(air, trace.base_columns(), extension_trace.as_ref());
+    let last_row = rows.len() - 1;
+
+    let mut constraints = Vec::new();
+    let mut cover =
+        |kind: &'static str, constraints_of_kind: Vec<(Constraint<A::Fq>, Range<usize>)>| {
+            for (index, (constraint, rows_evaluated)) in constraints_of_kind.into_iter().enumerate()
+            {
+                let frame_len = constraint.max_row_offset() + 1;
+                let mut is_zero_term = vec![true; constraint.terms().len()];
+                let mut active_rows = 0;
+                for row in rows_evaluated.clone() {
+                    let frame = rows[row..(row + frame_len).min(rows.len())]
+                        .iter()
+                        .map(Vec::as_slice)
+                        .collect::<Vec<&[A::Fq]>>();
+                    let terms = constraint.evaluate_terms(&challenges, &hints, &frame);
+                    let mut is_active = false;
+                    for (is_zero, term) in is_zero_term.iter_mut().zip(terms) {
+                        if !term.is_zero() {
+                            *is_zero = false;
+                            is_active = true;
+                        }
+                    }
+                    active_rows += usize::from(is_active);
+                }
+                let zero_terms = constraint
+                    .terms()
+                    .iter()
+                    .zip(is_zero_term)
+                    .filter(|(_, is_zero)| *is_zero)
+                    .map(|(term, _)| format!("{term:?}"))
+                    .collect();
+                constraints.push(ConstraintCoverage {
+                    kind,
+                    index,
+                    num_rows: rows_evaluated.len(),
+                    active_rows,
+                    zero_terms,
+                });
+            }
+        };
+
+    let boundary_constraints = air.all_boundary_constraints().into_iter();
+    cover(
+        "boundary",
+        boundary_constraints.map(|c| (c, 0..1)).collect(),
+    );
+    let transition_constraints = air.all_transition_constraints().into_iter();
+    cover(
+        "transition",
+        transition_constraints
+            .map(|(c, num_exempt_rows)| (c, 0..rows.len() - num_exempt_rows))
+            .collect(),
+    );
+    let terminal_constraints = air.all_terminal_constraints().into_iter();
+    cover(
+        "terminal",
+        terminal_constraints
+            .map(|c| (c, last_row..last_row + 1))
+            .collect(),
+    );
+
+    Ok(CoverageReport { constraints })
+}
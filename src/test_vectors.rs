@@ -0,0 +1,249 @@
+//! Conformance test vectors for third-party verifier implementations. Enabled
+//! with the `test-vectors` feature.
+//!
+//! A vector is generated by proving a fixed Fibonacci AIR with fixed proof
+//! options and then verifying the proof with [Proof::verify_explained]. Proof
+//! generation is deterministic so a vector is identical on every machine and
+//! another implementation can check it reproduces every transcript state,
+//! challenge and commitment byte-for-byte.
+//!
+//! The AIR has two columns `a` and `b` over the 64-bit Goldilocks field:
+//!
+//! ```text
+//! boundary:   a[0] = 1, b[0] = 1
+//! transition: a[i+1] = b[i], b[i+1] = a[i] + b[i]
+//! terminal:   b[n-1] = result
+//! ```
+use crate::Air;
+use crate::Column;
+use crate::Constraint;
+use crate::Matrix;
+use crate::Proof;
+use crate::ProofOptions;
+use crate::Prover;
+use crate::Trace;
+use crate::TraceInfo;
+use crate::VerificationEvent;
+use ark_ff::One;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use std::fmt::Write;
+
+pub struct FibonacciTrace(Matrix<Fp>);
+
+impl FibonacciTrace {
+    pub fn new(len: usize) -> Self {
+        let mut a = Vec::with_capacity_in(len, PageAlignedAllocator);
+        let mut b = Vec::with_capacity_in(len, PageAlignedAllocator);
+        let (mut curr_a, mut curr_b) = (Fp::one(), Fp::one());
+        for _ in 0..len {
+            a.push(curr_a);
+            b.push(curr_b);
+            (curr_a, curr_b) = (curr_b, curr_a + curr_b);
+        }
+        FibonacciTrace(Matrix::new(vec![a, b]))
+    }
+}
+
+impl Trace for FibonacciTrace {
+    const NUM_BASE_COLUMNS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+pub struct FibonacciAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    result: Fp,
+    boundary_constraints: Vec<Constraint<Fp>>,
+    transition_constraints: Vec<Constraint<Fp>>,
+    terminal_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for FibonacciAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        let (a, b) = (0, 1);
+        FibonacciAir {
+            options,
+            trace_info,
+            result,
+            boundary_constraints: vec![a.curr() - Fp::one(), b.curr() - Fp::one()],
+            transition_constraints: vec![a.next() - b.curr(), b.next() - a.curr() - b.curr()],
+            terminal_constraints: vec![b.curr() - result],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Self::PublicInputs {
+        &self.result
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Self::Fq>] {
+        &self.terminal_constraints
+    }
+}
+
+pub struct FibonacciProver(ProofOptions);
+
+impl Prover for FibonacciProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        FibonacciProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.0[1].last().unwrap()
+    }
+}
+
+/// Everything a verifier sees while checking a proof of the Fibonacci AIR
+#[derive(Clone, Debug)]
+pub struct TestVector {
+    pub options: ProofOptions,
+    pub trace_len: usize,
+    /// Compressed serialization of the public inputs
+    pub public_inputs: Vec<u8>,
+    /// Bytes the public coin is created from i.e. the serialized public
+    /// inputs, trace info and proof options
+    pub seed: Vec<u8>,
+    pub base_trace_commitment: Vec<u8>,
+    pub composition_trace_commitment: Vec<u8>,
+    /// Every step of verification in order. Includes every transcript state
+    /// and drawn challenge.
+    pub events: Vec<VerificationEvent>,
+    /// Compressed serialization of the proof
+    pub proof: Vec<u8>,
+}
+
+impl TestVector {
+    pub const TRACE_LEN: usize = TraceInfo::MIN_TRACE_LENGTH;
+
+    /// Small proof options so vectors stay readable. NOT secure.
+    pub fn options() -> ProofOptions {
+        ProofOptions::new(4, 4, 2, 4, 16)
+    }
+
+    /// Generates the test vector for the fixed Fibonacci AIR and options
+    pub fn generate() -> Self {
+        let options = Self::options();
+        let prover = FibonacciProver::new(options);
+        let proof = prover
+            .generate_proof(FibonacciTrace::new(Self::TRACE_LEN))
+            .expect("failed to prove the Fibonacci AIR");
+
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        let result = proof.public_inputs.unwrap();
+        let mut public_inputs = Vec::new();
+        result.serialize_compressed(&mut public_inputs).unwrap();
+        let base_trace_commitment = proof.base_trace_commitment.clone();
+        let composition_trace_commitment = proof.composition_trace_commitment.clone();
+
+        let (verification, events) = Proof::verify_explained(proof, result);
+        verification.expect("generated proof is invalid");
+        let seed = match events.first() {
+            Some(VerificationEvent::Absorb(seed)) => seed.clone(),
+            _ => unreachable!("verification starts by seeding the public coin"),
+        };
+
+        TestVector {
+            options,
+            trace_len: Self::TRACE_LEN,
+            public_inputs,
+            seed,
+            base_trace_commitment,
+            composition_trace_commitment,
+            events,
+            proof: proof_bytes,
+        }
+    }
+
+    /// Encodes the vector as JSON. Byte strings are hex encoded and events
+    /// use their [Display](std::fmt::Display) format.
+    pub fn to_json(&self) -> String {
+        let ProofOptions {
+            num_queries,
+            lde_blowup_factor,
+            grinding_factor,
+            fri_folding_factor,
+            fri_max_remainder_size,
+            low_degree_test,
+            num_ood_points,
+            merkle_cap_height,
+            hash_function,
+            fri_folding_schedule,
+            fri_remainder_coefficients,
+            grinding_hash,
+            nonce_encoding,
+        } = self.options;
+        let fri_folding_schedule = fri_folding_schedule.factors();
+        let grinding_hash =
+            grinding_hash.map_or("null".to_string(), |hash| format!("\"{hash:?}\""));
+        let mut json = String::from("{\n");
+        writeln!(
+            json,
+            "  \"options\": {{\"num_queries\": {num_queries}, \"lde_blowup_factor\": {lde_blowup_factor}, \"grinding_factor\": {grinding_factor}, \"fri_folding_factor\": {fri_folding_factor}, \"fri_max_remainder_size\": {fri_max_remainder_size}, \"low_degree_test\": \"{low_degree_test:?}\", \"num_ood_points\": {num_ood_points}, \"merkle_cap_height\": {merkle_cap_height}, \"hash_function\": \"{hash_function:?}\", \"fri_folding_schedule\": {fri_folding_schedule:?}, \"fri_remainder_coefficients\": {fri_remainder_coefficients}, \"grinding_hash\": {grinding_hash}, \"nonce_encoding\": \"{nonce_encoding:?}\"}},"
+        )
+        .unwrap();
+        writeln!(json, "  \"trace_len\": {},", self.trace_len).unwrap();
+        for (name, bytes) in [
+            ("public_inputs", &self.public_inputs),
+            ("seed", &self.seed),
+            ("base_trace_commitment", &self.base_trace_commitment),
+            (
+                "composition_trace_commitment",
+                &self.composition_trace_commitment,
+            ),
+            ("proof", &self.proof),
+        ] {
+            writeln!(json, "  \"{name}\": \"{}\",", hex(bytes)).unwrap();
+        }
+        json.push_str("  \"events\": [");
+        for (i, event) in self.events.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(json, "{separator}\n    \"{event}\"").unwrap();
+        }
+        json.push_str("\n  ]\n}\n");
+        json
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
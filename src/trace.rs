@@ -1,14 +1,38 @@
 use crate::challenges::Challenges;
+use crate::evm::EvmEncode;
+use crate::evm::EvmEncoder;
+use crate::hints::Hints;
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "json")]
+use crate::json::JsonEncode;
+use crate::matrix::CompactColumn;
+use crate::matrix::CompactMatrix;
 use crate::merkle::MerkleProof;
 use crate::merkle::MerkleTree;
+#[cfg(feature = "arbitrary")]
+use crate::utils::arbitrary_elements;
+use crate::Column;
+use crate::Constraint;
 use crate::Matrix;
 use ark_ff::FftField;
+use ark_ff::Zero;
 use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::Compress;
+use ark_serialize::Read;
+use ark_serialize::SerializationError;
+use ark_serialize::Valid;
+use ark_serialize::Validate;
+use ark_serialize::Write;
 use digest::Digest;
 use gpu_poly::GpuField;
+use std::fmt::Debug;
+use std::fmt::Display;
+use std::fmt::Write as _;
 use std::ops::Add;
 use std::ops::MulAssign;
+use thiserror::Error;
 
 #[derive(CanonicalSerialize, CanonicalDeserialize, Clone)]
 pub struct Queries<Fp: GpuField, Fq: GpuField> {
@@ -20,46 +44,106 @@ pub struct Queries<Fp: GpuField, Fq: GpuField> {
     pub composition_trace_proofs: Vec<MerkleProof>,
 }
 
+#[cfg(feature = "arbitrary")]
+impl<'a, Fp: GpuField, Fq: GpuField> arbitrary::Arbitrary<'a> for Queries<Fp, Fq> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Queries {
+            base_trace_values: arbitrary_elements(u)?,
+            extension_trace_values: arbitrary_elements(u)?,
+            composition_trace_values: arbitrary_elements(u)?,
+            base_trace_proofs: u.arbitrary()?,
+            extension_trace_proofs: u.arbitrary()?,
+            composition_trace_proofs: u.arbitrary()?,
+        })
+    }
+}
+
+impl<Fp: GpuField, Fq: GpuField> EvmEncode for Queries<Fp, Fq> {
+    fn encode_evm(&self, encoder: &mut EvmEncoder) {
+        encoder.write_elements(&self.base_trace_values);
+        encoder.write_elements(&self.extension_trace_values);
+        encoder.write_elements(&self.composition_trace_values);
+        encoder.write_array(&self.base_trace_proofs);
+        encoder.write_array(&self.extension_trace_proofs);
+        encoder.write_array(&self.composition_trace_proofs);
+    }
+}
+
+#[cfg(feature = "json")]
+impl<Fp: GpuField, Fq: GpuField> JsonEncode for Queries<Fp, Fq> {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "base_trace_values": json::elements_to_json(&self.base_trace_values),
+            "extension_trace_values": json::elements_to_json(&self.extension_trace_values),
+            "composition_trace_values": json::elements_to_json(&self.composition_trace_values),
+            "base_trace_proofs": self.base_trace_proofs.encode_json(),
+            "extension_trace_proofs": self.extension_trace_proofs.encode_json(),
+            "composition_trace_proofs": self.composition_trace_proofs.encode_json(),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        let field = |name| json::field(value, name);
+        Ok(Queries {
+            base_trace_values: json::elements_from_json(field("base_trace_values")?)?,
+            extension_trace_values: json::elements_from_json(field("extension_trace_values")?)?,
+            composition_trace_values: json::elements_from_json(field("composition_trace_values")?)?,
+            base_trace_proofs: Vec::decode_json(field("base_trace_proofs")?)?,
+            extension_trace_proofs: Vec::decode_json(field("extension_trace_proofs")?)?,
+            composition_trace_proofs: Vec::decode_json(field("composition_trace_proofs")?)?,
+        })
+    }
+}
+
 impl<Fp: GpuField, Fq: GpuField> Queries<Fp, Fq> {
+    /// `extension_commitments` holds the commitment of each interaction round
+    /// in order. Proofs end at the trees' caps of height `cap_height`.
+    #[allow(clippy::too_many_arguments)]
     pub fn new<D: Digest>(
         base_trace_lde: &Matrix<Fp>,
         extension_trace_lde: Option<&Matrix<Fq>>,
         composition_trace_lde: &Matrix<Fq>,
         base_commitment: MerkleTree<D>,
-        extension_commitment: Option<MerkleTree<D>>,
+        extension_commitments: Vec<MerkleTree<D>>,
         composition_commitment: MerkleTree<D>,
         positions: &[usize],
+        cap_height: usize,
     ) -> Self {
         let mut base_trace_values = Vec::new();
         let mut extension_trace_values = Vec::new();
         let mut composition_trace_values = Vec::new();
         let mut base_trace_proofs = Vec::new();
-        let mut extension_trace_proofs = Vec::new();
         let mut composition_trace_proofs = Vec::new();
         for &position in positions {
             // execution trace
             let base_trace_row = base_trace_lde.get_row(position).unwrap();
             base_trace_values.extend(base_trace_row);
-            let base_proof = base_commitment.prove(position).unwrap();
+            let base_proof = base_commitment
+                .prove_with_cap(position, cap_height)
+                .unwrap();
             base_trace_proofs.push(base_proof);
 
             if let Some(extension_trace_lde) = extension_trace_lde {
                 let extension_trace_row = extension_trace_lde.get_row(position).unwrap();
                 extension_trace_values.extend(extension_trace_row);
-                let extension_proof = extension_commitment
-                    .as_ref()
-                    .unwrap()
-                    .prove(position)
-                    .unwrap();
-                extension_trace_proofs.push(extension_proof);
             }
 
             // composition trace
             let composition_trace_row = composition_trace_lde.get_row(position).unwrap();
             composition_trace_values.extend(composition_trace_row);
-            let composition_proof = composition_commitment.prove(position).unwrap();
+            let composition_proof = composition_commitment
+                .prove_with_cap(position, cap_height)
+                .unwrap();
             composition_trace_proofs.push(composition_proof);
         }
+        // proofs for all positions of the first round then the second etc.
+        let mut extension_trace_proofs = Vec::new();
+        for commitment in &extension_commitments {
+            for &position in positions {
+                extension_trace_proofs
+                    .push(commitment.prove_with_cap(position, cap_height).unwrap());
+            }
+        }
         Queries {
             base_trace_values,
             extension_trace_values,
@@ -72,13 +156,20 @@ impl<Fp: GpuField, Fq: GpuField> Queries<Fp, Fq> {
 }
 
 /// Public metadata about a trace.
-#[derive(Debug, Clone, CanonicalSerialize, CanonicalDeserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TraceInfo {
     pub num_base_columns: usize,
     pub num_extension_columns: usize,
     pub trace_len: usize,
     // TODO: want to change this to auxiliary data
     pub meta: Vec<u8>,
+    /// Names of the base columns followed by the extension columns. Only
+    /// used for debugging so they're left out of proofs. Empty if the columns
+    /// aren't named.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub column_names: Vec<String>,
 }
 
 impl TraceInfo {
@@ -107,8 +198,149 @@ impl TraceInfo {
             num_extension_columns,
             trace_len,
             meta,
+            column_names: Vec::new(),
         }
     }
+
+    /// Names the base columns followed by the extension columns. See
+    /// [segment_column_names] to name the columns of a segment after their
+    /// [Column] enum.
+    pub fn with_column_names(mut self, names: Vec<String>) -> Self {
+        assert_eq!(
+            names.len(),
+            self.num_base_columns + self.num_extension_columns,
+            "expected a name for each column"
+        );
+        self.column_names = names;
+        self
+    }
+
+    /// Name of column `index` or `column {index}` if it isn't named. Indices
+    /// past the trace columns (e.g. periodic columns) are never named.
+    pub fn column_name(&self, index: usize) -> String {
+        match self.column_names.get(index) {
+            Some(name) => name.clone(),
+            None => format!("column {index}"),
+        }
+    }
+}
+
+/// Names the columns of a segment `segment::Column` using their [Debug] names
+/// e.g. `Processor::MemValInv`. Names are placed at the index of each column.
+pub fn segment_column_names<C: Column + Debug>(
+    names: &mut [String],
+    segment: &str,
+    columns: impl IntoIterator<Item = C>,
+) {
+    for column in columns {
+        names[column.index()] = format!("{segment}::{column:?}");
+    }
+}
+
+// Column names are left out so they don't end up in proofs or the transcript
+impl CanonicalSerialize for TraceInfo {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.num_base_columns
+            .serialize_with_mode(&mut writer, compress)?;
+        self.num_extension_columns
+            .serialize_with_mode(&mut writer, compress)?;
+        self.trace_len.serialize_with_mode(&mut writer, compress)?;
+        self.meta.serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.num_base_columns.serialized_size(compress)
+            + self.num_extension_columns.serialized_size(compress)
+            + self.trace_len.serialized_size(compress)
+            + self.meta.serialized_size(compress)
+    }
+}
+
+impl Valid for TraceInfo {
+    fn check(&self) -> Result<(), SerializationError> {
+        Ok(())
+    }
+}
+
+/// Column names are left out like in the binary format
+#[cfg(feature = "json")]
+impl JsonEncode for TraceInfo {
+    fn encode_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "num_base_columns": self.num_base_columns,
+            "num_extension_columns": self.num_extension_columns,
+            "trace_len": self.trace_len,
+            "meta": json::bytes_to_json(&self.meta),
+        })
+    }
+
+    fn decode_json(value: &serde_json::Value) -> Result<Self, json::JsonError> {
+        let usize_field = |name| -> Result<usize, json::JsonError> {
+            Ok(json::uint_from_json(json::field(value, name)?)? as usize)
+        };
+        Ok(TraceInfo {
+            num_base_columns: usize_field("num_base_columns")?,
+            num_extension_columns: usize_field("num_extension_columns")?,
+            trace_len: usize_field("trace_len")?,
+            meta: json::bytes_from_json(json::field(value, "meta")?)?,
+            column_names: Vec::new(),
+        })
+    }
+}
+
+impl CanonicalDeserialize for TraceInfo {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        Ok(TraceInfo {
+            num_base_columns: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            num_extension_columns: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            trace_len: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            meta: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            column_names: Vec::new(),
+        })
+    }
+}
+
+/// Problems with a trace found before proving
+#[derive(Error, Debug)]
+pub enum TraceError {
+    #[error(
+        "trace length {0} is not a power of two of at least {}",
+        TraceInfo::MIN_TRACE_LENGTH
+    )]
+    InvalidLength(usize),
+    #[error("trace length is {0} but its columns have {1} rows")]
+    LengthMismatch(usize, usize),
+    #[error("expected {expected} base columns but the trace has {actual}")]
+    NumBaseColumns { expected: usize, actual: usize },
+    #[error(
+        "AIR expects {air} extension columns over its interaction rounds but the trace has {trace}"
+    )]
+    NumExtensionColumns { air: usize, trace: usize },
+    #[error(
+        "trace has {0} columns which exceeds the maximum of {}",
+        TraceInfo::MAX_TRACE_WIDTH
+    )]
+    TooWide(usize),
+    #[error("column {column} has {len} rows but column {first} has {expected}")]
+    ColumnLength {
+        column: String,
+        len: usize,
+        first: String,
+        expected: usize,
+    },
+    #[error(
+        "trace metadata has {0} bytes which exceeds the maximum of {}",
+        TraceInfo::MAX_META_BYTES
+    )]
+    MetaTooLarge(usize),
 }
 
 // TODO: docs: An execution trace of a computation, or the trace in short, is a
@@ -128,6 +360,16 @@ pub trait Trace {
     /// Returns a reference to the base trace columns.
     fn base_columns(&self) -> &Matrix<Self::Fp>;
 
+    /// Returns the base trace columns in compact form for traces that keep
+    /// them compact (see [CompactMatrix]). The prover interpolates these
+    /// columns one at a time so a compact column is only dense while it's
+    /// interpolated. Outside of debug builds the prover then never calls
+    /// [Trace::base_columns] so a trace that returns columns here can build
+    /// its dense columns lazily.
+    fn compact_base_columns(&self) -> Option<&CompactMatrix<Self::Fp>> {
+        None
+    }
+
     /// Builds and returns the extension columns
     /// These columns require auxiliary random elements to be constructed.
     /// Returns None if there are no columns that require this.
@@ -138,6 +380,78 @@ pub trait Trace {
         None
     }
 
+    /// Builds the extension columns of an interaction round from the
+    /// challenges drawn in this round and all earlier rounds. Returns None if
+    /// the round has no columns. Single round traces only need to implement
+    /// [Trace::build_extension_columns].
+    fn build_round_columns(
+        &self,
+        round: usize,
+        challenges: &Challenges<Self::Fq>,
+    ) -> Option<Matrix<Self::Fq>> {
+        if round == 0 {
+            self.build_extension_columns(challenges)
+        } else {
+            None
+        }
+    }
+
+    /// Checks the trace has the shape the prover expects: the declared
+    /// number of base columns, columns of equal length and a power of two
+    /// length that isn't too short. [Prover::generate_proof] calls this
+    /// before anything else so a malformed trace fails with an error naming
+    /// the offending column rather than a panic deep inside the prover.
+    ///
+    /// [Prover::generate_proof]: crate::Prover::generate_proof
+    fn validate(&self) -> Result<(), TraceError> {
+        let column_lens: Vec<usize> = match self.compact_base_columns() {
+            Some(columns) => columns.0.iter().map(CompactColumn::len).collect(),
+            None => self
+                .base_columns()
+                .iter()
+                .map(|column| column.len())
+                .collect(),
+        };
+        if column_lens.len() != Self::NUM_BASE_COLUMNS {
+            return Err(TraceError::NumBaseColumns {
+                expected: Self::NUM_BASE_COLUMNS,
+                actual: column_lens.len(),
+            });
+        }
+        let num_columns = Self::NUM_BASE_COLUMNS + Self::NUM_EXTENSION_COLUMNS;
+        if num_columns > TraceInfo::MAX_TRACE_WIDTH {
+            return Err(TraceError::TooWide(num_columns));
+        }
+
+        // check the columns are aligned before `len` which may assume they are
+        let names = self.column_names();
+        let name = |i: usize| names.get(i).cloned().unwrap_or_else(|| format!("base_{i}"));
+        let num_rows = column_lens.first().copied().unwrap_or(0);
+        for (i, &len) in column_lens.iter().enumerate() {
+            if len != num_rows {
+                return Err(TraceError::ColumnLength {
+                    column: name(i),
+                    len,
+                    first: name(0),
+                    expected: num_rows,
+                });
+            }
+        }
+        let len = self.len();
+        if len != num_rows {
+            return Err(TraceError::LengthMismatch(len, num_rows));
+        }
+        if !len.is_power_of_two() || len < TraceInfo::MIN_TRACE_LENGTH {
+            return Err(TraceError::InvalidLength(len));
+        }
+
+        let meta_len = self.meta().map_or(0, <[u8]>::len);
+        if meta_len > TraceInfo::MAX_META_BYTES {
+            return Err(TraceError::MetaTooLarge(meta_len));
+        }
+        Ok(())
+    }
+
     /// Returns trace info for this trace.
     fn info(&self) -> TraceInfo {
         TraceInfo::new(
@@ -146,6 +460,7 @@ pub trait Trace {
             self.len(),
             self.meta().map(|meta| meta.to_vec()),
         )
+        .with_column_names(self.column_names())
     }
 
     /// Returns metadata associated with this trace.
@@ -156,4 +471,239 @@ pub trait Trace {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Returns the names of the base columns followed by the names of the
+    /// extension columns. Used as headers when exporting the trace.
+    fn column_names(&self) -> Vec<String> {
+        let base_names = (0..Self::NUM_BASE_COLUMNS).map(|i| format!("base_{i}"));
+        let extension_names = (0..Self::NUM_EXTENSION_COLUMNS).map(|i| format!("extension_{i}"));
+        base_names.chain(extension_names).collect()
+    }
+
+    /// Dumps the base and extension columns as CSV with one line per row.
+    /// If `highlight` is given an extra `violated` column is added that is
+    /// `1` on rows where the constraint evaluates to a non-zero value.
+    fn to_csv(
+        &self,
+        challenges: &Challenges<Self::Fq>,
+        hints: &Hints<Self::Fq>,
+        highlight: Option<&Constraint<Self::Fq>>,
+    ) -> String {
+        let rows = export_rows(self, challenges);
+        let violations = highlight.map(|c| find_violations(c, challenges, hints, &rows));
+        let mut headers = self.column_names();
+        if violations.is_some() {
+            headers.push("violated".to_string());
+        }
+        let mut csv = String::new();
+        let headers = headers.iter().map(|name| csv_field(name));
+        writeln!(csv, "{}", headers.collect::<Vec<String>>().join(",")).unwrap();
+        for (i, row) in rows.iter().enumerate() {
+            let mut fields = row
+                .iter()
+                .map(|value| csv_field(&value.to_string()))
+                .collect::<Vec<String>>();
+            if let Some(violations) = &violations {
+                fields.push(u8::from(violations[i]).to_string());
+            }
+            writeln!(csv, "{}", fields.join(",")).unwrap();
+        }
+        csv
+    }
+
+    /// Dumps the base and extension columns as a standalone HTML table. If
+    /// `highlight` is given rows where the constraint evaluates to a non-zero
+    /// value are given the class `violated` and shown in red.
+    fn to_html(
+        &self,
+        challenges: &Challenges<Self::Fq>,
+        hints: &Hints<Self::Fq>,
+        highlight: Option<&Constraint<Self::Fq>>,
+    ) -> String {
+        let rows = export_rows(self, challenges);
+        let violations = highlight.map(|c| find_violations(c, challenges, hints, &rows));
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<style>\n");
+        html.push_str("table { border-collapse: collapse; font-family: monospace; }\n");
+        html.push_str("th, td { border: 1px solid #ccc; padding: 2px 6px; }\n");
+        html.push_str("tr.violated { background: #f8d0d0; }\n");
+        html.push_str("</style>\n</head>\n<body>\n<table>\n<tr><th>row</th>");
+        for name in self.column_names() {
+            write!(html, "<th>{}</th>", html_escape(&name)).unwrap();
+        }
+        html.push_str("</tr>\n");
+        for (i, row) in rows.iter().enumerate() {
+            let is_violated = violations.as_ref().map_or(false, |v| v[i]);
+            if is_violated {
+                write!(html, "<tr class=\"violated\"><td>{i}</td>").unwrap();
+            } else {
+                write!(html, "<tr><td>{i}</td>").unwrap();
+            }
+            for value in row {
+                write!(html, "<td>{}</td>", html_escape(&value.to_string())).unwrap();
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+
+    /// Compares the base columns of this trace with those of `other` cell by
+    /// cell. Handy for checking an optimized trace generator against a
+    /// reference implementation. Columns and rows only one of the traces has
+    /// are reported by [TraceDiff::len] and [TraceDiff::num_columns] rather
+    /// than compared.
+    fn diff<T: Trace<Fp = Self::Fp> + ?Sized>(&self, other: &T) -> TraceDiff<Self::Fp> {
+        let (columns, other_columns) = (self.base_columns(), other.base_columns());
+        let len = (self.len() != other.len()).then_some((self.len(), other.len()));
+        let num_columns = (columns.num_cols() != other_columns.num_cols())
+            .then_some((columns.num_cols(), other_columns.num_cols()));
+        let names = self.column_names();
+        let columns = columns
+            .iter()
+            .zip(other_columns.iter())
+            .enumerate()
+            .filter_map(|(index, (column, other_column))| {
+                let rows = column
+                    .iter()
+                    .zip(other_column.iter())
+                    .enumerate()
+                    .filter(|(_, (value, other_value))| value != other_value)
+                    .map(|(row, (value, other_value))| (row, *value, *other_value))
+                    .collect::<Vec<(usize, Self::Fp, Self::Fp)>>();
+                (!rows.is_empty()).then(|| ColumnDiff {
+                    index,
+                    name: names
+                        .get(index)
+                        .cloned()
+                        .unwrap_or_else(|| format!("base_{index}")),
+                    rows,
+                })
+            })
+            .collect();
+        TraceDiff {
+            len,
+            num_columns,
+            columns,
+        }
+    }
+}
+
+/// Mismatches between two traces returned by [Trace::diff]. Displays as a
+/// report listing every mismatched cell grouped by column.
+#[derive(Debug, Clone)]
+pub struct TraceDiff<F> {
+    /// Lengths of the two traces if they differ
+    pub len: Option<(usize, usize)>,
+    /// Number of base columns of the two traces if they differ
+    pub num_columns: Option<(usize, usize)>,
+    /// Columns with at least one mismatched row
+    pub columns: Vec<ColumnDiff<F>>,
+}
+
+impl<F> TraceDiff<F> {
+    /// Returns true if the traces are identical
+    pub fn is_empty(&self) -> bool {
+        self.len.is_none() && self.num_columns.is_none() && self.columns.is_empty()
+    }
+
+    /// Total number of mismatched cells
+    pub fn num_mismatches(&self) -> usize {
+        self.columns.iter().map(|column| column.rows.len()).sum()
+    }
+}
+
+impl<F: Display> Display for TraceDiff<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "traces are identical");
+        }
+        if let Some((len, other_len)) = self.len {
+            writeln!(f, "lengths differ: {len} != {other_len}")?;
+        }
+        if let Some((num_columns, other_num_columns)) = self.num_columns {
+            writeln!(
+                f,
+                "number of base columns differ: {num_columns} != {other_num_columns}"
+            )?;
+        }
+        for column in &self.columns {
+            let ColumnDiff { index, name, rows } = column;
+            writeln!(f, "column {index} ({name}): {} rows differ", rows.len())?;
+            for (row, value, other_value) in rows {
+                writeln!(f, "  row {row}: {value} != {other_value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rows of a base column where two traces differ
+#[derive(Debug, Clone)]
+pub struct ColumnDiff<F> {
+    pub index: usize,
+    pub name: String,
+    /// Row and the values of each trace at that row
+    pub rows: Vec<(usize, F, F)>,
+}
+
+/// Rows of the base columns followed by the extension columns
+fn export_rows<T: Trace + ?Sized>(trace: &T, challenges: &Challenges<T::Fq>) -> Vec<Vec<T::Fq>> {
+    let mut rows = trace
+        .base_columns()
+        .rows()
+        .into_iter()
+        .map(|row| row.into_iter().map(Into::into).collect::<Vec<T::Fq>>())
+        .collect::<Vec<Vec<T::Fq>>>();
+    if let Some(extension_columns) = trace.build_extension_columns(challenges) {
+        for (row, extension_row) in rows.iter_mut().zip(extension_columns.rows()) {
+            row.extend(extension_row);
+        }
+    }
+    rows
+}
+
+/// Returns which rows the constraint evaluates to a non-zero value on. Rows
+/// that would need a frame extending past the end of the trace are skipped
+/// rather than wrapped around since transition constraints are usually not
+/// enforced there.
+fn find_violations<F: GpuField>(
+    constraint: &Constraint<F>,
+    challenges: &Challenges<F>,
+    hints: &Hints<F>,
+    rows: &[Vec<F>],
+) -> Vec<bool> {
+    let frame_len = constraint.max_row_offset() + 1;
+    (0..rows.len())
+        .map(|i| {
+            if i + frame_len > rows.len() {
+                return false;
+            }
+            let frame = rows[i..i + frame_len]
+                .iter()
+                .map(Vec::as_slice)
+                .collect::<Vec<&[F]>>();
+            !constraint
+                .evaluate_frame(challenges, hints, &frame)
+                .is_zero()
+        })
+        .collect()
+}
+
+/// Quotes a CSV field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains(|c| matches!(c, ',' | '"' | '\n' | '\r')) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
@@ -0,0 +1,176 @@
+//! Every public coin absorb and draw made while verifying a proof.
+//!
+//! A [Transcript] can be exported so verifiers written in other languages
+//! (EVM, Cairo, C) can be checked against this one step by step. Items are
+//! encoded as described in the [PublicCoin](crate::PublicCoin) docs and are
+//! absorbed and drawn in this order:
+//!
+//! 1. the coin is seeded with `hash(public inputs || trace info || options)`
+//! 2. absorb the base trace commitment
+//! 3. for each interaction round draw the round's challenges then absorb the
+//!    round's extension trace commitment if the round has columns
+//! 4. draw the constraint composition coefficients
+//! 5. absorb the composition trace commitment
+//! 6. for each out-of-domain point draw the point, redrawing points the AIR
+//!    rejects, then absorb each row of the out-of-domain trace frame followed
+//!    by the out-of-domain constraint evaluations
+//! 7. draw the DEEP composition coefficients for each out-of-domain point
+//! 8. the low-degree test:
+//!    - FRI absorbs each layer commitment followed by a draw of the layer's
+//!      folding challenge. The remainder is then either absorbed as
+//!      coefficients or absorbed as a commitment followed by a draw.
+//!    - STIR absorbs the first round commitment. Each round draws a folding
+//!      challenge. The last round absorbs the final polynomial and draws its
+//!      query positions. Other rounds absorb the next round commitment, draw
+//!      an out-of-domain point, absorb the out-of-domain evaluations, draw
+//!      query positions and draw combination randomness.
+//! 9. absorb the proof-of-work nonce if the grinding factor isn't zero
+//! 10. draw the query positions
+//!
+//! Field elements (challenges, coefficients, folding challenges and
+//! out-of-domain points) are drawn one at a time by
+//! [PublicCoin::draw](crate::PublicCoin::draw) and each set of query
+//! positions by [PublicCoin::draw_positions](crate::PublicCoin::draw_positions).
+//! Composition coefficients are drawn as `(alpha, beta)` pairs in constraint
+//! order. DEEP coefficients are drawn for the base trace, extension trace
+//! and composition trace columns in order followed by the two degree
+//! adjustment coefficients.
+#[cfg(feature = "json")]
+use crate::json::bytes_to_json;
+use crate::Air;
+use crate::HashFunction;
+use crate::Proof;
+use crate::VerificationError;
+use crate::VerificationEvent;
+#[cfg(feature = "json")]
+use serde_json::json;
+#[cfg(feature = "json")]
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptEntry {
+    /// Serialized item absorbed under `label`. `seed` is the seed of the
+    /// public coin after the absorb.
+    Absorb {
+        label: &'static str,
+        bytes: Vec<u8>,
+        seed: Vec<u8>,
+    },
+    /// Output of `hash(seed || counter)`. The counter restarts from 1 after
+    /// every absorb.
+    Draw { counter: u64, output: Vec<u8> },
+    /// Value the verifier derived from the preceding draws
+    Challenge { name: &'static str, value: String },
+}
+
+/// Absorbs and draws made while verifying a proof. Returned by
+/// [Proof::transcript].
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub hash_function: HashFunction,
+    /// Bytes hashed to get the initial seed i.e. the serialized public
+    /// inputs, trace info and proof options
+    pub seed_input: Vec<u8>,
+    pub initial_seed: Vec<u8>,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    fn from_events(hash_function: HashFunction, events: Vec<VerificationEvent>) -> Self {
+        let mut seed_input = Vec::new();
+        let mut initial_seed = Vec::new();
+        let mut entries = Vec::new();
+        let mut label = None;
+        let mut absorbed = None;
+        let mut counter = 0;
+        for event in events {
+            match event {
+                VerificationEvent::Label(l) => label = Some(l),
+                VerificationEvent::Absorb(bytes) => absorbed = Some(bytes),
+                VerificationEvent::Seed(seed) => {
+                    counter = 0;
+                    match (label.take(), absorbed.take()) {
+                        (Some(label), Some(bytes)) => {
+                            entries.push(TranscriptEntry::Absorb { label, bytes, seed })
+                        }
+                        // the coin is seeded without a label
+                        (None, Some(bytes)) => {
+                            seed_input = bytes;
+                            initial_seed = seed;
+                        }
+                        _ => {}
+                    }
+                }
+                VerificationEvent::Draw(output) => {
+                    counter += 1;
+                    entries.push(TranscriptEntry::Draw { counter, output });
+                }
+                VerificationEvent::Challenge(name, value) => {
+                    entries.push(TranscriptEntry::Challenge { name, value })
+                }
+                VerificationEvent::OodConstraint { .. } | VerificationEvent::QueryCheck { .. } => {}
+            }
+        }
+        Transcript {
+            hash_function,
+            seed_input,
+            initial_seed,
+            entries,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl Transcript {
+    /// Encodes the transcript as JSON. Byte strings are lowercase hex and
+    /// entries are objects with a single `absorb`, `draw` or `challenge`
+    /// field.
+    pub fn to_json(&self) -> String {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                TranscriptEntry::Absorb { label, bytes, seed } => json!({
+                    "absorb": {
+                        "label": label,
+                        "bytes": bytes_to_json(bytes),
+                        "seed": bytes_to_json(seed),
+                    }
+                }),
+                TranscriptEntry::Draw { counter, output } => json!({
+                    "draw": {
+                        "counter": counter,
+                        "output": bytes_to_json(output),
+                    }
+                }),
+                TranscriptEntry::Challenge { name, value } => json!({
+                    "challenge": {
+                        "name": name,
+                        "value": value,
+                    }
+                }),
+            })
+            .collect::<Vec<Value>>();
+        let transcript = json!({
+            "hash_function": format!("{:?}", self.hash_function),
+            "seed_input": bytes_to_json(&self.seed_input),
+            "initial_seed": bytes_to_json(&self.initial_seed),
+            "entries": entries,
+        });
+        serde_json::to_string_pretty(&transcript).unwrap()
+    }
+}
+
+impl<A: Air> Proof<A> {
+    /// Verifies the proof like [Proof::verify_explained] and returns the
+    /// transcript of the verification. The transcript is returned even if
+    /// verification fails.
+    pub fn transcript(
+        self,
+        public_inputs: A::PublicInputs,
+    ) -> (Result<(), VerificationError>, Transcript) {
+        let hash_function = self.options.hash_function;
+        let (result, events) = self.verify_explained(public_inputs);
+        (result, Transcript::from_events(hash_function, events))
+    }
+}
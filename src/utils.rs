@@ -1,13 +1,68 @@
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+#[cfg(feature = "arbitrary")]
+use arbitrary::Unstructured;
 use ark_ff::FftField;
 use ark_ff::Field;
 use ark_ff::Zero;
 use ark_poly::domain::Radix2EvaluationDomain;
 use ark_poly::EvaluationDomain;
+#[cfg(feature = "gpu")]
+use gpu_poly::prelude::*;
+use gpu_poly::GpuField;
+use gpu_poly::GpuVec;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 use std::ops::Add;
 use std::time::Instant;
 
+/// Generates a vector with a length bounded by the remaining fuzzer input
+#[cfg(feature = "arbitrary")]
+pub(crate) fn arbitrary_vec<'a, T>(
+    u: &mut Unstructured<'a>,
+    mut f: impl FnMut(&mut Unstructured<'a>) -> arbitrary::Result<T>,
+) -> arbitrary::Result<Vec<T>> {
+    let len = u.arbitrary_len::<u64>()?;
+    (0..len).map(|_| f(u)).collect()
+}
+
+/// Generates field elements for fuzzing. Elements are mapped from a `u64`
+/// which is enough to reach every code path of the verifier.
+#[cfg(feature = "arbitrary")]
+pub(crate) fn arbitrary_elements<F: Field>(u: &mut Unstructured) -> arbitrary::Result<Vec<F>> {
+    arbitrary_vec(u, |u| Ok(F::from(u64::arbitrary(u)?)))
+}
+
+/// Accepts bytes from serde formats that have a byte type (CBOR, bincode)
+/// and those that write bytes as a sequence of numbers (JSON)
+#[cfg(feature = "serde")]
+pub(crate) struct BytesVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("bytes")
+    }
+
+    fn visit_bytes<E: serde::de::Error>(self, bytes: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(bytes.to_vec())
+    }
+
+    fn visit_byte_buf<E: serde::de::Error>(self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(bytes)
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        Ok(bytes)
+    }
+}
+
 pub struct Timer<'a> {
     name: &'a str,
     start: Instant,
@@ -57,8 +112,41 @@ pub fn ceil_power_of_two(value: usize) -> usize {
     }
 }
 
+/// Assigns items to devices in turn. Item `i` goes to device `i % n`. Used to
+/// split matrix columns between GPUs.
+pub fn round_robin<D: Copy, T>(
+    devices: &[D],
+    items: impl IntoIterator<Item = T>,
+) -> Vec<(D, Vec<T>)> {
+    assert!(!devices.is_empty(), "at least one device is required");
+    let mut groups = devices
+        .iter()
+        .map(|&device| (device, Vec::new()))
+        .collect::<Vec<(D, Vec<T>)>>();
+    for (i, item) in items.into_iter().enumerate() {
+        groups[i % devices.len()].1.push(item);
+    }
+    groups
+}
+
+/// Returns the rows `offset, offset + stride, offset + 2 * stride, ...` of
+/// `domain` as a domain of their own. The vanishing polynomial of a subdomain
+/// is a divisor for constraints that only apply to every `stride`-th row.
+pub fn subdomain<F: FftField>(
+    domain: &Radix2EvaluationDomain<F>,
+    stride: usize,
+    offset: usize,
+) -> Radix2EvaluationDomain<F> {
+    assert!(stride.is_power_of_two(), "stride must be a power of two");
+    assert!(stride <= domain.size(), "stride exceeds the domain size");
+    let subdomain_offset = domain.coset_offset() * domain.group_gen().pow([offset as u64]);
+    Radix2EvaluationDomain::new_coset(domain.size() / stride, subdomain_offset).unwrap()
+}
+
 // from arkworks
 /// This evaluates the vanishing polynomial for this domain at tau.
+/// Coset domains and subdomains (see [subdomain]) are supported since the
+/// vanishing polynomial of a coset `hD` of size `n` is `x^n - h^n`.
 pub fn evaluate_vanishing_polynomial<F: FftField, T: Field>(
     domain: &Radix2EvaluationDomain<F>,
     tau: T,
@@ -81,10 +169,11 @@ pub fn fill_vanishing_polynomial<F: FftField>(
     let scaled_eval_generator = eval_domain.group_gen().pow([n as u64]);
     let scaled_vanish_offset = vanish_domain.coset_offset_pow_size();
 
+    // chunks are sized by `dst` since subdomains can be much smaller
     #[cfg(feature = "parallel")]
-    let chunk_size = std::cmp::max(n / rayon::current_num_threads(), 1024);
+    let chunk_size = std::cmp::max(dst.len() / rayon::current_num_threads(), 1024);
     #[cfg(not(feature = "parallel"))]
-    let chunk_size = n;
+    let chunk_size = std::cmp::max(dst.len(), 1);
 
     ark_std::cfg_chunks_mut!(dst, chunk_size)
         .enumerate()
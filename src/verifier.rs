@@ -1,26 +1,42 @@
 use crate::challenges::Challenges;
 use crate::composer::DeepCompositionCoeffs;
 use crate::fri;
-use crate::fri::FriVerifier;
+use crate::grinding::proof_of_work;
 use crate::hints::Hints;
+use crate::kernel::ConstraintKernel;
+use crate::ldt::LowDegreeProof;
+use crate::ldt::LowDegreeVerifier;
+use crate::merkle::cap_len;
+use crate::merkle::parse_cap;
+use crate::merkle::path_len;
 use crate::merkle::MerkleProof;
-use crate::merkle::MerkleTree;
 use crate::merkle::MerkleTreeError;
 use crate::random::PublicCoin;
+use crate::random::TranscriptLabel;
+use crate::stir;
+use crate::trace::Queries;
+use crate::utils::batch_inverse;
 use crate::utils::evaluate_vanishing_polynomial;
 use crate::Air;
+use crate::HashFunction;
 // use crate::channel::VerifierChannel;
 use crate::Proof;
+use crate::ProofHeader;
+use crate::ProofOptions;
+use crate::ProofSummary;
+use crate::TraceInfo;
 use ark_ff::Field;
 use ark_ff::One;
 use ark_ff::Zero;
 use ark_poly::EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
 use ark_serialize::CanonicalSerialize;
+use ark_serialize::SerializationError;
 use digest::Digest;
 use digest::Output;
-use rand::Rng;
-use sha2::Sha256;
-use std::ops::Deref;
+use digest::OutputSizeUser;
+use std::fmt::Display;
+use std::io::Read;
 use thiserror::Error;
 
 /// Errors that are returned during verification of a STARK proof
@@ -30,6 +46,10 @@ pub enum VerificationError {
     InconsistentOodConstraintEvaluations,
     #[error("fri verification failed")]
     FriVerification(#[from] fri::VerificationError),
+    #[error("stir verification failed")]
+    StirVerification(#[from] stir::VerificationError),
+    #[error("low-degree proof does not match the low-degree test in the proof options")]
+    LowDegreeTestMismatch,
     #[error("query does not resolve to the base trace commitment")]
     BaseTraceQueryDoesNotMatchCommitment,
     #[error("query does not resolve to the extension trace commitment")]
@@ -38,101 +58,442 @@ pub enum VerificationError {
     CompositionTraceQueryDoesNotMatchCommitment,
     #[error("insufficient proof of work on fri commitments")]
     FriProofOfWork,
+    #[error("proof does not contain public inputs")]
+    MissingPublicInputs,
+    #[error("trace length `{0}` is not a power of two or is less than the minimum")]
+    InvalidTraceLength(usize),
+    #[error("trace metadata has `{0}` bytes which exceeds the maximum")]
+    InvalidTraceMeta(usize),
+    #[error("trace with `{0}` base columns and `{1}` extension columns is not supported")]
+    InvalidTraceWidth(usize, usize),
+    #[error("commitment has `{1}` bytes, but `{0}` were expected")]
+    InvalidCommitmentLength(usize, usize),
+    #[error("extension trace commitment is missing or unexpected")]
+    ExtensionTraceCommitmentMismatch,
+    #[error("expected `{0}` out-of-domain points, but `{1}` trace states and `{2}` constraint evaluations were provided")]
+    NumOodPoints(usize, usize, usize),
+    #[error("expected `{0}` rows in the out-of-domain evaluation frame, but `{1}` were provided")]
+    OodFrameSize(usize, usize),
+    #[error("expected `{0}` out-of-domain trace evaluations, but `{1}` were provided")]
+    OodTraceStatesLength(usize, usize),
+    #[error("expected `{0}` out-of-domain constraint evaluations, but `{1}` were provided")]
+    OodConstraintEvaluationsLength(usize, usize),
+    #[error("expected `{0}` base trace query values, but `{1}` were provided")]
+    BaseTraceQueriesLength(usize, usize),
+    #[error("expected `{0}` extension trace query values, but `{1}` were provided")]
+    ExtensionTraceQueriesLength(usize, usize),
+    #[error("expected `{0}` composition trace query values, but `{1}` were provided")]
+    CompositionTraceQueriesLength(usize, usize),
+    #[error("expected `{0}` merkle proofs, but `{1}` were provided")]
+    MerkleProofCount(usize, usize),
+    #[error("merkle proof does not have a node for each of the `{0}` levels of the tree")]
+    MerkleProofLength(usize),
+    #[error("constraint divisors are not invertible at the out-of-domain point")]
+    InvalidOodPoint,
+    #[error("blowup factor `{0}` is not a power of two between 2 and 64")]
+    InvalidBlowupFactor(u8),
+    #[error("number of queries `{0}` is not between 1 and 128")]
+    InvalidNumQueries(u8),
+    #[error("grinding factor `{0}` exceeds the maximum of 32")]
+    InvalidGrindingFactor(u8),
+    #[error("folding factor `{0}` is not one of 2, 4, 8 or 16")]
+    InvalidFoldingFactor(u8),
+    #[error("remainder size `{0}` is smaller than the folding factor or larger than the LDE domain of size `{1}`")]
+    InvalidRemainderSize(u8, usize),
+    #[error("number of out-of-domain points `{0}` is not between 1 and 8")]
+    InvalidNumOodPoints(u8),
+    #[error("merkle cap height `{0}` exceeds the maximum of 16")]
+    InvalidMerkleCapHeight(u8),
+    #[error("assertion `{0}` is not on a trace column or has steps outside the trace")]
+    InvalidAssertion(usize),
+    #[error("proof has `{0}` bytes which exceeds the limit of `{1}`")]
+    ProofTooLarge(usize, usize),
+    #[error("proof has `{0}` queries which exceeds the limit of `{1}`")]
+    TooManyQueries(usize, usize),
+    #[error("proof has `{0}` fri layers which exceeds the limit of `{1}`")]
+    TooManyFriLayers(usize, usize),
+    #[error("proof is from version `{}.{}` but the verifier is version `{}.{}`", .0[0], .0[1], .1[0], .1[1])]
+    VersionMismatch([u16; 2], [u16; 2]),
+    #[error("proof is over the field with id `{0:016x}` but the AIR is over `{1:016x}`")]
+    FieldMismatch(u64, u64),
+    #[error("proof header has hash function `{0:?}` but the proof options have `{1:?}`")]
+    HashFunctionMismatch(HashFunction, HashFunction),
+    #[error("proof is for the AIR with id `{0:016x}` but was verified with `{1:016x}`")]
+    AirMismatch(u64, u64),
+    #[error("constraint `{index}` has evaluation degree `{degree}` which exceeds the composition degree `{max}`")]
+    ConstraintDegreeTooHigh {
+        index: usize,
+        degree: usize,
+        max: usize,
+    },
+    #[error("proof could not be deserialized")]
+    Deserialization(#[from] SerializationError),
+}
+
+/// Serialized as the error message. Nested errors such as
+/// [SerializationError] have no serde representation so verification errors
+/// can't be deserialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for VerificationError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A step of verification recorded by [Proof::verify_explained]. Field
+/// elements are stored in their display format so events from different
+/// verifier implementations can be compared line by line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerificationEvent {
+    /// Domain-separation label absorbed ahead of the next item
+    Label(&'static str),
+    /// Serialized item absorbed into the public coin
+    Absorb(Vec<u8>),
+    /// Seed of the public coin after it's created or absorbs an item
+    Seed(Vec<u8>),
+    /// Output of the public coin i.e. `hash(seed || counter)`
+    Draw(Vec<u8>),
+    /// Value derived from the public coin by the verifier
+    Challenge(&'static str, String),
+    /// A constraint evaluated at out-of-domain point `point` and its term in
+    /// the constraint composition
+    OodConstraint {
+        point: usize,
+        constraint: usize,
+        evaluation: String,
+        term: String,
+    },
+    /// Whether the values queried at `position` resolve to a commitment
+    QueryCheck {
+        commitment: &'static str,
+        position: usize,
+        passed: bool,
+    },
+}
+
+impl Display for VerificationEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let hex =
+            |bytes: &[u8]| -> String { bytes.iter().map(|byte| format!("{byte:02x}")).collect() };
+        match self {
+            Self::Label(label) => write!(f, "label {label}"),
+            Self::Absorb(bytes) => write!(f, "absorb {}", hex(bytes)),
+            Self::Seed(bytes) => write!(f, "seed {}", hex(bytes)),
+            Self::Draw(bytes) => write!(f, "draw {}", hex(bytes)),
+            Self::Challenge(label, value) => write!(f, "challenge {label} = {value}"),
+            Self::OodConstraint {
+                point,
+                constraint,
+                evaluation,
+                term,
+            } => write!(
+                f,
+                "ood point {point} constraint {constraint}: evaluation = {evaluation}, term = {term}"
+            ),
+            Self::QueryCheck {
+                commitment,
+                position,
+                passed,
+            } => {
+                let result = if *passed { "ok" } else { "failed" };
+                write!(f, "query {commitment} at {position}: {result}")
+            }
+        }
+    }
 }
 
 impl<A: Air> Proof<A> {
-    pub fn verify(self) -> Result<(), VerificationError> {
+    /// Verifies the proof against the public inputs embedded in the proof.
+    /// Callers should check the embedded public inputs are the ones they
+    /// expect or use [Proof::verify_with_public_inputs] instead.
+    pub fn verify(mut self) -> Result<(), VerificationError> {
+        let public_inputs = self
+            .public_inputs
+            .take()
+            .ok_or(VerificationError::MissingPublicInputs)?;
+        self.verify_with_public_inputs(public_inputs)
+    }
+
+    /// Verifies the proof against public inputs supplied by the verifier. Any
+    /// public inputs embedded in the proof are ignored.
+    pub fn verify_with_public_inputs(
+        self,
+        public_inputs: A::PublicInputs,
+    ) -> Result<(), VerificationError> {
+        let seed = self.public_coin_seed(&public_inputs);
+        with_digest!(self.options.hash_function, D => {
+            let mut public_coin = PublicCoin::<D>::new(&seed);
+            self.verify_with_public_coin(public_inputs, &mut public_coin)
+        })
+    }
+
+    /// Verifies the proof like [Proof::verify_with_public_inputs] and records
+    /// every transcript absorb, drawn challenge, out-of-domain constraint
+    /// term and trace query check. Events are returned even if verification
+    /// fails so they can be diffed against another verifier implementation.
+    pub fn verify_explained(
+        self,
+        public_inputs: A::PublicInputs,
+    ) -> (Result<(), VerificationError>, Vec<VerificationEvent>) {
+        let seed = self.public_coin_seed(&public_inputs);
+        with_digest!(self.options.hash_function, D => {
+            let mut public_coin = PublicCoin::<D>::new(&seed);
+            public_coin.record_events();
+            public_coin.record(VerificationEvent::Absorb(seed));
+            public_coin.record(VerificationEvent::Seed(public_coin.seed.to_vec()));
+            let result = self.verify_with_public_coin(public_inputs, &mut public_coin);
+            (result, public_coin.take_events())
+        })
+    }
+
+    /// Deserializes a compressed proof and verifies it against public inputs
+    /// supplied by the verifier after checking it's within limits on the
+    /// verifier's work. The proof size is
+    /// checked first and the number of queries and FRI layers are checked
+    /// against the proof header so proofs over the limits are rejected before
+    /// anything is allocated or hashed. STIR proofs never have more rounds
+    /// than the FRI layers their header implies.
+    pub fn verify_with_limits(
+        proof_bytes: &[u8],
+        public_inputs: A::PublicInputs,
+        max_queries: usize,
+        max_fri_layers: usize,
+        max_proof_bytes: usize,
+    ) -> Result<(), VerificationError> {
+        if proof_bytes.len() > max_proof_bytes {
+            return Err(VerificationError::ProofTooLarge(
+                proof_bytes.len(),
+                max_proof_bytes,
+            ));
+        }
+
+        let mut reader = proof_bytes;
+        let header = ProofHeader::deserialize_compressed(&mut reader)?;
+        let options = ProofOptions::deserialize_compressed(&mut reader)?;
+        let trace_info = TraceInfo::deserialize_compressed(&mut reader)?;
+        // the folding factor must be valid to count the layers
+        validate_header::<A>(&header, &options, &trace_info)?;
+        let num_queries = usize::from(options.num_queries);
+        if num_queries > max_queries {
+            return Err(VerificationError::TooManyQueries(num_queries, max_queries));
+        }
+        let lde_domain_size = trace_info.trace_len * usize::from(options.lde_blowup_factor);
+        let num_fri_layers = options.into_fri_options().num_layers(lde_domain_size);
+        if num_fri_layers > max_fri_layers {
+            return Err(VerificationError::TooManyFriLayers(
+                num_fri_layers,
+                max_fri_layers,
+            ));
+        }
+
+        let proof = Proof::<A>::deserialize_compressed(proof_bytes)?;
+        proof.verify_with_public_inputs(public_inputs)
+    }
+
+    /// Reads the proof options, trace info and public inputs of a compressed
+    /// proof from `reader` without verifying it. Commitments and queries that
+    /// come before the public inputs are read without validation and
+    /// discarded. Nothing read this way can be trusted until the proof is
+    /// verified.
+    pub fn peek_public_inputs<R: Read>(
+        mut reader: R,
+    ) -> Result<ProofSummary<A>, VerificationError> {
+        let header = ProofHeader::deserialize_compressed(&mut reader)?;
+        let options = ProofOptions::deserialize_compressed(&mut reader)?;
+        let trace_info = TraceInfo::deserialize_compressed(&mut reader)?;
+        validate_header::<A>(&header, &options, &trace_info)?;
+
+        // base trace, extension trace and composition trace commitments
+        Vec::<u8>::deserialize_compressed_unchecked(&mut reader)?;
+        Vec::<Vec<u8>>::deserialize_compressed_unchecked(&mut reader)?;
+        Vec::<u8>::deserialize_compressed_unchecked(&mut reader)?;
+        LowDegreeProof::<A::Fq>::deserialize_compressed_unchecked(&mut reader)?;
+        u64::deserialize_compressed_unchecked(&mut reader)?;
+        Queries::<A::Fp, A::Fq>::deserialize_compressed_unchecked(&mut reader)?;
+        let public_inputs = CanonicalDeserialize::deserialize_compressed(&mut reader)?;
+
+        Ok(ProofSummary {
+            header,
+            options,
+            trace_info,
+            public_inputs,
+        })
+    }
+
+    fn public_coin_seed(&self, public_inputs: &A::PublicInputs) -> Vec<u8> {
+        let mut seed = Vec::new();
+        public_inputs.serialize_compressed(&mut seed).unwrap();
+        self.trace_info.serialize_compressed(&mut seed).unwrap();
+        self.options.serialize_compressed(&mut seed).unwrap();
+        seed
+    }
+
+    fn verify_with_public_coin<D: Digest>(
+        self,
+        public_inputs: A::PublicInputs,
+        public_coin: &mut PublicCoin<D>,
+    ) -> Result<(), VerificationError> {
         use VerificationError::*;
 
+        // check the trace info before it's given to the AIR
+        validate_header::<A>(&self.header, &self.options, &self.trace_info)?;
+
         let Proof {
             base_trace_commitment,
-            extension_trace_commitment,
+            extension_trace_commitments,
             composition_trace_commitment,
             ood_constraint_evaluations,
             ood_trace_states,
             trace_queries,
             trace_info,
-            public_inputs,
             options,
-            fri_proof,
+            low_degree_proof,
             pow_nonce,
             ..
         } = self;
 
-        let mut seed = Vec::new();
-        public_inputs.serialize_compressed(&mut seed).unwrap();
-        trace_info.serialize_compressed(&mut seed).unwrap();
-        options.serialize_compressed(&mut seed).unwrap();
-        let mut public_coin = PublicCoin::<Sha256>::new(&seed);
-
         let air = A::new(trace_info, public_inputs, options);
+        // assertions come from the public inputs which aren't trusted
+        if let Some(i) = air
+            .assertions()
+            .iter()
+            .position(|assertion| !assertion.fits_trace(air.trace_info()))
+        {
+            return Err(InvalidAssertion(i));
+        }
+        validate_shape::<A, D>(
+            &air,
+            &base_trace_commitment,
+            &extension_trace_commitments,
+            &composition_trace_commitment,
+            &ood_trace_states,
+            &ood_constraint_evaluations,
+            &trace_queries,
+        )?;
 
-        let base_trace_comitment = Output::<Sha256>::from_iter(base_trace_commitment);
-        public_coin.reseed(&base_trace_comitment.deref());
-        let challenges = air.get_challenges(&mut public_coin);
+        public_coin.reseed(TranscriptLabel::BaseTraceCommitment, &base_trace_commitment);
+        // the shape check ensures commitments are a whole number of digests
+        let base_trace_cap = parse_cap::<D>(&base_trace_commitment).unwrap();
+        let mut challenges = Challenges::default();
+        let mut extension_trace_commitments = extension_trace_commitments.into_iter();
+        let mut extension_rounds = Vec::new();
+        for round in 0..air.num_interaction_rounds() {
+            let round_challenges = air.get_round_challenges(round, public_coin);
+            record_challenges(public_coin, "challenge", round_challenges.iter());
+            challenges.append(round_challenges);
+            let num_columns = air.num_round_extension_columns(round);
+            if num_columns > 0 {
+                // the shape check ensures every round with columns has a commitment
+                let commitment = extension_trace_commitments.next().unwrap();
+                public_coin.reseed(TranscriptLabel::ExtensionTraceCommitment, &commitment);
+                let cap = parse_cap::<D>(&commitment).unwrap();
+                extension_rounds.push((num_columns, cap));
+            }
+        }
         let hints = air.get_hints(&challenges);
 
-        let extension_trace_commitment =
-            extension_trace_commitment.map(|extension_trace_commitment| {
-                let extension_trace_commitment =
-                    Output::<Sha256>::from_iter(extension_trace_commitment);
-                public_coin.reseed(&extension_trace_commitment.deref());
-                extension_trace_commitment
-            });
-
-        let composition_coeffs = air.get_constraint_composition_coeffs(&mut public_coin);
-        let composition_trace_commitment =
-            Output::<Sha256>::from_iter(composition_trace_commitment);
-        public_coin.reseed(&composition_trace_commitment.deref());
-
-        let z = public_coin.draw::<A::Fq>();
-        public_coin.reseed(&ood_trace_states.0);
-        public_coin.reseed(&ood_trace_states.1);
-        let calculated_ood_constraint_evaluation = ood_constraint_evaluation(
-            composition_coeffs,
-            &challenges,
-            &hints,
-            &ood_trace_states.0,
-            &ood_trace_states.1,
-            &air,
-            z,
+        let composition_coeffs = air.get_constraint_composition_coeffs(public_coin);
+        for (alpha, beta) in &composition_coeffs {
+            record_challenges(public_coin, "composition coefficient", [alpha, beta]);
+        }
+        public_coin.reseed(
+            TranscriptLabel::CompositionTraceCommitment,
+            &composition_trace_commitment,
         );
+        let composition_trace_cap = parse_cap::<D>(&composition_trace_commitment).unwrap();
 
-        public_coin.reseed(&ood_constraint_evaluations);
-        let mut acc = A::Fq::one();
-        let provided_ood_constraint_evaluation =
-            ood_constraint_evaluations
-                .iter()
-                .fold(A::Fq::zero(), |mut res, value| {
-                    res += *value * acc;
-                    acc *= z;
-                    res
-                });
-
-        if calculated_ood_constraint_evaluation != provided_ood_constraint_evaluation {
-            return Err(InconsistentOodConstraintEvaluations);
+        let mut zs = Vec::new();
+        for (point, (ood_trace_state, ood_constraint_evaluations)) in ood_trace_states
+            .iter()
+            .zip(&ood_constraint_evaluations)
+            .enumerate()
+        {
+            let z = air.get_ood_point(public_coin);
+            record_challenges(public_coin, "ood point", [z]);
+            for evals in ood_trace_state {
+                public_coin.reseed(TranscriptLabel::OodTraceStates, evals);
+            }
+            let ood_constraint_terms = ood_constraint_terms(
+                composition_coeffs.clone(),
+                &challenges,
+                &hints,
+                ood_trace_state,
+                &air,
+                z,
+            )?;
+            let mut calculated_ood_constraint_evaluation = A::Fq::zero();
+            for (constraint, (evaluation, term)) in ood_constraint_terms.into_iter().enumerate() {
+                if public_coin.is_recording() {
+                    public_coin.record(VerificationEvent::OodConstraint {
+                        point,
+                        constraint,
+                        evaluation: evaluation.to_string(),
+                        term: term.to_string(),
+                    });
+                }
+                calculated_ood_constraint_evaluation += term;
+            }
+
+            public_coin.reseed(
+                TranscriptLabel::OodConstraintEvaluations,
+                ood_constraint_evaluations,
+            );
+            let mut acc = A::Fq::one();
+            let provided_ood_constraint_evaluation =
+                ood_constraint_evaluations
+                    .iter()
+                    .fold(A::Fq::zero(), |mut res, value| {
+                        res += *value * acc;
+                        acc *= z;
+                        res
+                    });
+
+            if calculated_ood_constraint_evaluation != provided_ood_constraint_evaluation {
+                return Err(InconsistentOodConstraintEvaluations);
+            }
+            zs.push(z);
         }
 
-        let deep_coeffs = air.get_deep_composition_coeffs(&mut public_coin);
-        let fri_verifier = FriVerifier::<A::Fq, Sha256>::new(
-            &mut public_coin,
-            options.into_fri_options(),
-            fri_proof,
-            air.trace_len() - 1,
+        let deep_coeffs = zs
+            .iter()
+            .map(|_| {
+                let coeffs = air.get_deep_composition_coeffs(public_coin);
+                let DeepCompositionCoeffs {
+                    base_trace,
+                    extension_trace,
+                    constraints,
+                    degree: (alpha, beta),
+                } = &coeffs;
+                let trace_coeffs = base_trace.iter().chain(extension_trace).flatten();
+                record_challenges(public_coin, "deep trace coefficient", trace_coeffs);
+                record_challenges(public_coin, "deep constraint coefficient", constraints);
+                record_challenges(public_coin, "deep degree coefficient", [alpha, beta]);
+                coeffs
+            })
+            .collect::<Vec<_>>();
+        let ldt_verifier = LowDegreeVerifier::<A::Fq, D>::new(
+            public_coin,
+            &options,
+            low_degree_proof,
+            air.deep_composition_len() - 1,
+            air.ldt_blowup_factor(),
         )?;
 
         if options.grinding_factor != 0 {
-            public_coin.reseed(&pow_nonce);
-            if public_coin.seed_leading_zeros() < options.grinding_factor as u32 {
+            let grinding_hash = options.grinding_hash.unwrap_or(options.hash_function);
+            let proof_of_work = with_digest!(grinding_hash, G => {
+                proof_of_work::<G>(&public_coin.seed, pow_nonce, options.nonce_encoding)
+            });
+            if proof_of_work.unwrap_or(0) < options.grinding_factor as u32 {
                 return Err(FriProofOfWork);
             }
+            public_coin.reseed(TranscriptLabel::PowNonce, &pow_nonce);
         }
 
-        let mut rng = public_coin.draw_rng();
         let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
-        let query_positions = (0..options.num_queries)
-            .map(|_| rng.gen_range(0..lde_domain_size))
-            .collect::<Vec<usize>>();
+        let query_positions = public_coin.draw_positions(options.num_queries, lde_domain_size);
+        record_challenges(public_coin, "query position", &query_positions);
+        let depth = path_len(lde_domain_size, options.merkle_cap_height.into());
 
         let base_trace_rows = trace_queries
             .base_trace_values
@@ -149,82 +510,158 @@ impl<A: Air> Proof<A> {
 
         let composition_trace_rows = trace_queries
             .composition_trace_values
-            .chunks(air.ce_blowup_factor())
+            .chunks(air.num_composition_columns())
             .collect::<Vec<&[A::Fq]>>();
 
         // base trace positions
-        verify_positions::<Sha256>(
-            base_trace_comitment,
+        verify_positions::<D>(
+            public_coin,
+            "base trace",
+            &base_trace_cap,
             &query_positions,
             &base_trace_rows,
             trace_queries.base_trace_proofs,
+            depth,
         )
         .map_err(|_| BaseTraceQueryDoesNotMatchCommitment)?;
 
-        if let Some(extension_trace_commitment) = extension_trace_commitment {
-            // extension trace positions
-            verify_positions::<Sha256>(
-                extension_trace_commitment,
+        // extension trace positions. Each round commits to its own columns.
+        let mut extension_trace_proofs = trace_queries.extension_trace_proofs.into_iter();
+        let mut column_offset = 0;
+        for (num_columns, cap) in extension_rounds {
+            let columns = column_offset..column_offset + num_columns;
+            let rows = extension_trace_rows
+                .iter()
+                .map(|row| &row[columns.clone()])
+                .collect::<Vec<&[A::Fq]>>();
+            let proofs = extension_trace_proofs
+                .by_ref()
+                .take(query_positions.len())
+                .collect();
+            verify_positions::<D>(
+                public_coin,
+                "extension trace",
+                &cap,
                 &query_positions,
-                &extension_trace_rows,
-                trace_queries.extension_trace_proofs,
+                &rows,
+                proofs,
+                depth,
             )
             .map_err(|_| ExtensionTraceQueryDoesNotMatchCommitment)?;
+            column_offset += num_columns;
         }
 
         // composition trace positions
-        verify_positions::<Sha256>(
-            composition_trace_commitment,
+        verify_positions::<D>(
+            public_coin,
+            "composition trace",
+            &composition_trace_cap,
             &query_positions,
             &composition_trace_rows,
             trace_queries.composition_trace_proofs,
+            depth,
         )
         .map_err(|_| CompositionTraceQueryDoesNotMatchCommitment)?;
 
-        let deep_evaluations = deep_composition_evaluations(
-            &air,
-            &query_positions,
-            deep_coeffs,
-            base_trace_rows,
-            extension_trace_rows,
-            composition_trace_rows,
-            z,
-            ood_trace_states,
-            ood_constraint_evaluations,
-        );
+        // the DEEP composition polynomial sums the quotients of every point
+        let mut deep_evaluations = vec![A::Fq::zero(); query_positions.len()];
+        for (((z, deep_coeffs), ood_trace_state), ood_constraint_evaluations) in zs
+            .into_iter()
+            .zip(deep_coeffs)
+            .zip(ood_trace_states)
+            .zip(ood_constraint_evaluations)
+        {
+            let evaluations = deep_composition_evaluations(
+                &air,
+                &query_positions,
+                deep_coeffs,
+                &base_trace_rows,
+                &extension_trace_rows,
+                &composition_trace_rows,
+                z,
+                ood_trace_state,
+                ood_constraint_evaluations,
+            );
+            for (acc, evaluation) in deep_evaluations.iter_mut().zip(evaluations) {
+                *acc += evaluation;
+            }
+        }
 
-        Ok(fri_verifier.verify(&query_positions, &deep_evaluations)?)
+        ldt_verifier.verify(&query_positions, &deep_evaluations)
     }
 }
 
-fn ood_constraint_evaluation<A: Air>(
+/// Records values drawn by the verifier when the public coin is recording
+fn record_challenges<D: Digest>(
+    public_coin: &mut PublicCoin<D>,
+    label: &'static str,
+    values: impl IntoIterator<Item = impl Display>,
+) {
+    if public_coin.is_recording() {
+        for value in values {
+            public_coin.record(VerificationEvent::Challenge(label, value.to_string()));
+        }
+    }
+}
+
+/// Returns the evaluation of each constraint at the out-of-domain point
+/// alongside its term in the constraint composition. The terms sum to the
+/// constraint composition evaluation.
+fn ood_constraint_terms<A: Air>(
     mut composition_coefficients: Vec<(A::Fq, A::Fq)>,
     challenges: &Challenges<A::Fq>,
     hints: &Hints<A::Fq>,
-    curr_trace_evals: &[A::Fq],
-    next_trace_evals: &[A::Fq],
+    ood_frame: &[Vec<A::Fq>],
     air: &A,
     x: A::Fq,
-) -> A::Fq {
+) -> Result<Vec<(A::Fq, A::Fq)>, VerificationError> {
     // TODO: refactor constraint and their divisors so they are grouped together
-    let boundary_constraints = air.boundary_constraints();
-    let transition_constraints = air.transition_constraints();
-    let terminal_constraints = air.terminal_constraints();
+    let boundary_constraints = air.all_boundary_constraints();
+    let transition_constraints = air.all_transition_constraints();
+    let terminal_constraints = air.all_terminal_constraints();
 
     let boundary_divisor_degree = 1;
-    let transition_divisor_degree = air.trace_len() - 1;
     let terminal_divisor_degree = 1;
 
     let trace_domain = air.trace_domain();
+    // periodic, linear and assertion columns aren't sent by the prover. The
+    // verifier evaluates them itself and appends them to the out-of-domain
+    // trace states.
+    let g = A::Fq::from(trace_domain.group_gen);
+    let mut frame_x = x;
+    let mut frame = Vec::new();
+    for evals in ood_frame {
+        frame.push(
+            [
+                evals,
+                &air.evaluate_periodic_columns_at(frame_x),
+                &air.evaluate_linear_columns_at(frame_x),
+                &air.evaluate_assertion_columns_at(frame_x),
+            ]
+            .concat(),
+        );
+        frame_x *= g;
+    }
+    let frame = frame.iter().map(Vec::as_slice).collect::<Vec<_>>();
     let first_trace_x = A::Fp::one();
     let last_trace_x = trace_domain.group_gen_inv;
     // TODO docs
-    let boundary_divisor = (x - A::Fq::from(first_trace_x)).inverse().unwrap();
-    let terminal_divisor = (x - A::Fq::from(last_trace_x)).inverse().unwrap();
-    let transition_divisor = (x - A::Fq::from(last_trace_x))
-        * evaluate_vanishing_polynomial(&trace_domain, x)
-            .inverse()
-            .unwrap();
+    let boundary_divisor = (x - A::Fq::from(first_trace_x))
+        .inverse()
+        .ok_or(VerificationError::InvalidOodPoint)?;
+    let terminal_divisor = (x - A::Fq::from(last_trace_x))
+        .inverse()
+        .ok_or(VerificationError::InvalidOodPoint)?;
+    let vanishing_inv = evaluate_vanishing_polynomial(&trace_domain, x)
+        .inverse()
+        .ok_or(VerificationError::InvalidOodPoint)?;
+    // transition constraints don't apply to their last `num_exempt_rows` rows
+    let transition_divisor = |num_exempt_rows: usize| {
+        let excluded_rows = (1..=num_exempt_rows)
+            .map(|i| x - A::Fq::from(trace_domain.group_gen_inv.pow([i as u64])))
+            .product::<A::Fq>();
+        excluded_rows * vanishing_inv
+    };
 
     // TODO: honestly I hate this
     let boundary_iter = boundary_constraints
@@ -232,67 +669,327 @@ fn ood_constraint_evaluation<A: Air>(
         .map(|constraint| (constraint, boundary_divisor, boundary_divisor_degree));
     let transition_iter = transition_constraints
         .iter()
-        .map(|constraint| (constraint, transition_divisor, transition_divisor_degree));
+        .map(|(constraint, num_exempt_rows)| {
+            (
+                constraint,
+                transition_divisor(*num_exempt_rows),
+                air.trace_len() - num_exempt_rows,
+            )
+        });
     let terminal_iter = terminal_constraints
         .iter()
         .map(|constraint| (constraint, terminal_divisor, terminal_divisor_degree));
 
-    let mut result = A::Fq::zero();
+    let constraints = boundary_constraints
+        .iter()
+        .chain(
+            transition_constraints
+                .iter()
+                .map(|(constraint, _)| constraint),
+        )
+        .chain(terminal_constraints.iter());
+    let evaluations = ConstraintKernel::new(constraints, challenges, hints).evaluate_frame(&frame);
+
+    let mut terms = Vec::new();
     let trace_degree = air.trace_len() - 1;
     let composition_degree = air.composition_degree();
-    for (constraint, divisor, divisor_degree) in
-        boundary_iter.chain(transition_iter).chain(terminal_iter)
+    for (index, ((constraint, divisor, divisor_degree), evaluation)) in boundary_iter
+        .chain(transition_iter)
+        .chain(terminal_iter)
+        .zip(evaluations)
+        .enumerate()
     {
-        // TODO: proper errors
-        let evaluation = constraint.evaluate(challenges, hints, curr_trace_evals, next_trace_evals);
         // TODO: consider better name here. Multiplying by divisor seems kinda retarded
         let quotient = evaluation * divisor;
 
         // TODO: don't allow degree 0 constraints
-        let evaluation_degree = constraint.degree() * trace_degree - divisor_degree;
-        assert!(evaluation_degree <= composition_degree);
+        let evaluation_degree = (constraint.degree() * trace_degree).saturating_sub(divisor_degree);
+        if evaluation_degree > composition_degree {
+            return Err(VerificationError::ConstraintDegreeTooHigh {
+                index,
+                degree: evaluation_degree,
+                max: composition_degree,
+            });
+        }
         let degree_adjustment = (composition_degree - evaluation_degree) as u64;
 
         let (alpha, beta) = composition_coefficients.pop().unwrap();
-        result += quotient * (alpha * x.pow([degree_adjustment]) + beta)
+        terms.push((
+            evaluation,
+            quotient * (alpha * x.pow([degree_adjustment]) + beta),
+        ));
+    }
+
+    Ok(terms)
+}
+
+/// Checks the header, proof options and trace info at the start of a proof.
+/// The header must identify this version of the crate and the AIR `A`.
+fn validate_header<A: Air>(
+    header: &ProofHeader,
+    options: &ProofOptions,
+    trace_info: &TraceInfo,
+) -> Result<(), VerificationError> {
+    use VerificationError::*;
+    let expected = ProofHeader::new::<A>(options.hash_function);
+    if header.version != expected.version {
+        return Err(VersionMismatch(header.version, expected.version));
+    }
+    if header.field_id != expected.field_id {
+        return Err(FieldMismatch(header.field_id, expected.field_id));
+    }
+    if header.hash_function != options.hash_function {
+        return Err(HashFunctionMismatch(
+            header.hash_function,
+            options.hash_function,
+        ));
+    }
+    if header.air_id != expected.air_id {
+        return Err(AirMismatch(header.air_id, expected.air_id));
     }
 
-    result
+    let TraceInfo {
+        num_base_columns,
+        num_extension_columns,
+        trace_len,
+        ref meta,
+        ..
+    } = *trace_info;
+    if !trace_len.is_power_of_two()
+        || trace_len < TraceInfo::MIN_TRACE_LENGTH
+        || trace_len > options.max_trace_len::<A::Fp>()
+    {
+        return Err(InvalidTraceLength(trace_len));
+    }
+    if meta.len() > TraceInfo::MAX_META_BYTES {
+        return Err(InvalidTraceMeta(meta.len()));
+    }
+    if num_base_columns == 0
+        || num_base_columns.saturating_add(num_extension_columns) > TraceInfo::MAX_TRACE_WIDTH
+    {
+        return Err(InvalidTraceWidth(num_base_columns, num_extension_columns));
+    }
+    validate_options(options, trace_len)
+}
+
+/// Checks the proof options are ones a prover could have used. Options come
+/// from the proof so they must be checked before they size any domain or loop.
+fn validate_options(options: &ProofOptions, trace_len: usize) -> Result<(), VerificationError> {
+    use VerificationError::*;
+    let ProofOptions {
+        num_queries,
+        lde_blowup_factor,
+        grinding_factor,
+        fri_folding_factor,
+        fri_max_remainder_size,
+        num_ood_points,
+        merkle_cap_height,
+        fri_folding_schedule,
+        ..
+    } = *options;
+    if !lde_blowup_factor.is_power_of_two()
+        || !(ProofOptions::MIN_BLOWUP_FACTOR..=ProofOptions::MAX_BLOWUP_FACTOR)
+            .contains(&lde_blowup_factor)
+    {
+        return Err(InvalidBlowupFactor(lde_blowup_factor));
+    }
+    if !(ProofOptions::MIN_NUM_QUERIES..=ProofOptions::MAX_NUM_QUERIES).contains(&num_queries) {
+        return Err(InvalidNumQueries(num_queries));
+    }
+    if grinding_factor > ProofOptions::MAX_GRINDING_FACTOR {
+        return Err(InvalidGrindingFactor(grinding_factor));
+    }
+    for &folding_factor in [fri_folding_factor]
+        .iter()
+        .chain(fri_folding_schedule.factors())
+    {
+        if !matches!(folding_factor, 2 | 4 | 8 | 16) {
+            return Err(InvalidFoldingFactor(folding_factor));
+        }
+    }
+    let lde_domain_size = trace_len.saturating_mul(lde_blowup_factor.into());
+    if fri_max_remainder_size < fri_folding_factor
+        || usize::from(fri_max_remainder_size) > lde_domain_size
+    {
+        return Err(InvalidRemainderSize(
+            fri_max_remainder_size,
+            lde_domain_size,
+        ));
+    }
+    if num_ood_points == 0 || num_ood_points > ProofOptions::MAX_NUM_OOD_POINTS {
+        return Err(InvalidNumOodPoints(num_ood_points));
+    }
+    if merkle_cap_height > ProofOptions::MAX_MERKLE_CAP_HEIGHT {
+        return Err(InvalidMerkleCapHeight(merkle_cap_height));
+    }
+    Ok(())
+}
+
+/// Checks the lengths of all values in a proof match what the AIR expects.
+/// Values are later zipped and indexed so a malformed proof could otherwise be
+/// partially checked or panic the verifier. The FRI and STIR verifiers check
+/// the shape of the low-degree proof when they're created.
+#[allow(clippy::too_many_arguments)]
+fn validate_shape<A: Air, D: Digest>(
+    air: &A,
+    base_trace_commitment: &[u8],
+    extension_trace_commitments: &[Vec<u8>],
+    composition_trace_commitment: &[u8],
+    ood_trace_states: &[Vec<Vec<A::Fq>>],
+    ood_constraint_evaluations: &[Vec<A::Fq>],
+    trace_queries: &Queries<A::Fp, A::Fq>,
+) -> Result<(), VerificationError> {
+    use VerificationError::*;
+    // trees have a leaf for each row of the LDE
+    let lde_domain_size = air.trace_len() * air.lde_blowup_factor();
+    let cap_height = air.options().merkle_cap_height.into();
+    let cap_size = cap_len(lde_domain_size, cap_height) * <D as OutputSizeUser>::output_size();
+    let depth = path_len(lde_domain_size, cap_height);
+    let check_commitment = |commitment: &[u8]| {
+        if commitment.len() == cap_size {
+            Ok(())
+        } else {
+            Err(InvalidCommitmentLength(cap_size, commitment.len()))
+        }
+    };
+    check_commitment(base_trace_commitment)?;
+    check_commitment(composition_trace_commitment)?;
+    let num_base_columns = air.trace_info().num_base_columns;
+    let num_extension_columns = air.trace_info().num_extension_columns;
+    // one commitment for each interaction round with extension columns
+    let round_widths = (0..air.num_interaction_rounds())
+        .map(|round| air.num_round_extension_columns(round))
+        .filter(|&num_columns| num_columns > 0)
+        .collect::<Vec<usize>>();
+    if round_widths.iter().sum::<usize>() != num_extension_columns
+        || round_widths.len() != extension_trace_commitments.len()
+    {
+        return Err(ExtensionTraceCommitmentMismatch);
+    }
+    for commitment in extension_trace_commitments {
+        check_commitment(commitment)?;
+    }
+
+    let num_ood_points = air.options().num_ood_points as usize;
+    if ood_trace_states.len() != num_ood_points
+        || ood_constraint_evaluations.len() != num_ood_points
+    {
+        return Err(NumOodPoints(
+            num_ood_points,
+            ood_trace_states.len(),
+            ood_constraint_evaluations.len(),
+        ));
+    }
+    let trace_width = num_base_columns + num_extension_columns;
+    let frame_size = air.max_row_offset() + 1;
+    for ood_frame in ood_trace_states {
+        if ood_frame.len() != frame_size {
+            return Err(OodFrameSize(frame_size, ood_frame.len()));
+        }
+        for ood_trace_state in ood_frame {
+            if ood_trace_state.len() != trace_width {
+                return Err(OodTraceStatesLength(trace_width, ood_trace_state.len()));
+            }
+        }
+    }
+    let num_composition_columns = air.num_composition_columns();
+    for evaluations in ood_constraint_evaluations {
+        if evaluations.len() != num_composition_columns {
+            return Err(OodConstraintEvaluationsLength(
+                num_composition_columns,
+                evaluations.len(),
+            ));
+        }
+    }
+
+    let num_queries = air.options().num_queries as usize;
+    let Queries {
+        base_trace_values,
+        extension_trace_values,
+        composition_trace_values,
+        base_trace_proofs,
+        extension_trace_proofs,
+        composition_trace_proofs,
+    } = trace_queries;
+    let expected = num_queries * num_base_columns;
+    if base_trace_values.len() != expected {
+        return Err(BaseTraceQueriesLength(expected, base_trace_values.len()));
+    }
+    let expected = num_queries * num_extension_columns;
+    if extension_trace_values.len() != expected {
+        return Err(ExtensionTraceQueriesLength(
+            expected,
+            extension_trace_values.len(),
+        ));
+    }
+    let expected = num_queries * num_composition_columns;
+    if composition_trace_values.len() != expected {
+        return Err(CompositionTraceQueriesLength(
+            expected,
+            composition_trace_values.len(),
+        ));
+    }
+
+    let num_extension_proofs = num_queries * round_widths.len();
+    for (proofs, expected) in [
+        (base_trace_proofs, num_queries),
+        (extension_trace_proofs, num_extension_proofs),
+        (composition_trace_proofs, num_queries),
+    ] {
+        if proofs.len() != expected {
+            return Err(MerkleProofCount(expected, proofs.len()));
+        }
+        // paths of any other length could open inner nodes as leaves
+        if !proofs.iter().all(|proof| proof.has_depth::<D>(depth)) {
+            return Err(MerkleProofLength(depth));
+        }
+    }
+
+    Ok(())
 }
 
 fn verify_positions<D: Digest>(
-    commitment: Output<D>,
+    public_coin: &mut PublicCoin<impl Digest>,
+    commitment_name: &'static str,
+    cap: &[Output<D>],
     positions: &[usize],
-    rows: &[&[impl CanonicalSerialize]],
+    rows: &[&[impl Field]],
     proofs: Vec<MerkleProof>,
+    depth: usize,
 ) -> Result<(), MerkleTreeError> {
-    for ((position, proof), row) in positions.iter().zip(proofs).zip(rows) {
-        let proof = proof.parse::<D>();
-        let expected_leaf = &proof[0];
-        let mut row_bytes = Vec::with_capacity(row.compressed_size());
-        row.serialize_compressed(&mut row_bytes).unwrap();
-        let actual_leaf = D::new_with_prefix(&row_bytes).finalize();
-
-        if *expected_leaf != actual_leaf {
-            return Err(MerkleTreeError::InvalidProof);
-        }
-
-        MerkleTree::<D>::verify(&commitment, &proof, *position)?;
+    for ((&position, proof), row) in positions.iter().zip(proofs).zip(rows) {
+        let result = verify_position::<D>(cap, position, row, proof, depth);
+        public_coin.record(VerificationEvent::QueryCheck {
+            commitment: commitment_name,
+            position,
+            passed: result.is_ok(),
+        });
+        result?;
     }
 
     Ok(())
 }
 
+fn verify_position<D: Digest>(
+    cap: &[Output<D>],
+    position: usize,
+    row: &[impl Field],
+    proof: MerkleProof,
+    depth: usize,
+) -> Result<(), MerkleTreeError> {
+    proof.verify_leaf_with_cap::<D, _>(cap, row, position, depth)
+}
+
 #[allow(clippy::too_many_arguments)]
 fn deep_composition_evaluations<A: Air>(
     air: &A,
     query_positions: &[usize],
     composition_coeffs: DeepCompositionCoeffs<A::Fq>,
-    base_trace_rows: Vec<&[A::Fp]>,
-    extension_trace_rows: Vec<&[A::Fq]>,
-    composition_trace_rows: Vec<&[A::Fq]>,
+    base_trace_rows: &[&[A::Fp]],
+    extension_trace_rows: &[&[A::Fq]],
+    composition_trace_rows: &[&[A::Fq]],
     z: A::Fq,
-    ood_trace_states: (Vec<A::Fq>, Vec<A::Fq>),
+    ood_frame: Vec<Vec<A::Fq>>,
     ood_constraint_evaluations: Vec<A::Fq>,
 ) -> Vec<A::Fq> {
     let trace_domain = air.trace_domain();
@@ -304,34 +1001,80 @@ fn deep_composition_evaluations<A: Air>(
 
     let mut evals = vec![A::Fq::zero(); query_positions.len()];
 
+    // points of the evaluation frame i.e. `z * g^k`
+    let g = A::Fq::from(trace_domain.group_gen());
+    let frame_zs = (0..ood_frame.len())
+        .scan(z, |frame_z, _| {
+            let curr = *frame_z;
+            *frame_z *= g;
+            Some(curr)
+        })
+        .collect::<Vec<A::Fq>>();
+
+    // `1 / (x - z * g^k)` for every query and frame point. The same
+    // denominators are shared by every trace column.
+    let mut frame_denominators = xs
+        .iter()
+        .flat_map(|&x| {
+            frame_zs
+                .iter()
+                .map(move |&frame_z| A::Fq::from(x) - frame_z)
+        })
+        .collect::<Vec<A::Fq>>();
+    batch_inverse(&mut frame_denominators);
+    let frame_denominators = frame_denominators
+        .chunks(frame_zs.len().max(1))
+        .collect::<Vec<&[A::Fq]>>();
+
     // add base trace
-    let next_z = z * &trace_domain.group_gen();
-    for ((&x, row), eval) in xs.iter().zip(base_trace_rows).zip(&mut evals) {
+    for ((row, denominators), eval) in base_trace_rows
+        .iter()
+        .zip(&frame_denominators)
+        .zip(&mut evals)
+    {
         for (i, &val) in row.iter().enumerate() {
-            let (alpha, beta, _) = composition_coeffs.base_trace[i];
-            let t1 = (A::Fq::from(val) - ood_trace_states.0[i]) / (A::Fq::from(x) - z);
-            let t2 = (A::Fq::from(val) - ood_trace_states.1[i]) / (A::Fq::from(x) - next_z);
-            *eval += t1 * alpha + t2 * beta;
+            let coeffs = &composition_coeffs.base_trace[i];
+            for ((ood_evals, &denominator), &coeff) in
+                ood_frame.iter().zip(*denominators).zip(coeffs)
+            {
+                *eval += (A::Fq::from(val) - ood_evals[i]) * denominator * coeff;
+            }
         }
     }
 
     // add extension trace
     let num_base_columns = air.trace_info().num_base_columns;
-    for ((&x, row), eval) in xs.iter().zip(extension_trace_rows).zip(&mut evals) {
+    for ((row, denominators), eval) in extension_trace_rows
+        .iter()
+        .zip(&frame_denominators)
+        .zip(&mut evals)
+    {
         for (i, &val) in row.iter().enumerate() {
-            let (alpha, beta, _) = composition_coeffs.extension_trace[i];
-            let t1 = (val - ood_trace_states.0[num_base_columns + i]) / (A::Fq::from(x) - z);
-            let t2 = (val - ood_trace_states.1[num_base_columns + i]) / (A::Fq::from(x) - next_z);
-            *eval += t1 * alpha + t2 * beta;
+            let coeffs = &composition_coeffs.extension_trace[i];
+            for ((ood_evals, &denominator), &coeff) in
+                ood_frame.iter().zip(*denominators).zip(coeffs)
+            {
+                let ood_eval = ood_evals[num_base_columns + i];
+                *eval += (val - ood_eval) * denominator * coeff;
+            }
         }
     }
 
     // add composition trace
-    let z_n = z.pow([air.ce_blowup_factor() as u64]);
-    for ((&x, row), eval) in xs.iter().zip(composition_trace_rows).zip(&mut evals) {
+    let z_n = z.pow([air.num_composition_columns() as u64]);
+    let mut composition_denominators = xs
+        .iter()
+        .map(|&x| A::Fq::from(x) - z_n)
+        .collect::<Vec<A::Fq>>();
+    batch_inverse(&mut composition_denominators);
+    for ((row, &denominator), eval) in composition_trace_rows
+        .iter()
+        .zip(&composition_denominators)
+        .zip(&mut evals)
+    {
         for (i, &value) in row.iter().enumerate() {
             let alpha = composition_coeffs.constraints[i];
-            *eval += alpha * (value - ood_constraint_evaluations[i]) / (A::Fq::from(x) - z_n);
+            *eval += alpha * (value - ood_constraint_evaluations[i]) * denominator;
         }
     }
 
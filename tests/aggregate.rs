@@ -0,0 +1,64 @@
+use ark_ff::One;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::aggregate::AggregateProver;
+use ministark::aggregate::AggregateTrace;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::Prover;
+use ministark::Trace;
+
+type Aggregate = AggregateProver<FibonacciProver, 4>;
+
+fn aggregate_trace() -> AggregateTrace<FibonacciTrace, 4> {
+    AggregateTrace::new([(); 4].map(|_| FibonacciTrace::new(TestVector::TRACE_LEN)))
+}
+
+#[test]
+fn aggregate_trace_interleaves_rows() {
+    let trace = aggregate_trace();
+
+    assert_eq!(trace.info().num_base_columns, 2);
+    assert_eq!(trace.len(), 4 * TestVector::TRACE_LEN);
+    assert_eq!(
+        trace.base_columns()[1][5 * 4 + 2],
+        trace.traces()[2].base_columns()[1][5]
+    );
+}
+
+#[test]
+fn aggregate_proof_verifies() {
+    let prover = Aggregate::new(TestVector::options());
+    let proof = prover.generate_proof(aggregate_trace()).unwrap();
+
+    let public_inputs = proof.public_inputs.clone().unwrap();
+    assert_eq!(public_inputs.len(), 4);
+    assert!(proof.verify().is_ok());
+}
+
+#[test]
+fn aggregate_proof_binds_every_execution() {
+    let prover = Aggregate::new(TestVector::options());
+    let proof = prover.generate_proof(aggregate_trace()).unwrap();
+
+    for k in 0..4 {
+        let mut public_inputs = proof.public_inputs.clone().unwrap();
+        public_inputs[k] += Fp::one();
+        assert!(proof
+            .clone()
+            .verify_with_public_inputs(public_inputs)
+            .is_err());
+    }
+}
+
+#[test]
+fn aggregate_proof_of_mismatched_executions_is_rejected() {
+    let prover = Aggregate::new(TestVector::options());
+    let proof = prover.generate_proof(aggregate_trace()).unwrap();
+
+    // executions of different lengths end with different Fibonacci numbers
+    let short_trace = FibonacciTrace::new(TestVector::TRACE_LEN / 2);
+    let mut public_inputs = proof.public_inputs.clone().unwrap();
+    public_inputs[3] = *short_trace.base_columns()[1].last().unwrap();
+    assert!(proof.verify_with_public_inputs(public_inputs).is_err());
+}
@@ -0,0 +1,199 @@
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::assertion::Assertion;
+use ministark::assertion::AssertionConstraints;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+const TRACE_LEN: usize = 16;
+
+/// Column `0` is the Fibonacci sequence and column `1` is the step mod four
+fn trace_rows() -> Vec<Vec<Fp>> {
+    let (mut a, mut b) = (Fp::one(), Fp::one());
+    (0..TRACE_LEN)
+        .map(|step| {
+            let row = vec![a, Fp::from((step % 4) as u64)];
+            (a, b) = (b, a + b);
+            row
+        })
+        .collect()
+}
+
+fn assertions(rows: &[Vec<Fp>]) -> Vec<Assertion<Fp>> {
+    let even_fibs = rows.iter().step_by(2).map(|row| row[0]).collect();
+    vec![
+        Assertion::single(0, 0, Fp::one()),
+        Assertion::single(0, 5, rows[5][0]),
+        Assertion::single(0, TRACE_LEN - 1, rows[TRACE_LEN - 1][0]),
+        Assertion::periodic(1, 3, 4, Fp::from(3u8)),
+        Assertion::periodic(1, 0, 4, Fp::zero()),
+        Assertion::sequence(0, 0, 2, even_fibs),
+    ]
+}
+
+/// Returns true if all constraints are satisfied by the trace rows
+fn is_satisfied(constraints: &AssertionConstraints<Fp, Fp>, rows: &[Vec<Fp>]) -> bool {
+    // append periodic columns to each row
+    let rows = rows
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row = row.clone();
+            for column in &constraints.periodic_columns {
+                row.push(column[i % column.len()]);
+            }
+            row
+        })
+        .collect::<Vec<Vec<Fp>>>();
+
+    let first_row = rows.first().unwrap();
+    let last_row = rows.last().unwrap();
+    constraints
+        .boundary_constraints
+        .iter()
+        .all(|constraint| constraint.evaluate(&[], &[], first_row, &[]).is_zero())
+        && constraints
+            .terminal_constraints
+            .iter()
+            .all(|constraint| constraint.evaluate(&[], &[], last_row, &[]).is_zero())
+        && rows.windows(2).all(|rows| {
+            constraints
+                .transition_constraints
+                .iter()
+                .all(|constraint| constraint.evaluate(&[], &[], &rows[0], &rows[1]).is_zero())
+        })
+}
+
+#[test]
+fn assertions_are_satisfied_by_valid_trace() {
+    let rows = trace_rows();
+    let constraints = AssertionConstraints::new(&assertions(&rows), TRACE_LEN, 2);
+
+    assert!(is_satisfied(&constraints, &rows));
+    assert_eq!(constraints.boundary_constraints.len(), 1);
+    // single at the last step, periodic at `3 + 4k` and sequence at `2k`
+    // never include the last step
+    assert_eq!(constraints.terminal_constraints.len(), 2);
+    // selectors for steps 5, 3 + 4k, 4k, 2k and the sequence's values
+    assert_eq!(constraints.periodic_columns.len(), 5);
+}
+
+#[test]
+fn assertions_reject_invalid_trace() {
+    let rows = trace_rows();
+    let constraints = AssertionConstraints::new(&assertions(&rows), TRACE_LEN, 2);
+
+    for (step, column) in [(0, 0), (5, 0), (7, 1), (TRACE_LEN - 1, 1), (8, 1), (6, 0)] {
+        let mut invalid_rows = rows.clone();
+        invalid_rows[step][column] += Fp::one();
+        assert!(
+            !is_satisfied(&constraints, &invalid_rows),
+            "changing column {column} at step {step} went unnoticed"
+        );
+    }
+}
+
+/// Fibonacci AIR that ties the first and last values to its public inputs
+/// with assertions rather than boundary and terminal constraints
+struct AssertedFibAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    result: Fp,
+    transition_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for AssertedFibAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, result: Fp, options: ProofOptions) -> Self {
+        let (a, b) = (0, 1);
+        AssertedFibAir {
+            options,
+            trace_info,
+            result,
+            transition_constraints: vec![a.next() - b.curr(), b.next() - a.curr() - b.curr()],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Fp {
+        &self.result
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        &self.transition_constraints
+    }
+
+    fn assertions(&self) -> Vec<Assertion<Fp>> {
+        let last_step = self.trace_len() - 1;
+        vec![
+            Assertion::single(0, 0, Fp::one()),
+            Assertion::single(1, 0, Fp::one()),
+            // a[5] is the sixth Fibonacci number
+            Assertion::single(0, 5, Fp::from(8u8)),
+            Assertion::single(1, last_step, self.result),
+        ]
+    }
+}
+
+struct AssertedFibProver(ProofOptions);
+
+impl Prover for AssertedFibProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = AssertedFibAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        AssertedFibProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap()
+    }
+}
+
+#[test]
+fn air_assertions_are_enforced() {
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let mut proof = AssertedFibProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+    proof.clone().verify().unwrap();
+
+    *proof.public_inputs.as_mut().unwrap() += Fp::one();
+
+    assert!(proof.verify().is_err());
+}
+
+#[test]
+fn air_assertions_must_fit_the_trace() {
+    let trace_len = TraceInfo::MIN_TRACE_LENGTH;
+    let trace_info = TraceInfo::new(2, 0, trace_len, None);
+
+    assert!(Assertion::single(1, trace_len - 1, Fp::one()).fits_trace(&trace_info));
+    assert!(!Assertion::single(2, 0, Fp::one()).fits_trace(&trace_info));
+    assert!(!Assertion::single(0, trace_len, Fp::one()).fits_trace(&trace_info));
+    assert!(!Assertion::sequence(0, 0, 2, vec![Fp::one(); 4]).fits_trace(&trace_info));
+}
@@ -0,0 +1,75 @@
+use ark_ff::Zero;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::gadgets::bits::pack;
+use ministark::gadgets::bits::rotate_right;
+use ministark::gadgets::bits::shift_right;
+use ministark::gadgets::bits::xor;
+use ministark::gadgets::bits::BitColumns;
+use ministark::Column;
+use ministark::Constraint;
+
+const BITS: BitColumns = BitColumns::new(1, 8);
+
+fn evaluate(constraint: &Constraint<Fp>, row: &[Fp]) -> Fp {
+    constraint.evaluate(&[], &[], row, &[])
+}
+
+fn row(value: u64) -> Vec<Fp> {
+    let mut row = vec![Fp::from(value); 9];
+    BITS.write(&mut row, value);
+    row
+}
+
+#[test]
+fn bits_range_check_their_value() {
+    let row = row(0b1011_0010);
+    let constraints = BITS.range_constraints::<Fp>(0);
+
+    assert_eq!(8, constraints.len());
+    assert!(constraints.iter().all(|c| evaluate(c, &row).is_zero()));
+    assert!(evaluate(&BITS.range_check(0usize.curr(), 0), &row).is_zero());
+}
+
+#[test]
+fn values_out_of_range_are_rejected() {
+    // 256 can only be packed from eight cells if one of them isn't a bit
+    let mut row = row(0);
+    row[0] = Fp::from(256u64);
+    row[1] = Fp::from(256u64);
+    let constraints = BITS.range_constraints::<Fp>(0);
+
+    assert!(evaluate(&BITS.range_check(0usize.curr(), 0), &row).is_zero());
+    assert!(!constraints.iter().all(|c| evaluate(c, &row).is_zero()));
+}
+
+#[test]
+#[should_panic]
+fn writing_a_value_that_does_not_fit_panics() {
+    row(256);
+}
+
+#[test]
+fn bitwise_operations_match_integers() {
+    let (a, b) = (0b1011_0010u64, 0b0110_1001u64);
+    let mut row = row(a);
+    let b_bits = BitColumns::new(9, 8);
+    row.resize(17, Fp::zero());
+    b_bits.write(&mut row, b);
+    let (a_bits, b_bits) = (BITS.bits::<Fp>(0), b_bits.bits::<Fp>(0));
+    let a_xor_b = a_bits
+        .iter()
+        .zip(&b_bits)
+        .map(|(a, b)| xor(a, b))
+        .collect::<Vec<_>>();
+
+    assert_eq!(Fp::from(a ^ b), evaluate(&pack(&a_xor_b), &row));
+    let rotated = (a as u8).rotate_right(3) as u64;
+    assert_eq!(
+        Fp::from(rotated),
+        evaluate(&pack(&rotate_right(&a_bits, 3)), &row)
+    );
+    assert_eq!(
+        Fp::from(a >> 3),
+        evaluate(&pack(&shift_right(&a_bits, 3)), &row)
+    );
+}
@@ -0,0 +1,119 @@
+#![feature(allocator_api, array_windows)]
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_std::test_rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::gadgets::bitwise;
+use ministark::gadgets::bitwise::BitwiseColumns;
+use ministark::gadgets::bitwise::BitwiseOp;
+use ministark::gadgets::bitwise::RequestColumns;
+use ministark::Constraint;
+use ministark::Matrix;
+
+const NUM_REQUEST_COLUMNS: usize = 5;
+
+const NUM_BASE_COLUMNS: usize = NUM_REQUEST_COLUMNS + bitwise::NUM_BASE_COLUMNS;
+
+const COLUMNS: BitwiseColumns = BitwiseColumns {
+    requests: RequestColumns {
+        is_request: 0,
+        op: 1,
+        a: 2,
+        b: 3,
+        result: 4,
+    },
+    base_offset: NUM_REQUEST_COLUMNS,
+    extension_offset: NUM_BASE_COLUMNS,
+    periodic_offset: NUM_BASE_COLUMNS + bitwise::NUM_EXTENSION_COLUMNS,
+    challenge_offset: 0,
+};
+
+const TRACE_LEN: usize = 4 * bitwise::CYCLE_LENGTH;
+
+/// Columns of a table requesting `(op, a, b, result)` on its first rows
+fn request_columns(requests: &[(BitwiseOp, u32, u32, u32)]) -> Matrix<Fp> {
+    let mut columns = (0..NUM_REQUEST_COLUMNS)
+        .map(|_| Vec::with_capacity_in(TRACE_LEN, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for i in 0..TRACE_LEN {
+        let row = match requests.get(i) {
+            Some(&(op, a, b, result)) => [
+                Fp::one(),
+                Fp::from(op as u64),
+                Fp::from(a),
+                Fp::from(b),
+                Fp::from(result),
+            ],
+            None => [Fp::zero(); NUM_REQUEST_COLUMNS],
+        };
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    Matrix::new(columns)
+}
+
+fn is_satisfied(requests: &[(BitwiseOp, u32, u32, u32)]) -> bool {
+    let challenges = Challenges::<Fq3>::new(&mut test_rng(), bitwise::NUM_CHALLENGES);
+    let operations = requests
+        .iter()
+        .map(|&(op, a, b, _)| (op, a, b))
+        .collect::<Vec<_>>();
+    let base_columns = Matrix::join(vec![
+        request_columns(requests),
+        bitwise::gen_base_columns(&operations, TRACE_LEN),
+    ]);
+    let extension_columns = bitwise::gen_extension_columns(COLUMNS, &base_columns, &challenges);
+    let periodic_columns = COLUMNS.periodic_columns::<Fp>();
+    let rows = base_columns
+        .rows()
+        .into_iter()
+        .zip(extension_columns.rows())
+        .enumerate()
+        .map(|(i, (base_row, extension_row))| {
+            let periodic_row = periodic_columns
+                .iter()
+                .map(|column| column[i % bitwise::CYCLE_LENGTH]);
+            base_row
+                .into_iter()
+                .map(Fq3::from)
+                .chain(extension_row)
+                .chain(periodic_row.map(Fq3::from))
+                .collect::<Vec<Fq3>>()
+        })
+        .collect::<Vec<_>>();
+
+    let boundary: Vec<Constraint<Fq3>> = COLUMNS.boundary_constraints();
+    let transition: Vec<Constraint<Fq3>> = COLUMNS.transition_constraints();
+    let terminal: Vec<Constraint<Fq3>> = COLUMNS.terminal_constraints();
+    let evaluates_to_zero = |constraints: &[Constraint<Fq3>], curr: &[Fq3], next: &[Fq3]| {
+        constraints
+            .iter()
+            .all(|c| c.evaluate(&challenges, &[], curr, next).is_zero())
+    };
+    evaluates_to_zero(&boundary, &rows[0], &[])
+        && rows
+            .array_windows()
+            .all(|[curr, next]| evaluates_to_zero(&transition, curr, next))
+        && evaluates_to_zero(&terminal, &rows[TRACE_LEN - 1], &[])
+}
+
+#[test]
+fn bitwise_operations_satisfy_constraints() {
+    let (a, b) = (0xDEADBEEF, 0x0F0F1234);
+    let requests =
+        [BitwiseOp::And, BitwiseOp::Xor, BitwiseOp::Or].map(|op| (op, a, b, op.apply(a, b)));
+
+    assert!(is_satisfied(&requests));
+}
+
+#[test]
+fn request_with_wrong_result_fails() {
+    let (a, b) = (0xDEADBEEF, 0x0F0F1234);
+    let requests = [(BitwiseOp::Xor, a, b, a | b)];
+
+    assert!(!is_satisfied(&requests));
+}
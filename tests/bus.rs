@@ -0,0 +1,92 @@
+#![feature(allocator_api, array_windows)]
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_std::test_rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::bus::Bus;
+use ministark::bus::Interaction;
+use ministark::challenges::Challenges;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+
+const TRACE_LEN: usize = 8;
+
+/// Two tables side by side. The first table sends `(x, x^2)` on rows where
+/// its flag is set and the second table receives `(y, y_squared)` on every
+/// row.
+fn bus() -> Bus<Fq3> {
+    let x = 1.curr::<Fq3>();
+    Bus::new(4, 0)
+        .with_send(Interaction::new(0.curr(), vec![x.clone(), &x * &x]))
+        .with_receive(Interaction::new(
+            Constraint::from(Fq3::one()),
+            vec![2.curr(), 3.curr()],
+        ))
+}
+
+fn base_columns(sent: &[u64], received: &[(u64, u64)]) -> Matrix<Fp> {
+    let mut columns = (0..4)
+        .map(|_| Vec::with_capacity_in(TRACE_LEN, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for i in 0..TRACE_LEN {
+        let (flag, x) = match sent.get(i) {
+            Some(&x) => (Fp::one(), Fp::from(x)),
+            None => (Fp::zero(), Fp::zero()),
+        };
+        let (y, y_squared) = received[i];
+        let row = [flag, x, Fp::from(y), Fp::from(y_squared)];
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    Matrix::new(columns)
+}
+
+fn is_satisfied(base_columns: Matrix<Fp>) -> bool {
+    let bus = bus();
+    let challenges = Challenges::<Fq3>::new(&mut test_rng(), bus.num_challenges());
+    let extension_columns = bus.gen_extension_columns(&base_columns, &[], 6, &challenges);
+    let rows = base_columns
+        .rows()
+        .into_iter()
+        .zip(extension_columns.rows())
+        .map(|(base_row, extension_row)| {
+            base_row
+                .into_iter()
+                .map(Fq3::from)
+                .chain(extension_row)
+                .collect::<Vec<Fq3>>()
+        })
+        .collect::<Vec<_>>();
+
+    let evaluates_to_zero = |constraints: &[Constraint<Fq3>], curr: &[Fq3], next: &[Fq3]| {
+        constraints
+            .iter()
+            .all(|c| c.evaluate(&challenges, &[], curr, next).is_zero())
+    };
+    evaluates_to_zero(&bus.boundary_constraints(), &rows[0], &[])
+        && rows
+            .array_windows()
+            .all(|[curr, next]| evaluates_to_zero(&bus.transition_constraints(), curr, next))
+        && evaluates_to_zero(&bus.terminal_constraints(), &rows[TRACE_LEN - 1], &[])
+}
+
+#[test]
+fn bus_with_matching_tuples_is_satisfied() {
+    let sent = [3, 5, 7, 9, 2, 4, 6, 8];
+    let received = [8, 6, 4, 2, 9, 7, 5, 3].map(|y| (y, y * y));
+
+    assert!(is_satisfied(base_columns(&sent, &received)));
+}
+
+#[test]
+fn bus_with_mismatched_tuple_fails() {
+    let sent = [3, 5, 7, 9, 2, 4, 6, 8];
+    let mut received = [8, 6, 4, 2, 9, 7, 5, 3].map(|y| (y, y * y));
+    received[0].1 += 1;
+
+    assert!(!is_satisfied(base_columns(&sent, &received)));
+}
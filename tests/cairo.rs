@@ -0,0 +1,63 @@
+use ark_ff::One;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::cairo::generate_constraint_evaluator;
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::TraceInfo;
+use std::fs;
+use std::process::Command;
+
+#[test]
+fn generates_evaluator_for_fibonacci_air() {
+    let trace_info = TraceInfo::new(2, 0, TestVector::TRACE_LEN, None);
+    let air = FibonacciAir::new(trace_info, Fp::one(), TestVector::options());
+
+    let code = generate_constraint_evaluator(&air).unwrap();
+
+    assert!(code.contains("const MODULUS: u128 = 18446744069414584321;"));
+    for name in ["boundary", "transition", "terminal"] {
+        assert!(code.contains(&format!("fn evaluate_{name}_constraints(")));
+    }
+    // two boundary, two transition and one terminal constraint
+    assert_eq!(code.matches("res.append(acc);").count(), 5);
+    assert!(code.contains("*(*frame.at(1)).at(1)"));
+}
+
+/// Builds the generated evaluator with Scarb. Skipped if Scarb isn't
+/// installed unless `REQUIRE_SCARB` is set (as it is in CI).
+#[test]
+fn generated_evaluator_compiles() {
+    if Command::new("scarb").arg("--version").output().is_err() {
+        assert!(
+            std::env::var_os("REQUIRE_SCARB").is_none(),
+            "scarb is required but wasn't found"
+        );
+        eprintln!("skipping: scarb is not installed");
+        return;
+    }
+    let trace_info = TraceInfo::new(2, 0, TestVector::TRACE_LEN, None);
+    let air = FibonacciAir::new(trace_info, Fp::one(), TestVector::options());
+    let code = generate_constraint_evaluator(&air).unwrap();
+    let package_dir = std::env::temp_dir().join(format!("ministark-cairo-{}", std::process::id()));
+    fs::create_dir_all(package_dir.join("src")).unwrap();
+    fs::write(
+        package_dir.join("Scarb.toml"),
+        "[package]\nname = \"fibonacci_evaluator\"\nversion = \"0.1.0\"\n",
+    )
+    .unwrap();
+    fs::write(package_dir.join("src/lib.cairo"), code).unwrap();
+
+    let output = Command::new("scarb")
+        .arg("build")
+        .current_dir(&package_dir)
+        .output()
+        .unwrap();
+
+    fs::remove_dir_all(&package_dir).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+}
@@ -0,0 +1,135 @@
+#![feature(allocator_api)]
+use ark_ff::Field;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// Single column trace with `a[i+1] = a[i]^3`
+struct CubeTrace(Matrix<Fp>);
+
+impl CubeTrace {
+    fn new(len: usize) -> Self {
+        let mut a = Vec::with_capacity_in(len, PageAlignedAllocator);
+        let mut curr = Fp::from(2u8);
+        for _ in 0..len {
+            a.push(curr);
+            curr = curr.pow([3]);
+        }
+        CubeTrace(Matrix::new(vec![a]))
+    }
+}
+
+impl Trace for CubeTrace {
+    const NUM_BASE_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+/// The degree three transition constraint needs a constraint evaluation
+/// blowup factor of two. The composition polynomial is kept in one column.
+struct CubeAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundary_constraints: Vec<Constraint<Fp>>,
+    transition_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for CubeAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        let a = 0;
+        CubeAir {
+            options,
+            trace_info,
+            boundary_constraints: vec![a.curr() - Fp::from(2u8)],
+            transition_constraints: vec![a.next() - a.curr() * a.curr() * a.curr()],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fp>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        &self.transition_constraints
+    }
+
+    fn num_composition_columns(&self) -> usize {
+        1
+    }
+}
+
+struct CubeProver(ProofOptions);
+
+impl Prover for CubeProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = CubeAir;
+    type Trace = CubeTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        CubeProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _trace: &CubeTrace) {}
+}
+
+#[test]
+fn single_composition_column() {
+    let options = TestVector::options();
+    let trace_len = TestVector::TRACE_LEN;
+    let air = CubeAir::new(CubeTrace::new(trace_len).info(), (), options);
+    assert_eq!(air.ce_blowup_factor(), 2);
+    assert_eq!(air.deep_composition_len(), 2 * trace_len);
+    assert_eq!(air.ldt_blowup_factor(), air.lde_blowup_factor() / 2);
+
+    let proof = CubeProver::new(options)
+        .generate_proof(CubeTrace::new(trace_len))
+        .unwrap();
+
+    assert!(proof
+        .ood_constraint_evaluations
+        .iter()
+        .all(|evals| evals.len() == 1));
+    let num_queries = options.num_queries as usize;
+    assert_eq!(
+        proof.trace_queries.composition_trace_values.len(),
+        num_queries
+    );
+    proof.verify().unwrap();
+}
@@ -12,11 +12,15 @@ use ark_std::rand::seq::SliceRandom;
 use ark_std::rand::Rng;
 use ark_std::UniformRand;
 use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fq3;
 use gpu_poly::GpuField;
+use ministark::constraint;
 use ministark::constraint::are_eq;
 use ministark::constraint::is_binary;
+use ministark::constraint::is_one_of;
 use ministark::constraint::Challenge;
 use ministark::constraint::Column;
+use ministark::constraint::Hint;
 use ministark::matrix::GroupItem;
 use ministark::matrix::MatrixGroup;
 use ministark::Constraint;
@@ -34,6 +38,115 @@ fn constraint_with_challenges() {
         .is_zero());
 }
 
+#[test]
+fn constraint_macro_matches_builder() {
+    const IP: usize = 0;
+    const MP: usize = 1;
+    let three = Fp::from(3);
+
+    let cases: [(Constraint<Fp>, Constraint<Fp>); 4] = [
+        (
+            constraint!(IP[next] - IP[curr] - 1),
+            IP.next() - IP.curr() - Fp::one(),
+        ),
+        (
+            constraint!(-(MP[curr] + 2) * MP[3] * three),
+            -(MP.curr() + Fp::from(2)) * MP.offset(3) * three,
+        ),
+        (
+            constraint!(0[challenge] - 1[hint] * is_binary(IP.curr()).pow(2)),
+            0.get_challenge() - 1.get_hint() * is_binary(IP.curr()).pow(2),
+        ),
+        (
+            constraint!(are_eq(IP.next(), MP.curr()) * (IP[curr] - (MP[next] - 1))),
+            are_eq(IP.next(), MP.curr()) * (IP.curr() - (MP.next() - Fp::one())),
+        ),
+    ];
+
+    for (from_macro, from_builder) in cases {
+        assert_eq!(format!("{from_macro:?}"), format!("{from_builder:?}"));
+    }
+}
+
+#[test]
+fn pow_matches_repeated_multiplication() {
+    let monomial: Constraint<Fp> = 0.curr() * 1.next() * Fp::from(3);
+    let binomial: Constraint<Fp> = 0.curr() - 1.next() + Fp::one();
+    let row = [Fp::from(5), Fp::from(9)];
+    let next_row = [Fp::from(2), Fp::from(7)];
+
+    for constraint in [monomial, binomial] {
+        let mut expected = Constraint::from(Fp::one());
+        for exp in 0..8 {
+            let actual = constraint.pow(exp);
+            assert_eq!(actual.degree(), exp * constraint.degree());
+            assert_eq!(
+                actual.evaluate(&[], &[], &row, &next_row),
+                expected.evaluate(&[], &[], &row, &next_row)
+            );
+            expected *= &constraint;
+        }
+    }
+}
+
+#[test]
+fn select_picks_branch_by_flag() {
+    let constraint: Constraint<Fp> = Constraint::select(0.curr(), 1.curr() * 1.curr(), 2.curr());
+    let row = |flag: u64| [Fp::from(flag), Fp::from(3), Fp::from(7)];
+
+    assert_eq!(constraint.degree(), 3);
+    assert_eq!(constraint.evaluate(&[], &[], &row(1), &[]), Fp::from(9));
+    assert_eq!(constraint.evaluate(&[], &[], &row(0), &[]), Fp::from(7));
+}
+
+#[test]
+fn is_one_of_vanishes_on_values() {
+    let values = [1u64, 5, 8].map(Fp::from);
+    let constraint: Constraint<Fp> = is_one_of(0.curr(), values);
+
+    assert_eq!(constraint.degree(), values.len());
+    for v in 0..10u64 {
+        let eval = constraint.evaluate(&[], &[], &[Fp::from(v)], &[]);
+        assert_eq!(eval.is_zero(), values.contains(&Fp::from(v)));
+    }
+}
+
+#[test]
+fn dead_structure_is_dropped() {
+    let x: Constraint<Fp> = 0.curr();
+    let y: Constraint<Fp> = 1.next();
+
+    let constraint = &x * &x * Fp::zero() + &y * Fp::one() - &y + (&x - &x) + Fp::zero();
+
+    assert!(constraint.terms().is_empty());
+    assert_eq!(constraint.degree(), 0);
+    let constraint = &x * &x * &x * Fp::zero() + &y - Constraint::from(Fp::zero()) * &x;
+    assert_eq!(constraint.degree(), 1);
+    assert_eq!(constraint.max_row_offset(), 1);
+    assert_eq!(
+        format!("{:?}", constraint.simplify()),
+        format!("{constraint:?}")
+    );
+}
+
+#[test]
+fn clones_share_terms_until_changed() {
+    let constraint: Constraint<Fp> = 0.curr() * 1.curr() - 2.next();
+    let row = [Fp::from(2), Fp::from(3), Fp::from(4)];
+    let next_row = [Fp::zero(), Fp::zero(), Fp::from(5)];
+
+    let mut scaled = constraint.clone();
+    let sum = Constraint::zero() + &constraint;
+    assert_eq!(scaled.terms().as_ptr(), constraint.terms().as_ptr());
+    assert_eq!(sum.terms().as_ptr(), constraint.terms().as_ptr());
+
+    scaled *= &Fp::from(2);
+
+    assert_ne!(scaled.terms().as_ptr(), constraint.terms().as_ptr());
+    assert_eq!(constraint.evaluate(&[], &[], &row, &next_row), Fp::one());
+    assert_eq!(scaled.evaluate(&[], &[], &row, &next_row), Fp::from(2));
+}
+
 #[test]
 fn symbolic_evaluation_with_challenges() {
     let n = 2048;
@@ -202,6 +315,38 @@ fn evaluate_permutation_constraint() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn evaluate_constraint_with_row_offsets() {
+    let n = 2048;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = trace_domain.get_coset(Fp::GENERATOR).unwrap();
+    // single column fibonacci sequence
+    let mut column = Vec::with_capacity_in(n, PageAlignedAllocator);
+    column.push(Fp::one());
+    column.push(Fp::one());
+    for i in 2..n {
+        let next = column[i - 1] + column[i - 2];
+        column.push(next);
+    }
+    let matrix = Matrix::new(vec![column]);
+    let poly_matrix = matrix.interpolate(trace_domain);
+    let lde_matrix = poly_matrix.evaluate(lde_domain);
+    let matrix_group = MatrixGroup::new(vec![GroupItem::Fp(&lde_matrix)]);
+    let constraint: Constraint<Fp> = are_eq(0.offset(2), 0.next() + 0.curr());
+    assert_eq!(constraint.max_row_offset(), 2);
+
+    let constraint_evals = matrix_group.evaluate_symbolic(&[constraint.clone()], &[], &[], 1);
+
+    let constraint_evals_poly = constraint_evals.interpolate(lde_domain);
+    let poly = DensePolynomial::from_coefficients_slice(&constraint_evals_poly[0]);
+    let rows = matrix.rows();
+    for (i, x) in trace_domain.elements().enumerate().take(n - 2) {
+        assert!(poly.evaluate(&x).is_zero(), "invalid at row {i}");
+        let frame = [&rows[i][..], &rows[i + 1][..], &rows[i + 2][..]];
+        assert!(constraint.evaluate_frame(&[], &[], &frame).is_zero());
+    }
+}
+
 #[test]
 fn evaluate_zerofier_constraint() {
     // TODO: clean up this testcase
@@ -239,6 +384,56 @@ fn evaluate_zerofier_constraint() {
     assert_valid_over_transition_domain(trace_domain, constraint_eval_poly);
 }
 
+#[test]
+fn symbolic_evaluation_matches_row_evaluation() {
+    let n = 2048;
+    let step = 2;
+    let mut rng = ark_std::test_rng();
+    let base_matrix = Matrix::new(
+        (0..2)
+            .map(|_| (0..n).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>())
+            .map(|column| column.to_vec_in(PageAlignedAllocator))
+            .collect(),
+    );
+    let extension_column = (0..n).map(|_| Fq3::rand(&mut rng)).collect::<Vec<Fq3>>();
+    let extension_matrix = Matrix::new(vec![extension_column.to_vec_in(PageAlignedAllocator)]);
+    let matrix_group = MatrixGroup::new(vec![
+        GroupItem::Fp(&base_matrix),
+        GroupItem::Fq(&extension_matrix),
+    ]);
+    // degree 7 deselector so the expanded terms share many factors
+    let deselector = (1..8u64).fold(Constraint::from(Fq3::one()), |acc, k| {
+        acc * (0.curr() - Fq3::from(Fp::from(k)))
+    });
+    let constraints: Vec<Constraint<Fq3>> = vec![
+        &deselector * &(2.next() - 1.curr()) + 1.curr() * 1.curr() * 1.curr() * 0.next(),
+        are_eq(2.curr() * 1.curr(), 2.next() * 0.get_challenge() + 0.curr()),
+    ];
+    let challenges = [Fq3::rand(&mut rng)];
+    let row = |i: usize| {
+        let i = i % n;
+        vec![
+            Fq3::from(base_matrix[0][i]),
+            Fq3::from(base_matrix[1][i]),
+            extension_matrix[0][i],
+        ]
+    };
+
+    let evaluations = matrix_group.evaluate_symbolic(&constraints, &challenges, &[], step);
+    let row_evaluations =
+        matrix_group.evaluate_symbolic_rows(&constraints, &challenges, &[], step, 100..301);
+
+    for (j, constraint) in constraints.iter().enumerate() {
+        for i in 0..n {
+            let expected = constraint.evaluate(&challenges, &[], &row(i), &row(i + step));
+            assert_eq!(evaluations[j][i], expected, "constraint {j} row {i}");
+            if (100..301).contains(&i) {
+                assert_eq!(row_evaluations[j][i - 100], expected);
+            }
+        }
+    }
+}
+
 /// Generates a matrix of fibbonacci sequence across two columns i.e.
 /// ┌───────┬───────┐
 /// │ Col 0 | Col 1 │
@@ -0,0 +1,225 @@
+#![feature(allocator_api)]
+use ark_ff::One;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::continuation::prove_segments;
+use ministark::continuation::ContinuationError;
+use ministark::continuation::ContinuationProof;
+use ministark::continuation::SegmentAir;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// Fibonacci trace with columns `a` and `b` that starts from `initial`
+struct FibSegmentTrace(Matrix<Fp>);
+
+impl FibSegmentTrace {
+    fn new(initial: [Fp; 2], len: usize) -> Self {
+        let mut a = Vec::with_capacity_in(len, PageAlignedAllocator);
+        let mut b = Vec::with_capacity_in(len, PageAlignedAllocator);
+        let [mut curr_a, mut curr_b] = initial;
+        for _ in 0..len {
+            a.push(curr_a);
+            b.push(curr_b);
+            (curr_a, curr_b) = (curr_b, curr_a + curr_b);
+        }
+        FibSegmentTrace(Matrix::new(vec![a, b]))
+    }
+
+    fn last_row(&self) -> [Fp; 2] {
+        [*self.0[0].last().unwrap(), *self.0[1].last().unwrap()]
+    }
+}
+
+impl Trace for FibSegmentTrace {
+    const NUM_BASE_COLUMNS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+/// First and last row of a segment
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Copy)]
+struct FibBoundaries {
+    first: [Fp; 2],
+    last: [Fp; 2],
+}
+
+struct FibSegmentAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundaries: FibBoundaries,
+    boundary_constraints: Vec<Constraint<Fp>>,
+    transition_constraints: Vec<Constraint<Fp>>,
+    terminal_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for FibSegmentAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = FibBoundaries;
+
+    fn new(trace_info: TraceInfo, boundaries: FibBoundaries, options: ProofOptions) -> Self {
+        let (a, b) = (0, 1);
+        let FibBoundaries { first, last } = boundaries;
+        FibSegmentAir {
+            options,
+            trace_info,
+            boundaries,
+            boundary_constraints: vec![a.curr() - first[0], b.curr() - first[1]],
+            transition_constraints: vec![a.next() - b.curr(), b.next() - a.curr() - b.curr()],
+            terminal_constraints: vec![a.curr() - last[0], b.curr() - last[1]],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &FibBoundaries {
+        &self.boundaries
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fp>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        &self.transition_constraints
+    }
+
+    fn terminal_constraints(&self) -> &[Constraint<Fp>] {
+        &self.terminal_constraints
+    }
+}
+
+/// The state carried between segments is the pair `(a, b)`. Consecutive
+/// segments overlap by one row.
+impl SegmentAir for FibSegmentAir {
+    type State = [Fp; 2];
+
+    fn initial_state(boundaries: &FibBoundaries) -> [Fp; 2] {
+        boundaries.first
+    }
+
+    fn final_state(boundaries: &FibBoundaries) -> [Fp; 2] {
+        boundaries.last
+    }
+}
+
+struct FibSegmentProver(ProofOptions);
+
+impl Prover for FibSegmentProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibSegmentAir;
+    type Trace = FibSegmentTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        FibSegmentProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibSegmentTrace) -> FibBoundaries {
+        FibBoundaries {
+            first: [trace.0[0][0], trace.0[1][0]],
+            last: trace.last_row(),
+        }
+    }
+}
+
+fn initial_state() -> [Fp; 2] {
+    [Fp::one(), Fp::one()]
+}
+
+/// Returns the traces of two consecutive segments
+fn segment_traces() -> [FibSegmentTrace; 2] {
+    let first = FibSegmentTrace::new(initial_state(), TestVector::TRACE_LEN);
+    let second = FibSegmentTrace::new(first.last_row(), TestVector::TRACE_LEN);
+    [first, second]
+}
+
+#[test]
+fn two_segment_chain_verifies() {
+    let prover = FibSegmentProver::new(TestVector::options());
+    let traces = segment_traces();
+    let final_state = traces[1].last_row();
+
+    let proof = prove_segments(&prover, traces).unwrap();
+
+    assert_eq!(proof.segments.len(), 2);
+    proof.verify(&initial_state(), &final_state).unwrap();
+}
+
+#[test]
+fn mismatched_boundary_state_is_rejected() {
+    let prover = FibSegmentProver::new(TestVector::options());
+    let [first, _] = segment_traces();
+    // restarts from the initial state instead of continuing the first segment
+    let second = FibSegmentTrace::new(initial_state(), TestVector::TRACE_LEN);
+    let final_state = second.last_row();
+    let segments = vec![
+        prover.generate_proof(first).unwrap(),
+        prover.generate_proof(second).unwrap(),
+    ];
+    let proof = ContinuationProof { segments };
+
+    let result = proof.verify(&initial_state(), &final_state);
+
+    assert!(matches!(
+        result,
+        Err(ContinuationError::BoundaryMismatch(1, 0))
+    ));
+}
+
+#[test]
+fn mismatched_boundary_state_is_rejected_before_proving() {
+    let prover = FibSegmentProver::new(TestVector::options());
+    let [first, _] = segment_traces();
+    let second = FibSegmentTrace::new(initial_state(), TestVector::TRACE_LEN);
+
+    let result = prove_segments(&prover, [first, second]);
+
+    assert!(matches!(
+        result,
+        Err(ContinuationError::BoundaryMismatch(1, 0))
+    ));
+}
+
+#[test]
+fn missing_public_inputs_are_rejected() {
+    let prover = FibSegmentProver::new(TestVector::options());
+    let traces = segment_traces();
+    let final_state = traces[1].last_row();
+    let mut proof = prove_segments(&prover, traces).unwrap();
+    proof.segments[1] = proof.segments[1].clone().without_public_inputs();
+
+    let result = proof.verify(&initial_state(), &final_state);
+
+    assert!(matches!(
+        result,
+        Err(ContinuationError::MissingPublicInputs(1))
+    ));
+}
@@ -0,0 +1,130 @@
+#![feature(allocator_api)]
+use ark_ff::fields::Fp64;
+use ark_ff::fields::MontBackend;
+use ark_ff::fields::MontConfig;
+use ark_ff::One;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::cpu_field;
+use gpu_poly::GpuField;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// The field `gpu_poly` has kernels for but built from the generic arkworks
+/// Montgomery backend, which has no kernels
+#[derive(MontConfig)]
+#[modulus = "18446744069414584321"]
+#[generator = "7"]
+struct GoldilocksConfig;
+
+cpu_field!(Goldilocks; Fp64<MontBackend<GoldilocksConfig, 1>>);
+
+/// Single column counting up from zero
+struct CounterTrace(Matrix<Goldilocks>);
+
+impl CounterTrace {
+    fn new(len: usize) -> Self {
+        let mut column = Vec::with_capacity_in(len, PageAlignedAllocator);
+        column.extend((0..len).map(|i| Goldilocks::from(i as u64)));
+        CounterTrace(Matrix::new(vec![column]))
+    }
+}
+
+impl Trace for CounterTrace {
+    const NUM_BASE_COLUMNS: usize = 1;
+    type Fp = Goldilocks;
+    type Fq = Goldilocks;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Goldilocks> {
+        &self.0
+    }
+}
+
+struct CounterAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundary_constraints: Vec<Constraint<Goldilocks>>,
+    transition_constraints: Vec<Constraint<Goldilocks>>,
+}
+
+impl Air for CounterAir {
+    type Fp = Goldilocks;
+    type Fq = Goldilocks;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        let a = 0;
+        CounterAir {
+            options,
+            trace_info,
+            boundary_constraints: vec![a.curr()],
+            transition_constraints: vec![a.next() - a.curr() - Goldilocks::one()],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Goldilocks>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Goldilocks>] {
+        &self.transition_constraints
+    }
+}
+
+struct CounterProver(ProofOptions);
+
+impl Prover for CounterProver {
+    type Fp = Goldilocks;
+    type Fq = Goldilocks;
+    type Air = CounterAir;
+    type Trace = CounterTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        CounterProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _trace: &CounterTrace) {}
+}
+
+#[test]
+fn cpu_fields_have_no_gpu_kernels() {
+    assert!(!Goldilocks::HAS_GPU_KERNELS);
+    assert!(gpu_poly::fields::p18446744069414584321::Fp::HAS_GPU_KERNELS);
+}
+
+#[test]
+fn proves_over_field_without_gpu_kernels() {
+    let trace = CounterTrace::new(TestVector::TRACE_LEN);
+
+    let proof = CounterProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+
+    proof.verify().unwrap();
+}
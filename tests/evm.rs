@@ -0,0 +1,63 @@
+use ark_serialize::CanonicalDeserialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::evm::EvmEncode;
+use ministark::evm::EvmEncoder;
+use ministark::evm::PROOF_HEAD_WORDS;
+use ministark::evm::WORD_SIZE;
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::TestVector;
+use ministark::Proof;
+
+/// Reads the `i`th word as an integer
+fn word(bytes: &[u8], i: usize) -> usize {
+    let word = &bytes[i * WORD_SIZE..(i + 1) * WORD_SIZE];
+    assert!(word[..WORD_SIZE - 8].iter().all(|&byte| byte == 0));
+    u64::from_be_bytes(word[WORD_SIZE - 8..].try_into().unwrap()) as usize
+}
+
+#[test]
+fn elements_and_bytes_are_word_aligned() {
+    let mut encoder = EvmEncoder::new();
+    encoder.write_elements(&[Fp::from(7u8)]);
+    encoder.write_bytes(b"abc");
+    let bytes = encoder.into_bytes();
+
+    let mut expected = vec![0; 4 * WORD_SIZE];
+    expected[WORD_SIZE - 1] = 1;
+    expected[2 * WORD_SIZE - 1] = 7;
+    expected[3 * WORD_SIZE - 1] = 3;
+    expected[3 * WORD_SIZE..3 * WORD_SIZE + 3].copy_from_slice(b"abc");
+    assert_eq!(bytes, expected);
+}
+
+#[test]
+fn proof_head_has_fixed_offsets() {
+    let vector = TestVector::generate();
+    let proof = Proof::<FibonacciAir>::deserialize_compressed(&*vector.proof).unwrap();
+
+    let bytes = proof.to_evm_bytes();
+
+    assert_eq!(bytes.len() % WORD_SIZE, 0);
+    assert_eq!(word(&bytes, 0), vector.options.num_queries as usize);
+    assert_eq!(word(&bytes, 8), vector.options.hash_function as usize);
+    assert_eq!(word(&bytes, 11), vector.trace_len);
+    assert_eq!(
+        &bytes[12 * WORD_SIZE..13 * WORD_SIZE],
+        &vector.base_trace_commitment[..]
+    );
+    assert_eq!(word(&bytes, 14), proof.pow_nonce as usize);
+    // sections follow the head in order
+    let offsets = (15..PROOF_HEAD_WORDS)
+        .map(|i| word(&bytes, i))
+        .collect::<Vec<_>>();
+    assert_eq!(offsets[0], PROOF_HEAD_WORDS * WORD_SIZE);
+    assert!(offsets.windows(2).all(|w| w[0] < w[1]));
+    assert!(*offsets.last().unwrap() < bytes.len());
+    // no extension trace commitments
+    assert_eq!(word(&bytes, offsets[1] / WORD_SIZE), 0);
+    // ood constraint evaluations section starts with the number of ood points
+    assert_eq!(
+        word(&bytes, offsets[3] / WORD_SIZE),
+        vector.options.num_ood_points as usize
+    );
+}
@@ -0,0 +1,138 @@
+#![feature(allocator_api)]
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// Number of padding rows at the end of the counter column
+const PADDING: usize = 4;
+
+/// Column `a` counts up then repeats its last value on the padding rows.
+/// Column `b` is constant.
+struct PaddedTrace(Matrix<Fp>);
+
+impl PaddedTrace {
+    fn new(len: usize) -> Self {
+        let mut a = Vec::with_capacity_in(len, PageAlignedAllocator);
+        let mut b = Vec::with_capacity_in(len, PageAlignedAllocator);
+        for i in 0..len {
+            a.push(Fp::from(i.min(len - PADDING) as u64));
+            b.push(Fp::one());
+        }
+        PaddedTrace(Matrix::new(vec![a, b]))
+    }
+}
+
+impl Trace for PaddedTrace {
+    const NUM_BASE_COLUMNS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+}
+
+struct PaddedAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundary_constraints: Vec<Constraint<Fp>>,
+    transition_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for PaddedAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        let (a, b) = (0, 1);
+        PaddedAir {
+            options,
+            trace_info,
+            boundary_constraints: vec![a.curr(), b.curr() - Fp::one()],
+            transition_constraints: vec![a.next() - a.curr() - Fp::one(), b.next() - b.curr()],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fp>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        &self.transition_constraints
+    }
+
+    fn transition_exemptions(&self) -> Vec<usize> {
+        vec![PADDING, 1]
+    }
+}
+
+struct PaddedProver(ProofOptions);
+
+impl Prover for PaddedProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = PaddedAir;
+    type Trace = PaddedTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        PaddedProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _trace: &PaddedTrace) {}
+}
+
+#[test]
+fn constraint_is_exempt_on_padding() {
+    let trace = PaddedTrace::new(TestVector::TRACE_LEN);
+    let air = PaddedAir::new(trace.info(), (), TestVector::options());
+    assert_eq!(air.ce_blowup_factor(), 1);
+
+    let proof = PaddedProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+
+    proof.verify().unwrap();
+}
+
+#[test]
+fn padding_violates_constraint() {
+    let trace = PaddedTrace::new(TestVector::TRACE_LEN);
+    let rows = trace.base_columns().rows();
+    let n = rows.len();
+
+    // the counter constraint doesn't hold on the last `PADDING` rows
+    let counter = |i: usize| rows[i + 1][0] - rows[i][0] - Fp::one();
+    assert!(counter(n - PADDING - 1).is_zero());
+    assert!(!counter(n - PADDING).is_zero());
+}
@@ -0,0 +1,66 @@
+use ministark::grinding::grind;
+use ministark::grinding::proof_of_work;
+use ministark::grinding::NonceEncoding;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::HashFunction;
+use ministark::Prover;
+use ministark::VerificationError;
+use sha2::Sha256;
+
+#[test]
+fn grind_finds_the_smallest_nonce() {
+    let seed = [7; 32];
+    let difficulty = 8;
+
+    let nonce = grind::<Sha256>(&seed, difficulty, NonceEncoding::U64Le).unwrap();
+
+    let work = |nonce| proof_of_work::<Sha256>(&seed, nonce, NonceEncoding::U64Le).unwrap();
+    assert!(work(nonce) >= difficulty);
+    assert!((1..nonce).all(|smaller| work(smaller) < difficulty));
+}
+
+#[test]
+fn grind_is_deterministic_across_batches() {
+    // 2^16 nonces are expected to be hashed so the search spans several batches
+    let seed = [9; 32];
+    let difficulty = 16;
+
+    let nonce = grind::<Sha256>(&seed, difficulty, NonceEncoding::U32Le).unwrap();
+
+    let work = |nonce| proof_of_work::<Sha256>(&seed, nonce, NonceEncoding::U32Le).unwrap();
+    assert!(work(nonce) >= difficulty);
+    assert!((1..nonce).all(|smaller| work(smaller) < difficulty));
+    assert_eq!(
+        grind::<Sha256>(&seed, difficulty, NonceEncoding::U32Le),
+        Some(nonce)
+    );
+}
+
+#[test]
+fn narrow_encodings_reject_wide_nonces() {
+    let seed = [7; 32];
+    let wide_nonce = u64::from(u32::MAX) + 1;
+
+    assert!(proof_of_work::<Sha256>(&seed, wide_nonce, NonceEncoding::U32Le).is_none());
+    assert!(proof_of_work::<Sha256>(&seed, wide_nonce, NonceEncoding::U64Be).is_some());
+    assert_eq!(NonceEncoding::U32Be.encode(1), Some(vec![0, 0, 0, 1]));
+}
+
+#[test]
+fn proofs_verify_with_custom_grinding_options() {
+    let options = TestVector::options()
+        .with_grinding_hash(HashFunction::Blake2s)
+        .with_nonce_encoding(NonceEncoding::U32Be);
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let proof = FibonacciProver::new(options).generate_proof(trace).unwrap();
+    proof.clone().verify().unwrap();
+
+    let mut tampered = proof;
+    tampered.pow_nonce = u64::from(u32::MAX) + 1;
+    assert!(matches!(
+        tampered.verify(),
+        Err(VerificationError::FriProofOfWork)
+    ));
+}
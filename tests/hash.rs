@@ -0,0 +1,119 @@
+#![feature(allocator_api, array_windows)]
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_std::test_rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::gadgets::hash;
+use ministark::gadgets::hash::HashColumns;
+use ministark::gadgets::hash::HashRequestColumns;
+use ministark::gadgets::rescue::RescuePrime;
+use ministark::gadgets::rescue::DIGEST_SIZE;
+use ministark::gadgets::rescue::RATE;
+use ministark::Constraint;
+use ministark::Matrix;
+
+const NUM_REQUEST_COLUMNS: usize = 1 + RATE + DIGEST_SIZE;
+
+const NUM_BASE_COLUMNS: usize = NUM_REQUEST_COLUMNS + hash::NUM_BASE_COLUMNS;
+
+const COLUMNS: HashColumns = HashColumns {
+    requests: HashRequestColumns {
+        is_request: 0,
+        inputs: [1, 2, 3, 4, 5, 6, 7, 8],
+        digest: [9, 10, 11, 12],
+    },
+    base_offset: NUM_REQUEST_COLUMNS,
+    extension_offset: NUM_BASE_COLUMNS,
+    periodic_offset: NUM_BASE_COLUMNS + hash::NUM_EXTENSION_COLUMNS,
+    challenge_offset: 0,
+};
+
+const TRACE_LEN: usize = 4 * hash::CYCLE_LENGTH;
+
+/// Columns of a table requesting hashes of `(inputs, digest)` on its first
+/// rows
+fn request_columns(requests: &[([Fp; RATE], [Fp; DIGEST_SIZE])]) -> Matrix<Fp> {
+    let mut columns = (0..NUM_REQUEST_COLUMNS)
+        .map(|_| Vec::with_capacity_in(TRACE_LEN, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for i in 0..TRACE_LEN {
+        let row = match requests.get(i) {
+            Some((inputs, digest)) => [[Fp::one()].as_slice(), inputs, digest].concat(),
+            None => vec![Fp::zero(); NUM_REQUEST_COLUMNS],
+        };
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    Matrix::new(columns)
+}
+
+fn is_satisfied(requests: &[([Fp; RATE], [Fp; DIGEST_SIZE])]) -> bool {
+    let rescue_prime = RescuePrime::new();
+    let challenges = Challenges::<Fq3>::new(&mut test_rng(), hash::NUM_CHALLENGES);
+    let inputs = requests
+        .iter()
+        .map(|(inputs, _)| *inputs)
+        .collect::<Vec<_>>();
+    let base_columns = Matrix::join(vec![
+        request_columns(requests),
+        hash::gen_base_columns(&rescue_prime, &inputs, TRACE_LEN),
+    ]);
+    let extension_columns = hash::gen_extension_columns(COLUMNS, &base_columns, &challenges);
+    let periodic_columns = rescue_prime.periodic_columns();
+    let rows = base_columns
+        .rows()
+        .into_iter()
+        .zip(extension_columns.rows())
+        .enumerate()
+        .map(|(i, (base_row, extension_row))| {
+            let periodic_row = periodic_columns
+                .iter()
+                .map(|column| column[i % hash::CYCLE_LENGTH]);
+            base_row
+                .into_iter()
+                .map(Fq3::from)
+                .chain(extension_row)
+                .chain(periodic_row.map(Fq3::from))
+                .collect::<Vec<Fq3>>()
+        })
+        .collect::<Vec<_>>();
+
+    let boundary: Vec<Constraint<Fq3>> = COLUMNS.boundary_constraints();
+    let transition: Vec<Constraint<Fq3>> = COLUMNS.transition_constraints(&rescue_prime);
+    let terminal: Vec<Constraint<Fq3>> = COLUMNS.terminal_constraints();
+    let evaluates_to_zero = |constraints: &[Constraint<Fq3>], curr: &[Fq3], next: &[Fq3]| {
+        constraints
+            .iter()
+            .all(|c| c.evaluate(&challenges, &[], curr, next).is_zero())
+    };
+    evaluates_to_zero(&boundary, &rows[0], &[])
+        && rows
+            .array_windows()
+            .all(|[curr, next]| evaluates_to_zero(&transition, curr, next))
+        && evaluates_to_zero(&terminal, &rows[TRACE_LEN - 1], &[])
+}
+
+#[test]
+fn hash_requests_satisfy_constraints() {
+    let rescue_prime = RescuePrime::new();
+    let requests = [1, 2, 3].map(|n| {
+        let inputs = [(); RATE].map(|_| Fp::from(n as u64));
+        (inputs, rescue_prime.hash_elements(&inputs))
+    });
+
+    assert!(is_satisfied(&requests));
+}
+
+#[test]
+fn hash_request_with_wrong_digest_fails() {
+    let rescue_prime = RescuePrime::new();
+    let inputs = [(); RATE].map(|_| Fp::from(7u64));
+    let mut digest = rescue_prime.hash_elements(&inputs);
+    digest[0] += Fp::one();
+
+    assert!(!is_satisfied(&[(inputs, digest)]));
+}
@@ -0,0 +1,109 @@
+use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::gadgets::rescue::STATE_WIDTH;
+use ministark::hashes::Griffin;
+use ministark::hashes::GriffinDigest;
+use ministark::hashes::RescueDigest;
+use ministark::hashes::BLOCK_SIZE;
+use ministark::merkle::hash_leaf;
+use ministark::merkle::MerkleTree;
+
+fn message(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 % 256) as u8).collect()
+}
+
+fn incremental_updates_match<D: Digest>() {
+    let message = message(3 * BLOCK_SIZE + 5);
+
+    let mut hasher = D::new();
+    for chunk in message.chunks(BLOCK_SIZE - 3) {
+        hasher.update(chunk);
+    }
+
+    assert_eq!(hasher.finalize(), D::digest(&message));
+}
+
+fn padding_separates_messages<D: Digest>() {
+    let digests = [
+        D::digest(b""),
+        D::digest([0u8]),
+        D::digest([0u8, 0]),
+        D::digest(message(BLOCK_SIZE - 1)),
+        D::digest(message(BLOCK_SIZE)),
+        D::digest(message(BLOCK_SIZE + 1)),
+    ];
+
+    for (i, a) in digests.iter().enumerate() {
+        for b in &digests[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
+
+fn merkle_tree_verifies<D: Digest>() {
+    let leaf_nodes = (0..16u64)
+        .map(|i| hash_leaf::<D, Fp>(&[Fp::from(i), Fp::from(i * i)]))
+        .collect();
+    let tree = MerkleTree::<D>::new(leaf_nodes).unwrap();
+    let i = 5;
+    let proof = tree.prove(i).unwrap();
+
+    assert!(MerkleTree::<D>::verify(tree.root(), &proof.parse::<D>().unwrap(), i, 4).is_ok());
+    assert!(proof
+        .verify_leaf::<D, Fp>(tree.root(), &[Fp::from(5u64), Fp::from(25u64)], i, 4)
+        .is_ok());
+    assert!(proof
+        .verify_leaf::<D, Fp>(tree.root(), &[Fp::from(5u64), Fp::from(24u64)], i, 4)
+        .is_err());
+}
+
+#[test]
+fn rescue_digest_absorbs_incrementally() {
+    incremental_updates_match::<RescueDigest>();
+}
+
+#[test]
+fn griffin_digest_absorbs_incrementally() {
+    incremental_updates_match::<GriffinDigest>();
+}
+
+#[test]
+fn rescue_digest_pads_messages() {
+    padding_separates_messages::<RescueDigest>();
+}
+
+#[test]
+fn griffin_digest_pads_messages() {
+    padding_separates_messages::<GriffinDigest>();
+}
+
+#[test]
+fn merkle_tree_with_rescue_digest() {
+    merkle_tree_verifies::<RescueDigest>();
+}
+
+#[test]
+fn merkle_tree_with_griffin_digest() {
+    merkle_tree_verifies::<GriffinDigest>();
+}
+
+#[test]
+fn rescue_and_griffin_digests_differ() {
+    assert_ne!(
+        RescueDigest::digest(b"ministark"),
+        GriffinDigest::digest(b"ministark")
+    );
+}
+
+#[test]
+fn griffin_permutation_is_deterministic() {
+    let griffin = Griffin::new();
+    let mut a = [(); STATE_WIDTH].map(|_| Fp::from(7u64));
+    let mut b = a;
+
+    griffin.permute(&mut a);
+    Griffin::default().permute(&mut b);
+
+    assert_eq!(a, b);
+    assert_ne!(a, [Fp::from(7u64); STATE_WIDTH]);
+}
@@ -0,0 +1,229 @@
+#![feature(allocator_api)]
+use ark_ff::One;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::challenges::Challenges;
+use ministark::constraint::Challenge;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::PublicCoin;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// Counter `a` with an extension column `b = a + alpha` committed in the first
+/// round and `c = beta * b` committed in the second round. `beta` is drawn
+/// after `b` is committed to.
+struct TwoRoundTrace(Matrix<Fp>);
+
+impl TwoRoundTrace {
+    fn new(len: usize) -> Self {
+        let mut a = Vec::with_capacity_in(len, PageAlignedAllocator);
+        a.extend((0..len).map(|i| Fp::from(i as u64)));
+        TwoRoundTrace(Matrix::new(vec![a]))
+    }
+
+    fn column(&self, f: impl Fn(Fp) -> Fp) -> Matrix<Fp> {
+        let mut column = Vec::with_capacity_in(self.len(), PageAlignedAllocator);
+        column.extend(self.0[0].iter().map(|&a| f(a)));
+        Matrix::new(vec![column])
+    }
+}
+
+impl Trace for TwoRoundTrace {
+    const NUM_BASE_COLUMNS: usize = 1;
+    const NUM_EXTENSION_COLUMNS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.0
+    }
+
+    fn build_round_columns(&self, round: usize, challenges: &Challenges<Fp>) -> Option<Matrix<Fp>> {
+        match round {
+            0 => Some(self.column(|a| a + challenges[0])),
+            1 => Some(self.column(|a| (a + challenges[0]) * challenges[1])),
+            _ => None,
+        }
+    }
+}
+
+struct TwoRoundAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    boundary_constraints: Vec<Constraint<Fp>>,
+    transition_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for TwoRoundAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        let (a, b, c) = (0, 1, 2);
+        let (alpha, beta) = (0, 1);
+        TwoRoundAir {
+            options,
+            trace_info,
+            boundary_constraints: vec![a.curr()],
+            transition_constraints: vec![
+                a.next() - a.curr() - Fp::one(),
+                b.curr() - a.curr() - alpha.get_challenge(),
+                c.curr() - b.curr() * beta.get_challenge(),
+            ],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn boundary_constraints(&self) -> &[Constraint<Fp>] {
+        &self.boundary_constraints
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        &self.transition_constraints
+    }
+
+    fn num_interaction_rounds(&self) -> usize {
+        2
+    }
+
+    fn num_round_extension_columns(&self, _round: usize) -> usize {
+        1
+    }
+
+    fn get_round_challenges(
+        &self,
+        _round: usize,
+        public_coin: &mut PublicCoin<impl digest::Digest>,
+    ) -> Challenges<Fp> {
+        Challenges::draw(public_coin, 1)
+    }
+}
+
+struct TwoRoundProver(ProofOptions);
+
+impl Prover for TwoRoundProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = TwoRoundAir;
+    type Trace = TwoRoundTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        TwoRoundProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _trace: &TwoRoundTrace) {}
+}
+
+/// Same trace as [TwoRoundTrace] that leaves building the extension columns
+/// to the prover
+struct BaseTrace(TwoRoundTrace);
+
+impl Trace for BaseTrace {
+    const NUM_BASE_COLUMNS: usize = 1;
+    const NUM_EXTENSION_COLUMNS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        self.0.base_columns()
+    }
+}
+
+struct AuxTraceProver(ProofOptions);
+
+impl Prover for AuxTraceProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = TwoRoundAir;
+    type Trace = BaseTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        AuxTraceProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _trace: &BaseTrace) {}
+
+    fn build_aux_trace(
+        &self,
+        trace: &BaseTrace,
+        round: usize,
+        challenges: &Challenges<Fp>,
+    ) -> Option<Matrix<Fp>> {
+        trace.0.build_round_columns(round, challenges)
+    }
+}
+
+#[test]
+fn commits_to_each_round() {
+    let prover = TwoRoundProver::new(TestVector::options());
+    let trace = TwoRoundTrace::new(TestVector::TRACE_LEN);
+
+    let proof = prover.generate_proof(trace).unwrap();
+
+    assert_eq!(proof.extension_trace_commitments.len(), 2);
+    proof.verify().unwrap();
+}
+
+#[test]
+fn rejects_missing_round_commitment() {
+    let prover = TwoRoundProver::new(TestVector::options());
+    let trace = TwoRoundTrace::new(TestVector::TRACE_LEN);
+    let mut proof = prover.generate_proof(trace).unwrap();
+
+    proof.extension_trace_commitments.pop();
+
+    assert!(proof.verify().is_err());
+}
+
+#[test]
+fn prover_builds_aux_trace() {
+    let options = TestVector::options();
+    let len = TestVector::TRACE_LEN;
+    let expected = TwoRoundProver::new(options)
+        .generate_proof(TwoRoundTrace::new(len))
+        .unwrap();
+
+    let proof = AuxTraceProver::new(options)
+        .generate_proof(BaseTrace(TwoRoundTrace::new(len)))
+        .unwrap();
+
+    let (mut proof_bytes, mut expected_bytes) = (Vec::new(), Vec::new());
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    expected.serialize_compressed(&mut expected_bytes).unwrap();
+    assert_eq!(proof_bytes, expected_bytes);
+}
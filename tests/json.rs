@@ -0,0 +1,111 @@
+#![cfg(feature = "json")]
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ministark::json::JsonEncode;
+use ministark::json::JsonError;
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::LowDegreeTest;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use serde_json::Value;
+
+fn prove(options: ProofOptions) -> Proof<FibonacciAir> {
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    FibonacciProver::new(options).generate_proof(trace).unwrap()
+}
+
+fn compressed_bytes(proof: &Proof<FibonacciAir>) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    proof.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn fri_proof_round_trips() {
+    let proof = prove(TestVector::options());
+
+    let decoded = Proof::<FibonacciAir>::from_json(&proof.to_json()).unwrap();
+
+    assert_eq!(compressed_bytes(&decoded), compressed_bytes(&proof));
+    decoded.verify().unwrap();
+}
+
+#[test]
+fn stir_proof_round_trips() {
+    let proof = prove(TestVector::options().with_low_degree_test(LowDegreeTest::Stir));
+
+    let decoded = Proof::<FibonacciAir>::from_json(&proof.to_json()).unwrap();
+
+    assert_eq!(compressed_bytes(&decoded), compressed_bytes(&proof));
+    decoded.verify().unwrap();
+}
+
+#[test]
+fn sections_are_named_hex_strings() {
+    let vector = TestVector::generate();
+    let proof = Proof::<FibonacciAir>::deserialize_compressed(vector.proof.as_slice()).unwrap();
+    let hex = |bytes: &[u8]| -> String { bytes.iter().map(|byte| format!("{byte:02x}")).collect() };
+
+    let json: Value = serde_json::from_str(&proof.to_json()).unwrap();
+
+    assert_eq!(
+        json["base_trace_commitment"],
+        hex(&vector.base_trace_commitment)
+    );
+    assert_eq!(json["public_inputs"], hex(&vector.public_inputs));
+    assert!(json["low_degree_proof"]["fri"]["layers"].is_array());
+    assert!(json["ood_constraint_evaluations"][0][0].is_string());
+}
+
+#[test]
+fn missing_public_inputs_are_null() {
+    let proof = prove(TestVector::options()).without_public_inputs();
+
+    let json = proof.to_json();
+    let decoded = Proof::<FibonacciAir>::from_json(&json).unwrap();
+
+    assert!(json.contains("\"public_inputs\": null"));
+    assert!(decoded.public_inputs.is_none());
+}
+
+#[test]
+fn malformed_json_is_an_error() {
+    let proof = prove(TestVector::options());
+    let mut json: Value = proof.encode_json();
+    json["base_trace_commitment"] = Value::String("0g".to_string());
+    let mut truncated = proof.encode_json();
+    truncated.as_object_mut().unwrap().remove("pow_nonce");
+
+    assert!(matches!(
+        Proof::<FibonacciAir>::decode_json(&json),
+        Err(JsonError::InvalidHex)
+    ));
+    assert!(matches!(
+        Proof::<FibonacciAir>::decode_json(&truncated),
+        Err(JsonError::MissingField("pow_nonce"))
+    ));
+    assert!(matches!(
+        Proof::<FibonacciAir>::from_json("{"),
+        Err(JsonError::Syntax(_))
+    ));
+}
+
+#[test]
+fn transcript_entries_are_tagged_objects() {
+    let proof = prove(TestVector::options());
+    let public_inputs = proof.public_inputs.unwrap();
+
+    let (result, transcript) = proof.transcript(public_inputs);
+    result.unwrap();
+    let json: Value = serde_json::from_str(&transcript.to_json()).unwrap();
+
+    assert_eq!(json["hash_function"], "Sha256");
+    let entries = json["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), transcript.entries.len());
+    assert_eq!(entries[0]["absorb"]["label"], "base_trace_commitment");
+    assert!(entries.iter().any(|entry| entry["draw"]["counter"] == 1));
+}
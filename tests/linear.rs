@@ -0,0 +1,155 @@
+#![feature(allocator_api)]
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Column;
+use ministark::Constraint;
+use ministark::Matrix;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::Trace;
+use ministark::TraceInfo;
+
+/// Slope of the linear column
+const SLOPE: u64 = 5;
+
+/// Column `a` has value `offset + SLOPE * x` at the row with trace domain
+/// element `x`
+struct LineTrace {
+    offset: Fp,
+    columns: Matrix<Fp>,
+}
+
+impl LineTrace {
+    fn new(len: usize, offset: Fp) -> Self {
+        let domain = Radix2EvaluationDomain::<Fp>::new(len).unwrap();
+        let mut a = Vec::with_capacity_in(len, PageAlignedAllocator);
+        a.extend(domain.elements().map(|x| offset + Fp::from(SLOPE) * x));
+        LineTrace {
+            offset,
+            columns: Matrix::new(vec![a]),
+        }
+    }
+}
+
+impl Trace for LineTrace {
+    const NUM_BASE_COLUMNS: usize = 1;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.columns.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Fp> {
+        &self.columns
+    }
+}
+
+/// Checks column `a` against a linear column derived from the public offset
+/// and a constant column
+struct LineAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+    offset: Fp,
+    transition_constraints: Vec<Constraint<Fp>>,
+}
+
+impl Air for LineAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = Fp;
+
+    fn new(trace_info: TraceInfo, offset: Fp, options: ProofOptions) -> Self {
+        let (a, line, two) = (0, 1, 2);
+        LineAir {
+            options,
+            trace_info,
+            offset,
+            transition_constraints: vec![
+                a.curr() - line.curr(),
+                a.curr() * two.curr() - line.curr() * Fp::from(2u64),
+            ],
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &Fp {
+        &self.offset
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        &self.transition_constraints
+    }
+
+    fn linear_columns(&self) -> Vec<(Fp, Fp)> {
+        vec![(self.offset, Fp::from(SLOPE)), (Fp::from(2u64), Fp::zero())]
+    }
+}
+
+struct LineProver(ProofOptions);
+
+impl Prover for LineProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = LineAir;
+    type Trace = LineTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        LineProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &LineTrace) -> Fp {
+        trace.offset
+    }
+}
+
+#[test]
+fn linear_columns_match_trace() {
+    let trace = LineTrace::new(TestVector::TRACE_LEN, Fp::from(3u64));
+    let air = LineAir::new(trace.info(), Fp::from(3u64), TestVector::options());
+
+    let linear_trace = air.linear_column_trace().unwrap();
+
+    assert_eq!(linear_trace[0], trace.base_columns()[0]);
+    assert!(linear_trace[1].iter().all(|&v| v == Fp::from(2u64)));
+}
+
+#[test]
+fn proves_with_linear_columns() {
+    let trace = LineTrace::new(TestVector::TRACE_LEN, Fp::from(3u64));
+
+    let proof = LineProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+
+    assert_eq!(proof.trace_info.num_base_columns, 1);
+    proof.verify().unwrap();
+}
+
+#[test]
+fn rejects_different_linear_column() {
+    let trace = LineTrace::new(TestVector::TRACE_LEN, Fp::from(3u64));
+    let mut proof = LineProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+
+    proof.public_inputs = Some(Fp::from(4u64));
+
+    assert!(proof.verify().is_err());
+}
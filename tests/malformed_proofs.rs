@@ -0,0 +1,242 @@
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::aggregate::AggregateAir;
+use ministark::merkle::path_len;
+use ministark::merkle::MerkleProof;
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::HashFunction;
+use ministark::Proof;
+use ministark::ProofHeader;
+use ministark::ProofOptions;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+use sha2::Sha256;
+
+/// Deserializes and verifies a proof. Returns true if the proof is valid.
+fn verify_bytes(bytes: &[u8]) -> bool {
+    match Proof::<FibonacciAir>::deserialize_compressed(bytes) {
+        Ok(proof) => proof.verify().is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[test]
+fn corrupted_proofs_are_rejected_without_panicking() {
+    let proof = TestVector::generate().proof;
+    assert!(verify_bytes(&proof));
+
+    let stride = proof.len() / 97 + 1;
+    for i in (0..proof.len()).step_by(stride) {
+        let mut corrupted = proof.clone();
+        corrupted[i] ^= 0xFF;
+        assert!(!verify_bytes(&corrupted), "corrupted byte {i} was accepted");
+    }
+}
+
+#[test]
+fn truncated_proofs_are_rejected_without_panicking() {
+    let proof = TestVector::generate().proof;
+
+    let stride = proof.len() / 31 + 1;
+    for len in (0..proof.len()).step_by(stride) {
+        assert!(!verify_bytes(&proof[..len]));
+    }
+}
+
+#[test]
+fn invalid_header_is_rejected_before_reading_the_proof() {
+    let vector = TestVector::generate();
+    let public_inputs = Fp::deserialize_compressed(&*vector.public_inputs).unwrap();
+    let mut header = Vec::new();
+    ProofHeader::new::<FibonacciAir>(vector.options.hash_function)
+        .serialize_compressed(&mut header)
+        .unwrap();
+    vector.options.serialize_compressed(&mut header).unwrap();
+    let trace_len = TraceInfo::MIN_TRACE_LENGTH + 1;
+    TraceInfo::new(2, 0, trace_len, None)
+        .serialize_compressed(&mut header)
+        .unwrap();
+
+    // nothing follows the header so reading any further would fail
+    let result = Proof::<FibonacciAir>::verify_with_limits(
+        &header,
+        public_inputs,
+        usize::MAX,
+        usize::MAX,
+        usize::MAX,
+    );
+
+    assert!(matches!(result, Err(VerificationError::InvalidTraceLength(len)) if len == trace_len));
+}
+
+#[test]
+fn mismatched_header_is_rejected() {
+    let vector = TestVector::generate();
+    let proof = Proof::<FibonacciAir>::deserialize_compressed(&*vector.proof).unwrap();
+    let expected = proof.header;
+    let verify_with_header = |header: ProofHeader| {
+        let mut proof = proof.clone();
+        proof.header = header;
+        proof.verify()
+    };
+
+    let version = [expected.version[0] + 1, expected.version[1]];
+    let old_version = ProofHeader {
+        version,
+        ..expected
+    };
+    let other_field = ProofHeader {
+        field_id: expected.field_id ^ 1,
+        ..expected
+    };
+    let other_hash = ProofHeader {
+        hash_function: HashFunction::Blake2s,
+        ..expected
+    };
+    let other_air = ProofHeader {
+        air_id: expected.air_id ^ 1,
+        ..expected
+    };
+
+    assert!(matches!(
+        verify_with_header(old_version),
+        Err(VerificationError::VersionMismatch(v, _)) if v == version
+    ));
+    assert!(matches!(
+        verify_with_header(other_field),
+        Err(VerificationError::FieldMismatch(..))
+    ));
+    assert!(matches!(
+        verify_with_header(other_hash),
+        Err(VerificationError::HashFunctionMismatch(
+            HashFunction::Blake2s,
+            HashFunction::Sha256
+        ))
+    ));
+    assert!(matches!(
+        verify_with_header(other_air),
+        Err(VerificationError::AirMismatch(..))
+    ));
+}
+
+#[test]
+fn proof_for_another_air_is_rejected_before_reading_it() {
+    let vector = TestVector::generate();
+
+    let result = Proof::<AggregateAir<FibonacciAir, 2>>::peek_public_inputs(&*vector.proof);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::AirMismatch(found, expected))
+            if found == FibonacciAir::air_id() && expected != found
+    ));
+}
+
+/// Verifies the test vector proof after changing its options
+fn verify_with_options(
+    update_options: impl FnOnce(&mut ProofOptions),
+) -> Result<(), VerificationError> {
+    let vector = TestVector::generate();
+    let mut proof = Proof::<FibonacciAir>::deserialize_compressed(&*vector.proof).unwrap();
+    update_options(&mut proof.options);
+    proof.verify()
+}
+
+#[test]
+fn invalid_blowup_factor_is_rejected() {
+    let result = verify_with_options(|options| options.lde_blowup_factor = 3);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidBlowupFactor(3))
+    ));
+}
+
+#[test]
+fn zero_queries_are_rejected() {
+    let result = verify_with_options(|options| options.num_queries = 0);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidNumQueries(0))
+    ));
+}
+
+#[test]
+fn excessive_grinding_factor_is_rejected() {
+    let grinding_factor = ProofOptions::MAX_GRINDING_FACTOR + 1;
+
+    let result = verify_with_options(|options| options.grinding_factor = grinding_factor);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidGrindingFactor(factor)) if factor == grinding_factor
+    ));
+}
+
+#[test]
+fn unsupported_folding_factor_is_rejected() {
+    let result = verify_with_options(|options| options.fri_folding_factor = 3);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidFoldingFactor(3))
+    ));
+}
+
+#[test]
+fn invalid_remainder_size_is_rejected() {
+    let lde_domain_size =
+        TestVector::TRACE_LEN * usize::from(TestVector::options().lde_blowup_factor);
+
+    // the remainder size is a `u8` so it can't exceed the LDE domain of a
+    // trace with the minimum length. The lower bound is checked instead.
+    let result = verify_with_options(|options| options.fri_max_remainder_size = 2);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidRemainderSize(2, size)) if size == lde_domain_size
+    ));
+}
+
+#[test]
+fn zero_ood_points_are_rejected() {
+    let result = verify_with_options(|options| options.num_ood_points = 0);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidNumOodPoints(0))
+    ));
+}
+
+#[test]
+fn excessive_merkle_cap_height_is_rejected() {
+    let cap_height = ProofOptions::MAX_MERKLE_CAP_HEIGHT + 1;
+
+    let result = verify_with_options(|options| options.merkle_cap_height = cap_height);
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::InvalidMerkleCapHeight(height)) if height == cap_height
+    ));
+}
+
+#[test]
+fn truncated_trace_query_paths_are_rejected() {
+    let vector = TestVector::generate();
+    let mut proof = Proof::<FibonacciAir>::deserialize_compressed(&*vector.proof).unwrap();
+    let lde_domain_size = vector.trace_len * proof.options.lde_blowup_factor as usize;
+    let depth = path_len(lde_domain_size, proof.options.merkle_cap_height.into());
+    let base_trace_proof = &mut proof.trace_queries.base_trace_proofs[0];
+    let mut nodes = base_trace_proof.parse::<Sha256>().unwrap();
+    nodes.pop();
+    *base_trace_proof = MerkleProof::new::<Sha256>(nodes);
+
+    assert!(matches!(
+        proof.verify(),
+        Err(VerificationError::MerkleProofLength(expected)) if expected == depth
+    ));
+}
@@ -0,0 +1,157 @@
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::matrix::CompactColumn;
+use ministark::matrix::CompactMatrix;
+use ministark::Matrix;
+
+fn values(values: &[u64]) -> Vec<Fp> {
+    values.iter().map(|&v| Fp::from(v)).collect()
+}
+
+/// Output table with a constant flag, a mostly zero value column and a
+/// column of runs
+fn io_table() -> CompactMatrix<Fp> {
+    CompactMatrix::new(vec![
+        CompactColumn::constant(Fp::one(), 6),
+        CompactColumn::sparse(
+            Fp::zero(),
+            6,
+            vec![(1, Fp::from(5u64)), (4, Fp::from(9u64))],
+        ),
+        CompactColumn::run_length([
+            (Fp::from(2u64), 2),
+            (Fp::from(3u64), 0),
+            (Fp::from(4u64), 4),
+        ]),
+    ])
+}
+
+#[test]
+fn compact_columns_densify_to_their_values() {
+    let dense = io_table().into_dense();
+
+    assert_eq!(dense[0].to_vec(), values(&[1, 1, 1, 1, 1, 1]));
+    assert_eq!(dense[1].to_vec(), values(&[0, 5, 0, 0, 9, 0]));
+    assert_eq!(dense[2].to_vec(), values(&[2, 2, 4, 4, 4, 4]));
+}
+
+#[test]
+fn rows_are_read_without_densifying() {
+    let table = io_table();
+    let dense = table.clone().into_dense();
+
+    for row in 0..table.num_rows() {
+        assert_eq!(table.get_row(row), dense.get_row(row));
+    }
+    assert_eq!(table.get_row(6), None);
+    assert_eq!(table.num_compact_cols(), 3);
+}
+
+#[test]
+fn column_densifies_only_that_column() {
+    let mut table = io_table();
+
+    assert_eq!(table.column(1).to_vec(), values(&[0, 5, 0, 0, 9, 0]));
+
+    assert!(table.0[1].is_dense());
+    assert_eq!(table.num_compact_cols(), 2);
+}
+
+#[test]
+fn compact_interpolation_matches_dense_interpolation() {
+    let table = CompactMatrix::new(vec![
+        CompactColumn::constant(Fp::one(), 8),
+        CompactColumn::sparse(Fp::zero(), 8, vec![(3, Fp::from(7u64))]),
+        CompactColumn::run_length([(Fp::from(2u64), 3), (Fp::from(6u64), 5)]),
+    ]);
+    let domain = Radix2EvaluationDomain::<Fp>::new(8).unwrap();
+
+    let polys = table.interpolate(domain);
+
+    assert_eq!(polys.rows(), table.into_dense().interpolate(domain).rows());
+}
+
+#[test]
+fn dense_matrix_round_trips() {
+    let matrix = Matrix::from_rows(vec![values(&[1, 2]), values(&[3, 4])]);
+
+    let compact = CompactMatrix::from(matrix.clone());
+
+    assert_eq!(compact.num_compact_cols(), 0);
+    assert_eq!(Matrix::from(compact).rows(), matrix.rows());
+}
+
+#[test]
+#[should_panic(expected = "sorted by row")]
+fn sparse_entries_must_be_sorted() {
+    CompactColumn::sparse(Fp::zero(), 4, vec![(2, Fp::one()), (1, Fp::one())]);
+}
+
+#[test]
+fn row_arrays_keep_their_width() {
+    let empty = Matrix::<Fp>::from_row_arrays::<3>(Vec::new());
+    let matrix = Matrix::from_row_arrays(vec![[Fp::one(), Fp::zero()]; 4]);
+
+    assert_eq!(empty.num_cols(), 3);
+    assert_eq!(matrix.num_cols(), 2);
+    assert_eq!(matrix.num_rows(), 4);
+}
+
+#[test]
+fn transpose_swaps_rows_and_columns() {
+    let matrix = Matrix::from_rows(vec![values(&[1, 2, 3]), values(&[4, 5, 6])]);
+
+    let transposed = matrix.transpose();
+
+    assert_eq!(
+        transposed.rows(),
+        vec![values(&[1, 4]), values(&[2, 5]), values(&[3, 6])]
+    );
+    assert_eq!(transposed.transpose().rows(), matrix.rows());
+}
+
+#[test]
+fn views_borrow_a_block_of_the_matrix() {
+    let matrix = Matrix::from_rows(vec![
+        values(&[1, 2, 3]),
+        values(&[4, 5, 6]),
+        values(&[7, 8, 9]),
+    ]);
+
+    let view = matrix.slice(1..3, 1..3);
+
+    assert_eq!(view.num_rows(), 2);
+    assert_eq!(view.num_cols(), 2);
+    assert_eq!(view.column(0), values(&[5, 8]));
+    assert_eq!(view.row(1).to_vec(), values(&[8, 9]));
+    assert_eq!(
+        view.slice(1..2, 0..1).to_matrix().rows(),
+        vec![values(&[6])]
+    );
+}
+
+#[test]
+fn rows_are_iterated_in_place() {
+    let matrix = Matrix::from_rows(vec![values(&[1, 2]), values(&[3, 4])]);
+
+    let sums = matrix
+        .iter_rows()
+        .map(|row| row.iter().sum::<Fp>())
+        .collect::<Vec<Fp>>();
+
+    assert_eq!(sums, values(&[3, 7]));
+    assert_eq!(matrix.iter_rows().nth(1).unwrap()[1], Fp::from(4u64));
+}
+
+#[test]
+fn scalar_ops_apply_to_every_value() {
+    let mut matrix = Matrix::from_rows(vec![values(&[1, 2]), values(&[3, 4])]);
+
+    matrix.mul_scalar(Fp::from(2u64));
+    matrix.add_scalar(Fp::one());
+
+    assert_eq!(matrix.rows(), vec![values(&[3, 5]), values(&[7, 9])]);
+}
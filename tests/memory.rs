@@ -0,0 +1,88 @@
+#![feature(array_windows)]
+use ark_ff::Zero;
+use ark_std::test_rng;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::gadgets::memory;
+use ministark::gadgets::memory::MemoryColumns;
+use ministark::gadgets::memory::PublicMemory;
+use ministark::Constraint;
+use ministark::Matrix;
+
+const COLUMNS: MemoryColumns = MemoryColumns {
+    base_offset: 0,
+    extension_offset: memory::NUM_BASE_COLUMNS,
+    challenge_offset: 0,
+    hint: 0,
+};
+
+const TRACE_LEN: usize = 16;
+
+fn cell(address: u64, value: u64) -> (Fp, Fp) {
+    (Fp::from(address), Fp::from(value))
+}
+
+/// Returns each row of the base and extension columns lifted into `Fq3`
+fn trace_rows(public_memory: &PublicMemory<Fp>, accesses: &[(Fp, Fp)]) -> Vec<Vec<Fq3>> {
+    let challenges = Challenges::new(&mut test_rng(), memory::NUM_CHALLENGES);
+    let base_columns = public_memory.gen_base_columns(accesses, TRACE_LEN);
+    let extension_columns = memory::gen_extension_columns(COLUMNS, &base_columns, &challenges);
+    let base_columns = Matrix::<Fq3>::from_rows(
+        base_columns
+            .rows()
+            .into_iter()
+            .map(|row| row.into_iter().map(Fq3::from).collect())
+            .collect(),
+    );
+    Matrix::join(vec![base_columns, extension_columns]).rows()
+}
+
+fn is_satisfied(public_memory: &PublicMemory<Fp>, rows: &[Vec<Fq3>]) -> bool {
+    let challenges = Challenges::<Fq3>::new(&mut test_rng(), memory::NUM_CHALLENGES);
+    let hints = [public_memory.hint(COLUMNS, &challenges)];
+    let boundary: Vec<Constraint<Fq3>> = COLUMNS.boundary_constraints();
+    let transition: Vec<Constraint<Fq3>> = COLUMNS.transition_constraints();
+    let terminal: Vec<Constraint<Fq3>> = COLUMNS.terminal_constraints();
+    let evaluates_to_zero = |constraints: &[Constraint<Fq3>], curr: &[Fq3], next: &[Fq3]| {
+        constraints
+            .iter()
+            .all(|c| c.evaluate(&challenges, &hints, curr, next).is_zero())
+    };
+    let first = rows.first().unwrap();
+    let last = rows.last().unwrap();
+    evaluates_to_zero(&boundary, first, &[])
+        && rows
+            .array_windows()
+            .all(|[curr, next]| evaluates_to_zero(&transition, curr, next))
+        && evaluates_to_zero(&terminal, last, &[])
+}
+
+#[test]
+fn public_memory_satisfies_constraints() {
+    let public_memory = PublicMemory::new(vec![cell(1, 10), cell(2, 20)]);
+    let accesses = [cell(3, 30), cell(1, 10), cell(4, 40), cell(3, 30)];
+    let rows = trace_rows(&public_memory, &accesses);
+
+    assert_eq!(TRACE_LEN, rows.len());
+    assert!(is_satisfied(&public_memory, &rows));
+}
+
+#[test]
+fn public_memory_with_wrong_value_fails() {
+    let public_memory = PublicMemory::new(vec![cell(1, 10), cell(2, 20)]);
+    let accesses = [cell(3, 30), cell(1, 10), cell(4, 40), cell(3, 30)];
+    let rows = trace_rows(&public_memory, &accesses);
+    let claimed_memory = PublicMemory::new(vec![cell(1, 10), cell(2, 21)]);
+
+    assert!(!is_satisfied(&claimed_memory, &rows));
+}
+
+#[test]
+#[should_panic(expected = "has multiple values")]
+fn conflicting_accesses_panic() {
+    let public_memory = PublicMemory::new(vec![cell(1, 10)]);
+    let accesses = [cell(1, 11)];
+
+    trace_rows(&public_memory, &accesses);
+}
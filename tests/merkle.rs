@@ -1,5 +1,19 @@
+#![feature(allocator_api)]
+use ark_ff::FftField;
+use ark_ff_optimized::fp64::Fp;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_std::UniformRand;
 use digest::Digest;
+use digest::Output;
+use gpu_poly::allocator::PageAlignedAllocator;
+use ministark::merkle::hash_leaf;
+use ministark::merkle::path_len;
+use ministark::merkle::verify_leaves_bounded;
+use ministark::merkle::MerkleProof;
 use ministark::merkle::MerkleTree;
+use ministark::merkle::MerkleTreeError;
+use ministark::Matrix;
 use sha2::Sha256;
 
 #[test]
@@ -12,9 +26,9 @@ fn merkle_verify() {
     let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
     let commitment = tree.root();
     let i = 3;
-    let proof = tree.prove(i).unwrap().parse::<Sha256>();
+    let proof = tree.prove(i).unwrap().parse::<Sha256>().unwrap();
 
-    assert!(MerkleTree::<Sha256>::verify(commitment, &proof, i).is_ok());
+    assert!(MerkleTree::<Sha256>::verify(commitment, &proof, i, 3).is_ok());
 }
 
 #[test]
@@ -27,7 +41,258 @@ fn merkle_verify_large_tree() {
     let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
     let commitment = tree.root();
     let i = 378;
-    let proof = tree.prove(i).unwrap().parse::<Sha256>();
+    let proof = tree.prove(i).unwrap().parse::<Sha256>().unwrap();
 
-    assert!(MerkleTree::<Sha256>::verify(commitment, &proof, i).is_ok());
+    assert!(MerkleTree::<Sha256>::verify(commitment, &proof, i, 10).is_ok());
+}
+
+#[test]
+fn evaluate_and_commit_matches_evaluate_then_commit() {
+    let n = 1 << 8;
+    let mut rng = ark_std::test_rng();
+    let columns = (0..5)
+        .map(|_| {
+            let mut column = Vec::with_capacity_in(n, PageAlignedAllocator);
+            column.resize_with(n, || Fp::rand(&mut rng));
+            column
+        })
+        .collect();
+    let polys = Matrix::new(columns);
+    let lde_domain = Radix2EvaluationDomain::<Fp>::new_coset(n * 4, Fp::GENERATOR).unwrap();
+
+    let evaluations = polys.evaluate(lde_domain);
+    let tree = evaluations.commit_to_rows::<Sha256>();
+    let (pipelined_evaluations, pipelined_tree) = polys.evaluate_and_commit::<Sha256>(lde_domain);
+
+    assert_eq!(tree.root(), pipelined_tree.root());
+    assert_eq!(evaluations.0, pipelined_evaluations.0);
+}
+
+#[test]
+fn leaves_are_fixed_width_values() {
+    let row = [Fp::from(1u64), Fp::from(1u64 << 40)];
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&1u64.to_le_bytes());
+    bytes.extend_from_slice(&(1u64 << 40).to_le_bytes());
+
+    assert_eq!(hash_leaf::<Sha256, _>(&row), Sha256::digest(bytes));
+}
+
+#[test]
+fn rows_are_committed_as_leaves() {
+    let matrix = Matrix::from_rows(
+        (0..8u64)
+            .map(|i| vec![Fp::from(i), Fp::from(i + 8)])
+            .collect(),
+    );
+    let leaf_nodes = matrix
+        .rows()
+        .iter()
+        .map(|row| hash_leaf::<Sha256, _>(row))
+        .collect();
+
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
+
+    assert_eq!(matrix.commit_to_rows::<Sha256>().root(), tree.root());
+}
+
+#[test]
+fn merkle_verify_leaf_in_place() {
+    let rows = (0..16u64)
+        .map(|i| vec![Fp::from(i), Fp::from(i * i)])
+        .collect::<Vec<Vec<Fp>>>();
+    let leaf_nodes = rows.iter().map(|row| hash_leaf::<Sha256, _>(row)).collect();
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
+    let i = 5;
+    let proof = tree.prove(i).unwrap();
+
+    assert!(proof
+        .verify_leaf::<Sha256, _>(tree.root(), &rows[i], i, 4)
+        .is_ok());
+    assert!(proof
+        .verify_leaf::<Sha256, _>(tree.root(), &rows[i + 1], i, 4)
+        .is_err());
+    assert!(proof
+        .verify_leaf::<Sha256, _>(tree.root(), &rows[i], i + 1, 4)
+        .is_err());
+}
+
+#[test]
+fn merkle_cap_proofs_are_shorter() {
+    let rows = (0..16u64)
+        .map(|i| vec![Fp::from(i)])
+        .collect::<Vec<Vec<Fp>>>();
+    let leaf_nodes = rows.iter().map(|row| hash_leaf::<Sha256, _>(row)).collect();
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
+    let i = 11;
+    let cap = tree.cap(2);
+    let proof = tree.prove_with_cap(i, 2).unwrap();
+
+    assert_eq!(cap.len(), 4);
+    assert_eq!(proof.parse::<Sha256>().unwrap().len(), 3);
+    assert_eq!(path_len(16, 2), 2);
+    assert!(proof
+        .verify_leaf_with_cap::<Sha256, _>(cap, &rows[i], i, 2)
+        .is_ok());
+    assert!(proof
+        .verify_leaf_with_cap::<Sha256, _>(cap, &rows[i], i - 4, 2)
+        .is_err());
+    // caps are limited to the level above the leaves
+    assert_eq!(tree.cap(10).len(), 8);
+    assert_eq!(tree.cap(0), std::slice::from_ref(tree.root()));
+}
+
+#[test]
+fn merkle_verify_batch_with_shared_paths() {
+    let rows = (0..16u64)
+        .map(|i| vec![Fp::from(i), Fp::from(i * i)])
+        .collect::<Vec<Vec<Fp>>>();
+    let leaf_nodes = rows.iter().map(|row| hash_leaf::<Sha256, _>(row)).collect();
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
+    let cap = tree.cap(1);
+    // leaves 2 and 3 are siblings and share their whole path
+    let positions = [2, 3, 5, 11];
+    let proofs = positions
+        .iter()
+        .map(|&i| tree.prove_with_cap(i, 1).unwrap())
+        .collect::<Vec<MerkleProof>>();
+    let values = positions
+        .iter()
+        .map(|&i| rows[i].clone())
+        .collect::<Vec<Vec<Fp>>>();
+    let mut swapped_values = values.clone();
+    swapped_values.swap(0, 1);
+
+    assert!(MerkleProof::verify_batch_with_cap::<Sha256, Fp, _>(
+        &proofs, cap, &values, &positions, 3
+    )
+    .is_ok());
+    assert!(MerkleProof::verify_batch_with_cap::<Sha256, Fp, _>(
+        &proofs,
+        cap,
+        &swapped_values,
+        &positions,
+        3
+    )
+    .is_err());
+    assert!(MerkleProof::verify_batch_with_cap::<Sha256, Fp, _>(
+        &proofs[1..],
+        cap,
+        &values[1..],
+        &[3, 5, 10],
+        3
+    )
+    .is_err());
+}
+
+#[test]
+fn merkle_verify_leaves_within_bounds() {
+    let rows = (0..16u64)
+        .map(|i| [Fp::from(i), Fp::from(i * i)])
+        .collect::<Vec<[Fp; 2]>>();
+    let leaf_nodes = rows.iter().map(|row| hash_leaf::<Sha256, _>(row)).collect();
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes).unwrap();
+    let cap = tree.cap(1);
+    // leaves 2 and 3 are siblings so share every node above the leaves
+    let positions = [2, 3, 5, 11];
+    let values = positions.map(|i| rows[i]);
+    // paths have 3 siblings since the cap is one level below the root
+    let paths = positions
+        .iter()
+        .flat_map(|&i| {
+            let nodes = tree.prove_with_cap(i, 1).unwrap().parse::<Sha256>();
+            // the first node is the leaf hash
+            nodes.unwrap().into_iter().skip(1)
+        })
+        .collect::<Vec<_>>();
+    let mut scratch = [Output::<Sha256>::default(); 4];
+    let mut verify = |values: &[[Fp; 2]], paths: &[Output<Sha256>], depth| {
+        verify_leaves_bounded::<Sha256, Fp, _, 4, 3>(
+            cap,
+            values,
+            &positions[..values.len()],
+            paths,
+            depth,
+            &mut scratch,
+        )
+    };
+    let mut wrong_sibling = paths.clone();
+    wrong_sibling[0] = Output::<Sha256>::default();
+    let mut wrong_values = values;
+    wrong_values[1][0] += Fp::from(1u8);
+
+    assert!(verify(&values, &paths, 3).is_ok());
+    assert!(verify(&values[..1], &paths[..3], 3).is_ok());
+    assert!(matches!(
+        verify(&values, &wrong_sibling, 3),
+        Err(MerkleTreeError::InvalidProof)
+    ));
+    assert!(matches!(
+        verify(&wrong_values, &paths, 3),
+        Err(MerkleTreeError::InvalidProof)
+    ));
+    assert!(matches!(
+        verify(&values, &paths[..11], 3),
+        Err(MerkleTreeError::PathLengthMismatch(2, 3))
+    ));
+    assert!(matches!(
+        verify(&values, &paths, 4),
+        Err(MerkleTreeError::PathTooLong(4, 3))
+    ));
+    assert!(matches!(
+        verify_leaves_bounded::<Sha256, Fp, _, 3, 3>(
+            cap,
+            &values,
+            &positions,
+            &paths,
+            3,
+            &mut [Output::<Sha256>::default(); 3],
+        ),
+        Err(MerkleTreeError::TooManyOpenings(4, 3))
+    ));
+}
+
+#[test]
+fn merkle_paths_shorter_than_the_tree_are_rejected() {
+    let rows = (0..16u64)
+        .map(|i| vec![Fp::from(i); 8])
+        .collect::<Vec<Vec<Fp>>>();
+    let leaf_nodes = rows
+        .iter()
+        .map(|row| hash_leaf::<Sha256, _>(row))
+        .collect::<Vec<_>>();
+    let tree = MerkleTree::<Sha256>::new(leaf_nodes.clone()).unwrap();
+    let path = tree.prove(5).unwrap().parse::<Sha256>().unwrap();
+    // an inner node is the hash of its two children. Read as field elements
+    // the children's digests are a row that hashes to the inner node.
+    let children = [leaf_nodes[4].as_slice(), leaf_nodes[5].as_slice()].concat();
+    let inner_row = children
+        .chunks(8)
+        .map(|limb| Fp::from(u64::from_le_bytes(limb.try_into().unwrap())))
+        .collect::<Vec<Fp>>();
+    let inner_node = hash_leaf::<Sha256, _>(&inner_row);
+    // opens the parent of leaves 4 and 5 as if it were the leaf at position 2
+    let short_path = [std::slice::from_ref(&inner_node), &path[2..]].concat();
+    let short_proof = MerkleProof::new::<Sha256>(short_path.clone());
+    let root = std::slice::from_ref(tree.root());
+
+    // the path is valid for a tree one level shorter
+    assert!(short_proof
+        .verify_leaf::<Sha256, _>(tree.root(), &inner_row, 2, 3)
+        .is_ok());
+    assert!(matches!(
+        short_proof.verify_leaf::<Sha256, _>(tree.root(), &inner_row, 2, 4),
+        Err(MerkleTreeError::PathLengthMismatch(3, 4))
+    ));
+    assert!(matches!(
+        MerkleProof::verify_batch_with_cap::<Sha256, Fp, _>(
+            &[short_proof],
+            root,
+            &[inner_row],
+            &[2],
+            4
+        ),
+        Err(MerkleTreeError::PathLengthMismatch(3, 4))
+    ));
+    assert!(MerkleTree::<Sha256>::verify(tree.root(), &short_path, 2, 4).is_err());
 }
@@ -0,0 +1,100 @@
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::test_vectors::FibonacciAir;
+use ministark::Air;
+use ministark::HashFunction;
+use ministark::InsufficientSecurityError;
+use ministark::LowDegreeTest;
+use ministark::ProofOptions;
+use ministark::TraceInfo;
+
+/// AIR without constraints over a cubic extension of the 64-bit field
+struct CubicExtensionAir {
+    options: ProofOptions,
+    trace_info: TraceInfo,
+}
+
+impl Air for CubicExtensionAir {
+    type Fp = Fp;
+    type Fq = Fq3;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        CubicExtensionAir {
+            options,
+            trace_info,
+        }
+    }
+
+    fn options(&self) -> &ProofOptions {
+        &self.options
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        &self.trace_info
+    }
+}
+
+#[test]
+fn secure_presets_accept_extension_fields() {
+    let options_96 = ProofOptions::secure_96::<CubicExtensionAir>().unwrap();
+    let options_128 = ProofOptions::secure_128::<CubicExtensionAir>().unwrap();
+
+    let max_trace_len = options_128.max_trace_len::<Fp>();
+    assert!(options_96.conjectured_security_level::<CubicExtensionAir>(max_trace_len) >= 96);
+    assert!(options_128.conjectured_security_level::<CubicExtensionAir>(max_trace_len) >= 128);
+}
+
+#[test]
+fn secure_presets_reject_64_bit_fields_without_an_extension() {
+    let result_96 = ProofOptions::secure_96::<FibonacciAir>();
+    let result_128 = ProofOptions::secure_128::<FibonacciAir>();
+
+    assert!(matches!(
+        result_96,
+        Err(InsufficientSecurityError { requested: 96, supported }) if supported < 96
+    ));
+    assert!(matches!(
+        result_128,
+        Err(InsufficientSecurityError { requested: 128, supported }) if supported < 128
+    ));
+}
+
+#[test]
+fn security_level_is_capped_by_the_hash() {
+    let options = ProofOptions::secure_128::<CubicExtensionAir>().unwrap();
+    let trace_len = TraceInfo::MIN_TRACE_LENGTH;
+    assert_eq!(
+        options.conjectured_security_level::<CubicExtensionAir>(trace_len),
+        128
+    );
+
+    let rescue = options.with_hash_function(HashFunction::Rescue);
+    let rescue_grinding = options.with_grinding_hash(HashFunction::Griffin);
+
+    assert_eq!(
+        rescue.conjectured_security_level::<CubicExtensionAir>(trace_len),
+        127
+    );
+    assert_eq!(
+        rescue_grinding.conjectured_security_level::<CubicExtensionAir>(trace_len),
+        127
+    );
+}
+
+#[test]
+fn stir_loses_field_bits_to_folding() {
+    let options = ProofOptions::new(64, 8, 0, 16, 64);
+    let stir = options.with_low_degree_test(LowDegreeTest::Stir);
+    let max_trace_len = options.max_trace_len::<Fp>();
+
+    // the queries give far more bits than the 64-bit field so the field decides
+    let fri_level = options.conjectured_security_level::<FibonacciAir>(max_trace_len);
+    let stir_level = stir.conjectured_security_level::<FibonacciAir>(max_trace_len);
+
+    assert_eq!(fri_level, stir_level + 4);
+}
@@ -0,0 +1,81 @@
+#![feature(allocator_api)]
+use ark_ff::One;
+use ark_ff::Zero;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::padding::padded_len;
+use ministark::padding::Padding;
+use ministark::padding::PaddingRules;
+use ministark::Matrix;
+use ministark::TraceInfo;
+
+/// Rules for a table with columns `cycle | register | dummy | flag`
+fn rules() -> PaddingRules<Fp> {
+    PaddingRules::repeat_last(4)
+        .with(0, Padding::Increment(Fp::one()))
+        .with(2, Padding::Constant(Fp::one()))
+        .with(3, Padding::Zero)
+}
+
+fn row(values: [u64; 4]) -> [Fp; 4] {
+    values.map(Fp::from)
+}
+
+#[test]
+fn pads_rows_with_rules() {
+    let mut rows = vec![row([0, 7, 0, 1]), row([1, 9, 0, 1])];
+
+    rules().pad_rows(&mut rows, 4);
+
+    assert_eq!(
+        rows,
+        vec![
+            row([0, 7, 0, 1]),
+            row([1, 9, 0, 1]),
+            row([2, 9, 1, 0]),
+            row([3, 9, 1, 0]),
+        ]
+    );
+}
+
+#[test]
+fn pads_matrix_columns_like_rows() {
+    let mut rows = vec![row([5, 3, 0, 1])];
+    let mut matrix = Matrix::from_row_arrays(rows.clone());
+
+    rules().pad_rows(&mut rows, 8);
+    rules().pad(&mut matrix, 8);
+
+    assert_eq!(matrix.rows(), Matrix::from_row_arrays(rows).rows());
+}
+
+#[test]
+fn empty_table_pads_from_zero() {
+    let mut rows = Vec::new();
+    let mut column = Vec::new_in(PageAlignedAllocator);
+    column.push(Fp::from(4u64));
+    let mut matrix = Matrix::new(vec![column, Vec::new_in(PageAlignedAllocator)]);
+
+    rules().pad_rows(&mut rows, 2);
+    PaddingRules::repeat_last(2).pad(&mut matrix, 3);
+
+    assert_eq!(rows, vec![row([1, 0, 1, 0]), row([2, 0, 1, 0])]);
+    assert_eq!(matrix[0].to_vec(), vec![Fp::from(4u64); 3]);
+    assert_eq!(matrix[1].to_vec(), vec![Fp::zero(); 3]);
+}
+
+#[test]
+fn padded_len_is_a_power_of_two_above_the_minimum() {
+    let min = TraceInfo::MIN_TRACE_LENGTH;
+
+    assert_eq!(padded_len([]), min);
+    assert_eq!(padded_len([3, min - 1]), min);
+    assert_eq!(padded_len([min + 1, 10]), 2 * min);
+}
+
+#[test]
+#[should_panic(expected = "expected a rule for each column")]
+fn every_column_needs_a_rule() {
+    let mut rows = vec![[Fp::zero(); 3]];
+    rules().pad_rows(&mut rows, 4);
+}
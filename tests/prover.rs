@@ -0,0 +1,902 @@
+use ark_ff::One;
+use ark_ff::Zero;
+use ark_poly::EvaluationDomain;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use digest::Output;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::challenges::Challenges;
+use ministark::constraint::Column;
+use ministark::fri;
+use ministark::fri::FoldingSchedule;
+use ministark::job::AbortHandle;
+use ministark::job::ProvingPhase;
+use ministark::ldt::LowDegreeProof;
+use ministark::stir;
+use ministark::test_utils::MockAir;
+use ministark::test_utils::MockTrace;
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::Air;
+use ministark::Constraint;
+use ministark::DeepCompositionCoeffs;
+use ministark::DefaultProverChannel;
+use ministark::HashFunction;
+use ministark::LowDegreeTest;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::ProverChannel;
+use ministark::ProvingError;
+use ministark::Queries;
+use ministark::Trace;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+use sha2::Sha256;
+use std::cell::Cell;
+use std::future::Future;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Wake;
+use std::thread::Thread;
+
+/// Proves the Fibonacci AIR while evaluating constraints 100 rows at a time
+struct LowMemoryProver(ProofOptions);
+
+impl Prover for LowMemoryProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        LowMemoryProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn max_memory(&self) -> Option<usize> {
+        Some(100 * 7 * std::mem::size_of::<Fp>())
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap()
+    }
+}
+
+#[test]
+fn chunked_constraint_evaluation_matches_unbounded() {
+    let prover = LowMemoryProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let proof = prover.generate_proof(trace).unwrap();
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(proof_bytes, TestVector::generate().proof);
+}
+
+/// Proves the Fibonacci AIR and records that the grinding phase ran
+struct GrindingProver(ProofOptions, Cell<bool>);
+
+impl Prover for GrindingProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        GrindingProver(options, Cell::new(false))
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap()
+    }
+
+    fn grind(&self, channel: &mut impl ProverChannel<FibonacciAir>) {
+        self.1.set(true);
+        channel.grind_fri_commitments();
+    }
+}
+
+#[test]
+fn overridden_phase_is_used() {
+    let prover = GrindingProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let proof = prover.generate_proof(trace).unwrap();
+
+    assert!(prover.1.get());
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(proof_bytes, TestVector::generate().proof);
+}
+
+/// Passes everything on to the default channel and records the name of each
+/// message
+struct RecordingChannel<'a>(
+    DefaultProverChannel<'a, FibonacciAir, Sha256>,
+    Vec<&'static str>,
+);
+
+impl<'a> fri::ProverChannel<Fp> for RecordingChannel<'a> {
+    type Digest = Sha256;
+
+    fn commit_fri_layer(&mut self, layer_cap: &[Output<Sha256>]) {
+        self.1.push("fri layer");
+        fri::ProverChannel::commit_fri_layer(&mut self.0, layer_cap)
+    }
+
+    fn draw_fri_alpha(&mut self) -> Fp {
+        fri::ProverChannel::draw_fri_alpha(&mut self.0)
+    }
+
+    fn send_fri_remainder(&mut self, coeffs: &[Fp]) {
+        fri::ProverChannel::send_fri_remainder(&mut self.0, coeffs)
+    }
+}
+
+impl<'a> stir::ProverChannel<Fp> for RecordingChannel<'a> {
+    type Digest = Sha256;
+
+    fn commit_stir_round(&mut self, round_root: &Output<Sha256>) {
+        self.1.push("stir round");
+        stir::ProverChannel::commit_stir_round(&mut self.0, round_root)
+    }
+
+    fn draw_stir_challenge(&mut self) -> Fp {
+        stir::ProverChannel::draw_stir_challenge(&mut self.0)
+    }
+
+    fn send_stir_evaluations(&mut self, evals: &[Fp]) {
+        stir::ProverChannel::send_stir_evaluations(&mut self.0, evals)
+    }
+
+    fn draw_stir_positions(&mut self, num_positions: usize, domain_size: usize) -> Vec<usize> {
+        stir::ProverChannel::draw_stir_positions(&mut self.0, num_positions, domain_size)
+    }
+}
+
+impl<'a> ProverChannel<FibonacciAir> for RecordingChannel<'a> {
+    fn commit_base_trace(&mut self, cap: &[Output<Sha256>]) {
+        self.1.push("base trace");
+        self.0.commit_base_trace(cap)
+    }
+
+    fn commit_extension_trace(&mut self, cap: &[Output<Sha256>]) {
+        self.1.push("extension trace");
+        self.0.commit_extension_trace(cap)
+    }
+
+    fn draw_round_challenges(&mut self, round: usize) -> Challenges<Fp> {
+        self.0.draw_round_challenges(round)
+    }
+
+    fn draw_constraint_composition_coeffs(&mut self) -> Vec<(Fp, Fp)> {
+        self.0.draw_constraint_composition_coeffs()
+    }
+
+    fn commit_composition_trace(&mut self, cap: &[Output<Sha256>]) {
+        self.1.push("composition trace");
+        self.0.commit_composition_trace(cap)
+    }
+
+    fn get_ood_point(&mut self) -> Fp {
+        self.0.get_ood_point()
+    }
+
+    fn send_ood_trace_states(&mut self, frame: &[Vec<Fp>]) {
+        self.1.push("ood trace states");
+        self.0.send_ood_trace_states(frame)
+    }
+
+    fn send_ood_constraint_evaluations(&mut self, evals: &[Fp]) {
+        self.1.push("ood constraint evaluations");
+        self.0.send_ood_constraint_evaluations(evals)
+    }
+
+    fn draw_deep_composition_coeffs(&mut self) -> DeepCompositionCoeffs<Fp> {
+        self.0.draw_deep_composition_coeffs()
+    }
+
+    fn grind_fri_commitments(&mut self) {
+        self.1.push("grinding");
+        self.0.grind_fri_commitments()
+    }
+
+    fn get_fri_query_positions(&mut self) -> Vec<usize> {
+        self.0.get_fri_query_positions()
+    }
+
+    fn build_proof(
+        self,
+        trace_queries: Queries<Fp, Fp>,
+        low_degree_proof: LowDegreeProof<Fp>,
+    ) -> Proof<FibonacciAir> {
+        let messages = self.1;
+        assert_eq!(
+            messages[..3],
+            ["base trace", "composition trace", "ood trace states"]
+        );
+        assert_eq!(messages.last(), Some(&"grinding"));
+        self.0.build_proof(trace_queries, low_degree_proof)
+    }
+}
+
+#[test]
+fn custom_channel_is_used() {
+    let prover = FibonacciProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let air = prover.build_air(&trace).unwrap();
+    let channel = RecordingChannel(DefaultProverChannel::new(&air), Vec::new());
+
+    let proof = prover
+        .generate_proof_with_channel(&air, trace, channel)
+        .unwrap();
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(proof_bytes, TestVector::generate().proof);
+}
+
+/// Proves the Fibonacci AIR on its own two thread pool
+#[cfg(feature = "parallel")]
+struct PooledProver(ProofOptions, rayon::ThreadPool);
+
+#[cfg(feature = "parallel")]
+impl Prover for PooledProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        PooledProver(options, pool)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn thread_pool(&self) -> Option<&rayon::ThreadPool> {
+        Some(&self.1)
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap()
+    }
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn proving_in_a_thread_pool_matches_global_pool() {
+    let prover = PooledProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let proof = prover.generate_proof_in_pool(trace).unwrap();
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(proof_bytes, TestVector::generate().proof);
+}
+
+#[test]
+#[cfg(feature = "parallel")]
+fn grinding_is_independent_of_thread_count() {
+    let options = ProofOptions::new(4, 4, 12, 4, 16);
+    let prove = |num_threads: usize| {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .unwrap();
+        let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+        let proof = pool
+            .install(|| FibonacciProver::new(options).generate_proof(trace))
+            .unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        proof_bytes
+    };
+
+    assert_eq!(prove(1), prove(4));
+}
+
+/// Proves the Fibonacci AIR with the LDEs and FRI layers spilled to disk
+#[cfg(feature = "spill")]
+struct SpillingProver(ProofOptions, std::path::PathBuf);
+
+#[cfg(feature = "spill")]
+impl Prover for SpillingProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        let dir = std::env::temp_dir().join(format!("ministark-spill-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        SpillingProver(options, dir)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn working_dir(&self) -> Option<std::path::PathBuf> {
+        Some(self.1.clone())
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap()
+    }
+}
+
+#[test]
+#[cfg(feature = "spill")]
+fn spilling_to_disk_matches_in_memory() {
+    let prover = SpillingProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let proof = prover.generate_proof(trace).unwrap();
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(proof_bytes, TestVector::generate().proof);
+    // spill files are removed once they're read back
+    assert_eq!(std::fs::read_dir(&prover.1).unwrap().count(), 0);
+    std::fs::remove_dir(&prover.1).unwrap();
+}
+
+#[test]
+fn proofs_with_merkle_caps_verify() {
+    let options = TestVector::options().with_merkle_cap_height(2);
+    let prover = FibonacciProver::new(options);
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let proof = prover.generate_proof(trace).unwrap();
+
+    assert_eq!(proof.base_trace_commitment.len(), 4 * 32);
+    proof.verify().unwrap();
+}
+
+#[test]
+fn merkle_cap_must_match_options() {
+    let options = TestVector::options().with_merkle_cap_height(1);
+    let prover = FibonacciProver::new(options);
+    let mut proof = prover
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+
+    proof.options.merkle_cap_height = 2;
+
+    assert!(proof.verify().is_err());
+}
+
+#[test]
+fn proves_with_each_hash_function() {
+    let sha256_proof = TestVector::generate();
+
+    for hash_function in [
+        HashFunction::Blake2b,
+        HashFunction::Blake2s,
+        HashFunction::Rescue,
+        HashFunction::Griffin,
+    ] {
+        let options = TestVector::options().with_hash_function(hash_function);
+        let prover = FibonacciProver::new(options);
+        let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+        let proof = prover.generate_proof(trace).unwrap();
+
+        assert_ne!(
+            proof.base_trace_commitment, sha256_proof.base_trace_commitment,
+            "{hash_function:?}"
+        );
+        proof.verify().unwrap();
+    }
+}
+
+#[test]
+fn hash_function_must_match_options() {
+    let options = TestVector::options().with_hash_function(HashFunction::Blake2b);
+    let prover = FibonacciProver::new(options);
+    let mut proof = prover
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+
+    proof.options.hash_function = HashFunction::Sha256;
+
+    assert!(proof.verify().is_err());
+}
+
+#[test]
+fn public_inputs_are_read_without_verifying() {
+    let vector = TestVector::generate();
+
+    let summary = Proof::<FibonacciAir>::peek_public_inputs(&*vector.proof).unwrap();
+
+    assert_eq!(summary.trace_info.trace_len, TestVector::TRACE_LEN);
+    assert_eq!(summary.options, TestVector::options());
+    let mut public_inputs = Vec::new();
+    summary
+        .public_inputs
+        .unwrap()
+        .serialize_compressed(&mut public_inputs)
+        .unwrap();
+    assert_eq!(public_inputs, vector.public_inputs);
+}
+
+#[test]
+fn peeking_proof_without_public_inputs() {
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let proof = FibonacciProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap()
+        .without_public_inputs();
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+    let summary = Proof::<FibonacciAir>::peek_public_inputs(&*proof_bytes).unwrap();
+
+    assert!(summary.public_inputs.is_none());
+}
+
+#[test]
+fn proof_without_public_inputs_verifies_against_supplied_inputs() {
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let proof = FibonacciProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+    let result = proof.public_inputs.unwrap();
+    let mut proof_bytes = Vec::new();
+    proof
+        .without_public_inputs()
+        .serialize_compressed(&mut proof_bytes)
+        .unwrap();
+    let proof = Proof::<FibonacciAir>::deserialize_compressed(&*proof_bytes).unwrap();
+
+    assert!(matches!(
+        proof.clone().verify(),
+        Err(VerificationError::MissingPublicInputs)
+    ));
+    assert!(proof
+        .clone()
+        .verify_with_public_inputs(result + Fp::one())
+        .is_err());
+    proof.verify_with_public_inputs(result).unwrap();
+}
+
+#[test]
+fn verifies_within_limits() {
+    let vector = TestVector::generate();
+    let result = Fp::deserialize_compressed(&*vector.public_inputs).unwrap();
+    let num_queries = usize::from(vector.options.num_queries);
+    let lde_domain_size = vector.trace_len * usize::from(vector.options.lde_blowup_factor);
+    let num_fri_layers = vector
+        .options
+        .into_fri_options()
+        .num_layers(lde_domain_size);
+    let verify = |max_queries, max_fri_layers, max_proof_bytes| {
+        Proof::<FibonacciAir>::verify_with_limits(
+            &vector.proof,
+            result,
+            max_queries,
+            max_fri_layers,
+            max_proof_bytes,
+        )
+    };
+    let proof_len = vector.proof.len();
+
+    verify(num_queries, num_fri_layers, proof_len).unwrap();
+    assert!(matches!(
+        verify(num_queries, num_fri_layers, proof_len - 1),
+        Err(VerificationError::ProofTooLarge(..))
+    ));
+    assert!(matches!(
+        verify(num_queries - 1, num_fri_layers, proof_len),
+        Err(VerificationError::TooManyQueries(..))
+    ));
+    assert!(matches!(
+        verify(num_queries, num_fri_layers - 1, proof_len),
+        Err(VerificationError::TooManyFriLayers(..))
+    ));
+}
+
+/// Claims a Fibonacci result one larger than the trace's
+struct WrongResultProver(ProofOptions);
+
+impl Prover for WrongResultProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        WrongResultProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap() + Fp::from(1u8)
+    }
+}
+
+#[test]
+fn ood_points_in_the_trace_or_lde_domain_are_invalid() {
+    let trace_info = TraceInfo::new(2, 0, TestVector::TRACE_LEN, None);
+    let air = FibonacciAir::new(trace_info, Fp::one(), TestVector::options());
+    let trace_domain = air.trace_domain();
+    let lde_element = air.lde_domain().element(5);
+    // the evaluation frame of `z` is `[z, z * g]`
+    let shifted_lde_element = lde_element * trace_domain.group_gen_inv;
+
+    assert!(!air.is_valid_ood_point(Fp::zero()));
+    assert!(!air.is_valid_ood_point(trace_domain.element(3)));
+    assert!(!air.is_valid_ood_point(lde_element));
+    assert!(!air.is_valid_ood_point(shifted_lde_element));
+    assert!(air.is_valid_ood_point(Fp::from(3u8)));
+}
+
+fn prove_with_two_ood_points() -> Proof<FibonacciAir> {
+    let options = TestVector::options().with_num_ood_points(2);
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    FibonacciProver::new(options).generate_proof(trace).unwrap()
+}
+
+#[test]
+fn proofs_with_two_ood_points_verify() {
+    let proof = prove_with_two_ood_points();
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+
+    let proof = Proof::<FibonacciAir>::deserialize_compressed(&*proof_bytes).unwrap();
+
+    assert_eq!(proof.ood_trace_states.len(), 2);
+    assert_eq!(proof.ood_constraint_evaluations.len(), 2);
+    proof.verify().unwrap();
+}
+
+#[test]
+fn missing_ood_frame_is_rejected() {
+    let mut proof = prove_with_two_ood_points();
+    proof.ood_trace_states.pop();
+
+    let result = proof.verify();
+
+    assert!(matches!(
+        result,
+        Err(VerificationError::NumOodPoints(2, 1, 2))
+    ));
+}
+
+#[test]
+fn zero_ood_points_is_an_error() {
+    let mut options = TestVector::options();
+    options.num_ood_points = 0;
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let result = FibonacciProver::new(options).generate_proof(trace);
+
+    assert!(matches!(result, Err(ProvingError::InvalidNumOodPoints(0))));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn unsatisfied_constraint_is_an_error() {
+    let prover = WrongResultProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let result = prover.generate_proof(trace);
+
+    match result {
+        Err(ProvingError::UnsatisfiedConstraint {
+            kind,
+            constraint,
+            row,
+            ..
+        }) => assert_eq!(
+            (kind, constraint, row),
+            ("terminal", 0, TestVector::TRACE_LEN - 1)
+        ),
+        _ => panic!("expected the terminal constraint to be unsatisfied"),
+    }
+}
+
+#[test]
+fn description_accounts_for_every_byte() {
+    let vector = TestVector::generate();
+    let proof = Proof::<FibonacciAir>::deserialize_compressed(vector.proof.as_slice()).unwrap();
+
+    let description = proof.describe();
+
+    assert_eq!(description.size(), vector.proof.len());
+    assert_eq!(
+        description.commitments[0],
+        ("base trace".to_string(), vector.base_trace_commitment)
+    );
+    assert_eq!(description.ldt_layers, description.expected_ldt_layers);
+    assert_eq!(
+        description.conjectured_security_level,
+        proof.conjectured_security_level()
+    );
+}
+
+#[test]
+fn description_lists_stir_rounds() {
+    let options = TestVector::options().with_low_degree_test(LowDegreeTest::Stir);
+    let prover = FibonacciProver::new(options);
+    let proof = prover
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+
+    let description = proof.describe();
+    let text = description.to_string();
+
+    assert_eq!(description.ldt_layers, description.expected_ldt_layers);
+    assert!(description.ldt_layers > 0);
+    assert!(text.contains("stir round 0: "));
+    assert!(text.contains(&format!("total: {} bytes", proof.compressed_size())));
+}
+
+#[test]
+fn proves_with_fri_folding_schedule() {
+    let options = TestVector::options().with_fri_folding_schedule(&[8, 4]);
+    let fri_options = options.into_fri_options();
+    let lde_domain_size = TestVector::TRACE_LEN * usize::from(options.lde_blowup_factor);
+    let prover = FibonacciProver::new(options);
+
+    let proof = prover
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+
+    assert_eq!(
+        (0..3)
+            .map(|i| fri_options.folding_factor(i))
+            .collect::<Vec<usize>>(),
+        vec![8, 4, 4]
+    );
+    assert_eq!(
+        proof.describe().ldt_layers,
+        fri_options.num_layers(lde_domain_size)
+    );
+    assert!(
+        fri_options.num_layers(lde_domain_size)
+            < TestVector::options()
+                .into_fri_options()
+                .num_layers(lde_domain_size)
+    );
+    proof.verify().unwrap();
+}
+
+#[test]
+fn proves_with_fri_remainder_coefficients() {
+    let options = TestVector::options().with_fri_remainder_coefficients();
+    let prover = FibonacciProver::new(options);
+    let default_proof = FibonacciProver::new(TestVector::options())
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+
+    let proof = prover
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+    let description = proof.describe();
+
+    assert_eq!(description.ldt_layers, description.expected_ldt_layers);
+    assert!(description.ldt_layers < default_proof.describe().ldt_layers);
+    assert!(!description
+        .commitments
+        .iter()
+        .any(|(label, _)| label == "fri remainder"));
+    assert!(proof.compressed_size() < default_proof.compressed_size());
+    proof.verify().unwrap();
+}
+
+#[test]
+fn tampered_fri_remainder_coefficients_are_rejected() {
+    let options = TestVector::options().with_fri_remainder_coefficients();
+    let mut proof = FibonacciProver::new(options)
+        .generate_proof(FibonacciTrace::new(TestVector::TRACE_LEN))
+        .unwrap();
+    let mut ldt_bytes = Vec::new();
+    proof
+        .low_degree_proof
+        .serialize_compressed(&mut ldt_bytes)
+        .unwrap();
+    // the proof ends with the last coefficient and an empty remainder commitment
+    let last_coeff = ldt_bytes.len() - 8 - 8;
+    ldt_bytes[last_coeff] ^= 1;
+    proof.low_degree_proof = LowDegreeProof::deserialize_compressed(&*ldt_bytes).unwrap();
+
+    assert!(proof.verify().is_err());
+}
+
+#[test]
+fn unsupported_scheduled_folding_factor_is_rejected() {
+    let vector = TestVector::generate();
+    let mut proof = Proof::<FibonacciAir>::deserialize_compressed(&*vector.proof).unwrap();
+    let mut schedule_bytes = Vec::new();
+    proof
+        .options
+        .fri_folding_schedule
+        .serialize_compressed(&mut schedule_bytes)
+        .unwrap();
+    // the schedule's factors are its last bytes
+    let first_factor = schedule_bytes.len() - FoldingSchedule::MAX_LEN;
+    schedule_bytes[first_factor] = 3;
+    proof.options.fri_folding_schedule =
+        CanonicalDeserialize::deserialize_compressed(&*schedule_bytes).unwrap();
+
+    assert!(matches!(
+        proof.verify(),
+        Err(VerificationError::InvalidFoldingFactor(3))
+    ));
+}
+
+/// Wakes the test thread blocked on a future
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+#[test]
+fn async_proof_matches_blocking_proof() {
+    let prover = Arc::new(FibonacciProver::new(TestVector::options()));
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let waker = Arc::new(ThreadWaker(std::thread::current())).into();
+    let mut cx = Context::from_waker(&waker);
+
+    let mut future = Box::pin(prover.generate_proof_async(trace));
+    let mut phases = Vec::new();
+    let proof = loop {
+        phases.push(future.phase());
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(proof) => break proof.unwrap(),
+            Poll::Pending => std::thread::park(),
+        }
+    };
+
+    let mut proof_bytes = Vec::new();
+    proof.serialize_compressed(&mut proof_bytes).unwrap();
+    assert_eq!(proof_bytes, TestVector::generate().proof);
+    // wakes may be coalesced but phases are only ever entered in order
+    assert!(phases.windows(2).all(|pair| pair[0] <= pair[1]));
+}
+
+/// Proves the Fibonacci AIR and aborts itself while grinding
+struct AbortingProver(ProofOptions, AbortHandle);
+
+impl Prover for AbortingProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = FibonacciAir;
+    type Trace = FibonacciTrace;
+
+    fn new(options: ProofOptions) -> Self {
+        AbortingProver(options, AbortHandle::new())
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, trace: &FibonacciTrace) -> Fp {
+        *trace.base_columns()[1].last().unwrap()
+    }
+
+    fn grind(&self, channel: &mut impl ProverChannel<FibonacciAir>) {
+        self.1.abort();
+        channel.grind_fri_commitments();
+    }
+}
+
+#[test]
+fn aborted_proof_stops_at_next_phase() {
+    let prover = AbortingProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+
+    let result = prover.generate_proof_abortable(trace, &prover.1);
+
+    assert!(matches!(result, Err(ProvingError::Aborted)));
+}
+
+#[test]
+fn proof_aborted_before_starting_fails() {
+    let prover = FibonacciProver::new(TestVector::options());
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let abort = AbortHandle::new();
+    abort.abort();
+
+    let result = prover.generate_proof_abortable(trace, &abort);
+
+    assert!(matches!(result, Err(ProvingError::Aborted)));
+}
+
+/// AIR with a cubic constraint that claims a constraint evaluation blowup of
+/// one i.e. constraints of degree at most two
+struct UnderestimatedAir(MockAir<Fp>);
+
+impl Air for UnderestimatedAir {
+    type Fp = Fp;
+    type Fq = Fp;
+    type PublicInputs = ();
+
+    fn new(trace_info: TraceInfo, _: (), options: ProofOptions) -> Self {
+        let cube = 0.curr::<Fp>() * 0.curr() * 0.curr();
+        UnderestimatedAir(
+            MockAir::new(trace_info, (), options).with_transition_constraints(vec![cube]),
+        )
+    }
+
+    fn options(&self) -> &ProofOptions {
+        self.0.options()
+    }
+
+    fn pub_inputs(&self) -> &() {
+        &()
+    }
+
+    fn trace_info(&self) -> &TraceInfo {
+        self.0.trace_info()
+    }
+
+    fn transition_constraints(&self) -> &[Constraint<Fp>] {
+        self.0.transition_constraints()
+    }
+
+    fn ce_blowup_factor(&self) -> usize {
+        1
+    }
+}
+
+struct UnderestimatedProver(ProofOptions);
+
+impl Prover for UnderestimatedProver {
+    type Fp = Fp;
+    type Fq = Fp;
+    type Air = UnderestimatedAir;
+    type Trace = MockTrace<Fp, 1>;
+
+    fn new(options: ProofOptions) -> Self {
+        UnderestimatedProver(options)
+    }
+
+    fn options(&self) -> ProofOptions {
+        self.0
+    }
+
+    fn get_pub_inputs(&self, _: &MockTrace<Fp, 1>) {}
+}
+
+#[test]
+fn constraint_degree_above_composition_degree_is_an_error() {
+    let prover = UnderestimatedProver::new(ProofOptions::new(32, 8, 0, 4, 16));
+    let trace = MockTrace::from_fn(2048, |_, _| Fp::from(0u64));
+
+    let result = prover.generate_proof(trace);
+
+    match result {
+        Err(ProvingError::ConstraintDegreeTooHigh { index, degree, max }) => {
+            assert_eq!((index, degree, max), (0, 2 * 2047, 2047))
+        }
+        _ => panic!("expected the cubic constraint's degree to be too high"),
+    }
+}
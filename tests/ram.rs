@@ -0,0 +1,134 @@
+#![feature(allocator_api, array_windows)]
+use ark_ff::Zero;
+use ark_std::test_rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::gadgets::ram;
+use ministark::gadgets::ram::Access;
+use ministark::gadgets::ram::AccessColumns;
+use ministark::gadgets::ram::RamColumns;
+use ministark::Constraint;
+use ministark::Matrix;
+
+const NUM_PROCESSOR_COLUMNS: usize = 5;
+
+const COLUMNS: RamColumns = RamColumns {
+    processor: AccessColumns {
+        clk: 0,
+        address: 1,
+        value: 2,
+        is_write: 3,
+        is_padding: 4,
+    },
+    base_offset: NUM_PROCESSOR_COLUMNS,
+    extension_offset: NUM_PROCESSOR_COLUMNS + ram::NUM_BASE_COLUMNS,
+    challenge_offset: 0,
+};
+
+const TRACE_LEN: usize = 16;
+
+fn read(clk: u64, address: u64, value: u64) -> Access<Fp> {
+    Access {
+        clk,
+        address: Fp::from(address),
+        value: Fp::from(value),
+        is_write: false,
+    }
+}
+
+fn write(clk: u64, address: u64, value: u64) -> Access<Fp> {
+    Access {
+        is_write: true,
+        ..read(clk, address, value)
+    }
+}
+
+/// Processor columns making one access per row followed by padding
+fn processor_columns(accesses: &[Access<Fp>]) -> Matrix<Fp> {
+    let mut columns = (0..NUM_PROCESSOR_COLUMNS)
+        .map(|_| Vec::with_capacity_in(TRACE_LEN, PageAlignedAllocator))
+        .collect::<Vec<_>>();
+    for i in 0..TRACE_LEN {
+        let row = match accesses.get(i) {
+            Some(access) => [
+                Fp::from(access.clk),
+                access.address,
+                access.value,
+                Fp::from(access.is_write),
+                Fp::zero(),
+            ],
+            None => [
+                Fp::from(i as u64),
+                Fp::zero(),
+                Fp::zero(),
+                Fp::zero(),
+                Fp::from(1u64),
+            ],
+        };
+        for (column, value) in columns.iter_mut().zip(row) {
+            column.push(value);
+        }
+    }
+    Matrix::new(columns)
+}
+
+fn is_satisfied(accesses: &[Access<Fp>]) -> bool {
+    let challenges = Challenges::<Fq3>::new(&mut test_rng(), ram::NUM_CHALLENGES);
+    let base_columns = Matrix::join(vec![
+        processor_columns(accesses),
+        ram::gen_base_columns(accesses, TRACE_LEN),
+    ]);
+    let extension_columns = ram::gen_extension_columns(COLUMNS, &base_columns, &challenges);
+    let base_columns = Matrix::<Fq3>::from_rows(
+        base_columns
+            .rows()
+            .into_iter()
+            .map(|row| row.into_iter().map(Fq3::from).collect())
+            .collect(),
+    );
+    let rows = Matrix::join(vec![base_columns, extension_columns]).rows();
+
+    let boundary: Vec<Constraint<Fq3>> = COLUMNS.boundary_constraints();
+    let transition: Vec<Constraint<Fq3>> = COLUMNS.transition_constraints();
+    let terminal: Vec<Constraint<Fq3>> = COLUMNS.terminal_constraints();
+    let evaluates_to_zero = |constraints: &[Constraint<Fq3>], curr: &[Fq3], next: &[Fq3]| {
+        constraints
+            .iter()
+            .all(|c| c.evaluate(&challenges, &[], curr, next).is_zero())
+    };
+    evaluates_to_zero(&boundary, &rows[0], &[])
+        && rows
+            .array_windows()
+            .all(|[curr, next]| evaluates_to_zero(&transition, curr, next))
+        && evaluates_to_zero(&terminal, &rows[TRACE_LEN - 1], &[])
+}
+
+#[test]
+fn consistent_accesses_satisfy_constraints() {
+    let accesses = [
+        write(0, 2, 5),
+        read(1, 0, 0),
+        read(2, 2, 5),
+        write(3, 2, 7),
+        read(4, 2, 7),
+        read(5, 0, 0),
+    ];
+
+    assert!(is_satisfied(&accesses));
+}
+
+#[test]
+fn read_of_stale_value_fails() {
+    let accesses = [write(0, 2, 5), read(1, 0, 0), write(2, 2, 7), read(3, 2, 5)];
+
+    assert!(!is_satisfied(&accesses));
+}
+
+#[test]
+fn read_of_uninitialized_memory_must_be_zero() {
+    let accesses = [read(0, 1, 3)];
+
+    assert!(!is_satisfied(&accesses));
+}
@@ -0,0 +1,82 @@
+#![feature(array_windows)]
+use ark_ff::Zero;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::gadgets::rescue;
+use ministark::gadgets::rescue::RescueColumns;
+use ministark::gadgets::rescue::RescuePrime;
+use ministark::Constraint;
+
+const COLUMNS: RescueColumns = RescueColumns {
+    trace_offset: 0,
+    periodic_offset: rescue::NUM_COLUMNS,
+};
+
+/// Returns the trace row with the periodic column values appended
+fn row_with_periodic_values(row: &[Fp], periodic_columns: &[Vec<Fp>], round: usize) -> Vec<Fp> {
+    let mut res = row.to_vec();
+    res.extend(periodic_columns.iter().map(|column| column[round]));
+    res
+}
+
+#[test]
+fn permutation_trace_ends_with_permutation_output() {
+    let rescue_prime = RescuePrime::new();
+    let mut state = [(); rescue::STATE_WIDTH].map(|_| Fp::from(7u64));
+
+    let rows = rescue_prime.permutation_trace(state);
+    rescue_prime.permute(&mut state);
+
+    assert_eq!(rescue::CYCLE_LENGTH, rows.len());
+    assert_eq!(state, rows.last().unwrap()[..rescue::STATE_WIDTH]);
+}
+
+#[test]
+fn permutation_trace_satisfies_constraints() {
+    let rescue_prime = RescuePrime::new();
+    let state = [(); rescue::STATE_WIDTH].map(|_| Fp::from(3u64));
+    let periodic_columns = rescue_prime.periodic_columns();
+    let constraints: Vec<Constraint<Fp>> = rescue_prime.transition_constraints(COLUMNS);
+    // two permutations back to back. The transition between them is unconstrained
+    let rows = [
+        rescue_prime.permutation_trace(state),
+        rescue_prime.permutation_trace(state),
+    ]
+    .concat();
+
+    for (i, [curr, next]) in rows.array_windows::<2>().enumerate() {
+        let round = i % rescue::CYCLE_LENGTH;
+        let curr = row_with_periodic_values(curr, &periodic_columns, round);
+        let next =
+            row_with_periodic_values(next, &periodic_columns, (round + 1) % rescue::CYCLE_LENGTH);
+        for (j, constraint) in constraints.iter().enumerate() {
+            let eval = constraint.evaluate(&[], &[], &curr, &next);
+            assert!(eval.is_zero(), "constraint {j} failed at row {i}");
+        }
+    }
+}
+
+#[test]
+fn tampered_round_fails_constraints() {
+    let rescue_prime = RescuePrime::new();
+    let state = [(); rescue::STATE_WIDTH].map(|_| Fp::from(5u64));
+    let periodic_columns = rescue_prime.periodic_columns();
+    let constraints: Vec<Constraint<Fp>> = rescue_prime.transition_constraints(COLUMNS);
+    let mut rows = rescue_prime.permutation_trace(state);
+    rows[3][0] += Fp::from(1u64);
+
+    let curr = row_with_periodic_values(&rows[2], &periodic_columns, 2);
+    let next = row_with_periodic_values(&rows[3], &periodic_columns, 3);
+    assert!(constraints
+        .iter()
+        .any(|constraint| !constraint.evaluate(&[], &[], &curr, &next).is_zero()));
+}
+
+#[test]
+fn hash_separates_input_lengths() {
+    let rescue_prime = RescuePrime::new();
+
+    let a = rescue_prime.hash_elements(&[Fp::zero()]);
+    let b = rescue_prime.hash_elements(&[Fp::zero(), Fp::zero()]);
+
+    assert_ne!(a, b);
+}
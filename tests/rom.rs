@@ -0,0 +1,91 @@
+#![feature(array_windows)]
+use ark_ff::Zero;
+use ark_std::test_rng;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::challenges::Challenges;
+use ministark::gadgets::rom;
+use ministark::gadgets::rom::Rom;
+use ministark::gadgets::rom::RomColumns;
+use ministark::Constraint;
+use ministark::Matrix;
+
+const COLUMNS: RomColumns = RomColumns {
+    width: 2,
+    base_offset: 0,
+    extension_offset: 6,
+    challenge_offset: 0,
+    hint: 0,
+};
+
+const TRACE_LEN: usize = 16;
+
+/// Program of `(opcode, argument)` pairs starting at address zero
+fn program(instructions: &[(u64, u64)]) -> Rom<Fp> {
+    let entries = instructions
+        .iter()
+        .map(|&(opcode, argument)| vec![Fp::from(opcode), Fp::from(argument)])
+        .collect();
+    Rom::new(Fp::zero(), entries)
+}
+
+fn trace_rows(rom: &Rom<Fp>, lookups: &[u64]) -> Vec<Vec<Fq3>> {
+    let challenges = Challenges::new(&mut test_rng(), COLUMNS.num_challenges());
+    let lookups = lookups.iter().map(|&i| Fp::from(i)).collect::<Vec<Fp>>();
+    let base_columns = rom.gen_base_columns(&lookups, TRACE_LEN);
+    let extension_columns = rom::gen_extension_columns(COLUMNS, &base_columns, &challenges);
+    let base_columns = Matrix::<Fq3>::from_rows(
+        base_columns
+            .rows()
+            .into_iter()
+            .map(|row| row.into_iter().map(Fq3::from).collect())
+            .collect(),
+    );
+    Matrix::join(vec![base_columns, extension_columns]).rows()
+}
+
+fn is_satisfied(rom: &Rom<Fp>, rows: &[Vec<Fq3>]) -> bool {
+    let challenges = Challenges::<Fq3>::new(&mut test_rng(), COLUMNS.num_challenges());
+    let hints = [rom.hint(COLUMNS, &challenges)];
+    let boundary: Vec<Constraint<Fq3>> = COLUMNS.boundary_constraints(rom);
+    let transition: Vec<Constraint<Fq3>> = COLUMNS.transition_constraints();
+    let terminal: Vec<Constraint<Fq3>> = COLUMNS.terminal_constraints(rom);
+    let evaluates_to_zero = |constraints: &[Constraint<Fq3>], curr: &[Fq3], next: &[Fq3]| {
+        constraints
+            .iter()
+            .all(|c| c.evaluate(&challenges, &hints, curr, next).is_zero())
+    };
+    let first = rows.first().unwrap();
+    let last = rows.last().unwrap();
+    evaluates_to_zero(&boundary, first, &[])
+        && rows
+            .array_windows()
+            .all(|[curr, next]| evaluates_to_zero(&transition, curr, next))
+        && evaluates_to_zero(&terminal, last, &[])
+}
+
+#[test]
+fn rom_lookups_satisfy_constraints() {
+    let rom = program(&[(1, 5), (2, 0), (3, 7), (4, 1)]);
+    let rows = trace_rows(&rom, &[0, 1, 2, 1, 2, 3]);
+
+    assert_eq!(TRACE_LEN, rows.len());
+    assert!(is_satisfied(&rom, &rows));
+}
+
+#[test]
+fn rom_lookups_against_different_program_fail() {
+    let rom = program(&[(1, 5), (2, 0), (3, 7), (4, 1)]);
+    let rows = trace_rows(&rom, &[0, 1, 2, 1, 2, 3]);
+    let claimed_rom = program(&[(1, 5), (2, 0), (3, 8), (4, 1)]);
+
+    assert!(!is_satisfied(&claimed_rom, &rows));
+}
+
+#[test]
+#[should_panic(expected = "isn't in the table")]
+fn lookup_outside_table_panics() {
+    let rom = program(&[(1, 5), (2, 0)]);
+
+    trace_rows(&rom, &[2]);
+}
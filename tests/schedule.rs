@@ -0,0 +1,26 @@
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::schedule::exceeds_gpu_fft_crossover;
+use ministark::schedule::gpu_fft_crossover;
+use ministark::schedule::set_gpu_fft_crossover;
+use ministark::schedule::use_gpu_fft;
+use ministark::schedule::DEFAULT_GPU_FFT_CROSSOVER;
+
+// the crossover is global so it's only changed by this test
+#[test]
+fn ffts_at_or_above_the_crossover_use_the_gpu() {
+    assert_eq!(gpu_fft_crossover(), DEFAULT_GPU_FFT_CROSSOVER);
+
+    set_gpu_fft_crossover(1 << 16);
+    assert!(!exceeds_gpu_fft_crossover(1 << 15, 2048));
+    assert!(exceeds_gpu_fft_crossover(1 << 16, 2048));
+
+    // FFTs smaller than the GPU supports stay on the CPU
+    set_gpu_fft_crossover(1024);
+    assert!(!exceeds_gpu_fft_crossover(1024, 2048));
+    assert!(exceeds_gpu_fft_crossover(2048, 2048));
+
+    if cfg!(not(feature = "gpu")) {
+        assert!(!use_gpu_fft::<Fp>(1 << 20));
+    }
+    set_gpu_fft_crossover(DEFAULT_GPU_FFT_CROSSOVER);
+}
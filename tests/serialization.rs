@@ -0,0 +1,46 @@
+#![cfg(feature = "serde")]
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::Proof;
+use ministark::ProofOptions;
+use ministark::Prover;
+use ministark::TraceInfo;
+use ministark::VerificationError;
+
+#[test]
+fn proof_round_trips_through_json() {
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let proof = FibonacciProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+
+    let json = serde_json::to_string(&proof).unwrap();
+    let proof: Proof<FibonacciAir> = serde_json::from_str(&json).unwrap();
+
+    proof.verify().unwrap();
+}
+
+#[test]
+fn options_and_trace_info_are_plain_json() {
+    let options = TestVector::options();
+    let trace_info = TraceInfo::new(2, 0, TestVector::TRACE_LEN, None);
+
+    let options_json = serde_json::to_value(options).unwrap();
+    let trace_info_json = serde_json::to_value(&trace_info).unwrap();
+
+    assert_eq!(options_json["num_queries"], options.num_queries);
+    assert_eq!(trace_info_json["trace_len"], TestVector::TRACE_LEN);
+    let options_copy: ProofOptions = serde_json::from_value(options_json).unwrap();
+    assert_eq!(options_copy, options);
+}
+
+#[test]
+fn errors_serialize_as_their_message() {
+    let error = VerificationError::InvalidNumQueries(0);
+
+    let json = serde_json::to_string(&error).unwrap();
+
+    assert_eq!(json, format!("\"{error}\""));
+}
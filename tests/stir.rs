@@ -0,0 +1,108 @@
+use ark_ff::One;
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::ldt::LowDegreeProof;
+use ministark::stir;
+use ministark::stir::StirOptions;
+use ministark::stir::StirVerifier;
+use ministark::test_vectors::FibonacciAir;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::LowDegreeTest;
+use ministark::Proof;
+use ministark::Prover;
+use ministark::PublicCoin;
+use sha2::Sha256;
+
+fn prove() -> Proof<FibonacciAir> {
+    let options = TestVector::options().with_low_degree_test(LowDegreeTest::Stir);
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    FibonacciProver::new(options).generate_proof(trace).unwrap()
+}
+
+fn compressed_bytes(item: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    item.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+fn stir_proof(proof: &Proof<FibonacciAir>) -> &stir::StirProof<Fp> {
+    match &proof.low_degree_proof {
+        LowDegreeProof::Stir(stir_proof) => stir_proof,
+        LowDegreeProof::Fri(_) => panic!("expected a STIR proof"),
+    }
+}
+
+/// Replaces the only occurrence of `from` in the serialized proof with `to`
+/// and verifies the result
+fn verify_replaced(proof: &Proof<FibonacciAir>, from: &[u8], to: &[u8]) -> bool {
+    let mut bytes = compressed_bytes(proof);
+    let matches = bytes
+        .windows(from.len())
+        .enumerate()
+        .filter(|(_, window)| *window == from)
+        .map(|(i, _)| i)
+        .collect::<Vec<usize>>();
+    assert_eq!(matches.len(), 1);
+    bytes[matches[0]..matches[0] + from.len()].copy_from_slice(to);
+    match Proof::<FibonacciAir>::deserialize_compressed(&*bytes) {
+        Ok(proof) => proof.verify().is_ok(),
+        Err(_) => false,
+    }
+}
+
+#[test]
+fn stir_proofs_verify() {
+    let proof = prove();
+
+    let bytes = compressed_bytes(&proof);
+    let decoded = Proof::<FibonacciAir>::deserialize_compressed(&*bytes).unwrap();
+
+    assert!(stir_proof(&decoded).num_rounds() > 0);
+    decoded.verify().unwrap();
+}
+
+#[test]
+fn tampered_round_commitment_is_rejected() {
+    let proof = prove();
+    let commitment = stir_proof(&proof)
+        .round_commitments()
+        .next()
+        .unwrap()
+        .to_vec();
+    let mut tampered = commitment.clone();
+    tampered[0] ^= 1;
+
+    assert!(!verify_replaced(&proof, &commitment, &tampered));
+}
+
+#[test]
+fn wrong_final_polynomial_is_rejected() {
+    let proof = prove();
+    let final_poly = stir_proof(&proof).final_poly().to_vec();
+    let mut wrong_poly = final_poly.clone();
+    wrong_poly[0] += Fp::one();
+
+    assert!(!verify_replaced(
+        &proof,
+        &compressed_bytes(&final_poly),
+        &compressed_bytes(&wrong_poly)
+    ));
+}
+
+#[test]
+fn unsupported_folding_factor_is_rejected() {
+    let proof = prove();
+    let stir_proof = stir_proof(&proof).clone();
+    let options = StirOptions::new(4, 3, 16, 4);
+    let mut public_coin = PublicCoin::<Sha256>::new(b"stir");
+
+    let result = StirVerifier::<_, Sha256>::new(&mut public_coin, options, stir_proof, 64);
+
+    assert!(matches!(
+        result,
+        Err(stir::VerificationError::UnsupportedFoldingFactor(3))
+    ));
+}
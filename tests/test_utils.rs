@@ -0,0 +1,148 @@
+use ark_ff::One;
+use ark_std::test_rng;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::constraint::Column;
+use ministark::test_utils::assert_constraints_satisfied;
+use ministark::test_utils::assert_rejects_mutation;
+use ministark::test_utils::check_constraints;
+use ministark::test_utils::constraint_coverage;
+#[cfg(feature = "proptest")]
+use ministark::test_utils::mutations;
+use ministark::test_utils::MockAir;
+use ministark::test_utils::MockTrace;
+use ministark::test_utils::TraceMutation;
+use ministark::ProvingError;
+
+const LEN: usize = 2048;
+
+/// Column 0 counts up from zero and column 1 is one ahead of it
+fn counter_trace() -> MockTrace<Fp, 2> {
+    MockTrace::from_fn(LEN, |row, col| Fp::from((row + col) as u64))
+}
+
+fn counter_air(trace: &MockTrace<Fp, 2>) -> MockAir<Fp> {
+    MockAir::for_trace(trace)
+        .with_boundary_constraints(vec![0.curr::<Fp>()])
+        .with_transition_constraints(vec![0.next() - 1.curr()])
+        .with_terminal_constraints(vec![1.curr() - Fp::from(LEN as u64)])
+}
+
+#[test]
+fn satisfied_constraints_pass() {
+    let trace = counter_trace();
+    let air = counter_air(&trace);
+
+    assert_constraints_satisfied(&air, &trace);
+}
+
+#[test]
+fn unsatisfied_constraint_names_the_row() {
+    let mut columns = vec![Vec::new(), Vec::new()];
+    for row in 0..LEN as u64 {
+        columns[0].push(Fp::from(row));
+        columns[1].push(Fp::from(row + 1));
+    }
+    columns[1][100] += Fp::one();
+    let trace = MockTrace::<Fp, 2>::from_columns(columns);
+    let air = MockAir::for_trace(&trace).with_transition_constraints(vec![0.next() - 1.curr()]);
+
+    let result = check_constraints(&air, &trace);
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::UnsatisfiedConstraint {
+            kind: "transition",
+            constraint: 0,
+            row: 100,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn periodic_columns_follow_trace_columns() {
+    let trace = MockTrace::<Fp, 1>::from_fn(LEN, |row, _| Fp::from((row % 4) as u64));
+    let periodic_column = (0..4u64).map(Fp::from).collect();
+    let air = MockAir::for_trace(&trace)
+        .with_periodic_columns(vec![periodic_column])
+        .with_transition_constraints(vec![0.curr() - 1.curr()]);
+
+    assert_constraints_satisfied(&air, &trace);
+}
+
+#[test]
+#[should_panic(expected = "boundary constraint 0 is not satisfied at row 0")]
+fn assertion_panics_on_random_trace() {
+    let trace = MockTrace::<Fp, 1>::random(LEN, &mut test_rng());
+    let air = MockAir::for_trace(&trace).with_boundary_constraints(vec![0.curr::<Fp>()]);
+
+    assert_constraints_satisfied(&air, &trace);
+}
+
+#[test]
+fn constrained_columns_reject_mutations() {
+    let trace = counter_trace();
+    let air = counter_air(&trace);
+    let mut rng = test_rng();
+
+    for _ in 0..32 {
+        assert_rejects_mutation(&air, &trace, TraceMutation::random(&mut rng, LEN, 2));
+    }
+}
+
+#[test]
+#[should_panic(expected = "AIR accepts the trace after mutation FlipCell")]
+fn missing_terminal_constraint_is_caught() {
+    let trace = counter_trace();
+    let air = counter_air(&trace).with_terminal_constraints(Vec::new());
+
+    // nothing constrains the last value of column 1 without the terminal constraint
+    assert_rejects_mutation(
+        &air,
+        &trace,
+        TraceMutation::FlipCell {
+            row: LEN - 1,
+            col: 1,
+        },
+    );
+}
+
+#[test]
+fn coverage_finds_dead_constraints() {
+    // column 0 selects odd rows, column 1 counts pairs of rows and column 2 is
+    // a selector that's never set
+    let trace = MockTrace::<Fp, 3>::from_fn(LEN, |row, col| match col {
+        0 => Fp::from((row % 2) as u64),
+        1 => Fp::from((row / 2) as u64),
+        _ => Fp::from(0u64),
+    });
+    let air = MockAir::for_trace(&trace).with_transition_constraints(vec![
+        0.curr() * (1.next() - 1.curr() - Fp::one()),
+        2.curr() * (1.curr() - 0.curr()),
+    ]);
+
+    let report = constraint_coverage(&air, &trace).unwrap();
+
+    let counter = report.get("transition", 0).unwrap();
+    assert_eq!(
+        (counter.active_rows, counter.num_rows),
+        (LEN / 2 - 1, LEN - 1)
+    );
+    assert!(counter.zero_terms.is_empty());
+    let dead = report.dead_constraints().collect::<Vec<_>>();
+    assert_eq!(dead.len(), 1);
+    assert_eq!(dead[0].index, 1);
+    assert_eq!(dead[0].zero_terms.len(), 2);
+    assert!(report
+        .to_string()
+        .contains("transition constraint 1: active on 0/2047 rows (0.0%) DEAD"));
+}
+
+#[cfg(feature = "proptest")]
+proptest::proptest! {
+    #[test]
+    fn constrained_columns_reject_generated_mutations(mutation in mutations(LEN, 2)) {
+        let trace = counter_trace();
+        assert_rejects_mutation(&counter_air(&trace), &trace, mutation);
+    }
+}
@@ -0,0 +1,26 @@
+use ministark::test_vectors::TestVector;
+use ministark::VerificationEvent;
+
+#[test]
+fn test_vector_is_deterministic() {
+    let vector = TestVector::generate();
+
+    assert_eq!(vector.to_json(), TestVector::generate().to_json());
+}
+
+#[test]
+fn test_vector_records_transcript() {
+    let vector = TestVector::generate();
+
+    let events = &vector.events;
+    assert_eq!(events[0], VerificationEvent::Absorb(vector.seed.clone()));
+    assert!(events.contains(&VerificationEvent::Absorb(
+        vector.base_trace_commitment.clone()
+    )));
+    let num_query_checks = events
+        .iter()
+        .filter(|event| matches!(event, VerificationEvent::QueryCheck { passed: true, .. }))
+        .count();
+    // base trace and composition trace checks for every query
+    assert_eq!(num_query_checks, 2 * vector.options.num_queries as usize);
+}
@@ -0,0 +1,242 @@
+#![feature(allocator_api)]
+use ark_serialize::CanonicalDeserialize;
+use ark_serialize::CanonicalSerialize;
+use ark_std::test_rng;
+use gpu_poly::allocator::PageAlignedAllocator;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::challenges::Challenges;
+use ministark::hints::Hints;
+use ministark::segment_column_names;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::Column;
+use ministark::Matrix;
+use ministark::Prover;
+use ministark::ProvingError;
+use ministark::Trace;
+use ministark::TraceError;
+use ministark::TraceInfo;
+
+struct CountTrace(Matrix<Fp>);
+
+impl Trace for CountTrace {
+    const NUM_BASE_COLUMNS: usize = 2;
+    type Fp = Fp;
+    type Fq = Fp;
+
+    fn len(&self) -> usize {
+        self.0.num_rows()
+    }
+
+    fn base_columns(&self) -> &Matrix<Self::Fp> {
+        &self.0
+    }
+}
+
+/// Two columns counting up from zero with a bad value on row 2
+fn count_trace() -> CountTrace {
+    let rows = [[0, 0], [1, 1], [2, 7], [3, 3]]
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| Fp::from(v as u64)).collect())
+        .collect();
+    CountTrace(Matrix::from_rows(rows))
+}
+
+#[test]
+fn csv_has_named_headers() {
+    let trace = count_trace();
+    let challenges = Challenges::new(&mut test_rng(), 0);
+
+    let csv = trace.to_csv(&challenges, &Hints::default(), None);
+
+    assert_eq!(csv, "base_0,base_1\n0,0\n1,1\n2,7\n3,3\n");
+}
+
+#[test]
+fn csv_marks_rows_violating_constraint() {
+    let trace = count_trace();
+    let challenges = Challenges::new(&mut test_rng(), 0);
+    let constraint = 1.next() - 1.curr() - Fp::from(1u64);
+
+    let csv = trace.to_csv(&challenges, &Hints::default(), Some(&constraint));
+
+    let violated = csv.lines().map(|line| line.rsplit(',').next().unwrap());
+    assert_eq!(
+        violated.collect::<Vec<&str>>(),
+        ["violated", "0", "1", "1", "0"]
+    );
+}
+
+#[test]
+fn html_highlights_rows_violating_constraint() {
+    let trace = count_trace();
+    let challenges = Challenges::new(&mut test_rng(), 0);
+    let constraint = 1.curr() - 0.curr();
+
+    let html = trace.to_html(&challenges, &Hints::default(), Some(&constraint));
+
+    assert!(html.contains("<th>base_0</th><th>base_1</th>"));
+    assert!(html.contains("<tr class=\"violated\"><td>2</td><td>2</td><td>7</td></tr>"));
+    assert_eq!(html.matches("class=\"violated\"").count(), 1);
+}
+
+#[test]
+fn diff_reports_mismatched_cells() {
+    let reference = CountTrace(Matrix::from_rows(
+        (0..4u64).map(|i| vec![Fp::from(i), Fp::from(i)]).collect(),
+    ));
+    let trace = count_trace();
+
+    let diff = reference.diff(&trace);
+
+    assert!(!diff.is_empty());
+    assert_eq!(diff.num_mismatches(), 1);
+    assert_eq!(diff.columns[0].name, "base_1");
+    assert_eq!(diff.columns[0].rows, [(2, Fp::from(2u64), Fp::from(7u64))]);
+    assert_eq!(
+        diff.to_string(),
+        "column 1 (base_1): 1 rows differ\n  row 2: 2 != 7\n"
+    );
+    assert!(trace.diff(&trace).is_empty());
+}
+
+#[test]
+fn diff_reports_length_mismatch() {
+    let trace = count_trace();
+    let rows = [[0, 0], [1, 1]]
+        .into_iter()
+        .map(|row| row.into_iter().map(|v| Fp::from(v as u64)).collect())
+        .collect();
+    let truncated = CountTrace(Matrix::from_rows(rows));
+
+    let diff = trace.diff(&truncated);
+
+    assert_eq!(diff.len, Some((4, 2)));
+    assert!(diff.columns.is_empty());
+}
+
+#[test]
+fn matrix_from_row_blocks_matches_from_rows() {
+    let rows = (0..10u64)
+        .map(|i| vec![Fp::from(i), Fp::from(i * i)])
+        .collect::<Vec<Vec<Fp>>>();
+    let blocks = rows.chunks(3).map(<[Vec<Fp>]>::to_vec);
+
+    let matrix = Matrix::from_row_blocks(2, rows.len(), blocks);
+
+    assert_eq!(Matrix::from_rows(rows).0, matrix.0);
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Counter {
+    Value,
+    Copy,
+}
+
+impl Column for Counter {
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[test]
+fn trace_info_names_columns() {
+    let info = FibonacciTrace::new(TestVector::TRACE_LEN).info();
+
+    assert_eq!(info.column_names, ["base_0", "base_1"]);
+    assert_eq!(info.column_name(1), "base_1");
+    assert_eq!(info.column_name(2), "column 2");
+}
+
+#[test]
+fn segment_columns_are_named_after_their_enum() {
+    let mut names = vec![String::new(); 2];
+
+    segment_column_names(&mut names, "Counter", [Counter::Copy, Counter::Value]);
+
+    assert_eq!(names, ["Counter::Value", "Counter::Copy"]);
+}
+
+#[test]
+fn column_names_are_not_serialized() {
+    let unnamed = TraceInfo::new(2, 0, TraceInfo::MIN_TRACE_LENGTH, None);
+    let named = unnamed
+        .clone()
+        .with_column_names(vec!["a".to_string(), "b".to_string()]);
+    let (mut unnamed_bytes, mut named_bytes) = (Vec::new(), Vec::new());
+    unnamed.serialize_compressed(&mut unnamed_bytes).unwrap();
+    named.serialize_compressed(&mut named_bytes).unwrap();
+
+    let deserialized = TraceInfo::deserialize_compressed(&*named_bytes).unwrap();
+
+    assert_eq!(named_bytes, unnamed_bytes);
+    assert!(deserialized.column_names.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "expected a name for each column")]
+fn every_column_must_be_named() {
+    TraceInfo::new(2, 1, TraceInfo::MIN_TRACE_LENGTH, None)
+        .with_column_names(vec!["a".to_string()]);
+}
+
+/// Counting columns of the given lengths
+fn ragged_trace(lens: &[usize]) -> CountTrace {
+    let columns = lens
+        .iter()
+        .map(|&len| {
+            let mut column = Vec::with_capacity_in(len, PageAlignedAllocator);
+            column.extend((0..len as u64).map(Fp::from));
+            column
+        })
+        .collect();
+    CountTrace(Matrix::new(columns))
+}
+
+#[test]
+fn validate_names_misaligned_column() {
+    let len = TraceInfo::MIN_TRACE_LENGTH;
+    let trace = ragged_trace(&[len, len - 1]);
+
+    match trace.validate() {
+        Err(TraceError::ColumnLength {
+            column, len: 2047, ..
+        }) => assert_eq!(column, "base_1"),
+        result => panic!("unexpected result {result:?}"),
+    }
+}
+
+#[test]
+fn validate_checks_shape() {
+    let len = TraceInfo::MIN_TRACE_LENGTH;
+
+    assert!(ragged_trace(&[len, len]).validate().is_ok());
+    assert!(matches!(
+        count_trace().validate(),
+        Err(TraceError::InvalidLength(4))
+    ));
+    assert!(matches!(
+        ragged_trace(&[len + 1, len + 1]).validate(),
+        Err(TraceError::InvalidLength(_))
+    ));
+    assert!(matches!(
+        ragged_trace(&[len; 3]).validate(),
+        Err(TraceError::NumBaseColumns {
+            expected: 2,
+            actual: 3
+        })
+    ));
+}
+
+#[test]
+fn prover_rejects_invalid_trace() {
+    let trace = FibonacciTrace::new(100);
+
+    let result = FibonacciProver::new(TestVector::options()).generate_proof(trace);
+
+    assert!(matches!(
+        result,
+        Err(ProvingError::InvalidTrace(TraceError::InvalidLength(100)))
+    ));
+}
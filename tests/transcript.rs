@@ -0,0 +1,209 @@
+use ark_ff::Field;
+use ark_ff::One;
+use ark_ff::PrimeField;
+use ark_serialize::CanonicalSerialize;
+use digest::Digest;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use gpu_poly::fields::p18446744069414584321::Fq3;
+use ministark::test_vectors::FibonacciProver;
+use ministark::test_vectors::FibonacciTrace;
+use ministark::test_vectors::TestVector;
+use ministark::Prover;
+use ministark::PublicCoin;
+use ministark::TraceInfo;
+use ministark::TranscriptEntry;
+use ministark::TranscriptLabel;
+use sha2::Sha256;
+
+fn encode(item: &impl CanonicalSerialize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    item.serialize_compressed(&mut bytes).unwrap();
+    bytes
+}
+
+#[test]
+fn integers_are_little_endian_u64() {
+    assert_eq!(encode(&5usize), [5, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(encode(&0x0102u64), [2, 1, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn field_elements_are_canonical_little_endian() {
+    assert_eq!(encode(&Fp::one()), [1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(encode(&-Fp::one()), [0, 0, 0, 0, 255, 255, 255, 255]);
+}
+
+#[test]
+fn slices_are_length_prefixed() {
+    assert_eq!(encode(&[7u8, 9].as_slice()), [2, 0, 0, 0, 0, 0, 0, 0, 7, 9]);
+}
+
+#[test]
+fn options_and_trace_info_encodings_are_fixed() {
+    assert_eq!(encode(&TestVector::options()), [4, 4, 2, 4, 16, 0, 1]);
+
+    let trace_info = TraceInfo::new(2, 0, TraceInfo::MIN_TRACE_LENGTH, Some(vec![3]));
+    let mut expected = vec![2, 0, 0, 0, 0, 0, 0, 0];
+    expected.extend([0; 8]);
+    expected.extend((TraceInfo::MIN_TRACE_LENGTH as u64).to_le_bytes());
+    expected.extend([1, 0, 0, 0, 0, 0, 0, 0, 3]);
+    assert_eq!(encode(&trace_info), expected);
+}
+
+/// Reference implementation of the coin's rejection sampling for fields with
+/// a 64-bit base field modulus
+fn draw_reference<F: Field>(seed: &[u8]) -> F {
+    let modulus = F::BasePrimeField::MODULUS.as_ref()[0];
+    let seed = Sha256::new_with_prefix(seed).finalize();
+    let mut stream = Vec::new();
+    let mut coeffs = Vec::new();
+    for counter in 1u64.. {
+        if coeffs.len() == F::extension_degree() as usize {
+            break;
+        }
+        let mut hasher = Sha256::new_with_prefix(seed);
+        hasher.update(counter.to_be_bytes());
+        stream.extend(hasher.finalize());
+        while stream.len() >= 8 && coeffs.len() < F::extension_degree() as usize {
+            let bytes = stream.drain(..8).collect::<Vec<u8>>();
+            let candidate = u64::from_le_bytes(bytes.try_into().unwrap());
+            if candidate < modulus {
+                coeffs.push(F::BasePrimeField::from(candidate));
+            }
+        }
+    }
+    F::from_base_prime_field_elems(&coeffs).unwrap()
+}
+
+#[test]
+fn draws_match_rejection_sampling_reference() {
+    for seed in [b"a".as_slice(), b"b", b"ministark"] {
+        let mut coin = PublicCoin::<Sha256>::new(seed);
+        assert_eq!(coin.draw::<Fp>(), draw_reference::<Fp>(seed));
+        let mut coin = PublicCoin::<Sha256>::new(seed);
+        assert_eq!(coin.draw::<Fq3>(), draw_reference::<Fq3>(seed));
+    }
+}
+
+#[test]
+fn positions_are_low_bits_of_hash_outputs() {
+    let seed = b"positions";
+    let mut coin = PublicCoin::<Sha256>::new(seed);
+    let positions = coin.draw_positions(40, 1 << 12);
+
+    // 12 bit positions take two bytes each
+    let seed = Sha256::new_with_prefix(seed).finalize();
+    let mut stream = Vec::new();
+    for counter in 1u64..=3 {
+        let mut hasher = Sha256::new_with_prefix(seed);
+        hasher.update(counter.to_be_bytes());
+        stream.extend(hasher.finalize());
+    }
+    let expected = stream
+        .chunks(2)
+        .take(40)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as usize & 0xFFF)
+        .collect::<Vec<usize>>();
+    assert_eq!(positions, expected);
+}
+
+#[test]
+fn positions_are_rejection_sampled_in_the_domain() {
+    let mut coin = PublicCoin::<Sha256>::new(b"positions");
+
+    let positions = coin.draw_positions(1000, 5);
+
+    assert!(positions.iter().all(|&position| position < 5));
+    assert!((0..5).all(|position| positions.contains(&position)));
+}
+
+#[test]
+fn draws_are_roughly_uniform() {
+    let mut coin = PublicCoin::<Sha256>::new(b"uniformity");
+    let half = Fp::MODULUS_MINUS_ONE_DIV_TWO;
+    let num_draws = 4096;
+    let num_upper = (0..num_draws)
+        .filter(|_| coin.draw::<Fp>().into_bigint() > half)
+        .count();
+    // expected is `num_draws / 2` with a standard deviation of 32
+    assert!((num_draws / 2).abs_diff(num_upper) < 256, "{num_upper}");
+}
+
+#[test]
+fn absorbs_hash_label_before_item() {
+    let mut coin = PublicCoin::<Sha256>::new(b"labels");
+    let initial_seed = coin.seed;
+    coin.reseed(TranscriptLabel::PowNonce, &7u64);
+
+    let mut hasher = Sha256::new_with_prefix(initial_seed);
+    hasher.update(encode(&b"pow_nonce".as_slice()));
+    hasher.update(encode(&7u64));
+    assert_eq!(coin.seed, hasher.finalize());
+}
+
+#[test]
+fn labels_separate_identical_items() {
+    let commitment = vec![1u8; 32];
+    let mut base = PublicCoin::<Sha256>::new(b"labels");
+    base.reseed(TranscriptLabel::BaseTraceCommitment, &commitment);
+    let mut composition = PublicCoin::<Sha256>::new(b"labels");
+    composition.reseed(TranscriptLabel::CompositionTraceCommitment, &commitment);
+
+    assert_ne!(base.seed, composition.seed);
+}
+
+#[test]
+fn exported_transcript_replays_from_the_spec() {
+    let trace = FibonacciTrace::new(TestVector::TRACE_LEN);
+    let proof = FibonacciProver::new(TestVector::options())
+        .generate_proof(trace)
+        .unwrap();
+    let public_inputs = proof.public_inputs.unwrap();
+
+    let (result, transcript) = proof.transcript(public_inputs);
+
+    result.unwrap();
+    let initial_seed = Sha256::new_with_prefix(&transcript.seed_input).finalize();
+    assert_eq!(transcript.initial_seed, initial_seed.to_vec());
+    assert!(matches!(
+        transcript.entries[0],
+        TranscriptEntry::Absorb {
+            label: "base_trace_commitment",
+            ..
+        }
+    ));
+    let mut seed = transcript.initial_seed.clone();
+    for entry in &transcript.entries {
+        match entry {
+            TranscriptEntry::Absorb {
+                label,
+                bytes,
+                seed: next_seed,
+            } => {
+                let mut hasher = Sha256::new_with_prefix(&seed);
+                hasher.update(encode(&label.as_bytes()));
+                hasher.update(bytes);
+                assert_eq!(hasher.finalize().to_vec(), *next_seed);
+                seed = next_seed.clone();
+            }
+            TranscriptEntry::Draw { counter, output } => {
+                let mut hasher = Sha256::new_with_prefix(&seed);
+                hasher.update(counter.to_be_bytes());
+                assert_eq!(hasher.finalize().to_vec(), *output);
+            }
+            TranscriptEntry::Challenge { .. } => {}
+        }
+    }
+}
+
+#[test]
+fn grinding_check_matches_absorbed_nonce() {
+    let coin = PublicCoin::<Sha256>::new(b"grinding");
+    let nonce = 42u64;
+    let expected = coin.check_leading_zeros(nonce);
+
+    let mut coin = coin;
+    coin.reseed(TranscriptLabel::PowNonce, &nonce);
+
+    assert_eq!(coin.seed_leading_zeros(), expected);
+}
@@ -0,0 +1,87 @@
+use ark_ff::Field;
+use ark_ff::Zero;
+use gpu_poly::fields::p18446744069414584321::Fp;
+use ministark::gadgets::u32_arithmetic::U32Columns;
+use ministark::gadgets::u32_arithmetic::U32Op;
+use ministark::Constraint;
+
+/// Row with the operands in the first two columns followed by the gadget
+fn row(columns: &U32Columns, a: u32, b: u32) -> Vec<Fp> {
+    [vec![Fp::from(a), Fp::from(b)], columns.gen_row(a, b)].concat()
+}
+
+fn instance(op: U32Op) -> U32Columns {
+    U32Columns {
+        op,
+        a: 0,
+        b: 1,
+        offset: 2,
+    }
+}
+
+fn is_satisfied(columns: &U32Columns, row: &[Fp]) -> bool {
+    let constraints: Vec<Constraint<Fp>> = columns.boundary_constraints();
+    constraints
+        .iter()
+        .all(|constraint| constraint.evaluate(&[], &[], row, &[]).is_zero())
+}
+
+#[test]
+fn u32_operations_satisfy_constraints() {
+    let operands = [
+        (0, 0),
+        (1, u32::MAX),
+        (u32::MAX, u32::MAX),
+        (0xDEADBEEF, 0x12345678),
+    ];
+    for op in [U32Op::Add, U32Op::Sub, U32Op::Mul] {
+        let columns = instance(op);
+        for (a, b) in operands {
+            let row = row(&columns, a, b);
+            assert_eq!(2 + columns.num_columns(), row.len());
+            assert!(is_satisfied(&columns, &row), "{op:?} {a} {b}");
+        }
+    }
+}
+
+#[test]
+fn u32_add_with_wrong_carry_fails() {
+    let columns = instance(U32Op::Add);
+    let mut row = row(&columns, u32::MAX, 2);
+    row[columns.carry()] = Fp::zero();
+
+    assert!(!is_satisfied(&columns, &row));
+}
+
+#[test]
+fn u32_mul_with_wrapped_result_fails() {
+    let columns = instance(U32Op::Mul);
+    let (a, b) = (7u32, 6u32);
+    // `(a * b + 1) + (2^32 - 1) * 2^32` is `a * b` in the field
+    let (low, high) = (a * b + 1, u32::MAX);
+    let mut row = row(&columns, a, b);
+    for i in 0..32 {
+        row[columns.bit(i)] = Fp::from((low >> i) & 1 == 1);
+        row[columns.bit(32 + i)] = Fp::from((high >> i) & 1 == 1);
+    }
+    // the inverse doesn't exist so any value can be tried
+    for high_inv in [
+        Fp::zero(),
+        Fp::from(1u64),
+        Fp::from(2u64).inverse().unwrap(),
+    ] {
+        row[columns.high_inv()] = high_inv;
+        assert!(!is_satisfied(&columns, &row));
+    }
+}
+
+#[test]
+fn u32_transition_constraints_use_next_row() {
+    let columns = instance(U32Op::Mul);
+    let constraints: Vec<Constraint<Fp>> = columns.transition_constraints();
+    let row = row(&columns, 7, 6);
+
+    assert!(constraints
+        .iter()
+        .all(|constraint| constraint.evaluate(&[], &[], &[], &row).is_zero()));
+}
@@ -0,0 +1,79 @@
+#![feature(allocator_api)]
+use ark_ff::FftField;
+use ark_ff::Field;
+use ark_ff::Zero;
+use ark_ff_optimized::fp64::Fp;
+use ark_poly::EvaluationDomain;
+use ark_poly::Radix2EvaluationDomain;
+use ark_std::UniformRand;
+use gpu_poly::allocator::PageAlignedAllocator;
+use ministark::utils::batch_inverse;
+use ministark::utils::batch_inverse_gpu_vec;
+use ministark::utils::evaluate_vanishing_polynomial;
+use ministark::utils::fill_vanishing_polynomial;
+use ministark::utils::round_robin;
+use ministark::utils::subdomain;
+
+#[test]
+fn batch_inverse_skips_zeros() {
+    let mut rng = ark_std::test_rng();
+    let mut values = (0..5000).map(|_| Fp::rand(&mut rng)).collect::<Vec<Fp>>();
+    values[0] = Fp::zero();
+    values[1234] = Fp::zero();
+    let expected = values
+        .iter()
+        .map(|value| value.inverse().unwrap_or_else(Fp::zero))
+        .collect::<Vec<Fp>>();
+
+    let mut gpu_values = values.to_vec_in(PageAlignedAllocator);
+    batch_inverse(&mut values);
+    batch_inverse_gpu_vec(&mut gpu_values);
+
+    assert_eq!(expected, values);
+    assert_eq!(expected, gpu_values.to_vec());
+}
+
+#[test]
+fn subdomain_vanishing_polynomial_is_zero_on_every_kth_row() {
+    let n = 64;
+    let (stride, offset) = (8, 3);
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let rows = subdomain(&trace_domain, stride, offset);
+
+    for (i, x) in trace_domain.elements().enumerate() {
+        let is_zero = evaluate_vanishing_polynomial(&rows, x).is_zero();
+        assert_eq!(i % stride == offset, is_zero, "row {i}");
+    }
+}
+
+#[test]
+fn fill_vanishing_polynomial_over_coset_matches_evaluate() {
+    let n = 64;
+    let trace_domain = Radix2EvaluationDomain::<Fp>::new(n).unwrap();
+    let lde_domain = Radix2EvaluationDomain::<Fp>::new_coset(n * 4, Fp::GENERATOR).unwrap();
+    let rows = subdomain(&trace_domain, 4, 1);
+
+    let mut evaluations = vec![Fp::zero(); lde_domain.size()];
+    fill_vanishing_polynomial(&mut evaluations, &rows, &lde_domain);
+
+    for (x, evaluation) in lde_domain.elements().zip(evaluations) {
+        assert_eq!(evaluate_vanishing_polynomial(&rows, x), evaluation);
+    }
+}
+
+#[test]
+fn round_robin_assigns_items_to_devices_in_turn() {
+    let groups = round_robin(&['a', 'b', 'c'], 0..7);
+
+    assert_eq!(
+        groups,
+        [('a', vec![0, 3, 6]), ('b', vec![1, 4]), ('c', vec![2, 5])]
+    );
+}
+
+#[test]
+fn round_robin_gives_extra_devices_no_items() {
+    let groups = round_robin(&[0, 1, 2], ["column"]);
+
+    assert_eq!(groups, [(0, vec!["column"]), (1, vec![]), (2, vec![])]);
+}